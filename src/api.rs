@@ -0,0 +1,423 @@
+// Versioned data shapes handed to other programs
+//
+// Template contexts and MQTT payloads are built from these types instead of
+// serializing the stored models, so adding a field to `Product` or renaming
+// an enum variant doesn't change what an integration sees. Each shape is
+// converted from the model field by field, and enums are named with
+// exhaustive matches, so a model change that would leak out fails to compile
+// here instead.
+//
+// Compatibility policy: within a version, fields are only added, and only as
+// optional ones; nothing is removed, renamed, or retyped, and enum values
+// keep their names. A change that can't follow those rules goes into a new
+// version module. The old module is then marked `#[deprecated]` and kept for
+// at least one release before it is removed.
+
+/// Version of the shapes the CLI currently hands out
+pub const API_VERSION: u32 = 1;
+
+/// First version of the machine-readable shapes
+pub mod v1 {
+    use std::collections::BTreeMap;
+
+    use chrono::{DateTime, Utc};
+    use serde::Serialize;
+
+    use crate::events::InventoryEvent;
+    use crate::models::{self, Condition, LinkKind, Quota, ReasonCode, TransactionType};
+    use crate::schema::RecordKind;
+    use crate::service;
+
+    /// A product with its stock levels
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    pub struct Product {
+        pub id: String,
+        pub sku: String,
+        pub name: String,
+        pub description: String,
+        /// Available units
+        pub quantity: u32,
+        pub reorder_point: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub category: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub supplier: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub location: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub barcode: Option<String>,
+        /// Large-movement threshold, e.g. `"100"` or `"25%"`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub large_movement_threshold: Option<String>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub components: Vec<Component>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        pub links: Vec<Link>,
+        /// Units on hold, not included in `quantity`
+        #[serde(skip_serializing_if = "is_zero")]
+        pub quarantined: u32,
+        /// Units ordered but not yet received
+        #[serde(skip_serializing_if = "is_zero")]
+        pub on_order: u32,
+        /// Available units by condition, other than new
+        #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+        pub conditions: BTreeMap<&'static str, u32>,
+        /// Cost of one unit, e.g. `"12.50"`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub unit_cost: Option<String>,
+        /// e.g. `"0.250 kg"`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub tare_weight: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub unit_weight: Option<String>,
+        /// Days a reorder takes to arrive
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub lead_time_days: Option<u32>,
+        /// Whether the product is at or below its reorder point
+        pub low_stock: bool,
+        /// Units still to order to get back to the reorder point, after those on order
+        pub shortfall: u32,
+    }
+
+    impl From<&models::Product> for Product {
+        fn from(product: &models::Product) -> Self {
+            Product {
+                id: product.id.clone(),
+                sku: product.sku.clone(),
+                name: product.name.clone(),
+                description: product.description.clone(),
+                quantity: product.quantity,
+                reorder_point: product.reorder_point,
+                category: product.category.clone(),
+                supplier: product.supplier.clone(),
+                location: product.location.clone(),
+                barcode: product.barcode.clone(),
+                large_movement_threshold: product.large_movement_threshold.map(|threshold| threshold.to_string()),
+                components: product
+                    .components
+                    .iter()
+                    .map(|component| Component { sku: component.sku.clone(), quantity: component.quantity })
+                    .collect(),
+                links: product
+                    .links
+                    .iter()
+                    .map(|link| Link { kind: link_kind_name(link.kind), sku: link.sku.clone() })
+                    .collect(),
+                quarantined: product.quarantined,
+                on_order: product.on_order,
+                conditions: product
+                    .conditions
+                    .iter()
+                    .map(|(condition, units)| (condition_name(*condition), *units))
+                    .collect(),
+                unit_cost: product.unit_cost.map(|cost| cost.to_string()),
+                tare_weight: product.tare_weight.map(|weight| weight.to_string()),
+                unit_weight: product.unit_weight.map(|weight| weight.to_string()),
+                lead_time_days: product.lead_time_days,
+                low_stock: product.is_low_stock(),
+                shortfall: product.shortfall(),
+            }
+        }
+    }
+
+    /// Count, units, and value of a group of products
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct GroupTotals {
+        /// Group value, or null for products without the field set
+        pub key: Option<String>,
+        pub products: usize,
+        pub quantity: u64,
+        /// Stock on hand at FIFO cost, leaving out units without a known cost
+        pub value: String,
+        pub uncosted_units: u64,
+    }
+
+    impl From<&service::GroupTotals> for GroupTotals {
+        fn from(totals: &service::GroupTotals) -> Self {
+            GroupTotals {
+                key: totals.key.clone(),
+                products: totals.products,
+                quantity: totals.quantity,
+                value: totals.value.value.to_string(),
+                uncosted_units: totals.value.uncosted_units,
+            }
+        }
+    }
+
+    /// A component of a kit and how many go into one kit
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct Component {
+        pub sku: String,
+        pub quantity: u32,
+    }
+
+    /// A typed link to another product
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+    pub struct Link {
+        /// `substitute-for` or `accessory-of`
+        pub kind: &'static str,
+        pub sku: String,
+    }
+
+    /// A recorded stock movement
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    pub struct Transaction {
+        pub id: String,
+        pub product_sku: String,
+        /// `Addition`, `Removal`, `InitialStock`, `Damage`, `WriteOff`,
+        /// `Adjustment`, `Quarantine`, `ReleaseQuarantine`, or `ConditionChange`
+        pub transaction_type: &'static str,
+        pub quantity: u32,
+        pub timestamp: DateTime<Utc>,
+        pub notes: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub operator: Option<String>,
+        /// `sale`, `internal-use`, `sample`, or `damage`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub reason: Option<&'static str>,
+        /// `new`, `refurbished`, or `damaged`
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub condition: Option<&'static str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub from_condition: Option<&'static str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub unit_cost: Option<String>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub customer: Option<String>,
+        /// SHA-256 of the previous transaction, when the ledger is hash-chained
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub prev_hash: Option<String>,
+        /// Order in which the transaction was recorded in its inventory;
+        /// absent for transactions recorded before sequence numbers
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub seq: Option<u64>,
+    }
+
+    impl From<&models::Transaction> for Transaction {
+        fn from(transaction: &models::Transaction) -> Self {
+            Transaction {
+                id: transaction.id.clone(),
+                product_sku: transaction.product_sku.clone(),
+                transaction_type: transaction_type_name(transaction.transaction_type),
+                quantity: transaction.quantity,
+                timestamp: transaction.timestamp,
+                notes: transaction.notes.clone(),
+                operator: transaction.operator.clone(),
+                reason: transaction.reason.map(reason_name),
+                condition: transaction.condition.map(condition_name),
+                from_condition: transaction.from_condition.map(condition_name),
+                unit_cost: transaction.unit_cost.map(|cost| cost.to_string()),
+                customer: transaction.customer.clone(),
+                prev_hash: transaction.prev_hash.clone(),
+                seq: (transaction.seq > 0).then_some(transaction.seq),
+            }
+        }
+    }
+
+    /// A notable occurrence raised by a stock operation
+    #[derive(Debug, Clone, PartialEq, Serialize)]
+    #[serde(tag = "type", rename_all = "snake_case")]
+    pub enum Event {
+        /// A product's available quantity changed
+        StockChanged {
+            sku: String,
+            /// Transaction that changed it; absent when the product was
+            /// added with stock, deleted, or restored
+            #[serde(skip_serializing_if = "Option::is_none")]
+            transaction_id: Option<String>,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            transaction_seq: Option<u64>,
+            /// Named as in `Transaction::transaction_type`
+            #[serde(skip_serializing_if = "Option::is_none")]
+            transaction_type: Option<&'static str>,
+            /// Available units before the change
+            before: u32,
+            /// Available units after the change
+            after: u32,
+            timestamp: DateTime<Utc>,
+        },
+        /// A confirmed removal exceeded the large-movement threshold
+        LargeMovement {
+            sku: String,
+            quantity: u32,
+            available: u32,
+            threshold: String,
+            timestamp: DateTime<Utc>,
+        },
+        /// Stock arrived for a product with backorders waiting
+        BackordersWaiting {
+            sku: String,
+            available: u32,
+            outstanding: u32,
+            timestamp: DateTime<Utc>,
+        },
+        /// Stock was removed to fill a backorder
+        BackorderFilled {
+            sku: String,
+            backorder_id: String,
+            quantity: u32,
+            remaining: u32,
+            timestamp: DateTime<Utc>,
+        },
+        /// A configured alert rule started matching a product
+        RuleMatched {
+            rule: String,
+            sku: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            message: Option<String>,
+            timestamp: DateTime<Utc>,
+        },
+        /// A product's history holds a transaction later than the clock allows
+        ClockDrift {
+            sku: String,
+            latest: DateTime<Utc>,
+            timestamp: DateTime<Utc>,
+        },
+        /// Imported movements are dated before their product was added
+        BeforeCreation {
+            sku: String,
+            count: usize,
+            created_at: DateTime<Utc>,
+            timestamp: DateTime<Utc>,
+        },
+        /// A record got a new ID because an earlier record had the same one
+        IdReassigned {
+            /// `product` or `transaction`
+            record: &'static str,
+            sku: String,
+            old_id: String,
+            new_id: String,
+            timestamp: DateTime<Utc>,
+        },
+        /// A change left the inventory near or over one of its size quotas
+        QuotaApproached {
+            /// `products`, `transactions`, or `data_bytes`
+            quota: &'static str,
+            used: u64,
+            limit: u64,
+            timestamp: DateTime<Utc>,
+        },
+    }
+
+    impl From<&InventoryEvent> for Event {
+        fn from(event: &InventoryEvent) -> Self {
+            match event.clone() {
+                InventoryEvent::StockChanged { sku, transaction_id, transaction_seq, transaction_type, before, after, timestamp } => {
+                    let transaction_type = transaction_type.map(transaction_type_name);
+                    Event::StockChanged { sku, transaction_id, transaction_seq, transaction_type, before, after, timestamp }
+                }
+                InventoryEvent::LargeMovement { sku, quantity, available, threshold, timestamp } => {
+                    Event::LargeMovement { sku, quantity, available, threshold: threshold.to_string(), timestamp }
+                }
+                InventoryEvent::BackordersWaiting { sku, available, outstanding, timestamp } => {
+                    Event::BackordersWaiting { sku, available, outstanding, timestamp }
+                }
+                InventoryEvent::BackorderFilled { sku, backorder_id, quantity, remaining, timestamp } => {
+                    Event::BackorderFilled { sku, backorder_id, quantity, remaining, timestamp }
+                }
+                InventoryEvent::RuleMatched { rule, sku, message, timestamp } => {
+                    Event::RuleMatched { rule, sku, message, timestamp }
+                }
+                InventoryEvent::ClockDrift { sku, latest, timestamp } => Event::ClockDrift { sku, latest, timestamp },
+                InventoryEvent::BeforeCreation { sku, count, created_at, timestamp } => {
+                    Event::BeforeCreation { sku, count, created_at, timestamp }
+                }
+                InventoryEvent::IdReassigned { record, sku, old_id, new_id, timestamp } => {
+                    Event::IdReassigned { record: record_kind_name(record), sku, old_id, new_id, timestamp }
+                }
+                InventoryEvent::QuotaApproached { quota, used, limit, timestamp } => {
+                    Event::QuotaApproached { quota: quota_name(quota), used, limit, timestamp }
+                }
+            }
+        }
+    }
+
+    fn transaction_type_name(transaction_type: TransactionType) -> &'static str {
+        match transaction_type {
+            TransactionType::Addition => "Addition",
+            TransactionType::Removal => "Removal",
+            TransactionType::InitialStock => "InitialStock",
+            TransactionType::Damage => "Damage",
+            TransactionType::WriteOff => "WriteOff",
+            TransactionType::Adjustment => "Adjustment",
+            TransactionType::Quarantine => "Quarantine",
+            TransactionType::ReleaseQuarantine => "ReleaseQuarantine",
+            TransactionType::ConditionChange => "ConditionChange",
+        }
+    }
+
+    fn record_kind_name(kind: RecordKind) -> &'static str {
+        match kind {
+            RecordKind::Product => "product",
+            RecordKind::Transaction => "transaction",
+        }
+    }
+
+    fn quota_name(quota: Quota) -> &'static str {
+        match quota {
+            Quota::Products => "products",
+            Quota::Transactions => "transactions",
+            Quota::DataBytes => "data_bytes",
+        }
+    }
+
+    fn condition_name(condition: Condition) -> &'static str {
+        match condition {
+            Condition::New => "new",
+            Condition::Refurbished => "refurbished",
+            Condition::Damaged => "damaged",
+        }
+    }
+
+    fn link_kind_name(kind: LinkKind) -> &'static str {
+        match kind {
+            LinkKind::SubstituteFor => "substitute-for",
+            LinkKind::AccessoryOf => "accessory-of",
+        }
+    }
+
+    fn reason_name(reason: ReasonCode) -> &'static str {
+        match reason {
+            ReasonCode::Sale => "sale",
+            ReasonCode::InternalUse => "internal-use",
+            ReasonCode::Sample => "sample",
+            ReasonCode::Damage => "damage",
+        }
+    }
+
+    fn is_zero(value: &u32) -> bool {
+        *value == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Condition, LinkKind, Product, ProductLink, Transaction, TransactionType};
+
+    #[test]
+    fn test_v1_matches_the_stored_shape() {
+        let mut product = Product {
+            sku: "SKU001".to_string(),
+            quantity: 3,
+            reorder_point: 5,
+            unit_cost: Some("2.50".parse().unwrap()),
+            ..Default::default()
+        };
+        product.conditions.insert(Condition::Refurbished, 1);
+        product.links.push(ProductLink { kind: LinkKind::SubstituteFor, sku: "SKU002".to_string() });
+        let mut transaction = Transaction::new("SKU001", TransactionType::WriteOff, 2, None);
+        transaction.condition = Some(Condition::Refurbished);
+        transaction.seq = 7;
+
+        // v1 started out as the stored JSON plus low_stock and shortfall
+        let mut stored = serde_json::to_value(&product).unwrap();
+        stored["low_stock"] = true.into();
+        stored["shortfall"] = 2.into();
+        assert_eq!(serde_json::to_value(v1::Product::from(&product)).unwrap(), stored);
+        assert_eq!(
+            serde_json::to_value(v1::Transaction::from(&transaction)).unwrap(),
+            serde_json::to_value(&transaction).unwrap()
+        );
+    }
+}
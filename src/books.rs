@@ -0,0 +1,154 @@
+// Stock movements as plain-text accounting entries
+//
+// `ledger-entries` writes one balanced entry per receipt or issue valued at
+// cost, for users who keep their books in beancount or ledger-cli:
+//
+//     2025-03-01 * "Received 10 x SKU001 Widget"
+//       sku: "SKU001"
+//       Assets:Inventory                         120.00 USD
+//       Liabilities:AccountsPayable             -120.00 USD
+//
+// Receipts are posted at the unit cost recorded on them, and issues at the
+// cost of the oldest receipts they took units from. Each type of movement
+// has a fixed pair of accounts (see `accounts`); movements with no known
+// cost are left out, as neither tool accepts a posting without an amount.
+
+use crate::models::{Money, TransactionType};
+use crate::service::AccountingEntry;
+
+/// Account holding the stock on hand
+pub const INVENTORY_ACCOUNT: &str = "Assets:Inventory";
+
+/// Plain-text accounting tool an export is written for
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BookFormat {
+    #[default]
+    Beancount,
+    Ledger,
+}
+
+impl BookFormat {
+    /// Every format, in the order they are listed
+    pub const ALL: [BookFormat; 2] = [BookFormat::Beancount, BookFormat::Ledger];
+
+    /// Name given to --format
+    pub fn name(self) -> &'static str {
+        match self {
+            BookFormat::Beancount => "beancount",
+            BookFormat::Ledger => "ledger",
+        }
+    }
+}
+
+impl std::str::FromStr for BookFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        BookFormat::ALL.into_iter().find(|format| format.name() == lower).ok_or_else(|| {
+            let names: Vec<&str> = BookFormat::ALL.iter().map(|format| format.name()).collect();
+            format!("Invalid ledger format '{}': expected one of {}", s, names.join(", "))
+        })
+    }
+}
+
+/// Accounts a movement is debited to and credited from, and the verb
+/// describing it
+fn accounts(transaction_type: TransactionType) -> (&'static str, &'static str, &'static str) {
+    match transaction_type {
+        TransactionType::InitialStock => (INVENTORY_ACCOUNT, "Equity:OpeningBalances", "Opening balance of"),
+        TransactionType::Removal => ("Expenses:CostOfGoodsSold", INVENTORY_ACCOUNT, "Issued"),
+        TransactionType::Damage => ("Expenses:Shrinkage:Damage", INVENTORY_ACCOUNT, "Damaged"),
+        TransactionType::WriteOff => ("Expenses:Shrinkage:WriteOff", INVENTORY_ACCOUNT, "Wrote off"),
+        TransactionType::Adjustment => ("Expenses:Shrinkage:Adjustment", INVENTORY_ACCOUNT, "Adjusted away"),
+        _ => (INVENTORY_ACCOUNT, "Liabilities:AccountsPayable", "Received"),
+    }
+}
+
+/// Entries with a known cost, written for `format` in `currency`
+///
+/// Units of a movement without a known cost are left out of its amount;
+/// movements with none at all are left out entirely. Beancount output opens
+/// the accounts it uses on the date of the first entry.
+pub fn render(entries: &[AccountingEntry], format: BookFormat, currency: &str) -> String {
+    let entries: Vec<&AccountingEntry> = entries.iter().filter(|entry| entry.cost.value > Money::default()).collect();
+    let mut lines: Vec<String> = Vec::new();
+    if format == BookFormat::Beancount {
+        if let Some(first) = entries.first() {
+            let mut opened: Vec<&str> = Vec::new();
+            for entry in &entries {
+                let (debit, credit, _) = accounts(entry.transaction_type);
+                for account in [debit, credit] {
+                    if !opened.contains(&account) {
+                        opened.push(account);
+                    }
+                }
+            }
+            opened.sort_unstable();
+            for account in opened {
+                lines.push(format!("{} open {} {}", first.timestamp.format("%Y-%m-%d"), account, currency));
+            }
+            lines.push(String::new());
+        }
+    }
+    for entry in entries {
+        let (debit, credit, verb) = accounts(entry.transaction_type);
+        let amount = entry.cost.value.to_string();
+        let description = format!("{} {} x {} {}", verb, entry.cost.units - entry.cost.uncosted_units, entry.sku, entry.name);
+        match format {
+            BookFormat::Beancount => {
+                lines.push(format!("{} * \"{}\"", entry.timestamp.format("%Y-%m-%d"), description.replace('"', "'")));
+                lines.push(format!("  sku: \"{}\"", entry.sku.replace('"', "'")));
+                lines.push(format!("  {:<34} {:>12} {}", debit, amount, currency));
+                lines.push(format!("  {:<34} {:>12} {}", credit, format!("-{}", amount), currency));
+            }
+            BookFormat::Ledger => {
+                lines.push(format!("{} {}", entry.timestamp.format("%Y/%m/%d"), description));
+                lines.push(format!("    ; sku: {}", entry.sku));
+                lines.push(format!("    {:<34} {:>12} {}", debit, amount, currency));
+                lines.push(format!("    {:<34} {:>12} {}", credit, format!("-{}", amount), currency));
+            }
+        }
+        lines.push(String::new());
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::costing::Valuation;
+    use chrono::TimeZone;
+    use chrono::Utc;
+
+    fn entry(transaction_type: TransactionType, units: u64, cents: u64) -> AccountingEntry {
+        AccountingEntry {
+            timestamp: Utc.with_ymd_and_hms(2025, 3, 1, 9, 30, 0).unwrap(),
+            sku: "SKU001".to_string(),
+            name: "Widget".to_string(),
+            transaction_type,
+            cost: Valuation { units, value: Money::from_cents(cents), uncosted_units: 0 },
+        }
+    }
+
+    #[test]
+    fn test_render_balanced_entries() {
+        let entries = [
+            entry(TransactionType::Addition, 10, 12000),
+            entry(TransactionType::Removal, 4, 4800),
+            entry(TransactionType::Damage, 1, 0),
+        ];
+        let beancount = render(&entries, BookFormat::Beancount, "USD");
+        assert!(beancount.starts_with("2025-03-01 open Assets:Inventory USD\n"), "{}", beancount);
+        assert!(beancount.contains("2025-03-01 * \"Received 10 x SKU001 Widget\"\n  sku: \"SKU001\"\n"));
+        assert!(beancount.contains("  Expenses:CostOfGoodsSold                  48.00 USD\n"));
+        assert!(beancount.contains("  Assets:Inventory                         -48.00 USD\n"));
+        assert!(!beancount.contains("Damage"));
+
+        let ledger = render(&entries, BookFormat::Ledger, "EUR");
+        assert!(ledger.starts_with("2025/03/01 Received 10 x SKU001 Widget\n    ; sku: SKU001\n"), "{}", ledger);
+        assert!(ledger.contains("    Liabilities:AccountsPayable             -120.00 EUR\n"));
+        assert!(!ledger.contains(" open "));
+        assert_eq!(render(&[], BookFormat::Ledger, "EUR"), "");
+    }
+}
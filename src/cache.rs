@@ -0,0 +1,164 @@
+// Derived read models kept up to date as products change
+//
+// `InventoryService` updates the read model whenever it changes a product or
+// its cost layers, so `low-stock` and `stats` read ready-made answers instead
+// of scanning every product or replaying its history. Each SKU's last contribution is remembered, which lets an update
+// take back the old figures before adding the new ones.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::costing::Valuation;
+use crate::models::Product;
+
+/// Product and unit counts and stock value for a group of products
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Totals {
+    /// Number of products
+    pub products: usize,
+    /// Available units across those products
+    pub units: u64,
+    /// Stock on hand, quarantined units included, at FIFO cost
+    pub value: Valuation,
+}
+
+impl Totals {
+    fn add(&mut self, contribution: &Contribution) {
+        self.products += 1;
+        self.units += contribution.units;
+        self.value.merge(contribution.value);
+    }
+
+    fn subtract(&mut self, contribution: &Contribution) {
+        self.products -= 1;
+        self.units -= contribution.units;
+        self.value.subtract(contribution.value);
+    }
+}
+
+/// What one product adds to the read model
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Contribution {
+    category: Option<String>,
+    units: u64,
+    value: Valuation,
+    low_stock: bool,
+}
+
+impl Contribution {
+    fn of(product: &Product, value: Valuation) -> Self {
+        Contribution {
+            category: product.category.clone(),
+            units: u64::from(product.quantity),
+            value,
+            low_stock: product.is_low_stock(),
+        }
+    }
+}
+
+/// Low-stock set, per-category totals, and valuation totals, maintained one
+/// product at a time
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReadModel {
+    low_stock: BTreeSet<String>,
+    categories: BTreeMap<Option<String>, Totals>,
+    totals: Totals,
+    contributions: HashMap<String, Contribution>,
+}
+
+impl ReadModel {
+    /// Build the read model from scratch, from each product and the value of
+    /// its cost layers
+    pub fn build<'a>(products: impl IntoIterator<Item = (&'a Product, Valuation)>) -> Self {
+        let mut model = ReadModel::default();
+        for (product, value) in products {
+            model.update(product, value);
+        }
+        model
+    }
+
+    /// Record a product's current state and the value of its cost layers,
+    /// replacing what it contributed before
+    pub fn update(&mut self, product: &Product, value: Valuation) {
+        let contribution = Contribution::of(product, value);
+        if self.contributions.get(&product.sku) == Some(&contribution) {
+            return;
+        }
+        self.remove(&product.sku);
+        if contribution.low_stock {
+            self.low_stock.insert(product.sku.clone());
+        }
+        self.categories.entry(contribution.category.clone()).or_default().add(&contribution);
+        self.totals.add(&contribution);
+        self.contributions.insert(product.sku.clone(), contribution);
+    }
+
+    /// Take back everything a deleted product contributed
+    pub fn remove(&mut self, sku: &str) {
+        let Some(old) = self.contributions.remove(sku) else {
+            return;
+        };
+        self.low_stock.remove(sku);
+        let category = self.categories.get_mut(&old.category).expect("category of a recorded product");
+        category.subtract(&old);
+        if category.products == 0 {
+            self.categories.remove(&old.category);
+        }
+        self.totals.subtract(&old);
+    }
+
+    /// SKUs of low-stock products, in SKU order
+    pub fn low_stock(&self) -> impl Iterator<Item = &str> {
+        self.low_stock.iter().map(String::as_str)
+    }
+
+    /// Totals per category, uncategorized products under `None` first
+    pub fn categories(&self) -> &BTreeMap<Option<String>, Totals> {
+        &self.categories
+    }
+
+    /// Totals across all products
+    pub fn totals(&self) -> Totals {
+        self.totals
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Money;
+    use crate::testing::ProductBuilder;
+
+    /// Value of `units` units at a dollar each
+    fn at_a_dollar(units: u64) -> Valuation {
+        Valuation { units, value: Money::from_cents(units * 100), uncosted_units: 0 }
+    }
+
+    fn with_values(products: &[Product]) -> Vec<(&Product, Valuation)> {
+        products.iter().map(|product| (product, at_a_dollar(u64::from(product.quantity)))).collect()
+    }
+
+    #[test]
+    fn test_updates_match_rebuild() {
+        let mut products = vec![
+            ProductBuilder::new("A").quantity(5).reorder_point(10).category("tools").build(),
+            ProductBuilder::new("B").quantity(50).reorder_point(10).category("tools").build(),
+            ProductBuilder::new("C").quantity(7).build(),
+        ];
+        let mut model = ReadModel::build(with_values(&products));
+        assert_eq!(model.low_stock().collect::<Vec<_>>(), vec!["A"]);
+        assert_eq!(model.categories()[&Some("tools".to_string())], Totals { products: 2, units: 55, value: at_a_dollar(55) });
+
+        products[0].quantity = 20;
+        products[1].category = Some("parts".to_string());
+        products[2].reorder_point = 7;
+        for (product, value) in with_values(&products) {
+            model.update(product, value);
+        }
+        model.remove("B");
+        products.remove(1);
+        assert_eq!(model, ReadModel::build(with_values(&products)));
+        assert_eq!(model.low_stock().collect::<Vec<_>>(), vec!["C"]);
+        assert_eq!(model.totals(), Totals { products: 2, units: 27, value: at_a_dollar(27) });
+        assert!(!model.categories().contains_key(&Some("parts".to_string())));
+    }
+}
@@ -0,0 +1,140 @@
+// Checkpoints of long imports, so one that fails partway can be resumed
+//
+// `import-transactions` applies a file of more than CHUNK_ROWS movements a
+// chunk at a time. Before the first chunk it writes a job to
+// imports/<job-id>.json in the data directory, and after each chunk is saved
+// it records how many rows are in and the sequence number of the last
+// transaction. If a chunk fails, `import-transactions --resume <job-id>`
+// reads the same file again, with the same import profile, and carries on
+// after the checkpoint. The job is removed once every row is in.
+//
+// A chunk whose save failed may still reach the data files when the service
+// saves on exit, so a resume first looks for the chunk after the checkpoint
+// among the transactions recorded since, and skips it if it is all there.
+
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::mapping::ImportProfile;
+
+/// Directory in the data directory holding unfinished import jobs
+pub const IMPORTS_DIR: &str = "imports";
+
+/// Rows applied and saved together between checkpoints
+pub const CHUNK_ROWS: usize = 500;
+
+/// An import being applied a chunk at a time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ImportJob {
+    /// Short identifier given to `--resume`
+    pub id: String,
+    /// The file being imported
+    pub file: PathBuf,
+    /// SHA-256 of the file when the import started
+    pub digest: String,
+    /// Mapping the file is read through, if it is another system's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<ImportProfile>,
+    /// Rows applied so far, counted in file order
+    pub applied: usize,
+    /// Sequence number of the last transaction when the checkpoint was
+    /// made; none until the first one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seq: Option<u64>,
+    /// When the import started
+    pub started_at: DateTime<Utc>,
+}
+
+impl ImportJob {
+    /// A new job for the file holding `contents`
+    pub fn start(file: &Path, contents: &[u8], profile: Option<ImportProfile>) -> ImportJob {
+        ImportJob {
+            id: Uuid::new_v4().simple().to_string()[..8].to_string(),
+            file: file.to_path_buf(),
+            digest: digest(contents),
+            profile,
+            applied: 0,
+            last_seq: None,
+            started_at: Utc::now(),
+        }
+    }
+
+    /// The unfinished job with this identifier
+    pub fn load(data_dir: &Path, id: &str) -> Result<ImportJob, String> {
+        let path = job_path(data_dir, id);
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(format!("No unfinished import with job ID '{}'", id)),
+            Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Write the job's checkpoint
+    pub fn save(&self, data_dir: &Path) -> Result<(), String> {
+        let path = job_path(data_dir, &self.id);
+        std::fs::create_dir_all(data_dir.join(IMPORTS_DIR))
+            .map_err(|e| format!("Failed to create {}: {}", data_dir.join(IMPORTS_DIR).display(), e))?;
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    /// Remove the finished job, if it was ever saved
+    pub fn remove(&self, data_dir: &Path) -> Result<(), String> {
+        let path = job_path(data_dir, &self.id);
+        match std::fs::remove_file(&path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(format!("Failed to remove {}: {}", path.display(), e)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Refuse to resume from a file that changed since the import started,
+    /// as its rows may no longer line up with the checkpoint
+    pub fn check_file(&self, contents: &[u8]) -> Result<(), String> {
+        if digest(contents) != self.digest {
+            return Err(format!(
+                "{} has changed since import {} started, so it can't be resumed",
+                self.file.display(), self.id
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Path of an import job
+fn job_path(data_dir: &Path, id: &str) -> PathBuf {
+    data_dir.join(IMPORTS_DIR).join(format!("{}.json", id))
+}
+
+/// Hex SHA-256 of a file's contents
+fn digest(contents: &[u8]) -> String {
+    Sha256::digest(contents).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_job_saved_loaded_and_removed() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut job = ImportJob::start(Path::new("history.csv"), b"SKU001,2024-03-01,addition,5\n", None);
+        assert_eq!(job.id.len(), 8);
+        job.applied = 500;
+        job.last_seq = Some(12);
+        job.save(temp_dir.path()).unwrap();
+
+        let loaded = ImportJob::load(temp_dir.path(), &job.id).unwrap();
+        assert_eq!(loaded, job);
+        assert!(loaded.check_file(b"SKU001,2024-03-01,addition,5\n").is_ok());
+        assert!(loaded.check_file(b"SKU001,2024-03-01,addition,6\n").is_err());
+
+        job.remove(temp_dir.path()).unwrap();
+        job.remove(temp_dir.path()).unwrap();
+        assert!(ImportJob::load(temp_dir.path(), &job.id).unwrap_err().contains("No unfinished import"));
+    }
+}
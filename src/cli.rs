@@ -1,11 +1,57 @@
 // Command-line interface for Stock Control System
 
+use std::collections::BTreeMap;
 use std::env;
-use chrono::{DateTime, NaiveDateTime, Utc};
+use std::io;
+use std::path::PathBuf;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc};
+use serde_json::{json, Value};
 
-use crate::errors::ServiceError;
-use crate::service::InventoryService;
-use crate::storage::JsonStorage;
+use crate::api;
+use crate::books::{self, BookFormat};
+use crate::checkpoint::{ImportJob, CHUNK_ROWS};
+use crate::costing::{CostLayers, Valuation};
+use crate::config::Config;
+use crate::convert;
+use crate::diff::{self, InventoryDiff};
+use crate::edi::{self, FeedFormat};
+use crate::export::{self, Anonymizer, Dataset, ExportFormat};
+use crate::feed;
+use crate::forecast::{self, Seasonality};
+use crate::errors::{DeletionBlocker, ServiceError, StorageError};
+use crate::hooks;
+use crate::i18n::{self, condition_label, tr, trf, Locale, Msg};
+use crate::inbox;
+use crate::logging;
+use crate::plugins;
+use crate::mqtt;
+use crate::outbox;
+use crate::receipt::{Printer, Receipt, ReceiptFormat};
+use crate::reorder;
+use crate::rules;
+use crate::schema;
+use crate::script;
+use crate::shop;
+use crate::stocktake::{self, SheetFormat};
+use crate::template::Template;
+use crate::models::{
+    Component, Condition, GroupBy, Interval, KpiSnapshot, LinkKind, ListColumn, MergePolicy, Money, MovementThreshold, Product, ProductFilter, ProductLink,
+    ProductSort, ProductTemplate, Quota, ReasonCode,
+    ReceivingSession, Transaction, TransactionType, Weight,
+};
+use crate::service::{AdditionOptions, DeleteOptions, Discrepancy, ExternalQuantity, HistoricalEntry, InventoryService, ProductUpdate, ReceivingReview, RemovalOptions};
+use crate::storage::{JsonStorage, Storage};
+
+mod args;
+mod bulk;
+mod help;
+mod kiosk;
+mod parse;
+mod wizard;
+
+pub use parse::{parse_args, parse_args_with_aliases, split_global_options, GlobalOptions};
+pub use kiosk::run_kiosk;
+pub use wizard::{review_reorder_suggestions, run_product_wizard};
 
 /// CLI commands for inventory operations
 #[derive(Debug, PartialEq)]
@@ -17,6 +63,32 @@ pub enum Command {
         description: String,
         quantity: u32,
         reorder_point: u32,
+        category: Option<String>,
+        supplier: Option<String>,
+        location: Option<String>,
+        unit_cost: Option<Money>,
+    },
+    /// Add a new product with the defaults of a saved template
+    AddProductFromTemplate {
+        template: String,
+        sku: String,
+        name: String,
+        description: Option<String>,
+        quantity: u32,
+        reorder_point: Option<u32>,
+        category: Option<String>,
+        supplier: Option<String>,
+        location: Option<String>,
+        unit_cost: Option<Money>,
+    },
+    /// Add a new product by prompting for each field
+    AddProductInteractive,
+    /// Apply the same changes to every product passing a filter, or list
+    /// them unless confirmed
+    BulkUpdate {
+        filter: ProductFilter,
+        changes: ProductUpdate,
+        confirm: bool,
     },
     /// Update an existing product
     UpdateProduct {
@@ -24,633 +96,4910 @@ pub enum Command {
         name: Option<String>,
         description: Option<String>,
         reorder_point: Option<u32>,
+        category: Option<String>,
+        supplier: Option<String>,
+        location: Option<String>,
+        barcode: Option<String>,
+        large_threshold: Option<Option<MovementThreshold>>,
+        components: Option<Vec<Component>>,
+        unit_cost: Option<Option<Money>>,
+        tare_weight: Option<Option<Weight>>,
+        unit_weight: Option<Option<Weight>>,
+        lead_time: Option<Option<u32>>,
+    },
+    /// Copy all data to another storage backend and verify the copy
+    ConvertStorage {
+        target: String,
+        from: String,
+        to: String,
+    },
+    /// Add or remove stock counted by weight on a scale
+    Weigh {
+        sku: String,
+        remove: bool,
+        weight: Option<Weight>,
+        port: String,
+        baud: u32,
+        notes: Option<String>,
     },
     /// Add stock to a product
     AddStock {
         sku: String,
         quantity: u32,
         notes: Option<String>,
+        condition: Option<Condition>,
+        unit_cost: Option<Money>,
     },
     /// Remove stock from a product
     RemoveStock {
         sku: String,
         quantity: u32,
         notes: Option<String>,
+        confirm_large: bool,
+        transaction_type: TransactionType,
+        reason: Option<ReasonCode>,
+        condition: Option<Condition>,
+        backorder: bool,
+        customer: Option<String>,
+    },
+    /// Add a customer that removals can be shipped to
+    AddCustomer {
+        code: String,
+        name: String,
+    },
+    /// List customers
+    ListCustomers,
+    /// Summarize units shipped to customers by product
+    Shipments {
+        customer: Option<String>,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    },
+    /// List outstanding backorders, optionally for one product
+    Backorders {
+        sku: Option<String>,
+    },
+    /// Fill a product's backorders from its available stock
+    FillBackorders {
+        sku: String,
+    },
+    /// Move stock from one condition to another
+    ChangeCondition {
+        sku: String,
+        quantity: u32,
+        from: Condition,
+        to: Condition,
+        notes: Option<String>,
+    },
+    /// Add or remove stock for every `SKU,QTY[,NOTES]` line read from stdin
+    StockFromStdin {
+        transaction_type: TransactionType,
+        confirm_large: bool,
+        reason: Option<ReasonCode>,
+    },
+    /// Import past stock movements from a `SKU,TIMESTAMP,TYPE,QTY[,NOTES]` file,
+    /// or another system's file through a named import profile
+    ImportTransactions {
+        file: String,
+        profile: Option<String>,
+    },
+    /// Carry on with an import that stopped partway
+    ResumeImport {
+        job: String,
+    },
+    /// Apply an import file a chunk at a time, checkpointing the job in the
+    /// data directory so it can be resumed
+    ImportJob {
+        job: ImportJob,
+        data_dir: PathBuf,
+    },
+    /// Store a supplier's `SKU,PRICE[,EFFECTIVE_DATE]` price list
+    ImportPrices {
+        supplier: String,
+        file: String,
+    },
+    /// Write current inventory positions as an EDI 846 or CSV feed
+    InventoryAdvice {
+        format: FeedFormat,
+        sender: Option<String>,
+        receiver: Option<String>,
+        control: Option<u32>,
+        output: Option<String>,
+    },
+    /// Push every mapped product's stock level to the configured online store
+    ShopSync {
+        dry_run: bool,
+    },
+    /// Render configured feeds and upload them; all of them, the named ones,
+    /// or with `due` those whose interval has passed
+    FeedPush {
+        names: Vec<String>,
+        due: bool,
+    },
+    /// Show each configured feed and the outcome of its last pushes
+    FeedStatus,
+    /// Print the outbox events numbered after `since`, oldest first
+    EventsTail {
+        since: u64,
+        limit: Option<usize>,
+    },
+    /// Compare quantities with another system's CSV export or a stock count,
+    /// optionally adjusting to match it
+    Reconcile {
+        file: String,
+        key: String,
+        qty_column: String,
+        apply: bool,
+        /// Second count confirming variances beyond the stocktake tolerance
+        recount: Option<String>,
+        /// SKUs whose variances beyond the tolerance are accepted as counted
+        confirm: Vec<String>,
+        /// Count sheet whose lines alone are read, leaving out products not on it
+        sheet: Option<usize>,
+    },
+    /// Write numbered count sheets of products to count in a stocktake
+    CountSheets {
+        location: Option<String>,
+        category: Option<String>,
+        blind: bool,
+        format: SheetFormat,
+        output: Option<String>,
+    },
+    /// Show each supplier's current price for a product, cheapest first
+    ComparePrices {
+        sku: String,
+        on: Option<NaiveDate>,
+    },
+    /// Create products from a legacy system's `SKU,QTY,REORDER_POINT,NAME` file,
+    /// recording their stock as of the cutover date
+    MigrateOpeningBalances {
+        file: String,
+        cutover: DateTime<Utc>,
+        merge: Option<MergePolicy>,
     },
     /// View a single product by SKU
     ViewProduct {
         sku: String,
+        template: Option<String>,
+    },
+    /// List all products, or those passing a filter
+    ListProducts {
+        filter: ProductFilter,
+        template: Option<String>,
+        /// Columns of a table listing; empty for the plain listing
+        columns: Vec<ListColumn>,
+        /// Add a totals row to the table
+        totals: bool,
+        /// List group totals instead of products
+        group_by: Option<GroupBy>,
+    },
+    /// List products with low stock, optionally grouped
+    LowStock {
+        group_by: Option<GroupBy>,
+        template: Option<String>,
+    },
+    /// Record units ordered from a supplier, or take them off the order with `cancel`
+    MarkOrdered {
+        sku: String,
+        quantity: u32,
+        cancel: bool,
+    },
+    /// List the configured alert rules and the products they match
+    CheckRules,
+    /// Run a Rhai script against a read-only view of the inventory
+    RunScript {
+        path: String,
+        args: Vec<String>,
+    },
+    /// Acknowledge a low-stock alert, or withdraw the acknowledgement
+    Ack {
+        sku: String,
+        until: Option<NaiveDate>,
+        clear: bool,
     },
-    /// List all products
-    ListProducts,
-    /// List products with low stock
-    LowStock,
     /// View transaction history for a product
     History {
         sku: String,
         start: Option<DateTime<Utc>>,
         end: Option<DateTime<Utc>>,
+        include_archived: bool,
+        customer: Option<String>,
+        template: Option<String>,
+    },
+    /// Compare two snapshots, a snapshot with the current inventory, or the
+    /// current inventory with its state at a past date
+    Diff {
+        snapshots: Vec<String>,
+        since: Option<DateTime<Utc>>,
+        verify: Option<String>,
+    },
+    /// Put available stock on hold, or release held stock with `release`
+    Quarantine {
+        sku: String,
+        quantity: u32,
+        notes: Option<String>,
+        release: bool,
+    },
+    /// Show product, unit, and value totals, overall and per category
+    Stats,
+    /// Compare the movements of several products side by side, or merge their histories
+    HistoryCompare {
+        skus: Vec<String>,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        merge: bool,
+    },
+    /// Show the stock value at each period boundary
+    ValueHistory {
+        interval: Interval,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    },
+    /// Suggest reorder points from recorded demand, optionally reviewing them to apply
+    SuggestReorderPoints {
+        service_level: f64,
+        lead_time: Option<u32>,
+        days: Option<u32>,
+        apply: bool,
+    },
+    /// Forecast when each product runs out, optionally with seasonal demand
+    Forecast {
+        seasonality: Option<Seasonality>,
+        days: Option<u32>,
+        horizon: Option<u32>,
+    },
+    /// Chart the recorded KPI snapshots with `start <= date < end`
+    Kpis {
+        start: Option<NaiveDate>,
+        end: Option<NaiveDate>,
+    },
+    /// Show the cost of the goods issued in a period, taken from FIFO cost layers
+    Cogs {
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    },
+    /// Write receipts and issues valued at cost as beancount or ledger-cli entries
+    LedgerEntries {
+        format: BookFormat,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        currency: String,
+    },
+    /// Summarize damage, write-offs, and adjustments by product and type
+    Shrinkage {
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        condition: Option<Condition>,
+    },
+    /// Summarize removals by reason code, for all products or one
+    Consumption {
+        sku: Option<String>,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        condition: Option<Condition>,
+    },
+    /// Report how many kits the component stock can assemble
+    CheckBuildable {
+        sku: String,
+    },
+    /// Start staging a delivery, optionally with the expected `SKU,QTY` lines from a file
+    ReceiveStart {
+        reference: Option<String>,
+        expected_file: Option<String>,
+    },
+    /// Stage units counted in the delivery
+    ReceiveAdd {
+        sku: String,
+        quantity: u32,
+        notes: Option<String>,
+    },
+    /// Compare the staged delivery with what was expected
+    ReceiveReview,
+    /// Record the staged delivery as stock additions
+    ReceiveCommit,
+    /// Discard the staged delivery
+    ReceiveCancel,
+    /// Stage suppliers' e-mailed shipping notices from a maildir, or start
+    /// receiving a staged one
+    ReceiveInbox {
+        maildir: Option<String>,
+        start: Option<String>,
+    },
+    /// Write products and transactions to a new data directory, or their
+    /// stock changes as change data capture records
+    Export {
+        dir: String,
+        anonymize: bool,
+        sign: Option<String>,
+        format: ExportFormat,
+        since: Option<u64>,
+    },
+    /// Move transactions before a date to the archive
+    ArchiveTransactions {
+        before: DateTime<Utc>,
+    },
+    /// Rewrite the data files, optionally archiving old transactions
+    Compact {
+        archive_years: Option<u32>,
+    },
+    /// Clear notes and customer codes older than the retention policy allows
+    ApplyRetention {
+        dry_run: bool,
+    },
+    /// Give new IDs to records whose ID an earlier record already has
+    HealIds {
+        dry_run: bool,
+    },
+    /// Record today's inventory totals in the KPI file
+    SnapshotKpis,
+    /// Check the transaction hash chain, optionally against a backup's chain head
+    VerifyLedger {
+        against: Option<String>,
+    },
+    /// Print a goods-received or goods-issued note for a transaction
+    Receipt {
+        transaction_id: String,
+        format: ReceiptFormat,
+        output: Option<String>,
+        printer: Option<Printer>,
     },
     /// Delete a product
     DeleteProduct {
         sku: String,
+        force: bool,
+    },
+    /// Link a product to another, or take the link away with `remove`
+    LinkProduct {
+        sku: String,
+        kind: LinkKind,
+        target: String,
+        remove: bool,
+    },
+    /// List deleted products that can still be restored
+    TrashList,
+    /// Run the locked-down scan-in/scan-out mode on stdin
+    Kiosk,
+    /// Save product listing filters under a name
+    SaveView {
+        name: String,
+        filter: ProductFilter,
+    },
+    /// List the products matching a saved view
+    RunView {
+        name: String,
+        template: Option<String>,
+    },
+    /// List saved views
+    ListViews,
+    /// Remove a saved view
+    DeleteView {
+        name: String,
+    },
+    /// Save defaults for new products under a name
+    SaveProductTemplate {
+        name: String,
+        template: ProductTemplate,
+    },
+    /// List saved product templates
+    ListProductTemplates,
+    /// Remove a saved product template
+    DeleteProductTemplate {
+        name: String,
+    },
+    /// Restore a deleted product from the trash
+    TrashRestore {
+        sku: String,
     },
     /// Show help message
     Help,
+    /// Show detailed help for a single command
+    CommandHelp {
+        command: String,
+    },
+    /// Describe all commands, or a single one, as JSON
+    HelpJson {
+        command: Option<String>,
+    },
 }
 
-/// Parse command-line arguments into a Command
+/// How command results are rendered
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputMode {
+    /// Human-readable output with labels and headings
+    #[default]
+    Normal,
+    /// Only essential values, one record per line
+    Quiet,
+}
+
+/// Execute a command against the inventory service
 /// 
 /// # Arguments
-/// * `args` - Command-line arguments (including program name)
+/// * `command` - The command to execute
+/// * `service` - The inventory service instance
 /// 
 /// # Returns
-/// * `Ok(Command)` - Successfully parsed command
-/// * `Err(String)` - Error message describing the parsing failure
-pub fn parse_args(args: &[String]) -> Result<Command, String> {
-    if args.len() < 2 {
-        return Ok(Command::Help);
-    }
-
-    let command = args[1].as_str();
-    
-    match command {
-        "add-product" => parse_add_product(&args[2..]),
-        "update-product" => parse_update_product(&args[2..]),
-        "add-stock" => parse_add_stock(&args[2..]),
-        "remove-stock" => parse_remove_stock(&args[2..]),
-        "view-product" => parse_view_product(&args[2..]),
-        "list-products" => Ok(Command::ListProducts),
-        "low-stock" => Ok(Command::LowStock),
-        "history" => parse_history(&args[2..]),
-        "delete-product" => parse_delete_product(&args[2..]),
-        "help" | "--help" | "-h" => Ok(Command::Help),
-        _ => Err(format!("Unknown command: '{}'. Use 'help' to see available commands.", command)),
-    }
+/// * `Ok(String)` - Success message to display
+/// * `Err(String)` - Error message to display
+pub fn execute_command(command: Command, service: &mut InventoryService) -> Result<String, String> {
+    execute_command_with_mode(command, service, OutputMode::Normal)
 }
 
+/// Execute a command, rendering its result in the given output mode
+pub fn execute_command_with_mode(
+    command: Command,
+    service: &mut InventoryService,
+    mode: OutputMode,
+) -> Result<String, String> {
+    let quiet = mode == OutputMode::Quiet;
+    match command {
+        Command::AddProduct {
+            sku, name, description, quantity, reorder_point, category, supplier, location, unit_cost,
+        } => {
+            let mut product = service.add_product(sku, name, description, quantity, reorder_point)
+                .map_err(format_error)?;
+            if category.is_some() || supplier.is_some() || location.is_some() || unit_cost.is_some() {
+                let unit_cost = unit_cost.map(Some);
+                let update = ProductUpdate { category, supplier, location, unit_cost, ..Default::default() };
+                product = service.update_product(&product.sku, update).map_err(format_error)?;
+            }
+            if quiet {
+                return Ok(product.sku);
+            }
+            Ok(format!("{}\n{}", tr(Msg::ProductAdded), format_product_fields(&product, true, "")))
+        }
+        
+        Command::AddProductInteractive => {
+            let stdin = io::stdin();
+            let product = run_product_wizard(service, &mut stdin.lock(), &mut io::stdout())?;
+            if quiet {
+                return Ok(product.sku);
+            }
+            Ok(format!("{}\n{}", tr(Msg::ProductAdded), format_product_fields(&product, true, "")))
+        }
 
-/// Parse add-product command arguments
-fn parse_add_product(args: &[String]) -> Result<Command, String> {
-    if args.len() < 5 {
-        return Err(
-            "Usage: add-product <sku> <name> <description> <quantity> <reorder_point>\n\
-             Example: add-product SKU001 \"Widget\" \"A useful widget\" 100 20".to_string()
-        );
-    }
+        Command::BulkUpdate { filter, changes, confirm: false } => {
+            let products = service.query_products(&filter);
+            if quiet {
+                return Ok(sku_lines(products));
+            }
+            if products.is_empty() {
+                return Ok(tr(Msg::NoProductsMatch).to_string());
+            }
+            let mut output = vec![trf(Msg::BulkUpdatePreview, &[("count", &products.len())])];
+            output.extend(products.iter().map(|p| format!("  {} - {}", p.sku, p.name)));
+            output.push(trf(Msg::BulkUpdateConfirm, &[("changes", &update_options(&changes))]));
+            Ok(output.join("\n"))
+        }
 
-    let sku = args[0].clone();
-    let name = args[1].clone();
-    let description = args[2].clone();
-    let quantity = args[3].parse::<u32>()
-        .map_err(|_| format!("Invalid quantity '{}': must be a non-negative integer", args[3]))?;
-    let reorder_point = args[4].parse::<u32>()
-        .map_err(|_| format!("Invalid reorder point '{}': must be a non-negative integer", args[4]))?;
+        Command::BulkUpdate { filter, changes, confirm: true } => {
+            let updated = service.update_where(&filter, changes).map_err(format_error)?;
+            if quiet {
+                return Ok(sku_lines(&updated));
+            }
+            if updated.is_empty() {
+                return Ok(tr(Msg::NoProductsMatch).to_string());
+            }
+            Ok(trf(Msg::BulkUpdated, &[("count", &updated.len())]))
+        }
 
-    Ok(Command::AddProduct {
-        sku,
-        name,
-        description,
-        quantity,
-        reorder_point,
-    })
-}
+        Command::UpdateProduct {
+            sku, name, description, reorder_point, category, supplier, location, barcode, large_threshold, components,
+            unit_cost, tare_weight, unit_weight, lead_time,
+        } => {
+            let update = ProductUpdate {
+                name,
+                description,
+                reorder_point,
+                category,
+                supplier,
+                location,
+                barcode,
+                large_movement_threshold: large_threshold,
+                components,
+                unit_cost,
+                tare_weight,
+                unit_weight,
+                lead_time_days: lead_time,
+            };
+            let product = service.update_product(&sku, update)
+                .map_err(format_error)?;
+            if quiet {
+                return Ok(product.sku);
+            }
+            Ok(format!("{}\n{}", tr(Msg::ProductUpdated), format_product_fields(&product, false, "")))
+        }
+        
+        Command::AddStock { sku, quantity, notes, condition, unit_cost } => {
+            let options = AdditionOptions { condition: condition.unwrap_or_default(), unit_cost };
+            service.add_stock_with_options(&sku, quantity, notes, options).map_err(format_error)?;
+            let product = service.get_product(&sku).map_err(format_error)?;
+            if quiet {
+                return Ok(product.quantity.to_string());
+            }
+            Ok(format!(
+                "{}\n  {}: {}\n  {}: {}\n  {}: {}",
+                tr(Msg::StockAdded),
+                tr(Msg::LabelSku), sku,
+                tr(Msg::LabelAdded), quantity,
+                tr(Msg::LabelNewQuantity), product.quantity
+            ))
+        }
+        
+        Command::Weigh { sku, remove, weight, port, baud, notes } => {
+            let gross = match weight {
+                Some(weight) => weight,
+                None => crate::scale::read_weight(&port, baud)?,
+            };
+            let weighing = service.units_for_weight(&sku, gross).map_err(format_error)?;
+            let notes = Some(match notes {
+                Some(notes) => format!("{}: {}", weighing, notes),
+                None => weighing.to_string(),
+            });
+            if remove {
+                service.remove_stock(&sku, weighing.units, notes).map_err(format_error)?;
+            } else {
+                service.add_stock(&sku, weighing.units, notes).map_err(format_error)?;
+            }
+            let product = service.get_product(&sku).map_err(format_error)?;
+            if quiet {
+                return Ok(format!("{}\t{}", weighing.units, product.quantity));
+            }
+            let args: [(&str, &dyn std::fmt::Display); 4] = [
+                ("gross", &weighing.gross),
+                ("units", &weighing.units),
+                ("sku", &sku),
+                ("total", &product.quantity),
+            ];
+            Ok(trf(if remove { Msg::WeighedOut } else { Msg::WeighedIn }, &args))
+        }
 
-/// Parse update-product command arguments
-fn parse_update_product(args: &[String]) -> Result<Command, String> {
-    if args.is_empty() {
-        return Err(
-            "Usage: update-product <sku> [--name <name>] [--description <desc>] [--reorder-point <n>]\n\
-             Example: update-product SKU001 --name \"New Name\" --reorder-point 30".to_string()
-        );
-    }
+        Command::MarkOrdered { sku, quantity, cancel } => {
+            let on_order = if cancel {
+                service.cancel_ordered(&sku, quantity).map_err(format_error)?
+            } else {
+                service.mark_ordered(&sku, quantity).map_err(format_error)?
+            };
+            if quiet {
+                return Ok(on_order.to_string());
+            }
+            let msg = if cancel { Msg::OrderCancelled } else { Msg::OrderMarked };
+            Ok(trf(msg, &[("quantity", &quantity), ("sku", &sku), ("on_order", &on_order)]))
+        }
 
-    let sku = args[0].clone();
-    let mut name = None;
-    let mut description = None;
-    let mut reorder_point = None;
+        Command::Quarantine { sku, quantity, notes, release } => {
+            if release {
+                service.release_quarantine(&sku, quantity, notes).map_err(format_error)?;
+            } else {
+                service.quarantine(&sku, quantity, notes).map_err(format_error)?;
+            }
+            let product = service.get_product(&sku).map_err(format_error)?;
+            if quiet {
+                return Ok(format!("{}\t{}", product.quantity, product.quarantined));
+            }
+            Ok(format!(
+                "{}\n  {}: {}\n  {}: {}\n  {}: {}",
+                tr(if release { Msg::StockReleased } else { Msg::StockQuarantined }),
+                tr(Msg::LabelSku), sku,
+                tr(Msg::LabelAvailable), product.quantity,
+                tr(Msg::LabelQuarantined), product.quarantined
+            ))
+        }
 
-    let mut i = 1;
-    while i < args.len() {
-        match args[i].as_str() {
-            "--name" => {
-                if i + 1 >= args.len() {
-                    return Err("--name requires a value".to_string());
-                }
-                name = Some(args[i + 1].clone());
-                i += 2;
+        Command::ChangeCondition { sku, quantity, from, to, notes } => {
+            service.change_condition(&sku, quantity, from, to, notes).map_err(format_error)?;
+            let product = service.get_product(&sku).map_err(format_error)?;
+            if quiet {
+                return Ok(format!("{}\t{}", product.condition_quantity(from), product.condition_quantity(to)));
             }
-            "--description" => {
-                if i + 1 >= args.len() {
-                    return Err("--description requires a value".to_string());
-                }
-                description = Some(args[i + 1].clone());
-                i += 2;
+            Ok(format!(
+                "{}\n  {}: {}\n  {}: {} {} -> {}\n  {}: {}",
+                tr(Msg::ConditionChanged),
+                tr(Msg::LabelSku), sku,
+                tr(Msg::LabelMoved), quantity, condition_label(from), condition_label(to),
+                tr(Msg::LabelCondition), condition_breakdown(product)
+            ))
+        }
+
+        Command::RemoveStock { sku, quantity, notes, confirm_large, transaction_type, reason, condition, backorder, customer } => {
+            let options = RemovalOptions { confirm_large, transaction_type: Some(transaction_type), reason, condition, customer };
+            let backorder = if backorder {
+                service.remove_or_backorder(&sku, quantity, notes, options).map_err(format_error)?
+            } else {
+                service.remove_stock_with_options(&sku, quantity, notes, options).map_err(format_error)?;
+                None
+            };
+            let removed = quantity - backorder.as_ref().map_or(0, |backorder| backorder.quantity);
+            let product = service.get_product(&sku).map_err(format_error)?;
+            if quiet {
+                return Ok(product.quantity.to_string());
             }
-            "--reorder-point" => {
-                if i + 1 >= args.len() {
-                    return Err("--reorder-point requires a value".to_string());
-                }
-                reorder_point = Some(args[i + 1].parse::<u32>()
-                    .map_err(|_| format!("Invalid reorder point '{}': must be a non-negative integer", args[i + 1]))?);
-                i += 2;
+            let mut output = format!(
+                "{}\n  {}: {}\n  {}: {}\n  {}: {}",
+                tr(Msg::StockRemoved),
+                tr(Msg::LabelSku), sku,
+                tr(Msg::LabelRemoved), removed,
+                tr(Msg::LabelNewQuantity), product.quantity
+            );
+            if let Some(backorder) = backorder {
+                output.push_str(&format!("\n  {}: {} ({})", tr(Msg::LabelBackordered), backorder.quantity, backorder.short_id()));
             }
-            _ => {
-                return Err(format!("Unknown option: '{}'. Valid options: --name, --description, --reorder-point", args[i]));
+            Ok(output)
+        }
+
+        Command::AddCustomer { code, name } => {
+            let customer = service.add_customer(code, name).map_err(format_error)?;
+            if quiet {
+                return Ok(customer.code);
             }
+            Ok(trf(Msg::CustomerAdded, &[("code", &customer.code), ("name", &customer.name)]))
         }
-    }
 
-    Ok(Command::UpdateProduct {
-        sku,
-        name,
-        description,
-        reorder_point,
-    })
-}
+        Command::ListCustomers => {
+            let customers = service.customers().map_err(format_error)?;
+            if quiet {
+                let records: Vec<String> = customers.iter().map(|c| format!("{}\t{}", c.code, c.name)).collect();
+                return Ok(records.join("\n"));
+            }
+            if customers.is_empty() {
+                return Ok(tr(Msg::NoCustomers).to_string());
+            }
+            let mut output = vec![trf(Msg::CustomersHeader, &[("count", &customers.len())])];
+            output.extend(customers.iter().map(|customer| format!("  {} - {}", customer.code, customer.name)));
+            Ok(output.join("\n"))
+        }
 
-/// Parse add-stock command arguments
-fn parse_add_stock(args: &[String]) -> Result<Command, String> {
-    if args.len() < 2 {
-        return Err(
-            "Usage: add-stock <sku> <quantity> [--notes <notes>]\n\
-             Example: add-stock SKU001 50 --notes \"Received shipment\"".to_string()
-        );
-    }
+        Command::Shipments { customer, start, end } => {
+            let lines = service.shipments(customer.as_deref(), start, end).map_err(format_error)?;
+            if quiet {
+                let records: Vec<String> = lines
+                    .iter()
+                    .map(|line| format!("{}\t{}\t{}\t{}", line.customer, line.sku, line.transactions, line.units))
+                    .collect();
+                return Ok(records.join("\n"));
+            }
+            let (from, to) = period_labels(start, end);
+            if lines.is_empty() {
+                return Ok(trf(Msg::NoShipments, &[("from", &from), ("to", &to)]));
+            }
 
-    let sku = args[0].clone();
-    let quantity = args[1].parse::<u32>()
-        .map_err(|_| format!("Invalid quantity '{}': must be a positive integer", args[1]))?;
-    
-    let mut notes = None;
-    if args.len() > 2 && args[2] == "--notes" {
-        if args.len() > 3 {
-            notes = Some(args[3].clone());
-        } else {
-            return Err("--notes requires a value".to_string());
+            let customers = service.customers().map_err(format_error)?;
+            let units: u64 = lines.iter().map(|line| line.units).sum();
+            let mut codes: Vec<&str> = lines.iter().map(|line| line.customer.as_str()).collect();
+            codes.dedup();
+            let mut output = vec![trf(Msg::ShipmentsHeader, &[
+                ("from", &from),
+                ("to", &to),
+                ("units", &units),
+                ("count", &codes.len()),
+            ])];
+            for (index, line) in lines.iter().enumerate() {
+                if index == 0 || lines[index - 1].customer != line.customer {
+                    let name = customers.iter().find(|c| c.code == line.customer).map_or("", |c| c.name.as_str());
+                    output.push(format!("  {} - {}", line.customer, name));
+                }
+                let product = service.get_product(&line.sku).map(|product| product.name.as_str()).unwrap_or("");
+                output.push(trf(Msg::ShipmentLine, &[
+                    ("sku", &line.sku),
+                    ("name", &product),
+                    ("units", &line.units),
+                    ("count", &line.transactions),
+                ]));
+            }
+            Ok(output.join("\n"))
         }
-    }
 
-    Ok(Command::AddStock { sku, quantity, notes })
-}
-
-/// Parse remove-stock command arguments
-fn parse_remove_stock(args: &[String]) -> Result<Command, String> {
-    if args.len() < 2 {
-        return Err(
-            "Usage: remove-stock <sku> <quantity> [--notes <notes>]\n\
-             Example: remove-stock SKU001 10 --notes \"Sold to customer\"".to_string()
-        );
-    }
+        Command::Backorders { sku } => {
+            let mut backorders = service.backorders().map_err(format_error)?;
+            if let Some(sku) = &sku {
+                service.get_product(sku).map_err(format_error)?;
+                backorders.retain(|backorder| &backorder.sku == sku);
+            }
+            if quiet {
+                let records: Vec<String> = backorders
+                    .iter()
+                    .map(|b| format!("{}\t{}\t{}\t{}", b.id, b.sku, b.quantity, b.created_at.to_rfc3339()))
+                    .collect();
+                return Ok(records.join("\n"));
+            }
+            if backorders.is_empty() {
+                return Ok(tr(Msg::NoBackorders).to_string());
+            }
 
-    let sku = args[0].clone();
-    let quantity = args[1].parse::<u32>()
-        .map_err(|_| format!("Invalid quantity '{}': must be a positive integer", args[1]))?;
-    
-    let mut notes = None;
-    if args.len() > 2 && args[2] == "--notes" {
-        if args.len() > 3 {
-            notes = Some(args[3].clone());
-        } else {
-            return Err("--notes requires a value".to_string());
+            let units: u64 = backorders.iter().map(|backorder| u64::from(backorder.quantity)).sum();
+            let mut output = vec![trf(Msg::BackordersHeader, &[("count", &backorders.len()), ("units", &units)])];
+            for backorder in &backorders {
+                output.push(trf(Msg::BackorderLine, &[
+                    ("id", &backorder.short_id()),
+                    ("date", &backorder.created_at.format("%Y-%m-%d")),
+                    ("sku", &backorder.sku),
+                    ("quantity", &backorder.quantity),
+                    ("notes", &backorder.notes.as_deref().map(|notes| format!(" - {}", notes)).unwrap_or_default()),
+                ]));
+            }
+            Ok(output.join("\n"))
         }
-    }
 
-    Ok(Command::RemoveStock { sku, quantity, notes })
-}
+        Command::FillBackorders { sku } => {
+            let filled = service.fill_backorders(&sku).map_err(format_error)?;
+            let owed: u32 = service.backorders()
+                .map_err(format_error)?
+                .iter()
+                .filter(|backorder| backorder.sku == sku)
+                .map(|backorder| backorder.quantity)
+                .sum();
+            if quiet {
+                return Ok(format!("{}\t{}", filled, owed));
+            }
+            Ok(trf(Msg::BackordersFilled, &[("filled", &filled), ("sku", &sku), ("owed", &owed)]))
+        }
+        
+        Command::StockFromStdin { transaction_type, confirm_large, reason } => {
+            let input = bulk::read_stock_entries(io::stdin().lock())?;
+            apply_bulk_stock(service, input, transaction_type, RemovalOptions { confirm_large, reason, ..Default::default() }, quiet)
+        }
 
-/// Parse view-product command arguments
-fn parse_view_product(args: &[String]) -> Result<Command, String> {
-    if args.is_empty() {
-        return Err(
-            "Usage: view-product <sku>\n\
-             Example: view-product SKU001".to_string()
-        );
-    }
+        Command::ImportTransactions { file, profile: None } => {
+            let reader = std::fs::File::open(&file)
+                .map(io::BufReader::new)
+                .map_err(|e| format!("Error: Failed to read {}: {}", file, e))?;
+            let input = bulk::read_historical_entries(reader)?;
+            import_transactions(service, input, None, quiet)
+        }
 
-    Ok(Command::ViewProduct { sku: args[0].clone() })
-}
+        // Import profiles live in config.json and jobs in the data directory,
+        // so `run_with_args` looks them up
+        Command::ImportTransactions { profile: Some(_), .. } | Command::ResumeImport { .. } => {
+            Err(tr(Msg::ErrImportNested).to_string())
+        }
 
-/// Parse history command arguments
-fn parse_history(args: &[String]) -> Result<Command, String> {
-    if args.is_empty() {
-        return Err(
-            "Usage: history <sku> [--start <datetime>] [--end <datetime>]\n\
-             Example: history SKU001 --start 2025-01-01T00:00:00 --end 2025-12-31T23:59:59".to_string()
-        );
-    }
+        Command::ImportJob { mut job, data_dir } => {
+            let contents = std::fs::read(&job.file)
+                .map_err(|e| format!("Error: Failed to read {}: {}", job.file.display(), e))?;
+            job.check_file(&contents)?;
+            let input = match &job.profile {
+                Some(profile) => {
+                    let json = job.file.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+                    bulk::read_mapped_history(contents.as_slice(), profile, json)?
+                }
+                None => bulk::read_historical_entries(contents.as_slice())?,
+            };
+            import_transactions(service, input, Some((&mut job, data_dir.as_path())), quiet)
+        }
 
-    let sku = args[0].clone();
-    let mut start = None;
-    let mut end = None;
+        Command::ImportPrices { supplier, file } => {
+            let reader = std::fs::File::open(&file)
+                .map(io::BufReader::new)
+                .map_err(|e| format!("Error: Failed to read {}: {}", file, e))?;
+            let (entries, lines, mut report) = bulk::read_price_entries(reader)?.into_parts();
+            report.extend(service.validate_price_entries(&entries).renumber(|row| lines[row - 1]));
+            if !report.is_valid() {
+                return Err(format_error(ServiceError::ValidationFailed { report }));
+            }
+            let replaced = service.import_prices(&supplier, &entries).map_err(format_error)?;
+            if quiet {
+                return Ok(format!("{}\t{}", entries.len(), replaced));
+            }
+            Ok(trf(Msg::PricesImported, &[("count", &entries.len()), ("supplier", &supplier), ("replaced", &replaced)]))
+        }
 
-    let mut i = 1;
-    while i < args.len() {
-        match args[i].as_str() {
-            "--start" => {
-                if i + 1 >= args.len() {
-                    return Err("--start requires a datetime value (e.g., 2025-01-01T00:00:00)".to_string());
+        Command::InventoryAdvice { format, sender, receiver, control, output } => {
+            let now = Utc::now();
+            let mut products = service.list_products();
+            products.sort_by(|a, b| a.sku.cmp(&b.sku));
+            let envelope = match (format, sender, receiver) {
+                (FeedFormat::X12, Some(sender), Some(receiver)) => {
+                    Some(edi::Envelope { sender, receiver, control: control.unwrap_or_else(|| edi::Envelope::control_for(now)) })
                 }
-                start = Some(parse_datetime(&args[i + 1])?);
-                i += 2;
+                (FeedFormat::X12, _, _) => return Err("Error: An X12 feed needs --sender and --receiver IDs".to_string()),
+                _ => None,
+            };
+            let feed = edi::render(format, &products, envelope.as_ref(), now).map_err(|e| format!("Error: {}", e))?;
+            let Some(path) = output else {
+                return Ok(feed.trim_end().to_string());
+            };
+            std::fs::write(&path, feed).map_err(|e| format!("Error: Failed to write {}: {}", path, e))?;
+            if quiet {
+                return Ok(path);
+            }
+            Ok(trf(Msg::InventoryAdviceWritten, &[("count", &products.len()), ("path", &path)]))
+        }
+
+        Command::Reconcile { file, key, qty_column, apply, recount, confirm, sheet } => {
+            let sheet = sheet.map(|sheet| sheet.to_string());
+            let only = sheet.as_deref().map(|sheet| (stocktake::SHEET_COLUMN, sheet));
+            let quantities = read_counted_quantities(service, &file, &key, &qty_column, only)?;
+            let mut discrepancies = service.compare_external(&quantities);
+            if sheet.is_some() {
+                discrepancies.retain(|discrepancy| discrepancy.theirs.is_some());
+            }
+            let recounted: BTreeMap<String, u32> = match &recount {
+                Some(recount) => read_counted_quantities(service, recount, &key, &qty_column, only)?
+                    .into_iter()
+                    .map(|quantity| (quantity.sku, quantity.quantity))
+                    .collect(),
+                None => BTreeMap::new(),
+            };
+            // Variances beyond the tolerance wait for a recount that agrees, or a confirmation
+            let (held, accepted): (Vec<Discrepancy>, Vec<Discrepancy>) = discrepancies.iter().cloned().partition(|discrepancy| {
+                service.needs_confirmation(discrepancy)
+                    && !confirm.contains(&discrepancy.sku)
+                    && recounted.get(&discrepancy.sku).copied() != discrepancy.theirs
+            });
+            let adjusted = if apply {
+                let notes = trf(Msg::ReconcileNote, &[("file", &file)]);
+                service.align_to_external(&accepted, &notes).map_err(format_error)?
+            } else {
+                0
+            };
+            if quiet {
+                let side = |quantity: Option<u32>| quantity.map_or("-".to_string(), |quantity| quantity.to_string());
+                let records: Vec<String> = discrepancies
+                    .iter()
+                    .map(|discrepancy| {
+                        let record = format!("{}\t{}\t{}", discrepancy.sku, side(discrepancy.ours), side(discrepancy.theirs));
+                        if apply && held.contains(discrepancy) { format!("{}\theld", record) } else { record }
+                    })
+                    .collect();
+                return Ok(records.join("\n"));
+            }
+
+            let mut output = vec![trf(Msg::ReconcileHeader, &[
+                ("count", &quantities.len()),
+                ("file", &file),
+                ("discrepancies", &discrepancies.len()),
+            ])];
+            for discrepancy in &discrepancies {
+                output.push(match (discrepancy.ours, discrepancy.theirs) {
+                    (Some(ours), Some(theirs)) => trf(Msg::ReconcileLine, &[
+                        ("sku", &discrepancy.sku),
+                        ("ours", &ours),
+                        ("theirs", &theirs),
+                        ("difference", &format!("{:+}", i64::from(theirs) - i64::from(ours))),
+                    ]),
+                    (Some(ours), None) => trf(Msg::ReconcileNotInExport, &[("sku", &discrepancy.sku), ("ours", &ours)]),
+                    (None, theirs) => trf(Msg::ReconcileNotInInventory, &[
+                        ("sku", &discrepancy.sku),
+                        ("theirs", &theirs.unwrap_or(0)),
+                    ]),
+                });
             }
-            "--end" => {
-                if i + 1 >= args.len() {
-                    return Err("--end requires a datetime value (e.g., 2025-12-31T23:59:59)".to_string());
+            if apply {
+                output.push(trf(Msg::ReconcileApplied, &[("count", &adjusted)]));
+                if !held.is_empty() {
+                    let skus: Vec<&str> = held.iter().map(|discrepancy| discrepancy.sku.as_str()).collect();
+                    output.push(trf(Msg::ReconcileHeld, &[("count", &held.len()), ("skus", &skus.join(", "))]));
                 }
-                end = Some(parse_datetime(&args[i + 1])?);
-                i += 2;
             }
-            _ => {
-                return Err(format!("Unknown option: '{}'. Valid options: --start, --end", args[i]));
+            Ok(output.join("\n"))
+        }
+
+        Command::ComparePrices { sku, on } => {
+            let on = on.unwrap_or_else(|| Utc::now().date_naive());
+            let prices = service.compare_prices(&sku, on).map_err(format_error)?;
+            if quiet {
+                let records: Vec<String> = prices
+                    .iter()
+                    .map(|price| format!("{}\t{}\t{}", price.supplier, price.unit_cost, price.effective_from))
+                    .collect();
+                return Ok(records.join("\n"));
+            }
+            if prices.is_empty() {
+                return Ok(trf(Msg::NoPrices, &[("sku", &sku), ("date", &on)]));
+            }
+
+            let mut output = vec![trf(Msg::PricesHeader, &[("sku", &sku), ("date", &on)])];
+            for (index, price) in prices.iter().enumerate() {
+                let marker = if index == 0 { format!(" {}", tr(Msg::CheapestMarker)) } else { String::new() };
+                output.push(trf(Msg::PriceLine, &[
+                    ("supplier", &price.supplier),
+                    ("cost", &price.unit_cost),
+                    ("date", &price.effective_from),
+                    ("marker", &marker),
+                ]));
             }
+            Ok(output.join("\n"))
         }
-    }
 
-    Ok(Command::History { sku, start, end })
-}
+        Command::MigrateOpeningBalances { file, cutover, merge } => {
+            let reader = std::fs::File::open(&file)
+                .map(io::BufReader::new)
+                .map_err(|e| format!("Error: Failed to read {}: {}", file, e))?;
+            let input = bulk::read_opening_balances(reader)?;
+            migrate_opening_balances(service, input, cutover, merge.as_ref(), quiet)
+        }
 
-/// Parse a datetime string into DateTime<Utc>
-fn parse_datetime(s: &str) -> Result<DateTime<Utc>, String> {
-    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S")
-        .map(|dt| dt.and_utc())
-        .map_err(|_| format!("Invalid datetime '{}': expected format YYYY-MM-DDTHH:MM:SS", s))
-}
+        Command::ViewProduct { sku, template: Some(template) } => {
+            let product = service.get_product(&sku).map_err(format_error)?;
+            render_template(&template, json!({
+                "product": product_context(product),
+                "generated_at": Utc::now().to_rfc3339(),
+                "api_version": api::API_VERSION,
+            }))
+        }
 
-/// Parse delete-product command arguments
-fn parse_delete_product(args: &[String]) -> Result<Command, String> {
-    if args.is_empty() {
-        return Err(
-            "Usage: delete-product <sku>\n\
-             Example: delete-product SKU001".to_string()
-        );
-    }
+        Command::ListProducts { filter, template: Some(template), group_by: Some(group_by), .. } => {
+            let groups = service.aggregate_products(&filter, group_by).map_err(format_error)?;
+            render_template(&template, json!({
+                "group_by": group_by.to_string(),
+                "groups": groups.iter().map(api::v1::GroupTotals::from).collect::<Vec<_>>(),
+                "count": groups.iter().map(|group| group.products).sum::<usize>(),
+                "generated_at": Utc::now().to_rfc3339(),
+                "api_version": api::API_VERSION,
+            }))
+        }
 
-    Ok(Command::DeleteProduct { sku: args[0].clone() })
-}
+        Command::ListProducts { filter, template: Some(template), .. } => {
+            let products = service.query_products(&filter);
+            render_template(&template, json!({
+                "products": products.iter().map(|p| product_context(p)).collect::<Vec<_>>(),
+                "count": products.len(),
+                "generated_at": Utc::now().to_rfc3339(),
+                "api_version": api::API_VERSION,
+            }))
+        }
 
+        Command::LowStock { group_by: None, template: Some(template) } => {
+            let products = sorted_by_sku(service.list_low_stock());
+            render_template(&template, json!({
+                "products": products.iter().map(|p| product_context(p)).collect::<Vec<_>>(),
+                "count": products.len(),
+                "generated_at": Utc::now().to_rfc3339(),
+                "api_version": api::API_VERSION,
+            }))
+        }
 
-/// Execute a command against the inventory service
-/// 
-/// # Arguments
-/// * `command` - The command to execute
-/// * `service` - The inventory service instance
-/// 
-/// # Returns
-/// * `Ok(String)` - Success message to display
-/// * `Err(String)` - Error message to display
-pub fn execute_command(command: Command, service: &mut InventoryService) -> Result<String, String> {
-    match command {
-        Command::AddProduct { sku, name, description, quantity, reorder_point } => {
-            let product = service.add_product(sku, name, description, quantity, reorder_point)
-                .map_err(format_error)?;
-            Ok(format!(
-                "Product added successfully:\n  ID: {}\n  SKU: {}\n  Name: {}\n  Description: {}\n  Quantity: {}\n  Reorder Point: {}",
-                product.id, product.sku, product.name, product.description, product.quantity, product.reorder_point
-            ))
-        }
-        
-        Command::UpdateProduct { sku, name, description, reorder_point } => {
-            let product = service.update_product(&sku, name, description, reorder_point)
-                .map_err(format_error)?;
-            Ok(format!(
-                "Product updated successfully:\n  SKU: {}\n  Name: {}\n  Description: {}\n  Quantity: {}\n  Reorder Point: {}",
-                product.sku, product.name, product.description, product.quantity, product.reorder_point
-            ))
+        Command::LowStock { group_by: Some(group_by), template: Some(template) } => {
+            let groups = service.list_low_stock_grouped(group_by);
+            let count: usize = groups.iter().map(|g| g.products.len()).sum();
+            let groups: Vec<Value> = groups
+                .iter()
+                .map(|group| json!({
+                    "key": group.key,
+                    "products": group.products.iter().map(|p| product_context(p)).collect::<Vec<_>>(),
+                    "count": group.products.len(),
+                    "shortfall": group.products.iter().map(|p| u64::from(p.shortfall())).sum::<u64>(),
+                }))
+                .collect();
+            render_template(&template, json!({
+                "group_by": group_by.to_string(),
+                "groups": groups,
+                "count": count,
+                "generated_at": Utc::now().to_rfc3339(),
+                "api_version": api::API_VERSION,
+            }))
         }
-        
-        Command::AddStock { sku, quantity, notes } => {
-            service.add_stock(&sku, quantity, notes)
-                .map_err(format_error)?;
-            let product = service.get_product(&sku).map_err(format_error)?;
-            Ok(format!(
-                "Stock added successfully:\n  SKU: {}\n  Added: {}\n  New Quantity: {}",
-                sku, quantity, product.quantity
-            ))
-        }
-        
-        Command::RemoveStock { sku, quantity, notes } => {
-            service.remove_stock(&sku, quantity, notes)
-                .map_err(format_error)?;
+
+        Command::History { sku, start, end, include_archived, customer, template: Some(template) } => {
             let product = service.get_product(&sku).map_err(format_error)?;
-            Ok(format!(
-                "Stock removed successfully:\n  SKU: {}\n  Removed: {}\n  New Quantity: {}",
-                sku, quantity, product.quantity
-            ))
+            let transactions = history_transactions(service, &sku, start, end, include_archived, customer.as_deref())?;
+            render_template(&template, json!({
+                "product": product_context(product),
+                "transactions": transactions.iter().map(api::v1::Transaction::from).collect::<Vec<_>>(),
+                "count": transactions.len(),
+                "start": start.map(|s| s.to_rfc3339()),
+                "end": end.map(|e| e.to_rfc3339()),
+                "generated_at": Utc::now().to_rfc3339(),
+                "api_version": api::API_VERSION,
+            }))
         }
-        
-        Command::ViewProduct { sku } => {
+
+        Command::ViewProduct { sku, .. } => {
             let product = service.get_product(&sku).map_err(format_error)?;
+            if quiet {
+                return Ok(format!(
+                    "{}\t{}\t{}\t{}",
+                    product.sku, product.name, product.quantity, product.reorder_point
+                ));
+            }
             let low_stock_warning = if product.quantity <= product.reorder_point {
-                " [LOW STOCK]"
+                format!(" {}", tr(Msg::LowStockMarker))
             } else {
-                ""
+                String::new()
             };
-            Ok(format!(
-                "Product Details:\n  ID: {}\n  SKU: {}\n  Name: {}\n  Description: {}\n  Quantity: {}{}\n  Reorder Point: {}",
-                product.id, product.sku, product.name, product.description, product.quantity, low_stock_warning, product.reorder_point
-            ))
+            let mut output = format!("{}\n{}", tr(Msg::ProductDetails), format_product_fields(product, true, &low_stock_warning));
+            if let Some(substitutes) = substitute_list(service, product) {
+                output.push_str(&format!("\n  {}: {}", tr(Msg::LabelSubstitutes), substitutes));
+            }
+            let committed = service.committed_quantity(&sku).map_err(format_error)?;
+            if committed > 0 {
+                output.push_str(&format!("\n  {}: {}\n", tr(Msg::LabelCommitted), committed));
+                output.push_str(&trf(Msg::AvailableToPromise, &[
+                    ("available", &product.quantity.saturating_sub(committed)),
+                    ("quantity", &product.quantity),
+                    ("committed", &committed),
+                ]));
+            }
+            let layers = service.current_cost_layers(&sku).map_err(format_error)?;
+            if layers.layers().any(|layer| layer.unit_cost.is_some()) {
+                output.push('\n');
+                output.push_str(&format_cost_layers(&layers));
+            }
+            Ok(output)
         }
         
-        Command::ListProducts => {
-            let products = service.list_products();
+        Command::ListProducts { filter, group_by: Some(group_by), totals, .. } => {
+            let groups = service.aggregate_products(&filter, group_by).map_err(format_error)?;
+            let count: usize = groups.iter().map(|group| group.products).sum();
+            let mut table: Vec<Vec<String>> = groups
+                .iter()
+                .map(|group| {
+                    let key = group.key.as_deref().unwrap_or(if quiet { "-" } else { tr(Msg::GroupNone) });
+                    vec![key.to_string(), group.products.to_string(), group.quantity.to_string(), group.value.value.to_string()]
+                })
+                .collect();
+            if totals {
+                let mut value = Valuation::default();
+                groups.iter().for_each(|group| value.merge(group.value));
+                table.push(vec![
+                    if quiet { "total" } else { tr(Msg::ListTotal) }.to_string(),
+                    count.to_string(),
+                    groups.iter().map(|group| group.quantity).sum::<u64>().to_string(),
+                    value.value.to_string(),
+                ]);
+            }
+            if quiet {
+                return Ok(table.iter().map(|row| row.join("\t")).collect::<Vec<_>>().join("\n"));
+            }
+            if groups.is_empty() {
+                return Ok(tr(Msg::NoProducts).to_string());
+            }
+            let key_label = match group_by {
+                GroupBy::Category => Msg::LabelCategory,
+                GroupBy::Supplier => Msg::LabelSupplier,
+                GroupBy::Location => Msg::LabelLocation,
+            };
+            let header = [key_label, Msg::LabelProducts, Msg::LabelQuantity, Msg::LabelValue].map(|label| tr(label).to_string());
+            let mut output = vec![trf(Msg::ProductsGroupedHeader, &[("group", &group_by), ("count", &count)])];
+            output.extend(format_table(&header, &table, &[false, true, true, true]));
+            Ok(output.join("\n"))
+        }
+
+        Command::ListProducts { filter, columns, totals, .. } if totals || !columns.is_empty() => {
+            let condition = filter.condition;
+            let products = service.query_products(&filter);
+            let columns = if columns.is_empty() { vec![ListColumn::Sku, ListColumn::Name, ListColumn::Quantity] } else { columns };
+            let total_label = if quiet { "total" } else { tr(Msg::ListTotal) };
+            let table = product_table(service, &products, condition, &columns, totals.then_some(total_label))?;
+            if quiet {
+                return Ok(table.iter().map(|row| row.join("\t")).collect::<Vec<_>>().join("\n"));
+            }
+            if products.is_empty() {
+                return Ok(tr(Msg::NoProducts).to_string());
+            }
+            let header: Vec<String> = columns.iter().map(|column| list_column_label(*column).to_string()).collect();
+            let numeric: Vec<bool> = columns.iter().map(|column| column.is_numeric()).collect();
+            let mut output = vec![trf(Msg::ProductsHeader, &[("count", &products.len())])];
+            output.extend(format_table(&header, &table, &numeric));
+            Ok(output.join("\n"))
+        }
+
+        Command::ListProducts { filter, .. } => {
+            let condition = filter.condition;
+            let products = service.query_products(&filter);
+            if quiet {
+                return Ok(sku_lines(products));
+            }
             if products.is_empty() {
-                return Ok("No products in inventory.".to_string());
+                return Ok(tr(Msg::NoProducts).to_string());
             }
             
-            let mut output = format!("Products ({} total):\n", products.len());
+            let mut output = format!("{}\n", trf(Msg::ProductsHeader, &[("count", &products.len())]));
             for product in products {
-                let low_stock = if product.quantity <= product.reorder_point { " [LOW]" } else { "" };
-                output.push_str(&format!(
-                    "  {} - {} (Qty: {}{})\n",
-                    product.sku, product.name, product.quantity, low_stock
-                ));
+                let low_stock = if product.quantity <= product.reorder_point {
+                    format!(" {}", tr(Msg::LowMarker))
+                } else {
+                    String::new()
+                };
+                let quantity = condition.map_or(product.quantity, |c| product.condition_quantity(c));
+                output.push_str(&trf(Msg::ProductLine, &[
+                    ("sku", &product.sku),
+                    ("name", &product.name),
+                    ("quantity", &quantity),
+                    ("marker", &low_stock),
+                ]));
+                output.push('\n');
             }
             Ok(output.trim_end().to_string())
         }
         
-        Command::LowStock => {
+        Command::CheckRules => {
+            let rules = service.alert_rules();
+            if quiet {
+                let lines: Vec<String> = service.rule_matches().into_iter().map(|(rule, sku)| format!("{}\t{}", rule, sku)).collect();
+                return Ok(lines.join("\n"));
+            }
+            if rules.is_empty() {
+                return Ok(tr(Msg::NoAlertRules).to_string());
+            }
+            let mut output = vec![trf(Msg::RulesHeader, &[("count", &rules.len())])];
+            for rule in rules {
+                let products = service.products_matching(rule);
+                output.push(trf(Msg::RuleLine, &[("rule", &rule.name), ("when", &rule.when), ("count", &products.len())]));
+                for product in products {
+                    output.push(trf(Msg::RuleProductLine, &[
+                        ("sku", &product.sku),
+                        ("name", &product.name),
+                        ("quantity", &product.quantity),
+                    ]));
+                }
+            }
+            Ok(output.join("\n"))
+        }
+
+        Command::RunScript { path, args } => {
+            let source = std::fs::read_to_string(&path)
+                .map_err(|e| format!("Error: Failed to read script {}: {}", path, e))?;
+            script::run(&source, service, &args)
+        }
+
+        Command::LowStock { group_by: Some(group_by), .. } => {
+            let groups = service.list_low_stock_grouped(group_by);
+            if quiet {
+                return Ok(sku_lines(groups.into_iter().flat_map(|group| group.products)));
+            }
+            if groups.is_empty() {
+                return Ok(with_snoozed_note(service, tr(Msg::NoLowStock).to_string()));
+            }
+
+            let total: usize = groups.iter().map(|g| g.products.len()).sum();
+            let mut output = format!(
+                "{}\n",
+                trf(Msg::LowStockGroupedHeader, &[("group", &group_by), ("count", &total)])
+            );
+            for group in groups {
+                let key = group.key.as_deref().unwrap_or(tr(Msg::GroupNone));
+                output.push_str(&format!("\n{}:\n", key));
+                let mut group_shortfall = 0u64;
+                for product in &group.products {
+                    let short = product.shortfall();
+                    group_shortfall += u64::from(short);
+                    output.push_str(&trf(Msg::LowStockGroupedLine, &[
+                        ("sku", &product.sku),
+                        ("name", &product.name),
+                        ("quantity", &product.quantity),
+                        ("reorder_point", &product.reorder_point),
+                        ("short", &short),
+                    ]));
+                    output.push('\n');
+                    push_on_order(&mut output, product);
+                    push_substitutes(&mut output, service, product);
+                }
+                output.push_str(&trf(Msg::GroupSubtotal, &[
+                    ("count", &group.products.len()),
+                    ("short", &group_shortfall),
+                ]));
+                output.push('\n');
+            }
+            Ok(with_snoozed_note(service, output.trim_end().to_string()))
+        }
+
+        Command::LowStock { group_by: None, .. } => {
             let products = service.list_low_stock();
+            if quiet {
+                return Ok(sku_lines(products));
+            }
             if products.is_empty() {
-                return Ok("No products with low stock.".to_string());
+                return Ok(with_snoozed_note(service, tr(Msg::NoLowStock).to_string()));
             }
             
-            let mut output = format!("Low Stock Products ({} total):\n", products.len());
+            let cheapest = service.cheapest_prices(Utc::now().date_naive()).map_err(format_error)?;
+            let mut output = format!("{}\n", trf(Msg::LowStockHeader, &[("count", &products.len())]));
             for product in products {
-                output.push_str(&format!(
-                    "  {} - {} (Qty: {}, Reorder at: {})\n",
-                    product.sku, product.name, product.quantity, product.reorder_point
-                ));
+                output.push_str(&trf(Msg::LowStockLine, &[
+                    ("sku", &product.sku),
+                    ("name", &product.name),
+                    ("quantity", &product.quantity),
+                    ("reorder_point", &product.reorder_point),
+                ]));
+                output.push('\n');
+                push_on_order(&mut output, product);
+                push_substitutes(&mut output, service, product);
+                if let Some(price) = cheapest.get(&product.sku) {
+                    output.push_str(&trf(Msg::LowStockCheapest, &[("supplier", &price.supplier), ("cost", &price.unit_cost)]));
+                    output.push('\n');
+                }
             }
-            Ok(output.trim_end().to_string())
+            Ok(with_snoozed_note(service, output.trim_end().to_string()))
+        }
+
+        Command::Ack { sku, until, clear } => {
+            if clear {
+                let cleared = service.clear_alert_snooze(&sku).map_err(format_error)?;
+                let msg = if cleared { Msg::AlertSnoozeCleared } else { Msg::AlertNotSnoozed };
+                return Ok(trf(msg, &[("sku", &sku)]));
+            }
+            let snooze = service.acknowledge_alert(&sku, until).map_err(format_error)?;
+            Ok(match snooze.until {
+                Some(until) => trf(Msg::AlertAcknowledgedUntil, &[
+                    ("sku", &sku),
+                    ("quantity", &snooze.quantity),
+                    ("until", &until),
+                ]),
+                None => trf(Msg::AlertAcknowledged, &[("sku", &sku), ("quantity", &snooze.quantity)]),
+            })
         }
         
-        Command::History { sku, start, end } => {
+        Command::History { sku, start, end, include_archived, customer, .. } => {
             // Verify product exists first
             service.get_product(&sku).map_err(format_error)?;
             
-            let transactions = match (start, end) {
-                (Some(s), Some(e)) => service.get_transactions_in_range(&sku, s, e),
-                _ => service.get_transactions(&sku),
-            };
+            let transactions = history_transactions(service, &sku, start, end, include_archived, customer.as_deref())?;
+
+            if quiet {
+                let lines: Vec<String> = transactions
+                    .iter()
+                    .map(|txn| format!(
+                        "{}\t{}\t{}\t{}",
+                        txn.id,
+                        txn.timestamp.to_rfc3339(),
+                        txn.transaction_type.name(),
+                        txn.quantity
+                    ))
+                    .collect();
+                return Ok(lines.join("\n"));
+            }
             
             if transactions.is_empty() {
-                return Ok(format!("No transactions found for product '{}'.", sku));
+                return Ok(trf(Msg::NoTransactions, &[("sku", &sku)]));
             }
             
-            let mut output = format!("Transaction History for '{}' ({} transactions):\n", sku, transactions.len());
-            for txn in transactions {
-                let type_str = match txn.transaction_type {
-                    crate::models::TransactionType::Addition => "+",
-                    crate::models::TransactionType::Removal => "-",
+            let mut output = format!(
+                "{}\n",
+                trf(Msg::HistoryHeader, &[("sku", &sku), ("count", &transactions.len())])
+            );
+            for txn in &transactions {
+                output.push_str(&format!("  {}\n", history_line(txn)));
+            }
+            Ok(output.trim_end().to_string())
+        }
+        
+        Command::HistoryCompare { skus, start, end, merge } => {
+            let summaries = service.summarize_movements(&skus, start, end).map_err(format_error)?;
+            let sku_list = skus.join(", ");
+            if merge {
+                let mut transactions = Vec::new();
+                for sku in &skus {
+                    transactions.extend(service.transactions_for(sku).map_err(format_error)?);
+                }
+                transactions.retain(|t| {
+                    start.is_none_or(|start| t.timestamp >= start) && end.is_none_or(|end| t.timestamp <= end)
+                });
+                transactions.sort_by_key(|t| t.order_key());
+                if quiet {
+                    let lines: Vec<String> = transactions
+                        .iter()
+                        .map(|txn| format!(
+                            "{}\t{}\t{}\t{}\t{}",
+                            txn.product_sku,
+                            txn.id,
+                            txn.timestamp.to_rfc3339(),
+                            txn.transaction_type.name(),
+                            txn.quantity
+                        ))
+                        .collect();
+                    return Ok(lines.join("\n"));
+                }
+                if transactions.is_empty() {
+                    return Ok(trf(Msg::NoTransactionsCompared, &[("skus", &sku_list)]));
+                }
+                let width = skus.iter().map(|sku| sku.chars().count()).max().unwrap_or(0);
+                let mut output = vec![trf(Msg::MergedHistoryHeader, &[("skus", &sku_list), ("count", &transactions.len())])];
+                for txn in &transactions {
+                    output.push(format!("  {:<width$} {}", txn.product_sku, history_line(txn)));
+                }
+                return Ok(output.join("\n"));
+            }
+
+            if quiet {
+                let lines: Vec<String> = summaries
+                    .iter()
+                    .map(|s| format!(
+                        "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                        s.sku, s.received, s.removed, s.shrinkage, s.net_change(), s.movements, s.on_hand
+                    ))
+                    .collect();
+                return Ok(lines.join("\n"));
+            }
+            let rows: [(Msg, Vec<String>); 6] = [
+                (Msg::CompareReceived, summaries.iter().map(|s| s.received.to_string()).collect()),
+                (Msg::CompareRemoved, summaries.iter().map(|s| s.removed.to_string()).collect()),
+                (Msg::CompareShrinkage, summaries.iter().map(|s| s.shrinkage.to_string()).collect()),
+                (Msg::CompareNetChange, summaries.iter().map(|s| format!("{:+}", s.net_change())).collect()),
+                (Msg::CompareMovements, summaries.iter().map(|s| s.movements.to_string()).collect()),
+                (Msg::CompareOnHand, summaries.iter().map(|s| s.on_hand.to_string()).collect()),
+            ];
+            let label_width = rows.iter().map(|(label, _)| tr(*label).chars().count()).max().unwrap_or(0);
+            let widths: Vec<usize> = skus
+                .iter()
+                .enumerate()
+                .map(|(column, sku)| {
+                    rows.iter().map(|(_, values)| values[column].len()).chain([sku.chars().count()]).max().unwrap_or(0)
+                })
+                .collect();
+            let line = |label: &str, cells: Vec<&str>| {
+                let cells: Vec<String> =
+                    cells.iter().zip(&widths).map(|(cell, width)| format!("{:>width$}", cell)).collect();
+                format!("  {:<label_width$}  {}", label, cells.join("  ")).trim_end().to_string()
+            };
+            let mut output = vec![tr(Msg::CompareHeader).to_string()];
+            output.push(line("", skus.iter().map(String::as_str).collect()));
+            for (label, values) in &rows {
+                output.push(line(tr(*label), values.iter().map(String::as_str).collect()));
+            }
+            Ok(output.join("\n"))
+        }
+
+        Command::Diff { snapshots, since, verify } => {
+            let mut verified = Vec::new();
+            if let Some(public_key) = &verify {
+                for snapshot in &snapshots {
+                    let path = std::path::Path::new(snapshot);
+                    let dir = if path.is_file() { path.parent().unwrap_or(std::path::Path::new(".")) } else { path };
+                    export::verify(dir, std::path::Path::new(public_key))
+                        .map_err(|e| trf(Msg::ErrSignature, &[("snapshot", snapshot), ("error", &e)]))?;
+                    verified.push(trf(Msg::SignatureVerified, &[("snapshot", snapshot), ("key", public_key)]));
+                }
+            }
+            let (diff, from, to) = match (since, snapshots.as_slice()) {
+                (Some(since), _) => {
+                    let diff = service.diff_since(since).map_err(format_error)?;
+                    (diff, since.format("%Y-%m-%d").to_string(), tr(Msg::DiffCurrent).to_string())
+                }
+                (None, [snapshot]) => {
+                    let before = load_snapshot(snapshot)?;
+                    let current: Vec<Product> = service.list_products().into_iter().cloned().collect();
+                    (InventoryDiff::between(&before, &current), snapshot.clone(), tr(Msg::DiffCurrent).to_string())
+                }
+                (None, [a, b]) => {
+                    let diff = InventoryDiff::between(&load_snapshot(a)?, &load_snapshot(b)?);
+                    (diff, a.clone(), b.clone())
+                }
+                (None, _) => return Err(help::usage_error("diff")),
+            };
+            if quiet {
+                return Ok(format_diff(&diff, &from, &to, quiet));
+            }
+            verified.push(format_diff(&diff, &from, &to, quiet));
+            Ok(verified.join("\n"))
+        }
+
+        Command::Stats => {
+            let stats = service.stats();
+            let totals = stats.totals();
+            let low_stock = stats.low_stock().count();
+            if quiet {
+                let mut records = vec![format!(
+                    "total\t{}\t{}\t{}\t{}", totals.products, totals.units, low_stock, totals.value.value
+                )];
+                records.extend(stats.categories().iter().map(|(category, totals)| {
+                    format!("{}\t{}\t{}\t{}", category.as_deref().unwrap_or("-"), totals.products, totals.units, totals.value.value)
+                }));
+                return Ok(records.join("\n"));
+            }
+
+            let mut output = vec![
+                tr(Msg::StatsHeader).to_string(),
+                format!("  {}: {}", tr(Msg::LabelProducts), totals.products),
+                format!("  {}: {}", tr(Msg::LabelUnits), totals.units),
+                format!("  {}: {}", tr(Msg::LabelValue), totals.value.value),
+                format!("  {}: {}", tr(Msg::LabelLowStock), low_stock),
+            ];
+            if !stats.categories().is_empty() {
+                output.push(tr(Msg::StatsByCategory).to_string());
+            }
+            for (category, totals) in stats.categories() {
+                output.push(trf(Msg::StatsCategoryLine, &[
+                    ("category", &category.as_deref().unwrap_or(tr(Msg::GroupNone))),
+                    ("products", &totals.products),
+                    ("units", &totals.units),
+                    ("value", &totals.value.value),
+                ]));
+            }
+            if totals.value.uncosted_units > 0 {
+                output.push(trf(Msg::ValueUncostedNote, &[("units", &totals.value.uncosted_units)]));
+            }
+            let quotas = service.quota_usage().map_err(format_error)?;
+            if !quotas.is_empty() {
+                output.push(tr(Msg::StatsQuotas).to_string());
+            }
+            for usage in quotas {
+                let msg = match usage.quota {
+                    Quota::Products => Msg::StatsQuotaProducts,
+                    Quota::Transactions => Msg::StatsQuotaTransactions,
+                    Quota::DataBytes => Msg::StatsQuotaDataBytes,
                 };
-                let notes_str = txn.notes.as_ref().map(|n| format!(" - {}", n)).unwrap_or_default();
-                output.push_str(&format!(
-                    "  {} {} {} {}{}\n",
-                    txn.timestamp.format("%Y-%m-%d %H:%M:%S"),
-                    type_str,
-                    txn.quantity,
-                    txn.transaction_type.to_string().to_lowercase(),
-                    notes_str
-                ));
+                output.push(trf(msg, &[("used", &usage.used), ("limit", &usage.limit), ("percent", &usage.percent())]));
+                if usage.percent() >= u64::from(service.size_quotas().warn_percent) {
+                    output.push(tr(Msg::StatsQuotaNear).to_string());
+                }
+            }
+            Ok(output.join("\n"))
+        }
+
+        Command::ValueHistory { interval, start, end } => {
+            let end = end.unwrap_or_else(Utc::now);
+            let start = start.unwrap_or_else(|| {
+                let year_start = NaiveDate::from_ymd_opt(end.year(), 1, 1).expect("valid date");
+                year_start.and_hms_opt(0, 0, 0).expect("valid time").and_utc()
+            });
+            let points = service.value_history(interval, start, end).map_err(format_error)?;
+            let label = |at: &DateTime<Utc>| {
+                if at.time() == NaiveTime::MIN {
+                    at.format("%Y-%m-%d").to_string()
+                } else {
+                    at.format("%Y-%m-%d %H:%M").to_string()
+                }
+            };
+            if quiet {
+                let records: Vec<String> = points
+                    .iter()
+                    .map(|point| format!("{}\t{}\t{}\t{}", point.at.to_rfc3339(), point.units, point.value, point.uncosted_units))
+                    .collect();
+                return Ok(records.join("\n"));
+            }
+
+            let mut output = vec![trf(Msg::ValueHistoryHeader, &[("interval", &interval_label(interval))])];
+            for point in &points {
+                output.push(trf(Msg::ValueHistoryLine, &[
+                    ("at", &label(&point.at)),
+                    ("units", &point.units),
+                    ("value", &point.value),
+                ]));
+            }
+            let uncosted = points.last().map_or(0, |point| point.uncosted_units);
+            if uncosted > 0 {
+                output.push(trf(Msg::ValueUncostedNote, &[("units", &uncosted)]));
+            }
+            Ok(output.join("\n"))
+        }
+
+        Command::Forecast { seasonality, days, horizon } => {
+            let seasonality = seasonality.unwrap_or(service.seasonality());
+            let days = days.unwrap_or(seasonality.default_window_days());
+            let horizon = horizon.unwrap_or(forecast::DEFAULT_HORIZON_DAYS);
+            let now = Utc::now();
+            let today = now.date_naive();
+            let forecasts = service
+                .forecast_stock_outs(Some(seasonality), days, horizon, now)
+                .map_err(format_error)?;
+            if quiet {
+                let records: Vec<String> = forecasts
+                    .iter()
+                    .map(|f| format!(
+                        "{}\t{}\t{:.2}\t{}",
+                        f.sku,
+                        f.available,
+                        f.forecast.mean,
+                        f.stock_out.map(|date| date.to_string()).unwrap_or_default()
+                    ))
+                    .collect();
+                return Ok(records.join("\n"));
+            }
+            if forecasts.is_empty() {
+                return Ok(trf(Msg::NoForecast, &[("days", &days)]));
+            }
+
+            let mut output = vec![trf(Msg::ForecastHeader, &[("seasonality", &seasonality), ("days", &days)])];
+            for f in &forecasts {
+                let outlook = match f.stock_out {
+                    Some(date) if date == today => tr(Msg::ForecastOutNow).to_string(),
+                    Some(date) => trf(Msg::ForecastRunsOut, &[("date", &date), ("days", &(date - today).num_days())]),
+                    None => trf(Msg::ForecastLasts, &[("days", &horizon)]),
+                };
+                output.push(trf(Msg::ForecastLine, &[
+                    ("sku", &f.sku),
+                    ("name", &f.name),
+                    ("available", &f.available),
+                    ("mean", &format!("{:.2}", f.forecast.mean)),
+                    ("week", &format!("{:.1}", f.forecast.expected_over(today, 7))),
+                    ("outlook", &outlook),
+                ]));
+            }
+            Ok(output.join("\n"))
+        }
+
+        Command::SuggestReorderPoints { service_level, lead_time, days, apply } => {
+            let days = days.unwrap_or(reorder::DEFAULT_WINDOW_DAYS);
+            let lead_time = lead_time.unwrap_or(reorder::DEFAULT_LEAD_TIME_DAYS);
+            let suggestions = service
+                .suggest_reorder_points(service_level, lead_time, days, Utc::now())
+                .map_err(format_error)?;
+            if quiet && !apply {
+                let records: Vec<String> = suggestions
+                    .iter()
+                    .map(|s| format!(
+                        "{}\t{}\t{}\t{:.2}\t{:.2}\t{}\t{}",
+                        s.sku, s.current, s.suggested, s.demand.mean, s.demand.std_dev, s.lead_time_days, s.safety_stock
+                    ))
+                    .collect();
+                return Ok(records.join("\n"));
+            }
+            if suggestions.is_empty() {
+                return Ok(trf(Msg::NoReorderSuggestions, &[("days", &days)]));
+            }
+
+            let level = format!("{}%", service_level * 100.0);
+            let mut output = vec![trf(Msg::ReorderSuggestionsHeader, &[("level", &level), ("days", &days)])];
+            for s in &suggestions {
+                output.push(trf(Msg::ReorderSuggestionLine, &[
+                    ("sku", &s.sku),
+                    ("name", &s.name),
+                    ("current", &s.current),
+                    ("suggested", &s.suggested),
+                    ("mean", &format!("{:.2}", s.demand.mean)),
+                    ("std_dev", &format!("{:.2}", s.demand.std_dev)),
+                    ("lead_time", &s.lead_time_days),
+                    ("safety", &s.safety_stock),
+                ]));
+            }
+            let changes = suggestions.iter().filter(|s| s.suggested != s.current).count();
+            if changes == 0 {
+                output.push(tr(Msg::ReorderPointsUpToDate).to_string());
+                return Ok(output.join("\n"));
+            }
+            if !apply {
+                output.push(trf(Msg::ReorderApplyHint, &[("count", &changes)]));
+                return Ok(output.join("\n"));
+            }
+
+            println!("{}", output.join("\n"));
+            let stdin = io::stdin();
+            let applied = review_reorder_suggestions(service, &suggestions, &mut stdin.lock(), &mut io::stdout())?;
+            if quiet {
+                return Ok(applied.join("\n"));
+            }
+            Ok(trf(Msg::ReorderPointsApplied, &[("count", &applied.len())]))
+        }
+
+        Command::Kpis { start, end } => {
+            let snapshots = service.kpi_snapshots(start, end).map_err(format_error)?;
+            if quiet {
+                let records: Vec<String> = snapshots.iter().map(kpi_record).collect();
+                return Ok(records.join("\n"));
+            }
+            if snapshots.is_empty() {
+                return Ok(tr(Msg::NoKpiSnapshots).to_string());
+            }
+            let max = snapshots.iter().map(|snapshot| snapshot.value.cents()).max().unwrap_or_default();
+            let mut output = vec![trf(Msg::KpisHeader, &[("count", &snapshots.len())])];
+            for snapshot in &snapshots {
+                output.push(trf(Msg::KpisLine, &[
+                    ("date", &snapshot.date),
+                    ("bar", &format!("{:<width$}", kpi_bar(snapshot.value, max), width = KPI_BAR_WIDTH)),
+                    ("value", &snapshot.value),
+                    ("units", &snapshot.units),
+                    ("low", &snapshot.low_stock),
+                    ("movements", &snapshot.movements),
+                ]));
+            }
+            Ok(output.join("\n"))
+        }
+
+        Command::Cogs { start, end } => {
+            let lines = service.cost_of_goods(start, end).map_err(format_error)?;
+            if quiet {
+                let records: Vec<String> = lines
+                    .iter()
+                    .map(|line| format!("{}\t{}\t{}\t{}", line.sku, line.cost.units, line.cost.value, line.cost.uncosted_units))
+                    .collect();
+                return Ok(records.join("\n"));
+            }
+            let (from, to) = period_labels(start, end);
+            if lines.is_empty() {
+                return Ok(trf(Msg::NoCogs, &[("from", &from), ("to", &to)]));
+            }
+
+            let mut output = vec![trf(Msg::CogsHeader, &[("from", &from), ("to", &to)])];
+            let mut total = Valuation::default();
+            for line in &lines {
+                output.push(trf(Msg::CogsLine, &[
+                    ("sku", &line.sku),
+                    ("name", &line.name),
+                    ("units", &line.cost.units),
+                    ("cost", &line.cost.value),
+                ]));
+                total.merge(line.cost);
+            }
+            output.push(trf(Msg::CogsTotal, &[("units", &total.units), ("cost", &total.value)]));
+            if total.uncosted_units > 0 {
+                output.push(trf(Msg::ValueUncostedNote, &[("units", &total.uncosted_units)]));
+            }
+            Ok(output.join("\n"))
+        }
+
+        Command::LedgerEntries { format, start, end, currency } => {
+            let entries = service.accounting_entries(start, end).map_err(format_error)?;
+            let mut output = books::render(&entries, format, &currency);
+            let uncosted: u64 = entries.iter().map(|entry| entry.cost.uncosted_units).sum();
+            if uncosted > 0 && !quiet {
+                output.push_str(&format!("\n; {}", trf(Msg::LedgerUncostedNote, &[("units", &uncosted)])));
+            }
+            Ok(output.trim().to_string())
+        }
+
+        Command::Shrinkage { start, end, condition } => {
+            let lines = service.shrinkage(start, end, condition);
+            if quiet {
+                let records: Vec<String> = lines
+                    .iter()
+                    .map(|line| format!("{}\t{}\t{}\t{}", line.sku, line.transaction_type.name(), line.transactions, line.units))
+                    .collect();
+                return Ok(records.join("\n"));
+            }
+
+            let (from, to) = period_labels(start, end);
+            if lines.is_empty() {
+                return Ok(trf(Msg::NoShrinkage, &[("from", &from), ("to", &to)]));
+            }
+
+            let units: u64 = lines.iter().map(|line| line.units).sum();
+            let mut skus: Vec<&str> = lines.iter().map(|line| line.sku.as_str()).collect();
+            skus.dedup();
+            let mut output = vec![trf(Msg::ShrinkageHeader, &[
+                ("from", &from),
+                ("to", &to),
+                ("units", &units),
+                ("count", &skus.len()),
+            ])];
+            let mut totals: Vec<(TransactionType, u64)> = Vec::new();
+            for (index, line) in lines.iter().enumerate() {
+                if index == 0 || lines[index - 1].sku != line.sku {
+                    output.push(format!("  {} - {}", line.sku, line.name));
+                }
+                output.push(trf(Msg::ShrinkageLine, &[
+                    ("type", &type_label(line.transaction_type)),
+                    ("units", &line.units),
+                    ("count", &line.transactions),
+                ]));
+                match totals.iter_mut().find(|(t, _)| *t == line.transaction_type) {
+                    Some((_, total)) => *total += line.units,
+                    None => totals.push((line.transaction_type, line.units)),
+                }
+            }
+            output.push(tr(Msg::ShrinkageByType).to_string());
+            for (transaction_type, units) in totals {
+                output.push(trf(Msg::ShrinkageTotal, &[("type", &type_label(transaction_type)), ("units", &units)]));
+            }
+            Ok(output.join("\n"))
+        }
+
+        Command::Consumption { sku, start, end, condition } => {
+            if let Some(sku) = &sku {
+                service.get_product(sku).map_err(format_error)?;
+            }
+            let totals = service.consumption_by_reason(sku.as_deref(), start, end, condition);
+            if quiet {
+                let records: Vec<String> = totals
+                    .iter()
+                    .map(|total| {
+                        let reason = total.reason.map_or("unspecified", ReasonCode::name);
+                        format!("{}\t{}\t{}", reason, total.transactions, total.units)
+                    })
+                    .collect();
+                return Ok(records.join("\n"));
+            }
+
+            let (from, to) = period_labels(start, end);
+            if totals.is_empty() {
+                return Ok(trf(Msg::NoConsumption, &[("from", &from), ("to", &to)]));
+            }
+            let units: u64 = totals.iter().map(|total| total.units).sum();
+            let mut output = vec![trf(Msg::ConsumptionHeader, &[("from", &from), ("to", &to), ("units", &units)])];
+            for total in &totals {
+                let share = format!("{:.1}", total.units as f64 * 100.0 / units as f64);
+                output.push(trf(Msg::ConsumptionLine, &[
+                    ("reason", &reason_label(total.reason)),
+                    ("units", &total.units),
+                    ("share", &share),
+                    ("count", &total.transactions),
+                ]));
+            }
+            Ok(output.join("\n"))
+        }
+
+        Command::CheckBuildable { sku } => {
+            let buildable = service.check_buildable(&sku).map_err(format_error)?;
+            if quiet {
+                return Ok(buildable.kits.to_string());
+            }
+            let limiting = buildable.limiting().map(|component| component.sku.as_str());
+            let mut output = vec![trf(Msg::BuildableHeader, &[("sku", &sku), ("kits", &buildable.kits)])];
+            for component in &buildable.components {
+                let line = trf(Msg::BuildableLine, &[
+                    ("sku", &component.sku),
+                    ("available", &component.available),
+                    ("per_kit", &component.per_kit),
+                    ("kits", &component.kits()),
+                ]);
+                if limiting == Some(component.sku.as_str()) {
+                    output.push(format!("{} {}", line, tr(Msg::LimitingMarker)));
+                } else {
+                    output.push(line);
+                }
+            }
+            Ok(output.join("\n"))
+        }
+
+        Command::ReceiveStart { reference, expected_file } => {
+            let expected = match &expected_file {
+                Some(file) => {
+                    let reader = std::fs::File::open(file)
+                        .map(io::BufReader::new)
+                        .map_err(|e| format!("Error: Failed to read {}: {}", file, e))?;
+                    let (entries, lines, mut report) = bulk::read_expected_receipt(reader)?.into_parts();
+                    let stock_report =
+                        service.validate_stock_entries(&entries, TransactionType::Addition, &RemovalOptions::default());
+                    report.extend(stock_report.renumber(|row| lines[row - 1]));
+                    if !report.is_valid() {
+                        return Err(format_error(ServiceError::ValidationFailed { report }));
+                    }
+                    entries
+                }
+                None => Vec::new(),
+            };
+            let session = service.start_receiving(reference, &expected).map_err(format_error)?;
+            if quiet {
+                return Ok(session.expected.len().to_string());
+            }
+            Ok(trf(Msg::ReceivingStarted, &[
+                ("reference", &receipt_reference(&session)),
+                ("count", &session.expected.len()),
+            ]))
+        }
+
+        Command::ReceiveAdd { sku, quantity, notes } => {
+            let review = service.stage_receipt(&sku, quantity, notes).map_err(format_error)?;
+            let received = review.lines.iter().find(|line| line.sku == sku).map_or(0, |line| line.received);
+            if quiet {
+                return Ok(format!("{}\t{}", sku, received));
+            }
+            Ok(trf(Msg::ReceivingStaged, &[("quantity", &quantity), ("sku", &sku), ("received", &received)]))
+        }
+
+        Command::ReceiveReview => {
+            let review = service.review_receiving().map_err(format_error)?;
+            Ok(format_receiving_review(&review, quiet))
+        }
+
+        Command::ReceiveCommit => {
+            let review = service.commit_receiving().map_err(format_error)?;
+            if quiet {
+                return Ok(review.received_units().to_string());
+            }
+            let products = review.lines.iter().filter(|line| line.received > 0).count();
+            let mut output = vec![trf(Msg::ReceivingCommitted, &[
+                ("reference", &receipt_reference(&review.session)),
+                ("units", &review.received_units()),
+                ("products", &products),
+            ])];
+            if !review.session.expected.is_empty() && !review.matches_expected() {
+                output.push(tr(Msg::ReceivingVariances).to_string());
+                output.extend(receiving_lines(&review, true));
+            }
+            Ok(output.join("\n"))
+        }
+
+        Command::ReceiveCancel => {
+            let session = service.cancel_receiving().map_err(format_error)?;
+            if quiet {
+                return Ok(session.received.len().to_string());
+            }
+            Ok(trf(Msg::ReceivingCancelled, &[
+                ("reference", &receipt_reference(&session)),
+                ("count", &session.received.len()),
+            ]))
+        }
+
+        Command::Export { dir, format: ExportFormat::Cdc, since, .. } => {
+            let changes = export::changes(service, since);
+            export::write_changes(std::path::Path::new(&dir), &changes)
+                .map_err(|e| trf(Msg::ErrExport, &[("error", &e)]))?;
+            let last_seq = changes.iter().map(|change| change.source.seq).max().or(since).unwrap_or(0);
+            if quiet {
+                return Ok(last_seq.to_string());
+            }
+            Ok(trf(Msg::ExportedChanges, &[
+                ("count", &changes.len()),
+                ("file", &std::path::Path::new(&dir).join(export::CHANGES_FILE).display()),
+                ("seq", &last_seq),
+            ]))
+        }
+
+        Command::Export { dir, anonymize, sign, format: ExportFormat::Dataset, .. } => {
+            let mut dataset = Dataset::from_service(service);
+            if anonymize {
+                dataset.anonymize(&Anonymizer::new());
+            }
+            dataset.write_to(std::path::Path::new(&dir))
+                .map_err(|e| trf(Msg::ErrExport, &[("error", &e)]))?;
+            if let Some(key) = &sign {
+                export::sign(std::path::Path::new(&dir), std::path::Path::new(key))
+                    .map_err(|e| trf(Msg::ErrExport, &[("error", &e)]))?;
+            }
+            if quiet {
+                return Ok(dir);
+            }
+            let msg = if anonymize { Msg::ExportedAnonymized } else { Msg::Exported };
+            let mut output = vec![trf(msg, &[
+                ("products", &dataset.products.len()),
+                ("transactions", &dataset.transactions.len()),
+                ("dir", &dir),
+            ])];
+            if sign.is_some() {
+                output.push(trf(Msg::ExportSigned, &[("manifest", &export::MANIFEST_FILE), ("signature", &export::SIGNATURE_FILE)]));
+            }
+            Ok(output.join("\n"))
+        }
+
+        Command::ArchiveTransactions { before } => {
+            let archived = service.archive_transactions(before).map_err(format_error)?;
+            if quiet {
+                return Ok(archived.to_string());
+            }
+            Ok(trf(Msg::TransactionsArchived, &[("count", &archived), ("date", &before.format("%Y-%m-%d"))]))
+        }
+
+        Command::Compact { archive_years } => {
+            let archive_before = archive_years
+                .map(|years| Utc::now() - chrono::Months::new(years.saturating_mul(12)));
+            let report = service.compact(archive_before).map_err(format_error)?;
+            if quiet {
+                return Ok(report.reclaimed().to_string());
+            }
+            let mut output = vec![
+                trf(Msg::CompactHeader, &[
+                    ("reclaimed", &report.reclaimed()),
+                    ("before", &report.bytes_before),
+                    ("after", &report.bytes_after),
+                ]),
+                trf(Msg::CompactDuplicateProducts, &[("count", &report.duplicate_products)]),
+                trf(Msg::CompactDuplicateTransactions, &[("count", &report.duplicate_transactions)]),
+                trf(Msg::CompactPurgedTrash, &[("count", &report.purged_trash)]),
+            ];
+            if archive_years.is_some() {
+                output.push(trf(Msg::CompactArchived, &[("count", &report.archived_transactions)]));
+            }
+            Ok(output.join("\n"))
+        }
+
+        Command::ApplyRetention { dry_run } => {
+            if service.retention_policy().is_empty() {
+                return Err(tr(Msg::ErrNoRetentionRules).to_string());
+            }
+            let report = service.apply_retention(dry_run).map_err(format_error)?;
+            if quiet {
+                return Ok(format!("{}\t{}\t{}", report.notes, report.customers, report.relinked));
+            }
+            let msg = if dry_run { Msg::RetentionDryRun } else { Msg::RetentionApplied };
+            let mut output = vec![trf(msg, &[("notes", &report.notes), ("customers", &report.customers)])];
+            if report.relinked > 0 {
+                let msg = if dry_run { Msg::RetentionWouldRelink } else { Msg::RetentionRelinked };
+                output.push(trf(msg, &[("count", &report.relinked)]));
+            }
+            Ok(output.join("\n"))
+        }
+
+        Command::HealIds { dry_run } => {
+            let report = service.heal_ids(dry_run).map_err(format_error)?;
+            if quiet {
+                return Ok(report.changes.len().to_string());
+            }
+            let mut output = vec![match (report.changes.len(), dry_run) {
+                (0, _) => tr(Msg::IdsUnique).to_string(),
+                (count, true) => trf(Msg::IdsWouldHeal, &[("count", &count)]),
+                (count, false) => trf(Msg::IdsHealed, &[("count", &count)]),
+            }];
+            output.extend(report.changes.iter().map(|change| {
+                format!("  {} '{}': {} -> {}", change.record, change.sku, change.old_id, change.new_id)
+            }));
+            if report.copies > 0 {
+                output.push(trf(Msg::IdsCopies, &[("count", &report.copies)]));
+            }
+            Ok(output.join("\n"))
+        }
+
+        Command::SnapshotKpis => {
+            let snapshot = service.snapshot_kpis(Utc::now()).map_err(format_error)?;
+            if quiet {
+                return Ok(kpi_record(&snapshot));
+            }
+            Ok(trf(Msg::KpiSnapshotTaken, &[
+                ("date", &snapshot.date),
+                ("units", &snapshot.units),
+                ("value", &snapshot.value),
+                ("low", &snapshot.low_stock),
+                ("movements", &snapshot.movements),
+            ]))
+        }
+
+        Command::VerifyLedger { against } => {
+            let backup = match &against {
+                Some(dir) => Some(
+                    JsonStorage::new(dir)
+                        .load_ledger_head()
+                        .map_err(|e| trf(Msg::ErrLedgerBackup, &[("dir", dir), ("error", &e)]))?
+                        .ok_or_else(|| trf(Msg::ErrLedgerNoBackupHead, &[("dir", dir)]))?,
+                ),
+                None => None,
+            };
+            let report = service.verify_ledger(backup.as_ref()).map_err(format_error)?;
+            if !report.is_intact() {
+                let mut output = vec![trf(Msg::ErrLedgerTampered, &[("count", &report.problems.len())])];
+                output.extend(report.problems.iter().map(|problem| format!("  - {}", problem)));
+                return Err(output.join("\n"));
+            }
+            let Some(head) = &report.head else {
+                return Ok(tr(Msg::LedgerNotChained).to_string());
+            };
+            if quiet {
+                return Ok(head.hash.clone());
+            }
+            let mut output = vec![trf(Msg::LedgerIntact, &[("count", &report.chained), ("head", &head.hash)])];
+            if report.unchained > 0 {
+                output.push(trf(Msg::LedgerUnchained, &[("count", &report.unchained)]));
+            }
+            if let Some(dir) = &against {
+                output.push(trf(Msg::LedgerMatchesBackup, &[("dir", dir)]));
+            }
+            Ok(output.join("\n"))
+        }
+
+        Command::CountSheets { location, category, blind, format, output } => {
+            let filter = ProductFilter { category, ..Default::default() };
+            let products: Vec<&Product> = service
+                .query_products(&filter)
+                .into_iter()
+                .filter(|product| {
+                    location.as_ref().is_none_or(|location| product.location.as_ref().is_some_and(|at| at.starts_with(location.as_str())))
+                })
+                .collect();
+            let sheets = stocktake::build(&products, blind);
+            let contents = match format {
+                SheetFormat::Csv => stocktake::to_csv(&sheets, blind).into_bytes(),
+                SheetFormat::Pdf => stocktake::to_pdf(&sheets, blind),
+            };
+            let Some(path) = output else {
+                return Ok(String::from_utf8_lossy(&contents).trim_end().to_string());
+            };
+            std::fs::write(&path, contents).map_err(|e| format!("Error: Failed to write {}: {}", path, e))?;
+            if quiet {
+                return Ok(path);
+            }
+            Ok(trf(Msg::CountSheetsWritten, &[("sheets", &sheets.len()), ("count", &products.len()), ("path", &path)]))
+        }
+
+        Command::Receipt { transaction_id, format, output, printer } => {
+            let transaction = service.find_transaction(&transaction_id).map_err(format_error)?;
+            let product = service.get_product(&transaction.product_sku).map_err(format_error)?;
+            let receipt = Receipt { transaction, product };
+
+            if let Some(printer) = printer {
+                printer
+                    .send(&receipt.render(format))
+                    .map_err(|e| format!("Error: Failed to print to {}: {}", printer, e))?;
+                if quiet {
+                    return Ok(receipt.reference());
+                }
+                let printer = printer.to_string();
+                return Ok(trf(Msg::ReceiptPrinted, &[("reference", &receipt.reference()), ("printer", &printer)]));
+            }
+            let Some(path) = output else {
+                return Ok(match format {
+                    ReceiptFormat::Zpl => receipt.to_zpl().trim_end().to_string(),
+                    _ => receipt.to_text(),
+                });
+            };
+            std::fs::write(&path, receipt.render(format))
+                .map_err(|e| format!("Error: Failed to write {}: {}", path, e))?;
+            if quiet {
+                return Ok(path);
+            }
+            Ok(trf(Msg::ReceiptWritten, &[("reference", &receipt.reference()), ("path", &path)]))
+        }
+
+        Command::DeleteProduct { sku, force } => {
+            service.delete_product_with_options(&sku, DeleteOptions { force }).map_err(format_error)?;
+            if quiet {
+                return Ok(String::new());
+            }
+            Ok(trf(Msg::ProductDeleted, &[("sku", &sku)]))
+        }
+
+        Command::LinkProduct { sku, kind, target, remove } => {
+            let changed = if remove {
+                service.unlink_products(&sku, kind, &target).map_err(format_error)?
+            } else {
+                service.link_products(&sku, kind, &target).map_err(format_error)?
+            };
+            if quiet {
+                return Ok(String::new());
+            }
+            let msg = match (remove, changed) {
+                (false, true) => Msg::ProductsLinked,
+                (false, false) => Msg::AlreadyLinked,
+                (true, true) => Msg::ProductsUnlinked,
+                (true, false) => Msg::NotLinked,
+            };
+            Ok(trf(msg, &[("sku", &sku), ("link", &link_label(&ProductLink { kind, sku: target }))]))
+        }
+
+        // Needs the configured unlock code, so `run_with_args` starts it
+        Command::Kiosk => Err(tr(Msg::ErrKioskNested).to_string()),
+
+        // Works on the data directory itself, so `run_with_args` handles it
+        Command::ConvertStorage { .. } => Err(tr(Msg::ErrConvertNested).to_string()),
+
+        // Needs the store settings, so `run_with_args` runs it
+        Command::ShopSync { .. } => Err(tr(Msg::ErrShopSyncNested).to_string()),
+
+        // Suppliers and staged notices live in the data directory, so
+        // `run_with_args` reads them
+        Command::ReceiveInbox { .. } => Err(tr(Msg::ErrInboxNested).to_string()),
+
+        // Feeds are configured in config.json, so `run_with_args` pushes them
+        Command::FeedPush { .. } | Command::FeedStatus => Err(tr(Msg::ErrFeedNested).to_string()),
+
+        // The outbox is written after each command-line command, so reading it
+        // in the middle of a script would miss the script's own events
+        Command::EventsTail { .. } => Err(tr(Msg::ErrEventsNested).to_string()),
+
+        // Saved views live in config.json, so `run_with_args` handles them
+        Command::SaveView { .. } | Command::RunView { .. } | Command::ListViews | Command::DeleteView { .. } => {
+            Err(tr(Msg::ErrViewsNested).to_string())
+        }
+
+        // Product templates live in config.json too
+        Command::AddProductFromTemplate { .. }
+        | Command::SaveProductTemplate { .. }
+        | Command::ListProductTemplates
+        | Command::DeleteProductTemplate { .. } => Err(tr(Msg::ErrTemplatesNested).to_string()),
+
+        Command::TrashList => {
+            let trash = service.trash().map_err(format_error)?;
+            if quiet {
+                let skus: Vec<&str> = trash.iter().map(|tombstone| tombstone.product.sku.as_str()).collect();
+                return Ok(skus.join("\n"));
+            }
+            if trash.is_empty() {
+                return Ok(tr(Msg::TrashEmpty).to_string());
+            }
+
+            let mut output = format!("{}\n", trf(Msg::TrashHeader, &[("count", &trash.len())]));
+            for tombstone in &trash {
+                let by = tombstone.deleted_by
+                    .as_ref()
+                    .map(|operator| trf(Msg::TrashDeletedBy, &[("operator", operator)]))
+                    .unwrap_or_default();
+                let expires = match service.trash_expiry(tombstone) {
+                    Some(date) => trf(Msg::TrashExpires, &[("date", &date.format("%Y-%m-%d"))]),
+                    None => tr(Msg::TrashNeverExpires).to_string(),
+                };
+                output.push_str(&trf(Msg::TrashLine, &[
+                    ("sku", &tombstone.product.sku),
+                    ("name", &tombstone.product.name),
+                    ("deleted", &tombstone.deleted_at.format("%Y-%m-%d %H:%M")),
+                    ("by", &by),
+                    ("count", &tombstone.transactions.len()),
+                    ("expires", &expires),
+                ]));
+                output.push('\n');
+            }
+            Ok(output.trim_end().to_string())
+        }
+
+        Command::TrashRestore { sku } => {
+            let product = service.restore_product(&sku).map_err(format_error)?;
+            if quiet {
+                return Ok(product.sku);
+            }
+            let count = service.get_transactions(&product.sku).len();
+            Ok(trf(Msg::ProductRestored, &[("sku", &product.sku), ("count", &count)]))
+        }
+        
+        Command::Help => {
+            Ok(help::get_help_text())
+        }
+
+        Command::CommandHelp { command } => command_help_text(&command),
+
+        Command::HelpJson { command } => help_json(command.as_deref()),
+    }
+}
+
+/// Get the detailed help text for the named command
+fn command_help_text(command: &str) -> Result<String, String> {
+    help::command_help(command)
+        .ok_or_else(|| format!("Unknown command: '{}'", command))
+}
+
+/// Get the JSON description of the CLI, or of the named command
+fn help_json(command: Option<&str>) -> Result<String, String> {
+    let value = match command {
+        Some(command) => help::command_json(command)
+            .ok_or_else(|| format!("Unknown command: '{}'", command))?,
+        None => help::help_json(),
+    };
+    serde_json::to_string_pretty(&value).map_err(|e| format!("Error: Failed to serialize help: {}", e))
+}
+
+/// Validate and apply bulk stock entries, reporting problems by input line
+fn apply_bulk_stock(
+    service: &mut InventoryService,
+    input: bulk::StockInput,
+    transaction_type: TransactionType,
+    options: RemovalOptions,
+    quiet: bool,
+) -> Result<String, String> {
+    let (entries, lines, mut report) = input.into_parts();
+
+    // Report malformed lines together with stock problems in the valid ones
+    let stock_report = service.validate_stock_entries(&entries, transaction_type, &options);
+    report.extend(stock_report.renumber(|row| lines[row - 1]));
+    if !report.is_valid() {
+        return Err(format_error(ServiceError::ValidationFailed { report }));
+    }
+
+    // Quantities before the batch, to show the running quantity per line
+    let mut running: std::collections::HashMap<String, u32> = entries
+        .iter()
+        .filter_map(|entry| service.get_product(&entry.sku).ok())
+        .map(|product| (product.sku.clone(), product.quantity))
+        .collect();
+    service.apply_stock_entries(&entries, transaction_type, options).map_err(format_error)?;
+
+    let mut output = if quiet {
+        String::new()
+    } else {
+        let header = if transaction_type.adds_stock() { Msg::BulkAdded } else { Msg::BulkRemoved };
+        format!("{}\n", trf(header, &[("count", &entries.len())]))
+    };
+    let sign = if transaction_type.adds_stock() { '+' } else { '-' };
+    for entry in &entries {
+        let quantity = running.get_mut(&entry.sku).unwrap();
+        if transaction_type.adds_stock() {
+            *quantity += entry.quantity;
+        } else {
+            *quantity -= entry.quantity;
+        }
+        if quiet {
+            output.push_str(&format!("{}\t{}\n", entry.sku, quantity));
+        } else {
+            let change = format!("{}{}", sign, entry.quantity);
+            output.push_str(&trf(Msg::BulkLine, &[("sku", &entry.sku), ("change", &change), ("quantity", quantity)]));
+            output.push('\n');
+        }
+    }
+    Ok(output.trim_end().to_string())
+}
+
+/// A receipt's reference for display
+fn receipt_reference(session: &ReceivingSession) -> String {
+    session.reference.clone().unwrap_or_else(|| tr(Msg::ReceiptNoReference).to_string())
+}
+
+/// Render a staged receipt line by line: `SKU\tEXPECTED\tRECEIVED\tVARIANCE` records when quiet
+///
+/// Variances are only marked when the receipt was started with expected quantities.
+fn format_receiving_review(review: &ReceivingReview, quiet: bool) -> String {
+    if quiet {
+        let records: Vec<String> = review.lines
+            .iter()
+            .map(|line| {
+                let expected = line.expected.map_or("-".to_string(), |expected| expected.to_string());
+                format!("{}\t{}\t{}\t{}", line.sku, expected, line.received, line.variance())
+            })
+            .collect();
+        return records.join("\n");
+    }
+
+    let session = &review.session;
+    let by = session.started_by
+        .as_ref()
+        .map(|operator| trf(Msg::ReceivingBy, &[("operator", operator)]))
+        .unwrap_or_default();
+    let mut output = vec![trf(Msg::ReceivingHeader, &[
+        ("reference", &receipt_reference(session)),
+        ("date", &session.started_at.format("%Y-%m-%d %H:%M")),
+        ("by", &by),
+        ("lines", &session.received.len()),
+        ("units", &review.received_units()),
+    ])];
+    output.extend(receiving_lines(review, false));
+    output.join("\n")
+}
+
+/// One line per product of a staged receipt, optionally only those that differ from the expected quantity
+fn receiving_lines(review: &ReceivingReview, only_variances: bool) -> Vec<String> {
+    let checked = !review.session.expected.is_empty();
+    let mut output = Vec::new();
+    for line in &review.lines {
+        if only_variances && line.expected.is_some() && line.variance() == 0 {
+            continue;
+        }
+        let Some(expected) = line.expected.filter(|_| checked) else {
+            let marker = if checked { format!(" {}", tr(Msg::ReceivingUnexpected)) } else { String::new() };
+            output.push(trf(Msg::ReceivingLine, &[("sku", &line.sku), ("received", &line.received), ("marker", &marker)]));
+            continue;
+        };
+        let marker = match line.variance() {
+            0 => String::new(),
+            v if v < 0 => format!(" {}", trf(Msg::ReceivingShort, &[("units", &-v)])),
+            v => format!(" {}", trf(Msg::ReceivingOver, &[("units", &v)])),
+        };
+        output.push(trf(Msg::ReceivingExpectedLine, &[
+            ("sku", &line.sku),
+            ("expected", &expected),
+            ("received", &line.received),
+            ("marker", &marker),
+        ]));
+    }
+    output
+}
+
+/// Validate and import historical transactions, listing each product's new quantity
+///
+/// With a job, a file of more than `CHUNK_ROWS` rows, or one being resumed,
+/// is imported a chunk at a time, and only the rows after the checkpoint are
+/// validated and imported.
+fn import_transactions(
+    service: &mut InventoryService,
+    input: bulk::HistoryInput,
+    job: Option<(&mut ImportJob, &std::path::Path)>,
+    quiet: bool,
+) -> Result<String, String> {
+    let (entries, lines, mut report) = input.into_parts();
+    let start = job.as_ref().map_or(0, |(job, _)| resume_point(service, job, &entries));
+    let (entries, lines) = (&entries[start..], &lines[start..]);
+
+    // Report malformed lines together with chronology and stock problems
+    let history_report = service.validate_historical_entries(entries);
+    report.extend(history_report.renumber(|row| lines[row - 1]));
+    if !report.is_valid() {
+        return Err(format_error(ServiceError::ValidationFailed { report }));
+    }
+    match job {
+        Some((job, data_dir)) if job.last_seq.is_some() || entries.len() > CHUNK_ROWS => {
+            import_in_chunks(service, entries, start, job, data_dir)?
+        }
+        _ => service.import_transactions(entries).map_err(format_error)?,
+    }
+
+    let mut skus: Vec<&str> = Vec::new();
+    for entry in entries {
+        if !skus.contains(&entry.sku.as_str()) {
+            skus.push(&entry.sku);
+        }
+    }
+    let mut output = if quiet {
+        String::new()
+    } else {
+        format!("{}\n", trf(Msg::TransactionsImported, &[("count", &entries.len()), ("products", &skus.len())]))
+    };
+    for sku in skus {
+        let quantity = service.get_product(sku).map_err(format_error)?.quantity;
+        if quiet {
+            output.push_str(&format!("{}\t{}\n", sku, quantity));
+        } else {
+            let count = entries.iter().filter(|entry| entry.sku == sku).count();
+            output.push_str(&trf(Msg::ImportedLine, &[("sku", &sku), ("count", &count), ("quantity", &quantity)]));
+            output.push('\n');
+        }
+    }
+    Ok(output.trim_end().to_string())
+}
+
+/// Where a resumed import carries on: after its checkpoint, and after the
+/// chunk that followed too if that reached the data files despite failing
+fn resume_point(service: &InventoryService, job: &ImportJob, entries: &[HistoricalEntry]) -> usize {
+    let Some(seq) = job.last_seq else { return 0 };
+    let start = job.applied.min(entries.len());
+    let next = &entries[start..(start + CHUNK_ROWS).min(entries.len())];
+    if !next.is_empty() && service.imported_since(seq, next) {
+        start + next.len()
+    } else {
+        start
+    }
+}
+
+/// Import entries a chunk at a time, saving each and checkpointing the job
+/// after it; the job is removed once the last chunk is in
+fn import_in_chunks(
+    service: &mut InventoryService,
+    entries: &[HistoricalEntry],
+    start: usize,
+    job: &mut ImportJob,
+    data_dir: &std::path::Path,
+) -> Result<(), String> {
+    let total = start + entries.len();
+    job.applied = start;
+    job.last_seq = Some(service.last_seq());
+    job.save(data_dir)?;
+    for chunk in entries.chunks(CHUNK_ROWS) {
+        if let Err(err) = service.import_transactions(chunk).and_then(|()| service.flush()) {
+            return Err(format!(
+                "{}\n{}",
+                format_error(err),
+                trf(Msg::ImportInterrupted, &[("applied", &job.applied), ("total", &total), ("id", &job.id)])
+            ));
+        }
+        job.applied += chunk.len();
+        job.last_seq = Some(service.last_seq());
+        job.save(data_dir)?;
+    }
+    job.remove(data_dir)
+}
+
+/// Validate and create opening balances, merging them into existing
+/// products with `merge`, then print the reconciliation
+///
+/// Fails after the products are created if the totals don't reconcile, so
+/// scripts can stop the go-live.
+fn migrate_opening_balances(
+    service: &mut InventoryService,
+    input: bulk::OpeningInput,
+    cutover: DateTime<Utc>,
+    merge: Option<&MergePolicy>,
+    quiet: bool,
+) -> Result<String, String> {
+    let (balances, lines, mut report) = input.into_parts();
+
+    let balance_report = match merge {
+        Some(policy) => service.validate_opening_merge(&balances, policy),
+        None => service.validate_opening_balances(&balances),
+    };
+    report.extend(balance_report.renumber(|row| lines[row - 1]));
+    if !report.is_valid() {
+        return Err(format_error(ServiceError::ValidationFailed { report }));
+    }
+    let reconciliation = match merge {
+        Some(policy) => service.merge_opening_balances(&balances, cutover, policy),
+        None => service.migrate_opening_balances(&balances, cutover),
+    }
+    .map_err(format_error)?;
+
+    let output = if quiet {
+        let mut output = format!(
+            "products\t{}\t{}\nunits\t{}\t{}",
+            reconciliation.expected_products, reconciliation.created_products,
+            reconciliation.expected_units, reconciliation.created_units
+        );
+        if merge.is_some() {
+            output.push_str(&format!("\nmerged\t{}", reconciliation.merged_products));
+        }
+        output
+    } else {
+        let mut lines = vec![
+            trf(Msg::OpeningBalancesCreated, &[
+                ("count", &reconciliation.created_products),
+                ("date", &cutover.format("%Y-%m-%d")),
+            ]),
+        ];
+        if merge.is_some() {
+            lines.push(trf(Msg::OpeningBalancesMerged, &[("count", &reconciliation.merged_products)]));
+        }
+        lines.extend([
+            tr(Msg::ReconciliationHeader).to_string(),
+            trf(Msg::ReconciliationLine, &[
+                ("label", &tr(Msg::LabelProducts)),
+                ("expected", &reconciliation.expected_products),
+                ("created", &reconciliation.created_products),
+            ]),
+            trf(Msg::ReconciliationLine, &[
+                ("label", &tr(Msg::LabelUnits)),
+                ("expected", &reconciliation.expected_units),
+                ("created", &reconciliation.created_units),
+            ]),
+        ]);
+        for (sku, expected, recorded) in &reconciliation.mismatches {
+            lines.push(trf(Msg::ReconciliationMismatch, &[
+                ("sku", sku),
+                ("expected", expected),
+                ("recorded", recorded),
+            ]));
+        }
+        if reconciliation.is_balanced() {
+            lines.push(tr(Msg::ReconciliationBalanced).to_string());
+        }
+        lines.join("\n")
+    };
+
+    if !reconciliation.is_balanced() {
+        return Err(format!("{}\n{}", tr(Msg::ErrReconciliation), output));
+    }
+    Ok(output)
+}
+
+/// Localized name of a transaction type, as shown by history and reports
+fn type_label(transaction_type: TransactionType) -> &'static str {
+    tr(match transaction_type {
+        TransactionType::Addition => Msg::TypeAddition,
+        TransactionType::Removal => Msg::TypeRemoval,
+        TransactionType::InitialStock => Msg::TypeInitialStock,
+        TransactionType::Damage => Msg::TypeDamage,
+        TransactionType::WriteOff => Msg::TypeWriteOff,
+        TransactionType::Adjustment => Msg::TypeAdjustment,
+        TransactionType::Quarantine => Msg::TypeQuarantine,
+        TransactionType::ReleaseQuarantine => Msg::TypeReleaseQuarantine,
+        TransactionType::ConditionChange => Msg::TypeConditionChange,
+    })
+}
+
+/// Localized name of a value-history interval
+fn interval_label(interval: Interval) -> &'static str {
+    tr(match interval {
+        Interval::Day => Msg::IntervalDay,
+        Interval::Week => Msg::IntervalWeek,
+        Interval::Month => Msg::IntervalMonth,
+        Interval::Quarter => Msg::IntervalQuarter,
+        Interval::Year => Msg::IntervalYear,
+    })
+}
+
+/// Available units by condition, e.g. "20 new, 5 refurbished", leaving out empty conditions
+fn condition_breakdown(product: &Product) -> String {
+    let parts: Vec<String> = Condition::ALL
+        .into_iter()
+        .map(|condition| (condition, product.condition_quantity(condition)))
+        .filter(|&(_, quantity)| quantity > 0)
+        .map(|(condition, quantity)| format!("{} {}", quantity, condition_label(condition)))
+        .collect();
+    if parts.is_empty() {
+        format!("0 {}", condition_label(Condition::New))
+    } else {
+        parts.join(", ")
+    }
+}
+
+/// Localized name of a reason code, or "unspecified"
+fn reason_label(reason: Option<ReasonCode>) -> &'static str {
+    tr(match reason {
+        Some(ReasonCode::Sale) => Msg::ReasonSale,
+        Some(ReasonCode::InternalUse) => Msg::ReasonInternalUse,
+        Some(ReasonCode::Sample) => Msg::ReasonSample,
+        Some(ReasonCode::Damage) => Msg::ReasonDamage,
+        None => Msg::ReasonUnspecified,
+    })
+}
+
+/// Width of the value bars in the KPI trend report
+const KPI_BAR_WIDTH: usize = 30;
+
+/// A bar as long as `value` is against the largest value charted
+fn kpi_bar(value: Money, max: u64) -> String {
+    if max == 0 {
+        return String::new();
+    }
+    let width = (value.cents() as u128 * KPI_BAR_WIDTH as u128 / max as u128) as usize;
+    "#".repeat(if value.cents() > 0 { width.max(1) } else { 0 })
+}
+
+/// Tab-separated KPI snapshot: date, products, units, value, low stock, movements
+fn kpi_record(snapshot: &KpiSnapshot) -> String {
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}",
+        snapshot.date, snapshot.products, snapshot.units, snapshot.value, snapshot.low_stock, snapshot.movements
+    )
+}
+
+/// Labels for a report period whose end is exclusive, showing the last day included
+fn period_labels(start: Option<DateTime<Utc>>, end: Option<DateTime<Utc>>) -> (String, String) {
+    let from = start
+        .map(|d| d.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| tr(Msg::PeriodStart).to_string());
+    let to = end
+        .map(|d| (d - chrono::Duration::days(1)).format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| tr(Msg::PeriodNow).to_string());
+    (from, to)
+}
+
+/// Read the quantities of a CSV export or count sheet for `reconcile`,
+/// refusing a file with bad lines or unknown SKUs
+fn read_counted_quantities(
+    service: &InventoryService,
+    file: &str,
+    key: &str,
+    qty_column: &str,
+    only: Option<(&str, &str)>,
+) -> Result<Vec<ExternalQuantity>, String> {
+    let reader = std::fs::File::open(file)
+        .map(io::BufReader::new)
+        .map_err(|e| format!("Error: Failed to read {}: {}", file, e))?;
+    let (quantities, lines, mut report) = bulk::read_external_quantities(reader, key, qty_column, only)?.into_parts();
+    report.extend(service.validate_external_quantities(&quantities).renumber(|row| lines[row - 1]));
+    if !report.is_valid() {
+        return Err(format_error(ServiceError::ValidationFailed { report }));
+    }
+    Ok(quantities)
+}
+
+/// Load the products of a snapshot directory or file for `diff`
+fn load_snapshot(path: &str) -> Result<Vec<Product>, String> {
+    diff::load_snapshot(std::path::Path::new(path))
+        .map_err(|e| trf(Msg::ErrSnapshot, &[("path", &path), ("error", &e)]))
+}
+
+/// Render a diff: tab-separated `added`/`removed`/`changed` records when quiet
+fn format_diff(diff: &InventoryDiff, from: &str, to: &str, quiet: bool) -> String {
+    if quiet {
+        let mut lines: Vec<String> = Vec::new();
+        lines.extend(diff.added.iter().map(|p| format!("added\t{}\t{}", p.sku, p.quantity)));
+        lines.extend(diff.removed.iter().map(|p| format!("removed\t{}\t{}", p.sku, p.quantity)));
+        lines.extend(diff.changed.iter().map(|c| format!("changed\t{}\t{}\t{}", c.sku, c.before, c.after)));
+        return lines.join("\n");
+    }
+    if diff.is_empty() {
+        return trf(Msg::DiffNone, &[("from", &from), ("to", &to)]);
+    }
+
+    let mut lines = vec![trf(Msg::DiffHeader, &[("from", &from), ("to", &to)])];
+    let sections = [(Msg::DiffAdded, &diff.added), (Msg::DiffRemoved, &diff.removed)];
+    for (title, products) in sections {
+        if products.is_empty() {
+            continue;
+        }
+        lines.push(trf(title, &[("count", &products.len())]));
+        for product in products {
+            lines.push(trf(Msg::ProductLine, &[
+                ("sku", &product.sku),
+                ("name", &product.name),
+                ("quantity", &product.quantity),
+                ("marker", &""),
+            ]));
+        }
+    }
+    if !diff.changed.is_empty() {
+        lines.push(trf(Msg::DiffChanged, &[("count", &diff.changed.len())]));
+        for change in &diff.changed {
+            lines.push(trf(Msg::DiffChangeLine, &[
+                ("sku", &change.sku),
+                ("name", &change.name),
+                ("before", &change.before),
+                ("after", &change.after),
+                ("delta", &format!("{:+}", change.delta())),
+            ]));
+        }
+    }
+    lines.push(trf(Msg::DiffNet, &[("delta", &format!("{:+}", diff.net_change()))]));
+    lines.join("\n")
+}
+
+/// Transactions shown by `history`, limited to the range when both ends are given
+fn history_transactions(
+    service: &InventoryService,
+    sku: &str,
+    start: Option<DateTime<Utc>>,
+    end: Option<DateTime<Utc>>,
+    include_archived: bool,
+    customer: Option<&str>,
+) -> Result<Vec<Transaction>, String> {
+    let range = start.zip(end).map(|(s, e)| s..=e);
+    if let Some(code) = customer {
+        service.customer(code).map_err(format_error)?;
+    }
+    let mut transactions = service.transactions_for(sku).map_err(format_error)?;
+    if include_archived {
+        transactions.extend(service.archived_transactions(sku).map_err(format_error)?);
+    }
+    transactions.retain(|t| range.as_ref().is_none_or(|range| range.contains(&t.timestamp)));
+    transactions.retain(|t| customer.is_none_or(|code| t.customer.as_deref() == Some(code)));
+    transactions.sort_by_key(|t| t.order_key());
+    Ok(transactions)
+}
+
+/// One transaction as a line of `history`: ID, time, direction, quantity,
+/// type, and notes
+fn history_line(txn: &Transaction) -> String {
+    let type_str = match txn.transaction_type {
+        TransactionType::ConditionChange => "~",
+        t if t.adds_stock() => "+",
+        _ => "-",
+    };
+    let mut type_name = match txn.reason {
+        Some(reason) => format!("{} [{}]", type_label(txn.transaction_type), reason_label(Some(reason))),
+        None => type_label(txn.transaction_type).to_string(),
+    };
+    match (txn.from_condition, txn.condition) {
+        (Some(from), Some(to)) => {
+            type_name.push_str(&format!(" ({} -> {})", condition_label(from), condition_label(to)));
+        }
+        (None, Some(condition)) => type_name.push_str(&format!(" ({})", condition_label(condition))),
+        _ => {}
+    }
+    if let Some(customer) = &txn.customer {
+        type_name.push_str(&format!(" {}", trf(Msg::ShippedTo, &[("customer", customer)])));
+    }
+    let notes_str = txn.notes.as_ref().map(|n| format!(" - {}", n)).unwrap_or_default();
+    format!(
+        "{} {} {} {} {}{}",
+        &txn.id[..txn.id.len().min(8)],
+        txn.timestamp.format("%Y-%m-%d %H:%M:%S"),
+        type_str,
+        txn.quantity,
+        type_name,
+        notes_str
+    )
+}
+
+/// Products ordered by SKU, for stable template output
+fn sorted_by_sku(mut products: Vec<&Product>) -> Vec<&Product> {
+    products.sort_by(|a, b| a.sku.cmp(&b.sku));
+    products
+}
+
+/// Template data for a product, in the current API shape
+fn product_context(product: &Product) -> Value {
+    json!(api::v1::Product::from(product))
+}
+
+/// Render a template file against command data
+fn render_template(path: &str, context: Value) -> Result<String, String> {
+    let source = std::fs::read_to_string(path)
+        .map_err(|e| format!("Error: Failed to read template {}: {}", path, e))?;
+    let template = Template::parse(&source)
+        .map_err(|e| format!("Error: Invalid template {}: {}", path, e))?;
+    Ok(template.render(&context).trim_end_matches('\n').to_string())
+}
+
+/// Add a low-stock line's note of the units already on order, if any
+fn push_on_order(output: &mut String, product: &Product) {
+    if product.on_order > 0 {
+        output.push_str(&trf(Msg::LowStockOnOrder, &[("quantity", &product.on_order)]));
+        output.push('\n');
+    }
+}
+
+/// Add the in-stock substitutes for an out-of-stock product, if it has any
+fn push_substitutes(output: &mut String, service: &InventoryService, product: &Product) {
+    if let Some(substitutes) = substitute_list(service, product) {
+        output.push_str(&trf(Msg::LowStockSubstitutes, &[("substitutes", &substitutes)]));
+        output.push('\n');
+    }
+}
+
+/// Cells of a `list-products --columns` table, one row per product plus a
+/// totals row labelled `totals` if given
+///
+/// Quantities are those in `condition` when the listing is filtered by one.
+fn product_table(
+    service: &InventoryService,
+    products: &[&Product],
+    condition: Option<Condition>,
+    columns: &[ListColumn],
+    totals: Option<&str>,
+) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let (mut quantity, mut reorder_point, mut value) = (0u64, 0u64, Money::default());
+    for product in products {
+        let product_quantity = condition.map_or(product.quantity, |c| product.condition_quantity(c));
+        let product_value = if columns.contains(&ListColumn::Value) {
+            service.current_cost_layers(&product.sku).map_err(format_error)?.valuation().value
+        } else {
+            Money::default()
+        };
+        quantity += u64::from(product_quantity);
+        reorder_point += u64::from(product.reorder_point);
+        value = value + product_value;
+        let label = |label: &Option<String>| label.clone().unwrap_or_else(|| "-".to_string());
+        rows.push(columns.iter().map(|column| match column {
+            ListColumn::Sku => product.sku.clone(),
+            ListColumn::Name => product.name.clone(),
+            ListColumn::Quantity => product_quantity.to_string(),
+            ListColumn::ReorderPoint => product.reorder_point.to_string(),
+            ListColumn::Category => label(&product.category),
+            ListColumn::Supplier => label(&product.supplier),
+            ListColumn::Location => label(&product.location),
+            ListColumn::UnitCost => product.unit_cost.map_or_else(|| "-".to_string(), |cost| cost.to_string()),
+            ListColumn::Value => product_value.to_string(),
+        }).collect());
+    }
+    if let Some(total_label) = totals {
+        let label_column = columns.iter().position(|column| !column.is_numeric());
+        rows.push(columns.iter().enumerate().map(|(i, column)| match column {
+            ListColumn::Quantity => quantity.to_string(),
+            ListColumn::ReorderPoint => reorder_point.to_string(),
+            ListColumn::Value => value.to_string(),
+            _ if Some(i) == label_column => total_label.to_string(),
+            _ => String::new(),
+        }).collect());
+    }
+    Ok(rows)
+}
+
+/// Lines of a table under `header`, with `numeric` columns right-aligned
+fn format_table(header: &[String], rows: &[Vec<String>], numeric: &[bool]) -> Vec<String> {
+    let widths: Vec<usize> = (0..header.len())
+        .map(|i| rows.iter().map(Vec::as_slice).chain([header]).map(|row| row[i].chars().count()).max().unwrap_or(0))
+        .collect();
+    let line = |row: &[String]| {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(numeric)
+            .zip(&widths)
+            .map(|((cell, numeric), width)| if *numeric { format!("{:>width$}", cell) } else { format!("{:<width$}", cell) })
+            .collect();
+        format!("  {}", cells.join("  ")).trim_end().to_string()
+    };
+    [header].into_iter().chain(rows.iter().map(Vec::as_slice)).map(line).collect()
+}
+
+/// Heading of a `list-products --columns` column
+fn list_column_label(column: ListColumn) -> &'static str {
+    tr(match column {
+        ListColumn::Sku => Msg::LabelSku,
+        ListColumn::Name => Msg::LabelName,
+        ListColumn::Quantity => Msg::LabelQuantity,
+        ListColumn::ReorderPoint => Msg::LabelReorderPoint,
+        ListColumn::Category => Msg::LabelCategory,
+        ListColumn::Supplier => Msg::LabelSupplier,
+        ListColumn::Location => Msg::LabelLocation,
+        ListColumn::UnitCost => Msg::LabelUnitCost,
+        ListColumn::Value => Msg::LabelValue,
+    })
+}
+
+/// In-stock substitutes for a product that is out of stock, with their quantities
+fn substitute_list(service: &InventoryService, product: &Product) -> Option<String> {
+    if product.quantity > 0 {
+        return None;
+    }
+    let substitutes: Vec<String> = service
+        .substitutes_in_stock(&product.sku)
+        .iter()
+        .map(|substitute| format!("{} - {} ({})", substitute.sku, substitute.name, substitute.quantity))
+        .collect();
+    (!substitutes.is_empty()).then(|| substitutes.join(", "))
+}
+
+/// A product link as shown to the user, e.g. "substitute for SKU001"
+fn link_label(link: &ProductLink) -> String {
+    let msg = match link.kind {
+        LinkKind::SubstituteFor => Msg::LinkSubstituteFor,
+        LinkKind::AccessoryOf => Msg::LinkAccessoryOf,
+    };
+    trf(msg, &[("sku", &link.sku)])
+}
+
+/// Add a note on how many low-stock products are hidden by `ack`, if any
+fn with_snoozed_note(service: &InventoryService, output: String) -> String {
+    let snoozed = service.snoozed_low_stock(Utc::now().date_naive()).len();
+    if snoozed == 0 {
+        return output;
+    }
+    format!("{}\n{}", output, trf(Msg::LowStockSnoozedNote, &[("count", &snoozed)]))
+}
+
+/// One SKU per line, for quiet output
+fn sku_lines<'a>(products: impl IntoIterator<Item = &'a Product>) -> String {
+    products.into_iter().map(|p| p.sku.as_str()).collect::<Vec<_>>().join("\n")
+}
+
+/// Format the labelled fields of a product, one per line
+///
+/// `quantity_marker` is appended to the quantity (e.g. a low-stock flag).
+fn format_product_fields(product: &Product, with_id: bool, quantity_marker: &str) -> String {
+    let mut lines = Vec::new();
+    if with_id {
+        lines.push(format!("  {}: {}", tr(Msg::LabelId), product.id));
+    }
+    lines.push(format!("  {}: {}", tr(Msg::LabelSku), product.sku));
+    lines.push(format!("  {}: {}", tr(Msg::LabelName), product.name));
+    lines.push(format!("  {}: {}", tr(Msg::LabelDescription), product.description));
+    lines.push(format!("  {}: {}{}", tr(Msg::LabelQuantity), product.quantity, quantity_marker));
+    if product.quarantined > 0 {
+        lines.push(format!("  {}: {}", tr(Msg::LabelQuarantined), product.quarantined));
+    }
+    if product.on_order > 0 {
+        lines.push(format!("  {}: {}", tr(Msg::LabelOnOrder), product.on_order));
+    }
+    if !product.conditions.is_empty() {
+        lines.push(format!("  {}: {}", tr(Msg::LabelCondition), condition_breakdown(product)));
+    }
+    lines.push(format!("  {}: {}", tr(Msg::LabelReorderPoint), product.reorder_point));
+    let classification = [
+        (Msg::LabelCategory, &product.category),
+        (Msg::LabelSupplier, &product.supplier),
+        (Msg::LabelLocation, &product.location),
+        (Msg::LabelBarcode, &product.barcode),
+    ];
+    for (label, value) in classification {
+        if let Some(value) = value {
+            lines.push(format!("  {}: {}", tr(label), value));
+        }
+    }
+    if let Some(unit_cost) = product.unit_cost {
+        lines.push(format!("  {}: {}", tr(Msg::LabelUnitCost), unit_cost));
+    }
+    if let Some(unit_weight) = product.unit_weight {
+        lines.push(format!("  {}: {}", tr(Msg::LabelUnitWeight), unit_weight));
+    }
+    if let Some(tare_weight) = product.tare_weight {
+        lines.push(format!("  {}: {}", tr(Msg::LabelTareWeight), tare_weight));
+    }
+    if let Some(days) = product.lead_time_days {
+        lines.push(format!("  {}: {}", tr(Msg::LabelLeadTime), trf(Msg::LeadTimeDays, &[("days", &days)])));
+    }
+    if !product.components.is_empty() {
+        let components: Vec<String> = product.components.iter().map(Component::to_string).collect();
+        lines.push(format!("  {}: {}", tr(Msg::LabelComponents), components.join(", ")));
+    }
+    if !product.links.is_empty() {
+        let links: Vec<String> = product.links.iter().map(link_label).collect();
+        lines.push(format!("  {}: {}", tr(Msg::LabelLinks), links.join(", ")));
+    }
+    if let Some(snooze) = product.alert_snooze {
+        let detail = match snooze.until {
+            Some(until) => trf(Msg::SnoozeDetailUntil, &[("quantity", &snooze.quantity), ("until", &until)]),
+            None => trf(Msg::SnoozeDetail, &[("quantity", &snooze.quantity)]),
+        };
+        lines.push(format!("  {}: {}", tr(Msg::LabelAlertSnooze), detail));
+    }
+    lines.join("\n")
+}
+
+/// Format a product's FIFO cost layers on one line, oldest first, with their total value
+fn format_cost_layers(layers: &CostLayers) -> String {
+    let layers_text: Vec<String> = layers
+        .layers()
+        .map(|layer| {
+            let cost = layer.unit_cost.map_or_else(|| "-".to_string(), |cost| cost.to_string());
+            format!("{} @ {}", layer.quantity, cost)
+        })
+        .collect();
+    format!("  {}: {} ({})", tr(Msg::LabelCostLayers), layers_text.join(", "), trf(Msg::CostLayersValue, &[
+        ("value", &layers.valuation().value),
+    ]))
+}
+
+/// Format a ServiceError into a user-friendly message
+fn format_error(err: ServiceError) -> String {
+    log::debug!("Service error {}: {}", err.code(), err);
+    match err {
+        ServiceError::ProductNotFound { sku } => trf(Msg::ErrProductNotFound, &[("sku", &sku)]),
+        ServiceError::DuplicateSKU { sku } => trf(Msg::ErrDuplicateSku, &[("sku", &sku)]),
+        ServiceError::InvalidInput { message } => trf(Msg::ErrInvalidInput, &[("message", &message)]),
+        ServiceError::TransactionNotFound { id } => trf(Msg::ErrTransactionNotFound, &[("id", &id)]),
+        ServiceError::InsufficientStock { sku, requested, available } => trf(
+            Msg::ErrInsufficientStock,
+            &[("sku", &sku), ("requested", &requested), ("available", &available)],
+        ),
+        ServiceError::LargeMovement { sku, requested, threshold } => format!(
+            "{} {}",
+            trf(Msg::ErrLargeMovement, &[("sku", &sku), ("requested", &requested), ("threshold", &threshold)]),
+            tr(Msg::ConfirmLargeHint)
+        ),
+        ServiceError::NotInTrash { sku } => trf(Msg::ErrNotInTrash, &[("sku", &sku)]),
+        ServiceError::CustomerNotFound { code } => trf(Msg::ErrCustomerNotFound, &[("code", &code)]),
+        ServiceError::DuplicateCustomer { code } => trf(Msg::ErrDuplicateCustomer, &[("code", &code)]),
+        ServiceError::ReadOnly => tr(Msg::ErrReadOnly).to_string(),
+        ServiceError::ClockDrift { sku, latest } => {
+            trf(Msg::ErrClockDrift, &[("sku", &sku), ("latest", &latest.format("%Y-%m-%d %H:%M:%S"))])
+        }
+        ServiceError::DeletionBlocked { sku, blocker } => {
+            let reason = match blocker {
+                DeletionBlocker::InStock { quantity } => {
+                    trf(Msg::ErrDeletionInStock, &[("sku", &sku), ("quantity", &quantity)])
+                }
+                DeletionBlocker::RecentActivity { transactions, days } => trf(
+                    Msg::ErrDeletionRecentActivity,
+                    &[("sku", &sku), ("count", &transactions), ("days", &days)],
+                ),
+            };
+            format!("{} {}", reason, tr(Msg::ForceDeleteHint))
+        }
+        ServiceError::ValidationFailed { report } => {
+            let mut message = trf(Msg::BulkRejected, &[("rejected", &report.rejected_rows()), ("total", &report.rows)]);
+            for issue in report.issues() {
+                message.push('\n');
+                message.push_str(&trf(Msg::BulkRejectedLine, &[("line", &issue.row), ("error", &issue.message)]));
+            }
+            if report.has_code("LARGE_MOVEMENT") {
+                message.push('\n');
+                message.push_str(tr(Msg::ConfirmLargeHint));
+            }
+            message
+        }
+        ServiceError::StorageError { operation, source } => {
+            trf(Msg::ErrStorage, &[("operation", &operation), ("error", &source)])
+        }
+    }
+}
+
+/// Run the CLI application
+/// 
+/// # Arguments
+/// * `data_dir` - Directory for storing data files
+/// 
+/// # Returns
+/// * `Ok(())` - Application completed successfully
+/// * `Err(String)` - Error message
+pub fn run(data_dir: &str) -> Result<(), String> {
+    let args: Vec<String> = env::args().collect();
+    run_with_args(&args, data_dir)
+}
+
+/// Run the CLI application with provided arguments
+/// 
+/// # Arguments
+/// * `args` - Command-line arguments
+/// * `data_dir` - Directory for storing data files
+/// 
+/// # Returns
+/// * `Ok(())` - Application completed successfully
+/// * `Err(String)` - Error message
+pub fn run_with_args(args: &[String], data_dir: &str) -> Result<(), String> {
+    let (options, args) = split_global_options(args);
+    let config = Config::load(data_dir)
+        .map_err(|e| format!("Failed to load configuration: {}", e))?;
+    logging::init(options.verbosity, std::path::Path::new(data_dir), &config.log)?;
+    i18n::set_locale(Locale::detect(config.locale.as_deref())?);
+    if let Some(plugin) = find_plugin(&args, &config.aliases) {
+        return plugins::run(&plugin, &args[2..], &options, std::path::Path::new(data_dir));
+    }
+    let command = parse_args_with_aliases(&args, &config.aliases)?;
+    log::debug!("Parsed command: {:?}", command);
+    let mode = if options.quiet { OutputMode::Quiet } else { OutputMode::Normal };
+    
+    // Help commands don't need the service
+    match &command {
+        Command::Help => {
+            let installed = plugins::discover(&env::var_os("PATH").unwrap_or_default());
+            println!("{}{}", help::get_help_text(), plugin_help(&installed));
+            return Ok(());
+        }
+        Command::CommandHelp { command } => {
+            println!("{}", command_help_text(command)?);
+            return Ok(());
+        }
+        Command::HelpJson { command } => {
+            println!("{}", help_json(command.as_deref())?);
+            return Ok(());
+        }
+        _ => {}
+    }
+    
+    let mut started_notice = None;
+    let command = match command {
+        Command::RunView { name, template } => {
+            let filter = config.views.get(&name).cloned()
+                .ok_or_else(|| trf(Msg::ErrViewNotFound, &[("name", &name)]))?;
+            Command::ListProducts { filter, template, columns: Vec::new(), totals: false, group_by: None }
+        }
+        Command::SaveView { .. } | Command::ListViews | Command::DeleteView { .. } => {
+            println!("{}", manage_views(command, data_dir, &config.views, options.quiet)?);
+            return Ok(());
+        }
+        Command::AddProductFromTemplate { .. } => from_template(command, &config.product_templates)?,
+        Command::ImportTransactions { file, profile } => {
+            let profile = match profile {
+                Some(name) => Some(config.import_profiles.get(&name).cloned()
+                    .ok_or_else(|| trf(Msg::ErrImportProfileNotFound, &[("name", &name)]))?),
+                None => None,
+            };
+            let contents = std::fs::read(&file).map_err(|e| format!("Error: Failed to read {}: {}", file, e))?;
+            let path = std::fs::canonicalize(&file).map_err(|e| format!("Error: Failed to read {}: {}", file, e))?;
+            Command::ImportJob { job: ImportJob::start(&path, &contents, profile), data_dir: PathBuf::from(data_dir) }
+        }
+        Command::ResumeImport { job } => {
+            Command::ImportJob { job: ImportJob::load(std::path::Path::new(data_dir), &job)?, data_dir: PathBuf::from(data_dir) }
+        }
+        Command::SaveProductTemplate { .. } | Command::ListProductTemplates | Command::DeleteProductTemplate { .. } => {
+            println!("{}", manage_product_templates(command, data_dir, &config.product_templates, options.quiet)?);
+            return Ok(());
+        }
+        Command::ConvertStorage { target, from, to } => {
+            println!("{}", convert_storage(data_dir, &target, &from, &to, options.quiet)?);
+            return Ok(());
+        }
+        Command::EventsTail { since, limit } => {
+            let lines = outbox::since(std::path::Path::new(data_dir), since, limit)?;
+            if !lines.is_empty() {
+                println!("{}", lines.join("\n"));
+            }
+            return Ok(());
+        }
+        Command::FeedStatus => {
+            let state = feed::load_state(std::path::Path::new(data_dir))?;
+            println!("{}", feed_status(&config.feeds, &state, options.quiet));
+            return Ok(());
+        }
+        Command::ReceiveInbox { maildir, start: None } => {
+            println!("{}", receive_inbox(&config.inbox, maildir.as_deref(), std::path::Path::new(data_dir), options.quiet)?);
+            return Ok(());
+        }
+        Command::ReceiveInbox { start: Some(reference), .. } => {
+            let path = inbox::notice_path(std::path::Path::new(data_dir), &reference);
+            if !path.is_file() {
+                return Err(trf(Msg::ErrNoticeNotFound, &[("reference", &reference)]));
+            }
+            started_notice = Some(path.clone());
+            Command::ReceiveStart { reference: Some(reference), expected_file: Some(path.display().to_string()) }
+        }
+        command => command,
+    };
+
+    // Initialize storage and service; with memory mapping on, commands that
+    // only read one product's history skip loading the whole ledger
+    let storage = JsonStorage::new(data_dir).with_memory_map(config.storage.memory_map);
+    if options.strict_load {
+        strict_load_check(&storage, config.clock.tolerance())?;
+    }
+    let read_only = config.storage.memory_map
+        && matches!(command, Command::ViewProduct { .. } | Command::History { .. });
+    let service = if read_only {
+        InventoryService::open_read_only(Box::new(storage))
+    } else {
+        InventoryService::new(Box::new(storage))
+    };
+    let mut service = service
+        .map_err(|e| format!("Failed to initialize inventory service: {}", e))?
+        .with_large_movement_threshold(config.large_movement_threshold)
+        .with_field_limits(config.limits)
+        .with_deletion_policy(config.deletion)
+        .with_stocktake_policy(config.stocktake)
+        .with_backorder_allocation(config.backorders.auto_allocate)
+        .with_retention_policy(config.retention)
+        .with_operator(config.operator.or_else(|| env::var("USER").or_else(|_| env::var("USERNAME")).ok()))
+        .with_seasonality(config.forecast.seasonality)
+        .with_alert_rules(rules::compile_all(&config.rules).map_err(|e| format!("Failed to load configuration: {}", e))?)
+        .with_clock_policy(config.clock)
+        .with_save_mode(config.storage.autosave)
+        .with_size_quotas(config.quotas)
+        .with_hash_chain(config.ledger.hash_chain)
+        .map_err(|e| format!("Failed to initialize inventory service: {}", e))?;
+
+    let mut dispatch = Dispatch::new(std::path::Path::new(data_dir), &config.hooks, &config.mqtt, &config.shop);
+    if command == Command::Kiosk {
+        let unlock_code = config.kiosk.unlock_code.as_deref();
+        run_kiosk(&mut service, unlock_code, &config.aliases, &mut dispatch, &mut io::stdin().lock(), &mut io::stdout())?;
+        return service.flush().map_err(format_error);
+    }
+
+    if let Command::ShopSync { dry_run } = command {
+        println!("{}", shop_sync(&service, &config.shop, std::path::Path::new(data_dir), dry_run, options.quiet)?);
+        return Ok(());
+    }
+
+    if let Command::FeedPush { names, due } = command {
+        let data_dir = std::path::Path::new(data_dir);
+        let (output, failed) = feed_push(&service, &config.feeds, data_dir, &names, due, Utc::now(), options.quiet)?;
+        if failed {
+            return Err(output);
+        }
+        println!("{}", output);
+        return Ok(());
+    }
+
+    // Execute command and print result
+    dispatch.begin(&service);
+    match execute_command_with_mode(command, &mut service, mode) {
+        Ok(output) => {
+            service.flush().map_err(format_error)?;
+            if !output.is_empty() {
+                println!("{}", output);
+            }
+            if let Some(path) = &started_notice {
+                if let Err(e) = std::fs::remove_file(path) {
+                    log::warn!("Failed to remove the received notice {}: {}", path.display(), e);
+                }
+            }
+            for message in dispatch.finish(&mut service) {
+                eprintln!("{}", trf(Msg::Warning, &[("message", &message)]));
+            }
+            Ok(())
+        }
+        Err(err) => {
+            log::warn!("Command failed: {}", err);
+            for message in dispatch.finish_failed(&mut service) {
+                eprintln!("{}", trf(Msg::Warning, &[("message", &message)]));
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Check every stored product and transaction before the inventory is opened
+fn strict_load_check(storage: &JsonStorage, tolerance: chrono::Duration) -> Result<(), String> {
+    let failed = |e: StorageError| format!("Failed to initialize inventory service: {}", e);
+    let products = storage.load_products().map_err(failed)?;
+    let transactions = storage.load_transactions().map_err(failed)?;
+    let problems = schema::check(&products, &transactions, Utc::now(), tolerance);
+    if problems.is_empty() {
+        return Ok(());
+    }
+    let mut output = vec![trf(Msg::ErrStrictLoad, &[("count", &problems.len())])];
+    output.extend(problems.iter().map(|problem| format!("  - {}", problem)));
+    Err(output.join("\n"))
+}
+
+/// Push every mapped product's stock level to the store, or with `dry_run`
+/// list the requests that would be sent
+fn shop_sync(
+    service: &InventoryService,
+    config: &shop::ShopConfig,
+    data_dir: &std::path::Path,
+    dry_run: bool,
+    quiet: bool,
+) -> Result<String, String> {
+    let Some(platform) = config.platform.filter(|_| config.is_configured()) else {
+        return Err(tr(Msg::ErrShopNotConfigured).to_string());
+    };
+    let quantities = available_quantities(service);
+    let pushes = shop::pushes(&shop::load_mapping(config, data_dir)?, &BTreeMap::new(), &quantities);
+    let unmapped = quantities.len() - pushes.len();
+    let requests = pushes.iter().map(|push| shop::request(config, push)).collect::<Result<Vec<_>, _>>()?;
+    if dry_run {
+        let mut output: Vec<String> = requests.iter().map(ToString::to_string).collect();
+        if !quiet {
+            output.insert(0, trf(Msg::ShopDryRunHeader, &[("count", &requests.len()), ("platform", &platform)]));
+        }
+        return Ok(output.join("\n"));
+    }
+    shop::send(config, &requests)?;
+    if quiet {
+        return Ok(requests.len().to_string());
+    }
+    let url = config.url.as_deref().unwrap_or_default();
+    let mut output = trf(Msg::ShopSynced, &[("count", &requests.len()), ("platform", &platform), ("url", &url)]);
+    if unmapped > 0 {
+        output.push_str(&trf(Msg::ShopUnmapped, &[("count", &unmapped)]));
+    }
+    Ok(output)
+}
+
+/// Stage new notices from the maildir, then list every staged notice
+fn receive_inbox(
+    config: &inbox::InboxConfig,
+    maildir: Option<&str>,
+    data_dir: &std::path::Path,
+    quiet: bool,
+) -> Result<String, String> {
+    let maildir = maildir.or(config.maildir.as_deref()).ok_or_else(|| tr(Msg::ErrNoMaildir).to_string())?;
+    if config.suppliers.is_empty() {
+        return Err(tr(Msg::ErrNoInboxSuppliers).to_string());
+    }
+    let fetched = inbox::fetch(config, std::path::Path::new(maildir), data_dir).map_err(|e| format!("Error: {}", e))?;
+    let pending = inbox::pending(data_dir)?;
+    if quiet {
+        let lines: Vec<String> = pending.iter().map(|notice| format!("{}\t{}", notice.reference, notice.lines)).collect();
+        return Ok(lines.join("\n"));
+    }
+
+    let mut output = Vec::new();
+    for outcome in &fetched {
+        output.push(match outcome {
+            inbox::Fetched::Staged(notice) => trf(
+                Msg::NoticeStaged,
+                &[("reference", &notice.reference), ("supplier", &notice.supplier), ("count", &notice.lines.len())],
+            ),
+            inbox::Fetched::Rejected { message, error } => trf(Msg::NoticeRejected, &[("message", message), ("error", error)]),
+        });
+    }
+    if pending.is_empty() {
+        output.push(tr(Msg::NoNotices).to_string());
+    } else {
+        output.push(trf(Msg::NoticesHeader, &[("count", &pending.len())]));
+        for notice in &pending {
+            output.push(trf(
+                Msg::NoticeLine,
+                &[("reference", &notice.reference), ("summary", &notice.summary), ("count", &notice.lines)],
+            ));
+        }
+        output.push(tr(Msg::NoticesHint).to_string());
+    }
+    Ok(output.join("\n"))
+}
+
+/// Render and upload the selected feeds, recording each outcome in the
+/// feed state; the flag is set when any push failed
+fn feed_push(
+    service: &InventoryService,
+    feeds: &BTreeMap<String, feed::FeedConfig>,
+    data_dir: &std::path::Path,
+    names: &[String],
+    due: bool,
+    now: DateTime<Utc>,
+    quiet: bool,
+) -> Result<(String, bool), String> {
+    if feeds.is_empty() {
+        return Err(tr(Msg::ErrNoFeeds).to_string());
+    }
+    if let Some(unknown) = names.iter().find(|name| !feeds.contains_key(*name)) {
+        return Err(trf(Msg::ErrFeedNotFound, &[("name", unknown)]));
+    }
+    let mut state = feed::load_state(data_dir)?;
+    let selected: Vec<(&String, &feed::FeedConfig)> = feeds
+        .iter()
+        .filter(|(name, config)| {
+            if !names.is_empty() {
+                names.contains(name)
+            } else if due {
+                config.is_due(state.get(*name).unwrap_or(&feed::FeedState::default()), now)
+            } else {
+                true
+            }
+        })
+        .collect();
+    if selected.is_empty() {
+        return Ok((if quiet { String::new() } else { tr(Msg::FeedNoneDue).to_string() }, false));
+    }
+
+    let mut products = service.list_products();
+    products.sort_by(|a, b| a.sku.cmp(&b.sku));
+    let mut output = Vec::new();
+    let mut failed = false;
+    for (name, config) in selected {
+        let result = edi::render(config.format, &products, config.envelope(now).as_ref(), now)
+            .and_then(|contents| feed::deliver(name, config, &contents, now));
+        let entry = state.entry(name.clone()).or_default();
+        match result {
+            Ok(location) => {
+                log::info!("Pushed feed '{}' to {}", name, location);
+                entry.last_success = Some(now);
+                entry.last_location = Some(location.clone());
+                output.push(if quiet {
+                    format!("{}\t{}", name, location)
+                } else {
+                    trf(Msg::FeedPushed, &[("name", name), ("location", &location)])
+                });
+            }
+            Err(error) => {
+                log::warn!("Feed '{}' failed: {}", name, error);
+                entry.last_failure = Some(now);
+                entry.last_error = Some(error.clone());
+                output.push(trf(Msg::FeedFailed, &[("name", name), ("error", &error)]));
+                failed = true;
+            }
+        }
+    }
+    feed::save_state(data_dir, &state)?;
+    Ok((output.join("\n"), failed))
+}
+
+/// Each configured feed with the outcome of its last pushes
+fn feed_status(feeds: &BTreeMap<String, feed::FeedConfig>, state: &BTreeMap<String, feed::FeedState>, quiet: bool) -> String {
+    if feeds.is_empty() {
+        return if quiet { String::new() } else { tr(Msg::NoFeeds).to_string() };
+    }
+    let time = |at: Option<DateTime<Utc>>| at.map_or_else(|| tr(Msg::FeedNever).to_string(), |at| at.format("%Y-%m-%d %H:%M").to_string());
+    let mut lines = Vec::new();
+    for (name, config) in feeds {
+        let status = state.get(name).cloned().unwrap_or_default();
+        if quiet {
+            let stamp = |at: Option<DateTime<Utc>>| at.map(|at| at.to_rfc3339()).unwrap_or_default();
+            lines.push(format!("{}\t{}\t{}", name, stamp(status.last_success), stamp(status.last_failure)));
+            continue;
+        }
+        let schedule = config
+            .interval_minutes
+            .map(|minutes| trf(Msg::FeedSchedule, &[("minutes", &minutes)]))
+            .unwrap_or_default();
+        let mut line = trf(
+            Msg::FeedStatusLine,
+            &[
+                ("name", name),
+                ("format", &config.format.name()),
+                ("destination", &config.destination),
+                ("schedule", &schedule),
+                ("success", &time(status.last_success)),
+            ],
+        );
+        if let Some(error) = status.last_error.filter(|_| status.last_failure > status.last_success) {
+            line.push_str(&trf(Msg::FeedStatusFailure, &[("failure", &time(status.last_failure)), ("error", &error)]));
+        }
+        lines.push(line);
+    }
+    lines.join("\n")
+}
+
+/// Copy the data directory to another backend, verifying the copy
+fn convert_storage(data_dir: &str, target: &str, from: &str, to: &str, quiet: bool) -> Result<String, String> {
+    let same_dir = match (std::fs::canonicalize(target), std::fs::canonicalize(data_dir)) {
+        (Ok(target), Ok(data_dir)) => target == data_dir,
+        _ => false,
+    };
+    if same_dir {
+        return Err(tr(Msg::ErrConvertSameDir).to_string());
+    }
+    let source = convert::open_backend(from, data_dir)?;
+    let destination = convert::open_backend(to, target)?;
+    let summaries = convert::convert(source.as_ref(), destination.as_ref())
+        .map_err(|e| trf(Msg::ErrConvert, &[("error", &e)]))?;
+    if quiet {
+        return Ok(target.to_string());
+    }
+    let mut output = vec![trf(Msg::ConvertHeader, &[("from", &from), ("to", &to), ("dir", &target)])];
+    for summary in summaries.iter().filter(|summary| summary.count > 0) {
+        output.push(trf(Msg::ConvertLine, &[
+            ("kind", &summary.kind),
+            ("count", &summary.count),
+            ("checksum", &&summary.checksum[..16]),
+        ]));
+    }
+    Ok(output.join("\n"))
+}
+
+/// Passes on what a command did: the alert rules it set off, its events to
+/// the events.ndjson outbox, and its stock changes to hooks, MQTT, and the
+/// shop
+///
+/// `begin` records the state before a command and `finish` compares the state
+/// after it. They go around every command, and around each line of a kiosk
+/// session, so scans reach the same consumers as other commands.
+pub struct Dispatch<'a> {
+    data_dir: &'a std::path::Path,
+    hooks: &'a hooks::HooksConfig,
+    mqtt: &'a mqtt::MqttConfig,
+    shop: &'a shop::ShopConfig,
+    rule_matches: std::collections::BTreeSet<(String, String)>,
+    quantities: BTreeMap<String, u32>,
+    products: BTreeMap<String, api::v1::Product>,
+}
+
+impl<'a> Dispatch<'a> {
+    pub fn new(
+        data_dir: &'a std::path::Path,
+        hooks: &'a hooks::HooksConfig,
+        mqtt: &'a mqtt::MqttConfig,
+        shop: &'a shop::ShopConfig,
+    ) -> Self {
+        Dispatch {
+            data_dir,
+            hooks,
+            mqtt,
+            shop,
+            rule_matches: Default::default(),
+            quantities: BTreeMap::new(),
+            products: BTreeMap::new(),
+        }
+    }
+
+    /// Whether stock levels go to an MQTT broker or a shop
+    fn publishing(&self) -> bool {
+        self.mqtt.broker_address().is_some() || self.shop.is_configured()
+    }
+
+    /// Record the state a command starts from
+    pub fn begin(&mut self, service: &InventoryService) {
+        self.rule_matches = service.rule_matches();
+        if self.publishing() {
+            self.quantities = available_quantities(service);
+        }
+        if !self.hooks.is_empty() {
+            self.products = product_snapshot(service);
+        }
+    }
+
+    /// Raise rule events for the command since `begin`, then pass its events
+    /// and changes on, returning the events and any delivery failures as
+    /// warnings to show
+    pub fn finish(&mut self, service: &mut InventoryService) -> Vec<String> {
+        service.raise_rule_events(&self.rule_matches);
+        let events = service.take_events();
+        let mut warnings: Vec<String> = events.iter().filter(|event| event.is_warning()).map(ToString::to_string).collect();
+        if let Err(message) = outbox::append(self.data_dir, &events) {
+            log::warn!("{}", message);
+            warnings.push(message);
+        }
+        if !self.hooks.is_empty() {
+            let calls = hooks::calls(&self.products, &product_snapshot(service));
+            // hooks::run logs its own failures
+            warnings.extend(hooks::run(self.hooks, self.data_dir, &calls));
+        }
+        if self.publishing() {
+            let after = available_quantities(service);
+            if self.mqtt.broker_address().is_some() {
+                let prefix = self.mqtt.topic_prefix.as_deref().unwrap_or(mqtt::DEFAULT_TOPIC_PREFIX);
+                let messages = mqtt::messages(prefix, &self.quantities, &after, &events);
+                if let Err(message) = mqtt::publish(self.mqtt, &messages) {
+                    log::warn!("{}", message);
+                    warnings.push(message);
+                }
+            }
+            if self.shop.is_configured() {
+                if let Err(message) = shop::push_changes(self.shop, self.data_dir, &self.quantities, &after) {
+                    log::warn!("{}", message);
+                    warnings.push(message);
+                }
+            }
+        }
+        warnings
+    }
+
+    /// Like `finish`, for a command that failed: what it saved before the
+    /// failure, such as the checkpointed chunks of an import, is still
+    /// passed on
+    ///
+    /// Pending changes are saved first, as dropping the service would save
+    /// them anyway. If that fails, the stock in memory isn't what was saved,
+    /// so only the events of saved changes go to the outbox.
+    pub fn finish_failed(&mut self, service: &mut InventoryService) -> Vec<String> {
+        if let Err(e) = service.flush() {
+            log::warn!("Failed to save changes of the failed command: {}", e);
+            let events = service.take_events();
+            let mut warnings: Vec<String> = events.iter().filter(|event| event.is_warning()).map(ToString::to_string).collect();
+            if let Err(message) = outbox::append(self.data_dir, &events) {
+                log::warn!("{}", message);
+                warnings.push(message);
+            }
+            return warnings;
+        }
+        self.finish(service)
+    }
+}
+
+/// Available quantity of every product, by SKU
+fn available_quantities(service: &InventoryService) -> BTreeMap<String, u32> {
+    service.list_products().into_iter().map(|product| (product.sku.clone(), product.quantity)).collect()
+}
+
+/// Plugin executable for the command in `args`, unless the name is taken by
+/// a built-in command, group, or alias
+fn find_plugin(args: &[String], aliases: &BTreeMap<String, String>) -> Option<std::path::PathBuf> {
+    let name = args.get(1)?;
+    if help::is_builtin(name) || aliases.contains_key(name) {
+        return None;
+    }
+    plugins::find(name, &env::var_os("PATH")?)
+}
+
+/// Help section listing installed plugins; empty when there are none
+fn plugin_help(installed: &BTreeMap<String, std::path::PathBuf>) -> String {
+    if installed.is_empty() {
+        return String::new();
+    }
+    let width = installed.keys().map(|name| name.chars().count()).max().unwrap_or(0);
+    let mut help = format!("\n\n{}", tr(Msg::HelpPlugins));
+    for (name, path) in installed {
+        help.push_str(&format!("\n    {:width$}  {}", name, path.display(), width = width));
+    }
+    help
+}
+
+/// Every product in the API shape, keyed by SKU, for working out which hooks fire
+fn product_snapshot(service: &InventoryService) -> BTreeMap<String, api::v1::Product> {
+    service.list_products().into_iter().map(|product| (product.sku.clone(), api::v1::Product::from(product))).collect()
+}
+
+/// Save, list, or delete views in the configuration file
+fn manage_views(
+    command: Command,
+    data_dir: &str,
+    views: &BTreeMap<String, ProductFilter>,
+    quiet: bool,
+) -> Result<String, String> {
+    let save = |name: &str, view: Option<&ProductFilter>| {
+        Config::save_view(data_dir, name, view).map_err(|e| format!("Failed to save configuration: {}", e))
+    };
+    match command {
+        Command::SaveView { name, filter } => {
+            save(&name, Some(&filter))?;
+            Ok(if quiet { String::new() } else { trf(Msg::ViewSaved, &[("name", &name)]) })
+        }
+        Command::DeleteView { name } => {
+            if !views.contains_key(&name) {
+                return Err(trf(Msg::ErrViewNotFound, &[("name", &name)]));
+            }
+            save(&name, None)?;
+            Ok(if quiet { String::new() } else { trf(Msg::ViewDeleted, &[("name", &name)]) })
+        }
+        Command::ListViews if quiet => Ok(views.keys().cloned().collect::<Vec<_>>().join("\n")),
+        Command::ListViews if views.is_empty() => Ok(tr(Msg::NoViews).to_string()),
+        Command::ListViews => {
+            let mut output = trf(Msg::ViewsHeader, &[("count", &views.len())]);
+            for (name, filter) in views {
+                output.push_str(&format!("\n  {}: {}", name, filter_options(filter)));
+            }
+            Ok(output)
+        }
+        _ => unreachable!("not a view command"),
+    }
+}
+
+/// Turn an add-product --from-template command into a plain add-product,
+/// filling in what wasn't given from the template
+fn from_template(command: Command, templates: &BTreeMap<String, ProductTemplate>) -> Result<Command, String> {
+    let Command::AddProductFromTemplate {
+        template, sku, name, description, quantity, reorder_point, category, supplier, location, unit_cost,
+    } = command else {
+        unreachable!("not an add-product --from-template command")
+    };
+    let template = templates.get(&template).ok_or_else(|| trf(Msg::ErrTemplateNotFound, &[("name", &template)]))?;
+    Ok(Command::AddProduct {
+        sku: template.sku(&sku),
+        name: template.name(&name),
+        description: description.or_else(|| template.description.clone()).unwrap_or_default(),
+        quantity,
+        reorder_point: reorder_point.or(template.reorder_point).unwrap_or(0),
+        category: category.or_else(|| template.category.clone()),
+        supplier: supplier.or_else(|| template.supplier.clone()),
+        location: location.or_else(|| template.location.clone()),
+        unit_cost: unit_cost.or(template.unit_cost),
+    })
+}
+
+/// Save, list, or delete product templates in config.json
+fn manage_product_templates(
+    command: Command,
+    data_dir: &str,
+    templates: &BTreeMap<String, ProductTemplate>,
+    quiet: bool,
+) -> Result<String, String> {
+    let save = |name: &str, template: Option<&ProductTemplate>| {
+        Config::save_product_template(data_dir, name, template).map_err(|e| format!("Failed to save configuration: {}", e))
+    };
+    match command {
+        Command::SaveProductTemplate { name, template } => {
+            save(&name, Some(&template))?;
+            Ok(if quiet { String::new() } else { trf(Msg::TemplateSaved, &[("name", &name)]) })
+        }
+        Command::DeleteProductTemplate { name } => {
+            if !templates.contains_key(&name) {
+                return Err(trf(Msg::ErrTemplateNotFound, &[("name", &name)]));
+            }
+            save(&name, None)?;
+            Ok(if quiet { String::new() } else { trf(Msg::TemplateDeleted, &[("name", &name)]) })
+        }
+        Command::ListProductTemplates if quiet => Ok(templates.keys().cloned().collect::<Vec<_>>().join("\n")),
+        Command::ListProductTemplates if templates.is_empty() => Ok(tr(Msg::NoTemplates).to_string()),
+        Command::ListProductTemplates => {
+            let mut output = trf(Msg::TemplatesHeader, &[("count", &templates.len())]);
+            for (name, template) in templates {
+                output.push_str(&format!("\n  {}: {}", name, template_options(template)));
+            }
+            Ok(output)
+        }
+        _ => unreachable!("not a product template command"),
+    }
+}
+
+/// The template save options that recreate a template
+fn template_options(template: &ProductTemplate) -> String {
+    let values = [
+        ("--sku-prefix", template.sku_prefix.clone()),
+        ("--name-pattern", template.name_pattern.clone()),
+        ("--description", template.description.clone()),
+        ("--reorder-point", template.reorder_point.map(|n| n.to_string())),
+        ("--category", template.category.clone()),
+        ("--supplier", template.supplier.clone()),
+        ("--location", template.location.clone()),
+        ("--unit-cost", template.unit_cost.map(|cost| cost.to_string())),
+    ];
+    values
+        .into_iter()
+        .filter_map(|(option, value)| value.map(|value| format!("{} \"{}\"", option, value)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The bulk-update --set arguments that make a set of changes
+fn update_options(changes: &ProductUpdate) -> String {
+    let cleared = |value: Option<String>| value.unwrap_or_default();
+    let values = [
+        ("description", changes.description.clone()),
+        ("reorder-point", changes.reorder_point.map(|n| n.to_string())),
+        ("category", changes.category.clone()),
+        ("supplier", changes.supplier.clone()),
+        ("location", changes.location.clone()),
+        ("large-threshold", changes.large_movement_threshold.map(|t| cleared(t.map(|t| t.to_string())))),
+        ("unit-cost", changes.unit_cost.map(|c| cleared(c.map(|c| c.to_string())))),
+        ("tare-weight", changes.tare_weight.map(|w| cleared(w.map(|w| w.to_string())))),
+        ("unit-weight", changes.unit_weight.map(|w| cleared(w.map(|w| w.to_string())))),
+        ("lead-time", changes.lead_time_days.map(|d| cleared(d.map(|d| d.to_string())))),
+    ];
+    values
+        .into_iter()
+        .filter_map(|(field, value)| value.map(|value| format!("--set \"{}={}\"", field, value)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// The list-products options that select a filter
+fn filter_options(filter: &ProductFilter) -> String {
+    let mut options = Vec::new();
+    let values = [
+        ("--sku-prefix", filter.sku_prefix.clone()),
+        ("--category", filter.category.clone()),
+        ("--supplier", filter.supplier.clone()),
+        ("--condition", filter.condition.map(|c| c.to_string())),
+    ];
+    for (option, value) in values {
+        if let Some(value) = value {
+            options.push(format!("{} {}", option, value));
+        }
+    }
+    if filter.below_reorder {
+        options.push("--below-reorder".to_string());
+    }
+    if filter.sort != ProductSort::default() {
+        options.push(format!("--sort {}", filter.sort));
+    }
+    options.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(s: &str) -> Vec<String> {
+        s.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse_help() {
+        assert_eq!(parse_args(&args("prog help")).unwrap(), Command::Help);
+        assert_eq!(parse_args(&args("prog --help")).unwrap(), Command::Help);
+        assert_eq!(parse_args(&args("prog -h")).unwrap(), Command::Help);
+        assert_eq!(parse_args(&args("prog")).unwrap(), Command::Help);
+    }
+
+    #[test]
+    fn test_parse_add_product() {
+        let result = parse_args(&args("prog add-product SKU001 Widget Description 100 20")).unwrap();
+        assert_eq!(result, Command::AddProduct {
+            sku: "SKU001".to_string(),
+            name: "Widget".to_string(),
+            description: "Description".to_string(),
+            quantity: 100,
+            reorder_point: 20,
+            category: None,
+            supplier: None,
+            location: None,
+            unit_cost: None,
+        });
+    }
+
+    #[test]
+    fn test_parse_add_product_with_classification() {
+        let result = parse_args(&args(
+            "prog add-product SKU001 Widget Description 100 20 --supplier Acme --location A1 --unit-cost 4.5",
+        )).unwrap();
+        assert_eq!(result, Command::AddProduct {
+            sku: "SKU001".to_string(),
+            name: "Widget".to_string(),
+            description: "Description".to_string(),
+            quantity: 100,
+            reorder_point: 20,
+            category: None,
+            supplier: Some("Acme".to_string()),
+            location: Some("A1".to_string()),
+            unit_cost: Some(Money::from_cents(450)),
+        });
+    }
+
+    #[test]
+    fn test_parse_add_product_missing_args() {
+        let result = parse_args(&args("prog add-product SKU001 Widget"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_add_product_interactive() {
+        let result = parse_args(&args("prog add-product --interactive")).unwrap();
+        assert_eq!(result, Command::AddProductInteractive);
+
+        let result = parse_args(&args("prog add-product --interactive SKU001"));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_update_product() {
+        let result = parse_args(&args("prog update-product SKU001 --name NewName --reorder-point 30")).unwrap();
+        assert_eq!(result, Command::UpdateProduct {
+            sku: "SKU001".to_string(),
+            name: Some("NewName".to_string()),
+            description: None,
+            reorder_point: Some(30),
+            category: None,
+            supplier: None,
+            location: None,
+            barcode: None,
+            large_threshold: None,
+            components: None,
+            unit_cost: None,
+            tare_weight: None,
+            unit_weight: None,
+            lead_time: None,
+        });
+    }
+
+    #[test]
+    fn test_parse_components_and_check_buildable() {
+        let result = parse_args(&args("prog update-product KIT --components BOLT:4,NUT:4")).unwrap();
+        let Command::UpdateProduct { components: Some(components), .. } = result else {
+            panic!("expected components");
+        };
+        assert_eq!(components, vec![
+            Component { sku: "BOLT".to_string(), quantity: 4 },
+            Component { sku: "NUT".to_string(), quantity: 4 },
+        ]);
+        assert!(matches!(
+            parse_args(&args("prog update-product KIT --components=")).unwrap(),
+            Command::UpdateProduct { components: Some(ref c), .. } if c.is_empty()
+        ));
+        assert!(parse_args(&args("prog update-product KIT --components BOLT")).is_err());
+        assert_eq!(
+            parse_args(&args("prog product buildable KIT")).unwrap(),
+            Command::CheckBuildable { sku: "KIT".to_string() }
+        );
+    }
+
+    #[test]
+    fn test_parse_add_stock() {
+        let result = parse_args(&args("prog add-stock SKU001 50")).unwrap();
+        assert_eq!(result, Command::AddStock {
+            sku: "SKU001".to_string(),
+            quantity: 50,
+            notes: None,
+            condition: None,
+            unit_cost: None,
+        });
+    }
+
+    #[test]
+    fn test_parse_add_stock_with_notes() {
+        let result = parse_args(&args("prog add-stock SKU001 50 --notes Shipment")).unwrap();
+        assert_eq!(result, Command::AddStock {
+            sku: "SKU001".to_string(),
+            quantity: 50,
+            notes: Some("Shipment".to_string()),
+            condition: None,
+            unit_cost: None,
+        });
+    }
+
+    #[test]
+    fn test_parse_remove_stock() {
+        let result = parse_args(&args("prog remove-stock SKU001 10")).unwrap();
+        assert_eq!(result, Command::RemoveStock {
+            sku: "SKU001".to_string(),
+            quantity: 10,
+            notes: None,
+            confirm_large: false,
+            transaction_type: TransactionType::Removal,
+            reason: None,
+            condition: None,
+            backorder: false,
+            customer: None,
+        });
+    }
+
+    #[test]
+    fn test_parse_options_anywhere() {
+        let result = parse_args(&args("prog add-stock --notes=Shipment SKU001 50")).unwrap();
+        assert_eq!(result, Command::AddStock {
+            sku: "SKU001".to_string(),
+            quantity: 50,
+            notes: Some("Shipment".to_string()),
+            condition: None,
+            unit_cost: None,
+        });
+    }
+
+    #[test]
+    fn test_parse_repeated_notes_are_combined() {
+        let result = parse_args(&args("prog remove-stock SKU001 5 --notes Damaged --notes Returned")).unwrap();
+        assert!(matches!(result, Command::RemoveStock { notes: Some(n), .. } if n == "Damaged; Returned"));
+    }
+
+    #[test]
+    fn test_parse_double_dash_allows_dash_prefixed_values() {
+        let result = parse_args(&args("prog view-product -- --odd-sku")).unwrap();
+        assert_eq!(result, Command::ViewProduct { sku: "--odd-sku".to_string(), template: None });
+    }
+
+    #[test]
+    fn test_parse_rejects_extra_arguments() {
+        let result = parse_args(&args("prog view-product SKU001 SKU002"));
+        assert!(result.unwrap_err().contains("unexpected argument 'SKU002'"));
+    }
+
+    #[test]
+    fn test_parse_remove_stock_confirm_large() {
+        let result = parse_args(&args("prog remove-stock SKU001 1000 --confirm-large --notes Recount")).unwrap();
+        assert_eq!(result, Command::RemoveStock {
+            sku: "SKU001".to_string(),
+            quantity: 1000,
+            notes: Some("Recount".to_string()),
+            confirm_large: true,
+            transaction_type: TransactionType::Removal,
+            reason: None,
+            condition: None,
+            backorder: false,
+            customer: None,
+        });
+    }
+
+    #[test]
+    fn test_parse_update_product_large_threshold() {
+        let result = parse_args(&args("prog update-product SKU001 --large-threshold 25%")).unwrap();
+        assert!(matches!(result, Command::UpdateProduct {
+            large_threshold: Some(Some(MovementThreshold::Percent(25))),
+            ..
+        }));
+    }
+
+    #[test]
+    fn test_parse_view_product() {
+        let result = parse_args(&args("prog view-product SKU001")).unwrap();
+        assert_eq!(result, Command::ViewProduct { sku: "SKU001".to_string(), template: None });
+    }
+
+    #[test]
+    fn test_parse_list_products() {
+        let result = parse_args(&args("prog list-products")).unwrap();
+        assert_eq!(result, Command::ListProducts { filter: ProductFilter::default(), template: None, columns: Vec::new(), totals: false, group_by: None });
+    }
+
+    #[test]
+    fn test_parse_low_stock() {
+        let result = parse_args(&args("prog low-stock")).unwrap();
+        assert_eq!(result, Command::LowStock { group_by: None, template: None });
+    }
+
+    #[test]
+    fn test_parse_low_stock_group_by() {
+        let result = parse_args(&args("prog low-stock --group-by supplier")).unwrap();
+        assert_eq!(result, Command::LowStock { group_by: Some(GroupBy::Supplier), template: None });
+
+        let result = parse_args(&args("prog low-stock --group-by colour"));
+        assert!(result.unwrap_err().contains("Invalid group"));
+    }
+
+    #[test]
+    fn test_parse_history() {
+        let result = parse_args(&args("prog history SKU001")).unwrap();
+        assert_eq!(result, Command::History {
+            sku: "SKU001".to_string(),
+            start: None,
+            end: None,
+            include_archived: false,
+            customer: None,
+            template: None,
+        });
+        assert!(matches!(
+            parse_args(&args("prog history SKU001 --include-archived")).unwrap(),
+            Command::History { include_archived: true, .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_history_compare() {
+        assert_eq!(
+            parse_args(&args("prog history-compare SKU001 SKU002 --merge")).unwrap(),
+            Command::HistoryCompare {
+                skus: vec!["SKU001".to_string(), "SKU002".to_string()],
+                start: None,
+                end: None,
+                merge: true,
+            }
+        );
+        assert!(parse_args(&args("prog history-compare SKU001")).is_err());
+        assert!(parse_args(&args("prog history-compare SKU001 SKU002 SKU001")).is_err());
+    }
+
+    #[test]
+    fn test_parse_quarantine() {
+        assert_eq!(
+            parse_args(&args("prog stock quarantine SKU001 5 --notes QC")).unwrap(),
+            Command::Quarantine { sku: "SKU001".to_string(), quantity: 5, notes: Some("QC".to_string()), release: false }
+        );
+        assert!(matches!(
+            parse_args(&args("prog release-quarantine SKU001 5")).unwrap(),
+            Command::Quarantine { release: true, .. }
+        ));
+        assert!(parse_args(&args("prog quarantine SKU001")).is_err());
+        assert!(parse_args(&args("prog remove-stock SKU001 1 --type quarantine")).is_err());
+    }
+
+    #[test]
+    fn test_parse_conditions() {
+        assert_eq!(
+            parse_args(&args("prog stock condition SKU001 2 --to damaged")).unwrap(),
+            Command::ChangeCondition {
+                sku: "SKU001".to_string(),
+                quantity: 2,
+                from: Condition::New,
+                to: Condition::Damaged,
+                notes: None,
+            }
+        );
+        assert!(parse_args(&args("prog change-condition SKU001 2")).unwrap_err().contains("--to"));
+        assert!(parse_args(&args("prog change-condition SKU001 2 --to used")).unwrap_err().contains("Invalid condition"));
+        assert!(matches!(
+            parse_args(&args("prog add-stock SKU001 3 --condition refurbished")).unwrap(),
+            Command::AddStock { condition: Some(Condition::Refurbished), .. }
+        ));
+        assert!(parse_args(&args("prog remove-stock --from-stdin --condition damaged")).is_err());
+        assert_eq!(
+            parse_args(&args("prog view save low-widgets --category widgets --below-reorder --sort quantity")).unwrap(),
+            Command::SaveView {
+                name: "low-widgets".to_string(),
+                filter: ProductFilter {
+                    category: Some("widgets".to_string()),
+                    below_reorder: true,
+                    sort: ProductSort::Quantity,
+                    ..Default::default()
+                },
+            }
+        );
+        assert!(parse_args(&args("prog view run low-widgets --below-reorder")).is_err());
+        assert!(matches!(
+            parse_args(&args("prog ls --sku-prefix WID*")).unwrap(),
+            Command::ListProducts { filter: ProductFilter { sku_prefix: Some(prefix), .. }, .. } if prefix == "WID*"
+        ));
+        assert_eq!(
+            parse_args(&args("prog ls --condition damaged")).unwrap(),
+            Command::ListProducts {
+                filter: ProductFilter { condition: Some(Condition::Damaged), ..Default::default() },
+                template: None,
+                columns: Vec::new(),
+                totals: false,
+                group_by: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_receive() {
+        assert_eq!(
+            parse_args(&args("prog receive start PO-17 --expected po.csv")).unwrap(),
+            Command::ReceiveStart { reference: Some("PO-17".to_string()), expected_file: Some("po.csv".to_string()) }
+        );
+        assert_eq!(
+            parse_args(&args("prog receive-start")).unwrap(),
+            Command::ReceiveStart { reference: None, expected_file: None }
+        );
+        assert_eq!(
+            parse_args(&args("prog receive inbox --start PO-17")).unwrap(),
+            Command::ReceiveInbox { maildir: None, start: Some("PO-17".to_string()) }
+        );
+        assert!(parse_args(&args("prog receive inbox Maildir --start PO-17")).is_err());
+        assert_eq!(
+            parse_args(&args("prog receive add SKU001 12 --notes damaged-box")).unwrap(),
+            Command::ReceiveAdd { sku: "SKU001".to_string(), quantity: 12, notes: Some("damaged-box".to_string()) }
+        );
+        assert!(parse_args(&args("prog receive add SKU001")).is_err());
+        assert_eq!(parse_args(&args("prog receive review")).unwrap(), Command::ReceiveReview);
+        assert_eq!(parse_args(&args("prog receive commit")).unwrap(), Command::ReceiveCommit);
+        assert_eq!(parse_args(&args("prog receive cancel")).unwrap(), Command::ReceiveCancel);
+    }
+
+    #[test]
+    fn test_parse_export() {
+        assert_eq!(
+            parse_args(&args("prog export shared --anonymize")).unwrap(),
+            Command::Export {
+                dir: "shared".to_string(),
+                anonymize: true,
+                sign: None,
+                format: ExportFormat::Dataset,
+                since: None,
+            }
+        );
+        assert_eq!(
+            parse_args(&args("prog export changes-0042 --format cdc --since 120")).unwrap(),
+            Command::Export {
+                dir: "changes-0042".to_string(),
+                anonymize: false,
+                sign: None,
+                format: ExportFormat::Cdc,
+                since: Some(120),
+            }
+        );
+        assert!(parse_args(&args("prog export shared --format cdc --anonymize")).is_err());
+        assert!(parse_args(&args("prog export shared --since 120")).is_err());
+        assert!(parse_args(&args("prog data export")).is_err());
+    }
+
+    #[test]
+    fn test_parse_archive_transactions() {
+        let Command::ArchiveTransactions { before } =
+            parse_args(&args("prog data archive --before 2024-01-01")).unwrap()
+        else {
+            panic!("expected archive-transactions");
+        };
+        assert_eq!(before.to_rfc3339(), "2024-01-01T00:00:00+00:00");
+        assert!(parse_args(&args("prog archive-transactions")).unwrap_err().contains("--before"));
+    }
+
+    #[test]
+    fn test_parse_delete_product() {
+        let result = parse_args(&args("prog delete-product SKU001")).unwrap();
+        assert_eq!(result, Command::DeleteProduct { sku: "SKU001".to_string(), force: false });
+    }
+
+    #[test]
+    fn test_parse_import_transactions() {
+        assert_eq!(
+            parse_args(&args("prog stock import history.csv")).unwrap(),
+            Command::ImportTransactions { file: "history.csv".to_string(), profile: None }
+        );
+        assert!(parse_args(&args("prog import-transactions")).is_err());
+        assert_eq!(
+            parse_args(&args("prog import-transactions --resume 3f9c2a1b")).unwrap(),
+            Command::ResumeImport { job: "3f9c2a1b".to_string() }
+        );
+        assert!(parse_args(&args("prog import-transactions history.csv --resume 3f9c2a1b")).is_err());
+    }
+
+    #[test]
+    fn test_parse_migrate_opening_balances() {
+        let Command::MigrateOpeningBalances { file, cutover, merge } =
+            parse_args(&args("prog migrate-opening-balances legacy.csv --date 2025-01-01")).unwrap()
+        else {
+            panic!("expected migrate-opening-balances");
+        };
+        assert_eq!(file, "legacy.csv");
+        assert_eq!(cutover.to_rfc3339(), "2025-01-01T00:00:00+00:00");
+        assert_eq!(merge, None);
+        let Command::MigrateOpeningBalances { merge: Some(merge), .. } = parse_args(&args(
+            "prog migrate-opening-balances sync.csv --date 2025-01-01 --merge name=fill-if-empty,quantity=set",
+        ))
+        .unwrap()
+        else {
+            panic!("expected a merge");
+        };
+        assert_eq!(merge, MergePolicy {
+            name: crate::models::FieldMerge::FillIfEmpty,
+            quantity: crate::models::QuantityMerge::Set,
+            ..Default::default()
+        });
+        assert!(parse_args(&args("prog migrate-opening-balances sync.csv --date 2025-01-01 --merge cost=keep")).is_err());
+        assert!(parse_args(&args("prog migrate-opening-balances sync.csv --date 2025-01-01 --merge quantity=fill-if-empty")).is_err());
+        assert!(parse_args(&args("prog migrate-opening-balances legacy.csv")).unwrap_err().contains("--date"));
+        assert!(parse_args(&args("prog migrate-opening-balances legacy.csv --date 01/01/2025")).is_err());
+    }
+
+    #[test]
+    fn test_parse_events_tail() {
+        assert_eq!(parse_args(&args("prog events tail")).unwrap(), Command::EventsTail { since: 0, limit: None });
+        assert_eq!(
+            parse_args(&args("prog events-tail --since 41 --limit 10")).unwrap(),
+            Command::EventsTail { since: 41, limit: Some(10) }
+        );
+        assert!(parse_args(&args("prog events tail --since -1")).is_err());
+        assert!(parse_args(&args("prog events tail --limit 0")).is_err());
+    }
+
+    #[test]
+    fn test_parse_diff() {
+        assert_eq!(
+            parse_args(&args("prog report diff a b")).unwrap(),
+            Command::Diff { snapshots: vec!["a".to_string(), "b".to_string()], since: None, verify: None }
+        );
+        assert!(matches!(
+            parse_args(&args("prog diff --since 2025-02-01")).unwrap(),
+            Command::Diff { since: Some(_), .. }
+        ));
+        assert!(parse_args(&args("prog diff")).unwrap_err().starts_with("Usage: diff"));
+        assert!(parse_args(&args("prog diff a --since 2025-02-01")).is_err());
+    }
+
+    #[test]
+    fn test_parse_removal_type_and_shrinkage() {
+        assert!(matches!(
+            parse_args(&args("prog remove-stock SKU001 2 --type write-off")).unwrap(),
+            Command::RemoveStock { transaction_type: TransactionType::WriteOff, .. }
+        ));
+        assert!(parse_args(&args("prog remove-stock SKU001 2 --type addition")).is_err());
+        assert!(matches!(
+            parse_args(&args("prog out SKU001 2 --reason internal-use")).unwrap(),
+            Command::RemoveStock { reason: Some(ReasonCode::InternalUse), .. }
+        ));
+        assert!(parse_args(&args("prog out SKU001 2 --reason gift")).unwrap_err().contains("sale, internal-use"));
+        assert!(parse_args(&args("prog add-stock SKU001 2 --reason sale")).is_err());
+        assert!(matches!(
+            parse_args(&args("prog stock remove --from-stdin --type damage")).unwrap(),
+            Command::StockFromStdin { transaction_type: TransactionType::Damage, .. }
+        ));
+
+        let Command::Shrinkage { start, end, .. } =
+            parse_args(&args("prog report shrinkage --start 2025-01-01 --end 2025-01-31")).unwrap()
+        else {
+            panic!("expected shrinkage");
+        };
+        assert_eq!(start.unwrap().to_rfc3339(), "2025-01-01T00:00:00+00:00");
+        assert_eq!(end.unwrap().to_rfc3339(), "2025-02-01T00:00:00+00:00");
+    }
+
+    #[test]
+    fn test_parse_mark_ordered() {
+        assert_eq!(
+            parse_args(&args("prog stock order SKU001 50")).unwrap(),
+            Command::MarkOrdered { sku: "SKU001".to_string(), quantity: 50, cancel: false }
+        );
+        assert_eq!(
+            parse_args(&args("prog mark-ordered SKU001 10 --cancel")).unwrap(),
+            Command::MarkOrdered { sku: "SKU001".to_string(), quantity: 10, cancel: true }
+        );
+        assert!(parse_args(&args("prog mark-ordered SKU001 many")).is_err());
+        assert!(parse_args(&args("prog mark-ordered SKU001")).is_err());
+    }
+
+    #[test]
+    fn test_parse_ack() {
+        assert_eq!(
+            parse_args(&args("prog stock ack SKU001 --until 2025-03-31")).unwrap(),
+            Command::Ack { sku: "SKU001".to_string(), until: NaiveDate::from_ymd_opt(2025, 3, 31), clear: false }
+        );
+        assert_eq!(
+            parse_args(&args("prog ack SKU001 --clear")).unwrap(),
+            Command::Ack { sku: "SKU001".to_string(), until: None, clear: true }
+        );
+        assert!(parse_args(&args("prog ack SKU001 --clear --until 2025-03-31")).is_err());
+        assert!(parse_args(&args("prog ack")).is_err());
+    }
+
+    #[test]
+    fn test_parse_suggest_reorder_points() {
+        assert_eq!(
+            parse_args(&args("prog report reorder-points --service-level 99% --lead-time 10 --apply")).unwrap(),
+            Command::SuggestReorderPoints { service_level: 0.99, lead_time: Some(10), days: None, apply: true }
+        );
+        assert_eq!(
+            parse_args(&args("prog suggest-reorder-points --days 30")).unwrap(),
+            Command::SuggestReorderPoints { service_level: 0.95, lead_time: None, days: Some(30), apply: false }
+        );
+        assert!(parse_args(&args("prog suggest-reorder-points --service-level 1")).is_err());
+        assert!(parse_args(&args("prog suggest-reorder-points --days 0")).is_err());
+        assert!(matches!(
+            parse_args(&args("prog update-product SKU001 --lead-time 12")).unwrap(),
+            Command::UpdateProduct { lead_time: Some(Some(12)), .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_kpi_commands() {
+        assert_eq!(parse_args(&args("prog data snapshot-kpis")).unwrap(), Command::SnapshotKpis);
+        assert_eq!(
+            parse_args(&args("prog report kpis --start 2025-01-01 --end 2025-01-31")).unwrap(),
+            Command::Kpis {
+                start: NaiveDate::from_ymd_opt(2025, 1, 1),
+                end: NaiveDate::from_ymd_opt(2025, 2, 1),
             }
-            Ok(output.trim_end().to_string())
-        }
-        
-        Command::DeleteProduct { sku } => {
-            service.delete_product(&sku).map_err(format_error)?;
-            Ok(format!("Product '{}' deleted successfully.", sku))
-        }
-        
-        Command::Help => {
-            Ok(get_help_text())
-        }
+        );
+        assert_eq!(kpi_bar(Money::from_cents(500), 1000), "#".repeat(KPI_BAR_WIDTH / 2));
+        assert_eq!(kpi_bar(Money::from_cents(1), 1000), "#");
     }
-}
 
-/// Format a ServiceError into a user-friendly message
-fn format_error(err: ServiceError) -> String {
-    match err {
-        ServiceError::ProductNotFound(sku) => format!("Error: Product '{}' not found.", sku),
-        ServiceError::DuplicateSKU(sku) => format!("Error: Product with SKU '{}' already exists.", sku),
-        ServiceError::InvalidInput(msg) => format!("Error: {}", msg),
-        ServiceError::InsufficientStock { sku, requested, available } => {
-            format!("Error: Insufficient stock for '{}'. Requested: {}, Available: {}", sku, requested, available)
-        }
-        ServiceError::StorageError(e) => format!("Error: Storage operation failed - {}", e),
+    #[test]
+    fn test_parse_forecast() {
+        assert_eq!(
+            parse_args(&args("prog report forecast --seasonality Weekly --horizon 30")).unwrap(),
+            Command::Forecast { seasonality: Some(Seasonality::Weekly), days: None, horizon: Some(30) }
+        );
+        assert!(parse_args(&args("prog forecast --seasonality yearly")).is_err());
+        assert!(parse_args(&args("prog forecast --horizon 0")).is_err());
     }
-}
 
-/// Get the help text for the CLI
-fn get_help_text() -> String {
-    r#"Stock Control System - Inventory Management CLI
+    #[test]
+    fn test_parse_trash_commands() {
+        assert_eq!(parse_args(&args("prog trash list")).unwrap(), Command::TrashList);
+        assert_eq!(
+            parse_args(&args("prog retention apply --dry-run")).unwrap(),
+            Command::ApplyRetention { dry_run: true }
+        );
+        assert_eq!(
+            parse_args(&args("prog trash restore SKU001")).unwrap(),
+            Command::TrashRestore { sku: "SKU001".to_string() }
+        );
+        assert!(parse_args(&args("prog trash-restore")).is_err());
+    }
 
-USAGE:
-    stock-control <COMMAND> [OPTIONS]
+    #[test]
+    fn test_parse_heal_ids() {
+        assert_eq!(parse_args(&args("prog heal-ids")).unwrap(), Command::HealIds { dry_run: false });
+        assert_eq!(parse_args(&args("prog data heal-ids --dry-run")).unwrap(), Command::HealIds { dry_run: true });
+    }
 
-COMMANDS:
-    add-product <sku> <name> <description> <quantity> <reorder_point>
-        Add a new product to inventory
-        Example: add-product SKU001 "Widget" "A useful widget" 100 20
+    #[test]
+    fn test_parse_compact() {
+        assert_eq!(parse_args(&args("prog compact")).unwrap(), Command::Compact { archive_years: None });
+        assert_eq!(
+            parse_args(&args("prog data compact --archive-older-than 3")).unwrap(),
+            Command::Compact { archive_years: Some(3) }
+        );
+        assert!(parse_args(&args("prog compact --archive-older-than 0")).is_err());
+    }
 
-    update-product <sku> [--name <name>] [--description <desc>] [--reorder-point <n>]
-        Update an existing product's details
-        Example: update-product SKU001 --name "New Widget" --reorder-point 30
+    #[test]
+    fn test_parse_unknown_command() {
+        let result = parse_args(&args("prog unknown-cmd"));
+        assert!(result.is_err());
+        assert!(result.unwrap_err().contains("unrecognized subcommand 'unknown-cmd'"));
+    }
 
-    add-stock <sku> <quantity> [--notes <notes>]
-        Add stock to a product
-        Example: add-stock SKU001 50 --notes "Received shipment"
+    #[test]
+    fn test_parse_unknown_command_suggests_closest() {
+        let result = parse_args(&args("prog add-stok SKU001 5"));
+        assert!(result.unwrap_err().contains("'add-stock'"));
+    }
 
-    remove-stock <sku> <quantity> [--notes <notes>]
-        Remove stock from a product
-        Example: remove-stock SKU001 10 --notes "Sold to customer"
+    #[test]
+    fn test_parse_grouped_commands() {
+        assert_eq!(
+            parse_args(&args("prog stock add SKU001 50")).unwrap(),
+            parse_args(&args("prog add-stock SKU001 50")).unwrap()
+        );
+        assert_eq!(
+            parse_args(&args("prog report low-stock")).unwrap(),
+            Command::LowStock { group_by: None, template: None }
+        );
+        assert_eq!(
+            parse_args(&args("prog product list")).unwrap(),
+            Command::ListProducts { filter: ProductFilter::default(), template: None, columns: Vec::new(), totals: false, group_by: None }
+        );
 
-    view-product <sku>
-        View details of a specific product
-        Example: view-product SKU001
+        let result = parse_args(&args("prog product ad SKU001"));
+        assert!(result.unwrap_err().contains("a similar subcommand exists: 'add'"));
+        assert!(parse_args(&args("prog stock")).unwrap_err().contains("Usage: stock <add|remove|quarantine|release|order|condition|weigh|kiosk|history|receipt|import|ack>"));
+    }
 
-    list-products
-        List all products in inventory
+    #[test]
+    fn test_parse_builtin_aliases() {
+        assert_eq!(parse_args(&args("prog ls")).unwrap(), Command::ListProducts { filter: ProductFilter::default(), template: None, columns: Vec::new(), totals: false, group_by: None });
+        assert_eq!(
+            parse_args(&args("prog rx SKU001 5")).unwrap(),
+            parse_args(&args("prog add-stock SKU001 5")).unwrap()
+        );
+        assert_eq!(
+            parse_args(&args("prog help ls")).unwrap(),
+            Command::CommandHelp { command: "list-products".to_string() }
+        );
+    }
 
-    low-stock
-        List products with stock at or below reorder point
+    #[test]
+    fn test_parse_config_aliases() {
+        let aliases = std::collections::BTreeMap::from([
+            ("lows".to_string(), "low-stock --group-by supplier".to_string()),
+            ("rcv".to_string(), "stock add".to_string()),
+            ("ls".to_string(), "low-stock".to_string()),
+        ]);
 
-    history <sku> [--start <datetime>] [--end <datetime>]
-        View transaction history for a product
-        Datetime format: YYYY-MM-DDTHH:MM:SS
-        Example: history SKU001 --start 2025-01-01T00:00:00 --end 2025-12-31T23:59:59
+        assert_eq!(
+            parse_args_with_aliases(&args("prog lows"), &aliases).unwrap(),
+            Command::LowStock { group_by: Some(GroupBy::Supplier), template: None }
+        );
+        assert_eq!(
+            parse_args_with_aliases(&args("prog rcv SKU001 5 --notes Box"), &aliases).unwrap(),
+            parse_args(&args("prog add-stock SKU001 5 --notes Box")).unwrap()
+        );
+        // Built-in names cannot be overridden
+        assert_eq!(
+            parse_args_with_aliases(&args("prog ls"), &aliases).unwrap(),
+            Command::ListProducts { filter: ProductFilter::default(), template: None, columns: Vec::new(), totals: false, group_by: None }
+        );
+    }
 
-    delete-product <sku>
-        Delete a product and all its transactions
-        Example: delete-product SKU001
+    #[test]
+    fn test_split_global_options() {
+        let (options, rest) = split_global_options(&args("prog -q add-stock SKU001 5"));
+        assert!(options.quiet);
+        assert_eq!(rest, args("prog add-stock SKU001 5"));
 
-    help
-        Show this help message"#.to_string()
-}
+        let (options, rest) = split_global_options(&args("prog ls --quiet"));
+        assert!(options.quiet);
+        assert_eq!(rest, args("prog ls"));
 
+        let (options, rest) = split_global_options(&args("prog -vv ls -v"));
+        assert_eq!(options.verbosity, 3);
+        assert_eq!(rest, args("prog ls"));
 
-/// Run the CLI application
-/// 
-/// # Arguments
-/// * `data_dir` - Directory for storing data files
-/// 
-/// # Returns
-/// * `Ok(())` - Application completed successfully
-/// * `Err(String)` - Error message
-pub fn run(data_dir: &str) -> Result<(), String> {
-    let args: Vec<String> = env::args().collect();
-    run_with_args(&args, data_dir)
-}
+        let (options, rest) = split_global_options(&args("prog --strict-load ls"));
+        assert!(options.strict_load);
+        assert_eq!(rest, args("prog ls"));
 
-/// Run the CLI application with provided arguments
-/// 
-/// # Arguments
-/// * `args` - Command-line arguments
-/// * `data_dir` - Directory for storing data files
-/// 
-/// # Returns
-/// * `Ok(())` - Application completed successfully
-/// * `Err(String)` - Error message
-pub fn run_with_args(args: &[String], data_dir: &str) -> Result<(), String> {
-    let command = parse_args(args)?;
-    
-    // Help command doesn't need the service
-    if matches!(command, Command::Help) {
-        println!("{}", get_help_text());
-        return Ok(());
+        let (options, rest) = split_global_options(&args("prog view-product -- -q"));
+        assert!(!options.quiet);
+        assert_eq!(rest, args("prog view-product -- -q"));
     }
-    
-    // Initialize storage and service
-    let storage = JsonStorage::new(data_dir);
-    let mut service = InventoryService::new(Box::new(storage))
-        .map_err(|e| format!("Failed to initialize inventory service: {}", e))?;
-    
-    // Execute command and print result
-    match execute_command(command, &mut service) {
-        Ok(output) => {
-            println!("{}", output);
-            Ok(())
-        }
-        Err(err) => {
-            Err(err)
+
+    #[test]
+    fn test_plugins_never_shadow_builtins_and_are_listed_in_help() {
+        let aliases = BTreeMap::from([("lows".to_string(), "low-stock".to_string())]);
+        for command in ["prog ls", "prog stock add", "prog help", "prog lows"] {
+            assert_eq!(find_plugin(&args(command), &aliases), None, "{}", command);
         }
+
+        assert_eq!(plugin_help(&BTreeMap::new()), "");
+        let installed = BTreeMap::from([
+            ("label".to_string(), std::path::PathBuf::from("/usr/bin/stock-control-label")),
+            ("sync-shop".to_string(), std::path::PathBuf::from("/opt/bin/stock-control-sync-shop")),
+        ]);
+        let help = plugin_help(&installed);
+        assert!(help.ends_with(
+            "\n    label      /usr/bin/stock-control-label\n    sync-shop  /opt/bin/stock-control-sync-shop"
+        ), "{}", help);
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_quiet_output() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path().to_str().unwrap());
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+        let quiet = |command: &str, service: &mut InventoryService| {
+            execute_command_with_mode(parse_args(&args(command)).unwrap(), service, OutputMode::Quiet).unwrap()
+        };
 
-    fn args(s: &str) -> Vec<String> {
-        s.split_whitespace().map(String::from).collect()
+        assert_eq!(quiet("prog add-product B Bolt Steel 5 10", &mut service), "B");
+        assert_eq!(quiet("prog add-product A Anchor Iron 50 10", &mut service), "A");
+        assert_eq!(quiet("prog add-stock B 3", &mut service), "8");
+        assert_eq!(quiet("prog remove-stock A 20", &mut service), "30");
+        let mut listed: Vec<String> = quiet("prog list-products", &mut service).lines().map(String::from).collect();
+        listed.sort();
+        assert_eq!(listed, vec!["A", "B"]);
+        assert_eq!(quiet("prog low-stock", &mut service), "B");
+        assert_eq!(quiet("prog view-product A", &mut service), "A\tAnchor\t30\t10");
+        assert_eq!(quiet("prog delete-product A --force", &mut service), "");
     }
 
     #[test]
-    fn test_parse_help() {
-        assert_eq!(parse_args(&args("prog help")).unwrap(), Command::Help);
-        assert_eq!(parse_args(&args("prog --help")).unwrap(), Command::Help);
-        assert_eq!(parse_args(&args("prog -h")).unwrap(), Command::Help);
-        assert_eq!(parse_args(&args("prog")).unwrap(), Command::Help);
+    fn test_parse_stock_from_stdin() {
+        assert_eq!(
+            parse_args(&args("prog add-stock --from-stdin")).unwrap(),
+            Command::StockFromStdin { transaction_type: TransactionType::Addition, confirm_large: false, reason: None }
+        );
+        assert_eq!(
+            parse_args(&args("prog stock remove --from-stdin --confirm-large")).unwrap(),
+            Command::StockFromStdin { transaction_type: TransactionType::Removal, confirm_large: true, reason: None }
+        );
+        assert!(parse_args(&args("prog add-stock SKU001 --from-stdin")).is_err());
+        assert!(parse_args(&args("prog add-stock SKU001")).unwrap_err().starts_with("Usage: add-stock"));
     }
 
     #[test]
-    fn test_parse_add_product() {
-        let result = parse_args(&args("prog add-product SKU001 Widget Description 100 20")).unwrap();
-        assert_eq!(result, Command::AddProduct {
-            sku: "SKU001".to_string(),
-            name: "Widget".to_string(),
-            description: "Description".to_string(),
-            quantity: 100,
-            reorder_point: 20,
-        });
+    fn test_bulk_stock_is_all_or_nothing() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path().to_str().unwrap());
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+        service.add_product("A".into(), "Anchor".into(), "".into(), 5, 1).unwrap();
+        service.add_product("B".into(), "Bolt".into(), "".into(), 5, 1).unwrap();
+
+        let input = bulk::read_stock_entries("A,3\nB,4\n\nA,3\nZ,1\nB,none\n".as_bytes()).unwrap();
+        let err = apply_bulk_stock(&mut service, input, TransactionType::Removal, RemovalOptions::default(), false)
+            .unwrap_err();
+        assert!(err.contains("3 of 5 lines rejected"));
+        assert!(err.contains("Line 4: Insufficient stock for product 'A': requested 3, available 2"));
+        assert!(err.contains("Line 5: Product not found: Z"));
+        assert!(err.contains("Line 6: Invalid quantity 'none'"));
+        assert_eq!(service.get_product("A").unwrap().quantity, 5);
+        assert!(service.get_transactions("A").is_empty());
+
+        let input = bulk::read_stock_entries("A,3\nB,4,Picked\n".as_bytes()).unwrap();
+        let output = apply_bulk_stock(&mut service, input, TransactionType::Removal, RemovalOptions::default(), true)
+            .unwrap();
+        assert_eq!(output, "A\t2\nB\t1");
     }
 
     #[test]
-    fn test_parse_add_product_missing_args() {
-        let result = parse_args(&args("prog add-product SKU001 Widget"));
-        assert!(result.is_err());
+    fn test_parse_receipt() {
+        assert_eq!(
+            parse_args(&args("prog receipt 1a2b3c4d")).unwrap(),
+            Command::Receipt {
+                transaction_id: "1a2b3c4d".to_string(),
+                format: ReceiptFormat::Text,
+                output: None,
+                printer: None,
+            }
+        );
+        assert_eq!(
+            parse_args(&args("prog stock receipt 1a2b3c4d --format=pdf --output grn.pdf")).unwrap(),
+            Command::Receipt {
+                transaction_id: "1a2b3c4d".to_string(),
+                format: ReceiptFormat::Pdf,
+                output: Some("grn.pdf".to_string()),
+                printer: None,
+            }
+        );
+        assert!(parse_args(&args("prog receipt 1a2b3c4d --format pdf")).unwrap_err().contains("--output"));
+        assert_eq!(
+            parse_args(&args("prog receipt 1a2b3c4d --format escpos --printer tcp://till-1")).unwrap(),
+            Command::Receipt {
+                transaction_id: "1a2b3c4d".to_string(),
+                format: ReceiptFormat::EscPos,
+                output: None,
+                printer: Some(Printer::Tcp("till-1:9100".to_string())),
+            }
+        );
+        assert!(parse_args(&args("prog receipt 1a2b3c4d --format escpos")).unwrap_err().contains("--printer"));
     }
 
     #[test]
-    fn test_parse_update_product() {
-        let result = parse_args(&args("prog update-product SKU001 --name NewName --reorder-point 30")).unwrap();
-        assert_eq!(result, Command::UpdateProduct {
-            sku: "SKU001".to_string(),
-            name: Some("NewName".to_string()),
-            description: None,
-            reorder_point: Some(30),
-        });
+    fn test_template_output() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path().to_str().unwrap());
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+        service.add_product("B".into(), "Bolt".into(), "".into(), 2, 10).unwrap();
+        service.add_product("A".into(), "Anchor".into(), "".into(), 50, 10).unwrap();
+
+        let template = temp_dir.path().join("sheet.hbs");
+        std::fs::write(
+            &template,
+            "{{count}} products\n{{#each products}}{{sku}} {{name}}{{#if low_stock}} (order {{shortfall}}){{/if}}\n{{/each}}",
+        ).unwrap();
+        let template = template.to_str().unwrap().to_string();
+
+        let output = execute_command(Command::ListProducts { filter: ProductFilter::default(), template: Some(template.clone()), columns: Vec::new(), totals: false, group_by: None }, &mut service).unwrap();
+        assert_eq!(output, "2 products\nA Anchor\nB Bolt (order 8)");
+
+        let missing = Command::ListProducts {
+            filter: ProductFilter::default(),
+            template: Some("missing.hbs".to_string()),
+            columns: Vec::new(),
+            totals: false,
+            group_by: None,
+        };
+        assert!(execute_command(missing, &mut service).unwrap_err().contains("Failed to read template"));
     }
 
     #[test]
-    fn test_parse_add_stock() {
-        let result = parse_args(&args("prog add-stock SKU001 50")).unwrap();
-        assert_eq!(result, Command::AddStock {
-            sku: "SKU001".to_string(),
-            quantity: 50,
-            notes: None,
+    fn test_bulk_update_needs_confirmation() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path().to_str().unwrap());
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+        service.add_product("A".into(), "Anchor".into(), "".into(), 0, 10).unwrap();
+        service.add_product("B".into(), "Bolt".into(), "".into(), 0, 2).unwrap();
+        service.update_product("B", ProductUpdate { category: Some("widgets".into()), ..Default::default() }).unwrap();
+
+        let preview = parse_args(&args("prog bulk-update --filter category=widgets --set reorder-point=30 --set unit-cost=")).unwrap();
+        assert_eq!(execute_command(preview, &mut service).unwrap(), [
+            "Would update 1 products:",
+            "  B - Bolt",
+            "Changes: --set \"reorder-point=30\" --set \"unit-cost=\"",
+            "Run again with --yes to apply them.",
+        ].join("\n"));
+        assert_eq!(service.get_product("B").unwrap().reorder_point, 2);
+
+        let apply = parse_args(&args("prog bulk-update --filter category=widgets --set reorder-point=30 --yes")).unwrap();
+        assert_eq!(execute_command(apply, &mut service).unwrap(), "Updated 1 products.");
+        assert_eq!(service.get_product("B").unwrap().reorder_point, 30);
+        assert_eq!(service.get_product("A").unwrap().reorder_point, 10);
+
+        assert!(parse_args(&args("prog bulk-update --set reorder-point=30")).unwrap_err().contains("--filter"));
+        assert!(parse_args(&args("prog bulk-update --filter colour=red --set reorder-point=1")).unwrap_err().contains("Invalid filter"));
+        assert!(parse_args(&args("prog bulk-update --filter sku=A --set name=x")).unwrap_err().contains("Invalid field"));
+    }
+
+    #[test]
+    fn test_add_product_from_template() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let data_dir = temp_dir.path().to_str().unwrap();
+        let save = parse_args(&args("prog template save cable --sku-prefix CBL- --name-pattern Cable-{name} --reorder-point 10 --supplier Acme")).unwrap();
+        assert_eq!(manage_product_templates(save, data_dir, &BTreeMap::new(), false).unwrap(), "Saved product template 'cable'.");
+        let templates = Config::load(data_dir).unwrap().product_templates;
+        let list = manage_product_templates(Command::ListProductTemplates, data_dir, &templates, false).unwrap();
+        assert_eq!(list, "Saved product templates (1):\n  cable: --sku-prefix \"CBL-\" --name-pattern \"Cable-{name}\" --reorder-point \"10\" --supplier \"Acme\"");
+
+        let add = parse_args(&args("prog add-product BLU2 blue cat6 5 --from-template cable --supplier Bolt")).unwrap();
+        assert_eq!(from_template(add, &templates).unwrap(), Command::AddProduct {
+            sku: "CBL-BLU2".to_string(),
+            name: "Cable-blue".to_string(),
+            description: "cat6".to_string(),
+            quantity: 5,
+            reorder_point: 10,
+            category: None,
+            supplier: Some("Bolt".to_string()),
+            location: None,
+            unit_cost: None,
         });
+        let add = parse_args(&args("prog add-product CBL-RED red --from-template cable")).unwrap();
+        assert!(matches!(from_template(add, &templates).unwrap(), Command::AddProduct { sku, quantity: 0, .. } if sku == "CBL-RED"));
+        let add = parse_args(&args("prog add-product A a --from-template hose")).unwrap();
+        assert!(from_template(add, &templates).unwrap_err().contains("No saved product template named 'hose'"));
+        assert!(parse_args(&args("prog add-product A --from-template cable")).is_err());
     }
 
     #[test]
-    fn test_parse_add_stock_with_notes() {
-        let result = parse_args(&args("prog add-stock SKU001 50 --notes Shipment")).unwrap();
-        assert_eq!(result, Command::AddStock {
-            sku: "SKU001".to_string(),
-            quantity: 50,
-            notes: Some("Shipment".to_string()),
+    fn test_failed_import_resumes_after_last_checkpoint() {
+        use crate::testing::FailingStorage;
+
+        // Loading takes three storage operations, and each chunk saves products
+        // and transactions, then audits that its movements predate the product.
+        // Failing the audit leaves the first chunk saved though reported as
+        // failed; failing the second chunk's save leaves only the first.
+        for (fail_from, applied) in [(6, 0), (7, 500)] {
+            let temp_dir = tempfile::TempDir::new().unwrap();
+            let file = temp_dir.path().join("history.csv");
+            let rows: String = (0..1200)
+                .map(|minute| format!("SKU001,2024-03-01T{:02}:{:02}:00,addition,1\n", minute / 60, minute % 60))
+                .collect();
+            std::fs::write(&file, &rows).unwrap();
+            let open = || InventoryService::new(Box::new(JsonStorage::new(temp_dir.path()))).unwrap();
+            open().add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+
+            let storage = FailingStorage::new(JsonStorage::new(temp_dir.path()), fail_from);
+            let mut service = InventoryService::new(Box::new(storage)).unwrap();
+            let mut job = ImportJob::start(&file, rows.as_bytes(), None);
+            let input = bulk::read_historical_entries(rows.as_bytes()).unwrap();
+            let err = import_transactions(&mut service, input, Some((&mut job, temp_dir.path())), true).unwrap_err();
+            let resume = format!("stopped after {} of 1200 rows; continue it with: import-transactions --resume {}", applied, job.id);
+            assert!(err.contains(&resume), "{}", err);
+            assert_eq!(open().get_product("SKU001").unwrap().quantity, 500);
+            let (hooks, mqtt, shop) = (Default::default(), Default::default(), Default::default());
+            let mut dispatch = Dispatch::new(temp_dir.path(), &hooks, &mqtt, &shop);
+            dispatch.finish_failed(&mut service);
+            drop(service);
+            let outbox = crate::outbox::since(temp_dir.path(), 0, None).unwrap();
+            assert_eq!(outbox.iter().filter(|line| line.contains(r#""type":"stock_changed""#)).count(), 500);
+
+            let job = ImportJob::load(temp_dir.path(), &job.id).unwrap();
+            let resume = Command::ImportJob { job: job.clone(), data_dir: temp_dir.path().to_path_buf() };
+            let output = execute_command_with_mode(resume, &mut open(), OutputMode::Quiet).unwrap();
+            assert_eq!(output, "SKU001\t1200");
+            assert_eq!(open().get_transactions("SKU001").len(), 1200);
+            assert!(ImportJob::load(temp_dir.path(), &job.id).is_err());
+        }
+    }
+
+    #[test]
+    fn test_ledger_entries_post_fifo_costs() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path().to_str().unwrap());
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+        service.add_product("A".into(), "Anchor".into(), "".into(), 0, 1).unwrap();
+        service.add_product("B".into(), "Bolt".into(), "".into(), 0, 1).unwrap();
+        let cost = |cents| crate::service::AdditionOptions { unit_cost: Some(Money::from_cents(cents)), ..Default::default() };
+        service.add_stock_with_options("A", 10, None, cost(200)).unwrap();
+        service.add_stock_with_options("A", 10, None, cost(300)).unwrap();
+        service.remove_stock("A", 15, None).unwrap();
+        service.add_stock("B", 5, None).unwrap();
+
+        let command = parse_args(&args("prog report ledger --format ledger --currency EUR")).unwrap();
+        let output = execute_command(command, &mut service).unwrap();
+        let entries: Vec<&str> = output.split("\n\n").collect();
+        assert_eq!(entries.len(), 4, "{}", output);
+        assert!(entries[2].contains(" Issued 15 x A Anchor\n"), "{}", entries[2]);
+        assert!(entries[2].contains("    Expenses:CostOfGoodsSold                  35.00 EUR\n"), "{}", entries[2]);
+        assert_eq!(entries[3], "; 5 units have no unit cost and are left out; record one with add-stock --unit-cost.");
+
+        let command = parse_args(&args("prog ledger-entries")).unwrap();
+        let output = execute_command_with_mode(command, &mut service, OutputMode::Quiet).unwrap();
+        assert!(output.contains("open Expenses:CostOfGoodsSold USD\n"), "{}", output);
+        assert!(!output.contains("; 5 units"));
+        assert!(parse_args(&args("prog ledger-entries --format gnucash")).unwrap_err().contains("beancount, ledger"));
+        assert!(parse_args(&args("prog ledger-entries --currency usd")).is_err());
+    }
+
+    #[test]
+    fn test_list_products_columns_and_totals() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path().to_str().unwrap());
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+        service.add_product("A".into(), "Anchor".into(), "".into(), 0, 10).unwrap();
+        service.add_product("B".into(), "Bolt".into(), "".into(), 0, 2).unwrap();
+        let cost = |cents| crate::service::AdditionOptions { unit_cost: Some(Money::from_cents(cents)), ..Default::default() };
+        service.add_stock_with_options("A", 3, None, cost(250)).unwrap();
+        service.add_stock_with_options("B", 40, None, cost(10)).unwrap();
+        service.update_product("B", crate::service::ProductUpdate { location: Some("Bin 4".into()), ..Default::default() }).unwrap();
+
+        let list = parse_args(&args("prog list-products --columns sku,location,quantity,value --totals")).unwrap();
+        assert_eq!(execute_command(list, &mut service).unwrap(), [
+            "Products (2 total):",
+            "  SKU    Location  Quantity  Value",
+            "  A      -                3   7.50",
+            "  B      Bin 4           40   4.00",
+            "  Total                  43  11.50",
+        ].join("\n"));
+        let list = parse_args(&args("prog ls --totals")).unwrap();
+        assert_eq!(execute_command_with_mode(list, &mut service, OutputMode::Quiet).unwrap(), "A\tAnchor\t3\nB\tBolt\t40\ntotal\t\t43");
+        assert!(parse_args(&args("prog ls --columns sku,colour")).unwrap_err().contains("Invalid column 'colour'"));
+        assert!(parse_args(&args("prog ls --totals --template list.hbs")).is_err());
+
+        let list = parse_args(&args("prog ls --group-by location --totals")).unwrap();
+        assert_eq!(execute_command(list, &mut service).unwrap(), [
+            "Products by location (2 total):",
+            "  Location  Products  Quantity  Value",
+            "  Bin 4            1        40   4.00",
+            "  (none)           1         3   7.50",
+            "  Total            2        43  11.50",
+        ].join("\n"));
+        assert!(parse_args(&args("prog ls --group-by location --columns sku")).is_err());
+    }
+
+    #[test]
+    fn test_low_stock_suggests_substitutes_for_products_out_of_stock() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path().to_str().unwrap());
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+        service.add_product("A".into(), "Anchor".into(), "".into(), 0, 10).unwrap();
+        service.add_product("B".into(), "Bolt".into(), "".into(), 4, 2).unwrap();
+
+        let link = parse_args(&args("prog product link B substitute-for A")).unwrap();
+        assert_eq!(link, Command::LinkProduct {
+            sku: "B".into(), kind: LinkKind::SubstituteFor, target: "A".into(), remove: false,
         });
+        assert_eq!(execute_command(link, &mut service).unwrap(), "Linked 'B': substitute for A.");
+        assert!(parse_args(&args("prog link-product B replaces A")).is_err());
+
+        let low_stock = execute_command(Command::LowStock { group_by: None, template: None }, &mut service).unwrap();
+        assert!(low_stock.contains("\n    Substitutes in stock: B - Bolt (4)"));
+        let view = execute_command(Command::ViewProduct { sku: "B".into(), template: None }, &mut service).unwrap();
+        assert!(view.contains("  Links: substitute for A"));
     }
 
     #[test]
-    fn test_parse_remove_stock() {
-        let result = parse_args(&args("prog remove-stock SKU001 10")).unwrap();
-        assert_eq!(result, Command::RemoveStock {
-            sku: "SKU001".to_string(),
-            quantity: 10,
-            notes: None,
+    fn test_reconcile_reports_and_applies_discrepancies() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path().to_str().unwrap());
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+        service.add_product("A".into(), "Anchor".into(), "".into(), 10, 1).unwrap();
+        service.add_product("B".into(), "Bolt".into(), "".into(), 5, 1).unwrap();
+        let file = temp_dir.path().join("erp.csv").display().to_string();
+        std::fs::write(&file, "SKU,Name,OnHand\nA,Anchor,8\nZ,Zip,1\n").unwrap();
+
+        let command = parse_args(&args(&format!("prog reconcile {} --qty-column onhand", file))).unwrap();
+        assert_eq!(command, Command::Reconcile {
+            file: file.clone(),
+            key: "sku".into(),
+            qty_column: "onhand".into(),
+            apply: false,
+            recount: None,
+            confirm: Vec::new(),
+            sheet: None,
         });
+        assert_eq!(
+            execute_command(command, &mut service).unwrap(),
+            format!("Compared 2 products in {}: 3 discrepancies\n  \
+                     A: 10 here, 8 in the export (-2)\n  \
+                     B: 5 here, not in the export\n  \
+                     Z: 1 in the export, not in the inventory", file)
+        );
+
+        let apply = Command::Reconcile {
+            file: file.clone(),
+            key: "sku".into(),
+            qty_column: "onhand".into(),
+            apply: true,
+            recount: None,
+            confirm: Vec::new(),
+            sheet: None,
+        };
+        assert!(execute_command(apply, &mut service).unwrap().ends_with("Adjusted 1 products to match the export."));
+        assert_eq!(service.get_product("A").unwrap().quantity, 8);
+        assert_eq!(service.get_transactions("A")[0].notes, Some(format!("Reconciled with {}", file)));
     }
 
     #[test]
-    fn test_parse_view_product() {
-        let result = parse_args(&args("prog view-product SKU001")).unwrap();
-        assert_eq!(result, Command::ViewProduct { sku: "SKU001".to_string() });
+    fn test_reconcile_holds_variances_beyond_tolerance() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path().to_str().unwrap());
+        let policy = crate::models::StocktakePolicy { tolerance_units: Some(2), tolerance_percent: Some(10.0) };
+        let mut service = InventoryService::new(Box::new(storage)).unwrap().with_stocktake_policy(policy);
+        for sku in ["A", "B", "C"] {
+            service.add_product(sku.into(), "Part".into(), "".into(), 40, 1).unwrap();
+        }
+        let count = temp_dir.path().join("count.csv").display().to_string();
+        std::fs::write(&count, "sku,counted\nA,38\nB,30\nC,52\n").unwrap();
+        let recount = temp_dir.path().join("recount.csv").display().to_string();
+        std::fs::write(&recount, "sku,counted\nB,30\nC,50\n").unwrap();
+
+        let command = parse_args(&args(&format!("prog reconcile {} --qty-column counted --apply", count))).unwrap();
+        assert_eq!(
+            execute_command_with_mode(command, &mut service, OutputMode::Quiet).unwrap(),
+            "A\t40\t38\nB\t40\t30\theld\nC\t40\t52\theld"
+        );
+        assert_eq!(service.get_product("A").unwrap().quantity, 38);
+        assert_eq!(service.get_product("B").unwrap().quantity, 40);
+
+        let command = parse_args(&args(&format!(
+            "prog reconcile {} --qty-column counted --apply --recount {} --confirm C",
+            count, recount
+        )))
+        .unwrap();
+        let output = execute_command(command, &mut service).unwrap();
+        assert!(output.ends_with("Adjusted 2 products to match the export."), "{}", output);
+        assert_eq!(service.get_product("B").unwrap().quantity, 30);
+        assert_eq!(service.get_product("C").unwrap().quantity, 52);
+
+        std::fs::write(&count, "sku,counted\nA,30\n").unwrap();
+        let command = parse_args(&args(&format!("prog reconcile {} --qty-column counted --apply --recount {}", count, recount))).unwrap();
+        assert!(execute_command(command, &mut service).unwrap().ends_with(
+            "Held 1 variances beyond the stocktake tolerance (A); count them again with --recount or accept them with --confirm."
+        ));
+        assert!(crate::models::StocktakePolicy::default().within_tolerance(0, 500));
     }
 
     #[test]
-    fn test_parse_list_products() {
-        let result = parse_args(&args("prog list-products")).unwrap();
-        assert_eq!(result, Command::ListProducts);
+    fn test_count_sheets_fed_back_by_sheet_number() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path().to_str().unwrap());
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+        for (sku, location) in [("A", "A1"), ("B", "A2"), ("C", "B1")] {
+            service.add_product(sku.into(), "Part".into(), "".into(), 10, 1).unwrap();
+            service.update_product(sku, crate::service::ProductUpdate { location: Some(location.into()), ..Default::default() }).unwrap();
+        }
+
+        let command = parse_args(&args("prog stocktake sheets --location A --blind")).unwrap();
+        assert_eq!(
+            execute_command(command, &mut service).unwrap(),
+            "sheet,line,location,sku,name,barcode,counted\n1,1,A1,A,Part,,\n2,1,A2,B,Part,,"
+        );
+        assert!(parse_args(&args("prog count-sheets --format pdf")).unwrap_err().contains("--output"));
+
+        let file = temp_dir.path().join("sheets.csv").display().to_string();
+        let command = parse_args(&args(&format!("prog count-sheets --output {}", file))).unwrap();
+        assert_eq!(execute_command(command, &mut service).unwrap(), format!("Wrote 3 count sheets listing 3 products to {}", file));
+        let sheets = std::fs::read_to_string(&file).unwrap().replace("A2,B,Part,,10,", "A2,B,Part,,10,7");
+        std::fs::write(&file, sheets).unwrap();
+
+        let command = parse_args(&args(&format!("prog reconcile {} --qty-column counted --sheet 2 --apply", file))).unwrap();
+        assert_eq!(execute_command_with_mode(command, &mut service, OutputMode::Quiet).unwrap(), "B\t10\t7");
+        assert_eq!(service.get_product("B").unwrap().quantity, 7);
+        assert!(parse_args(&args(&format!("prog reconcile {} --sheet 0", file))).is_err());
     }
 
     #[test]
-    fn test_parse_low_stock() {
-        let result = parse_args(&args("prog low-stock")).unwrap();
-        assert_eq!(result, Command::LowStock);
+    fn test_feed_push_delivers_due_feeds_and_records_failures() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path().to_str().unwrap());
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+        service.add_product("A".into(), "Anchor".into(), "".into(), 7, 1).unwrap();
+        assert_eq!(
+            parse_args(&args("prog feed push retail --due")).unwrap(),
+            Command::FeedPush { names: vec!["retail".to_string()], due: true }
+        );
+
+        let outbox = temp_dir.path().join("outbox");
+        std::fs::create_dir(&outbox).unwrap();
+        let feeds = BTreeMap::from([
+            ("broken".to_string(), feed::FeedConfig { format: FeedFormat::X12, destination: outbox.display().to_string(), ..Default::default() }),
+            (
+                "retail".to_string(),
+                feed::FeedConfig {
+                    format: FeedFormat::Csv,
+                    destination: outbox.display().to_string(),
+                    file_name: Some("{name}.{ext}".to_string()),
+                    interval_minutes: Some(60),
+                    ..Default::default()
+                },
+            ),
+        ]);
+        let now = Utc::now();
+        assert!(feed_push(&service, &feeds, temp_dir.path(), &["nope".to_string()], false, now, false).is_err());
+
+        let (output, failed) = feed_push(&service, &feeds, temp_dir.path(), &[], true, now, false).unwrap();
+        assert!(!failed);
+        assert!(output.starts_with("Pushed feed 'retail' to "));
+        assert!(std::fs::read_to_string(outbox.join("retail.csv")).unwrap().contains("\nA,,Anchor,7,0,"));
+        assert_eq!(feed_push(&service, &feeds, temp_dir.path(), &[], true, now, false).unwrap().0, "No feeds are due.");
+
+        let (output, failed) = feed_push(&service, &feeds, temp_dir.path(), &["broken".to_string()], false, now, false).unwrap();
+        assert!(failed);
+        assert_eq!(output, "Feed 'broken' failed: An X12 feed needs sender and receiver IDs");
+        let state = feed::load_state(temp_dir.path()).unwrap();
+        let status = feed_status(&feeds, &state, false);
+        assert!(status.contains("retail: csv to "), "{}", status);
+        assert!(status.contains(", every 60 minutes; last pushed "));
+        assert!(status.contains("broken: x12 to ") && status.contains("last pushed never; failed "));
     }
 
     #[test]
-    fn test_parse_history() {
-        let result = parse_args(&args("prog history SKU001")).unwrap();
-        assert_eq!(result, Command::History {
-            sku: "SKU001".to_string(),
-            start: None,
-            end: None,
-        });
+    fn test_shop_sync_dry_run_lists_requests_for_mapped_products() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path().to_str().unwrap());
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+        service.add_product("A".into(), "Anchor".into(), "".into(), 7, 1).unwrap();
+        service.add_product("B".into(), "Bolt".into(), "".into(), 2, 1).unwrap();
+        std::fs::write(temp_dir.path().join(shop::DEFAULT_MAPPING_FILE), "A,15\n").unwrap();
+        assert_eq!(parse_args(&args("prog data shop-sync --dry-run")).unwrap(), Command::ShopSync { dry_run: true });
+
+        let mut config = shop::ShopConfig::default();
+        assert!(shop_sync(&service, &config, temp_dir.path(), true, false).unwrap_err().contains("shop.platform"));
+        config.platform = Some(shop::Platform::WooCommerce);
+        config.url = Some("https://shop.example".to_string());
+        assert_eq!(
+            shop_sync(&service, &config, temp_dir.path(), true, false).unwrap(),
+            "Would send 1 requests to the woocommerce store:\n\
+             PUT https://shop.example/wp-json/wc/v3/products/15 {\"manage_stock\":true,\"stock_quantity\":7}"
+        );
     }
 
     #[test]
-    fn test_parse_delete_product() {
-        let result = parse_args(&args("prog delete-product SKU001")).unwrap();
-        assert_eq!(result, Command::DeleteProduct { sku: "SKU001".to_string() });
+    fn test_inventory_advice_needs_partner_ids_for_x12() {
+        assert_eq!(
+            parse_args(&args("prog export-846 --sender ACME --receiver SHOP --control 7")).unwrap(),
+            Command::InventoryAdvice {
+                format: FeedFormat::X12,
+                sender: Some("ACME".into()),
+                receiver: Some("SHOP".into()),
+                control: Some(7),
+                output: None,
+            }
+        );
+        assert!(parse_args(&args("prog inventory-advice --control 0")).is_err());
+        assert!(parse_args(&args("prog inventory-advice --format xml")).is_err());
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path().to_str().unwrap());
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+        service.add_product("A".into(), "Anchor".into(), "".into(), 7, 1).unwrap();
+        let x12 = parse_args(&args("prog inventory-advice")).unwrap();
+        assert!(execute_command(x12, &mut service).unwrap_err().contains("--sender and --receiver"));
+        let csv = parse_args(&args("prog inventory-advice --format csv")).unwrap();
+        let feed = execute_command(csv, &mut service).unwrap();
+        assert!(feed.starts_with("sku,upc,description,quantity_available,quantity_on_order,as_of\nA,,Anchor,7,0,"), "{}", feed);
     }
 
     #[test]
-    fn test_parse_unknown_command() {
-        let result = parse_args(&args("prog unknown-cmd"));
-        assert!(result.is_err());
-        assert!(result.unwrap_err().contains("Unknown command"));
+    fn test_parse_run_script() {
+        assert_eq!(
+            parse_args(&args("prog script run report.rhai tools -- -7")).unwrap(),
+            Command::RunScript { path: "report.rhai".into(), args: args("tools -7") }
+        );
+        assert!(parse_args(&args("prog script run")).is_err());
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path().to_str().unwrap());
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+        let path = temp_dir.path().join("report.rhai");
+        std::fs::write(&path, "print(products().len());").unwrap();
+        let run = Command::RunScript { path: path.display().to_string(), args: Vec::new() };
+        let output = execute_command(run, &mut service);
+        if cfg!(feature = "script") {
+            assert_eq!(output.unwrap(), "0");
+        } else {
+            assert!(output.unwrap_err().contains("--features script"));
+        }
+    }
+
+    #[test]
+    fn test_spanish_output() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path().to_str().unwrap());
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+        service.add_product("A".into(), "Anchor".into(), "".into(), 2, 10).unwrap();
+
+        i18n::set_locale(Locale::Es);
+        let low_stock = execute_command(Command::LowStock { group_by: None, template: None }, &mut service);
+        let missing = execute_command(Command::ViewProduct { sku: "Z".into(), template: None }, &mut service);
+        let zero = format_error(service.add_stock("A", 0, None).unwrap_err());
+        let history = execute_command(Command::ValueHistory { interval: Interval::Month, start: None, end: None }, &mut service);
+        let help = help::get_help_text();
+        i18n::set_locale(Locale::En);
+
+        assert!(low_stock.unwrap().starts_with("Productos con stock bajo (1 en total):"));
+        assert_eq!(missing.unwrap_err(), "Error: No se encontró el producto 'Z'.");
+        assert_eq!(zero, "Error: La cantidad debe ser positiva");
+        assert!(history.unwrap().starts_with("Valor del inventario por mes:"));
+        assert!(help.contains("COMANDOS DE PRODUCTO"));
+    }
+
+    #[test]
+    fn test_parse_command_help() {
+        let expected = Command::CommandHelp { command: "add-stock".to_string() };
+        assert_eq!(parse_args(&args("prog add-stock --help")).unwrap(), expected);
+        assert_eq!(parse_args(&args("prog stock add -h")).unwrap(), expected);
+        assert_eq!(parse_args(&args("prog help add-stock")).unwrap(), expected);
+        assert_eq!(parse_args(&args("prog help stock add")).unwrap(), expected);
+        assert!(matches!(
+            parse_args(&args("prog view-product -- --help")).unwrap(),
+            Command::ViewProduct { .. }
+        ));
+    }
+
+    #[test]
+    fn test_parse_help_json() {
+        assert_eq!(parse_args(&args("prog help --format json")).unwrap(), Command::HelpJson { command: None });
+        assert_eq!(
+            parse_args(&args("prog help stock add --format=json")).unwrap(),
+            Command::HelpJson { command: Some("add-stock".to_string()) }
+        );
+        assert_eq!(parse_args(&args("prog help --format text")).unwrap(), Command::Help);
+        assert!(parse_args(&args("prog help --format yaml")).unwrap_err().contains("expected text or json"));
+
+        let output = help_json(Some("remove-stock")).unwrap();
+        let value: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(value["name"], "remove-stock");
+    }
+
+    #[test]
+    fn test_parse_missing_arguments_shows_usage() {
+        let result = parse_args(&args("prog add-stock SKU001"));
+        assert_eq!(
+            result.unwrap_err(),
+            "Usage: add-stock <sku> <quantity> [--condition <condition>] [--unit-cost <amount>] [--notes <notes>]\n       add-stock --from-stdin\nExample: add-stock SKU001 50 --notes \"Received shipment\""
+        );
     }
 
     #[test]
@@ -0,0 +1,2835 @@
+// Command-line definitions parsed by clap
+//
+// Each command's arguments struct carries its help: the doc comment is the
+// summary line followed by notes, `override_usage` the usage synopses, and
+// `after_help` the examples, one per line. Commands are reachable by name
+// (`add-stock`) and in grouped form (`stock add`); the grouped subcommands
+// record the command name as their `display_name`.
+
+use chrono::Duration;
+use clap::builder::PossibleValuesParser;
+use clap::{Args, CommandFactory, Parser, Subcommand};
+
+use crate::books::BookFormat;
+use crate::convert;
+use crate::edi::FeedFormat;
+use crate::export::ExportFormat;
+use crate::forecast::Seasonality;
+use crate::models::{
+    Condition, GroupBy, Interval, LinkKind, Money, MovementThreshold, ProductFilter, ProductSort, ProductTemplate,
+    ReasonCode, TransactionType, Weight,
+};
+use crate::receipt::{Printer, ReceiptFormat};
+use crate::reorder;
+use crate::service::ProductUpdate;
+use crate::stocktake::SheetFormat;
+
+use super::help;
+use super::parse::{
+    choice, key_value, optional, parse_baud, parse_columns, parse_components, parse_control, parse_currency,
+    parse_date, parse_datetime, parse_day, parse_days, parse_lead_time, parse_limit, parse_merge_policy,
+    parse_quantity, parse_removal_type, parse_reorder_point, parse_seq, parse_service_level, parse_sheet,
+    parse_stock_quantity, parse_years,
+};
+use super::Command;
+
+/// Serial port the weigh command reads when `--port` isn't given
+const DEFAULT_SCALE_PORT: &str = "/dev/ttyUSB0";
+
+/// Baud rate the weigh command uses when `--baud` isn't given
+const DEFAULT_SCALE_BAUD: &str = "9600";
+
+/// A value that an empty argument clears, e.g. `--unit-cost ""`
+type Clearable<T> = Option<T>;
+
+/// Comma-separated kit components, parsed as a single value
+type ComponentList = Vec<crate::models::Component>;
+
+/// Comma-separated listing columns, parsed as a single value
+type ColumnList = Vec<crate::models::ListColumn>;
+
+/// The stock-control command line
+#[derive(Debug, Parser)]
+#[command(name = "stock-control", bin_name = "stock-control", disable_help_subcommand = true, args_override_self = true)]
+pub struct Cli {
+    /// Command to run; none shows the help
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+/// The clap command tree, used for parsing and for rendering help
+///
+/// A group given without a subcommand is an error showing the group's
+/// subcommands, rather than clap's help for the group.
+pub fn command() -> clap::Command {
+    Cli::command().mut_subcommands(|command| {
+        if !help::is_group(command.get_name()) {
+            return command;
+        }
+        let subcommands: Vec<&str> = command.get_subcommands().map(|subcommand| subcommand.get_name()).collect();
+        let usage = format!("{} <{}> [OPTIONS]", command.get_name(), subcommands.join("|"));
+        command.override_usage(usage).subcommand_required(true).arg_required_else_help(false)
+    })
+}
+
+/// Every command by name, followed by the command groups
+///
+/// Variants carry no doc comments so that each command's help comes from its
+/// arguments struct.
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    AddProduct(AddProductArgs),
+    BulkUpdate(BulkUpdateArgs),
+    UpdateProduct(UpdateProductArgs),
+    CheckBuildable(CheckBuildableArgs),
+    #[command(visible_alias = "show")]
+    ViewProduct(ViewProductArgs),
+    #[command(visible_alias = "ls")]
+    ListProducts(ListProductsArgs),
+    MigrateOpeningBalances(MigrateOpeningBalancesArgs),
+    DeleteProduct(DeleteProductArgs),
+    LinkProduct(LinkProductArgs),
+    #[command(visible_aliases = ["in", "rx"])]
+    AddStock(AddStockArgs),
+    #[command(visible_alias = "out")]
+    RemoveStock(RemoveStockArgs),
+    Quarantine(QuarantineArgs),
+    ReleaseQuarantine(ReleaseQuarantineArgs),
+    MarkOrdered(MarkOrderedArgs),
+    ChangeCondition(ChangeConditionArgs),
+    Weigh(WeighArgs),
+    Kiosk(KioskArgs),
+    #[command(visible_alias = "hist")]
+    History(HistoryArgs),
+    Receipt(ReceiptArgs),
+    ImportTransactions(ImportTransactionsArgs),
+    #[command(visible_alias = "low")]
+    LowStock(LowStockArgs),
+    CheckRules(CheckRulesArgs),
+    Ack(AckArgs),
+    Diff(DiffArgs),
+    Stats(StatsArgs),
+    HistoryCompare(HistoryCompareArgs),
+    ValueHistory(ValueHistoryArgs),
+    SuggestReorderPoints(SuggestReorderPointsArgs),
+    Forecast(ForecastArgs),
+    Kpis(KpisArgs),
+    Cogs(CogsArgs),
+    LedgerEntries(LedgerEntriesArgs),
+    Shrinkage(ShrinkageArgs),
+    Consumption(ConsumptionArgs),
+    ReceiveStart(ReceiveStartArgs),
+    ReceiveAdd(ReceiveAddArgs),
+    ReceiveReview(ReceiveReviewArgs),
+    ReceiveCommit(ReceiveCommitArgs),
+    ReceiveCancel(ReceiveCancelArgs),
+    ReceiveInbox(ReceiveInboxArgs),
+    TrashList(TrashListArgs),
+    TrashRestore(TrashRestoreArgs),
+    Export(ExportArgs),
+    ConvertStorage(ConvertStorageArgs),
+    ArchiveTransactions(ArchiveTransactionsArgs),
+    Compact(CompactArgs),
+    HealIds(HealIdsArgs),
+    RetentionApply(RetentionApplyArgs),
+    SnapshotKpis(SnapshotKpisArgs),
+    VerifyLedger(VerifyLedgerArgs),
+    Reconcile(ReconcileArgs),
+    CountSheets(CountSheetsArgs),
+    #[command(visible_alias = "export-846")]
+    InventoryAdvice(InventoryAdviceArgs),
+    ShopSync(ShopSyncArgs),
+    Shipments(ShipmentsArgs),
+    AddCustomer(AddCustomerArgs),
+    ListCustomers(ListCustomersArgs),
+    Backorders(BackordersArgs),
+    FillBackorders(FillBackordersArgs),
+    ImportPrices(ImportPricesArgs),
+    ComparePrices(ComparePricesArgs),
+    SaveView(SaveViewArgs),
+    RunView(RunViewArgs),
+    ListViews(ListViewsArgs),
+    DeleteView(DeleteViewArgs),
+    SaveTemplate(SaveTemplateArgs),
+    ListTemplates(ListTemplatesArgs),
+    DeleteTemplate(DeleteTemplateArgs),
+    RunScript(RunScriptArgs),
+    FeedPush(FeedPushArgs),
+    FeedStatus(FeedStatusArgs),
+    EventsTail(EventsTailArgs),
+    Help(HelpArgs),
+    #[command(subcommand)]
+    Product(ProductCommands),
+    #[command(subcommand)]
+    Stock(StockCommands),
+    #[command(subcommand)]
+    Report(ReportCommands),
+    #[command(subcommand)]
+    Receive(ReceiveCommands),
+    #[command(subcommand)]
+    Trash(TrashCommands),
+    #[command(subcommand)]
+    Data(DataCommands),
+    #[command(subcommand)]
+    View(ViewCommands),
+    #[command(subcommand)]
+    Template(TemplateCommands),
+    #[command(subcommand)]
+    Supplier(SupplierCommands),
+    #[command(subcommand)]
+    Backorder(BackorderCommands),
+    #[command(subcommand)]
+    Customer(CustomerCommands),
+    #[command(subcommand)]
+    Retention(RetentionCommands),
+    #[command(subcommand)]
+    Script(ScriptCommands),
+    #[command(subcommand)]
+    Feed(FeedCommands),
+    #[command(subcommand)]
+    Events(EventsCommands),
+    #[command(subcommand)]
+    Stocktake(StocktakeCommands),
+}
+
+impl Commands {
+    /// Build the command to execute, checking what clap can't
+    pub fn into_command(self) -> Result<Command, String> {
+        match self {
+            Commands::AddProduct(args) => args.into_command(),
+            Commands::BulkUpdate(args) => args.into_command(),
+            Commands::UpdateProduct(args) => args.into_command(),
+            Commands::CheckBuildable(args) => args.into_command(),
+            Commands::ViewProduct(args) => args.into_command(),
+            Commands::ListProducts(args) => args.into_command(),
+            Commands::MigrateOpeningBalances(args) => args.into_command(),
+            Commands::DeleteProduct(args) => args.into_command(),
+            Commands::LinkProduct(args) => args.into_command(),
+            Commands::AddStock(args) => args.into_command(),
+            Commands::RemoveStock(args) => args.into_command(),
+            Commands::Quarantine(args) => args.into_command(),
+            Commands::ReleaseQuarantine(args) => args.into_command(),
+            Commands::MarkOrdered(args) => args.into_command(),
+            Commands::ChangeCondition(args) => args.into_command(),
+            Commands::Weigh(args) => args.into_command(),
+            Commands::Kiosk(args) => args.into_command(),
+            Commands::History(args) => args.into_command(),
+            Commands::Receipt(args) => args.into_command(),
+            Commands::ImportTransactions(args) => args.into_command(),
+            Commands::LowStock(args) => args.into_command(),
+            Commands::CheckRules(args) => args.into_command(),
+            Commands::Ack(args) => args.into_command(),
+            Commands::Diff(args) => args.into_command(),
+            Commands::Stats(args) => args.into_command(),
+            Commands::HistoryCompare(args) => args.into_command(),
+            Commands::ValueHistory(args) => args.into_command(),
+            Commands::SuggestReorderPoints(args) => args.into_command(),
+            Commands::Forecast(args) => args.into_command(),
+            Commands::Kpis(args) => args.into_command(),
+            Commands::Cogs(args) => args.into_command(),
+            Commands::LedgerEntries(args) => args.into_command(),
+            Commands::Shrinkage(args) => args.into_command(),
+            Commands::Consumption(args) => args.into_command(),
+            Commands::ReceiveStart(args) => args.into_command(),
+            Commands::ReceiveAdd(args) => args.into_command(),
+            Commands::ReceiveReview(args) => args.into_command(),
+            Commands::ReceiveCommit(args) => args.into_command(),
+            Commands::ReceiveCancel(args) => args.into_command(),
+            Commands::ReceiveInbox(args) => args.into_command(),
+            Commands::TrashList(args) => args.into_command(),
+            Commands::TrashRestore(args) => args.into_command(),
+            Commands::Export(args) => args.into_command(),
+            Commands::ConvertStorage(args) => args.into_command(),
+            Commands::ArchiveTransactions(args) => args.into_command(),
+            Commands::Compact(args) => args.into_command(),
+            Commands::HealIds(args) => args.into_command(),
+            Commands::RetentionApply(args) => args.into_command(),
+            Commands::SnapshotKpis(args) => args.into_command(),
+            Commands::VerifyLedger(args) => args.into_command(),
+            Commands::Reconcile(args) => args.into_command(),
+            Commands::CountSheets(args) => args.into_command(),
+            Commands::InventoryAdvice(args) => args.into_command(),
+            Commands::ShopSync(args) => args.into_command(),
+            Commands::Shipments(args) => args.into_command(),
+            Commands::AddCustomer(args) => args.into_command(),
+            Commands::ListCustomers(args) => args.into_command(),
+            Commands::Backorders(args) => args.into_command(),
+            Commands::FillBackorders(args) => args.into_command(),
+            Commands::ImportPrices(args) => args.into_command(),
+            Commands::ComparePrices(args) => args.into_command(),
+            Commands::SaveView(args) => args.into_command(),
+            Commands::RunView(args) => args.into_command(),
+            Commands::ListViews(args) => args.into_command(),
+            Commands::DeleteView(args) => args.into_command(),
+            Commands::SaveTemplate(args) => args.into_command(),
+            Commands::ListTemplates(args) => args.into_command(),
+            Commands::DeleteTemplate(args) => args.into_command(),
+            Commands::RunScript(args) => args.into_command(),
+            Commands::FeedPush(args) => args.into_command(),
+            Commands::FeedStatus(args) => args.into_command(),
+            Commands::EventsTail(args) => args.into_command(),
+            Commands::Help(args) => args.into_command(),
+            Commands::Product(group) => group.into_command(),
+            Commands::Stock(group) => group.into_command(),
+            Commands::Report(group) => group.into_command(),
+            Commands::Receive(group) => group.into_command(),
+            Commands::Trash(group) => group.into_command(),
+            Commands::Data(group) => group.into_command(),
+            Commands::View(group) => group.into_command(),
+            Commands::Template(group) => group.into_command(),
+            Commands::Supplier(group) => group.into_command(),
+            Commands::Backorder(group) => group.into_command(),
+            Commands::Customer(group) => group.into_command(),
+            Commands::Retention(group) => group.into_command(),
+            Commands::Script(group) => group.into_command(),
+            Commands::Feed(group) => group.into_command(),
+            Commands::Events(group) => group.into_command(),
+            Commands::Stocktake(group) => group.into_command(),
+        }
+    }
+}
+
+/// Declare a command group whose subcommands build commands from their arguments
+macro_rules! command_group {
+    ($(#[$meta:meta])* $group:ident { $($variant:ident($args:ty) = $subcommand:literal => $name:literal,)* }) => {
+        $(#[$meta])*
+        #[derive(Debug, Subcommand)]
+        pub enum $group {
+            $(
+                #[command(name = $subcommand, display_name = $name)]
+                $variant($args),
+            )*
+        }
+
+        impl $group {
+            /// Build the command to execute, checking what clap can't
+            pub fn into_command(self) -> Result<Command, String> {
+                match self {
+                    $($group::$variant(args) => args.into_command(),)*
+                }
+            }
+        }
+    };
+}
+
+command_group!(
+    /// `product` subcommands
+    ProductCommands {
+        Add(AddProductArgs) = "add" => "add-product",
+        BulkUpdate(BulkUpdateArgs) = "bulk-update" => "bulk-update",
+        Update(UpdateProductArgs) = "update" => "update-product",
+        Buildable(CheckBuildableArgs) = "buildable" => "check-buildable",
+        View(ViewProductArgs) = "view" => "view-product",
+        List(ListProductsArgs) = "list" => "list-products",
+        Migrate(MigrateOpeningBalancesArgs) = "migrate" => "migrate-opening-balances",
+        Delete(DeleteProductArgs) = "delete" => "delete-product",
+        Link(LinkProductArgs) = "link" => "link-product",
+    }
+);
+
+command_group!(
+    /// `stock` subcommands
+    StockCommands {
+        Add(AddStockArgs) = "add" => "add-stock",
+        Remove(RemoveStockArgs) = "remove" => "remove-stock",
+        Quarantine(QuarantineArgs) = "quarantine" => "quarantine",
+        Release(ReleaseQuarantineArgs) = "release" => "release-quarantine",
+        Order(MarkOrderedArgs) = "order" => "mark-ordered",
+        Condition(ChangeConditionArgs) = "condition" => "change-condition",
+        Weigh(WeighArgs) = "weigh" => "weigh",
+        Kiosk(KioskArgs) = "kiosk" => "kiosk",
+        History(HistoryArgs) = "history" => "history",
+        Receipt(ReceiptArgs) = "receipt" => "receipt",
+        Import(ImportTransactionsArgs) = "import" => "import-transactions",
+        Ack(AckArgs) = "ack" => "ack",
+    }
+);
+
+command_group!(
+    /// `report` subcommands
+    ReportCommands {
+        LowStock(LowStockArgs) = "low-stock" => "low-stock",
+        Rules(CheckRulesArgs) = "rules" => "check-rules",
+        Diff(DiffArgs) = "diff" => "diff",
+        Stats(StatsArgs) = "stats" => "stats",
+        Compare(HistoryCompareArgs) = "compare" => "history-compare",
+        ValueHistory(ValueHistoryArgs) = "value-history" => "value-history",
+        ReorderPoints(SuggestReorderPointsArgs) = "reorder-points" => "suggest-reorder-points",
+        Forecast(ForecastArgs) = "forecast" => "forecast",
+        Kpis(KpisArgs) = "kpis" => "kpis",
+        Cogs(CogsArgs) = "cogs" => "cogs",
+        Ledger(LedgerEntriesArgs) = "ledger" => "ledger-entries",
+        Shrinkage(ShrinkageArgs) = "shrinkage" => "shrinkage",
+        Consumption(ConsumptionArgs) = "consumption" => "consumption",
+        Shipments(ShipmentsArgs) = "shipments" => "shipments",
+    }
+);
+
+command_group!(
+    /// `receive` subcommands
+    ReceiveCommands {
+        Start(ReceiveStartArgs) = "start" => "receive-start",
+        Add(ReceiveAddArgs) = "add" => "receive-add",
+        Review(ReceiveReviewArgs) = "review" => "receive-review",
+        Commit(ReceiveCommitArgs) = "commit" => "receive-commit",
+        Cancel(ReceiveCancelArgs) = "cancel" => "receive-cancel",
+        Inbox(ReceiveInboxArgs) = "inbox" => "receive-inbox",
+    }
+);
+
+command_group!(
+    /// `trash` subcommands
+    TrashCommands {
+        List(TrashListArgs) = "list" => "trash-list",
+        Restore(TrashRestoreArgs) = "restore" => "trash-restore",
+    }
+);
+
+command_group!(
+    /// `data` subcommands
+    DataCommands {
+        Export(ExportArgs) = "export" => "export",
+        Convert(ConvertStorageArgs) = "convert" => "convert-storage",
+        Archive(ArchiveTransactionsArgs) = "archive" => "archive-transactions",
+        Compact(CompactArgs) = "compact" => "compact",
+        HealIds(HealIdsArgs) = "heal-ids" => "heal-ids",
+        SnapshotKpis(SnapshotKpisArgs) = "snapshot-kpis" => "snapshot-kpis",
+        VerifyLedger(VerifyLedgerArgs) = "verify-ledger" => "verify-ledger",
+        Reconcile(ReconcileArgs) = "reconcile" => "reconcile",
+        InventoryAdvice(InventoryAdviceArgs) = "inventory-advice" => "inventory-advice",
+        ShopSync(ShopSyncArgs) = "shop-sync" => "shop-sync",
+    }
+);
+
+command_group!(
+    /// `view` subcommands
+    ViewCommands {
+        Save(SaveViewArgs) = "save" => "save-view",
+        Run(RunViewArgs) = "run" => "run-view",
+        List(ListViewsArgs) = "list" => "list-views",
+        Delete(DeleteViewArgs) = "delete" => "delete-view",
+    }
+);
+
+command_group!(
+    /// `template` subcommands
+    TemplateCommands {
+        Save(SaveTemplateArgs) = "save" => "save-template",
+        List(ListTemplatesArgs) = "list" => "list-templates",
+        Delete(DeleteTemplateArgs) = "delete" => "delete-template",
+    }
+);
+
+command_group!(
+    /// `supplier` subcommands
+    SupplierCommands {
+        Import(ImportPricesArgs) = "import" => "import-prices",
+        Compare(ComparePricesArgs) = "compare" => "compare-prices",
+    }
+);
+
+command_group!(
+    /// `backorder` subcommands
+    BackorderCommands {
+        List(BackordersArgs) = "list" => "backorders",
+        Fill(FillBackordersArgs) = "fill" => "fill-backorders",
+    }
+);
+
+command_group!(
+    /// `customer` subcommands
+    CustomerCommands {
+        Add(AddCustomerArgs) = "add" => "add-customer",
+        List(ListCustomersArgs) = "list" => "list-customers",
+    }
+);
+
+command_group!(
+    /// `retention` subcommands
+    RetentionCommands {
+        Apply(RetentionApplyArgs) = "apply" => "retention-apply",
+    }
+);
+
+command_group!(
+    /// `script` subcommands
+    ScriptCommands {
+        Run(RunScriptArgs) = "run" => "run-script",
+    }
+);
+
+command_group!(
+    /// `feed` subcommands
+    FeedCommands {
+        Push(FeedPushArgs) = "push" => "feed-push",
+        Status(FeedStatusArgs) = "status" => "feed-status",
+    }
+);
+
+command_group!(
+    /// `events` subcommands
+    EventsCommands {
+        Tail(EventsTailArgs) = "tail" => "events-tail",
+    }
+);
+
+command_group!(
+    /// `stocktake` subcommands
+    StocktakeCommands {
+        Sheets(CountSheetsArgs) = "sheets" => "count-sheets",
+    }
+);
+
+/// Combine the values of (possibly repeated) `--notes` options
+fn join_notes(notes: Vec<String>) -> Option<String> {
+    if notes.is_empty() {
+        None
+    } else {
+        Some(notes.join("; "))
+    }
+}
+
+/// Make an end date include the whole day
+fn inclusive_end(end: Option<chrono::DateTime<chrono::Utc>>) -> Option<chrono::DateTime<chrono::Utc>> {
+    end.map(|end| end + Duration::days(1))
+}
+
+/// Add a new product to inventory
+///
+/// With --interactive, prompts for each field with validation and defaults
+/// --from-template fills in what isn't given from a template saved with template save; the quantity defaults to 0
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "add-product <sku> <name> <description> <quantity> <reorder_point> [--category <c>] [--supplier <s>] [--location <l>] [--unit-cost <amount>]
+       add-product <sku> <name> [<description> [<quantity> [<reorder_point>]]] --from-template <template> [<options>]
+       add-product --interactive",
+    after_help = "add-product SKU001 \"Widget\" \"A useful widget\" 100 20 --supplier Acme
+add-product BLU-2M \"blue, 2 m\" --from-template cable
+add-product --interactive"
+)]
+pub struct AddProductArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: Option<String>,
+    /// Product name
+    #[arg(value_name = "name")]
+    name: Option<String>,
+    /// Product description
+    #[arg(value_name = "description")]
+    description: Option<String>,
+    /// Units in stock
+    #[arg(value_name = "quantity", value_parser = parse_quantity)]
+    quantity: Option<u32>,
+    /// Stock level at which to reorder
+    #[arg(value_name = "reorder_point", value_parser = parse_reorder_point)]
+    reorder_point: Option<u32>,
+    /// Product category
+    #[arg(long, value_name = "c")]
+    category: Option<String>,
+    /// Supplier name
+    #[arg(long, value_name = "s")]
+    supplier: Option<String>,
+    /// Bin location
+    #[arg(long, value_name = "l")]
+    location: Option<String>,
+    /// Cost of one unit
+    #[arg(long, value_name = "amount")]
+    unit_cost: Option<Money>,
+    /// Template to fill in missing fields from
+    #[arg(long, value_name = "template")]
+    from_template: Option<String>,
+    /// Prompt for each field
+    #[arg(long)]
+    interactive: bool,
+}
+
+impl AddProductArgs {
+    fn into_command(self) -> Result<Command, String> {
+        if self.interactive {
+            let combined = self.sku.is_some()
+                || self.category.is_some()
+                || self.supplier.is_some()
+                || self.location.is_some()
+                || self.unit_cost.is_some()
+                || self.from_template.is_some();
+            if combined {
+                return Err("--interactive cannot be combined with other add-product arguments".to_string());
+            }
+            return Ok(Command::AddProductInteractive);
+        }
+
+        let (Some(sku), Some(name)) = (self.sku, self.name) else {
+            return Err(help::usage_error("add-product"));
+        };
+        if let Some(template) = self.from_template {
+            return Ok(Command::AddProductFromTemplate {
+                template,
+                sku,
+                name,
+                description: self.description,
+                quantity: self.quantity.unwrap_or(0),
+                reorder_point: self.reorder_point,
+                category: self.category,
+                supplier: self.supplier,
+                location: self.location,
+                unit_cost: self.unit_cost,
+            });
+        }
+        let (Some(description), Some(quantity), Some(reorder_point)) =
+            (self.description, self.quantity, self.reorder_point)
+        else {
+            return Err(help::usage_error("add-product"));
+        };
+
+        Ok(Command::AddProduct {
+            sku,
+            name,
+            description,
+            quantity,
+            reorder_point,
+            category: self.category,
+            supplier: self.supplier,
+            location: self.location,
+            unit_cost: self.unit_cost,
+        })
+    }
+}
+
+/// Apply the same changes to every product passing a filter
+///
+/// Without --yes, lists the products that would change and changes nothing
+/// Filters: sku (prefix or pattern), category, supplier, condition, and below-reorder=true
+/// Fields: description, reorder-point, category, supplier, location, large-threshold, unit-cost,
+/// tare-weight, unit-weight, and lead-time; an empty value clears all but description and reorder-point
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "bulk-update --filter <key=value,...> --set <field=value> [--set <field=value>...] [--yes]",
+    after_help = "bulk-update --filter category=widgets --set reorder-point=30
+bulk-update --filter category=widgets,supplier=Acme --set location=B-12 --set lead-time=14 --yes"
+)]
+pub struct BulkUpdateArgs {
+    /// Products to change, as comma-separated key=value pairs
+    #[arg(long, value_name = "key=value,...")]
+    filter: Vec<String>,
+    /// Field to change
+    #[arg(long, value_name = "field=value")]
+    set: Vec<String>,
+    /// Apply the changes instead of listing them
+    #[arg(long)]
+    yes: bool,
+}
+
+impl BulkUpdateArgs {
+    fn into_command(self) -> Result<Command, String> {
+        if self.filter.is_empty() {
+            return Err("bulk-update needs --filter, e.g. --filter category=widgets".to_string());
+        }
+        let mut filter = ProductFilter::default();
+        for item in self.filter.iter().flat_map(|filters| filters.split(',')) {
+            let (key, value) = key_value(item)?;
+            match key {
+                "sku" => filter.sku_prefix = Some(value.to_string()),
+                "category" => filter.category = Some(value.to_string()),
+                "supplier" => filter.supplier = Some(value.to_string()),
+                "condition" => filter.condition = Some(value.parse::<Condition>()?),
+                "below-reorder" => {
+                    filter.below_reorder = value.parse::<bool>()
+                        .map_err(|_| format!("Invalid below-reorder '{}': expected true or false", value))?
+                }
+                _ => {
+                    return Err(format!(
+                        "Invalid filter '{}': expected sku, category, supplier, condition, or below-reorder",
+                        key
+                    ))
+                }
+            }
+        }
+
+        if self.set.is_empty() {
+            return Err("bulk-update needs --set, e.g. --set reorder-point=30".to_string());
+        }
+        let mut changes = ProductUpdate::default();
+        for set in &self.set {
+            let (key, value) = key_value(set)?;
+            match key {
+                "description" => changes.description = Some(value.to_string()),
+                "reorder-point" => changes.reorder_point = Some(parse_reorder_point(value)?),
+                "category" => changes.category = Some(value.to_string()),
+                "supplier" => changes.supplier = Some(value.to_string()),
+                "location" => changes.location = Some(value.to_string()),
+                "large-threshold" => changes.large_movement_threshold = Some(optional(value, str::parse::<MovementThreshold>)?),
+                "unit-cost" => changes.unit_cost = Some(optional(value, str::parse::<Money>)?),
+                "tare-weight" => changes.tare_weight = Some(optional(value, str::parse::<Weight>)?),
+                "unit-weight" => changes.unit_weight = Some(optional(value, str::parse::<Weight>)?),
+                "lead-time" => changes.lead_time_days = Some(optional(value, parse_lead_time)?),
+                _ => {
+                    return Err(format!(
+                        "Invalid field '{}': expected description, reorder-point, category, supplier, location, \
+                         large-threshold, unit-cost, tare-weight, unit-weight, or lead-time",
+                        key
+                    ))
+                }
+            }
+        }
+        Ok(Command::BulkUpdate { filter, changes, confirm: self.yes })
+    }
+}
+
+/// Update an existing product's details
+///
+/// An empty value clears category, supplier, location, barcode, large-threshold, components, unit-cost, a weight, or lead-time
+/// --barcode sets a code the kiosk accepts in place of the SKU
+/// --components makes the product a kit assembled from the listed products
+/// --unit-cost sets the cost of one unit (e.g. 12.50), used to value the stock
+/// --tare-weight and --unit-weight (e.g. 120g, 1.5kg) let weigh count the product by weight
+/// --lead-time sets how many days a reorder takes to arrive, used by suggest-reorder-points
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "update-product <sku> [--name <name>] [--description <desc>] [--reorder-point <n>] [--category <c>] [--supplier <s>] [--location <l>] [--barcode <code>] [--large-threshold <n|n%>] [--components <sku:qty,...>] [--unit-cost <amount>] [--tare-weight <weight>] [--unit-weight <weight>] [--lead-time <days>]",
+    after_help = "update-product SKU001 --name \"New Widget\" --reorder-point 30
+update-product SKU001 --unit-cost 12.50"
+)]
+pub struct UpdateProductArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: String,
+    /// New name
+    #[arg(long, value_name = "name")]
+    name: Option<String>,
+    /// New description
+    #[arg(long, value_name = "desc")]
+    description: Option<String>,
+    /// New reorder point
+    #[arg(long, value_name = "n", value_parser = parse_reorder_point)]
+    reorder_point: Option<u32>,
+    /// New category
+    #[arg(long, value_name = "c")]
+    category: Option<String>,
+    /// New supplier
+    #[arg(long, value_name = "s")]
+    supplier: Option<String>,
+    /// New bin location
+    #[arg(long, value_name = "l")]
+    location: Option<String>,
+    /// Code the kiosk accepts in place of the SKU
+    #[arg(long, value_name = "code")]
+    barcode: Option<String>,
+    /// Movement size that needs confirming
+    #[arg(long, value_name = "n|n%", value_parser = |value: &str| optional(value, str::parse::<MovementThreshold>))]
+    large_threshold: Option<Clearable<MovementThreshold>>,
+    /// Kit components
+    #[arg(long, value_name = "sku:qty,...", value_parser = parse_components)]
+    components: Option<ComponentList>,
+    /// Cost of one unit
+    #[arg(long, value_name = "amount", value_parser = |value: &str| optional(value, str::parse::<Money>))]
+    unit_cost: Option<Clearable<Money>>,
+    /// Weight of the empty container
+    #[arg(long, value_name = "weight", value_parser = |value: &str| optional(value, str::parse::<Weight>))]
+    tare_weight: Option<Clearable<Weight>>,
+    /// Weight of one unit
+    #[arg(long, value_name = "weight", value_parser = |value: &str| optional(value, str::parse::<Weight>))]
+    unit_weight: Option<Clearable<Weight>>,
+    /// Days a reorder takes to arrive
+    #[arg(long, value_name = "days", value_parser = |value: &str| optional(value, parse_lead_time))]
+    lead_time: Option<Clearable<u32>>,
+}
+
+impl UpdateProductArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::UpdateProduct {
+            sku: self.sku,
+            name: self.name,
+            description: self.description,
+            reorder_point: self.reorder_point,
+            category: self.category,
+            supplier: self.supplier,
+            location: self.location,
+            barcode: self.barcode,
+            large_threshold: self.large_threshold,
+            components: self.components,
+            unit_cost: self.unit_cost,
+            tare_weight: self.tare_weight,
+            unit_weight: self.unit_weight,
+            lead_time: self.lead_time,
+        })
+    }
+}
+
+/// Report how many kits can be assembled from current component stock
+///
+/// The component that runs out first is marked as limiting
+#[derive(Debug, Args)]
+#[command(verbatim_doc_comment, override_usage = "check-buildable <kit-sku>", after_help = "check-buildable KIT-001")]
+pub struct CheckBuildableArgs {
+    /// Kit SKU
+    #[arg(value_name = "kit-sku")]
+    sku: String,
+}
+
+impl CheckBuildableArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::CheckBuildable { sku: self.sku })
+    }
+}
+
+/// View details of a specific product
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "view-product <sku> [--template <file>]",
+    after_help = "view-product SKU001
+view-product SKU001 --template shelf-tag.hbs"
+)]
+pub struct ViewProductArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: String,
+    /// Handlebars template to render the product with
+    #[arg(long, value_name = "file")]
+    template: Option<String>,
+}
+
+impl ViewProductArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::ViewProduct { sku: self.sku, template: self.template })
+    }
+}
+
+/// Filters and sort order shared by list-products and view save
+#[derive(Debug, Args)]
+pub struct ProductFilterArgs {
+    /// SKU prefix or pattern
+    #[arg(long, value_name = "pattern")]
+    sku_prefix: Option<String>,
+    /// Category to list
+    #[arg(long, value_name = "c")]
+    category: Option<String>,
+    /// Supplier to list
+    #[arg(long, value_name = "s")]
+    supplier: Option<String>,
+    /// Stock condition to list
+    #[arg(long, value_name = "condition", value_parser = choice::<Condition>())]
+    condition: Option<Condition>,
+    /// List only products at or below their reorder point
+    #[arg(long)]
+    below_reorder: bool,
+    /// Sort order
+    #[arg(long, value_name = "order", value_parser = choice::<ProductSort>())]
+    sort: Option<ProductSort>,
+}
+
+impl From<ProductFilterArgs> for ProductFilter {
+    fn from(args: ProductFilterArgs) -> Self {
+        ProductFilter {
+            sku_prefix: args.sku_prefix,
+            category: args.category,
+            supplier: args.supplier,
+            condition: args.condition,
+            below_reorder: args.below_reorder,
+            sort: args.sort.unwrap_or_default(),
+        }
+    }
+}
+
+/// List all products in inventory, in SKU order
+///
+/// --sku-prefix lists only SKUs starting with the pattern, which may use * and ? wildcards
+/// --category and --supplier list only products with that category or supplier
+/// --condition lists only products with stock in that condition: new, refurbished, or damaged
+/// --below-reorder lists only products at or below their reorder point
+/// --sort orders the list by sku, name, or quantity
+/// --columns shows a table of the comma-separated columns: sku, name, quantity, reorder-point,
+/// category, supplier, location, unit-cost, and value (stock on hand at FIFO cost)
+/// --totals adds a row totalling quantity, reorder point, and value; alone it shows sku, name, and quantity
+/// --group-by lists the count, units, and value of the products per category, supplier, or location
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "list-products [<filters>] [--columns <list>] [--totals]
+       list-products [<filters>] --group-by <category|supplier|location> [--totals]
+       list-products [<filters>] [--group-by <field>] [--template <file>]",
+    after_help = "list-products
+list-products --sku-prefix WID
+list-products --sku-prefix 'ACME-?-*'
+list-products --category widgets --below-reorder --sort quantity
+list-products --condition refurbished
+list-products --category widgets --columns sku,name,location,quantity,value --totals
+list-products --group-by supplier --totals
+list-products --template catalogue.hbs"
+)]
+pub struct ListProductsArgs {
+    #[command(flatten)]
+    filter: ProductFilterArgs,
+    /// Handlebars template to render the listing with
+    #[arg(long, value_name = "file")]
+    template: Option<String>,
+    /// Columns of the table
+    #[arg(long, value_name = "list", value_parser = parse_columns)]
+    columns: Option<ColumnList>,
+    /// Add a totals row
+    #[arg(long)]
+    totals: bool,
+    /// Field to total the products by
+    #[arg(long, value_name = "category|supplier|location", value_parser = choice::<GroupBy>())]
+    group_by: Option<GroupBy>,
+}
+
+impl ListProductsArgs {
+    fn into_command(self) -> Result<Command, String> {
+        if self.template.is_some() && (self.columns.is_some() || self.totals) {
+            return Err("--template lays out the listing itself and cannot be combined with --columns or --totals".to_string());
+        }
+        if self.group_by.is_some() && self.columns.is_some() {
+            return Err("--group-by lists group totals and cannot be combined with --columns".to_string());
+        }
+        Ok(Command::ListProducts {
+            filter: self.filter.into(),
+            template: self.template,
+            columns: self.columns.unwrap_or_default(),
+            totals: self.totals,
+            group_by: self.group_by,
+        })
+    }
+}
+
+/// Create products with their stock from a legacy system as opening balances at the cutover date
+///
+/// The file holds SKU,QTY,REORDER_POINT,NAME lines; every SKU must be new
+/// Nothing is created if any line is invalid; afterwards expected and created totals are reconciled
+/// --merge allows existing SKUs: name and reorder-point take keep, overwrite, or fill-if-empty, and quantity keep, add, or set
+/// Everything is kept by default; a catalog refresh might use name=overwrite and a stock sync quantity=set
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "migrate-opening-balances <file> --date <YYYY-MM-DD> [--merge <field>=<strategy>[,...]]",
+    after_help = "migrate-opening-balances legacy-stock.csv --date 2025-01-01
+migrate-opening-balances counts.csv --date 2025-01-01 --merge quantity=set"
+)]
+pub struct MigrateOpeningBalancesArgs {
+    /// File of opening balances
+    #[arg(value_name = "file")]
+    file: String,
+    /// Cutover date
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+    date: Option<chrono::DateTime<chrono::Utc>>,
+    /// How to merge existing SKUs
+    #[arg(long, value_name = "field=strategy")]
+    merge: Vec<String>,
+}
+
+impl MigrateOpeningBalancesArgs {
+    fn into_command(self) -> Result<Command, String> {
+        let cutover = self.date.ok_or("migrate-opening-balances requires the cutover date: add --date <YYYY-MM-DD>")?;
+        Ok(Command::MigrateOpeningBalances { file: self.file, cutover, merge: parse_merge_policy(&self.merge)? })
+    }
+}
+
+/// Delete a product and all its transactions
+///
+/// Refused while the product has stock or recent transactions unless --force is given
+/// Deleted products and their history go to the trash and can be brought back with trash-restore
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "delete-product <sku> [--force]",
+    after_help = "delete-product SKU001
+delete-product SKU001 --force"
+)]
+pub struct DeleteProductArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: String,
+    /// Delete even with stock or recent transactions
+    #[arg(long)]
+    force: bool,
+}
+
+impl DeleteProductArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::DeleteProduct { sku: self.sku, force: self.force })
+    }
+}
+
+/// Link a product to another: substitute-for if it can be sold in its place, accessory-of if it is sold alongside it
+///
+/// low-stock and view-product suggest in-stock substitutes for products that are out of stock
+/// --remove takes the link away
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "link-product <sku> <substitute-for|accessory-of> <sku> [--remove]",
+    after_help = "link-product SKU002 substitute-for SKU001
+link-product CASE-01 accessory-of PHONE-01 --remove"
+)]
+pub struct LinkProductArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: String,
+    /// Kind of link
+    #[arg(value_name = "substitute-for|accessory-of", value_parser = choice::<LinkKind>())]
+    kind: LinkKind,
+    /// SKU of the linked product
+    #[arg(value_name = "sku")]
+    target: String,
+    /// Take the link away
+    #[arg(long)]
+    remove: bool,
+}
+
+impl LinkProductArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::LinkProduct { sku: self.sku, kind: self.kind, target: self.target, remove: self.remove })
+    }
+}
+
+/// Build a bulk stock command reading its entries from stdin
+fn stock_from_stdin(
+    command: &str,
+    transaction_type: TransactionType,
+    has_entry: bool,
+    has_condition: bool,
+    confirm_large: bool,
+    reason: Option<ReasonCode>,
+) -> Result<Command, String> {
+    if has_entry {
+        return Err(format!(
+            "--from-stdin cannot be combined with a SKU, quantity, or --notes; give them per line as SKU,QTY[,NOTES] ({})",
+            command
+        ));
+    }
+    if has_condition {
+        return Err(format!("--from-stdin only moves new stock; --condition needs a single SKU ({})", command));
+    }
+    Ok(Command::StockFromStdin { transaction_type, confirm_large, reason })
+}
+
+/// Add stock to a product
+///
+/// Repeated --notes are combined
+/// --condition records refurbished or damaged stock, e.g. returns (default: new)
+/// --unit-cost records what each unit cost on this receipt (default: the product's unit cost)
+/// --from-stdin reads SKU,QTY[,NOTES] lines; nothing is applied unless every line is valid
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "add-stock <sku> <quantity> [--condition <condition>] [--unit-cost <amount>] [--notes <notes>]
+       add-stock --from-stdin",
+    after_help = "add-stock SKU001 50 --notes \"Received shipment\"
+add-stock SKU001 3 --condition refurbished --notes \"Customer returns\"
+add-stock SKU001 100 --unit-cost 2.35 --notes \"PO 1042\"
+add-stock --from-stdin < packing-list.txt"
+)]
+pub struct AddStockArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: Option<String>,
+    /// Units to add
+    #[arg(value_name = "quantity", value_parser = parse_stock_quantity)]
+    quantity: Option<u32>,
+    /// Notes recorded with the transaction
+    #[arg(long, value_name = "notes")]
+    notes: Vec<String>,
+    /// Condition of the stock added
+    #[arg(long, value_name = "condition", value_parser = choice::<Condition>())]
+    condition: Option<Condition>,
+    /// Cost of each unit on this receipt
+    #[arg(long, value_name = "amount")]
+    unit_cost: Option<Money>,
+    /// Read SKU,QTY[,NOTES] lines from stdin
+    #[arg(long)]
+    from_stdin: bool,
+}
+
+impl AddStockArgs {
+    fn into_command(self) -> Result<Command, String> {
+        if self.from_stdin {
+            let has_entry = self.sku.is_some() || !self.notes.is_empty();
+            return stock_from_stdin("add-stock", TransactionType::Addition, has_entry, self.condition.is_some(), false, None);
+        }
+        let (Some(sku), Some(quantity)) = (self.sku, self.quantity) else {
+            return Err(help::usage_error("add-stock"));
+        };
+        Ok(Command::AddStock {
+            sku,
+            quantity,
+            notes: join_notes(self.notes),
+            condition: self.condition,
+            unit_cost: self.unit_cost,
+        })
+    }
+}
+
+/// Remove stock from a product
+///
+/// --type records a loss instead of an issue: damage, write-off, or adjustment (default: removal)
+/// --reason tags the removal for the consumption report: sale, internal-use, sample, or damage
+/// Removals above the large-movement threshold require --confirm-large
+/// --condition takes the stock from that condition; otherwise new stock goes first
+/// --backorder removes what is available and backorders the rest instead of failing
+/// --customer records which customer the removal was shipped to
+/// --from-stdin reads SKU,QTY[,NOTES] lines; nothing is applied unless every line is valid
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "remove-stock <sku> <quantity> [--type <type>] [--reason <reason>] [--condition <condition>] [--notes <notes>] [--confirm-large] [--backorder] [--customer <code>]
+       remove-stock --from-stdin [--type <type>] [--reason <reason>] [--confirm-large]",
+    after_help = "remove-stock SKU001 10 --reason sale --customer ACME --notes \"Order 1042\"
+remove-stock SKU001 40 --backorder --notes \"Order 1043\"
+remove-stock SKU001 2 --type damage --notes \"Dropped forklift\"
+remove-stock --from-stdin < picked.txt"
+)]
+pub struct RemoveStockArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: Option<String>,
+    /// Units to remove
+    #[arg(value_name = "quantity", value_parser = parse_stock_quantity)]
+    quantity: Option<u32>,
+    /// Notes recorded with the transaction
+    #[arg(long, value_name = "notes")]
+    notes: Vec<String>,
+    /// Kind of removal
+    #[arg(long = "type", value_name = "type", value_parser = parse_removal_type)]
+    transaction_type: Option<TransactionType>,
+    /// Reason code for the consumption report
+    #[arg(long, value_name = "reason", value_parser = choice::<ReasonCode>())]
+    reason: Option<ReasonCode>,
+    /// Condition to take the stock from
+    #[arg(long, value_name = "condition", value_parser = choice::<Condition>())]
+    condition: Option<Condition>,
+    /// Customer the removal was shipped to
+    #[arg(long, value_name = "code")]
+    customer: Option<String>,
+    /// Allow a removal above the large-movement threshold
+    #[arg(long)]
+    confirm_large: bool,
+    /// Read SKU,QTY[,NOTES] lines from stdin
+    #[arg(long)]
+    from_stdin: bool,
+    /// Backorder what isn't available
+    #[arg(long)]
+    backorder: bool,
+}
+
+impl RemoveStockArgs {
+    fn into_command(self) -> Result<Command, String> {
+        let transaction_type = self.transaction_type.unwrap_or(TransactionType::Removal);
+        if self.from_stdin {
+            let has_entry = self.sku.is_some() || !self.notes.is_empty();
+            return stock_from_stdin(
+                "remove-stock",
+                transaction_type,
+                has_entry,
+                self.condition.is_some(),
+                self.confirm_large,
+                self.reason,
+            );
+        }
+        let (Some(sku), Some(quantity)) = (self.sku, self.quantity) else {
+            return Err(help::usage_error("remove-stock"));
+        };
+        Ok(Command::RemoveStock {
+            sku,
+            quantity,
+            notes: join_notes(self.notes),
+            confirm_large: self.confirm_large,
+            transaction_type,
+            reason: self.reason,
+            condition: self.condition,
+            backorder: self.backorder,
+            customer: self.customer,
+        })
+    }
+}
+
+/// Put available stock on hold, e.g. goods awaiting QC
+///
+/// Quarantined units can't be removed and don't count toward the reorder point
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "quarantine <sku> <quantity> [--notes <notes>]",
+    after_help = "quarantine SKU001 12 --notes \"Awaiting QC\""
+)]
+pub struct QuarantineArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: String,
+    /// Units to put on hold
+    #[arg(value_name = "quantity", value_parser = parse_stock_quantity)]
+    quantity: u32,
+    /// Notes recorded with the transaction
+    #[arg(long, value_name = "notes")]
+    notes: Vec<String>,
+}
+
+impl QuarantineArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::Quarantine { sku: self.sku, quantity: self.quantity, notes: join_notes(self.notes), release: false })
+    }
+}
+
+/// Make quarantined stock available again
+///
+/// To scrap units that failed QC, release them and record a write-off
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "release-quarantine <sku> <quantity> [--notes <notes>]",
+    after_help = "release-quarantine SKU001 12 --notes \"QC passed\""
+)]
+pub struct ReleaseQuarantineArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: String,
+    /// Units to release
+    #[arg(value_name = "quantity", value_parser = parse_stock_quantity)]
+    quantity: u32,
+    /// Notes recorded with the transaction
+    #[arg(long, value_name = "notes")]
+    notes: Vec<String>,
+}
+
+impl ReleaseQuarantineArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::Quarantine { sku: self.sku, quantity: self.quantity, notes: join_notes(self.notes), release: true })
+    }
+}
+
+/// Record units ordered from a supplier but not yet received
+///
+/// Units on order are taken off the shortfall low-stock reports; adding new stock or committing a receipt takes them off the order
+/// --cancel takes units off the order instead
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "mark-ordered <sku> <quantity> [--cancel]",
+    after_help = "mark-ordered SKU001 50
+mark-ordered SKU001 10 --cancel"
+)]
+pub struct MarkOrderedArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: String,
+    /// Units ordered
+    #[arg(value_name = "quantity", value_parser = parse_stock_quantity)]
+    quantity: u32,
+    /// Take units off the order
+    #[arg(long)]
+    cancel: bool,
+}
+
+impl MarkOrderedArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::MarkOrdered { sku: self.sku, quantity: self.quantity, cancel: self.cancel })
+    }
+}
+
+/// Move available stock from one condition to another: new, refurbished, or damaged
+///
+/// --from defaults to new; the total quantity doesn't change
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "change-condition <sku> <quantity> --to <condition> [--from <condition>] [--notes <notes>]",
+    after_help = "change-condition SKU001 2 --to damaged --notes \"Water damage\"
+change-condition SKU001 2 --from damaged --to refurbished"
+)]
+pub struct ChangeConditionArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: String,
+    /// Units to move
+    #[arg(value_name = "quantity", value_parser = parse_stock_quantity)]
+    quantity: u32,
+    /// Condition the stock is in
+    #[arg(long, value_name = "condition", value_parser = choice::<Condition>())]
+    from: Option<Condition>,
+    /// Condition to move the stock to
+    #[arg(long, value_name = "condition", value_parser = choice::<Condition>())]
+    to: Option<Condition>,
+    /// Notes recorded with the transaction
+    #[arg(long, value_name = "notes")]
+    notes: Vec<String>,
+}
+
+impl ChangeConditionArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::ChangeCondition {
+            sku: self.sku,
+            quantity: self.quantity,
+            from: self.from.unwrap_or_default(),
+            to: self.to.ok_or("change-condition requires --to <condition>")?,
+            notes: join_notes(self.notes),
+        })
+    }
+}
+
+/// Add stock counted by weight on a scale, or remove it with --out
+///
+/// The product's tare weight is taken off and the rest divided by its unit weight, rounded to whole units
+/// The reading is recorded in the transaction notes
+/// Reading the scale on --port (default /dev/ttyUSB0, 9600 baud) needs a build with --features scale
+/// --weight gives the gross reading instead, e.g. 12.4kg
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "weigh <sku> [--out] [--weight <weight>] [--port <device>] [--baud <n>] [--notes <notes>]",
+    after_help = "weigh BOLT-M6 --port /dev/ttyUSB1
+weigh BOLT-M6 --out --weight 2.35kg --notes \"Job 88\""
+)]
+pub struct WeighArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: String,
+    /// Remove the weighed stock
+    #[arg(long)]
+    out: bool,
+    /// Gross reading, instead of reading the scale
+    #[arg(long, value_name = "weight")]
+    weight: Option<Weight>,
+    /// Serial port of the scale
+    #[arg(long, value_name = "device", default_value = DEFAULT_SCALE_PORT)]
+    port: String,
+    /// Baud rate of the scale
+    #[arg(long, value_name = "n", value_parser = parse_baud, default_value = DEFAULT_SCALE_BAUD)]
+    baud: u32,
+    /// Notes recorded with the transaction
+    #[arg(long, value_name = "notes")]
+    notes: Vec<String>,
+}
+
+impl WeighArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::Weigh {
+            sku: self.sku,
+            remove: self.out,
+            weight: self.weight,
+            port: self.port,
+            baud: self.baud,
+            notes: join_notes(self.notes),
+        })
+    }
+}
+
+/// Locked-down mode for a shared terminal: only scan-in, scan-out, and view
+///
+/// unlock <code> allows other commands until 'lock'; set the code as kiosk.unlock_code in config.json
+/// A barcode shared by several products asks which one was meant and remembers the answer for the session
+#[derive(Debug, Args)]
+#[command(verbatim_doc_comment, override_usage = "kiosk", after_help = "kiosk")]
+pub struct KioskArgs {}
+
+impl KioskArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::Kiosk)
+    }
+}
+
+/// View transaction history for a product
+///
+/// Datetime format: YYYY-MM-DDTHH:MM:SS
+/// --include-archived also searches transactions moved by archive-transactions
+/// --customer shows only removals shipped to that customer
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "history <sku> [--start <datetime>] [--end <datetime>] [--include-archived] [--customer <code>] [--template <file>]",
+    after_help = "history SKU001 --start 2025-01-01T00:00:00 --end 2025-12-31T23:59:59
+history SKU001 --customer ACME"
+)]
+pub struct HistoryArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: String,
+    /// Earliest transaction time
+    #[arg(long, value_name = "datetime", value_parser = parse_datetime)]
+    start: Option<chrono::DateTime<chrono::Utc>>,
+    /// Latest transaction time
+    #[arg(long, value_name = "datetime", value_parser = parse_datetime)]
+    end: Option<chrono::DateTime<chrono::Utc>>,
+    /// Also search archived transactions
+    #[arg(long)]
+    include_archived: bool,
+    /// Handlebars template to render the history with
+    #[arg(long, value_name = "file")]
+    template: Option<String>,
+    /// Customer the removals were shipped to
+    #[arg(long, value_name = "code")]
+    customer: Option<String>,
+}
+
+impl HistoryArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::History {
+            sku: self.sku,
+            start: self.start,
+            end: self.end,
+            include_archived: self.include_archived,
+            customer: self.customer,
+            template: self.template,
+        })
+    }
+}
+
+/// Print a goods-received or goods-issued note for a stock movement
+///
+/// The transaction ID may be shortened to the 8 characters shown by history
+/// PDF receipts must be written to a file with --output
+/// --format zpl or escpos makes a job for Zebra label printers or thermal receipt printers
+/// --printer sends the receipt straight to tcp://host[:port] (port 9100 by default) or usb://<device>
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "receipt <transaction-id> [--format text|pdf|zpl|escpos] [--output <file> | --printer <address>]",
+    after_help = "receipt 1a2b3c4d
+receipt 1a2b3c4d --format pdf --output grn.pdf
+receipt 1a2b3c4d --format escpos --printer tcp://192.168.1.50
+receipt 1a2b3c4d --format zpl --printer usb:///dev/usb/lp0"
+)]
+pub struct ReceiptArgs {
+    /// Transaction ID, or its first 8 characters
+    #[arg(value_name = "transaction-id")]
+    transaction_id: String,
+    /// Receipt format
+    #[arg(long, value_name = "text|pdf|zpl|escpos", value_parser = choice::<ReceiptFormat>())]
+    format: Option<ReceiptFormat>,
+    /// File to write the receipt to
+    #[arg(long, value_name = "file")]
+    output: Option<String>,
+    /// Printer to send the receipt to
+    #[arg(long, value_name = "address")]
+    printer: Option<Printer>,
+}
+
+impl ReceiptArgs {
+    fn into_command(self) -> Result<Command, String> {
+        let format = self.format.unwrap_or(ReceiptFormat::Text);
+        if self.output.is_some() && self.printer.is_some() {
+            return Err("Use either --output or --printer, not both".to_string());
+        }
+        if format == ReceiptFormat::Pdf && self.output.is_none() && self.printer.is_none() {
+            return Err("PDF receipts must be written to a file: add --output <file>".to_string());
+        }
+        if format == ReceiptFormat::EscPos && self.output.is_none() && self.printer.is_none() {
+            return Err("ESC/POS receipts must be sent to a printer or file: add --printer <address> or --output <file>".to_string());
+        }
+        Ok(Command::Receipt { transaction_id: self.transaction_id, format, output: self.output, printer: self.printer })
+    }
+}
+
+/// Import past stock movements with their original timestamps, e.g. when migrating from a spreadsheet
+///
+/// The file holds SKU,TIMESTAMP,TYPE,QTY[,NOTES] lines; TYPE is addition, removal, damage, write-off, or adjustment
+/// TIMESTAMP is YYYY-MM-DD, YYYY-MM-DDTHH:MM:SS (UTC), or RFC 3339; list each product's movements oldest first
+/// Quantities are recomputed from the full history; nothing is imported if any line is invalid or stock would go negative
+/// --profile reads another system's CSV with a header line, or a .json array of objects, through an import_profiles mapping in config.json
+/// Files of more than 500 rows are imported in chunks; if one fails, --resume <job-id> carries on after the last chunk saved
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "import-transactions [--profile <name>] <file>
+       import-transactions --resume <job-id>",
+    after_help = "import-transactions history.csv
+import-transactions --profile acme shipments.csv
+import-transactions --resume 3f9c2a1b"
+)]
+pub struct ImportTransactionsArgs {
+    /// File of transactions
+    #[arg(value_name = "file")]
+    file: Option<String>,
+    /// Import profile in config.json
+    #[arg(long, value_name = "name")]
+    profile: Option<String>,
+    /// Job to carry on with
+    #[arg(long, value_name = "job-id")]
+    resume: Option<String>,
+}
+
+impl ImportTransactionsArgs {
+    fn into_command(self) -> Result<Command, String> {
+        if let Some(job) = self.resume {
+            if self.file.is_some() || self.profile.is_some() {
+                return Err("--resume carries on with the job's own file and profile; don't give them again".to_string());
+            }
+            return Ok(Command::ResumeImport { job });
+        }
+        let file = self.file.ok_or("import-transactions needs a file, or --resume <job-id>")?;
+        Ok(Command::ImportTransactions { file, profile: self.profile })
+    }
+}
+
+/// List products with stock at or below reorder point
+///
+/// Grouped output includes per-group subtotals of units short
+/// Products acknowledged with ack are left out until their snooze ends
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "low-stock [--group-by supplier|category|location] [--template <file>]",
+    after_help = "low-stock --group-by supplier
+low-stock --group-by supplier --template order-sheet.hbs"
+)]
+pub struct LowStockArgs {
+    /// Field to group the products by
+    #[arg(long, value_name = "supplier|category|location", value_parser = choice::<GroupBy>())]
+    group_by: Option<GroupBy>,
+    /// Handlebars template to render the report with
+    #[arg(long, value_name = "file")]
+    template: Option<String>,
+}
+
+impl LowStockArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::LowStock { group_by: self.group_by, template: self.template })
+    }
+}
+
+/// List the alert rules in config.json and the products each one matches now
+///
+/// Rules are also checked after every command, which warns when a rule starts matching a product
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "check-rules",
+    after_help = "check-rules
+report rules"
+)]
+pub struct CheckRulesArgs {}
+
+impl CheckRulesArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::CheckRules)
+    }
+}
+
+/// Acknowledge a product's low-stock alert so low-stock leaves it out, e.g. while a reorder is on its way
+///
+/// The alert shows again on the --until date, when stock falls below today's level, or after a restock
+/// --clear withdraws the acknowledgement
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "ack <sku> [--until <YYYY-MM-DD>]
+       ack <sku> --clear",
+    after_help = "ack SKU001 --until 2025-03-31
+ack SKU001 --clear"
+)]
+pub struct AckArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: String,
+    /// Date the alert shows again
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_day)]
+    until: Option<chrono::NaiveDate>,
+    /// Withdraw the acknowledgement
+    #[arg(long)]
+    clear: bool,
+}
+
+impl AckArgs {
+    fn into_command(self) -> Result<Command, String> {
+        if self.clear && self.until.is_some() {
+            return Err("--clear withdraws the acknowledgement and cannot be combined with --until".to_string());
+        }
+        Ok(Command::Ack { sku: self.sku, until: self.until, clear: self.clear })
+    }
+}
+
+/// Show products added, removed, and changed in quantity between two states of the inventory
+///
+/// A snapshot is a data directory, a backup of one, or a products.json file
+/// With one snapshot, compare it with the current inventory; --since rebuilds the state at that date from transactions
+/// --verify first checks that each snapshot is an export signed with the key's private half (export --sign)
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "diff <snapshot-a> [<snapshot-b>] [--verify <public-key>]
+       diff --since <YYYY-MM-DD>",
+    after_help = "diff backups/2025-01-31 backups/2025-02-28
+diff --since 2025-02-01
+diff partner-count --verify partner.pub"
+)]
+pub struct DiffArgs {
+    /// Snapshots to compare
+    #[arg(value_name = "snapshot", num_args = 0..=2)]
+    snapshots: Vec<String>,
+    /// Date to rebuild the inventory at
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    /// Public key the snapshots must be signed with
+    #[arg(long, value_name = "public-key")]
+    verify: Option<String>,
+}
+
+impl DiffArgs {
+    fn into_command(self) -> Result<Command, String> {
+        if self.since.is_some() && self.verify.is_some() {
+            return Err("--verify checks the signature of snapshots and cannot be combined with --since".to_string());
+        }
+        match (self.since.is_some(), self.snapshots.len()) {
+            (true, 0) | (false, 1..) => Ok(Command::Diff { snapshots: self.snapshots, since: self.since, verify: self.verify }),
+            (true, _) => Err("--since compares with the current inventory and cannot be combined with snapshots".to_string()),
+            (false, _) => Err(help::usage_error("diff")),
+        }
+    }
+}
+
+/// Show product, unit, and value totals, overall and per category, and the number of low-stock products
+#[derive(Debug, Args)]
+#[command(verbatim_doc_comment, override_usage = "stats", after_help = "stats")]
+pub struct StatsArgs {}
+
+impl StatsArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::Stats)
+    }
+}
+
+/// Compare the movements of up to 10 products side by side, e.g. products that substitute for each other
+///
+/// Shows units received, removed, and lost, the net change, the number of movements, and the units on hand
+/// --merge lists their transactions together in time order instead
+/// Datetime format: YYYY-MM-DDTHH:MM:SS
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "history-compare <sku> <sku>... [--start <datetime>] [--end <datetime>] [--merge]",
+    after_help = "history-compare SKU001 SKU002
+history-compare SKU001 SKU002 --start 2025-01-01T00:00:00 --merge"
+)]
+pub struct HistoryCompareArgs {
+    /// Products to compare
+    #[arg(value_name = "sku", required = true, num_args = 2..=10)]
+    skus: Vec<String>,
+    /// Earliest transaction time
+    #[arg(long, value_name = "datetime", value_parser = parse_datetime)]
+    start: Option<chrono::DateTime<chrono::Utc>>,
+    /// Latest transaction time
+    #[arg(long, value_name = "datetime", value_parser = parse_datetime)]
+    end: Option<chrono::DateTime<chrono::Utc>>,
+    /// List the transactions together in time order
+    #[arg(long)]
+    merge: bool,
+}
+
+impl HistoryCompareArgs {
+    fn into_command(self) -> Result<Command, String> {
+        let skus = self.skus;
+        if let Some((index, sku)) = skus.iter().enumerate().find(|(index, sku)| skus[..*index].contains(sku)) {
+            return Err(format!("SKU '{}' is listed twice (argument {})", sku, index + 1));
+        }
+        Ok(Command::HistoryCompare { skus, start: self.start, end: self.end, merge: self.merge })
+    }
+}
+
+/// Show units on hand and their value at the start of each period, and at the end
+///
+/// Stock is rebuilt from the transaction history and valued at the cost each receipt came in at, oldest issued first
+/// --interval defaults to month; the period runs from the start of this year to now unless given
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "value-history [--interval <day|week|month|quarter|year>] [--start <YYYY-MM-DD>] [--end <YYYY-MM-DD>]",
+    after_help = "value-history
+value-history --interval quarter --start 2025-01-01 --end 2025-12-31"
+)]
+pub struct ValueHistoryArgs {
+    /// Length of each period
+    #[arg(long, value_name = "day|week|month|quarter|year", value_parser = choice::<Interval>())]
+    interval: Option<Interval>,
+    /// First day of the history
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+    start: Option<chrono::DateTime<chrono::Utc>>,
+    /// Last day of the history
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+    end: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ValueHistoryArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::ValueHistory {
+            interval: self.interval.unwrap_or_default(),
+            start: self.start,
+            end: inclusive_end(self.end),
+        })
+    }
+}
+
+/// Suggest reorder points that cover demand over each product's lead time, plus safety stock
+///
+/// Demand is the units removed per day over the last --days (default 90); safety stock grows with its variability
+/// --service-level is the chance of not running out before a reorder arrives (default 0.95)
+/// --lead-time is used for products without one set by update-product --lead-time (default 7 days)
+/// --apply asks about each change before setting it
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "suggest-reorder-points [--service-level <0.5-1>] [--lead-time <days>] [--days <n>] [--apply]",
+    after_help = "suggest-reorder-points
+suggest-reorder-points --service-level 0.99 --apply"
+)]
+pub struct SuggestReorderPointsArgs {
+    /// Chance of not running out before a reorder arrives
+    #[arg(long, value_name = "0.5-1", value_parser = parse_service_level)]
+    service_level: Option<f64>,
+    /// Lead time of products without one
+    #[arg(long, value_name = "days", value_parser = parse_lead_time)]
+    lead_time: Option<u32>,
+    /// Days of demand to look back over
+    #[arg(long, value_name = "n", value_parser = parse_days)]
+    days: Option<u32>,
+    /// Ask about each change before setting it
+    #[arg(long)]
+    apply: bool,
+}
+
+impl SuggestReorderPointsArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::SuggestReorderPoints {
+            service_level: self.service_level.unwrap_or(reorder::DEFAULT_SERVICE_LEVEL),
+            lead_time: self.lead_time,
+            days: self.days,
+            apply: self.apply,
+        })
+    }
+}
+
+/// Forecast when each product runs out, soonest first, from the units removed per day over the last --days
+///
+/// Stock committed to backorders isn't counted; products not running out within --horizon days (default 90) come last
+/// --seasonality scales demand by weekday or calendar month, defaulting to forecast.seasonality in config.json
+/// --days defaults to 90, or 365 with monthly seasonality
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "forecast [--seasonality <none|weekly|monthly>] [--days <n>] [--horizon <n>]",
+    after_help = "forecast
+forecast --seasonality weekly --horizon 30"
+)]
+pub struct ForecastArgs {
+    /// How demand varies over time
+    #[arg(long, value_name = "none|weekly|monthly", value_parser = choice::<Seasonality>())]
+    seasonality: Option<Seasonality>,
+    /// Days of demand to look back over
+    #[arg(long, value_name = "n", value_parser = parse_days)]
+    days: Option<u32>,
+    /// Days to forecast
+    #[arg(long, value_name = "n", value_parser = parse_days)]
+    horizon: Option<u32>,
+}
+
+impl ForecastArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::Forecast { seasonality: self.seasonality, days: self.days, horizon: self.horizon })
+    }
+}
+
+/// Chart the daily totals recorded by snapshot-kpis, oldest first
+///
+/// Each day shows units available, stock value with a bar to scale, low-stock products, and movements
+/// --start and --end limit the days shown, both included
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "kpis [--start <YYYY-MM-DD>] [--end <YYYY-MM-DD>]",
+    after_help = "report kpis
+report kpis --start 2025-01-01 --end 2025-03-31"
+)]
+pub struct KpisArgs {
+    /// First day shown
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_day)]
+    start: Option<chrono::NaiveDate>,
+    /// Last day shown
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_day)]
+    end: Option<chrono::NaiveDate>,
+}
+
+impl KpisArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::Kpis { start: self.start, end: self.end.map(|end| end + Duration::days(1)) })
+    }
+}
+
+/// Show the cost of the goods removed in a period, by product
+///
+/// Removals take units from the oldest receipts first, at the cost each was received at
+/// --start and --end limit the period to those dates, both included; losses are left to shrinkage
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "cogs [--start <YYYY-MM-DD>] [--end <YYYY-MM-DD>]",
+    after_help = "cogs --start 2025-01-01 --end 2025-03-31"
+)]
+pub struct CogsArgs {
+    /// First day of the period
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+    start: Option<chrono::DateTime<chrono::Utc>>,
+    /// Last day of the period
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+    end: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl CogsArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::Cogs { start: self.start, end: inclusive_end(self.end) })
+    }
+}
+
+/// Write receipts and issues valued at cost as double-entry beancount or ledger-cli entries
+///
+/// Receipts debit Assets:Inventory; removals, damage, write-offs, and adjustments credit it against an expense
+/// Removals are valued at the oldest receipts' cost; movements with no known cost are left out
+/// --format defaults to beancount and --currency to USD; --start and --end limit the period, both included
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "ledger-entries [--format <beancount|ledger>] [--start <YYYY-MM-DD>] [--end <YYYY-MM-DD>] [--currency <code>]",
+    after_help = "ledger-entries --start 2025-01-01 --end 2025-03-31 > q1.beancount
+report ledger --format ledger --currency EUR"
+)]
+pub struct LedgerEntriesArgs {
+    /// Bookkeeping format
+    #[arg(long, value_name = "beancount|ledger", value_parser = choice::<BookFormat>())]
+    format: Option<BookFormat>,
+    /// First day of the period
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+    start: Option<chrono::DateTime<chrono::Utc>>,
+    /// Last day of the period
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+    end: Option<chrono::DateTime<chrono::Utc>>,
+    /// Currency of the amounts
+    #[arg(long, value_name = "code", value_parser = parse_currency, default_value = "USD")]
+    currency: String,
+}
+
+impl LedgerEntriesArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::LedgerEntries {
+            format: self.format.unwrap_or_default(),
+            start: self.start,
+            end: inclusive_end(self.end),
+            currency: self.currency,
+        })
+    }
+}
+
+/// Summarize losses recorded as damage, write-off, or adjustment by product and type
+///
+/// --start and --end limit the period to those dates, both included
+/// --condition counts only stock in that condition
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "shrinkage [--start <YYYY-MM-DD>] [--end <YYYY-MM-DD>] [--condition <condition>]",
+    after_help = "shrinkage --start 2025-01-01 --end 2025-03-31
+shrinkage --condition refurbished"
+)]
+pub struct ShrinkageArgs {
+    /// First day of the period
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+    start: Option<chrono::DateTime<chrono::Utc>>,
+    /// Last day of the period
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+    end: Option<chrono::DateTime<chrono::Utc>>,
+    /// Condition of the stock to count
+    #[arg(long, value_name = "condition", value_parser = choice::<Condition>())]
+    condition: Option<Condition>,
+}
+
+impl ShrinkageArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::Shrinkage { start: self.start, end: inclusive_end(self.end), condition: self.condition })
+    }
+}
+
+/// Summarize units removed by reason code, for all products or one
+///
+/// Removals recorded without --reason are listed as unspecified
+/// --start and --end limit the period to those dates, both included
+/// --condition counts only stock in that condition
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "consumption [<sku>] [--start <YYYY-MM-DD>] [--end <YYYY-MM-DD>] [--condition <condition>]",
+    after_help = "consumption --start 2025-01-01 --end 2025-03-31
+consumption SKU001 --condition refurbished"
+)]
+pub struct ConsumptionArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: Option<String>,
+    /// First day of the period
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+    start: Option<chrono::DateTime<chrono::Utc>>,
+    /// Last day of the period
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+    end: Option<chrono::DateTime<chrono::Utc>>,
+    /// Condition of the stock to count
+    #[arg(long, value_name = "condition", value_parser = choice::<Condition>())]
+    condition: Option<Condition>,
+}
+
+impl ConsumptionArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::Consumption {
+            sku: self.sku,
+            start: self.start,
+            end: inclusive_end(self.end),
+            condition: self.condition,
+        })
+    }
+}
+
+/// Start staging a delivery; stock only changes on receive commit
+///
+/// --expected reads the SKU,QTY lines ordered, to review variances against
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "receive-start [<reference>] [--expected <file>]",
+    after_help = "receive-start PO-17 --expected po-17.csv"
+)]
+pub struct ReceiveStartArgs {
+    /// Delivery reference, e.g. the purchase order
+    #[arg(value_name = "reference")]
+    reference: Option<String>,
+    /// File of the quantities ordered
+    #[arg(long, value_name = "file")]
+    expected: Option<String>,
+}
+
+impl ReceiveStartArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::ReceiveStart { reference: self.reference, expected_file: self.expected })
+    }
+}
+
+/// Stage units counted in the delivery being received
+///
+/// A product can be added more than once, e.g. once per carton
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "receive-add <sku> <quantity> [--notes <notes>]",
+    after_help = "receive-add SKU001 24"
+)]
+pub struct ReceiveAddArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: String,
+    /// Units counted
+    #[arg(value_name = "quantity", value_parser = parse_stock_quantity)]
+    quantity: u32,
+    /// Notes recorded with the transaction
+    #[arg(long, value_name = "notes")]
+    notes: Vec<String>,
+}
+
+impl ReceiveAddArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::ReceiveAdd { sku: self.sku, quantity: self.quantity, notes: join_notes(self.notes) })
+    }
+}
+
+/// Show the staged delivery and its variances against the expected quantities
+#[derive(Debug, Args)]
+#[command(verbatim_doc_comment, override_usage = "receive-review", after_help = "receive-review")]
+pub struct ReceiveReviewArgs {}
+
+impl ReceiveReviewArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::ReceiveReview)
+    }
+}
+
+/// Add the staged delivery to stock, one addition per staged line
+///
+/// Lines without notes are noted with the receipt reference
+#[derive(Debug, Args)]
+#[command(verbatim_doc_comment, override_usage = "receive-commit", after_help = "receive-commit")]
+pub struct ReceiveCommitArgs {}
+
+impl ReceiveCommitArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::ReceiveCommit)
+    }
+}
+
+/// Discard the staged delivery without changing stock
+#[derive(Debug, Args)]
+#[command(verbatim_doc_comment, override_usage = "receive-cancel", after_help = "receive-cancel")]
+pub struct ReceiveCancelArgs {}
+
+impl ReceiveCancelArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::ReceiveCancel)
+    }
+}
+
+/// Stage the shipping notices suppliers listed under inbox.suppliers in config.json have e-mailed to a maildir
+///
+/// A notice's SKU,QTY lines come from its CSV attachment or body, its reference from the end of the subject
+/// Lists the staged notices; --start begins receiving one with its lines as the expected quantities
+/// Reading mail needs a build with --features email
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "receive-inbox [<maildir>]
+       receive-inbox --start <reference>",
+    after_help = "receive inbox ~/Maildir/asn
+receive inbox --start PO-17"
+)]
+pub struct ReceiveInboxArgs {
+    /// Maildir the notices are delivered to
+    #[arg(value_name = "maildir")]
+    maildir: Option<String>,
+    /// Reference of the notice to start receiving
+    #[arg(long, value_name = "reference")]
+    start: Option<String>,
+}
+
+impl ReceiveInboxArgs {
+    fn into_command(self) -> Result<Command, String> {
+        if self.start.is_some() && self.maildir.is_some() {
+            return Err("Use either a maildir or --start, not both".to_string());
+        }
+        Ok(Command::ReceiveInbox { maildir: self.maildir, start: self.start })
+    }
+}
+
+/// List deleted products that can still be restored
+///
+/// Products are purged once they have been in the trash longer than the retention period
+#[derive(Debug, Args)]
+#[command(verbatim_doc_comment, override_usage = "trash-list", after_help = "trash-list")]
+pub struct TrashListArgs {}
+
+impl TrashListArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::TrashList)
+    }
+}
+
+/// Restore a deleted product and its transactions from the trash
+///
+/// If the SKU was deleted more than once, the most recent deletion is restored
+#[derive(Debug, Args)]
+#[command(verbatim_doc_comment, override_usage = "trash-restore <sku>", after_help = "trash-restore SKU001")]
+pub struct TrashRestoreArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: String,
+}
+
+impl TrashRestoreArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::TrashRestore { sku: self.sku })
+    }
+}
+
+/// Write products and transactions to a new data directory
+///
+/// --anonymize replaces notes, operator names, customer codes, and suppliers with tokens, keeping SKUs and quantities
+/// --sign adds a SHA-256 manifest and its Ed25519 signature; make a key with openssl genpkey -algorithm ed25519
+/// --format cdc writes each transaction's stock change to changes.ndjson as a Debezium-style record with before and after images
+/// --since leaves out changes up to that sequence number; quiet output is the last one written, for the next --since
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "export <dir> [--anonymize] [--sign <private-key>]
+       export <dir> --format cdc [--since <seq>]",
+    after_help = "export support-case --anonymize
+export transfer-0042 --sign keys/warehouse.pem
+export changes-0042 --format cdc --since 1200"
+)]
+pub struct ExportArgs {
+    /// Directory to write to
+    #[arg(value_name = "dir")]
+    dir: String,
+    /// Replace personal data with tokens
+    #[arg(long)]
+    anonymize: bool,
+    /// Private key to sign the export with
+    #[arg(long, value_name = "private-key")]
+    sign: Option<String>,
+    /// What to export
+    #[arg(long, value_name = "format", value_parser = choice::<ExportFormat>())]
+    format: Option<ExportFormat>,
+    /// Last change already exported
+    #[arg(long, value_name = "seq", value_parser = parse_seq)]
+    since: Option<u64>,
+}
+
+impl ExportArgs {
+    fn into_command(self) -> Result<Command, String> {
+        let format = self.format.unwrap_or_default();
+        match format {
+            ExportFormat::Cdc if self.anonymize || self.sign.is_some() => {
+                return Err("--anonymize and --sign apply to dataset exports, not --format cdc".to_string());
+            }
+            ExportFormat::Dataset if self.since.is_some() => {
+                return Err("--since only applies to --format cdc".to_string());
+            }
+            _ => {}
+        }
+        Ok(Command::Export { dir: self.dir, anonymize: self.anonymize, sign: self.sign, format, since: self.since })
+    }
+}
+
+/// Copy every kind of stored data into an empty data directory, possibly on another backend
+///
+/// The copy is read back and its record counts and checksums compared with the source
+/// The source is only read; switch to the target once the conversion succeeds
+/// Backends: json (the default for both)
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "convert-storage <target-dir> [--from <backend>] [--to <backend>]",
+    after_help = "convert-storage /srv/stock-new
+convert-storage /srv/stock-new --from json --to json"
+)]
+pub struct ConvertStorageArgs {
+    /// Empty directory to copy into
+    #[arg(value_name = "target-dir")]
+    target: String,
+    /// Backend of the current data
+    #[arg(long, value_name = "backend", value_parser = PossibleValuesParser::new(convert::BACKENDS), default_value = "json")]
+    from: String,
+    /// Backend of the copy
+    #[arg(long, value_name = "backend", value_parser = PossibleValuesParser::new(convert::BACKENDS), default_value = "json")]
+    to: String,
+}
+
+impl ConvertStorageArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::ConvertStorage { target: self.target, from: self.from, to: self.to })
+    }
+}
+
+/// Move transactions before a date to archive/transactions-<year>.ndjson
+///
+/// Archived transactions no longer load at startup; view them with history --include-archived
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "archive-transactions --before <YYYY-MM-DD>",
+    after_help = "archive-transactions --before 2024-01-01"
+)]
+pub struct ArchiveTransactionsArgs {
+    /// First day to keep in the ledger
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+    before: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ArchiveTransactionsArgs {
+    fn into_command(self) -> Result<Command, String> {
+        let before = self.before.ok_or("archive-transactions requires a date: add --before <YYYY-MM-DD>")?;
+        Ok(Command::ArchiveTransactions { before })
+    }
+}
+
+/// Rewrite the data files without duplicate records or expired trash, and report space reclaimed
+///
+/// --archive-older-than moves older transactions to archive/transactions-<year>.ndjson
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "compact [--archive-older-than <years>]",
+    after_help = "compact
+compact --archive-older-than 2"
+)]
+pub struct CompactArgs {
+    /// Years of transactions to keep in the ledger
+    #[arg(long, value_name = "years", value_parser = parse_years)]
+    archive_older_than: Option<u32>,
+}
+
+impl CompactArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::Compact { archive_years: self.archive_older_than })
+    }
+}
+
+/// Give a new ID to every product and transaction whose ID an earlier record already has
+///
+/// The first record with an ID keeps it; each change is recorded in the audit log
+/// Run it before compact, which keeps only the first transaction with an ID
+/// --dry-run lists the records that would get new IDs without changing anything
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "heal-ids [--dry-run]",
+    after_help = "heal-ids --dry-run
+data heal-ids"
+)]
+pub struct HealIdsArgs {
+    /// List the changes without making them
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl HealIdsArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::HealIds { dry_run: self.dry_run })
+    }
+}
+
+/// Clear notes and customer codes older than the retention section of config.json allows
+///
+/// Covers transactions in the ledger, archive, and trash, and outstanding backorders
+/// --dry-run reports how many records would be cleared without changing anything
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "retention-apply [--dry-run]",
+    after_help = "retention apply --dry-run
+retention apply"
+)]
+pub struct RetentionApplyArgs {
+    /// Report the changes without making them
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl RetentionApplyArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::ApplyRetention { dry_run: self.dry_run })
+    }
+}
+
+/// Record today's units available, stock value, low-stock count, and movements in kpis.ndjson
+///
+/// Meant to run daily from cron; running it again the same day replaces that day's totals
+/// The totals come from the current stock, so the transaction history isn't replayed
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "snapshot-kpis",
+    after_help = "snapshot-kpis
+data snapshot-kpis"
+)]
+pub struct SnapshotKpisArgs {}
+
+impl SnapshotKpisArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::SnapshotKpis)
+    }
+}
+
+/// Check the transaction hash chain for changed, removed, or inserted records
+///
+/// Needs ledger.hash_chain in config.json; the ledger, archive, and trash are all checked
+/// --against also checks that the chain head saved in a backup is still on the chain
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "verify-ledger [--against <backup-dir>]",
+    after_help = "verify-ledger
+verify-ledger --against backups/2025-01-31"
+)]
+pub struct VerifyLedgerArgs {
+    /// Backup whose chain head to check
+    #[arg(long, value_name = "backup-dir")]
+    against: Option<String>,
+}
+
+impl VerifyLedgerArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::VerifyLedger { against: self.against })
+    }
+}
+
+/// Compare available quantities with a CSV export from another system, such as an ERP or web shop, or a stock count
+///
+/// The export's header names its columns; --key defaults to sku and --qty-column to quantity
+/// Lists products whose quantities differ and SKUs only one side has
+/// --apply adds or removes (as adjustments) units so products on both sides match the export
+/// Variances beyond the stocktake tolerance in config.json are held until --recount gives the same quantity or --confirm names the SKU
+/// --sheet reads only that count-sheets sheet's lines and leaves out products not on it
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "reconcile <export.csv> [--key <column>] [--qty-column <column>] [--sheet <n>] [--apply [--recount <file>] [--confirm <sku>]...]",
+    after_help = "reconcile erp-export.csv --key sku --qty-column onhand
+reconcile shop.csv --key \"Item ID\" --apply
+reconcile count.csv --qty-column counted --apply --recount recount.csv --confirm SKU001"
+)]
+pub struct ReconcileArgs {
+    /// CSV export to compare with
+    #[arg(value_name = "export.csv")]
+    file: String,
+    /// Column holding the SKU
+    #[arg(long, value_name = "column", default_value = "sku")]
+    key: String,
+    /// Column holding the quantity
+    #[arg(long, value_name = "column", default_value = "quantity")]
+    qty_column: String,
+    /// Make the quantities match the export
+    #[arg(long)]
+    apply: bool,
+    /// Recount of the held variances
+    #[arg(long, value_name = "file")]
+    recount: Option<String>,
+    /// SKU whose variance to apply without a recount
+    #[arg(long, value_name = "sku")]
+    confirm: Vec<String>,
+    /// Count sheet the lines come from
+    #[arg(long, value_name = "n", value_parser = parse_sheet)]
+    sheet: Option<usize>,
+}
+
+impl ReconcileArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::Reconcile {
+            file: self.file,
+            key: self.key,
+            qty_column: self.qty_column,
+            apply: self.apply,
+            recount: self.recount,
+            confirm: self.confirm,
+            sheet: self.sheet,
+        })
+    }
+}
+
+/// Write numbered count sheets of the products to count, one location after another
+///
+/// Each line shows the bin location, barcode, and recorded quantity, with a counted column to fill in
+/// --blind leaves the recorded quantities off; --location takes locations starting with it, --category one category
+/// Enter the filled-in CSV a sheet at a time with reconcile <file> --qty-column counted --sheet <n>; PDF sheets must go to a file with --output
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "count-sheets [--location <prefix>] [--category <category>] [--blind] [--format csv|pdf] [--output <file>]",
+    after_help = "count-sheets --location A --blind --output aisle-a.csv
+stocktake sheets --category Fasteners --format pdf --output fasteners.pdf
+reconcile aisle-a.csv --qty-column counted --sheet 2 --apply"
+)]
+pub struct CountSheetsArgs {
+    /// Start of the locations to count
+    #[arg(long, value_name = "prefix")]
+    location: Option<String>,
+    /// Category to count
+    #[arg(long, value_name = "category")]
+    category: Option<String>,
+    /// Leave the recorded quantities off
+    #[arg(long)]
+    blind: bool,
+    /// Sheet format
+    #[arg(long, value_name = "csv|pdf", value_parser = choice::<SheetFormat>())]
+    format: Option<SheetFormat>,
+    /// File to write the sheets to
+    #[arg(long, value_name = "file")]
+    output: Option<String>,
+}
+
+impl CountSheetsArgs {
+    fn into_command(self) -> Result<Command, String> {
+        let format = self.format.unwrap_or_default();
+        if format == SheetFormat::Pdf && self.output.is_none() {
+            return Err("PDF count sheets must be written to a file: add --output <file>".to_string());
+        }
+        Ok(Command::CountSheets {
+            location: self.location,
+            category: self.category,
+            blind: self.blind,
+            format,
+            output: self.output,
+        })
+    }
+}
+
+/// Write every product's available and on-order units as an EDI 846 inventory advice for retail partners
+///
+/// The X12 interchange (version 004010) needs the partners' IDs; --control defaults to YYDDDHHMM
+/// --format csv or ndjson writes the same positions as CSV, or one JSON product per line, for partners that don't take X12
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "inventory-advice --sender <id> --receiver <id> [--control <number>] [--output <file>]
+       inventory-advice --format <csv|ndjson> [--output <file>]",
+    after_help = "inventory-advice --sender ACMESUPPLY --receiver BIGRETAIL --output feed.edi
+export-846 --format csv"
+)]
+pub struct InventoryAdviceArgs {
+    /// Feed format
+    #[arg(long, value_name = "csv|ndjson", value_parser = choice::<FeedFormat>())]
+    format: Option<FeedFormat>,
+    /// Our interchange ID
+    #[arg(long, value_name = "id")]
+    sender: Option<String>,
+    /// The partner's interchange ID
+    #[arg(long, value_name = "id")]
+    receiver: Option<String>,
+    /// Interchange control number
+    #[arg(long, value_name = "number", value_parser = parse_control)]
+    control: Option<u32>,
+    /// File to write the feed to
+    #[arg(long, value_name = "file")]
+    output: Option<String>,
+}
+
+impl InventoryAdviceArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::InventoryAdvice {
+            format: self.format.unwrap_or_default(),
+            sender: self.sender,
+            receiver: self.receiver,
+            control: self.control,
+            output: self.output,
+        })
+    }
+}
+
+/// Push every mapped product's available stock to the Shopify or WooCommerce store in config.json
+///
+/// SKUs are paired with store IDs in shop-mapping.csv (SKU,STORE_ID lines) unless another file is configured
+/// Stock changes are also pushed after every command; --dry-run shows the requests without sending them
+/// Needs a build with --features shop
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "shop-sync [--dry-run]",
+    after_help = "shop-sync --dry-run
+data shop-sync"
+)]
+pub struct ShopSyncArgs {
+    /// Show the requests without sending them
+    #[arg(long)]
+    dry_run: bool,
+}
+
+impl ShopSyncArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::ShopSync { dry_run: self.dry_run })
+    }
+}
+
+/// Summarize units shipped to each customer by product
+///
+/// Only removals recorded with remove-stock --customer are counted
+/// --start and --end limit the period to those dates, both included
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "shipments [--customer <code>] [--start <YYYY-MM-DD>] [--end <YYYY-MM-DD>]",
+    after_help = "shipments --start 2025-03-01 --end 2025-03-31
+shipments --customer ACME"
+)]
+pub struct ShipmentsArgs {
+    /// Customer to report on
+    #[arg(long, value_name = "code")]
+    customer: Option<String>,
+    /// First day of the period
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+    start: Option<chrono::DateTime<chrono::Utc>>,
+    /// Last day of the period
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_date)]
+    end: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl ShipmentsArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::Shipments { customer: self.customer, start: self.start, end: inclusive_end(self.end) })
+    }
+}
+
+/// Add a customer that removals can be shipped to
+///
+/// The code identifies the customer in remove-stock --customer, history --customer, and shipments
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "add-customer <code> <name>",
+    after_help = "add-customer ACME \"Acme Corporation\""
+)]
+pub struct AddCustomerArgs {
+    /// Customer code
+    #[arg(value_name = "code")]
+    code: String,
+    /// Customer name
+    #[arg(value_name = "name")]
+    name: String,
+}
+
+impl AddCustomerArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::AddCustomer { code: self.code, name: self.name })
+    }
+}
+
+/// List customers by code
+#[derive(Debug, Args)]
+#[command(verbatim_doc_comment, override_usage = "list-customers", after_help = "list-customers")]
+pub struct ListCustomersArgs {}
+
+impl ListCustomersArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::ListCustomers)
+    }
+}
+
+/// List outstanding backorders, oldest first
+///
+/// Backorders are recorded by remove-stock --backorder when stock runs short
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "backorders [--sku <sku>]",
+    after_help = "backorders
+backorders --sku SKU001"
+)]
+pub struct BackordersArgs {
+    /// Product SKU
+    #[arg(long, value_name = "sku")]
+    sku: Option<String>,
+}
+
+impl BackordersArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::Backorders { sku: self.sku })
+    }
+}
+
+/// Remove available stock to fill a product's backorders, oldest first
+///
+/// add-stock reports waiting backorders; set backorders.auto_allocate in config.json to fill them automatically
+#[derive(Debug, Args)]
+#[command(verbatim_doc_comment, override_usage = "fill-backorders <sku>", after_help = "fill-backorders SKU001")]
+pub struct FillBackordersArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: String,
+}
+
+impl FillBackordersArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::FillBackorders { sku: self.sku })
+    }
+}
+
+/// Store a supplier's price list, keeping earlier prices on record
+///
+/// The file holds SKU,PRICE[,EFFECTIVE_DATE] lines; EFFECTIVE_DATE is YYYY-MM-DD and defaults to today
+/// A price for a date the supplier already priced replaces the old one; nothing is stored if any line is invalid
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "import-prices <supplier> <file>",
+    after_help = "import-prices \"Acme Supply\" acme-2025.csv"
+)]
+pub struct ImportPricesArgs {
+    /// Supplier name
+    #[arg(value_name = "supplier")]
+    supplier: String,
+    /// Price list file
+    #[arg(value_name = "file")]
+    file: String,
+}
+
+impl ImportPricesArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::ImportPrices { supplier: self.supplier, file: self.file })
+    }
+}
+
+/// Show each supplier's current price for a product, cheapest first
+///
+/// --date compares the prices in force on that day instead of today
+/// low-stock names the cheapest supplier of each product that has prices
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "compare-prices <sku> [--date <YYYY-MM-DD>]",
+    after_help = "compare-prices SKU001
+compare-prices SKU001 --date 2025-07-01"
+)]
+pub struct ComparePricesArgs {
+    /// Product SKU
+    #[arg(value_name = "sku")]
+    sku: String,
+    /// Day to compare the prices on
+    #[arg(long, value_name = "YYYY-MM-DD", value_parser = parse_day)]
+    date: Option<chrono::NaiveDate>,
+}
+
+impl ComparePricesArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::ComparePrices { sku: self.sku, on: self.date })
+    }
+}
+
+/// Save list-products filters and sort order under a name in config.json
+///
+/// Takes the same filters as list-products; saving an existing name replaces it
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "save-view <name> [<filters>]",
+    after_help = "view save low-widgets --category widgets --below-reorder"
+)]
+pub struct SaveViewArgs {
+    /// View name
+    #[arg(value_name = "name")]
+    name: String,
+    #[command(flatten)]
+    filter: ProductFilterArgs,
+}
+
+impl SaveViewArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::SaveView { name: self.name, filter: self.filter.into() })
+    }
+}
+
+/// List the products matching a saved view
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "run-view <name> [--template <file>]",
+    after_help = "view run low-widgets"
+)]
+pub struct RunViewArgs {
+    /// View name
+    #[arg(value_name = "name")]
+    name: String,
+    /// Handlebars template to render the listing with
+    #[arg(long, value_name = "file")]
+    template: Option<String>,
+}
+
+impl RunViewArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::RunView { name: self.name, template: self.template })
+    }
+}
+
+/// List saved views and their filters
+#[derive(Debug, Args)]
+#[command(verbatim_doc_comment, override_usage = "list-views", after_help = "view list")]
+pub struct ListViewsArgs {}
+
+impl ListViewsArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::ListViews)
+    }
+}
+
+/// Remove a saved view from config.json
+#[derive(Debug, Args)]
+#[command(verbatim_doc_comment, override_usage = "delete-view <name>", after_help = "view delete low-widgets")]
+pub struct DeleteViewArgs {
+    /// View name
+    #[arg(value_name = "name")]
+    name: String,
+}
+
+impl DeleteViewArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::DeleteView { name: self.name })
+    }
+}
+
+/// Save defaults for add-product --from-template under a name in config.json
+///
+/// --sku-prefix is put in front of the SKU given; --name-pattern is the name with {name} for the one given
+/// Saving an existing name replaces it
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "save-template <name> [--sku-prefix <p>] [--name-pattern <pattern>] [--description <d>] [--reorder-point <n>] [--category <c>] [--supplier <s>] [--location <l>] [--unit-cost <amount>]",
+    after_help = "template save cable --sku-prefix CBL- --name-pattern \"Cable, {name}\" --reorder-point 10 --supplier Acme"
+)]
+pub struct SaveTemplateArgs {
+    /// Template name
+    #[arg(value_name = "name")]
+    name: String,
+    /// Put in front of the SKU given
+    #[arg(long, value_name = "p")]
+    sku_prefix: Option<String>,
+    /// Product name, with {name} for the one given
+    #[arg(long, value_name = "pattern")]
+    name_pattern: Option<String>,
+    /// Product description
+    #[arg(long, value_name = "d")]
+    description: Option<String>,
+    /// Reorder point
+    #[arg(long, value_name = "n", value_parser = parse_reorder_point)]
+    reorder_point: Option<u32>,
+    /// Product category
+    #[arg(long, value_name = "c")]
+    category: Option<String>,
+    /// Supplier name
+    #[arg(long, value_name = "s")]
+    supplier: Option<String>,
+    /// Bin location
+    #[arg(long, value_name = "l")]
+    location: Option<String>,
+    /// Cost of one unit
+    #[arg(long, value_name = "amount")]
+    unit_cost: Option<Money>,
+}
+
+impl SaveTemplateArgs {
+    fn into_command(self) -> Result<Command, String> {
+        let template = ProductTemplate {
+            sku_prefix: self.sku_prefix,
+            name_pattern: self.name_pattern,
+            description: self.description,
+            reorder_point: self.reorder_point,
+            category: self.category,
+            supplier: self.supplier,
+            location: self.location,
+            unit_cost: self.unit_cost,
+        };
+        Ok(Command::SaveProductTemplate { name: self.name, template })
+    }
+}
+
+/// List saved product templates and their defaults
+#[derive(Debug, Args)]
+#[command(verbatim_doc_comment, override_usage = "list-templates", after_help = "template list")]
+pub struct ListTemplatesArgs {}
+
+impl ListTemplatesArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::ListProductTemplates)
+    }
+}
+
+/// Remove a saved product template from config.json
+#[derive(Debug, Args)]
+#[command(verbatim_doc_comment, override_usage = "delete-template <name>", after_help = "template delete cable")]
+pub struct DeleteTemplateArgs {
+    /// Template name
+    #[arg(value_name = "name")]
+    name: String,
+}
+
+impl DeleteTemplateArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::DeleteProductTemplate { name: self.name })
+    }
+}
+
+/// Run a Rhai script for a custom report or check; it can read the inventory but not change it
+///
+/// Scripts call products(), product(sku), low_stock(), transactions([sku]), sum, count_by, and group_by
+/// Up to 16 arguments after the file are passed in args; put -- before any that start with -
+/// Needs a build with --features script
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "run-script <file> [<arg>...]",
+    after_help = "script run report.rhai
+script run check-costs.rhai tools"
+)]
+pub struct RunScriptArgs {
+    /// Script file
+    #[arg(value_name = "file")]
+    path: String,
+    /// Arguments passed to the script
+    #[arg(value_name = "arg", num_args = 0..=16)]
+    args: Vec<String>,
+}
+
+impl RunScriptArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::RunScript { path: self.path, args: self.args })
+    }
+}
+
+/// Render the feeds configured under feeds in config.json and upload them to SFTP, S3, or a directory
+///
+/// Without names every feed is pushed; --due pushes only those whose interval_minutes have passed
+/// Run feed push --due from cron every few minutes to deliver feeds on schedule; failures exit non-zero
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "feed-push [<name>...] [--due]",
+    after_help = "feed push
+feed push bigretail
+feed push --due"
+)]
+pub struct FeedPushArgs {
+    /// Feeds to push
+    #[arg(value_name = "name", num_args = 0..=16)]
+    names: Vec<String>,
+    /// Push only the feeds that are due
+    #[arg(long)]
+    due: bool,
+}
+
+impl FeedPushArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::FeedPush { names: self.names, due: self.due })
+    }
+}
+
+/// Show each configured feed, when it was last pushed, and its last error
+#[derive(Debug, Args)]
+#[command(verbatim_doc_comment, override_usage = "feed-status", after_help = "feed status")]
+pub struct FeedStatusArgs {}
+
+impl FeedStatusArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::FeedStatus)
+    }
+}
+
+/// Print the events recorded in events.ndjson after a sequence number, one JSON object per line
+///
+/// Every event a command raises is appended there with the next seq; a consumer passes the last one it handled
+#[derive(Debug, Args)]
+#[command(
+    verbatim_doc_comment,
+    override_usage = "events-tail [--since <seq>] [--limit <n>]",
+    after_help = "events tail --since 41
+events tail --limit 100"
+)]
+pub struct EventsTailArgs {
+    /// Last event already handled
+    #[arg(long, value_name = "seq", value_parser = parse_seq)]
+    since: Option<u64>,
+    /// Most events to print
+    #[arg(long, value_name = "n", value_parser = parse_limit)]
+    limit: Option<usize>,
+}
+
+impl EventsTailArgs {
+    fn into_command(self) -> Result<Command, String> {
+        Ok(Command::EventsTail { since: self.since.unwrap_or(0), limit: self.limit })
+    }
+}
+
+/// Show help for every command, or one command
+#[derive(Debug, Args)]
+#[command(override_usage = "help [<command> | <group> <subcommand>] [--format text|json]")]
+pub struct HelpArgs {
+    /// Command, or group and subcommand, to show help for
+    #[arg(value_name = "command", num_args = 0..=2)]
+    names: Vec<String>,
+    /// Help format
+    #[arg(long, value_name = "text|json")]
+    format: Option<String>,
+}
+
+impl HelpArgs {
+    fn into_command(self) -> Result<Command, String> {
+        let json = match self.format.as_deref() {
+            None | Some("text") => false,
+            Some("json") => true,
+            Some(other) => return Err(format!("Invalid help format '{}': expected text or json", other)),
+        };
+        let command = help::resolve_names(&self.names)?;
+        Ok(match (command, json) {
+            (command, true) => Command::HelpJson { command },
+            (Some(command), false) => Command::CommandHelp { command },
+            (None, false) => Command::Help,
+        })
+    }
+}
@@ -0,0 +1,578 @@
+// Bulk stock entries, transaction imports (plain or through an import profile), opening balances, and price lists read from standard input or a file
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::BufRead;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+
+use crate::mapping::{ImportField, ImportProfile};
+use crate::models::{Money, TransactionType};
+use crate::service::{ExternalQuantity, HistoricalEntry, OpeningBalance, PriceEntry, StockEntry};
+use crate::validation::ValidationReport;
+
+/// An entry together with the input line it came from
+#[derive(Debug, PartialEq)]
+pub struct NumberedEntry<T> {
+    /// One-based line number in the input
+    pub line: usize,
+    /// The parsed entry
+    pub entry: T,
+}
+
+/// Entries read from input, with any lines that could not be parsed
+#[derive(Debug)]
+pub struct BulkInput<T> {
+    /// Entries parsed from valid lines
+    pub entries: Vec<NumberedEntry<T>>,
+    /// Problems with malformed lines, by line number
+    pub report: ValidationReport,
+}
+
+impl<T> BulkInput<T> {
+    /// Split into the parsed entries, their line numbers, and the report
+    pub fn into_parts(self) -> (Vec<T>, Vec<usize>, ValidationReport) {
+        let (lines, entries) = self.entries.into_iter().map(|numbered| (numbered.line, numbered.entry)).unzip();
+        (entries, lines, self.report)
+    }
+}
+
+/// Stock movements read for `add-stock` or `remove-stock --from-stdin`
+pub type StockInput = BulkInput<StockEntry>;
+
+/// Past movements read for `import-transactions`
+pub type HistoryInput = BulkInput<HistoricalEntry>;
+
+/// Products and quantities read for `migrate-opening-balances`
+pub type OpeningInput = BulkInput<OpeningBalance>;
+
+/// A supplier's prices read for `import-prices`
+pub type PriceInput = BulkInput<PriceEntry>;
+
+/// Quantities read from another system's export for `reconcile`
+pub type ExternalInput = BulkInput<ExternalQuantity>;
+
+/// Read `SKU,QTY[,NOTES]` lines into stock entries
+///
+/// Blank lines and lines starting with `#` are skipped. Notes may contain
+/// commas. Malformed lines are recorded in the report rather than stopping
+/// the read, so they can be reported together with any stock problems.
+pub fn read_stock_entries<R: BufRead>(input: R) -> Result<StockInput, String> {
+    read_entries(input, parse_stock_line, "No stock entries read from stdin; expected lines of SKU,QTY[,NOTES]")
+}
+
+/// Read the `SKU,QTY` lines of the quantities expected in a delivery
+///
+/// Lines are skipped and reported as in `read_stock_entries`.
+pub fn read_expected_receipt<R: BufRead>(input: R) -> Result<StockInput, String> {
+    read_entries(input, parse_stock_line, "No expected lines read; expected lines of SKU,QTY")
+}
+
+/// Read `SKU,TIMESTAMP,TYPE,QTY[,NOTES]` lines into historical transactions
+///
+/// TIMESTAMP is `YYYY-MM-DDTHH:MM:SS` (UTC), an RFC 3339 timestamp with an
+/// offset, or a plain `YYYY-MM-DD` date (midnight UTC). TYPE is `addition`
+/// or `removal`, or a loss type such as `damage`. Lines are skipped and reported as in `read_stock_entries`.
+pub fn read_historical_entries<R: BufRead>(input: R) -> Result<HistoryInput, String> {
+    read_entries(input, parse_historical_line, "No transactions read; expected lines of SKU,TIMESTAMP,TYPE,QTY[,NOTES]")
+}
+
+/// Read another system's movements through an import profile
+///
+/// A CSV file starts with a header line naming its columns; a JSON file
+/// (`json`) is an array of objects, numbered from one. Columns and keys are
+/// matched ignoring case. A field the file has no column for must have a
+/// default in the profile, except notes. Records are skipped and reported as
+/// in `read_stock_entries`.
+pub fn read_mapped_history<R: BufRead>(input: R, profile: &ImportProfile, json: bool) -> Result<HistoryInput, String> {
+    let records = if json { json_records(input)? } else { csv_records(input)? };
+    if records.is_empty() {
+        return Err("No transactions read; the file has no records".to_string());
+    }
+    let columns: BTreeSet<&str> = records.iter().flat_map(|(_, record)| record.keys()).map(String::as_str).collect();
+    for field in ImportField::ALL {
+        let column = profile.column(field);
+        if field != ImportField::Notes
+            && !profile.has_default(field)
+            && !columns.iter().any(|name| name.eq_ignore_ascii_case(column))
+        {
+            let columns: Vec<&str> = columns.into_iter().collect();
+            return Err(format!(
+                "The file has no '{}' column for {} and the profile gives no default; its columns are {}",
+                column, field, columns.join(", ")
+            ));
+        }
+    }
+
+    let mut entries = Vec::new();
+    let mut report = ValidationReport::default();
+    for (line_number, record) in &records {
+        report.rows += 1;
+        let value = |field: ImportField| {
+            let column = profile.column(field);
+            let raw = record.iter().find(|(key, _)| key.eq_ignore_ascii_case(column)).map(|(_, value)| value.as_str());
+            profile.value(field, raw).unwrap_or_default()
+        };
+        let notes = value(ImportField::Notes);
+        let parsed = parse_historical_fields(
+            &value(ImportField::Sku),
+            &value(ImportField::Timestamp),
+            &value(ImportField::Type),
+            &value(ImportField::Quantity),
+            Some(notes.as_str()),
+        );
+        match parsed {
+            Ok(entry) => entries.push(NumberedEntry { line: *line_number, entry }),
+            Err(message) => report.add(*line_number, "INVALID_INPUT", message),
+        }
+    }
+    Ok(BulkInput { entries, report })
+}
+
+/// A record of an imported file by column name, with its line or position
+type Record = (usize, BTreeMap<String, String>);
+
+/// Read the non-blank lines after a CSV header as records
+fn csv_records<R: BufRead>(input: R) -> Result<Vec<Record>, String> {
+    let mut header = None;
+    let mut records = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read input: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_csv_record(line.trim_start_matches('\u{feff}'));
+        let Some(header) = &header else {
+            header = Some(fields.into_iter().map(|field| field.trim().to_string()).collect::<Vec<_>>());
+            continue;
+        };
+        let record = header.iter().cloned().zip(fields).collect();
+        records.push((index + 1, record));
+    }
+    Ok(records)
+}
+
+/// Read a JSON array of objects as records, turning numbers into text
+fn json_records<R: BufRead>(input: R) -> Result<Vec<Record>, String> {
+    let objects: Vec<serde_json::Map<String, serde_json::Value>> =
+        serde_json::from_reader(input).map_err(|e| format!("Invalid JSON import: expected an array of objects: {}", e))?;
+    Ok(objects
+        .into_iter()
+        .enumerate()
+        .map(|(index, object)| {
+            let record = object
+                .into_iter()
+                .filter_map(|(key, value)| match value {
+                    serde_json::Value::Null => None,
+                    serde_json::Value::String(text) => Some((key, text)),
+                    value => Some((key, value.to_string())),
+                })
+                .collect();
+            (index + 1, record)
+        })
+        .collect())
+}
+
+/// Read `SKU,QTY,REORDER_POINT,NAME` lines into opening balances
+///
+/// The name comes last so it may contain commas. Lines are skipped and
+/// reported as in `read_stock_entries`.
+pub fn read_opening_balances<R: BufRead>(input: R) -> Result<OpeningInput, String> {
+    read_entries(input, parse_opening_line, "No opening balances read; expected lines of SKU,QTY,REORDER_POINT,NAME")
+}
+
+/// Read `SKU,PRICE[,EFFECTIVE_DATE]` lines of a supplier's price list
+///
+/// EFFECTIVE_DATE is `YYYY-MM-DD` and defaults to today. Lines are skipped
+/// and reported as in `read_stock_entries`.
+pub fn read_price_entries<R: BufRead>(input: R) -> Result<PriceInput, String> {
+    read_entries(input, parse_price_line, "No prices read; expected lines of SKU,PRICE[,EFFECTIVE_DATE]")
+}
+
+/// Read the key and quantity columns of another system's CSV export
+///
+/// The first non-blank line is a header, and the columns are found in it by
+/// name, ignoring case. Fields may be quoted, with `""` for a quote inside
+/// one. Quantities must be whole and non-negative, though a zero fraction
+/// such as `12.000` is accepted. Lines are skipped and reported as in
+/// `read_stock_entries`. With `only`, a column and a value, lines holding
+/// anything else in that column are left out, e.g. other count sheets.
+pub fn read_external_quantities<R: BufRead>(
+    input: R,
+    key_column: &str,
+    quantity_column: &str,
+    only: Option<(&str, &str)>,
+) -> Result<ExternalInput, String> {
+    let mut lines = input.lines().enumerate();
+    let header = loop {
+        let Some((_, line)) = lines.next() else {
+            return Err("The export is empty; expected a header line naming its columns".to_string());
+        };
+        let line = line.map_err(|e| format!("Failed to read input: {}", e))?;
+        if !line.trim().is_empty() {
+            break split_csv_record(line.trim_start_matches('\u{feff}'));
+        }
+    };
+    let column = |name: &str| {
+        header.iter().position(|field| field.trim().eq_ignore_ascii_case(name)).ok_or_else(|| {
+            format!("The export has no '{}' column; its columns are {}", name, header.join(", "))
+        })
+    };
+    let (key, quantity) = (column(key_column)?, column(quantity_column)?);
+    let only = only.map(|(name, value)| column(name).map(|column| (column, value))).transpose()?;
+
+    let mut entries = Vec::new();
+    let mut report = ValidationReport::default();
+    for (index, line) in lines {
+        let line_number = index + 1;
+        let line = line.map_err(|e| format!("Failed to read input: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let fields = split_csv_record(&line);
+        let field = |column: usize| fields.get(column).map(|field| field.trim()).unwrap_or_default();
+        if only.is_some_and(|(column, value)| field(column) != value) {
+            continue;
+        }
+        report.rows += 1;
+        match parse_external_quantity(field(key), field(quantity)) {
+            Ok(entry) => entries.push(NumberedEntry { line: line_number, entry }),
+            Err(message) => report.add(line_number, "INVALID_INPUT", message),
+        }
+    }
+    Ok(BulkInput { entries, report })
+}
+
+/// Parse the SKU and quantity fields of one export line
+fn parse_external_quantity(sku: &str, quantity: &str) -> Result<ExternalQuantity, String> {
+    if sku.is_empty() {
+        return Err("SKU cannot be empty".to_string());
+    }
+    let whole = quantity.split_once('.').map_or(quantity, |(whole, fraction)| {
+        if fraction.chars().all(|c| c == '0') { whole } else { quantity }
+    });
+    let quantity = whole
+        .parse::<u32>()
+        .map_err(|_| format!("Invalid quantity '{}' for '{}': must be a non-negative whole number", quantity, sku))?;
+    Ok(ExternalQuantity { sku: sku.to_string(), quantity })
+}
+
+/// Split one CSV record into its fields, unquoting quoted ones
+fn split_csv_record(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+/// Read non-blank, non-comment lines with `parse`, failing with `empty` if there are none
+fn read_entries<R: BufRead, T>(
+    input: R,
+    parse: fn(&str) -> Result<T, String>,
+    empty: &str,
+) -> Result<BulkInput<T>, String> {
+    let mut entries = Vec::new();
+    let mut report = ValidationReport::default();
+
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.map_err(|e| format!("Failed to read input: {}", e))?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        report.rows += 1;
+        match parse(line) {
+            Ok(entry) => entries.push(NumberedEntry { line: line_number, entry }),
+            Err(message) => report.add(line_number, "INVALID_INPUT", message),
+        }
+    }
+
+    if report.rows == 0 {
+        return Err(empty.to_string());
+    }
+    Ok(BulkInput { entries, report })
+}
+
+/// Parse a single `SKU,QTY[,NOTES]` line
+fn parse_stock_line(line: &str) -> Result<StockEntry, String> {
+    let mut fields = line.splitn(3, ',').map(str::trim);
+    let sku = fields.next().unwrap_or_default();
+    let quantity = fields.next().ok_or("expected SKU,QTY[,NOTES]")?;
+    let notes = fields.next().filter(|notes| !notes.is_empty());
+
+    if sku.is_empty() {
+        return Err("SKU cannot be empty".to_string());
+    }
+    let quantity = match quantity.parse::<u32>() {
+        Ok(quantity) if quantity > 0 => quantity,
+        _ => return Err(format!("Invalid quantity '{}': must be a positive integer", quantity)),
+    };
+
+    Ok(StockEntry {
+        sku: sku.to_string(),
+        quantity,
+        notes: notes.map(String::from),
+    })
+}
+
+/// Parse a single `SKU,TIMESTAMP,TYPE,QTY[,NOTES]` line
+fn parse_historical_line(line: &str) -> Result<HistoricalEntry, String> {
+    let mut fields = line.splitn(5, ',').map(str::trim);
+    let (Some(sku), Some(timestamp), Some(transaction_type), Some(quantity)) =
+        (fields.next(), fields.next(), fields.next(), fields.next())
+    else {
+        return Err("expected SKU,TIMESTAMP,TYPE,QTY[,NOTES]".to_string());
+    };
+    parse_historical_fields(sku, timestamp, transaction_type, quantity, fields.next())
+}
+
+/// Parse the fields of one past movement, however they were laid out
+fn parse_historical_fields(
+    sku: &str,
+    timestamp: &str,
+    transaction_type: &str,
+    quantity: &str,
+    notes: Option<&str>,
+) -> Result<HistoricalEntry, String> {
+    let notes = notes.filter(|notes| !notes.is_empty());
+    if sku.is_empty() {
+        return Err("SKU cannot be empty".to_string());
+    }
+    let transaction_type = transaction_type.parse::<TransactionType>()?;
+    let quantity = match quantity.parse::<u32>() {
+        Ok(quantity) if quantity > 0 => quantity,
+        _ => return Err(format!("Invalid quantity '{}': must be a positive integer", quantity)),
+    };
+
+    Ok(HistoricalEntry {
+        sku: sku.to_string(),
+        timestamp: parse_timestamp(timestamp)?,
+        transaction_type,
+        quantity,
+        notes: notes.map(String::from),
+    })
+}
+
+/// Parse a single `SKU,QTY,REORDER_POINT,NAME` line
+fn parse_opening_line(line: &str) -> Result<OpeningBalance, String> {
+    let mut fields = line.splitn(4, ',').map(str::trim);
+    let (Some(sku), Some(quantity), Some(reorder_point), Some(name)) =
+        (fields.next(), fields.next(), fields.next(), fields.next())
+    else {
+        return Err("expected SKU,QTY,REORDER_POINT,NAME".to_string());
+    };
+
+    if sku.is_empty() {
+        return Err("SKU cannot be empty".to_string());
+    }
+    let quantity = quantity
+        .parse::<u32>()
+        .map_err(|_| format!("Invalid quantity '{}': must be a non-negative integer", quantity))?;
+    let reorder_point = reorder_point
+        .parse::<u32>()
+        .map_err(|_| format!("Invalid reorder point '{}': must be a non-negative integer", reorder_point))?;
+
+    Ok(OpeningBalance {
+        sku: sku.to_string(),
+        name: name.to_string(),
+        quantity,
+        reorder_point,
+    })
+}
+
+/// Parse a single `SKU,PRICE[,EFFECTIVE_DATE]` line
+fn parse_price_line(line: &str) -> Result<PriceEntry, String> {
+    let mut fields = line.split(',').map(str::trim);
+    let (Some(sku), Some(price)) = (fields.next(), fields.next()) else {
+        return Err("expected SKU,PRICE[,EFFECTIVE_DATE]".to_string());
+    };
+    let effective_from = fields.next().filter(|date| !date.is_empty());
+    if fields.next().is_some() {
+        return Err("expected SKU,PRICE[,EFFECTIVE_DATE]".to_string());
+    }
+
+    if sku.is_empty() {
+        return Err("SKU cannot be empty".to_string());
+    }
+    let unit_cost = price.parse::<Money>()?;
+    let effective_from = match effective_from {
+        Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| format!("Invalid effective date '{}': expected YYYY-MM-DD", date))?,
+        None => Utc::now().date_naive(),
+    };
+
+    Ok(PriceEntry {
+        sku: sku.to_string(),
+        unit_cost,
+        effective_from,
+    })
+}
+
+/// Parse an import timestamp: RFC 3339, `YYYY-MM-DDTHH:MM:SS` in UTC, or a date
+fn parse_timestamp(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(s) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+    if let Ok(timestamp) = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S") {
+        return Ok(timestamp.and_utc());
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+        .map_err(|_| format!("Invalid timestamp '{}': expected YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_stock_entries() {
+        let input = "# packing list\nSKU001, 5\n\nSKU002,3,Box 1, damaged corner\n";
+        let input = read_stock_entries(input.as_bytes()).unwrap();
+        assert!(input.report.is_valid());
+        assert_eq!(input.entries, vec![
+            NumberedEntry {
+                line: 2,
+                entry: StockEntry { sku: "SKU001".to_string(), quantity: 5, notes: None },
+            },
+            NumberedEntry {
+                line: 4,
+                entry: StockEntry {
+                    sku: "SKU002".to_string(),
+                    quantity: 3,
+                    notes: Some("Box 1, damaged corner".to_string()),
+                },
+            },
+        ]);
+    }
+
+    #[test]
+    fn test_read_stock_entries_reports_every_bad_line() {
+        let input = read_stock_entries("SKU001,5\nSKU002\n,4\nSKU003,zero\n".as_bytes()).unwrap();
+        assert_eq!(input.entries.len(), 1);
+        assert_eq!(input.report.rows, 4);
+        assert_eq!(
+            input.report.to_string(),
+            "Line 2: expected SKU,QTY[,NOTES]\n\
+             Line 3: SKU cannot be empty\n\
+             Line 4: Invalid quantity 'zero': must be a positive integer"
+        );
+        assert!(read_stock_entries("\n# nothing\n".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_read_historical_entries() {
+        let input = "SKU001,2024-03-01,addition,50,Opening count\n\
+                     SKU001,2024-03-05T14:30:00,Removal,5\n\
+                     SKU001,2024-03-06T09:00:00+02:00,removal,1\n\
+                     SKU001,2024-03-07,transfer,1\n\
+                     SKU001,March 8,addition,1\n";
+        let input = read_historical_entries(input.as_bytes()).unwrap();
+        let (entries, lines, report) = input.into_parts();
+        assert_eq!(lines, vec![1, 2, 3]);
+        assert_eq!(entries[0].notes.as_deref(), Some("Opening count"));
+        assert_eq!(entries[1].transaction_type, TransactionType::Removal);
+        assert_eq!(entries[2].timestamp.to_rfc3339(), "2024-03-06T07:00:00+00:00");
+        assert_eq!(
+            report.to_string(),
+            "Line 4: Invalid type 'transfer': expected one of addition, removal, initial-stock, damage, write-off, adjustment, quarantine, release-quarantine, condition-change\n\
+             Line 5: Invalid timestamp 'March 8': expected YYYY-MM-DD or YYYY-MM-DDTHH:MM:SS"
+        );
+    }
+
+    #[test]
+    fn test_read_mapped_history() {
+        let profile: ImportProfile = serde_json::from_str(
+            r#"{ "columns": { "sku": "Item No", "quantity": "Qty Shipped", "timestamp": "Ship Date" },
+                 "defaults": { "type": "addition" }, "transforms": { "sku": ["uppercase", { "prefix": "ACME-" }] } }"#,
+        )
+        .unwrap();
+
+        let csv = "item no,Ship Date,Qty Shipped,Carrier\n\nw1,2024-03-01,12,UPS\nw2,2024-03-02,none,UPS\n";
+        let (entries, lines, report) = read_mapped_history(csv.as_bytes(), &profile, false).unwrap().into_parts();
+        assert_eq!(lines, vec![3]);
+        assert_eq!(entries[0].sku, "ACME-W1");
+        assert_eq!(entries[0].transaction_type, TransactionType::Addition);
+        assert_eq!(entries[0].quantity, 12);
+        assert_eq!(report.to_string(), "Line 4: Invalid quantity 'none': must be a positive integer");
+
+        let json = r#"[{ "Item No": "W3", "Ship Date": "2024-03-03", "Qty Shipped": 4, "type": "removal", "notes": null }]"#;
+        let (entries, lines, _) = read_mapped_history(json.as_bytes(), &profile, true).unwrap().into_parts();
+        assert_eq!(lines, vec![1]);
+        assert_eq!((entries[0].sku.as_str(), entries[0].quantity), ("ACME-W3", 4));
+        assert_eq!(entries[0].transaction_type, TransactionType::Removal);
+        assert_eq!(entries[0].notes, None);
+
+        let error = read_mapped_history("Item No,Qty Shipped\nW1,1\n".as_bytes(), &profile, false).unwrap_err();
+        assert!(error.contains("no 'Ship Date' column for timestamp"), "{}", error);
+    }
+
+    #[test]
+    fn test_read_opening_balances() {
+        let input = "# SKU,QTY,REORDER_POINT,NAME\nSKU001,40,10,Bolts, M6\nSKU002,-1,5,Nuts\nSKU003,0,5\n";
+        let (entries, lines, report) = read_opening_balances(input.as_bytes()).unwrap().into_parts();
+        assert_eq!(lines, vec![2]);
+        assert_eq!(entries[0], OpeningBalance {
+            sku: "SKU001".to_string(),
+            name: "Bolts, M6".to_string(),
+            quantity: 40,
+            reorder_point: 10,
+        });
+        assert_eq!(
+            report.to_string(),
+            "Line 3: Invalid quantity '-1': must be a non-negative integer\n\
+             Line 4: expected SKU,QTY,REORDER_POINT,NAME"
+        );
+    }
+
+    #[test]
+    fn test_read_external_quantities_finds_columns_by_name() {
+        let input = "\u{feff}Item ID,\"Description\",OnHand\n\
+                     SKU001,\"Bolts, M6 \"\"zinc\"\"\",12.000\n\
+                     \n\
+                     SKU002,Nuts,-3\n\
+                     SKU003,Washers,2.5\n\
+                     ,Blank,4\n";
+        let (entries, lines, report) = read_external_quantities(input.as_bytes(), "item id", "onhand", None).unwrap().into_parts();
+        assert_eq!(lines, vec![2]);
+        assert_eq!(entries, vec![ExternalQuantity { sku: "SKU001".to_string(), quantity: 12 }]);
+        assert_eq!(
+            report.to_string(),
+            "Line 4: Invalid quantity '-3' for 'SKU002': must be a non-negative whole number\n\
+             Line 5: Invalid quantity '2.5' for 'SKU003': must be a non-negative whole number\n\
+             Line 6: SKU cannot be empty"
+        );
+        assert_eq!(split_csv_record("a,\"b,\"\"c\"\"\",,d"), vec!["a", "b,\"c\"", "", "d"]);
+
+        let error = read_external_quantities("sku,qty\n".as_bytes(), "sku", "onhand", None).unwrap_err();
+        assert_eq!(error, "The export has no 'onhand' column; its columns are sku, qty");
+    }
+
+    #[test]
+    fn test_read_price_entries() {
+        let input = "SKU001,2.10,2025-01-01\nSKU002, 3\nSKU003,1.999\nSKU004,4.00,Jan 1\n";
+        let (entries, lines, report) = read_price_entries(input.as_bytes()).unwrap().into_parts();
+        assert_eq!(lines, vec![1, 2]);
+        assert_eq!(entries[0].unit_cost.to_string(), "2.10");
+        assert_eq!(entries[0].effective_from, NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+        assert_eq!(entries[1].effective_from, Utc::now().date_naive());
+        assert_eq!(
+            report.to_string(),
+            "Line 3: Invalid amount '1.999': expected a non-negative number with up to two decimals\n\
+             Line 4: Invalid effective date 'Jan 1': expected YYYY-MM-DD"
+        );
+    }
+}
@@ -0,0 +1,406 @@
+// Help output and command lookup, drawn from the clap command tree
+
+use serde_json::{json, Value};
+
+use crate::i18n::{self, tr, trf, Msg};
+
+use super::args;
+
+/// Command groups, in help order, with their section titles
+pub const GROUPS: &[(&str, Msg)] = &[
+    ("product", Msg::GroupProduct),
+    ("stock", Msg::GroupStock),
+    ("report", Msg::GroupReport),
+    ("receive", Msg::GroupReceive),
+    ("trash", Msg::GroupTrash),
+    ("data", Msg::GroupData),
+    ("view", Msg::GroupView),
+    ("template", Msg::GroupTemplate),
+    ("supplier", Msg::GroupSupplier),
+    ("backorder", Msg::GroupBackorder),
+    ("customer", Msg::GroupCustomer),
+    ("retention", Msg::GroupRetention),
+    ("script", Msg::GroupScript),
+    ("feed", Msg::GroupFeed),
+    ("events", Msg::GroupEvents),
+    ("stocktake", Msg::GroupStocktake),
+];
+
+/// Options accepted by every command: long name, short name, and summary
+pub const GLOBAL_OPTIONS: &[(&str, &str, &str)] = &[
+    ("--quiet", "-q", "Print only essential values for use in scripts"),
+    ("--verbose", "-v", "Log diagnostics to stderr; repeat (-vv) for more detail"),
+    ("--strict-load", "", "Check every stored record and refuse to start if any fails"),
+    ("--help", "-h", "Show help for the command"),
+];
+
+/// Help for one command, read from its clap definition
+struct CommandHelp {
+    /// Command name (e.g. `add-stock`)
+    name: String,
+    /// Group and subcommand name for the grouped form (e.g. `stock add`)
+    group: (&'static str, String),
+    /// Built-in short forms of the command name
+    aliases: Vec<String>,
+    /// Usage synopses, starting with the command name
+    usage: Vec<String>,
+    /// Summary line followed by any notes, in the current locale
+    description: Vec<String>,
+    /// Example invocations
+    examples: Vec<String>,
+    /// The command's clap definition
+    command: clap::Command,
+}
+
+impl CommandHelp {
+    /// Gather the help of the command reached as `<group> <subcommand>`
+    fn new(root: &clap::Command, group: &'static str, subcommand: &clap::Command) -> Self {
+        let name = subcommand.get_display_name().unwrap_or(subcommand.get_name()).to_string();
+        let mut command = root.find_subcommand(&name).unwrap_or(subcommand).clone();
+        let usage = command.render_usage().to_string();
+        let description = match i18n::command_description(&name) {
+            Some(lines) => lines.iter().map(|line| line.to_string()).collect(),
+            None => command
+                .get_long_about()
+                .or(command.get_about())
+                .map(|about| about.to_string())
+                .unwrap_or_default()
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(String::from)
+                .collect(),
+        };
+        CommandHelp {
+            group: (group, subcommand.get_name().to_string()),
+            aliases: command.get_visible_aliases().map(String::from).collect(),
+            usage: usage.trim_start_matches("Usage: ").lines().map(|line| line.trim().to_string()).collect(),
+            description,
+            examples: lines(command.get_after_help()),
+            name,
+            command,
+        }
+    }
+
+    /// Usage error shown when required arguments are missing
+    fn usage_error(&self) -> String {
+        let mut message = format!("{}{}", tr(Msg::UsagePrefix), self.usage.join("\n       "));
+        if let Some(example) = self.examples.first() {
+            message.push_str(&format!("\n{}{}", tr(Msg::ExamplePrefix), example));
+        }
+        message
+    }
+
+    /// Usage synopses without the command name
+    fn usage_arguments(&self) -> impl Iterator<Item = &str> {
+        self.usage.iter().map(|usage| usage[self.name.len()..].trim_start())
+    }
+
+    /// Options the command accepts, those taking a value first
+    fn options(&self) -> impl Iterator<Item = &clap::Arg> {
+        let switches = self.command.get_arguments().filter(|arg| !arg.get_action().takes_values());
+        self.command
+            .get_opts()
+            .chain(switches)
+            .filter(|arg| arg.get_long().is_some_and(|long| long != "help"))
+    }
+
+    /// Structured description of the command for `help --format json`
+    fn to_json(&self) -> Value {
+        let arguments: Vec<Value> = self.command
+            .get_positionals()
+            .map(|arg| json!({ "name": value_name(arg), "required": arg.is_required_set() }))
+            .collect();
+        let value_counts = |arg: &clap::Arg| {
+            let range = arg.get_num_args().unwrap_or_default();
+            (range.min_values(), range.max_values())
+        };
+        let min_arguments: usize = self.command
+            .get_positionals()
+            .filter(|arg| arg.is_required_set())
+            .map(|arg| value_counts(arg).0.max(1))
+            .sum();
+        let max_arguments: usize = self.command.get_positionals().map(|arg| value_counts(arg).1).sum();
+        let options: Vec<Value> = self
+            .options()
+            .map(|arg| {
+                let name = format!("--{}", arg.get_long().unwrap_or_default());
+                if !arg.get_action().takes_values() {
+                    return json!({ "name": name, "takes_value": false });
+                }
+                let mut entry = json!({ "name": name, "takes_value": true, "value_name": value_name(arg) });
+                let choices: Vec<String> = arg.get_possible_values().iter().map(|value| value.get_name().to_string()).collect();
+                if !choices.is_empty() {
+                    entry["choices"] = json!(choices);
+                }
+                entry
+            })
+            .collect();
+
+        json!({
+            "name": self.name,
+            "group": self.group.0,
+            "subcommand": self.group.1,
+            "aliases": self.aliases,
+            "summary": self.description[0],
+            "notes": &self.description[1..],
+            "usage": self.usage,
+            "arguments": arguments,
+            "min_arguments": min_arguments,
+            "max_arguments": max_arguments,
+            "options": options,
+            "examples": self.examples,
+        })
+    }
+
+    /// Detailed help text for the command
+    fn to_text(&self) -> String {
+        let mut help = format!("{} - {}\n\n{}\n", self.name, self.description[0], tr(Msg::HelpCommandUsage));
+        for usage in self.usage_arguments() {
+            help.push_str(&format!("    stock-control {} {}\n", self.name, usage).replace(" \n", "\n"));
+            help.push_str(&format!("    stock-control {} {} {}\n", self.group.0, self.group.1, usage).replace(" \n", "\n"));
+        }
+
+        if self.description.len() > 1 {
+            help.push('\n');
+            for line in &self.description[1..] {
+                help.push_str(&format!("{}\n", line));
+            }
+        }
+
+        if !self.aliases.is_empty() {
+            help.push_str(&format!("\n{}\n    {}\n", tr(Msg::HelpCommandAliases), self.aliases.join(", ")));
+        }
+
+        let options: Vec<String> = self.options().filter_map(|arg| arg.get_long()).map(|long| format!("--{}", long)).collect();
+        if !options.is_empty() {
+            help.push_str(&format!("\n{}\n    {}\n", tr(Msg::HelpCommandOptions), options.join(", ")));
+        }
+
+        help.push_str(&format!("\n{}\n", tr(Msg::HelpCommandExamples)));
+        for example in &self.examples {
+            help.push_str(&format!("    {}\n", example));
+        }
+        help.trim_end().to_string()
+    }
+}
+
+/// Split help text given to clap into its non-empty lines
+fn lines(text: Option<&clap::builder::StyledStr>) -> Vec<String> {
+    text.map(|text| text.to_string())
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Value placeholder of an argument, e.g. `notes` or `text|pdf`
+fn value_name(arg: &clap::Arg) -> String {
+    arg.get_value_names()
+        .and_then(|names| names.first())
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| arg.get_id().to_string())
+}
+
+/// Every command, grouped in help order
+fn commands() -> Vec<CommandHelp> {
+    let root = args::command();
+    GROUPS
+        .iter()
+        .filter_map(|(group, _)| root.find_subcommand(group).map(|command| (*group, command)))
+        .flat_map(|(group, command)| command.get_subcommands().map(|subcommand| CommandHelp::new(&root, group, subcommand)).collect::<Vec<_>>())
+        .collect()
+}
+
+/// Find a command by its name
+fn find_command(name: &str) -> Option<CommandHelp> {
+    commands().into_iter().find(|command| command.name == name)
+}
+
+/// Whether `name` is a command group
+pub fn is_group(name: &str) -> bool {
+    GROUPS.iter().any(|(group, _)| *group == name)
+}
+
+/// Whether `name` is taken by a built-in command, alias, or group, or `help`
+pub fn is_builtin(name: &str) -> bool {
+    args::command().find_subcommand(name).is_some()
+}
+
+/// Subcommand names of a group, in help order
+fn group_subcommands(group: &str) -> Vec<String> {
+    args::command()
+        .find_subcommand(group)
+        .map(|command| command.get_subcommands().map(|subcommand| subcommand.get_name().to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// Name of the command that `args` start with, given by name, alias, or group and subcommand
+pub fn resolve(args: &[String]) -> Option<String> {
+    let root = args::command();
+    let first = args.first()?;
+    if is_group(first) {
+        let subcommand = root.find_subcommand(first)?.find_subcommand(args.get(1)?)?;
+        return subcommand.get_display_name().map(String::from);
+    }
+    root.find_subcommand(first)
+        .map(|command| command.get_name().to_string())
+        .filter(|name| name != "help")
+}
+
+/// Resolve the command named by the arguments of `help`; `None` for none or a group
+pub fn resolve_names(names: &[String]) -> Result<Option<String>, String> {
+    match names {
+        [] => Ok(None),
+        [group] if is_group(group) => Ok(None),
+        [command] => resolve(names)
+            .map(Some)
+            .ok_or_else(|| trf(Msg::UnknownCommand, &[("command", command)])),
+        [group, subcommand] if is_group(group) => resolve(names).map(Some).ok_or_else(|| {
+            let message = trf(Msg::UnknownGroupCommand, &[("group", group), ("command", subcommand)]);
+            format!("{}\n{}{} <{}>", message, tr(Msg::UsagePrefix), group, group_subcommands(group).join("|"))
+        }),
+        [_, extra, ..] => Err(trf(Msg::UnexpectedArgument, &[("argument", extra)])),
+    }
+}
+
+/// Usage error for the named command, shown when required arguments are missing
+pub fn usage_error(name: &str) -> String {
+    find_command(name).map(|command| command.usage_error()).unwrap_or_default()
+}
+
+/// Get the help text for the CLI
+pub fn get_help_text() -> String {
+    let mut help = format!(
+        "{}
+
+{}
+    stock-control <COMMAND> [OPTIONS]
+    stock-control <GROUP> <SUBCOMMAND> [OPTIONS]
+    stock-control <COMMAND> --help
+
+{}
+
+{}
+
+",
+        tr(Msg::HelpTitle),
+        tr(Msg::HelpUsage),
+        tr(Msg::HelpOptionsNote),
+        tr(Msg::HelpGlobalOptions),
+    );
+
+    let commands = commands();
+    for (group, title) in GROUPS {
+        help.push_str(&format!("{} ({} <{}>):\n", tr(*title), group, group_subcommands(group).join("|")));
+        for command in commands.iter().filter(|command| command.group.0 == *group) {
+            for usage in &command.usage {
+                help.push_str(&format!("    {}\n", usage));
+            }
+            for line in &command.description {
+                help.push_str(&format!("        {}\n", line));
+            }
+            if !command.aliases.is_empty() {
+                help.push_str(&format!("        {}{}\n", tr(Msg::HelpAliases), command.aliases.join(", ")));
+            }
+            if let Some(example) = command.examples.first() {
+                help.push_str(&format!("        {}{}\n", tr(Msg::ExamplePrefix), example));
+            }
+            help.push('\n');
+        }
+    }
+
+    help.push_str(&format!(
+        "{}\n\n{}\n\n{}",
+        tr(Msg::HelpOther),
+        tr(Msg::HelpTemplates),
+        tr(Msg::HelpConfiguration),
+    ));
+    help
+}
+
+/// Get the detailed help text for the named command
+pub fn command_help(name: &str) -> Option<String> {
+    find_command(name).map(|command| command.to_text())
+}
+
+/// Structured description of the named command for `help --format json`
+pub fn command_json(name: &str) -> Option<Value> {
+    find_command(name).map(|command| command.to_json())
+}
+
+/// Structured description of the whole CLI for `help --format json`
+pub fn help_json() -> Value {
+    let global_options: Vec<Value> = GLOBAL_OPTIONS
+        .iter()
+        .map(|(name, short, summary)| json!({ "name": name, "short": short, "summary": summary }))
+        .collect();
+    let groups: Vec<Value> = GROUPS
+        .iter()
+        .map(|(group, title)| json!({ "name": group, "title": tr(*title), "subcommands": group_subcommands(group) }))
+        .collect();
+    json!({
+        "program": "stock-control",
+        "version": env!("CARGO_PKG_VERSION"),
+        "global_options": global_options,
+        "groups": groups,
+        "commands": commands().iter().map(CommandHelp::to_json).collect::<Vec<_>>(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_tree_is_valid() {
+        args::command().debug_assert();
+    }
+
+    #[test]
+    fn test_help_json_describes_commands() {
+        let help = help_json();
+        assert_eq!(help["commands"].as_array().unwrap().len(), 72);
+        assert_eq!(help["groups"][1]["subcommands"], json!(["add", "remove", "quarantine", "release", "order", "condition", "weigh", "kiosk", "history", "receipt", "import", "ack"]));
+
+        let receipt = command_json("receipt").unwrap();
+        assert_eq!(receipt["arguments"], json!([{ "name": "transaction-id", "required": true }]));
+        assert_eq!(receipt["options"][0], json!({
+            "name": "--format",
+            "takes_value": true,
+            "value_name": "text|pdf|zpl|escpos",
+            "choices": ["text", "pdf", "zpl", "escpos"],
+        }));
+        assert_eq!(receipt["options"][1]["value_name"], "file");
+    }
+
+    #[test]
+    fn test_resolve_command_names() {
+        let args = |s: &str| s.split_whitespace().map(String::from).collect::<Vec<_>>();
+        assert_eq!(resolve(&args("in SKU001 5")).as_deref(), Some("add-stock"));
+        assert_eq!(resolve(&args("stock add SKU001 5")).as_deref(), Some("add-stock"));
+        assert_eq!(resolve(&args("export-846")).as_deref(), Some("inventory-advice"));
+        assert_eq!(resolve(&args("stock")), None);
+        assert_eq!(resolve(&args("frobnicate")), None);
+    }
+
+    #[test]
+    fn test_usage_error_lists_synopses_and_example() {
+        assert_eq!(
+            usage_error("ack"),
+            "Usage: ack <sku> [--until <YYYY-MM-DD>]\n       ack <sku> --clear\nExample: ack SKU001 --until 2025-03-31"
+        );
+    }
+
+    #[test]
+    fn test_every_command_has_usage_and_example() {
+        for command in commands() {
+            assert!(!command.usage.is_empty(), "{} has no usage", command.name);
+            assert!(command.usage.iter().all(|usage| usage.starts_with(&command.name)), "{} has another command's usage", command.name);
+            assert!(!command.description.is_empty(), "{} has no description", command.name);
+            assert!(!command.examples.is_empty(), "{} has no example", command.name);
+            assert!(args::command().find_subcommand(&command.name).is_some(), "{} has no flat form", command.name);
+        }
+    }
+}
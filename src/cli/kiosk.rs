@@ -0,0 +1,337 @@
+// Locked-down scan-in/scan-out mode for a shared shop-floor terminal
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, Write};
+
+use crate::i18n::{tr, trf, Msg};
+use crate::service::InventoryService;
+
+use super::{execute_command_with_mode, format_error, parse_args_with_aliases, Dispatch, OutputMode};
+
+/// What a scanned SKU does when no verb comes before it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ScanMode {
+    In,
+    Out,
+    View,
+}
+
+/// Run a kiosk session until input ends or an unlocked operator exits
+///
+/// While locked, only `in`, `out`, and `view` are accepted: either as
+/// `<verb> <sku> [qty]`, or as the verb alone to set what happens to each SKU
+/// scanned after it (one unit unless a quantity follows). `unlock <code>`
+/// allows any command until `lock`; without a configured code the kiosk
+/// can't be unlocked. Errors are shown and the session carries on.
+///
+/// A scanned code can also be a product's barcode. When it matches more than
+/// one product, the kiosk lists them and waits for a number; the choice is
+/// remembered for the code until the session ends.
+///
+/// Each line is saved as soon as it is handled, and its events and stock
+/// changes go through `dispatch` like those of any other command.
+pub fn run_kiosk<R: BufRead, W: Write>(
+    service: &mut InventoryService,
+    unlock_code: Option<&str>,
+    aliases: &BTreeMap<String, String>,
+    dispatch: &mut Dispatch,
+    input: &mut R,
+    output: &mut W,
+) -> Result<(), String> {
+    let mut kiosk = Kiosk {
+        service,
+        unlock_code,
+        aliases,
+        mode: ScanMode::View,
+        unlocked: false,
+        choices: HashMap::new(),
+        pending: None,
+    };
+    say(output, tr(Msg::KioskBanner))?;
+    loop {
+        write!(output, "{}", if kiosk.unlocked { "# " } else { "> " })
+            .and_then(|_| output.flush())
+            .map_err(|e| format!("Failed to write output: {}", e))?;
+        let mut line = String::new();
+        let read = input.read_line(&mut line)
+            .map_err(|e| format!("Failed to read input: {}", e))?;
+        if read == 0 {
+            return Ok(());
+        }
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+        dispatch.begin(kiosk.service);
+        let handled = kiosk.handle(&words);
+        match &handled {
+            Ok(Some(reply)) => say(output, reply)?,
+            Ok(None) => {}
+            Err(message) => say(output, message)?,
+        }
+        if let Err(e) = kiosk.service.flush() {
+            say(output, &format_error(e))?;
+        }
+        for message in dispatch.finish(kiosk.service) {
+            say(output, &trf(Msg::Warning, &[("message", &message)]))?;
+        }
+        if let Ok(None) = handled {
+            return Ok(());
+        }
+    }
+}
+
+/// State of a kiosk session
+struct Kiosk<'a> {
+    service: &'a mut InventoryService,
+    unlock_code: Option<&'a str>,
+    aliases: &'a BTreeMap<String, String>,
+    mode: ScanMode,
+    unlocked: bool,
+    /// SKU chosen for each ambiguous code scanned this session
+    choices: HashMap<String, String>,
+    /// A scan waiting for the operator to pick one of several products
+    pending: Option<PendingScan>,
+}
+
+/// A scan of a code that matched several products
+struct PendingScan {
+    mode: ScanMode,
+    code: String,
+    quantity: u32,
+    candidates: Vec<String>,
+}
+
+impl Kiosk<'_> {
+    /// Handle one line of input, returning the reply, or `None` to end the session
+    fn handle(&mut self, words: &[&str]) -> Result<Option<String>, String> {
+        if let Some(pending) = self.pending.take() {
+            let choice = match words {
+                [number] => number.parse::<usize>().ok().and_then(|n| n.checked_sub(1)),
+                _ => None,
+            };
+            let Some(sku) = choice.and_then(|index| pending.candidates.get(index)) else {
+                return Ok(Some(tr(Msg::KioskPickCancelled).to_string()));
+            };
+            self.choices.insert(pending.code, sku.clone());
+            return self.apply(pending.mode, &sku.clone(), pending.quantity).map(Some);
+        }
+        let verb = words[0].to_lowercase();
+        let mode = match verb.as_str() {
+            "in" => Some(ScanMode::In),
+            "out" => Some(ScanMode::Out),
+            "view" => Some(ScanMode::View),
+            _ => None,
+        };
+        match (verb.as_str(), mode) {
+            (_, Some(mode)) if words.len() == 1 => {
+                self.mode = mode;
+                Ok(Some(trf(Msg::KioskMode, &[("mode", &verb)])))
+            }
+            (_, Some(mode)) => self.scan(mode, &words[1..]).map(Some),
+            ("help", _) => Ok(Some(tr(Msg::KioskHelp).to_string())),
+            ("unlock", _) => self.unlock(words.get(1).copied()).map(Some),
+            ("lock", _) => {
+                self.unlocked = false;
+                Ok(Some(tr(Msg::KioskRelocked).to_string()))
+            }
+            ("exit" | "quit", _) if self.unlocked => Ok(None),
+            ("exit" | "quit", _) => Err(tr(Msg::KioskLocked).to_string()),
+            _ if self.unlocked => self.run_command(words).map(Some),
+            _ => self.scan(self.mode, words).map(Some),
+        }
+    }
+
+    /// Move or show stock of a scanned `<sku> [qty]`
+    fn scan(&mut self, mode: ScanMode, words: &[&str]) -> Result<String, String> {
+        let (sku, quantity) = match words {
+            [sku] => (*sku, 1),
+            [sku, quantity] => {
+                let quantity = quantity.parse::<u32>()
+                    .map_err(|_| format!("Invalid quantity '{}': must be a positive integer", quantity))?;
+                (*sku, quantity)
+            }
+            _ => return Err(tr(Msg::KioskLocked).to_string()),
+        };
+        if let Some(sku) = self.choices.get(sku) {
+            return self.apply(mode, &sku.clone(), quantity);
+        }
+        let candidates: Vec<String> = self.service
+            .products_for_code(sku)
+            .into_iter()
+            .map(|product| product.sku.clone())
+            .collect();
+        match candidates.as_slice() {
+            [] => self.apply(mode, sku, quantity),
+            [only] => self.apply(mode, &only.clone(), quantity),
+            _ => {
+                let mut lines = vec![trf(Msg::KioskPickHeader, &[("code", &sku), ("count", &candidates.len())])];
+                for (index, candidate) in candidates.iter().enumerate() {
+                    let name = self.service.get_product(candidate).map_or("", |product| product.name.as_str());
+                    lines.push(trf(Msg::KioskPickLine, &[("number", &(index + 1)), ("sku", candidate), ("name", &name)]));
+                }
+                self.pending = Some(PendingScan { mode, code: sku.to_string(), quantity, candidates });
+                Ok(lines.join("\n"))
+            }
+        }
+    }
+
+    /// Move or show `quantity` units of the product with a SKU
+    fn apply(&mut self, mode: ScanMode, sku: &str, quantity: u32) -> Result<String, String> {
+        match mode {
+            ScanMode::In => self.service.add_stock(sku, quantity, None).map_err(format_error)?,
+            ScanMode::Out => self.service.remove_stock(sku, quantity, None).map_err(format_error)?,
+            ScanMode::View => {}
+        }
+        let product = self.service.get_product(sku).map_err(format_error)?;
+        let low = if product.is_low_stock() { format!(" {}", tr(Msg::LowMarker)) } else { String::new() };
+        let args: [(&str, &dyn std::fmt::Display); 4] = [
+            ("sku", &product.sku),
+            ("quantity", &quantity),
+            ("total", &product.quantity),
+            ("marker", &low),
+        ];
+        Ok(match mode {
+            ScanMode::In => trf(Msg::KioskScannedIn, &args),
+            ScanMode::Out => trf(Msg::KioskScannedOut, &args),
+            ScanMode::View => trf(Msg::ProductLine, &[
+                ("sku", &product.sku),
+                ("name", &product.name),
+                ("quantity", &product.quantity),
+                ("marker", &low),
+            ]).trim_start().to_string(),
+        })
+    }
+
+    /// Allow every command if `code` matches the configured unlock code
+    fn unlock(&mut self, code: Option<&str>) -> Result<String, String> {
+        let Some(expected) = self.unlock_code else {
+            return Err(tr(Msg::KioskNoUnlockCode).to_string());
+        };
+        if code != Some(expected) {
+            log::warn!("Kiosk unlock refused: wrong code");
+            return Err(tr(Msg::KioskWrongCode).to_string());
+        }
+        log::info!("Kiosk unlocked");
+        self.unlocked = true;
+        Ok(tr(Msg::KioskUnlocked).to_string())
+    }
+
+    /// Run a full command line while unlocked
+    fn run_command(&mut self, words: &[&str]) -> Result<String, String> {
+        let args: Vec<String> = std::iter::once("stock-control")
+            .chain(words.iter().copied())
+            .map(String::from)
+            .collect();
+        let command = parse_args_with_aliases(&args, self.aliases)?;
+        execute_command_with_mode(command, self.service, OutputMode::Normal)
+    }
+}
+
+/// Write a line of text to the output
+fn say<W: Write>(output: &mut W, text: &str) -> Result<(), String> {
+    writeln!(output, "{}", text).map_err(|e| format!("Failed to write output: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use crate::hooks::HooksConfig;
+    use crate::mqtt::MqttConfig;
+    use crate::outbox::OUTBOX_FILE;
+    use crate::rules::{self, AlertRule};
+    use crate::shop::ShopConfig;
+    use crate::storage::JsonStorage;
+
+    fn run_in(dir: &std::path::Path, service: &mut InventoryService, unlock_code: Option<&str>, input: &str) -> String {
+        let (hooks, mqtt, shop) = (HooksConfig::default(), MqttConfig::default(), ShopConfig::default());
+        let mut dispatch = Dispatch::new(dir, &hooks, &mqtt, &shop);
+        let mut output = Vec::new();
+        run_kiosk(service, unlock_code, &BTreeMap::new(), &mut dispatch, &mut input.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn run(service: &mut InventoryService, unlock_code: Option<&str>, input: &str) -> String {
+        let outbox_dir = TempDir::new().unwrap();
+        run_in(outbox_dir.path(), service, unlock_code, input)
+    }
+
+    #[test]
+    fn test_kiosk_scan_events_reach_the_outbox() {
+        let temp_dir = TempDir::new().unwrap();
+        let rules = rules::compile_all(&[AlertRule {
+            name: "running-low".into(),
+            when: "quantity < 5".into(),
+            message: None,
+        }]).unwrap();
+        let mut service = InventoryService::new(Box::new(JsonStorage::new(temp_dir.path()))).unwrap()
+            .with_alert_rules(rules);
+        service.add_product("SKU001".to_string(), "Widget".to_string(), String::new(), 10, 2).unwrap();
+
+        let output = run_in(temp_dir.path(), &mut service, None, "out SKU001 6\nout SKU001 1\n");
+        assert!(output.contains("Warning: "));
+
+        let outbox = std::fs::read_to_string(temp_dir.path().join(OUTBOX_FILE)).unwrap();
+        let records: Vec<serde_json::Value> = outbox.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        let types: Vec<&str> = records.iter().filter_map(|record| record["type"].as_str()).collect();
+        assert_eq!(types, ["stock_changed", "stock_changed", "rule_matched", "stock_changed"]);
+        assert!(outbox.lines().all(|line| line.contains("SKU001")));
+        // Saved with the scan, not only when the session ends
+        let reloaded = InventoryService::new(Box::new(JsonStorage::new(temp_dir.path()))).unwrap();
+        assert_eq!(reloaded.get_product("SKU001").unwrap().quantity, 3);
+    }
+
+    #[test]
+    fn test_kiosk_scans_and_stays_locked() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = InventoryService::new(Box::new(JsonStorage::new(temp_dir.path()))).unwrap();
+        service.add_product("SKU001".to_string(), "Widget".to_string(), String::new(), 10, 2).unwrap();
+
+        let output = run(&mut service, Some("1234"), "in\nSKU001\nSKU001 4\nout SKU001 3\nview SKU001\nls\nunlock 9999\nexit\n");
+        assert_eq!(service.get_product("SKU001").unwrap().quantity, 12);
+        assert!(output.contains("SKU001: +4 -> 15"));
+        assert!(output.contains("SKU001: -3 -> 12"));
+        assert!(output.contains("Product 'ls' not found"));
+        assert!(output.contains("Wrong unlock code"));
+        assert!(output.contains("Locked: only in, out, and view"));
+    }
+
+    #[test]
+    fn test_kiosk_unlock_allows_commands() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = InventoryService::new(Box::new(JsonStorage::new(temp_dir.path()))).unwrap();
+
+        let output = run(&mut service, Some("1234"), "unlock 1234\nadd-product SKU001 Widget - 0 0\nlock\nls\n");
+        assert!(service.get_product("SKU001").is_ok());
+        assert!(output.contains("Product 'ls' not found"));
+
+        let output = run(&mut service, None, "unlock 1234\n");
+        assert!(output.contains("No unlock code"));
+    }
+
+    #[test]
+    fn test_kiosk_picks_between_products_sharing_a_barcode() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = InventoryService::new(Box::new(JsonStorage::new(temp_dir.path()))).unwrap();
+        for sku in ["RED", "BLUE", "PLAIN"] {
+            service.add_product(sku.to_string(), format!("{} widget", sku), String::new(), 10, 0).unwrap();
+        }
+        for sku in ["RED", "BLUE"] {
+            let update = crate::service::ProductUpdate { barcode: Some("4006381333931".to_string()), ..Default::default() };
+            service.update_product(sku, update).unwrap();
+        }
+
+        // The pick is remembered; a cancelled pick moves nothing
+        let output = run(&mut service, None, "out\n4006381333931 2\n2\n4006381333931\nPLAIN\n");
+        assert!(output.contains("'4006381333931' matches 2 products"));
+        assert!(output.contains("  1) BLUE - BLUE widget"));
+        assert_eq!(service.get_product("RED").unwrap().quantity, 7);
+        assert_eq!(service.get_product("BLUE").unwrap().quantity, 10);
+        assert_eq!(service.get_product("PLAIN").unwrap().quantity, 9);
+
+        let output = run(&mut service, None, "out\n4006381333931\nnone\n");
+        assert!(output.contains("Choice cancelled"));
+        assert_eq!(service.get_product("RED").unwrap().quantity, 7);
+    }
+}
@@ -0,0 +1,379 @@
+// Command-line argument parsing
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::marker::PhantomData;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use clap::builder::{PossibleValue, StringValueParser, TypedValueParser};
+use clap::error::ErrorKind;
+use clap::FromArgMatches;
+
+use crate::books::BookFormat;
+use crate::edi::{self, FeedFormat};
+use crate::export::ExportFormat;
+use crate::forecast::Seasonality;
+use crate::models::{
+    Component, Condition, GroupBy, Interval, LinkKind, ListColumn, MergePolicy, ProductSort, ReasonCode, TransactionType,
+};
+use crate::receipt::ReceiptFormat;
+use crate::reorder;
+use crate::stocktake::SheetFormat;
+
+use super::args::{self, Cli};
+use super::help;
+use super::Command;
+
+/// Parse command-line arguments into a Command
+///
+/// Commands can be given by name (`add-stock`) or in grouped form
+/// (`stock add`); `--help` after a command shows its detailed help.
+///
+/// # Arguments
+/// * `args` - Command-line arguments (including program name)
+///
+/// # Returns
+/// * `Ok(Command)` - Successfully parsed command
+/// * `Err(String)` - Error message describing the parsing failure
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let command = match args.get(1) {
+        None => return Ok(Command::Help),
+        Some(command) if command == "--help" || command == "-h" => return Ok(Command::Help),
+        Some(command) => command,
+    };
+    if wants_help(&args[1..]) {
+        if let Some(command) = help::resolve(&args[1..]) {
+            return Ok(Command::CommandHelp { command });
+        }
+        if help::is_group(command) {
+            return Ok(Command::Help);
+        }
+    }
+
+    let cli = args::command()
+        .try_get_matches_from(args)
+        .and_then(|matches| Cli::from_arg_matches(&matches))
+        .map_err(|error| match (error.kind(), help::resolve(&args[1..])) {
+            (ErrorKind::MissingRequiredArgument, Some(command)) => help::usage_error(&command),
+            _ => error.render().to_string().trim_end().to_string(),
+        })?;
+    match cli.command {
+        Some(command) => command.into_command(),
+        None => Ok(Command::Help),
+    }
+}
+
+/// Options accepted by every command
+#[derive(Debug, Default, PartialEq)]
+pub struct GlobalOptions {
+    /// Print only essential, machine-consumable values (`-q`/`--quiet`)
+    pub quiet: bool,
+    /// Diagnostic log verbosity on stderr (`-v`, `-vv`, `--verbose`)
+    pub verbosity: u8,
+    /// Check every loaded record and refuse to start on problems (`--strict-load`)
+    pub strict_load: bool,
+}
+
+/// Remove global options from the arguments, returning them separately
+///
+/// Global options may appear anywhere before a `--` separator. They are taken
+/// out before the command is parsed so that plugins receive them too.
+pub fn split_global_options(args: &[String]) -> (GlobalOptions, Vec<String>) {
+    let mut options = GlobalOptions::default();
+    let mut remaining = Vec::with_capacity(args.len());
+
+    let mut iter = args.iter();
+    for arg in iter.by_ref() {
+        match arg.as_str() {
+            "-q" | "--quiet" => options.quiet = true,
+            "--verbose" => options.verbosity = options.verbosity.saturating_add(1),
+            "--strict-load" => options.strict_load = true,
+            flag if is_verbosity_flag(flag) => {
+                options.verbosity = options.verbosity.saturating_add((flag.len() - 1) as u8);
+            }
+            "--" => {
+                remaining.push(arg.clone());
+                break;
+            }
+            _ => remaining.push(arg.clone()),
+        }
+    }
+    remaining.extend(iter.cloned());
+
+    (options, remaining)
+}
+
+/// Whether `arg` is `-v`, `-vv`, `-vvv`, ...
+fn is_verbosity_flag(arg: &str) -> bool {
+    arg.len() > 1 && arg.starts_with('-') && arg[1..].chars().all(|c| c == 'v')
+}
+
+/// Parse command-line arguments, first expanding user-defined aliases
+///
+/// An alias maps a name to a command line fragment (e.g. `"lows"` to
+/// `"low-stock --group-by supplier"`); arguments after the alias are appended.
+/// Built-in command names, groups, and aliases always take precedence.
+pub fn parse_args_with_aliases(
+    args: &[String],
+    aliases: &BTreeMap<String, String>,
+) -> Result<Command, String> {
+    parse_args(&expand_alias(args, aliases))
+}
+
+/// Replace a user-defined alias in command position with its expansion
+fn expand_alias(args: &[String], aliases: &BTreeMap<String, String>) -> Vec<String> {
+    let expansion = args
+        .get(1)
+        .filter(|name| !help::is_builtin(name))
+        .and_then(|name| aliases.get(name));
+
+    match expansion {
+        Some(expansion) => args[..1]
+            .iter()
+            .cloned()
+            .chain(expansion.split_whitespace().map(String::from))
+            .chain(args[2..].iter().cloned())
+            .collect(),
+        None => args.to_vec(),
+    }
+}
+
+/// Whether `--help`/`-h` appears before any `--` separator
+fn wants_help(args: &[String]) -> bool {
+    args.iter()
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == "--help" || arg == "-h")
+}
+
+/// Types whose values are one of a fixed set of names
+pub trait Choices: std::str::FromStr<Err = String> + Clone + Send + Sync + 'static {
+    /// Every accepted name, in the order help lists them
+    fn names() -> Vec<&'static str>;
+}
+
+macro_rules! choices_from_all {
+    ($($ty:ty),* $(,)?) => {
+        $(impl Choices for $ty {
+            fn names() -> Vec<&'static str> {
+                <$ty>::ALL.iter().map(|value| value.name()).collect()
+            }
+        })*
+    };
+}
+
+choices_from_all!(
+    BookFormat, Condition, ExportFormat, FeedFormat, Interval, LinkKind, ListColumn, ProductSort, ReasonCode, Seasonality,
+);
+
+impl Choices for GroupBy {
+    fn names() -> Vec<&'static str> {
+        vec!["category", "supplier", "location"]
+    }
+}
+
+impl Choices for ReceiptFormat {
+    fn names() -> Vec<&'static str> {
+        vec!["text", "pdf", "zpl", "escpos"]
+    }
+}
+
+impl Choices for SheetFormat {
+    fn names() -> Vec<&'static str> {
+        vec!["csv", "pdf"]
+    }
+}
+
+/// Value parser for a type with a fixed set of names
+///
+/// The names are offered to help and shell completion, while the value is
+/// parsed with the type's own `FromStr` so its error explains a bad value.
+#[derive(Clone)]
+pub struct Choice<T>(PhantomData<fn() -> T>);
+
+/// Parse the value of an option taking one of `T`'s names
+pub fn choice<T: Choices>() -> Choice<T> {
+    Choice(PhantomData)
+}
+
+impl<T: Choices> TypedValueParser for Choice<T> {
+    type Value = T;
+
+    fn parse_ref(&self, cmd: &clap::Command, arg: Option<&clap::Arg>, value: &OsStr) -> Result<T, clap::Error> {
+        StringValueParser::new().try_map(|value| value.parse::<T>()).parse_ref(cmd, arg, value)
+    }
+
+    fn possible_values(&self) -> Option<Box<dyn Iterator<Item = PossibleValue> + '_>> {
+        Some(Box::new(T::names().into_iter().map(PossibleValue::new)))
+    }
+}
+
+/// Parse a comma-separated list, skipping empty items
+pub fn parse_list<T: std::str::FromStr<Err = String>>(value: &str) -> Result<Vec<T>, String> {
+    value.split(',').filter(|item| !item.trim().is_empty()).map(str::parse::<T>).collect()
+}
+
+/// Parse the `--components` of a kit
+pub fn parse_components(value: &str) -> Result<Vec<Component>, String> {
+    parse_list(value)
+}
+
+/// Parse the `--columns` of a product listing
+pub fn parse_columns(value: &str) -> Result<Vec<ListColumn>, String> {
+    parse_list(value)
+}
+
+/// Parse a value, or treat an empty one as clearing the field
+pub fn optional<T>(value: &str, parse: impl Fn(&str) -> Result<T, String>) -> Result<Option<T>, String> {
+    if value.is_empty() {
+        Ok(None)
+    } else {
+        parse(value).map(Some)
+    }
+}
+
+/// Split a `key=value` argument
+pub fn key_value(argument: &str) -> Result<(&str, &str), String> {
+    argument
+        .split_once('=')
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .ok_or_else(|| format!("Invalid '{}': expected key=value", argument))
+}
+
+/// Parse the starting quantity of a new product
+pub fn parse_quantity(value: &str) -> Result<u32, String> {
+    value.parse::<u32>().map_err(|_| format!("Invalid quantity '{}': must be a non-negative integer", value))
+}
+
+/// Parse a reorder point
+pub fn parse_reorder_point(value: &str) -> Result<u32, String> {
+    value.parse::<u32>().map_err(|_| format!("Invalid reorder point '{}': must be a non-negative integer", value))
+}
+
+/// Parse the quantity argument of a stock movement
+pub fn parse_stock_quantity(value: &str) -> Result<u32, String> {
+    value.parse::<u32>()
+        .map_err(|_| format!("Invalid quantity '{}': must be a positive integer", value))
+}
+
+/// Parse a lead time in whole days
+pub fn parse_lead_time(value: &str) -> Result<u32, String> {
+    value.parse::<u32>().map_err(|_| format!("Invalid lead time '{}': must be a whole number of days", value))
+}
+
+/// Parse a positive number of days
+pub fn parse_days(value: &str) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(days) if days > 0 => Ok(days),
+        _ => Err(format!("Invalid number of days '{}': must be a positive integer", value)),
+    }
+}
+
+/// Parse a sequence number given to --since
+pub fn parse_seq(value: &str) -> Result<u64, String> {
+    value.parse::<u64>().map_err(|_| format!("Invalid sequence number '{}': must be a non-negative integer", value))
+}
+
+/// Parse the baud rate of a scale
+pub fn parse_baud(value: &str) -> Result<u32, String> {
+    value.parse::<u32>().map_err(|_| format!("Invalid baud rate '{}': must be a positive integer", value))
+}
+
+/// Parse the `--type` of a removal: removal, damage, write-off, or adjustment
+pub fn parse_removal_type(value: &str) -> Result<TransactionType, String> {
+    let transaction_type = value.parse::<TransactionType>()?;
+    if transaction_type.adds_stock() || transaction_type.is_internal_move() {
+        return Err(format!(
+            "Invalid removal type '{}': expected removal, damage, write-off, or adjustment",
+            transaction_type.name()
+        ));
+    }
+    Ok(transaction_type)
+}
+
+/// Parse an EDI interchange control number
+pub fn parse_control(value: &str) -> Result<u32, String> {
+    value
+        .parse::<u32>()
+        .ok()
+        .filter(|control| (1..=edi::Envelope::MAX_CONTROL).contains(control))
+        .ok_or_else(|| format!("Invalid control number '{}': expected 1 to {}", value, edi::Envelope::MAX_CONTROL))
+}
+
+/// Parse a count sheet number
+pub fn parse_sheet(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(sheet) if sheet > 0 => Ok(sheet),
+        _ => Err(format!("Invalid sheet number '{}': must be a positive integer", value)),
+    }
+}
+
+/// Parse the most events to print
+pub fn parse_limit(value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(limit) if limit > 0 => Ok(limit),
+        _ => Err(format!("Invalid limit '{}': must be a positive integer", value)),
+    }
+}
+
+/// Parse a number of years to keep transactions before archiving
+pub fn parse_years(value: &str) -> Result<u32, String> {
+    match value.parse::<u32>() {
+        Ok(years) if years > 0 => Ok(years),
+        _ => Err(format!("Invalid number of years '{}': must be a positive integer", value)),
+    }
+}
+
+/// Parse a service level given as a fraction (0.95) or a percentage (95%)
+pub fn parse_service_level(value: &str) -> Result<f64, String> {
+    let level = match value.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().map(|percent| percent / 100.0),
+        None => value.parse::<f64>(),
+    };
+    match level {
+        Ok(level) if reorder::is_valid_service_level(level) => Ok(level),
+        _ => Err(format!("Invalid service level '{}': expected a value above 0.5 and below 1, e.g. 0.95 or 95%", value)),
+    }
+}
+
+/// Parse an uppercase currency code such as USD
+pub fn parse_currency(value: &str) -> Result<String, String> {
+    if value.is_empty() || !value.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(format!("Invalid currency '{}': expected an uppercase code such as USD", value));
+    }
+    Ok(value.to_string())
+}
+
+/// Parse the `--merge` rules of an opening balance migration
+pub fn parse_merge_policy(merges: &[String]) -> Result<Option<MergePolicy>, String> {
+    let mut merge = None;
+    for item in merges.iter().flat_map(|merges| merges.split(',')) {
+        let policy: &mut MergePolicy = merge.get_or_insert_default();
+        let (key, value) = key_value(item)?;
+        match key {
+            "name" => policy.name = value.parse()?,
+            "reorder-point" => policy.reorder_point = value.parse()?,
+            "quantity" => policy.quantity = value.parse()?,
+            _ => return Err(format!("Invalid merge field '{}': expected name, reorder-point, or quantity", key)),
+        }
+    }
+    Ok(merge)
+}
+
+/// Parse a datetime string into DateTime<Utc>
+pub fn parse_datetime(s: &str) -> Result<DateTime<Utc>, String> {
+    NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S")
+        .map(|dt| dt.and_utc())
+        .map_err(|_| format!("Invalid datetime '{}': expected format YYYY-MM-DDTHH:MM:SS", s))
+}
+
+/// Parse a YYYY-MM-DD date into midnight UTC
+pub fn parse_date(s: &str) -> Result<DateTime<Utc>, String> {
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|date| date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+        .map_err(|_| format!("Invalid date '{}': expected format YYYY-MM-DD", s))
+}
+
+/// Parse a YYYY-MM-DD date
+pub fn parse_day(s: &str) -> Result<NaiveDate, String> {
+    parse_date(s).map(|date| date.date_naive())
+}
@@ -0,0 +1,237 @@
+// Interactive prompts: the product creation wizard and reviewing reorder suggestions
+
+use std::io::{BufRead, Write};
+
+use crate::models::Product;
+use crate::service::{InventoryService, ProductUpdate, ReorderSuggestion};
+
+use super::format_error;
+
+/// Interactively prompt for a new product's fields and add it to the inventory
+///
+/// Each field is validated as it is entered and re-prompted on error; the SKU
+/// is checked for duplicates immediately. Blank input accepts the default shown
+/// in brackets. Nothing is saved until the summary is confirmed.
+pub fn run_product_wizard<R: BufRead, W: Write>(
+    service: &mut InventoryService,
+    input: &mut R,
+    output: &mut W,
+) -> Result<Product, String> {
+    let mut prompter = Prompter { input, output, ended: "Input ended before the product was created." };
+
+    let sku = prompter.ask_valid("SKU", None, |value| {
+        if value.is_empty() {
+            Err("SKU cannot be empty".to_string())
+        } else if service.get_product(value).is_ok() {
+            Err(format!("Product with SKU '{}' already exists", value))
+        } else {
+            Ok(value.to_string())
+        }
+    })?;
+    let name = prompter.ask_valid("Name", None, |value| {
+        if value.is_empty() {
+            Err("Name cannot be empty".to_string())
+        } else {
+            Ok(value.to_string())
+        }
+    })?;
+    let description = prompter.ask_valid("Description", Some(""), |value| Ok(value.to_string()))?;
+    let quantity = prompter.ask_valid("Initial quantity", Some("0"), parse_count)?;
+    let reorder_point = prompter.ask_valid("Reorder point", Some("0"), parse_count)?;
+    let category = prompter.ask_valid("Category", Some(""), optional_text)?;
+    let supplier = prompter.ask_valid("Supplier", Some(""), optional_text)?;
+    let location = prompter.ask_valid("Location", Some(""), optional_text)?;
+
+    prompter.say(&format!(
+        "\nSKU: {}\nName: {}\nDescription: {}\nQuantity: {}\nReorder Point: {}",
+        sku, name, description, quantity, reorder_point
+    ))?;
+    let confirmed = prompter.ask_valid("Create this product? [y/n]", Some("y"), |value| {
+        match value.to_lowercase().as_str() {
+            "y" | "yes" => Ok(true),
+            "n" | "no" => Ok(false),
+            _ => Err("Please answer y or n".to_string()),
+        }
+    })?;
+    if !confirmed {
+        return Err("Product creation cancelled.".to_string());
+    }
+
+    let product = service.add_product(sku, name, description, quantity, reorder_point)
+        .map_err(format_error)?;
+    if category.is_none() && supplier.is_none() && location.is_none() {
+        return Ok(product);
+    }
+    let update = ProductUpdate { category, supplier, location, ..Default::default() };
+    service.update_product(&product.sku, update).map_err(format_error)
+}
+
+/// Ask about each reorder suggestion and apply the ones accepted
+///
+/// Answering `q` stops without asking about the rest; suggestions already
+/// accepted stay applied. Returns the SKUs whose reorder point was changed.
+pub fn review_reorder_suggestions<R: BufRead, W: Write>(
+    service: &mut InventoryService,
+    suggestions: &[ReorderSuggestion],
+    input: &mut R,
+    output: &mut W,
+) -> Result<Vec<String>, String> {
+    let mut prompter = Prompter { input, output, ended: "Input ended before all suggestions were reviewed." };
+    let mut applied = Vec::new();
+    for suggestion in suggestions.iter().filter(|s| s.suggested != s.current) {
+        let question = format!(
+            "Set the reorder point of {} ({}) from {} to {}? [y/n/q]",
+            suggestion.sku, suggestion.name, suggestion.current, suggestion.suggested
+        );
+        let answer = prompter.ask_valid(&question, Some("n"), |value| match value.to_lowercase().as_str() {
+            "y" | "yes" => Ok(Some(true)),
+            "n" | "no" => Ok(Some(false)),
+            "q" | "quit" => Ok(None),
+            _ => Err("Please answer y, n, or q".to_string()),
+        })?;
+        match answer {
+            Some(true) => {
+                let update = ProductUpdate { reorder_point: Some(suggestion.suggested), ..Default::default() };
+                service.update_product(&suggestion.sku, update).map_err(format_error)?;
+                applied.push(suggestion.sku.clone());
+            }
+            Some(false) => {}
+            None => break,
+        }
+    }
+    Ok(applied)
+}
+
+/// Line-based prompting over arbitrary input and output streams
+struct Prompter<'a, R, W> {
+    input: &'a mut R,
+    output: &'a mut W,
+    /// Error returned if the input ends before an answer is given
+    ended: &'static str,
+}
+
+impl<R: BufRead, W: Write> Prompter<'_, R, W> {
+    /// Write a line of text to the output
+    fn say(&mut self, text: &str) -> Result<(), String> {
+        writeln!(self.output, "{}", text).map_err(|e| format!("Failed to write output: {}", e))
+    }
+
+    /// Prompt until `validate` accepts the (trimmed) answer
+    ///
+    /// Blank answers are replaced by `default` when one is given.
+    fn ask_valid<T>(
+        &mut self,
+        label: &str,
+        default: Option<&str>,
+        mut validate: impl FnMut(&str) -> Result<T, String>,
+    ) -> Result<T, String> {
+        loop {
+            match default {
+                Some(default) if !default.is_empty() => write!(self.output, "{} [{}]: ", label, default),
+                _ => write!(self.output, "{}: ", label),
+            }
+            .and_then(|_| self.output.flush())
+            .map_err(|e| format!("Failed to write output: {}", e))?;
+
+            let mut line = String::new();
+            let read = self.input.read_line(&mut line)
+                .map_err(|e| format!("Failed to read input: {}", e))?;
+            if read == 0 {
+                return Err(self.ended.to_string());
+            }
+
+            let answer = match line.trim() {
+                "" => default.unwrap_or(""),
+                answer => answer,
+            };
+            match validate(answer) {
+                Ok(value) => return Ok(value),
+                Err(message) => self.say(&format!("  {}", message))?,
+            }
+        }
+    }
+}
+
+/// Parse a non-negative count entered at a prompt
+fn parse_count(value: &str) -> Result<u32, String> {
+    value.parse::<u32>()
+        .map_err(|_| format!("Invalid number '{}': must be a non-negative integer", value))
+}
+
+/// Accept optional free text, mapping blank input to `None`
+fn optional_text(value: &str) -> Result<Option<String>, String> {
+    Ok(if value.is_empty() { None } else { Some(value.to_string()) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use crate::reorder::Demand;
+    use crate::storage::JsonStorage;
+
+    #[test]
+    fn test_product_wizard_reprompts_invalid_fields() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = InventoryService::new(Box::new(JsonStorage::new(temp_dir.path()))).unwrap();
+        service.add_product("SKU001".to_string(), "Existing".to_string(), String::new(), 1, 1).unwrap();
+
+        // Duplicate SKU, blank name, and bad quantity are each re-prompted
+        let answers = "SKU001\nSKU002\n\nWidget\n\nabc\n25\n\nTools\n\n\ny\n";
+        let mut output = Vec::new();
+        let product = run_product_wizard(&mut service, &mut answers.as_bytes(), &mut output).unwrap();
+
+        assert_eq!(product.sku, "SKU002");
+        assert_eq!(product.name, "Widget");
+        assert_eq!(product.quantity, 25);
+        assert_eq!(product.reorder_point, 0);
+        assert_eq!(product.category, Some("Tools".to_string()));
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Product with SKU 'SKU001' already exists"));
+        assert!(output.contains("Name cannot be empty"));
+        assert!(output.contains("Invalid number 'abc'"));
+    }
+
+    #[test]
+    fn test_review_applies_accepted_reorder_points_until_quit() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = InventoryService::new(Box::new(JsonStorage::new(temp_dir.path()))).unwrap();
+        let demand = Demand::from_daily(&[2, 2]);
+        let mut suggestions = Vec::new();
+        for sku in ["SKU001", "SKU002", "SKU003", "SKU004"] {
+            service.add_product(sku.to_string(), sku.to_string(), String::new(), 0, 5).unwrap();
+            suggestions.push(ReorderSuggestion {
+                sku: sku.to_string(),
+                name: sku.to_string(),
+                current: 5,
+                suggested: 14,
+                lead_time_days: 7,
+                demand,
+                safety_stock: 0,
+            });
+        }
+        suggestions[1].suggested = 5;
+
+        // SKU002 needs no change and isn't asked about
+        let answers = "maybe\ny\nn\nq\n";
+        let mut output = Vec::new();
+        let applied = review_reorder_suggestions(&mut service, &suggestions, &mut answers.as_bytes(), &mut output).unwrap();
+        assert_eq!(applied, vec!["SKU001"]);
+        let points: Vec<u32> = service.list_products().iter().map(|p| p.reorder_point).collect();
+        assert_eq!(points.iter().filter(|&&point| point == 14).count(), 1);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("Please answer y, n, or q"));
+        assert!(!output.contains("SKU002"));
+    }
+
+    #[test]
+    fn test_product_wizard_cancelled_adds_nothing() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = InventoryService::new(Box::new(JsonStorage::new(temp_dir.path()))).unwrap();
+
+        let answers = "SKU001\nWidget\n\n\n\n\n\n\nn\n";
+        let result = run_product_wizard(&mut service, &mut answers.as_bytes(), &mut Vec::new());
+        assert!(result.is_err());
+        assert!(service.list_products().is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+// User configuration stored alongside the data files
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::errors::StorageError;
+use crate::feed::FeedConfig;
+use crate::forecast::Seasonality;
+use crate::hooks::HooksConfig;
+use crate::inbox::InboxConfig;
+use crate::limits::FieldLimits;
+use crate::logging::LogFileConfig;
+use crate::mapping::ImportProfile;
+use crate::models::{ClockPolicy, DeletionPolicy, MovementThreshold, ProductFilter, ProductTemplate, RetentionPolicy, SaveMode, SizeQuotas, StocktakePolicy};
+use crate::mqtt::MqttConfig;
+use crate::shop::ShopConfig;
+use crate::rules::AlertRule;
+
+/// Name of the configuration file inside the data directory
+pub const CONFIG_FILE: &str = "config.json";
+
+/// Application settings loaded from `{data_dir}/config.json`
+///
+/// Every field is optional; a missing file yields the defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Global large-movement threshold applied to products without their own
+    pub large_movement_threshold: Option<MovementThreshold>,
+    /// Custom command aliases, mapping a name to a command line fragment
+    pub aliases: BTreeMap<String, String>,
+    /// Name recorded on transactions; defaults to the login user name
+    pub operator: Option<String>,
+    /// Output language (`en` or `es`); detected from the environment if unset
+    pub locale: Option<String>,
+    /// Optional rotating log file in the data directory
+    pub log: LogFileConfig,
+    /// Length limits for free-text fields
+    pub limits: FieldLimits,
+    /// Safeguards and archiving for delete-product
+    pub deletion: DeletionPolicy,
+    /// Settings for the `kiosk` command
+    pub kiosk: KioskConfig,
+    /// How the data files are read
+    pub storage: StorageConfig,
+    /// Named product listings saved with `view save`
+    pub views: BTreeMap<String, ProductFilter>,
+    /// Named defaults for new products saved with `template save`
+    pub product_templates: BTreeMap<String, ProductTemplate>,
+    /// Column mappings for `import-transactions --profile`, by name
+    pub import_profiles: BTreeMap<String, ImportProfile>,
+    /// How backorders are handled when stock arrives
+    pub backorders: BackorderConfig,
+    /// MQTT broker that stock levels and events are published to
+    pub mqtt: MqttConfig,
+    /// Tamper evidence for the transaction ledger
+    pub ledger: LedgerConfig,
+    /// How long notes and customer references are kept
+    pub retention: RetentionPolicy,
+    /// How the `forecast` report projects demand
+    pub forecast: ForecastConfig,
+    /// Alert rules checked against every product after each command
+    pub rules: Vec<AlertRule>,
+    /// External commands run when stock changes
+    pub hooks: HooksConfig,
+    /// Online store that stock levels are pushed to
+    pub shop: ShopConfig,
+    /// Inventory feeds delivered by `feed push`, by name
+    pub feeds: BTreeMap<String, FeedConfig>,
+    /// Suppliers whose e-mailed shipping notices `receive inbox` stages
+    pub inbox: InboxConfig,
+    /// Guards against transactions timestamped beyond the clock tolerance
+    pub clock: ClockPolicy,
+    /// Count variances `reconcile --apply` applies without a second count
+    pub stocktake: StocktakePolicy,
+    /// Soft limits on the size of the inventory
+    pub quotas: SizeQuotas,
+}
+
+/// Settings for stock-out forecasts
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForecastConfig {
+    /// Pattern demand follows (`none`, `weekly`, or `monthly`) unless
+    /// `--seasonality` is given
+    pub seasonality: Seasonality,
+}
+
+/// Settings for the transaction ledger
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LedgerConfig {
+    /// Link each new transaction to the previous one by hash, for `verify-ledger`
+    pub hash_chain: bool,
+}
+
+/// Settings for outstanding backorders
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackorderConfig {
+    /// Fill backorders from stock as it is added instead of only reporting them
+    pub auto_allocate: bool,
+}
+
+/// Settings for reading the data files
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    /// Memory-map the data files and open `view-product` and `history`
+    /// without loading the whole transaction ledger
+    pub memory_map: bool,
+    /// When changes are written to the data files
+    pub autosave: SaveMode,
+}
+
+/// Settings for the locked-down `kiosk` mode
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KioskConfig {
+    /// Code that unlocks every command; without one the kiosk stays locked
+    pub unlock_code: Option<String>,
+}
+
+impl Config {
+    /// Load the configuration from the given data directory
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<Self, StorageError> {
+        let path = dir.as_ref().join(CONFIG_FILE);
+        match fs::read_to_string(&path) {
+            Ok(contents) if contents.trim().is_empty() => Ok(Config::default()),
+            Ok(contents) => serde_json::from_str(&contents)
+                .map_err(|source| StorageError::Parse { path, line: None, source }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(source) => Err(StorageError::Read { path, source }),
+        }
+    }
+
+    /// Save a named view to the configuration file, or remove it with `None`
+    ///
+    /// Only the `views` entry is rewritten; other settings are kept as written,
+    /// although the file is reformatted.
+    pub fn save_view<P: AsRef<Path>>(dir: P, name: &str, view: Option<&ProductFilter>) -> Result<(), StorageError> {
+        save_entry(dir.as_ref(), "views", name, view)
+    }
+
+    /// Save a named product template to the configuration file, or remove it
+    /// with `None`
+    ///
+    /// Only the `product_templates` entry is rewritten, as with `save_view`.
+    pub fn save_product_template<P: AsRef<Path>>(
+        dir: P,
+        name: &str,
+        template: Option<&ProductTemplate>,
+    ) -> Result<(), StorageError> {
+        save_entry(dir.as_ref(), "product_templates", name, template)
+    }
+}
+
+/// Set or remove one entry of a map-valued setting, keeping the rest of the
+/// configuration file as written
+fn save_entry<T: Serialize>(dir: &Path, section: &str, name: &str, value: Option<&T>) -> Result<(), StorageError> {
+    let path = dir.join(CONFIG_FILE);
+    let mut document = match fs::read_to_string(&path) {
+        Ok(contents) if !contents.trim().is_empty() => serde_json::from_str(&contents)
+            .map_err(|source| StorageError::Parse { path: path.clone(), line: None, source })?,
+        Ok(_) => Value::Object(Map::new()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Value::Object(Map::new()),
+        Err(source) => return Err(StorageError::Read { path, source }),
+    };
+    let Some(settings) = document.as_object_mut() else {
+        return Err(StorageError::Parse {
+            path,
+            line: None,
+            source: serde::de::Error::custom("the configuration must be a JSON object"),
+        });
+    };
+    let entries = settings.entry(section).or_insert_with(|| Value::Object(Map::new()));
+    if !entries.is_object() {
+        *entries = Value::Object(Map::new());
+    }
+    let entries = entries.as_object_mut().expect("section is an object");
+    match value {
+        Some(value) => {
+            let value = serde_json::to_value(value)
+                .map_err(|source| StorageError::Serialize { path: path.clone(), source })?;
+            entries.insert(name.to_string(), value);
+        }
+        None => {
+            entries.remove(name);
+        }
+    }
+    let json = serde_json::to_string_pretty(&document)
+        .map_err(|source| StorageError::Serialize { path: path.clone(), source })?;
+    fs::write(&path, json).map_err(|source| StorageError::Write { path, source })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_missing_config_returns_default() {
+        let temp_dir = TempDir::new().unwrap();
+        assert_eq!(Config::load(temp_dir.path()).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn test_load_config_settings() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(
+            temp_dir.path().join(CONFIG_FILE),
+            r#"{ "large_movement_threshold": "25%", "aliases": { "rcv": "add-stock" }, "kiosk": { "unlock_code": "4821" }, "storage": { "memory_map": true } }"#,
+        ).unwrap();
+
+        let config = Config::load(temp_dir.path()).unwrap();
+        assert_eq!(config.large_movement_threshold, Some(MovementThreshold::Percent(25)));
+        assert_eq!(config.aliases.get("rcv").map(String::as_str), Some("add-stock"));
+        assert_eq!(config.kiosk.unlock_code.as_deref(), Some("4821"));
+        assert!(config.storage.memory_map);
+    }
+
+    #[test]
+    fn test_retention_beyond_the_calendar_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join(CONFIG_FILE), r#"{ "retention": { "notes_years": 400000 } }"#).unwrap();
+        let error = Config::load(temp_dir.path()).unwrap_err();
+        assert!(error.to_string().contains("longer than the 1000 years allowed"), "{}", error);
+
+        fs::write(temp_dir.path().join(CONFIG_FILE), r#"{ "retention": { "notes_years": 7, "customer_years": null } }"#).unwrap();
+        assert_eq!(Config::load(temp_dir.path()).unwrap().retention.notes_years, Some(7));
+    }
+
+    #[test]
+    fn test_save_view_keeps_other_settings() {
+        let temp_dir = TempDir::new().unwrap();
+        fs::write(temp_dir.path().join(CONFIG_FILE), r#"{ "operator": "sam" }"#).unwrap();
+        let view = ProductFilter { category: Some("widgets".to_string()), below_reorder: true, ..Default::default() };
+
+        Config::save_view(temp_dir.path(), "low-widgets", Some(&view)).unwrap();
+        let config = Config::load(temp_dir.path()).unwrap();
+        assert_eq!(config.operator.as_deref(), Some("sam"));
+        assert_eq!(config.views.get("low-widgets"), Some(&view));
+
+        Config::save_view(temp_dir.path(), "low-widgets", None).unwrap();
+        assert!(Config::load(temp_dir.path()).unwrap().views.is_empty());
+    }
+}
@@ -0,0 +1,175 @@
+// Copying all stored data from one storage backend to another
+//
+// Every kind of record the `Storage` trait knows about is read from the
+// source and written to the target, then read back from the target and
+// compared: the record counts and a SHA-256 checksum of each kind must
+// match. The source is only ever read, so it stays in use until the target
+// has passed and the data directory is switched over.
+
+use std::fmt;
+
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+use crate::errors::StorageError;
+use crate::storage::{JsonStorage, Storage};
+
+/// Names of the storage backends that can be converted between
+pub const BACKENDS: &[&str] = &["json"];
+
+/// Open a registered backend on a data directory
+pub fn open_backend(name: &str, dir: &str) -> Result<Box<dyn Storage>, String> {
+    match name {
+        "json" => Ok(Box::new(JsonStorage::new(dir))),
+        _ => Err(format!("Unknown storage backend '{}': expected one of {}", name, BACKENDS.join(", "))),
+    }
+}
+
+/// Record count and checksum of one kind of stored data
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordSummary {
+    /// Kind of record, e.g. `products`
+    pub kind: &'static str,
+    /// Number of records
+    pub count: usize,
+    /// SHA-256 of the records in a canonical order, as hex
+    pub checksum: String,
+}
+
+/// Why a conversion stopped
+#[derive(Debug)]
+pub enum ConvertError {
+    /// Reading the source or writing the target failed
+    Storage(StorageError),
+    /// The target already holds data that the conversion would overwrite
+    TargetNotEmpty { kind: &'static str, count: usize },
+    /// The target read back differently from the source
+    Mismatch { source: RecordSummary, target: RecordSummary },
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConvertError::Storage(e) => write!(f, "{}", e),
+            ConvertError::TargetNotEmpty { kind, count } => {
+                write!(f, "the target already holds {} {}; convert into an empty data directory", count, kind)
+            }
+            ConvertError::Mismatch { source, target } => write!(
+                f,
+                "the target's {} don't match the source: {} records ({}...) instead of {} ({}...)",
+                source.kind,
+                target.count,
+                &target.checksum[..12],
+                source.count,
+                &source.checksum[..12]
+            ),
+        }
+    }
+}
+
+impl From<StorageError> for ConvertError {
+    fn from(e: StorageError) -> Self {
+        ConvertError::Storage(e)
+    }
+}
+
+/// Count and checksum every kind of data in a store
+pub fn summarize(storage: &dyn Storage) -> Result<Vec<RecordSummary>, StorageError> {
+    Ok(vec![
+        summary("products", &storage.load_products()?),
+        summary("transactions", &storage.load_transactions()?),
+        summary("archived transactions", &storage.load_archived_transactions()?),
+        summary("audit events", &storage.load_audit_log()?),
+        summary("deleted products", &storage.load_tombstones()?),
+        summary("staged receipts", &storage.load_receiving()?.into_iter().collect::<Vec<_>>()),
+        summary("supplier prices", &storage.load_prices()?),
+        summary("backorders", &storage.load_backorders()?),
+        summary("customers", &storage.load_customers()?),
+        summary("ledger heads", &storage.load_ledger_head()?.into_iter().collect::<Vec<_>>()),
+        summary("sequence numbers", &storage.load_last_seq()?.into_iter().collect::<Vec<_>>()),
+        summary("KPI snapshots", &storage.load_kpi_snapshots()?),
+    ])
+}
+
+/// Copy everything from `source` to an empty `target`, then verify it
+///
+/// Returns the summary both stores now share.
+pub fn convert(source: &dyn Storage, target: &dyn Storage) -> Result<Vec<RecordSummary>, ConvertError> {
+    if let Some(existing) = summarize(target)?.into_iter().find(|summary| summary.count > 0) {
+        return Err(ConvertError::TargetNotEmpty { kind: existing.kind, count: existing.count });
+    }
+    let expected = summarize(source)?;
+
+    target.save_products(&source.load_products()?)?;
+    target.save_transactions(&source.load_transactions()?)?;
+    let archived = source.load_archived_transactions()?;
+    if !archived.is_empty() {
+        target.archive_transactions(&archived)?;
+    }
+    for event in source.load_audit_log()? {
+        target.append_audit_event(&event)?;
+    }
+    target.save_tombstones(&source.load_tombstones()?)?;
+    target.save_receiving(source.load_receiving()?.as_ref())?;
+    target.save_prices(&source.load_prices()?)?;
+    target.save_backorders(&source.load_backorders()?)?;
+    target.save_customers(&source.load_customers()?)?;
+    if let Some(head) = source.load_ledger_head()? {
+        target.save_ledger_head(&head)?;
+    }
+    if let Some(seq) = source.load_last_seq()? {
+        target.save_last_seq(seq)?;
+    }
+    target.save_kpi_snapshots(&source.load_kpi_snapshots()?)?;
+
+    let actual = summarize(target)?;
+    for (source, target) in expected.iter().zip(&actual) {
+        if source != target {
+            return Err(ConvertError::Mismatch { source: source.clone(), target: target.clone() });
+        }
+    }
+    Ok(actual)
+}
+
+/// Count and checksum records, independent of their stored order
+fn summary<T: Serialize>(kind: &'static str, records: &[T]) -> RecordSummary {
+    let mut lines: Vec<String> = records
+        .iter()
+        .map(|record| serde_json::to_string(record).expect("stored records serialize"))
+        .collect();
+    lines.sort();
+    let mut hasher = Sha256::new();
+    for line in &lines {
+        hasher.update(line.as_bytes());
+        hasher.update(b"\n");
+    }
+    let checksum = hasher.finalize().iter().map(|byte| format!("{:02x}", byte)).collect();
+    RecordSummary { kind, count: records.len(), checksum }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::InventoryService;
+    use crate::testing::seed_demo_data;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_convert_copies_and_verifies_everything() {
+        let source_dir = TempDir::new().unwrap();
+        let target_dir = TempDir::new().unwrap();
+        let mut service = InventoryService::new(Box::new(JsonStorage::new(source_dir.path()))).unwrap();
+        seed_demo_data(&mut service).unwrap();
+        service.add_customer("ACME".into(), "Acme".into()).unwrap();
+        let source = JsonStorage::new(source_dir.path());
+        let before = summarize(&source).unwrap();
+
+        let summaries = convert(&source, &JsonStorage::new(target_dir.path())).unwrap();
+        assert_eq!(summaries, before);
+        assert!(summaries.iter().any(|s| s.kind == "transactions" && s.count > 0));
+
+        // Converting again would overwrite the copy
+        let again = convert(&source, &JsonStorage::new(target_dir.path()));
+        assert!(matches!(again, Err(ConvertError::TargetNotEmpty { kind: "products", .. })));
+    }
+}
@@ -0,0 +1,203 @@
+// FIFO cost layers rebuilt from the transaction history
+//
+// Every receipt of stock opens a layer at the unit cost recorded on its
+// transaction; every issue takes units from the oldest layers first. What's
+// left is the stock on hand at the cost it came in at, and what was taken is
+// the cost of the goods issued.
+
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+
+use crate::models::{Money, Product, Transaction};
+
+/// Units received together at one unit cost
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CostLayer {
+    /// When the units were received, or `None` for stock that predates the history
+    pub received_at: Option<DateTime<Utc>>,
+    /// Units of the receipt still on hand
+    pub quantity: u32,
+    /// Cost of one unit, if it is known
+    pub unit_cost: Option<Money>,
+}
+
+/// Value of a number of units, some of which may have no known cost
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Valuation {
+    /// Units counted
+    pub units: u64,
+    /// Value of the units with a known cost
+    pub value: Money,
+    /// Units without a known cost, left out of `value`
+    pub uncosted_units: u64,
+}
+
+impl Valuation {
+    fn add(&mut self, quantity: u32, unit_cost: Option<Money>) {
+        let units = u64::from(quantity);
+        self.units += units;
+        match unit_cost {
+            Some(cost) => self.value = self.value + cost.times(units),
+            None => self.uncosted_units += units,
+        }
+    }
+
+    /// Combine two valuations
+    pub fn merge(&mut self, other: Valuation) {
+        self.units += other.units;
+        self.value = self.value + other.value;
+        self.uncosted_units += other.uncosted_units;
+    }
+
+    /// Take back a valuation merged in earlier
+    pub fn subtract(&mut self, other: Valuation) {
+        self.units -= other.units;
+        self.value = self.value - other.value;
+        self.uncosted_units -= other.uncosted_units;
+    }
+}
+
+/// A product's stock on hand as FIFO cost layers, oldest first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CostLayers {
+    layers: VecDeque<CostLayer>,
+}
+
+impl CostLayers {
+    /// Rebuild a product's layers from its transactions, in any order
+    ///
+    /// Stock on hand that no transaction accounts for, such as the opening
+    /// quantity given to `add-product` or stock whose receipts were archived,
+    /// becomes a first layer at the product's unit cost. With `until`, only
+    /// transactions before it are replayed, giving the layers at that moment.
+    pub fn replay(product: &Product, transactions: &[&Transaction], until: Option<DateTime<Utc>>) -> Self {
+        Self::replay_with(product, transactions, until, |_, _| {})
+    }
+
+    /// Like `replay`, handing each replayed transaction to `on_apply` with
+    /// the cost of the units it issued
+    pub fn replay_with(
+        product: &Product,
+        transactions: &[&Transaction],
+        until: Option<DateTime<Utc>>,
+        mut on_apply: impl FnMut(&Transaction, Valuation),
+    ) -> Self {
+        let mut transactions: Vec<&Transaction> = transactions
+            .iter()
+            .copied()
+            .filter(|t| !t.transaction_type.is_internal_move())
+            .collect();
+        transactions.sort_by_key(|t| t.order_key());
+        let net: i64 = transactions.iter().map(|t| signed(t)).sum();
+        let opening = u32::try_from((product.on_hand() as i64 - net).max(0)).unwrap_or(u32::MAX);
+
+        let mut layers = CostLayers::default();
+        layers.receive(None, opening, product.unit_cost);
+        for transaction in transactions.into_iter().take_while(|t| until.is_none_or(|until| t.timestamp < until)) {
+            let issued = layers.apply(transaction);
+            on_apply(transaction, issued);
+        }
+        layers
+    }
+
+    /// Apply a transaction, returning the cost of the units it issued
+    ///
+    /// Moves into and out of quarantine or between conditions leave the
+    /// layers as they are.
+    pub fn apply(&mut self, transaction: &Transaction) -> Valuation {
+        let kind = transaction.transaction_type;
+        if kind.is_internal_move() {
+            Valuation::default()
+        } else if kind.adds_stock() {
+            self.receive(Some(transaction.timestamp), transaction.quantity, transaction.unit_cost);
+            Valuation::default()
+        } else {
+            self.issue(transaction.quantity)
+        }
+    }
+
+    /// Open a layer for received units
+    pub fn receive(&mut self, received_at: Option<DateTime<Utc>>, quantity: u32, unit_cost: Option<Money>) {
+        if quantity > 0 {
+            self.layers.push_back(CostLayer { received_at, quantity, unit_cost });
+        }
+    }
+
+    /// Take units from the oldest layers, returning their cost
+    ///
+    /// Units beyond what the layers hold are counted as uncosted.
+    pub fn issue(&mut self, mut quantity: u32) -> Valuation {
+        let mut issued = Valuation::default();
+        while quantity > 0 {
+            let Some(layer) = self.layers.front_mut() else {
+                issued.add(quantity, None);
+                break;
+            };
+            let taken = quantity.min(layer.quantity);
+            issued.add(taken, layer.unit_cost);
+            layer.quantity -= taken;
+            quantity -= taken;
+            if layer.quantity == 0 {
+                self.layers.pop_front();
+            }
+        }
+        issued
+    }
+
+    /// The layers, oldest first
+    pub fn layers(&self) -> impl Iterator<Item = &CostLayer> {
+        self.layers.iter()
+    }
+
+    /// Units held and their value at cost
+    pub fn valuation(&self) -> Valuation {
+        let mut valuation = Valuation::default();
+        for layer in &self.layers {
+            valuation.add(layer.quantity, layer.unit_cost);
+        }
+        valuation
+    }
+}
+
+/// Change in stock on hand made by a transaction that isn't an internal move
+fn signed(transaction: &Transaction) -> i64 {
+    let quantity = i64::from(transaction.quantity);
+    if transaction.transaction_type.adds_stock() { quantity } else { -quantity }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TransactionType;
+    use crate::testing::ProductBuilder;
+
+    fn transaction(transaction_type: TransactionType, quantity: u32, unit_cost: Option<&str>) -> Transaction {
+        Transaction {
+            unit_cost: unit_cost.map(|cost| cost.parse().unwrap()),
+            ..Transaction::new("SKU001", transaction_type, quantity, None)
+        }
+    }
+
+    #[test]
+    fn test_issues_take_oldest_layers_first() {
+        let mut product = ProductBuilder::new("SKU001").quantity(12).build();
+        product.unit_cost = Some("1.00".parse().unwrap());
+        let received = [
+            transaction(TransactionType::Addition, 5, Some("2.00")),
+            transaction(TransactionType::Addition, 5, Some("3.00")),
+        ];
+        let issued = transaction(TransactionType::Removal, 4, None);
+        let history: Vec<&Transaction> = received.iter().chain([&issued]).collect();
+
+        // 6 opening units at 1.00 less the 4 issued, then 5 at 2.00 and 5 at 3.00
+        let mut layers = CostLayers::replay(&product, &history, None);
+        assert_eq!(layers.layers().map(|l| l.quantity).collect::<Vec<_>>(), vec![2, 5, 5]);
+        assert_eq!(layers.valuation().value.to_string(), "27.00");
+
+        let cost = layers.issue(4);
+        assert_eq!((cost.units, cost.value.to_string()), (4, "6.00".to_string()));
+        assert_eq!(layers.valuation().value.to_string(), "21.00");
+        assert_eq!(layers.issue(20).uncosted_units, 12);
+    }
+}
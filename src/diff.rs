@@ -0,0 +1,140 @@
+// Differences between two states of the inventory
+//
+// Snapshots are data directories or backups of them (or a products.json
+// file on its own). Comparing two stocktakes this way shows what appeared,
+// disappeared, or changed quantity in between, which is where shrinkage
+// investigations start.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+
+use crate::errors::StorageError;
+use crate::models::Product;
+use crate::storage::{JsonStorage, Storage};
+
+/// A product whose quantity differs between two states
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantityChange {
+    /// Product SKU
+    pub sku: String,
+    /// Product name in the later state
+    pub name: String,
+    /// Quantity in the earlier state
+    pub before: u32,
+    /// Quantity in the later state
+    pub after: u32,
+}
+
+impl QuantityChange {
+    /// Signed change in quantity
+    pub fn delta(&self) -> i64 {
+        i64::from(self.after) - i64::from(self.before)
+    }
+}
+
+/// Products added, removed, and changed between two states, each ordered by SKU
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InventoryDiff {
+    /// Products only in the later state
+    pub added: Vec<Product>,
+    /// Products only in the earlier state
+    pub removed: Vec<Product>,
+    /// Products in both states with different quantities
+    pub changed: Vec<QuantityChange>,
+}
+
+impl InventoryDiff {
+    /// Compare an earlier list of products with a later one
+    pub fn between(before: &[Product], after: &[Product]) -> Self {
+        let before: BTreeMap<&str, &Product> = before.iter().map(|p| (p.sku.as_str(), p)).collect();
+        let after: BTreeMap<&str, &Product> = after.iter().map(|p| (p.sku.as_str(), p)).collect();
+
+        let mut diff = InventoryDiff::default();
+        for (sku, product) in &after {
+            match before.get(sku) {
+                None => diff.added.push((*product).clone()),
+                Some(old) if old.quantity != product.quantity => diff.changed.push(QuantityChange {
+                    sku: sku.to_string(),
+                    name: product.name.clone(),
+                    before: old.quantity,
+                    after: product.quantity,
+                }),
+                Some(_) => {}
+            }
+        }
+        diff.removed = before
+            .iter()
+            .filter(|(sku, _)| !after.contains_key(*sku))
+            .map(|(_, product)| (*product).clone())
+            .collect();
+        diff
+    }
+
+    /// Whether the two states hold the same products and quantities
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Net change in units on hand across all products
+    pub fn net_change(&self) -> i64 {
+        let added: i64 = self.added.iter().map(|p| i64::from(p.quantity)).sum();
+        let removed: i64 = self.removed.iter().map(|p| i64::from(p.quantity)).sum();
+        let changed: i64 = self.changed.iter().map(QuantityChange::delta).sum();
+        added - removed + changed
+    }
+}
+
+/// Load the products of a snapshot: a data directory or a products JSON file
+///
+/// Unlike normal startup, a missing snapshot is an error rather than an
+/// empty inventory.
+pub fn load_snapshot(path: &Path) -> Result<Vec<Product>, StorageError> {
+    let storage = if path.is_dir() {
+        JsonStorage::new(path)
+    } else if path.is_file() {
+        let dir = path.parent().unwrap_or(Path::new("."));
+        JsonStorage::with_paths(path, dir.join("transactions.json"))
+    } else {
+        return Err(StorageError::Read {
+            path: path.to_path_buf(),
+            source: io::Error::new(io::ErrorKind::NotFound, "snapshot not found"),
+        });
+    };
+    storage.load_products()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn product(sku: &str, quantity: u32) -> Product {
+        Product { sku: sku.to_string(), name: sku.to_lowercase(), quantity, ..Default::default() }
+    }
+
+    #[test]
+    fn test_diff_between_states() {
+        let before = vec![product("A", 10), product("B", 5), product("C", 1)];
+        let after = vec![product("D", 4), product("A", 7), product("C", 1)];
+        let diff = InventoryDiff::between(&before, &after);
+
+        assert_eq!(diff.added, vec![product("D", 4)]);
+        assert_eq!(diff.removed, vec![product("B", 5)]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].delta(), -3);
+        assert_eq!(diff.net_change(), 4 - 5 - 3);
+        assert!(InventoryDiff::between(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn test_load_snapshot_from_directory_or_file() {
+        let temp_dir = TempDir::new().unwrap();
+        JsonStorage::new(temp_dir.path()).save_products(&[product("A", 1)]).unwrap();
+
+        assert_eq!(load_snapshot(temp_dir.path()).unwrap(), vec![product("A", 1)]);
+        assert_eq!(load_snapshot(&temp_dir.path().join("products.json")).unwrap().len(), 1);
+        let missing = load_snapshot(&temp_dir.path().join("backup")).unwrap_err();
+        assert_eq!(missing.code(), "STORAGE_READ");
+    }
+}
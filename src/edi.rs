@@ -0,0 +1,250 @@
+// Inventory positions as an EDI 846 (Inventory Inquiry/Advice) feed
+//
+// Retail partners often want a periodic feed of what a supplier has on hand.
+// The X12 form is one interchange holding one 846 transaction set, version
+// 004010, with a LIN/PID/QTY loop per product:
+//
+//     LIN**SK*<sku>[*UP*<barcode>]   product identifiers
+//     PID*F****<name>                 description
+//     QTY*33*<available>*EA           quantity available for sale
+//     QTY*02*<on order>*EA            units on order, when there are any
+//
+// Elements are separated by `*`, segments end with `~`, and `>` separates
+// components, so those characters are replaced by spaces in free text.
+// Partners that don't take X12 usually accept the CSV form, one row per
+// product with the same values, or NDJSON with one `api::v1` product per line.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::api;
+use crate::models::Product;
+
+/// Interchange control version written in ISA12
+const X12_VERSION: &str = "00401";
+
+/// Shape of the feed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedFormat {
+    /// ANSI X12 846
+    #[default]
+    X12,
+    /// One CSV row per product
+    Csv,
+    /// One JSON product per line
+    Ndjson,
+}
+
+impl FeedFormat {
+    /// Every format, in the order listed in help
+    pub const ALL: [FeedFormat; 3] = [FeedFormat::X12, FeedFormat::Csv, FeedFormat::Ndjson];
+
+    /// Name used on the command line and in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            FeedFormat::X12 => "x12",
+            FeedFormat::Csv => "csv",
+            FeedFormat::Ndjson => "ndjson",
+        }
+    }
+
+    /// Usual file extension
+    pub fn extension(self) -> &'static str {
+        match self {
+            FeedFormat::X12 => "edi",
+            FeedFormat::Csv => "csv",
+            FeedFormat::Ndjson => "ndjson",
+        }
+    }
+}
+
+impl std::str::FromStr for FeedFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        FeedFormat::ALL.into_iter().find(|format| format.name() == lower).ok_or_else(|| {
+            let names: Vec<&str> = FeedFormat::ALL.iter().map(|format| format.name()).collect();
+            format!("Invalid feed format '{}': expected one of {}", s, names.join(", "))
+        })
+    }
+}
+
+/// Who the interchange is from and to, and how it is numbered
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    /// Sender ID (ISA06, GS02), up to 15 characters
+    pub sender: String,
+    /// Receiver ID (ISA08, GS03), up to 15 characters
+    pub receiver: String,
+    /// Interchange and group control number, up to 9 digits
+    pub control: u32,
+}
+
+impl Envelope {
+    /// Largest control number ISA13 can hold
+    pub const MAX_CONTROL: u32 = 999_999_999;
+
+    /// A control number that increases from minute to minute, `YYDDDHHMM`,
+    /// for feeds sent no more than once a minute
+    pub fn control_for(now: DateTime<Utc>) -> u32 {
+        now.format("%y%j%H%M").to_string().parse().unwrap_or(1)
+    }
+
+    /// Check the IDs and control number fit their elements
+    pub fn validate(&self) -> Result<(), String> {
+        for (what, id) in [("Sender", &self.sender), ("Receiver", &self.receiver)] {
+            if id.trim().is_empty() || id.len() > 15 || !id.is_ascii() || id.contains(['*', '~', '>']) {
+                return Err(format!("{} ID '{}' must be 1 to 15 ASCII characters without * ~ or >", what, id));
+            }
+        }
+        if self.control == 0 || self.control > Self::MAX_CONTROL {
+            return Err(format!("Control number {} must be between 1 and {}", self.control, Self::MAX_CONTROL));
+        }
+        Ok(())
+    }
+}
+
+/// The products' positions in a format; X12 needs an envelope
+pub fn render(format: FeedFormat, products: &[&Product], envelope: Option<&Envelope>, now: DateTime<Utc>) -> Result<String, String> {
+    match format {
+        FeedFormat::X12 => {
+            let envelope = envelope.ok_or("An X12 feed needs sender and receiver IDs")?;
+            envelope.validate()?;
+            Ok(to_x12(products, envelope, now))
+        }
+        FeedFormat::Csv => Ok(to_csv(products, now)),
+        FeedFormat::Ndjson => Ok(to_ndjson(products)),
+    }
+}
+
+/// An X12 846 interchange for the products, as of `now`
+pub fn to_x12(products: &[&Product], envelope: &Envelope, now: DateTime<Utc>) -> String {
+    let control = envelope.control;
+    let mut set = vec![
+        "ST*846*0001".to_string(),
+        format!("BIA*00*DD*{}*{}", control, now.format("%Y%m%d")),
+    ];
+    for product in products {
+        let mut lin = format!("LIN**SK*{}", element(&product.sku));
+        if let Some(barcode) = product.barcode.as_deref() {
+            lin.push_str(&format!("*UP*{}", element(barcode)));
+        }
+        set.push(lin);
+        set.push(format!("PID*F****{}", element(&product.name)));
+        set.push(format!("QTY*33*{}*EA", product.quantity));
+        if product.on_order > 0 {
+            set.push(format!("QTY*02*{}*EA", product.on_order));
+        }
+    }
+    set.push(format!("CTT*{}", products.len()));
+    set.push(format!("SE*{}*0001", set.len() + 1));
+
+    let mut segments = vec![
+        format!(
+            "ISA*00*{:10}*00*{:10}*ZZ*{:15}*ZZ*{:15}*{}*U*{}*{:09}*0*P*>",
+            "", "", envelope.sender, envelope.receiver, now.format("%y%m%d*%H%M"), X12_VERSION, control
+        ),
+        format!(
+            "GS*IB*{}*{}*{}*{}*X*004010",
+            envelope.sender.trim(), envelope.receiver.trim(), now.format("%Y%m%d*%H%M"), control
+        ),
+    ];
+    segments.extend(set);
+    segments.push(format!("GE*1*{}", control));
+    segments.push(format!("IEA*1*{:09}", control));
+    segments.iter().map(|segment| format!("{}~\n", segment)).collect()
+}
+
+/// The same positions as CSV, with a header row
+pub fn to_csv(products: &[&Product], now: DateTime<Utc>) -> String {
+    let as_of = now.format("%Y-%m-%dT%H:%M:%SZ").to_string();
+    let mut csv = String::from("sku,upc,description,quantity_available,quantity_on_order,as_of\n");
+    for product in products {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            csv_field(&product.sku),
+            csv_field(product.barcode.as_deref().unwrap_or_default()),
+            csv_field(&product.name),
+            product.quantity,
+            product.on_order,
+            as_of
+        ));
+    }
+    csv
+}
+
+/// One `api::v1` product per line
+pub fn to_ndjson(products: &[&Product]) -> String {
+    products
+        .iter()
+        .map(|product| format!("{}\n", serde_json::json!(api::v1::Product::from(*product))))
+        .collect()
+}
+
+/// Free text made safe for an X12 element
+fn element(text: &str) -> String {
+    text.chars().map(|c| if matches!(c, '*' | '~' | '>') || c.is_control() { ' ' } else { c }).collect()
+}
+
+/// A CSV field, quoted when it holds a comma, quote, or line break
+pub(crate) fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_846_lists_each_products_position() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 4, 9, 30, 0).unwrap();
+        let bolts = Product {
+            sku: "B-1".to_string(),
+            name: "Bolts *M6*, zinc".to_string(),
+            barcode: Some("012345678905".to_string()),
+            quantity: 40,
+            on_order: 100,
+            ..Default::default()
+        };
+        let nuts = Product { sku: "N-1".to_string(), name: "Nuts".to_string(), quantity: 0, ..Default::default() };
+        let envelope = Envelope { sender: "ACMESUPPLY".to_string(), receiver: "BIGRETAIL".to_string(), control: 42 };
+        assert!(envelope.validate().is_ok());
+        assert!(Envelope { sender: "A*B".to_string(), ..envelope.clone() }.validate().is_err());
+        assert_eq!(Envelope::control_for(now), 250630930);
+
+        assert_eq!(
+            to_x12(&[&bolts, &nuts], &envelope, now),
+            "ISA*00*          *00*          *ZZ*ACMESUPPLY     *ZZ*BIGRETAIL      *250304*0930*U*00401*000000042*0*P*>~\n\
+             GS*IB*ACMESUPPLY*BIGRETAIL*20250304*0930*42*X*004010~\n\
+             ST*846*0001~\n\
+             BIA*00*DD*42*20250304~\n\
+             LIN**SK*B-1*UP*012345678905~\n\
+             PID*F****Bolts  M6 , zinc~\n\
+             QTY*33*40*EA~\n\
+             QTY*02*100*EA~\n\
+             LIN**SK*N-1~\n\
+             PID*F****Nuts~\n\
+             QTY*33*0*EA~\n\
+             CTT*2~\n\
+             SE*11*0001~\n\
+             GE*1*42~\n\
+             IEA*1*000000042~\n"
+        );
+        assert_eq!(
+            to_csv(&[&bolts, &nuts], now),
+            "sku,upc,description,quantity_available,quantity_on_order,as_of\n\
+             B-1,012345678905,\"Bolts *M6*, zinc\",40,100,2025-03-04T09:30:00Z\n\
+             N-1,,Nuts,0,0,2025-03-04T09:30:00Z\n"
+        );
+        let ndjson = to_ndjson(&[&bolts, &nuts]);
+        let lines: Vec<serde_json::Value> = ndjson.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!((lines.len(), &lines[1]["sku"], &lines[1]["low_stock"]), (2, &serde_json::json!("N-1"), &serde_json::json!(true)));
+    }
+}
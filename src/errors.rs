@@ -1,73 +1,201 @@
 // Error types for the application
+//
+// Both enums are `#[non_exhaustive]`: match on the variants you handle and
+// fall back to `code()` or the `Display` message for the rest.
 
-use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use thiserror::Error;
+
+use crate::models::MovementThreshold;
+use crate::validation::ValidationReport;
 
 /// Errors that can occur in storage operations
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum StorageError {
-    /// Failed to read from storage
-    ReadError(String),
-    /// Failed to write to storage
-    WriteError(String),
-    /// Failed to parse/deserialize data
-    ParseError(String),
-    /// Storage file not found
-    FileNotFound(String),
+    /// Failed to read a storage file
+    #[error("Failed to read {}: {source}", path.display())]
+    Read { path: PathBuf, source: io::Error },
+    /// Failed to write a storage file
+    #[error("Failed to write {}: {source}", path.display())]
+    Write { path: PathBuf, source: io::Error },
+    /// Failed to create the directory holding a storage file
+    #[error("Failed to create directory {}: {source}", path.display())]
+    CreateDir { path: PathBuf, source: io::Error },
+    /// Failed to parse a storage file; `line` is set for line-delimited files
+    #[error("Failed to parse {}{}: {source}", path.display(), line.map(|n| format!(" line {}", n)).unwrap_or_default())]
+    Parse { path: PathBuf, line: Option<usize>, source: serde_json::Error },
+    /// Failed to serialize data before writing it
+    #[error("Failed to serialize data for {}: {source}", path.display())]
+    Serialize { path: PathBuf, source: serde_json::Error },
 }
 
-impl fmt::Display for StorageError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl StorageError {
+    /// Stable identifier for the kind of failure, e.g. `STORAGE_PARSE`
+    pub fn code(&self) -> &'static str {
+        match self {
+            StorageError::Read { .. } => "STORAGE_READ",
+            StorageError::Write { .. } => "STORAGE_WRITE",
+            StorageError::CreateDir { .. } => "STORAGE_CREATE_DIR",
+            StorageError::Parse { .. } => "STORAGE_PARSE",
+            StorageError::Serialize { .. } => "STORAGE_SERIALIZE",
+        }
+    }
+
+    /// The file or directory the operation failed on
+    pub fn path(&self) -> &Path {
         match self {
-            StorageError::ReadError(msg) => write!(f, "Failed to read from storage: {}", msg),
-            StorageError::WriteError(msg) => write!(f, "Failed to write to storage: {}", msg),
-            StorageError::ParseError(msg) => write!(f, "Failed to parse storage data: {}", msg),
-            StorageError::FileNotFound(msg) => write!(f, "Storage file not found: {}", msg),
+            StorageError::Read { path, .. }
+            | StorageError::Write { path, .. }
+            | StorageError::CreateDir { path, .. }
+            | StorageError::Parse { path, .. }
+            | StorageError::Serialize { path, .. } => path,
         }
     }
 }
 
-impl std::error::Error for StorageError {}
+/// Why a product deletion was refused
+#[derive(Debug, Clone, PartialEq, Error)]
+#[non_exhaustive]
+pub enum DeletionBlocker {
+    /// The product still has stock on hand
+    #[error("it still has {quantity} units in stock")]
+    InStock { quantity: u32 },
+    /// The product has transactions within the recent-activity window
+    #[error("it has {transactions} transactions in the last {days} days")]
+    RecentActivity { transactions: usize, days: u32 },
+}
 
 /// Errors that can occur in service operations
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum ServiceError {
     /// Product with the given SKU was not found
-    ProductNotFound(String),
+    #[error("Product not found: {sku}")]
+    ProductNotFound { sku: String },
     /// Product with the given SKU already exists
-    DuplicateSKU(String),
+    #[error("Product with SKU '{sku}' already exists")]
+    DuplicateSKU { sku: String },
     /// Invalid input provided (e.g., empty SKU, negative quantity)
-    InvalidInput(String),
+    #[error("Invalid input: {message}")]
+    InvalidInput { message: String },
+    /// Transaction with the given ID does not exist
+    #[error("Transaction not found: {id}")]
+    TransactionNotFound { id: String },
     /// Insufficient stock for the requested operation
+    #[error("Insufficient stock for product '{sku}': requested {requested}, available {available}")]
     InsufficientStock { sku: String, requested: u32, available: u32 },
-    /// Storage operation failed
-    StorageError(StorageError),
+    /// Removal exceeds the large-movement threshold and was not confirmed
+    #[error("Removal of {requested} units of '{sku}' exceeds the large-movement threshold ({threshold}) and must be confirmed")]
+    LargeMovement { sku: String, requested: u32, threshold: MovementThreshold },
+    /// Deleting the product would discard stock or recent history
+    #[error("Product '{sku}' cannot be deleted: {blocker}")]
+    DeletionBlocked { sku: String, blocker: DeletionBlocker },
+    /// No customer with the given code exists
+    #[error("Customer not found: {code}")]
+    CustomerNotFound { code: String },
+    /// A customer with the given code already exists
+    #[error("Customer '{code}' already exists")]
+    DuplicateCustomer { code: String },
+    /// No restorable product with the given SKU is in the trash
+    #[error("Product not in trash: {sku}")]
+    NotInTrash { sku: String },
+    /// A batch had invalid rows, so none of it was applied
+    #[error("{} of {} entries rejected, no changes applied:\n{report}", report.rejected_rows(), report.rows)]
+    ValidationFailed { report: ValidationReport },
+    /// The product's history holds a transaction later than the clock allows
+    #[error("'{sku}' has a transaction at {latest}, later than this machine's clock; check the clocks of machines sharing the data")]
+    ClockDrift { sku: String, latest: DateTime<Utc> },
+    /// The inventory was opened read-only, so nothing can be saved
+    #[error("Cannot save changes: the inventory was opened read-only")]
+    ReadOnly,
+    /// Storage operation failed while performing `operation` (e.g. "saving products")
+    #[error("Storage error while {operation}: {source}")]
+    StorageError { operation: &'static str, source: StorageError },
 }
 
-impl fmt::Display for ServiceError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl ServiceError {
+    /// Invalid input error with the given message
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        ServiceError::InvalidInput { message: message.into() }
+    }
+
+    /// Wrap a storage failure with the operation that was being performed
+    ///
+    /// Intended for `map_err`: `storage.save_products(..).map_err(ServiceError::storage("saving products"))`.
+    pub fn storage(operation: &'static str) -> impl FnOnce(StorageError) -> Self {
+        move |source| ServiceError::StorageError { operation, source }
+    }
+
+    /// Stable identifier for the kind of failure, e.g. `INSUFFICIENT_STOCK`
+    ///
+    /// Storage failures report the code of the underlying `StorageError`.
+    pub fn code(&self) -> &'static str {
         match self {
-            ServiceError::ProductNotFound(sku) => write!(f, "Product not found: {}", sku),
-            ServiceError::DuplicateSKU(sku) => write!(f, "Product with SKU '{}' already exists", sku),
-            ServiceError::InvalidInput(msg) => write!(f, "Invalid input: {}", msg),
-            ServiceError::InsufficientStock { sku, requested, available } => {
-                write!(f, "Insufficient stock for product '{}': requested {}, available {}", sku, requested, available)
-            }
-            ServiceError::StorageError(err) => write!(f, "Storage error: {}", err),
+            ServiceError::ProductNotFound { .. } => "PRODUCT_NOT_FOUND",
+            ServiceError::DuplicateSKU { .. } => "DUPLICATE_SKU",
+            ServiceError::InvalidInput { .. } => "INVALID_INPUT",
+            ServiceError::TransactionNotFound { .. } => "TRANSACTION_NOT_FOUND",
+            ServiceError::InsufficientStock { .. } => "INSUFFICIENT_STOCK",
+            ServiceError::LargeMovement { .. } => "LARGE_MOVEMENT",
+            ServiceError::DeletionBlocked { .. } => "DELETION_BLOCKED",
+            ServiceError::CustomerNotFound { .. } => "CUSTOMER_NOT_FOUND",
+            ServiceError::DuplicateCustomer { .. } => "DUPLICATE_CUSTOMER",
+            ServiceError::NotInTrash { .. } => "NOT_IN_TRASH",
+            ServiceError::ValidationFailed { .. } => "VALIDATION_FAILED",
+            ServiceError::ClockDrift { .. } => "CLOCK_DRIFT",
+            ServiceError::ReadOnly => "READ_ONLY",
+            ServiceError::StorageError { source, .. } => source.code(),
         }
     }
-}
 
-impl std::error::Error for ServiceError {
-    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+    /// The SKU the error concerns, if any
+    pub fn sku(&self) -> Option<&str> {
         match self {
-            ServiceError::StorageError(err) => Some(err),
+            ServiceError::ProductNotFound { sku }
+            | ServiceError::DuplicateSKU { sku }
+            | ServiceError::InsufficientStock { sku, .. }
+            | ServiceError::LargeMovement { sku, .. }
+            | ServiceError::DeletionBlocked { sku, .. }
+            | ServiceError::ClockDrift { sku, .. }
+            | ServiceError::NotInTrash { sku } => Some(sku),
             _ => None,
         }
     }
 }
 
-impl From<StorageError> for ServiceError {
-    fn from(err: StorageError) -> Self {
-        ServiceError::StorageError(err)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error as _;
+
+    #[test]
+    fn test_storage_error_context_and_source() {
+        let source = io::Error::new(io::ErrorKind::PermissionDenied, "permission denied");
+        let err = ServiceError::storage("saving products")(StorageError::Write {
+            path: PathBuf::from("data/products.json"),
+            source,
+        });
+
+        assert_eq!(err.code(), "STORAGE_WRITE");
+        assert_eq!(
+            err.to_string(),
+            "Storage error while saving products: Failed to write data/products.json: permission denied"
+        );
+        let storage = err.source().unwrap().downcast_ref::<StorageError>().unwrap();
+        assert_eq!(storage.path(), Path::new("data/products.json"));
+        assert_eq!(storage.source().unwrap().to_string(), "permission denied");
+    }
+
+    #[test]
+    fn test_service_error_codes() {
+        let err = ServiceError::InsufficientStock { sku: "A".to_string(), requested: 5, available: 2 };
+        assert_eq!(err.code(), "INSUFFICIENT_STOCK");
+        assert_eq!(err.sku(), Some("A"));
+        assert_eq!(ServiceError::invalid_input("Quantity must be positive").code(), "INVALID_INPUT");
+        assert_eq!(ServiceError::invalid_input("x").sku(), None);
     }
 }
@@ -0,0 +1,190 @@
+// Inventory events raised by the service
+
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{MovementThreshold, Quota, TransactionType};
+use crate::schema::RecordKind;
+
+/// Notable occurrences raised by inventory operations
+///
+/// Events are queued on the service (see `InventoryService::take_events`)
+/// and, where marked as auditable, appended to the storage audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InventoryEvent {
+    /// A product's available quantity changed
+    ///
+    /// Raised for every recorded transaction, and for products added with
+    /// stock, deleted, or restored from the trash, which have no transaction
+    /// of their own; the transaction fields are then `None`.
+    StockChanged {
+        sku: String,
+        transaction_id: Option<String>,
+        transaction_seq: Option<u64>,
+        transaction_type: Option<TransactionType>,
+        before: u32,
+        after: u32,
+        timestamp: DateTime<Utc>,
+    },
+    /// A confirmed stock removal exceeded the large-movement threshold
+    LargeMovement {
+        sku: String,
+        quantity: u32,
+        available: u32,
+        threshold: MovementThreshold,
+        timestamp: DateTime<Utc>,
+    },
+    /// Stock arrived for a product with backorders still waiting to be filled
+    BackordersWaiting {
+        sku: String,
+        available: u32,
+        outstanding: u32,
+        timestamp: DateTime<Utc>,
+    },
+    /// Stock was removed to fill a backorder
+    BackorderFilled {
+        sku: String,
+        backorder_id: String,
+        quantity: u32,
+        remaining: u32,
+        timestamp: DateTime<Utc>,
+    },
+    /// A configured alert rule started matching a product
+    RuleMatched {
+        rule: String,
+        sku: String,
+        message: Option<String>,
+        timestamp: DateTime<Utc>,
+    },
+    /// A product's history holds a transaction later than this machine's
+    /// clock allows, so its clock or another's has drifted
+    ClockDrift {
+        sku: String,
+        latest: DateTime<Utc>,
+        timestamp: DateTime<Utc>,
+    },
+    /// Imported movements are dated before their product was added
+    BeforeCreation {
+        sku: String,
+        count: usize,
+        created_at: DateTime<Utc>,
+        timestamp: DateTime<Utc>,
+    },
+    /// A record got a new ID because an earlier record had the same one
+    IdReassigned {
+        record: RecordKind,
+        sku: String,
+        old_id: String,
+        new_id: String,
+        timestamp: DateTime<Utc>,
+    },
+    /// A change left the inventory near or over one of its size quotas
+    QuotaApproached {
+        quota: Quota,
+        used: u64,
+        limit: u64,
+        timestamp: DateTime<Utc>,
+    },
+}
+
+impl InventoryEvent {
+    /// SKU of the product the event is about, if it is about one product
+    pub fn sku(&self) -> Option<&str> {
+        let sku = match self {
+            InventoryEvent::StockChanged { sku, .. }
+            | InventoryEvent::LargeMovement { sku, .. }
+            | InventoryEvent::BackordersWaiting { sku, .. }
+            | InventoryEvent::BackorderFilled { sku, .. }
+            | InventoryEvent::RuleMatched { sku, .. }
+            | InventoryEvent::ClockDrift { sku, .. }
+            | InventoryEvent::BeforeCreation { sku, .. }
+            | InventoryEvent::IdReassigned { sku, .. } => sku,
+            InventoryEvent::QuotaApproached { .. } => return None,
+        };
+        Some(sku)
+    }
+
+    /// Whether the event should be recorded in the audit log
+    pub fn is_auditable(&self) -> bool {
+        match self {
+            InventoryEvent::LargeMovement { .. }
+            | InventoryEvent::BackorderFilled { .. }
+            | InventoryEvent::ClockDrift { .. }
+            | InventoryEvent::BeforeCreation { .. }
+            | InventoryEvent::IdReassigned { .. } => true,
+            InventoryEvent::StockChanged { .. }
+            | InventoryEvent::BackordersWaiting { .. }
+            | InventoryEvent::RuleMatched { .. }
+            | InventoryEvent::QuotaApproached { .. } => false,
+        }
+    }
+
+    /// Whether the event is shown to the user as a warning after the command
+    ///
+    /// Stock changes only go to the outbox and other consumers; the command's
+    /// own output already reports them.
+    pub fn is_warning(&self) -> bool {
+        !matches!(self, InventoryEvent::StockChanged { .. })
+    }
+}
+
+impl fmt::Display for InventoryEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InventoryEvent::StockChanged { sku, transaction_type, before, after, .. } => {
+                write!(f, "Stock of '{}' changed: quantity {} -> {}", sku, before, after)?;
+                match transaction_type {
+                    Some(transaction_type) => write!(f, " ({})", transaction_type.name()),
+                    None => Ok(()),
+                }
+            }
+            InventoryEvent::LargeMovement { sku, quantity, available, threshold, .. } => write!(
+                f,
+                "Large movement: removed {} of {} units of '{}' (threshold {})",
+                quantity, available, sku, threshold
+            ),
+            InventoryEvent::BackordersWaiting { sku, available, outstanding, .. } => write!(
+                f,
+                "{} units of '{}' are backordered and {} are now available; fill them with fill-backorders {}",
+                outstanding, sku, available, sku
+            ),
+            InventoryEvent::BackorderFilled { sku, backorder_id, quantity, remaining, .. } => write!(
+                f,
+                "Filled backorder {} with {} units of '{}' ({} still owed)",
+                &backorder_id[..backorder_id.len().min(8)], quantity, sku, remaining
+            ),
+            InventoryEvent::RuleMatched { rule, sku, message, .. } => match message {
+                Some(message) => write!(f, "Rule '{}' matched '{}': {}", rule, sku, message),
+                None => write!(f, "Rule '{}' matched '{}'", rule, sku),
+            },
+            InventoryEvent::ClockDrift { sku, latest, .. } => write!(
+                f,
+                "'{}' has a transaction at {}, later than this machine's clock; check the clocks of machines sharing the data",
+                sku, latest.format("%Y-%m-%d %H:%M:%S")
+            ),
+            InventoryEvent::BeforeCreation { sku, count, created_at, .. } => write!(
+                f,
+                "{} imported movements of '{}' are dated before it was added on {}",
+                count, sku, created_at.format("%Y-%m-%d %H:%M:%S")
+            ),
+            InventoryEvent::IdReassigned { record, sku, old_id, new_id, .. } => write!(
+                f,
+                "Gave {} {} of '{}' the new ID {}, as an earlier {} had the same ID",
+                record, old_id, sku, new_id, record
+            ),
+            InventoryEvent::QuotaApproached { quota: Quota::Products, used, limit, .. } => write!(
+                f,
+                "{} of {} products allowed; consider deleting discontinued products",
+                used, limit
+            ),
+            InventoryEvent::QuotaApproached { quota, used, limit, .. } => write!(
+                f,
+                "{} of {} {} allowed; consider archive-transactions",
+                used, limit, quota
+            ),
+        }
+    }
+}
@@ -0,0 +1,530 @@
+// Exporting the inventory as a standalone dataset
+//
+// An export is a data directory of its own (products.json and
+// transactions.json), so it can be opened with `diff` or pointed at by a
+// second installation. Anonymized exports are meant for sharing with
+// support: free text and business identifiers are replaced by tokens while
+// SKUs, quantities, and timestamps stay intact.
+//
+// Exports exchanged with business partners can be signed. `sign` writes a
+// manifest of the files' SHA-256 hashes in `sha256sum` format plus a
+// detached Ed25519 signature of the manifest; keys are PEM files as made by
+// `openssl genpkey -algorithm ed25519`, so the recipient can also check a
+// bundle with `sha256sum -c` and `openssl pkeyutl -verify -rawin`.
+//
+// `--format cdc` writes stock changes for loading into a warehouse instead:
+// changes.ndjson holds one Debezium-style envelope per transaction in the
+// journal, in the order they were recorded, with the product's stock before
+// and after it:
+//
+//     {"before":{"sku":"SKU001","quantity":12,"quarantined":0},
+//      "after":{"sku":"SKU001","quantity":9,"quarantined":0},
+//      "source":{"connector":"stock-control","version":"0.1.0","table":"stock",
+//                "seq":42,"transaction_id":"…","transaction_type":"Removal","ts_ms":…},
+//      "op":"u","ts_ms":…}
+//
+// The first change of a product that started from nothing is a create ("c")
+// with no before image. Stock levels are worked back from today's, so
+// archived transactions don't throw them off. `--since <seq>` leaves out the
+// changes up to that sequence number, for loading only what is new.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use ed25519_dalek::pkcs8::{DecodePrivateKey, DecodePublicKey};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::errors::StorageError;
+use crate::models::{Product, Transaction, TransactionType};
+use crate::service::InventoryService;
+use crate::storage::{JsonStorage, Storage};
+
+/// Products and transactions to export
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Dataset {
+    /// Products ordered by SKU
+    pub products: Vec<Product>,
+    /// Transactions ordered by timestamp
+    pub transactions: Vec<Transaction>,
+}
+
+impl Dataset {
+    /// Copy the current state of the inventory
+    pub fn from_service(service: &InventoryService) -> Self {
+        let mut products: Vec<Product> = service.list_products().into_iter().cloned().collect();
+        products.sort_by(|a, b| a.sku.cmp(&b.sku));
+        Dataset {
+            products,
+            transactions: service.all_transactions().into_iter().cloned().collect(),
+        }
+    }
+
+    /// Replace notes, operator names, customer codes, and suppliers with opaque tokens
+    ///
+    /// Equal values get equal tokens within one dataset, so patterns such as
+    /// "all removals by the same operator" survive anonymization. Fields
+    /// this version doesn't know could hold anything, so they are dropped.
+    pub fn anonymize(&mut self, anonymizer: &Anonymizer) {
+        for product in &mut self.products {
+            product.supplier = product.supplier.as_deref().map(|s| anonymizer.token("supplier", s));
+            product.extra.clear();
+        }
+        for transaction in &mut self.transactions {
+            transaction.notes = transaction.notes.as_deref().map(|s| anonymizer.token("note", s));
+            transaction.operator = transaction.operator.as_deref().map(|s| anonymizer.token("operator", s));
+            transaction.customer = transaction.customer.as_deref().map(|s| anonymizer.token("customer", s));
+            transaction.extra.clear();
+        }
+    }
+
+    /// Write the dataset as a data directory, refusing to overwrite an existing one
+    pub fn write_to(&self, dir: &Path) -> Result<(), StorageError> {
+        let products_path = dir.join("products.json");
+        if products_path.exists() {
+            return Err(StorageError::Write {
+                path: products_path,
+                source: io::Error::new(io::ErrorKind::AlreadyExists, "a dataset already exists there"),
+            });
+        }
+        let storage = JsonStorage::new(dir);
+        storage.save_transactions(&self.transactions)?;
+        storage.save_products(&self.products)
+    }
+}
+
+/// What an export writes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// A data directory of products and transactions
+    #[default]
+    Dataset,
+    /// Stock changes as change data capture records
+    Cdc,
+}
+
+impl ExportFormat {
+    /// Every format, in the order they are listed
+    pub const ALL: [ExportFormat; 2] = [ExportFormat::Dataset, ExportFormat::Cdc];
+
+    /// Name given to --format
+    pub fn name(self) -> &'static str {
+        match self {
+            ExportFormat::Dataset => "dataset",
+            ExportFormat::Cdc => "cdc",
+        }
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        ExportFormat::ALL.into_iter().find(|format| format.name() == lower).ok_or_else(|| {
+            let names: Vec<&str> = ExportFormat::ALL.iter().map(|format| format.name()).collect();
+            format!("Invalid export format '{}': expected one of {}", s, names.join(", "))
+        })
+    }
+}
+
+/// File of a change data capture export
+pub const CHANGES_FILE: &str = "changes.ndjson";
+
+/// A product's stock just before or after a change
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StockImage {
+    pub sku: String,
+    pub quantity: u32,
+    pub quarantined: u32,
+}
+
+/// Where a change came from
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangeSource {
+    pub connector: &'static str,
+    pub version: &'static str,
+    pub table: &'static str,
+    /// Sequence number of the transaction; 0 if it was recorded before
+    /// sequence numbers
+    pub seq: u64,
+    pub transaction_id: String,
+    pub transaction_type: TransactionType,
+    /// When the transaction took place, in milliseconds since the epoch
+    pub ts_ms: i64,
+}
+
+/// A change to a product's stock, in the Debezium envelope
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangeEvent {
+    /// Stock before the change; none when the product started from nothing
+    pub before: Option<StockImage>,
+    pub after: StockImage,
+    pub source: ChangeSource,
+    /// "c" for a product's first stock, "u" for later changes
+    pub op: &'static str,
+    /// When the change was exported, in milliseconds since the epoch
+    pub ts_ms: i64,
+}
+
+/// Stock changes made by the transactions in the journal, in the order they
+/// were recorded, leaving out those numbered `since` or lower
+///
+/// Condition changes move stock without changing either level, so they
+/// make no change.
+pub fn changes(service: &InventoryService, since: Option<u64>) -> Vec<ChangeEvent> {
+    let mut transactions = service.all_transactions();
+    // Stable, so transactions from before sequence numbers keep their time order, ahead of the rest
+    transactions.sort_by_key(|transaction| transaction.seq);
+
+    let mut levels: HashMap<&str, (u32, u32)> = service
+        .list_products()
+        .into_iter()
+        .map(|product| (product.sku.as_str(), (product.quantity, product.quarantined)))
+        .collect();
+    let mut befores = vec![(0, 0); transactions.len()];
+    for (index, transaction) in transactions.iter().enumerate().rev() {
+        let level = levels.entry(transaction.product_sku.as_str()).or_default();
+        *level = shift(*level, stock_delta(transaction), -1);
+        befores[index] = *level;
+    }
+
+    let exported_at = chrono::Utc::now().timestamp_millis();
+    let mut seen = HashSet::new();
+    transactions
+        .into_iter()
+        .zip(befores)
+        .filter_map(|(transaction, before)| {
+            let first = seen.insert(transaction.product_sku.as_str());
+            if since.is_some_and(|since| transaction.seq <= since) {
+                return None;
+            }
+            let after = shift(before, stock_delta(transaction), 1);
+            if after == before {
+                return None;
+            }
+            let image = |(quantity, quarantined)| StockImage {
+                sku: transaction.product_sku.clone(),
+                quantity,
+                quarantined,
+            };
+            let before = (!first || before != (0, 0)).then(|| image(before));
+            Some(ChangeEvent {
+                op: if before.is_some() { "u" } else { "c" },
+                before,
+                after: image(after),
+                source: ChangeSource {
+                    connector: "stock-control",
+                    version: env!("CARGO_PKG_VERSION"),
+                    table: "stock",
+                    seq: transaction.seq,
+                    transaction_id: transaction.id.clone(),
+                    transaction_type: transaction.transaction_type,
+                    ts_ms: transaction.timestamp.timestamp_millis(),
+                },
+                ts_ms: exported_at,
+            })
+        })
+        .collect()
+}
+
+/// Write changes to changes.ndjson in `dir`, refusing to overwrite an earlier export
+pub fn write_changes(dir: &Path, changes: &[ChangeEvent]) -> Result<(), StorageError> {
+    let path = dir.join(CHANGES_FILE);
+    if path.exists() {
+        return Err(StorageError::Write {
+            path,
+            source: io::Error::new(io::ErrorKind::AlreadyExists, "changes were already exported there"),
+        });
+    }
+    fs::create_dir_all(dir).map_err(|source| StorageError::Write { path: dir.to_path_buf(), source })?;
+    let mut lines = String::new();
+    for change in changes {
+        let line = serde_json::to_string(change)
+            .map_err(|e| StorageError::Write { path: path.clone(), source: io::Error::other(e) })?;
+        lines.push_str(&line);
+        lines.push('\n');
+    }
+    write_file(&path, lines.as_bytes())
+}
+
+/// Change a transaction makes to available and quarantined stock
+fn stock_delta(transaction: &Transaction) -> (i64, i64) {
+    let quantity = i64::from(transaction.quantity);
+    match transaction.transaction_type {
+        TransactionType::Quarantine => (-quantity, quantity),
+        TransactionType::ReleaseQuarantine => (quantity, -quantity),
+        TransactionType::ConditionChange => (0, 0),
+        transaction_type if transaction_type.adds_stock() => (quantity, 0),
+        _ => (-quantity, 0),
+    }
+}
+
+/// Stock levels after applying a change (`direction` 1) or undoing it (-1)
+fn shift(level: (u32, u32), delta: (i64, i64), direction: i64) -> (u32, u32) {
+    let apply = |value: u32, delta: i64| {
+        u32::try_from((i64::from(value) + direction * delta).max(0)).unwrap_or(u32::MAX)
+    };
+    (apply(level.0, delta.0), apply(level.1, delta.1))
+}
+
+/// Manifest of a signed export: one `<sha256>  <file>` line per dataset file
+pub const MANIFEST_FILE: &str = "manifest.sha256";
+
+/// Detached Ed25519 signature of the manifest, as 64 raw bytes
+pub const SIGNATURE_FILE: &str = "manifest.sha256.sig";
+
+/// Files of an export covered by the signature
+const SIGNED_FILES: &[&str] = &["products.json", "transactions.json"];
+
+/// Why an export couldn't be signed or verified
+#[derive(Debug)]
+pub enum SignatureError {
+    /// Reading or writing a file failed
+    Storage(StorageError),
+    /// The key file isn't an Ed25519 key in PEM format
+    Key { path: PathBuf, reason: String },
+    /// The export has no manifest or signature
+    Unsigned { dir: PathBuf },
+    /// The manifest wasn't signed by the given key, or was changed after signing
+    BadSignature,
+    /// A file doesn't match the signed manifest
+    Modified { file: String },
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::Storage(e) => write!(f, "{}", e),
+            SignatureError::Key { path, reason } => {
+                write!(f, "{} is not an Ed25519 key in PEM format: {}", path.display(), reason)
+            }
+            SignatureError::Unsigned { dir } => {
+                write!(f, "{} is not signed: {} or {} is missing", dir.display(), MANIFEST_FILE, SIGNATURE_FILE)
+            }
+            SignatureError::BadSignature => {
+                write!(f, "the signature doesn't match the key; the export was signed by someone else or altered")
+            }
+            SignatureError::Modified { file } => write!(f, "{} was changed after the export was signed", file),
+        }
+    }
+}
+
+impl From<StorageError> for SignatureError {
+    fn from(e: StorageError) -> Self {
+        SignatureError::Storage(e)
+    }
+}
+
+/// Sign the export in `dir` with the Ed25519 private key in `key_path`
+pub fn sign(dir: &Path, key_path: &Path) -> Result<(), SignatureError> {
+    let key = SigningKey::read_pkcs8_pem_file(key_path)
+        .map_err(|e| SignatureError::Key { path: key_path.to_path_buf(), reason: e.to_string() })?;
+    let mut manifest = String::new();
+    for file in SIGNED_FILES {
+        manifest.push_str(&format!("{}  {}\n", file_hash(&dir.join(file))?, file));
+    }
+    let signature = key.sign(manifest.as_bytes());
+    write_file(&dir.join(MANIFEST_FILE), manifest.as_bytes())?;
+    write_file(&dir.join(SIGNATURE_FILE), &signature.to_bytes())?;
+    Ok(())
+}
+
+/// Check that the export in `dir` was signed with the private half of the
+/// Ed25519 public key in `public_key_path` and hasn't changed since
+pub fn verify(dir: &Path, public_key_path: &Path) -> Result<(), SignatureError> {
+    let key = VerifyingKey::read_public_key_pem_file(public_key_path)
+        .map_err(|e| SignatureError::Key { path: public_key_path.to_path_buf(), reason: e.to_string() })?;
+    let manifest_path = dir.join(MANIFEST_FILE);
+    let signature_path = dir.join(SIGNATURE_FILE);
+    if !manifest_path.is_file() || !signature_path.is_file() {
+        return Err(SignatureError::Unsigned { dir: dir.to_path_buf() });
+    }
+    let manifest = read_file(&manifest_path)?;
+    let signature = Signature::from_slice(&read_file(&signature_path)?).map_err(|_| SignatureError::BadSignature)?;
+    key.verify_strict(&manifest, &signature).map_err(|_| SignatureError::BadSignature)?;
+
+    let manifest = String::from_utf8_lossy(&manifest);
+    for file in SIGNED_FILES {
+        let signed = manifest
+            .lines()
+            .find_map(|line| line.split_once("  ").filter(|(_, name)| name == file).map(|(hash, _)| hash));
+        if signed != Some(file_hash(&dir.join(file))?.as_str()) {
+            return Err(SignatureError::Modified { file: file.to_string() });
+        }
+    }
+    Ok(())
+}
+
+/// SHA-256 of a file's contents, as hex
+fn file_hash(path: &Path) -> Result<String, StorageError> {
+    Ok(Sha256::digest(read_file(path)?).iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>, StorageError> {
+    fs::read(path).map_err(|source| StorageError::Read { path: path.to_path_buf(), source })
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> Result<(), StorageError> {
+    fs::write(path, contents).map_err(|source| StorageError::Write { path: path.to_path_buf(), source })
+}
+
+/// Turns sensitive values into salted, non-reversible tokens like `supplier-1a2b3c4d`
+#[derive(Debug, Clone)]
+pub struct Anonymizer {
+    salt: String,
+}
+
+impl Anonymizer {
+    /// An anonymizer with a random salt, so tokens can't be matched across exports
+    pub fn new() -> Self {
+        Self::with_salt(Uuid::new_v4().to_string())
+    }
+
+    /// An anonymizer with a fixed salt, for reproducible output
+    pub fn with_salt(salt: impl Into<String>) -> Self {
+        Anonymizer { salt: salt.into() }
+    }
+
+    /// Token for `value`, prefixed with the kind of value it replaces
+    pub fn token(&self, kind: &str, value: &str) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.salt.hash(&mut hasher);
+        kind.hash(&mut hasher);
+        value.hash(&mut hasher);
+        format!("{}-{:08x}", kind, hasher.finish() as u32)
+    }
+}
+
+impl Default for Anonymizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TransactionType;
+    use tempfile::TempDir;
+
+    fn transaction(notes: &str, operator: &str) -> Transaction {
+        Transaction {
+            notes: Some(notes.to_string()),
+            operator: Some(operator.to_string()),
+            ..Transaction::new("SKU001", TransactionType::Removal, 3, None)
+        }
+    }
+
+    #[test]
+    fn test_anonymize_keeps_structure_and_quantities() {
+        let mut dataset = Dataset {
+            products: vec![Product {
+                sku: "SKU001".to_string(),
+                quantity: 7,
+                supplier: Some("Acme Corp".to_string()),
+                ..Default::default()
+            }],
+            transactions: vec![
+                Transaction { customer: Some("CUST-BOB".to_string()), ..transaction("Order 991 for Bob", "alice") },
+                transaction("Restock", "alice"),
+            ],
+        };
+        dataset.anonymize(&Anonymizer::with_salt("test"));
+
+        let product = &dataset.products[0];
+        assert_eq!((product.sku.as_str(), product.quantity), ("SKU001", 7));
+        assert!(product.supplier.as_deref().unwrap().starts_with("supplier-"));
+        let [first, second] = &dataset.transactions[..] else { panic!("expected two transactions") };
+        assert_eq!(first.quantity, 3);
+        assert!(first.notes.as_deref().unwrap().starts_with("note-"));
+        assert_ne!(first.notes, second.notes);
+        assert_eq!(first.operator, second.operator);
+        assert_ne!(first.operator.as_deref(), Some("alice"));
+        assert!(first.customer.as_deref().unwrap().starts_with("customer-"));
+        assert_eq!(second.customer, None);
+    }
+
+    #[test]
+    fn test_changes_carry_before_and_after_images() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = InventoryService::new(Box::new(JsonStorage::new(temp_dir.path()))).unwrap();
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        service.add_product("SKU002".into(), "Gadget".into(), "".into(), 0, 1).unwrap();
+        service.add_stock("SKU001", 10, None).unwrap();
+        service.add_stock("SKU002", 4, None).unwrap();
+        service.quarantine("SKU001", 3, None).unwrap();
+        service.remove_stock("SKU001", 2, None).unwrap();
+
+        let all = changes(&service, None);
+        let levels: Vec<_> = all
+            .iter()
+            .map(|change| {
+                let before = change.before.as_ref().map(|image| (image.quantity, image.quarantined));
+                (change.op, change.after.sku.as_str(), before, (change.after.quantity, change.after.quarantined))
+            })
+            .collect();
+        assert_eq!(levels, vec![
+            ("c", "SKU001", None, (10, 0)),
+            ("c", "SKU002", None, (4, 0)),
+            ("u", "SKU001", Some((10, 0)), (7, 3)),
+            ("u", "SKU001", Some((7, 3)), (5, 3)),
+        ]);
+        assert_eq!(all[3].source.transaction_type, TransactionType::Removal);
+
+        let newer = changes(&service, Some(all[1].source.seq));
+        assert_eq!(newer.len(), 2);
+        assert_eq!(newer[0].before, all[2].before);
+
+        write_changes(temp_dir.path().join("cdc").as_path(), &newer).unwrap();
+        let written = fs::read_to_string(temp_dir.path().join("cdc").join(CHANGES_FILE)).unwrap();
+        assert_eq!(written.lines().count(), 2);
+        assert!(written.starts_with(r#"{"before":{"sku":"SKU001","quantity":10,"quarantined":0}"#), "{}", written);
+        assert!(write_changes(temp_dir.path().join("cdc").as_path(), &newer).is_err());
+    }
+
+    #[test]
+    fn test_write_to_refuses_to_overwrite() {
+        let temp_dir = TempDir::new().unwrap();
+        let dataset = Dataset { transactions: vec![transaction("n", "o")], ..Default::default() };
+        dataset.write_to(temp_dir.path()).unwrap();
+
+        assert_eq!(JsonStorage::new(temp_dir.path()).load_transactions().unwrap(), dataset.transactions);
+        let err = dataset.write_to(temp_dir.path()).unwrap_err();
+        assert_eq!(err.code(), "STORAGE_WRITE");
+    }
+
+    #[test]
+    fn test_signed_export_verifies_until_changed() {
+        use ed25519_dalek::pkcs8::{EncodePrivateKey, EncodePublicKey};
+        use ed25519_dalek::pkcs8::spki::der::pem::LineEnding;
+
+        let temp_dir = TempDir::new().unwrap();
+        let export_dir = temp_dir.path().join("export");
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let (private_pem, public_pem) = (temp_dir.path().join("key.pem"), temp_dir.path().join("key.pub"));
+        key.write_pkcs8_pem_file(&private_pem, LineEnding::LF).unwrap();
+        key.verifying_key().write_public_key_pem_file(&public_pem, LineEnding::LF).unwrap();
+        let other_pem = temp_dir.path().join("other.pub");
+        SigningKey::from_bytes(&[8; 32]).verifying_key().write_public_key_pem_file(&other_pem, LineEnding::LF).unwrap();
+
+        let dataset = Dataset { transactions: vec![transaction("n", "o")], ..Default::default() };
+        dataset.write_to(&export_dir).unwrap();
+        assert!(matches!(verify(&export_dir, &public_pem), Err(SignatureError::Unsigned { .. })));
+        sign(&export_dir, &private_pem).unwrap();
+        verify(&export_dir, &public_pem).unwrap();
+        assert!(matches!(verify(&export_dir, &other_pem), Err(SignatureError::BadSignature)));
+        assert!(matches!(verify(&export_dir, &private_pem), Err(SignatureError::Key { .. })));
+
+        fs::write(export_dir.join("products.json"), r#"[{"sku": "FORGED"}]"#).unwrap();
+        let err = verify(&export_dir, &public_pem).unwrap_err();
+        assert_eq!(err.to_string(), "products.json was changed after the export was signed");
+    }
+}
@@ -0,0 +1,269 @@
+// Inventory feeds delivered to partners on a schedule
+//
+// Each entry under `feeds` in config.json describes one feed: its format
+// (see `edi`), where it goes, and how often. `feed push` renders a feed to a
+// file and uploads it with the standard tools, so SSH keys and AWS
+// credentials are set up the usual way and never stored here:
+//
+//     sftp://user@host[:port]/dir   `sftp` in batch mode
+//     s3://bucket/prefix            `aws s3 cp`
+//     /some/dir                     copied into a local or mounted directory
+//
+// Scheduling is left to cron or a systemd timer running `feed push --due`
+// every few minutes: a feed is pushed when its `interval_minutes` have passed
+// since its last successful push. The outcome of every push is logged and
+// kept in feed-state.json, which `feed status` shows.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::edi::{Envelope, FeedFormat};
+
+/// File in the data directory that records each feed's last pushes
+pub const STATE_FILE: &str = "feed-state.json";
+
+/// A feed to deliver
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeedConfig {
+    pub format: FeedFormat,
+    /// `sftp://user@host[:port]/dir`, `s3://bucket/prefix`, or a directory
+    pub destination: String,
+    /// Name of the uploaded file; `{name}`, `{date}`, `{timestamp}`, and
+    /// `{ext}` are filled in. Defaults to `{name}-{timestamp}.{ext}`.
+    pub file_name: Option<String>,
+    /// Minutes between pushes for `feed push --due`; without it the feed is
+    /// only pushed by name
+    pub interval_minutes: Option<u32>,
+    /// Sender ID of an X12 feed
+    pub sender: Option<String>,
+    /// Receiver ID of an X12 feed
+    pub receiver: Option<String>,
+}
+
+impl FeedConfig {
+    /// Name of the file for a push at `now`
+    pub fn file_name(&self, name: &str, now: DateTime<Utc>) -> String {
+        self.file_name
+            .as_deref()
+            .unwrap_or("{name}-{timestamp}.{ext}")
+            .replace("{name}", name)
+            .replace("{date}", &now.format("%Y-%m-%d").to_string())
+            .replace("{timestamp}", &now.format("%Y%m%dT%H%M%SZ").to_string())
+            .replace("{ext}", self.format.extension())
+    }
+
+    /// The X12 envelope, when the feed has both partner IDs
+    pub fn envelope(&self, now: DateTime<Utc>) -> Option<Envelope> {
+        Some(Envelope {
+            sender: self.sender.clone()?,
+            receiver: self.receiver.clone()?,
+            control: Envelope::control_for(now),
+        })
+    }
+
+    /// Whether the interval has passed since the last successful push
+    pub fn is_due(&self, state: &FeedState, now: DateTime<Utc>) -> bool {
+        let Some(minutes) = self.interval_minutes else {
+            return false;
+        };
+        state.last_success.is_none_or(|last| now - last >= Duration::minutes(i64::from(minutes)))
+    }
+}
+
+/// Where a feed is uploaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Destination {
+    /// `user@host`, port, and remote directory
+    Sftp { target: String, port: Option<u16>, dir: String },
+    /// Bucket and key prefix
+    S3 { bucket: String, prefix: String },
+    /// A local or mounted directory
+    Directory(PathBuf),
+}
+
+impl Destination {
+    /// Upload a file, returning where it went
+    pub fn upload(&self, local: &Path, file_name: &str) -> Result<String, String> {
+        match self {
+            Destination::Sftp { target, port, dir } => {
+                let remote = format!("{}/{}", dir.trim_end_matches('/'), file_name);
+                let mut sftp = Command::new("sftp");
+                sftp.arg("-b").arg("-");
+                if let Some(port) = port {
+                    sftp.arg("-P").arg(port.to_string());
+                }
+                let batch = format!("put \"{}\" \"{}\"\n", local.display(), remote);
+                run_tool(sftp.arg(target), Some(&batch))?;
+                Ok(format!("sftp://{}{}", target, remote))
+            }
+            Destination::S3 { bucket, prefix } => {
+                let key = if prefix.is_empty() { file_name.to_string() } else { format!("{}/{}", prefix, file_name) };
+                let url = format!("s3://{}/{}", bucket, key);
+                run_tool(Command::new("aws").args(["s3", "cp", "--only-show-errors"]).arg(local).arg(&url), None)?;
+                Ok(url)
+            }
+            Destination::Directory(dir) => {
+                let path = dir.join(file_name);
+                std::fs::copy(local, &path).map_err(|e| format!("copying to {}: {}", path.display(), e))?;
+                Ok(path.display().to_string())
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Destination {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(rest) = s.strip_prefix("sftp://") {
+            let (authority, dir) = rest.split_once('/').map_or((rest, "/".to_string()), |(authority, dir)| (authority, format!("/{}", dir)));
+            let (target, port) = match authority.rsplit_once(':') {
+                Some((target, port)) => {
+                    (target, Some(port.parse::<u16>().map_err(|_| format!("Invalid port in destination '{}'", s))?))
+                }
+                None => (authority, None),
+            };
+            if target.is_empty() || target.starts_with('-') {
+                return Err(format!("Invalid destination '{}': missing host", s));
+            }
+            Ok(Destination::Sftp { target: target.to_string(), port, dir })
+        } else if let Some(rest) = s.strip_prefix("s3://") {
+            let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+            if bucket.is_empty() {
+                return Err(format!("Invalid destination '{}': missing bucket", s));
+            }
+            Ok(Destination::S3 { bucket: bucket.to_string(), prefix: prefix.trim_matches('/').to_string() })
+        } else if s.contains("://") || s.trim().is_empty() {
+            Err(format!("Invalid destination '{}': expected sftp://user@host/dir, s3://bucket/prefix, or a directory", s))
+        } else {
+            Ok(Destination::Directory(PathBuf::from(s)))
+        }
+    }
+}
+
+/// Outcome of a feed's recent pushes
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FeedState {
+    pub last_success: Option<DateTime<Utc>>,
+    /// Where the last successful push went
+    pub last_location: Option<String>,
+    pub last_failure: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+/// Every feed's state, by name; empty when nothing has been pushed yet
+pub fn load_state(data_dir: &Path) -> Result<BTreeMap<String, FeedState>, String> {
+    let path = data_dir.join(STATE_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents).map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(BTreeMap::new()),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+/// Write every feed's state
+pub fn save_state(data_dir: &Path, state: &BTreeMap<String, FeedState>) -> Result<(), String> {
+    let path = data_dir.join(STATE_FILE);
+    let json = serde_json::to_string_pretty(state).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Upload rendered feed contents under the feed's file name, returning
+/// where they went
+pub fn deliver(name: &str, feed: &FeedConfig, contents: &str, now: DateTime<Utc>) -> Result<String, String> {
+    let destination: Destination = feed.destination.parse()?;
+    let file_name = feed.file_name(name, now);
+    if file_name.contains(['/', '\\']) {
+        return Err(format!("Invalid file name '{}': it can't contain a path separator", file_name));
+    }
+    // Staged in a directory only this user can open, with an unpredictable
+    // name, so other local users can't plant or swap the file being uploaded.
+    // The directory and file are removed when `staging_dir` is dropped,
+    // whether or not the upload succeeds.
+    let staging_dir = tempfile::Builder::new()
+        .prefix("stock-control-feed-")
+        .tempdir()
+        .map_err(|e| format!("creating a staging directory: {}", e))?;
+    let staging = staging_dir.path().join(&file_name);
+    std::fs::write(&staging, contents).map_err(|e| format!("writing {}: {}", staging.display(), e))?;
+    destination.upload(&staging, &file_name)
+}
+
+/// Run an upload tool, feeding it `input` on stdin, and fail with its stderr
+fn run_tool(command: &mut Command, input: Option<&str>) -> Result<(), String> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    let mut child = command
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("running {}: {}", program, e))?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input.as_bytes()).map_err(|e| format!("running {}: {}", program, e))?;
+    }
+    let output = child.wait_with_output().map_err(|e| format!("running {}: {}", program, e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!("{} failed ({}): {}", program, output.status, stderr.trim()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_feeds_are_named_scheduled_and_delivered() {
+        assert_eq!(
+            "sftp://edi@partner.example:2222/inbound".parse::<Destination>(),
+            Ok(Destination::Sftp { target: "edi@partner.example".to_string(), port: Some(2222), dir: "/inbound".to_string() })
+        );
+        assert_eq!(
+            "s3://feeds/acme/".parse::<Destination>(),
+            Ok(Destination::S3 { bucket: "feeds".to_string(), prefix: "acme".to_string() })
+        );
+        assert!("ftp://host/dir".parse::<Destination>().is_err());
+        assert!("sftp://-oProxyCommand=x/dir".parse::<Destination>().is_err());
+
+        let now = Utc.with_ymd_and_hms(2025, 3, 4, 9, 30, 0).unwrap();
+        let dir = tempfile::TempDir::new().unwrap();
+        let feed = FeedConfig {
+            format: FeedFormat::Csv,
+            destination: dir.path().display().to_string(),
+            interval_minutes: Some(60),
+            ..Default::default()
+        };
+        assert_eq!(feed.file_name("retail", now), "retail-20250304T093000Z.csv");
+
+        let mut state = FeedState::default();
+        assert!(feed.is_due(&state, now));
+        state.last_success = Some(now - Duration::minutes(30));
+        assert!(!feed.is_due(&state, now));
+        assert!(!FeedConfig { interval_minutes: None, ..feed.clone() }.is_due(&FeedState::default(), now));
+
+        let location = deliver("retail", &feed, "sku\nA\n", now).unwrap();
+        assert_eq!(std::fs::read_to_string(&location).unwrap(), "sku\nA\n");
+
+        // A failed upload leaves nothing staged behind
+        let missing = FeedConfig { destination: dir.path().join("missing").display().to_string(), ..feed.clone() };
+        assert!(deliver("staging-check", &missing, "sku\n", now).is_err());
+        let staged = std::fs::read_dir(std::env::temp_dir())
+            .unwrap()
+            .filter_map(Result::ok)
+            .any(|entry| entry.path().join("staging-check-20250304T093000Z.csv").exists());
+        assert!(!staged);
+
+        let states = BTreeMap::from([("retail".to_string(), state)]);
+        save_state(dir.path(), &states).unwrap();
+        assert_eq!(load_state(dir.path()).unwrap(), states);
+    }
+}
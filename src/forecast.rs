@@ -0,0 +1,188 @@
+// Stock-out forecasts from the demand recorded in the ledger
+//
+// Daily demand is measured as for reorder points (see `reorder`). Without
+// seasonality every coming day is expected to see the mean; with it, the mean
+// is scaled by a seasonal index, the average demand on that weekday or in that
+// calendar month over the average of all days. A shop that sells most on
+// Saturdays then runs down faster over a weekend than a flat average says.
+//
+// Indices come only from the days in the history: a weekday or month that
+// never occurs in it keeps an index of one, so monthly seasonality needs a
+// year of history to say anything about every month.
+
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// Days ahead a stock-out is looked for when none is given
+pub const DEFAULT_HORIZON_DAYS: u32 = 90;
+
+/// Repeating pattern that demand is assumed to follow
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Seasonality {
+    /// Every day is expected to see the mean
+    #[default]
+    None,
+    /// Demand depends on the day of the week
+    Weekly,
+    /// Demand depends on the calendar month
+    Monthly,
+}
+
+impl Seasonality {
+    /// Every seasonality, in order of period length
+    pub const ALL: [Seasonality; 3] = [Seasonality::None, Seasonality::Weekly, Seasonality::Monthly];
+
+    /// Name used on the command line and in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            Seasonality::None => "none",
+            Seasonality::Weekly => "weekly",
+            Seasonality::Monthly => "monthly",
+        }
+    }
+
+    /// Days of history measured when none are given: a year for monthly
+    /// seasonality, so each month is seen at least once
+    pub fn default_window_days(self) -> u32 {
+        match self {
+            Seasonality::None | Seasonality::Weekly => crate::reorder::DEFAULT_WINDOW_DAYS,
+            Seasonality::Monthly => 365,
+        }
+    }
+
+    /// Number of seasons in one period
+    fn seasons(self) -> usize {
+        match self {
+            Seasonality::None => 1,
+            Seasonality::Weekly => 7,
+            Seasonality::Monthly => 12,
+        }
+    }
+
+    /// Season a date falls in
+    fn season_of(self, date: NaiveDate) -> usize {
+        match self {
+            Seasonality::None => 0,
+            Seasonality::Weekly => date.weekday().num_days_from_monday() as usize,
+            Seasonality::Monthly => date.month0() as usize,
+        }
+    }
+}
+
+impl std::fmt::Display for Seasonality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for Seasonality {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        Seasonality::ALL.into_iter().find(|seasonality| seasonality.name() == lower).ok_or_else(|| {
+            let names: Vec<&str> = Seasonality::ALL.iter().map(|seasonality| seasonality.name()).collect();
+            format!("Invalid seasonality '{}': expected one of {}", s, names.join(", "))
+        })
+    }
+}
+
+/// Expected daily demand, optionally varying with the season
+#[derive(Debug, Clone, PartialEq)]
+pub struct Forecast {
+    /// Mean units per day over the history
+    pub mean: f64,
+    pub seasonality: Seasonality,
+    /// Demand in each season relative to the mean
+    indices: Vec<f64>,
+}
+
+impl Forecast {
+    /// Fit a forecast to the units taken on each day from `start` on
+    pub fn fit(start: NaiveDate, daily: &[u64], seasonality: Seasonality) -> Self {
+        let mut totals = vec![0u64; seasonality.seasons()];
+        let mut days = vec![0u32; seasonality.seasons()];
+        for (offset, &units) in daily.iter().enumerate() {
+            let season = seasonality.season_of(start + Duration::days(offset as i64));
+            totals[season] += units;
+            days[season] += 1;
+        }
+        let mean = if daily.is_empty() { 0.0 } else { daily.iter().sum::<u64>() as f64 / daily.len() as f64 };
+        let indices = totals
+            .iter()
+            .zip(&days)
+            .map(|(&total, &days)| match days {
+                0 => 1.0,
+                _ if mean == 0.0 => 1.0,
+                days => total as f64 / f64::from(days) / mean,
+            })
+            .collect();
+        Forecast { mean, seasonality, indices }
+    }
+
+    /// Demand on `date` relative to the mean
+    pub fn index(&self, date: NaiveDate) -> f64 {
+        self.indices[self.seasonality.season_of(date)]
+    }
+
+    /// Units expected to be taken on `date`
+    pub fn expected(&self, date: NaiveDate) -> f64 {
+        self.mean * self.index(date)
+    }
+
+    /// Units expected to be taken over the `days` days after `today`
+    pub fn expected_over(&self, today: NaiveDate, days: u32) -> f64 {
+        (1..=i64::from(days)).map(|offset| self.expected(today + Duration::days(offset))).sum()
+    }
+
+    /// Day the expected demand after `today` uses up `available` units, if
+    /// within `horizon_days`; `today` itself when nothing is available
+    pub fn stock_out(&self, available: u32, today: NaiveDate, horizon_days: u32) -> Option<NaiveDate> {
+        if available == 0 {
+            return Some(today);
+        }
+        let mut taken = 0.0;
+        for offset in 1..=i64::from(horizon_days) {
+            let date = today + Duration::days(offset);
+            taken += self.expected(date);
+            // Round away float noise, so 9.999999 units of demand use up 10
+            if (taken * 1e6).round() / 1e6 >= f64::from(available) {
+                return Some(date);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weekly_seasonality_brings_a_weekend_stock_out_forward() {
+        // Four weeks from Monday 2025-01-06: 1 a day on weekdays, 8 on Saturdays
+        let start = NaiveDate::from_ymd_opt(2025, 1, 6).unwrap();
+        let daily: Vec<u64> = (0..28).map(|day| if day % 7 == 5 { 8 } else { 1 }).collect();
+        let flat = Forecast::fit(start, &daily, Seasonality::None);
+        let weekly = Forecast::fit(start, &daily, Seasonality::Weekly);
+        assert_eq!(flat.mean, 2.0);
+        assert_eq!(weekly.index(NaiveDate::from_ymd_opt(2025, 2, 8).unwrap()), 4.0);
+        assert_eq!(weekly.index(NaiveDate::from_ymd_opt(2025, 2, 9).unwrap()), 0.5);
+        assert!((weekly.expected_over(start, 7) - 14.0).abs() < 1e-9);
+
+        // Ten units left on Wednesday 2025-02-05 last until Monday on average,
+        // but Saturday's rush takes them two days sooner
+        let today = NaiveDate::from_ymd_opt(2025, 2, 5).unwrap();
+        assert_eq!(flat.stock_out(10, today, 90), NaiveDate::from_ymd_opt(2025, 2, 10));
+        assert_eq!(weekly.stock_out(10, today, 90), NaiveDate::from_ymd_opt(2025, 2, 8));
+        assert_eq!(weekly.stock_out(0, today, 90), Some(today));
+        assert_eq!(weekly.stock_out(1000, today, 90), None);
+
+        // A month missing from the history keeps an index of one
+        let monthly = Forecast::fit(start, &daily, Seasonality::Monthly);
+        assert_eq!(monthly.index(NaiveDate::from_ymd_opt(2025, 6, 1).unwrap()), 1.0);
+        assert_eq!("Weekly".parse::<Seasonality>(), Ok(Seasonality::Weekly));
+        assert!("yearly".parse::<Seasonality>().is_err());
+    }
+}
@@ -0,0 +1,210 @@
+// External commands run when stock changes
+//
+// The `hooks` section of config.json lists shell commands for each hook:
+//
+//     "hooks": { "post-stock-change": ["./notify.sh"], "low-stock": ["lp-order-sheet"] }
+//
+// After a command, products are compared with how they were before it. Each
+// product whose available quantity changed fires `post-stock-change`, each one
+// that was deleted fires `post-delete`, and each one that fell to its reorder
+// point fires `low-stock`. Every hook command gets one JSON document on
+// stdin, with the product in the `api::v1` shape, and the hook name in the
+// `STOCK_HOOK` environment variable. Commands run one after another through
+// the shell, in the data directory; a failing hook is reported but doesn't
+// fail the command that triggered it.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::api;
+
+/// Commands to run for each hook
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+pub struct HooksConfig {
+    /// Run for each product whose available quantity changed
+    pub post_stock_change: Vec<String>,
+    /// Run for each product deleted
+    pub post_delete: Vec<String>,
+    /// Run for each product that reached its reorder point
+    pub low_stock: Vec<String>,
+}
+
+impl HooksConfig {
+    /// Whether no hook has a command
+    pub fn is_empty(&self) -> bool {
+        self.post_stock_change.is_empty() && self.post_delete.is_empty() && self.low_stock.is_empty()
+    }
+
+    /// Commands configured for a hook
+    pub fn commands(&self, hook: Hook) -> &[String] {
+        match hook {
+            Hook::PostStockChange => &self.post_stock_change,
+            Hook::PostDelete => &self.post_delete,
+            Hook::LowStock => &self.low_stock,
+        }
+    }
+}
+
+/// Points at which hook commands run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    PostStockChange,
+    PostDelete,
+    LowStock,
+}
+
+impl Hook {
+    /// Name used in the configuration and passed in `STOCK_HOOK`
+    pub fn name(self) -> &'static str {
+        match self {
+            Hook::PostStockChange => "post-stock-change",
+            Hook::PostDelete => "post-delete",
+            Hook::LowStock => "low-stock",
+        }
+    }
+}
+
+/// A hook to fire and the JSON document its commands receive
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookCall {
+    pub hook: Hook,
+    pub payload: String,
+}
+
+/// Hook calls for the differences between two snapshots of the products,
+/// keyed by SKU
+pub fn calls(
+    before: &BTreeMap<String, api::v1::Product>,
+    after: &BTreeMap<String, api::v1::Product>,
+) -> Vec<HookCall> {
+    let timestamp = Utc::now();
+    let call = |hook: Hook, mut payload: serde_json::Value| {
+        payload["hook"] = hook.name().into();
+        payload["timestamp"] = json!(timestamp);
+        HookCall { hook, payload: payload.to_string() }
+    };
+    let mut calls = Vec::new();
+    for (sku, product) in after {
+        let previous = before.get(sku);
+        let quantity_before = previous.map_or(0, |previous| previous.quantity);
+        if previous.is_some() && quantity_before != product.quantity {
+            calls.push(call(Hook::PostStockChange, json!({
+                "sku": sku,
+                "before": quantity_before,
+                "after": product.quantity,
+                "product": product,
+            })));
+        }
+        if product.low_stock && previous.is_some_and(|previous| !previous.low_stock) {
+            calls.push(call(Hook::LowStock, json!({ "sku": sku, "product": product })));
+        }
+    }
+    for (sku, product) in before {
+        if !after.contains_key(sku) {
+            calls.push(call(Hook::PostDelete, json!({ "sku": sku, "product": product })));
+        }
+    }
+    calls
+}
+
+/// Run the configured commands for each call, returning a message for each
+/// command that couldn't be started or failed
+pub fn run(config: &HooksConfig, dir: &Path, calls: &[HookCall]) -> Vec<String> {
+    let mut failures = Vec::new();
+    for call in calls {
+        for command in config.commands(call.hook) {
+            log::info!("Running {} hook: {}", call.hook.name(), command);
+            if let Err(message) = run_one(command, dir, call) {
+                log::warn!("{}", message);
+                failures.push(message);
+            }
+        }
+    }
+    failures
+}
+
+/// Run one hook command through the shell with the payload on stdin
+fn run_one(command: &str, dir: &Path, call: &HookCall) -> Result<(), String> {
+    let failed = |e: &dyn std::fmt::Display| format!("The {} hook '{}' failed: {}", call.hook.name(), command, e);
+    let mut child = shell(command)
+        .current_dir(dir)
+        .env("STOCK_HOOK", call.hook.name())
+        .stdin(Stdio::piped())
+        .spawn()
+        .map_err(|e| failed(&e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        // A hook that doesn't read its input closes the pipe early; that's fine
+        if let Err(e) = stdin.write_all(call.payload.as_bytes()) {
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                return Err(failed(&e));
+            }
+        }
+    }
+    let status = child.wait().map_err(|e| failed(&e))?;
+    if !status.success() {
+        return Err(failed(&status));
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.arg("-c").arg(command);
+    shell
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.arg("/C").arg(command);
+    shell
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Product;
+
+    fn snapshot(products: &[(&str, u32)]) -> BTreeMap<String, api::v1::Product> {
+        products
+            .iter()
+            .map(|(sku, quantity)| {
+                let product = Product { sku: sku.to_string(), quantity: *quantity, reorder_point: 5, ..Default::default() };
+                (sku.to_string(), api::v1::Product::from(&product))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_hooks_fire_for_changes_and_get_the_payload_on_stdin() {
+        let before = snapshot(&[("A", 10), ("B", 10), ("GONE", 1)]);
+        let after = snapshot(&[("A", 4), ("B", 10), ("NEW", 0)]);
+        let calls = calls(&before, &after);
+        let hooks: Vec<Hook> = calls.iter().map(|call| call.hook).collect();
+        assert_eq!(hooks, [Hook::PostStockChange, Hook::LowStock, Hook::PostDelete]);
+        let payload: serde_json::Value = serde_json::from_str(&calls[0].payload).unwrap();
+        assert_eq!((&payload["hook"], &payload["before"], &payload["after"]), (&json!("post-stock-change"), &json!(10), &json!(4)));
+        assert_eq!(payload["product"]["sku"], "A");
+
+        if cfg!(unix) {
+            let dir = tempfile::TempDir::new().unwrap();
+            let out = dir.path().join("out.json");
+            let config = HooksConfig {
+                low_stock: vec![format!("echo \"$STOCK_HOOK\" > '{0}.name'; cat > '{0}'", out.display()), "exit 3".to_string()],
+                ..Default::default()
+            };
+            let failures = run(&config, dir.path(), &calls);
+            assert_eq!(failures, ["The low-stock hook 'exit 3' failed: exit status: 3"]);
+            assert_eq!(std::fs::read_to_string(&out).unwrap(), calls[1].payload);
+            assert_eq!(std::fs::read_to_string(format!("{}.name", out.display())).unwrap(), "low-stock\n");
+        }
+    }
+}
@@ -0,0 +1,1238 @@
+// Localized user-facing strings (English and Spanish)
+//
+// The locale is chosen once at startup and stored per thread, so library
+// callers and tests that never set it get English.
+
+use std::cell::Cell;
+use std::fmt::Display;
+
+use crate::models::Condition;
+
+/// Supported output languages
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Locale {
+    /// English
+    #[default]
+    En,
+    /// Spanish
+    Es,
+}
+
+impl Locale {
+    /// Choose the locale from the configured value, then `LC_ALL`,
+    /// `LC_MESSAGES`, and `LANG`, falling back to English
+    pub fn detect(configured: Option<&str>) -> Result<Self, String> {
+        if let Some(tag) = configured {
+            return tag.parse();
+        }
+        let from_env = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|value| !value.is_empty())
+            .and_then(|value| Locale::from_tag(&value));
+        Ok(from_env.unwrap_or_default())
+    }
+
+    /// Match a language tag such as `es`, `es_MX.UTF-8`, or `en-GB`
+    fn from_tag(tag: &str) -> Option<Self> {
+        let language = tag
+            .split(['_', '-', '.'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        match language.as_str() {
+            "en" | "c" | "posix" => Some(Locale::En),
+            "es" => Some(Locale::Es),
+            _ => None,
+        }
+    }
+}
+
+impl std::str::FromStr for Locale {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Locale::from_tag(s).ok_or_else(|| format!("Unsupported locale '{}': expected en or es", s))
+    }
+}
+
+thread_local! {
+    static LOCALE: Cell<Locale> = const { Cell::new(Locale::En) };
+}
+
+/// Set the locale used by `tr` and `trf` on this thread
+pub fn set_locale(locale: Locale) {
+    LOCALE.with(|current| current.set(locale));
+}
+
+/// The locale used by `tr` and `trf` on this thread
+pub fn locale() -> Locale {
+    LOCALE.with(Cell::get)
+}
+
+/// Translate a message with no placeholders
+pub fn tr(message: Msg) -> &'static str {
+    message.text(locale())
+}
+
+/// Translate a message, replacing each `{name}` placeholder with its value
+pub fn trf(message: Msg, args: &[(&str, &dyn Display)]) -> String {
+    let mut text = tr(message).to_string();
+    for (name, value) in args {
+        text = text.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    text
+}
+
+/// Localized name of a stock condition
+pub fn condition_label(condition: Condition) -> &'static str {
+    tr(match condition {
+        Condition::New => Msg::ConditionNew,
+        Condition::Refurbished => Msg::ConditionRefurbished,
+        Condition::Damaged => Msg::ConditionDamaged,
+    })
+}
+
+/// Translated summary and notes of a command, if the locale has them
+pub fn command_description(command: &str) -> Option<&'static [&'static str]> {
+    let table: &[(&str, &[&str])] = match locale() {
+        Locale::En => return None,
+        Locale::Es => COMMAND_DESCRIPTIONS_ES,
+    };
+    table.iter().find(|(name, _)| *name == command).map(|(_, lines)| *lines)
+}
+
+macro_rules! messages {
+    ($($name:ident => $en:expr, $es:expr;)*) => {
+        /// Keys of translatable messages
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Msg {
+            $($name,)*
+        }
+
+        impl Msg {
+            /// Message text in the given locale
+            pub fn text(self, locale: Locale) -> &'static str {
+                match locale {
+                    Locale::En => match self { $(Msg::$name => $en,)* },
+                    Locale::Es => match self { $(Msg::$name => $es,)* },
+                }
+            }
+        }
+    };
+}
+
+messages! {
+    // Field labels
+    LabelId => "ID", "ID";
+    LabelSku => "SKU", "SKU";
+    LabelName => "Name", "Nombre";
+    LabelDescription => "Description", "Descripción";
+    LabelQuantity => "Quantity", "Cantidad";
+    LabelReorderPoint => "Reorder Point", "Punto de reorden";
+    LabelCategory => "Category", "Categoría";
+    LabelSupplier => "Supplier", "Proveedor";
+    LabelLocation => "Location", "Ubicación";
+    LabelNotes => "Notes", "Notas";
+    LabelBarcode => "Barcode", "Código de barras";
+    LabelAlertSnooze => "Alert acknowledged", "Alerta confirmada";
+    SnoozeDetail => "at {quantity} units", "con {quantity} unidades";
+    SnoozeDetailUntil => "at {quantity} units, until {until}", "con {quantity} unidades, hasta el {until}";
+    LabelUnitWeight => "Unit weight", "Peso unitario";
+    LabelTareWeight => "Tare weight", "Tara";
+    LabelLeadTime => "Lead time", "Plazo de entrega";
+    LeadTimeDays => "{days} days", "{days} días";
+    LabelComponents => "Components", "Componentes";
+    LabelLinks => "Links", "Vínculos";
+    LabelSubstitutes => "Substitutes in stock", "Sustitutos con stock";
+    LabelUnitCost => "Unit cost", "Coste unitario";
+    LabelValue => "Value", "Valor";
+    LabelCostLayers => "Cost layers", "Capas de coste";
+    CostLayersValue => "value {value}", "valor {value}";
+    LabelAdded => "Added", "Añadido";
+    LabelRemoved => "Removed", "Retirado";
+    LabelNewQuantity => "New Quantity", "Nueva cantidad";
+    LabelAvailable => "Available", "Disponible";
+    LabelCommitted => "Committed to backorders", "Comprometidas en pedidos pendientes";
+    AvailableToPromise => "  Available to promise: {available} ({quantity} available − {committed} committed)",
+        "  Disponible para comprometer: {available} ({quantity} disponibles − {committed} comprometidas)";
+    LabelOnOrder => "On order", "Pedidas";
+    LabelQuarantined => "Quarantined", "En cuarentena";
+    LabelCondition => "Condition", "Estado";
+    LabelMoved => "Moved", "Movido";
+    LowStockMarker => "[LOW STOCK]", "[STOCK BAJO]";
+    LowMarker => "[LOW]", "[BAJO]";
+    TypeAddition => "addition", "entrada";
+    TypeRemoval => "removal", "salida";
+    TypeInitialStock => "opening balance", "saldo inicial";
+    TypeDamage => "damage", "daño";
+    TypeWriteOff => "write-off", "baja";
+    TypeAdjustment => "adjustment", "ajuste";
+    TypeQuarantine => "quarantine", "cuarentena";
+    TypeReleaseQuarantine => "released from quarantine", "liberado de cuarentena";
+    TypeConditionChange => "condition change", "cambio de estado";
+    ConditionNew => "new", "nuevo";
+    ConditionRefurbished => "refurbished", "reacondicionado";
+    ConditionDamaged => "damaged", "dañado";
+    IntervalDay => "day", "día";
+    IntervalWeek => "week", "semana";
+    IntervalMonth => "month", "mes";
+    IntervalQuarter => "quarter", "trimestre";
+    IntervalYear => "year", "año";
+
+    // Command results and report headers
+    ProductAdded => "Product added successfully:", "Producto añadido correctamente:";
+    ProductUpdated => "Product updated successfully:", "Producto actualizado correctamente:";
+    ProductDetails => "Product Details:", "Detalles del producto:";
+    ProductDeleted => "Product '{sku}' deleted successfully.", "Producto '{sku}' eliminado correctamente.";
+    StockAdded => "Stock added successfully:", "Stock añadido correctamente:";
+    StockQuarantined => "Stock put in quarantine:", "Stock puesto en cuarentena:";
+    OrderMarked => "Marked {quantity} units of '{sku}' as ordered; {on_order} on order.",
+        "{quantity} unidades de '{sku}' marcadas como pedidas; {on_order} pedidas.";
+    OrderCancelled => "Took {quantity} units of '{sku}' off the order; {on_order} on order.",
+        "{quantity} unidades de '{sku}' quitadas del pedido; {on_order} pedidas.";
+    ProductsLinked => "Linked '{sku}': {link}.", "Vinculado '{sku}': {link}.";
+    ProductsUnlinked => "Removed the link from '{sku}': {link}.", "Quitado el vínculo de '{sku}': {link}.";
+    AlreadyLinked => "'{sku}' is already linked: {link}.", "'{sku}' ya está vinculado: {link}.";
+    NotLinked => "'{sku}' has no such link: {link}.", "'{sku}' no tiene ese vínculo: {link}.";
+    LinkSubstituteFor => "substitute for {sku}", "sustituto de {sku}";
+    LinkAccessoryOf => "accessory of {sku}", "accesorio de {sku}";
+    StockReleased => "Stock released from quarantine:", "Stock liberado de cuarentena:";
+    ConditionChanged => "Stock condition changed:", "Estado del stock cambiado:";
+    StockRemoved => "Stock removed successfully:", "Stock retirado correctamente:";
+    NoProducts => "No products in inventory.", "No hay productos en el inventario.";
+    ProductsHeader => "Products ({count} total):", "Productos ({count} en total):";
+    ProductLine => "  {sku} - {name} (Qty: {quantity}{marker})", "  {sku} - {name} (Cant.: {quantity}{marker})";
+    ListTotal => "Total", "Total";
+    NoProductsMatch => "No products match the filter.", "Ningún producto cumple el filtro.";
+    BulkUpdatePreview => "Would update {count} products:", "Se actualizarían {count} productos:";
+    BulkUpdateConfirm => "Changes: {changes}\nRun again with --yes to apply them.",
+        "Cambios: {changes}\nVuelva a ejecutarlo con --yes para aplicarlos.";
+    BulkUpdated => "Updated {count} products.", "{count} productos actualizados.";
+    ProductsGroupedHeader => "Products by {group} ({count} total):", "Productos por {group} ({count} en total):";
+    NoLowStock => "No products with low stock.", "No hay productos con stock bajo.";
+    LowStockSnoozedNote => "{count} acknowledged products not shown; ack <sku> --clear shows one again.",
+        "{count} productos confirmados no se muestran; ack <sku> --clear vuelve a mostrar uno.";
+    AlertAcknowledged => "Acknowledged the low-stock alert for '{sku}' at {quantity} units; it shows again if stock falls below that or after a restock.",
+        "Alerta de stock bajo de '{sku}' confirmada con {quantity} unidades; vuelve a mostrarse si el stock baja de ahí o tras una reposición.";
+    AlertAcknowledgedUntil => "Acknowledged the low-stock alert for '{sku}' at {quantity} units until {until}; it shows again sooner if stock falls below that or after a restock.",
+        "Alerta de stock bajo de '{sku}' confirmada con {quantity} unidades hasta el {until}; vuelve a mostrarse antes si el stock baja de ahí o tras una reposición.";
+    AlertSnoozeCleared => "The low-stock alert for '{sku}' shows again.", "La alerta de stock bajo de '{sku}' vuelve a mostrarse.";
+    AlertNotSnoozed => "The low-stock alert for '{sku}' was not acknowledged.", "La alerta de stock bajo de '{sku}' no estaba confirmada.";
+    LowStockHeader => "Low Stock Products ({count} total):", "Productos con stock bajo ({count} en total):";
+    LowStockGroupedHeader => "Low Stock Products by {group} ({count} total):", "Productos con stock bajo por {group} ({count} en total):";
+    LowStockLine => "  {sku} - {name} (Qty: {quantity}, Reorder at: {reorder_point})",
+        "  {sku} - {name} (Cant.: {quantity}, Reordenar en: {reorder_point})";
+    LowStockGroupedLine => "  {sku} - {name} (Qty: {quantity}, Reorder at: {reorder_point}, Short: {short})",
+        "  {sku} - {name} (Cant.: {quantity}, Reordenar en: {reorder_point}, Faltan: {short})";
+    GroupNone => "(none)", "(ninguno)";
+    StatsHeader => "Inventory statistics:", "Estadísticas del inventario:";
+    StatsByCategory => "By category:", "Por categoría:";
+    StatsCategoryLine => "  {category}: {products} products, {units} units, value {value}",
+        "  {category}: {products} productos, {units} unidades, valor {value}";
+    StatsQuotas => "Size quotas:", "Cuotas de tamaño:";
+    StatsQuotaProducts => "  Products: {used} of {limit} ({percent}%)", "  Productos: {used} de {limit} ({percent}%)";
+    StatsQuotaTransactions => "  Transactions: {used} of {limit} ({percent}%)", "  Movimientos: {used} de {limit} ({percent}%)";
+    StatsQuotaDataBytes => "  Data files: {used} of {limit} bytes ({percent}%)",
+        "  Archivos de datos: {used} de {limit} bytes ({percent}%)";
+    StatsQuotaNear => "    Near the limit; consider archive-transactions or deleting discontinued products",
+        "    Cerca del límite; considere archive-transactions o eliminar productos descatalogados";
+    LabelLowStock => "Low stock", "Stock bajo";
+    ValueHistoryHeader => "Inventory value by {interval}:", "Valor del inventario por {interval}:";
+    ValueHistoryLine => "  {at}: {units} units, value {value}", "  {at}: {units} unidades, valor {value}";
+    ValueUncostedNote => "Note: {units} units have no unit cost and are not valued; record one with add-stock --unit-cost or update-product --unit-cost.",
+        "Nota: {units} unidades no tienen coste unitario y no se valoran; regístrelo con add-stock --unit-cost o update-product --unit-cost.";
+    ReorderSuggestionsHeader => "Suggested reorder points (service level {level}, demand over {days} days):",
+        "Puntos de reorden sugeridos (nivel de servicio {level}, demanda de {days} días):";
+    ReorderSuggestionLine => "  {sku} - {name}: {current} -> {suggested} ({mean}/day ± {std_dev}, lead time {lead_time} days, safety stock {safety})",
+        "  {sku} - {name}: {current} -> {suggested} ({mean}/día ± {std_dev}, plazo {lead_time} días, stock de seguridad {safety})";
+    NoReorderSuggestions => "No removals in the last {days} days to suggest reorder points from.",
+        "No hay salidas en los últimos {days} días a partir de las que sugerir puntos de reorden.";
+    ReorderPointsUpToDate => "All reorder points already match the suggestions.",
+        "Todos los puntos de reorden ya coinciden con las sugerencias.";
+    ReorderApplyHint => "{count} reorder points differ; run again with --apply to review and set them.",
+        "{count} puntos de reorden difieren; vuelva a ejecutar con --apply para revisarlos y fijarlos.";
+    ReorderPointsApplied => "Updated the reorder point of {count} products.",
+        "Punto de reorden actualizado en {count} productos.";
+    ForecastHeader => "Stock-out forecast (seasonality: {seasonality}, demand over {days} days):",
+        "Previsión de roturas de stock (estacionalidad: {seasonality}, demanda de {days} días):";
+    ForecastLine => "  {sku} - {name}: {available} available, {mean}/day, {week} expected next 7 days; {outlook}",
+        "  {sku} - {name}: {available} disponibles, {mean}/día, {week} previstas en 7 días; {outlook}";
+    ForecastRunsOut => "runs out around {date} (in {days} days)", "se agota hacia el {date} (en {days} días)";
+    ForecastOutNow => "out of stock now", "sin stock ahora";
+    ForecastLasts => "lasts beyond {days} days", "dura más de {days} días";
+    NoForecast => "No removals in the last {days} days to forecast from.",
+        "No hay retiradas en los últimos {days} días para hacer una previsión.";
+    KpisHeader => "Daily KPIs ({count} days):", "KPI diarios ({count} días):";
+    KpisLine => "  {date}  {bar} {value}  {units} units, {low} low stock, {movements} movements",
+        "  {date}  {bar} {value}  {units} unidades, {low} con stock bajo, {movements} movimientos";
+    NoKpiSnapshots => "No KPI snapshots recorded yet; run snapshot-kpis daily, e.g. from cron.",
+        "Aún no hay instantáneas de KPI; ejecute snapshot-kpis a diario, p. ej. desde cron.";
+    KpiSnapshotTaken => "KPIs for {date}: {units} units, value {value}, {low} low stock, {movements} movements",
+        "KPI del {date}: {units} unidades, valor {value}, {low} con stock bajo, {movements} movimientos";
+    LabelBackordered => "Backordered", "Pendiente";
+    NoBackorders => "No outstanding backorders.", "No hay pedidos pendientes.";
+    BackordersHeader => "Outstanding backorders ({count}, {units} units):", "Pedidos pendientes ({count}, {units} unidades):";
+    BackorderLine => "  {id} {date} {sku}: {quantity} units{notes}", "  {id} {date} {sku}: {quantity} unidades{notes}";
+    BackordersFilled => "Filled {filled} backordered units of '{sku}'; {owed} still owed.",
+        "Se sirvieron {filled} unidades pendientes de '{sku}'; quedan {owed} por servir.";
+    PricesImported => "Imported {count} prices from {supplier} ({replaced} replaced).",
+        "Se importaron {count} precios de {supplier} ({replaced} reemplazados).";
+    ShopDryRunHeader => "Would send {count} requests to the {platform} store:",
+        "Se enviarían {count} peticiones a la tienda {platform}:";
+    ShopSynced => "Pushed {count} stock levels to the {platform} store at {url}.",
+        "Se enviaron {count} niveles de stock a la tienda {platform} en {url}.";
+    ShopUnmapped => " {count} products have no store ID in the mapping.",
+        " {count} productos no tienen ID de tienda en el mapeo.";
+    NoticeStaged => "Staged notice {reference} from {supplier} ({count} lines).",
+        "Se registró el aviso {reference} de {supplier} ({count} líneas).";
+    NoticeRejected => "Skipped message {message}: {error}", "Se omitió el mensaje {message}: {error}";
+    NoNotices => "No shipping notices are waiting.", "No hay avisos de envío pendientes.";
+    NoticesHeader => "Shipping notices waiting ({count}):", "Avisos de envío pendientes ({count}):";
+    NoticeLine => "  {reference}: {summary}, {count} lines", "  {reference}: {summary}, {count} líneas";
+    NoticesHint => "Start receiving one with receive inbox --start <reference>.",
+        "Empieza a recibir uno con receive inbox --start <referencia>.";
+    FeedPushed => "Pushed feed '{name}' to {location}", "Se envió el feed '{name}' a {location}";
+    FeedFailed => "Feed '{name}' failed: {error}", "Falló el feed '{name}': {error}";
+    FeedNoneDue => "No feeds are due.", "Ningún feed está pendiente.";
+    NoFeeds => "No feeds are configured; add them under feeds in config.json.",
+        "No hay feeds configurados; añádelos en feeds de config.json.";
+    FeedStatusLine => "{name}: {format} to {destination}{schedule}; last pushed {success}",
+        "{name}: {format} a {destination}{schedule}; último envío {success}";
+    FeedSchedule => ", every {minutes} minutes", ", cada {minutes} minutos";
+    FeedStatusFailure => "; failed {failure}: {error}", "; falló {failure}: {error}";
+    FeedNever => "never", "nunca";
+    InventoryAdviceWritten => "Wrote the positions of {count} products to {path}",
+        "Se escribieron las existencias de {count} productos en {path}";
+    ReconcileHeader => "Compared {count} products in {file}: {discrepancies} discrepancies",
+        "Se compararon {count} productos de {file}: {discrepancies} discrepancias";
+    ReconcileLine => "  {sku}: {ours} here, {theirs} in the export ({difference})",
+        "  {sku}: {ours} aquí, {theirs} en la exportación ({difference})";
+    ReconcileNotInExport => "  {sku}: {ours} here, not in the export", "  {sku}: {ours} aquí, no está en la exportación";
+    ReconcileNotInInventory => "  {sku}: {theirs} in the export, not in the inventory",
+        "  {sku}: {theirs} en la exportación, no está en el inventario";
+    ReconcileApplied => "Adjusted {count} products to match the export.",
+        "Se ajustaron {count} productos para que coincidan con la exportación.";
+    ReconcileHeld => "Held {count} variances beyond the stocktake tolerance ({skus}); count them again with --recount or accept them with --confirm.",
+        "Se retuvieron {count} diferencias fuera de la tolerancia de recuento ({skus}); vuelve a contarlas con --recount o acéptalas con --confirm.";
+    CountSheetsWritten => "Wrote {sheets} count sheets listing {count} products to {path}",
+        "Se escribieron {sheets} hojas de recuento con {count} productos en {path}";
+    ReconcileNote => "Reconciled with {file}", "Conciliado con {file}";
+    PricesHeader => "Supplier prices for '{sku}' on {date}:", "Precios de proveedores para '{sku}' el {date}:";
+    PriceLine => "  {supplier}: {cost} (from {date}){marker}", "  {supplier}: {cost} (desde {date}){marker}";
+    CheapestMarker => "[CHEAPEST]", "[MÁS BARATO]";
+    NoPrices => "No supplier prices for '{sku}' on {date}.", "No hay precios de proveedores para '{sku}' el {date}.";
+    LowStockOnOrder => "    On order: {quantity}", "    Pedidas: {quantity}";
+    RulesHeader => "Alert rules ({count}):", "Reglas de alerta ({count}):";
+    RuleLine => "  {rule}: {when} ({count} matching)", "  {rule}: {when} ({count} coinciden)";
+    RuleProductLine => "    {sku} - {name} (Qty: {quantity})", "    {sku} - {name} (Cant.: {quantity})";
+    NoAlertRules => "No alert rules are defined; add them to the rules list in config.json.",
+        "No hay reglas de alerta; añádalas a la lista rules de config.json.";
+    LowStockSubstitutes => "    Substitutes in stock: {substitutes}", "    Sustitutos con stock: {substitutes}";
+    LowStockCheapest => "    Cheapest supplier: {supplier} at {cost}", "    Proveedor más barato: {supplier} a {cost}";
+    CogsHeader => "Cost of goods removed from {from} to {to}:", "Coste de las mercancías retiradas desde {from} hasta {to}:";
+    CogsLine => "  {sku} - {name}: {units} units, cost {cost}", "  {sku} - {name}: {units} unidades, coste {cost}";
+    CogsTotal => "Total: {units} units, cost {cost}", "Total: {units} unidades, coste {cost}";
+    LedgerUncostedNote => "{units} units have no unit cost and are left out; record one with add-stock --unit-cost.",
+        "{units} unidades no tienen coste unitario y se omiten; registra uno con add-stock --unit-cost.";
+    NoCogs => "No stock removed from {from} to {to}.", "No se retiró stock desde {from} hasta {to}.";
+    ShippedTo => "to {customer}", "a {customer}";
+    CustomerAdded => "Added customer {code} - {name}.", "Se añadió el cliente {code} - {name}.";
+    NoCustomers => "No customers. Add one with add-customer.", "No hay clientes. Añada uno con add-customer.";
+    CustomersHeader => "Customers ({count}):", "Clientes ({count}):";
+    ShipmentsHeader => "Shipped from {from} to {to}: {units} units to {count} customers",
+        "Enviado desde {from} hasta {to}: {units} unidades a {count} clientes";
+    ShipmentLine => "    {sku} - {name}: {units} units in {count} removals", "    {sku} - {name}: {units} unidades en {count} salidas";
+    NoShipments => "Nothing shipped to customers from {from} to {to}.", "No se envió nada a clientes desde {from} hasta {to}.";
+    GroupSubtotal => "  Subtotal: {count} products, {short} units short", "  Subtotal: {count} productos, faltan {short} unidades";
+    NoTransactions => "No transactions found for product '{sku}'.", "No se encontraron movimientos para el producto '{sku}'.";
+    HistoryHeader => "Transaction History for '{sku}' ({count} transactions):", "Historial de movimientos de '{sku}' ({count} movimientos):";
+    MergedHistoryHeader => "Merged history of {skus} ({count} transactions):",
+        "Historial combinado de {skus} ({count} movimientos):";
+    NoTransactionsCompared => "No transactions found for {skus}.", "No se encontraron movimientos para {skus}.";
+    CompareHeader => "Movements compared:", "Comparación de movimientos:";
+    CompareReceived => "Received", "Recibidas";
+    CompareRemoved => "Removed", "Retiradas";
+    CompareShrinkage => "Lost", "Perdidas";
+    CompareNetChange => "Net change", "Cambio neto";
+    CompareMovements => "Movements", "Movimientos";
+    CompareOnHand => "On hand", "En existencia";
+    ReceiptWritten => "Receipt {reference} written to {path}", "Comprobante {reference} guardado en {path}";
+    ReceiptPrinted => "Receipt {reference} sent to {printer}", "Comprobante {reference} enviado a {printer}";
+    BulkAdded => "Stock added successfully for {count} lines:", "Stock añadido correctamente en {count} líneas:";
+    BulkRemoved => "Stock removed successfully for {count} lines:", "Stock retirado correctamente en {count} líneas:";
+    BulkLine => "  {sku} {change} (New Quantity: {quantity})", "  {sku} {change} (Nueva cantidad: {quantity})";
+    TrashEmpty => "Trash is empty.", "La papelera está vacía.";
+    TrashHeader => "Deleted Products ({count} total):", "Productos eliminados ({count} en total):";
+    TrashLine => "  {sku} - {name} (deleted {deleted}{by}, {count} transactions, {expires})",
+        "  {sku} - {name} (eliminado {deleted}{by}, {count} movimientos, {expires})";
+    TrashDeletedBy => " by {operator}", " por {operator}";
+    TrashExpires => "purged after {date}", "se purga después del {date}";
+    TrashNeverExpires => "kept until restored", "se conserva hasta restaurarlo";
+    ProductRestored => "Product '{sku}' restored with {count} transactions.", "Producto '{sku}' restaurado con {count} movimientos.";
+    TransactionsImported => "Imported {count} transactions for {products} products:",
+        "Se importaron {count} movimientos de {products} productos:";
+    ImportedLine => "  {sku} ({count} transactions, New Quantity: {quantity})",
+        "  {sku} ({count} movimientos, Nueva cantidad: {quantity})";
+    OpeningBalancesCreated => "Created {count} products with opening balances as of {date}.",
+        "Se crearon {count} productos con saldos iniciales a fecha de {date}.";
+    OpeningBalancesMerged => "Merged opening balances into {count} existing products.",
+        "Se combinaron saldos iniciales con {count} productos existentes.";
+    ReconciliationHeader => "Reconciliation:", "Conciliación:";
+    ReconciliationLine => "  {label}: expected {expected}, created {created}", "  {label}: esperado {expected}, creado {created}";
+    ReconciliationMismatch => "  {sku}: expected {expected}, recorded {recorded}", "  {sku}: esperado {expected}, registrado {recorded}";
+    ReconciliationBalanced => "  All opening balances reconciled.", "  Todos los saldos iniciales cuadran.";
+    LabelProducts => "Products", "Productos";
+    LabelUnits => "Units", "Unidades";
+    DiffCurrent => "current inventory", "inventario actual";
+    DiffNone => "No differences between {from} and {to}.", "No hay diferencias entre {from} y {to}.";
+    DiffHeader => "Changes from {from} to {to}:", "Cambios de {from} a {to}:";
+    DiffAdded => "Added ({count}):", "Añadidos ({count}):";
+    DiffRemoved => "Removed ({count}):", "Eliminados ({count}):";
+    DiffChanged => "Quantity changes ({count}):", "Cambios de cantidad ({count}):";
+    DiffChangeLine => "  {sku} - {name}: {before} -> {after} ({delta})", "  {sku} - {name}: {before} -> {after} ({delta})";
+    DiffNet => "Net change: {delta} units", "Cambio neto: {delta} unidades";
+    PeriodStart => "the beginning", "el inicio";
+    PeriodNow => "now", "hoy";
+    NoShrinkage => "No shrinkage recorded from {from} to {to}.", "No hay mermas registradas desde {from} hasta {to}.";
+    ShrinkageHeader => "Shrinkage from {from} to {to}: {units} units lost across {count} products",
+        "Mermas desde {from} hasta {to}: {units} unidades perdidas en {count} productos";
+    ShrinkageLine => "    {type}: {units} units ({count} transactions)", "    {type}: {units} unidades ({count} movimientos)";
+    ShrinkageByType => "By type:", "Por tipo:";
+    ShrinkageTotal => "  {type}: {units} units", "  {type}: {units} unidades";
+    ReasonSale => "sale", "venta";
+    ReasonInternalUse => "internal use", "uso interno";
+    ReasonSample => "sample", "muestra";
+    ReasonDamage => "damage", "daño";
+    ReasonUnspecified => "unspecified", "sin especificar";
+    NoConsumption => "No stock removed from {from} to {to}.", "No se retiró stock desde {from} hasta {to}.";
+    ConsumptionHeader => "Consumption by reason from {from} to {to}: {units} units",
+        "Consumo por motivo desde {from} hasta {to}: {units} unidades";
+    ConsumptionLine => "  {reason}: {units} units ({share}%, {count} transactions)",
+        "  {reason}: {units} unidades ({share}%, {count} movimientos)";
+    BuildableHeader => "{sku} can be assembled {kits} times from current stock:",
+        "Se pueden montar {kits} unidades de {sku} con el stock actual:";
+    BuildableLine => "  {sku}: {available} in stock, {per_kit} per kit ({kits} kits)",
+        "  {sku}: {available} en stock, {per_kit} por kit ({kits} kits)";
+    LimitingMarker => "[LIMITING]", "[LIMITANTE]";
+    ReceiptNoReference => "(no reference)", "(sin referencia)";
+    ReceivingStarted => "Receiving {reference} with {count} expected lines. Stage goods with receive add <sku> <quantity>.",
+        "Recepción {reference} con {count} líneas esperadas. Registra la mercancía con receive add <sku> <cantidad>.";
+    ReceivingStaged => "Staged {quantity} units of {sku} ({received} received so far).",
+        "Registradas {quantity} unidades de {sku} ({received} recibidas hasta ahora).";
+    ReceivingHeader => "Receipt {reference} started {date}{by}: {lines} lines, {units} units received",
+        "Recepción {reference} iniciada el {date}{by}: {lines} líneas, {units} unidades recibidas";
+    ReceivingBy => " by {operator}", " por {operator}";
+    ReceivingLine => "  {sku}: received {received}{marker}", "  {sku}: recibidas {received}{marker}";
+    ReceivingExpectedLine => "  {sku}: expected {expected}, received {received}{marker}",
+        "  {sku}: esperadas {expected}, recibidas {received}{marker}";
+    ReceivingShort => "[SHORT {units}]", "[FALTAN {units}]";
+    ReceivingOver => "[OVER {units}]", "[SOBRAN {units}]";
+    ReceivingUnexpected => "[NOT EXPECTED]", "[NO ESPERADO]";
+    ReceivingCommitted => "Receipt {reference} committed: {units} units added to {products} products.",
+        "Recepción {reference} confirmada: {units} unidades añadidas a {products} productos.";
+    ReceivingVariances => "Committed with variances:", "Confirmada con diferencias:";
+    ReceivingCancelled => "Receipt {reference} cancelled; {count} staged lines discarded.",
+        "Recepción {reference} cancelada; se descartaron {count} líneas registradas.";
+    Exported => "Exported {products} products and {transactions} transactions to {dir}.",
+        "Se exportaron {products} productos y {transactions} movimientos a {dir}.";
+    ExportSigned => "Signed: {manifest} lists the files' SHA-256 and {signature} holds its Ed25519 signature.",
+        "Firmada: {manifest} enumera el SHA-256 de los archivos y {signature} contiene su firma Ed25519.";
+    SignatureVerified => "Signature of {snapshot} verified with {key}.", "Firma de {snapshot} verificada con {key}.";
+    ErrSignature => "Error: Could not authenticate {snapshot} - {error}",
+        "Error: No se pudo autenticar {snapshot} - {error}";
+    ExportedChanges => "Exported {count} stock changes to {file}; export with --since {seq} next time for the ones after.",
+        "Se exportaron {count} cambios de existencias a {file}; exporta con --since {seq} la próxima vez para los siguientes.";
+    ExportedAnonymized => "Exported {products} products and {transactions} transactions to {dir} (anonymized).",
+        "Se exportaron {products} productos y {transactions} movimientos a {dir} (anonimizados).";
+    TransactionsArchived => "Archived {count} transactions from before {date}.",
+        "Se archivaron {count} movimientos anteriores al {date}.";
+    CompactHeader => "Storage compacted: {reclaimed} bytes reclaimed ({before} -> {after} bytes)",
+        "Almacenamiento compactado: {reclaimed} bytes recuperados ({before} -> {after} bytes)";
+    CompactDuplicateProducts => "  Duplicate products removed: {count}", "  Productos duplicados eliminados: {count}";
+    CompactDuplicateTransactions => "  Duplicate transactions removed: {count}",
+        "  Movimientos duplicados eliminados: {count}";
+    CompactPurgedTrash => "  Expired trash purged: {count}", "  Elementos caducados purgados de la papelera: {count}";
+    CompactArchived => "  Transactions archived: {count}", "  Movimientos archivados: {count}";
+    IdsHealed => "Gave {count} records new IDs:", "Se dieron nuevos ID a {count} registros:";
+    IdsWouldHeal => "Dry run: {count} records would get new IDs; nothing was changed:",
+        "Simulación: {count} registros recibirían nuevos ID; no se cambió nada:";
+    IdsUnique => "No records share an ID.", "Ningún registro comparte ID.";
+    IdsCopies => "  {count} transactions are exact copies of an earlier one; compact removes them.",
+        "  {count} movimientos son copias exactas de uno anterior; compact los elimina.";
+    RetentionApplied => "Retention applied: cleared notes on {notes} records and customer codes on {customers}.",
+        "Conservación aplicada: se borraron las notas de {notes} registros y los códigos de cliente de {customers}.";
+    RetentionDryRun => "Dry run: would clear notes on {notes} records and customer codes on {customers}; nothing was changed.",
+        "Simulación: se borrarían las notas de {notes} registros y los códigos de cliente de {customers}; no se cambió nada.";
+    RetentionRelinked => "  The hash chain was linked again over {count} transactions; chain heads in earlier backups no longer match.",
+        "  La cadena de hashes se volvió a enlazar sobre {count} movimientos; las cabezas de copias anteriores ya no coinciden.";
+    RetentionWouldRelink => "  The hash chain would be linked again over {count} transactions.",
+        "  La cadena de hashes se volvería a enlazar sobre {count} movimientos.";
+    ErrNoRetentionRules => "Error: No retention rules are set; add retention.notes_years or retention.customer_years to config.json.",
+        "Error: No hay reglas de conservación; añada retention.notes_years o retention.customer_years a config.json.";
+    LedgerIntact => "Ledger intact: {count} chained transactions, head {head}",
+        "Registro íntegro: {count} movimientos encadenados, cabeza {head}";
+    LedgerUnchained => "  {count} transactions recorded before the chain was enabled are not covered.",
+        "  {count} movimientos registrados antes de activar la cadena no están cubiertos.";
+    LedgerMatchesBackup => "  The chain head saved in {dir} is on the chain.",
+        "  La cabeza de la cadena guardada en {dir} está en la cadena.";
+    LedgerNotChained => "The ledger is not hash-chained; set ledger.hash_chain to true in config.json.",
+        "El registro no está encadenado; active ledger.hash_chain en config.json.";
+    ErrLedgerTampered => "Error: The ledger failed verification ({count} problems):",
+        "Error: El registro no superó la verificación ({count} problemas):";
+    ErrStrictLoad => "Error: The stored data failed strict checks ({count} problems); nothing was changed:",
+        "Error: Los datos guardados no superaron las comprobaciones estrictas ({count} problemas); no se cambió nada:";
+    ErrLedgerBackup => "Error: Failed to read the chain head in {dir} - {error}",
+        "Error: No se pudo leer la cabeza de la cadena en {dir} - {error}";
+    ErrLedgerNoBackupHead => "Error: {dir} has no ledger-head.json to check against.",
+        "Error: {dir} no tiene ledger-head.json con el que comparar.";
+    BulkRejected => "Error: {rejected} of {total} lines rejected, no changes applied:",
+        "Error: {rejected} de {total} líneas rechazadas, no se aplicó ningún cambio:";
+    BulkRejectedLine => "  Line {line}: {error}", "  Línea {line}: {error}";
+    Warning => "Warning: {message}", "Aviso: {message}";
+    KioskBanner => "Kiosk mode. Type 'in', 'out', or 'view', then scan SKUs; 'help' lists commands.",
+        "Modo quiosco. Escribe 'in', 'out' o 'view' y escanea los SKU; 'help' muestra los comandos.";
+    KioskHelp => "  in|out|view           set what scanned SKUs do\n  [in|out|view] <sku> [qty]  move or show stock (1 unit by default)\n  unlock <code>         allow every command until 'lock'",
+        "  in|out|view           define qué hacen los SKU escaneados\n  [in|out|view] <sku> [cant]  mueve o muestra stock (1 unidad por defecto)\n  unlock <código>       permite todos los comandos hasta 'lock'";
+    KioskMode => "Mode: {mode}", "Modo: {mode}";
+    WeighedIn => "Weighed {gross}: added {units} units of '{sku}'; now {total}.",
+        "Pesado {gross}: se añadieron {units} unidades de '{sku}'; ahora {total}.";
+    WeighedOut => "Weighed {gross}: removed {units} units of '{sku}'; now {total}.",
+        "Pesado {gross}: se retiraron {units} unidades de '{sku}'; ahora {total}.";
+    KioskPickHeader => "'{code}' matches {count} products; enter a number to choose:",
+        "'{code}' corresponde a {count} productos; escribe un número para elegir:";
+    KioskPickLine => "  {number}) {sku} - {name}", "  {number}) {sku} - {name}";
+    KioskPickCancelled => "Choice cancelled; nothing was moved.", "Elección cancelada; no se movió nada.";
+    KioskScannedIn => "{sku}: +{quantity} -> {total}{marker}", "{sku}: +{quantity} -> {total}{marker}";
+    KioskScannedOut => "{sku}: -{quantity} -> {total}{marker}", "{sku}: -{quantity} -> {total}{marker}";
+    KioskLocked => "Locked: only in, out, and view are available; use 'unlock <code>' for other commands.",
+        "Bloqueado: solo están disponibles in, out y view; usa 'unlock <código>' para otros comandos.";
+    KioskUnlocked => "Unlocked: every command is available until 'lock'.",
+        "Desbloqueado: todos los comandos están disponibles hasta 'lock'.";
+    KioskRelocked => "Locked.", "Bloqueado.";
+    KioskWrongCode => "Wrong unlock code.", "Código de desbloqueo incorrecto.";
+    KioskNoUnlockCode => "No unlock code is configured (kiosk.unlock_code in config.json).",
+        "No hay código de desbloqueo configurado (kiosk.unlock_code en config.json).";
+    ErrShopSyncNested => "Error: shop-sync can only be run from the command line.",
+        "Error: shop-sync solo se puede ejecutar desde la línea de comandos.";
+    ErrShopNotConfigured => "Error: No store is configured; set shop.platform and shop.url in config.json.",
+        "Error: No hay ninguna tienda configurada; define shop.platform y shop.url en config.json.";
+    ErrInboxNested => "Error: receive inbox can only be run from the command line.",
+        "Error: receive inbox solo se puede ejecutar desde la línea de comandos.";
+    ErrNoMaildir => "Error: No maildir given; pass one or set inbox.maildir in config.json.",
+        "Error: No se indicó ningún maildir; pásalo o define inbox.maildir en config.json.";
+    ErrNoInboxSuppliers => "Error: No suppliers are listed under inbox.suppliers in config.json.",
+        "Error: No hay proveedores en inbox.suppliers de config.json.";
+    ErrNoticeNotFound => "Error: No shipping notice '{reference}' is staged; see receive inbox.",
+        "Error: No hay ningún aviso de envío '{reference}' registrado; consulta receive inbox.";
+    ErrFeedNested => "Error: Feeds can only be pushed from the command line.",
+        "Error: Los feeds solo se pueden enviar desde la línea de comandos.";
+    ErrEventsNested => "Error: events tail can only be run from the command line.",
+        "Error: events tail solo se puede ejecutar desde la línea de comandos.";
+    ErrNoFeeds => "Error: No feeds are configured; add them under feeds in config.json.",
+        "Error: No hay feeds configurados; añádelos en feeds de config.json.";
+    ErrFeedNotFound => "Error: No feed named '{name}' in config.json.", "Error: No hay ningún feed '{name}' en config.json.";
+    ErrKioskNested => "Error: The kiosk can only be started from the command line.",
+        "Error: El quiosco solo se puede iniciar desde la línea de comandos.";
+
+    // Saved views
+    ViewSaved => "Saved view '{name}'.", "Vista '{name}' guardada.";
+    ViewDeleted => "Deleted view '{name}'.", "Vista '{name}' eliminada.";
+    NoViews => "No saved views.", "No hay vistas guardadas.";
+    ViewsHeader => "Saved views ({count}):", "Vistas guardadas ({count}):";
+    ErrViewNotFound => "Error: No saved view named '{name}'.", "Error: No hay ninguna vista guardada llamada '{name}'.";
+    ErrViewsNested => "Error: Saved views can only be used from the command line.",
+        "Error: Las vistas guardadas solo se pueden usar desde la línea de comandos.";
+    TemplateSaved => "Saved product template '{name}'.", "Plantilla de producto '{name}' guardada.";
+    TemplateDeleted => "Deleted product template '{name}'.", "Plantilla de producto '{name}' eliminada.";
+    NoTemplates => "No saved product templates.", "No hay plantillas de producto guardadas.";
+    TemplatesHeader => "Saved product templates ({count}):", "Plantillas de producto guardadas ({count}):";
+    ErrTemplateNotFound => "Error: No saved product template named '{name}'.",
+        "Error: No hay ninguna plantilla de producto guardada llamada '{name}'.";
+    ErrTemplatesNested => "Error: Product templates can only be used from the command line.",
+        "Error: Las plantillas de producto solo se pueden usar desde la línea de comandos.";
+    ErrImportProfileNotFound => "Error: No import profile named '{name}' in config.json.",
+        "Error: No hay ningún perfil de importación llamado '{name}' en config.json.";
+    ErrImportNested => "Error: Import profiles and --resume can only be used from the command line.",
+        "Error: Los perfiles de importación y --resume solo se pueden usar desde la línea de comandos.";
+    ImportInterrupted => "The import stopped after {applied} of {total} rows; continue it with: import-transactions --resume {id}",
+        "La importación se detuvo tras {applied} de {total} filas; continúala con: import-transactions --resume {id}";
+
+    // Errors
+    ErrProductNotFound => "Error: Product '{sku}' not found.", "Error: No se encontró el producto '{sku}'.";
+    ErrDuplicateSku => "Error: Product with SKU '{sku}' already exists.", "Error: Ya existe un producto con el SKU '{sku}'.";
+    ErrInvalidInput => "Error: {message}", "Error: {message}";
+    ErrTransactionNotFound => "Error: Transaction '{id}' not found.", "Error: No se encontró el movimiento '{id}'.";
+    ErrInsufficientStock => "Error: Insufficient stock for '{sku}'. Requested: {requested}, Available: {available}",
+        "Error: Stock insuficiente para '{sku}'. Solicitado: {requested}, Disponible: {available}";
+    ErrLargeMovement => "Error: Removing {requested} units of '{sku}' exceeds the large-movement threshold ({threshold}).",
+        "Error: Retirar {requested} unidades de '{sku}' supera el umbral de movimiento grande ({threshold}).";
+    ErrStorage => "Error: Storage operation failed while {operation} - {error}",
+        "Error: Falló la operación de almacenamiento ({operation}) - {error}";
+    ErrDeletionInStock => "Error: Product '{sku}' still has {quantity} units in stock.",
+        "Error: El producto '{sku}' todavía tiene {quantity} unidades en stock.";
+    ErrDeletionRecentActivity => "Error: Product '{sku}' has {count} transactions in the last {days} days.",
+        "Error: El producto '{sku}' tiene {count} movimientos en los últimos {days} días.";
+    ErrNotInTrash => "Error: No deleted product '{sku}' in the trash.", "Error: No hay ningún producto eliminado '{sku}' en la papelera.";
+    ErrCustomerNotFound => "Error: Customer '{code}' not found.", "Error: No se encontró el cliente '{code}'.";
+    ErrDuplicateCustomer => "Error: Customer '{code}' already exists.", "Error: El cliente '{code}' ya existe.";
+    ErrClockDrift => "Error: '{sku}' has a transaction at {latest}, later than this machine's clock; check the clocks of machines sharing the data (clock.action in config.json).",
+        "Error: '{sku}' tiene un movimiento del {latest}, posterior al reloj de esta máquina; revisa los relojes de las máquinas que comparten los datos (clock.action en config.json).";
+    ErrReadOnly => "Error: The inventory was opened read-only; nothing was saved.", "Error: El inventario se abrió en modo de solo lectura; no se guardó nada.";
+    ErrReconciliation => "Error: Opening balances were created but do not reconcile with the input.",
+        "Error: Se crearon los saldos iniciales, pero no cuadran con el archivo de entrada.";
+    ErrSnapshot => "Error: Cannot load snapshot {path} - {error}", "Error: No se puede cargar la instantánea {path} - {error}";
+    ErrExport => "Error: Export failed - {error}", "Error: Falló la exportación - {error}";
+    ErrConvert => "Error: Conversion failed - {error}; the source was not changed.",
+        "Error: Falló la conversión - {error}; el origen no se modificó.";
+    ErrConvertSameDir => "Error: The target must be a different data directory.",
+        "Error: El destino debe ser otro directorio de datos.";
+    ErrConvertNested => "Error: convert-storage can only be run from the command line.",
+        "Error: convert-storage solo se puede ejecutar desde la línea de comandos.";
+    ConvertHeader => "Converted {from} data to {to} in {dir}; the copy matches the source:",
+        "Se convirtieron los datos {from} a {to} en {dir}; la copia coincide con el origen:";
+    ConvertLine => "  {kind}: {count} (sha256 {checksum}...)", "  {kind}: {count} (sha256 {checksum}...)";
+    ForceDeleteHint => "Re-run with --force to delete it anyway.", "Vuelva a ejecutar con --force para eliminarlo de todos modos.";
+    ConfirmLargeHint => "Re-run with --confirm-large to proceed.", "Vuelva a ejecutar con --confirm-large para continuar.";
+
+    // Invalid input reported by the service
+    InputSkuEmpty => "SKU cannot be empty", "El SKU no puede estar vacío";
+    InputNameEmpty => "Name cannot be empty", "El nombre no puede estar vacío";
+    InputCustomerEmpty => "Customer code cannot be empty", "El código de cliente no puede estar vacío";
+    InputSupplierEmpty => "Supplier cannot be empty", "El proveedor no puede estar vacío";
+    InputQuantityPositive => "Quantity must be positive", "La cantidad debe ser positiva";
+    InputControlCharacter => "{field} contains a control character (U+{code}) at position {position}",
+        "{field} contiene un carácter de control (U+{code}) en la posición {position}";
+    InputTooLong => "{field} is too long ({length} characters, maximum {max})",
+        "{field} es demasiado largo ({length} caracteres, máximo {max})";
+    InputSingleProductFields => "Name, barcode, and components can only be changed one product at a time",
+        "El nombre, el código de barras y los componentes solo se pueden cambiar de producto en producto";
+    InputComponentQuantity => "Component '{sku}' quantity must be positive", "La cantidad del componente '{sku}' debe ser positiva";
+    InputSelfComponent => "'{sku}' cannot be a component of itself", "'{sku}' no puede ser componente de sí mismo";
+    InputComponentRepeated => "Component '{sku}' is listed more than once", "El componente '{sku}' aparece más de una vez";
+    InputSelfLink => "'{sku}' cannot be linked to itself", "'{sku}' no se puede vincular consigo mismo";
+    InputNoComponents => "'{sku}' has no components; set them with update-product --components",
+        "'{sku}' no tiene componentes; defínalos con update-product --components";
+    InputNoUnitWeight => "Product '{sku}' has no unit weight; set one with update-product --unit-weight",
+        "El producto '{sku}' no tiene peso unitario; defínalo con update-product --unit-weight";
+    InputWeightBelowUnit => "Weight {gross} is less than one unit of '{sku}' ({unit_weight} each, tare {tare})",
+        "El peso {gross} es menor que una unidad de '{sku}' ({unit_weight} cada una, tara {tare})";
+    InputRedactBrokenChain => "the transaction hash chain is broken, so redacting would hide it; run verify-ledger first",
+        "la cadena de hashes de los movimientos está rota y redactar la ocultaría; ejecute verify-ledger primero";
+    InputRedactChainedId => "transaction {id} is on the hash chain, so a new ID would break the chain; run verify-ledger first",
+        "el movimiento {id} está en la cadena de hashes y un ID nuevo la rompería; ejecute verify-ledger primero";
+    InputNotRemovalType => "'{type}' is not a removal type; use damage, write-off, or adjustment",
+        "'{type}' no es un tipo de salida; use damage, write-off o adjustment";
+    InputQuantityOverflow => "Quantity of '{sku}' would overflow", "La cantidad de '{sku}' se desbordaría";
+    InputQuarantineOverflow => "Quarantine of '{sku}' would overflow", "La cuarentena de '{sku}' se desbordaría";
+    InputOnOrderOverflow => "Quantity on order of '{sku}' would overflow", "La cantidad pedida de '{sku}' se desbordaría";
+    InputQuarantineMove => "Quarantine moves are recorded with quarantine and release-quarantine",
+        "Los movimientos de cuarentena se registran con quarantine y release-quarantine";
+    InputConditionMove => "Condition changes are recorded with change-condition",
+        "Los cambios de estado se registran con change-condition";
+    InputQuarantineNotImported => "Quarantine moves cannot be imported", "Los movimientos de cuarentena no se pueden importar";
+    InputReleaseTooMany => "Cannot release {quantity} units of '{sku}': only {quarantined} in quarantine",
+        "No se pueden liberar {quantity} unidades de '{sku}': solo hay {quarantined} en cuarentena";
+    InputCancelTooMany => "Cannot cancel {quantity} units of '{sku}': only {on_order} on order",
+        "No se pueden cancelar {quantity} unidades de '{sku}': solo hay {on_order} pedidas";
+    InputSameCondition => "Stock is already {condition}", "El stock ya está en estado {condition}";
+    InputBackorderLoss => "Only removals can be backordered, not losses",
+        "Solo las salidas pueden quedar como pedido pendiente, no las pérdidas";
+    InputShipLoss => "Only removals can be shipped to a customer, not losses",
+        "Solo las salidas se pueden enviar a un cliente, no las pérdidas";
+    InputReceiptInProgress => "Receipt {reference} is already in progress; commit or cancel it first",
+        "La recepción {reference} ya está en curso; confírmela o cancélela primero";
+    InputNoReference => "(no reference)", "(sin referencia)";
+    InputNoReceipt => "No receipt in progress; start one with receive start",
+        "No hay ninguna recepción en curso; inicie una con receive start";
+    InputNothingReceived => "Nothing has been received yet; add lines with receive add",
+        "Todavía no se ha recibido nada; añada líneas con receive add";
+    InputTimestampFuture => "Timestamp {timestamp} is in the future", "La fecha {timestamp} está en el futuro";
+    InputTimestampBeforeAdded => "Timestamp {timestamp} is before '{sku}' was added ({added})",
+        "La fecha {timestamp} es anterior al alta de '{sku}' ({added})";
+    InputTimestampOutOfOrder => "Timestamp {timestamp} is earlier than the previous entry for '{sku}' ({previous}); list movements oldest first",
+        "La fecha {timestamp} es anterior a la entrada previa de '{sku}' ({previous}); ordene los movimientos del más antiguo al más reciente";
+    InputLeavesTooLittle => "Leaves too little stock of '{sku}' for the {quantity} units removed at {timestamp}",
+        "Deja muy poco stock de '{sku}' para las {quantity} unidades retiradas el {timestamp}";
+    InputSkuRepeated => "SKU '{sku}' is listed more than once (first at row {first})",
+        "El SKU '{sku}' aparece más de una vez (primero en la fila {first})";
+    InputCutoverFuture => "Cutover date {date} is in the future", "La fecha de corte {date} está en el futuro";
+    InputAlreadyListed => "'{sku}' is already listed on line {first}", "'{sku}' ya aparece en la línea {first}";
+    InputAlreadyPriced => "'{sku}' is already priced from {date} on line {first}",
+        "'{sku}' ya tiene precio desde el {date} en la línea {first}";
+    InputServiceLevel => "Service level {level} must be above 0.5 and below 1 (e.g. 0.95)",
+        "El nivel de servicio {level} debe ser mayor que 0.5 y menor que 1 (p. ej. 0.95)";
+    InputDemandWindow => "The demand window must be at least one day", "La ventana de demanda debe ser de al menos un día";
+    InputNotLowStock => "Product '{sku}' is above its reorder point; there is no low-stock alert to acknowledge",
+        "El producto '{sku}' está por encima de su punto de reorden; no hay ninguna alerta de stock bajo que confirmar";
+    InputAmbiguousId => "Transaction ID prefix '{id}' is ambiguous; give more characters",
+        "El prefijo de ID de movimiento '{id}' es ambiguo; indique más caracteres";
+
+    // Argument parsing
+    UnknownCommand => "Unknown command: '{command}'. Use 'help' to see available commands.",
+        "Comando desconocido: '{command}'. Use 'help' para ver los comandos disponibles.";
+    UnknownGroupCommand => "Unknown {group} command: '{command}'.", "Comando de {group} desconocido: '{command}'.";
+    UnexpectedArgument => "Unexpected argument: '{argument}'", "Argumento inesperado: '{argument}'";
+    UsagePrefix => "Usage: ", "Uso: ";
+    ExamplePrefix => "Example: ", "Ejemplo: ";
+
+    // Help text
+    HelpTitle => "Stock Control System - Inventory Management CLI", "Sistema de Control de Stock - CLI de gestión de inventario";
+    HelpUsage => "USAGE:", "USO:";
+    HelpOptionsNote => "Options may appear anywhere after the command name, either as
+\"--option value\" or \"--option=value\". Use \"--\" to treat all following
+arguments as positional (e.g. a SKU starting with \"--\").",
+        "Las opciones pueden aparecer en cualquier posición después del comando, como
+\"--opcion valor\" o \"--opcion=valor\". Use \"--\" para tratar todos los argumentos
+siguientes como posicionales (p. ej. un SKU que empieza por \"--\").";
+    HelpGlobalOptions => "GLOBAL OPTIONS:
+    -q, --quiet    Print only essential values for use in scripts: the SKU
+                   for product changes, the new quantity for stock movements,
+                   one SKU per line for lists, and tab-separated fields for
+                   view-product and history (ID, time, type, quantity)
+    -v, --verbose  Log diagnostics to stderr; repeat (-vv) for more detail
+    --strict-load  Check every stored product and transaction (non-empty SKU,
+                   unique IDs, known fields, plausible timestamps) and refuse
+                   to start if any fails",
+        "OPCIONES GLOBALES:
+    -q, --quiet    Muestra solo los valores esenciales para scripts: el SKU
+                   en cambios de producto, la nueva cantidad en movimientos,
+                   un SKU por línea en listados y campos separados por
+                   tabuladores en view-product e history (ID, hora, tipo, cantidad)
+    -v, --verbose  Registra diagnósticos en stderr; repita (-vv) para más detalle
+    --strict-load  Comprueba cada producto y movimiento guardado (SKU no vacío,
+                   IDs únicos, campos conocidos, fechas plausibles) y no arranca
+                   si alguno falla";
+    GroupProduct => "PRODUCT COMMANDS", "COMANDOS DE PRODUCTO";
+    GroupStock => "STOCK COMMANDS", "COMANDOS DE STOCK";
+    GroupReport => "REPORT COMMANDS", "COMANDOS DE INFORMES";
+    GroupReceive => "RECEIVING COMMANDS", "COMANDOS DE RECEPCIÓN";
+    GroupTrash => "TRASH COMMANDS", "COMANDOS DE PAPELERA";
+    GroupData => "DATA COMMANDS", "COMANDOS DE DATOS";
+    GroupView => "SAVED VIEW COMMANDS", "COMANDOS DE VISTAS GUARDADAS";
+    GroupTemplate => "PRODUCT TEMPLATE COMMANDS", "COMANDOS DE PLANTILLAS DE PRODUCTO";
+    GroupBackorder => "BACKORDER COMMANDS", "COMANDOS DE PEDIDOS PENDIENTES";
+    GroupCustomer => "CUSTOMER COMMANDS", "COMANDOS DE CLIENTES";
+    GroupRetention => "RETENTION COMMANDS", "COMANDOS DE CONSERVACIÓN DE DATOS";
+    GroupScript => "SCRIPT COMMANDS", "COMANDOS DE SCRIPTS";
+    GroupFeed => "FEED COMMANDS", "COMANDOS DE FEEDS";
+    GroupEvents => "EVENT COMMANDS", "COMANDOS DE EVENTOS";
+    GroupStocktake => "STOCKTAKE COMMANDS", "COMANDOS DE INVENTARIO FÍSICO";
+    GroupSupplier => "SUPPLIER COMMANDS", "COMANDOS DE PROVEEDORES";
+    HelpAliases => "Aliases: ", "Alias: ";
+    HelpOther => "OTHER COMMANDS:
+    help [command] [--format json]
+        Show this help message, or detailed help for one command
+        With --format json, describe commands, arguments, and options for tools",
+        "OTROS COMANDOS:
+    help [comando] [--format json]
+        Muestra esta ayuda, o la ayuda detallada de un comando
+        Con --format json, describe comandos, argumentos y opciones para herramientas";
+    HelpPlugins => "PLUGINS (stock-control-<name> executables on PATH):", "COMPLEMENTOS (ejecutables stock-control-<nombre> en el PATH):";
+    HelpTemplates => "TEMPLATES:
+    list-products, view-product, low-stock, and history accept --template <file>
+    to render their data through a Handlebars-style template:
+        {{name}}  {{product.sku}}  {{#each products}}...{{/each}}
+        {{#if low_stock}}...{{else}}...{{/if}}  {{#unless supplier}}...{{/unless}}
+    Products carry their stored fields plus low_stock and shortfall; lists
+    also provide count and generated_at, and grouped low-stock provides groups
+    (key, products, count, shortfall). api_version gives the version of these
+    shapes, which only gain optional fields until the version changes.",
+        "PLANTILLAS:
+    list-products, view-product, low-stock e history aceptan --template <archivo>
+    para generar su salida con una plantilla de estilo Handlebars:
+        {{name}}  {{product.sku}}  {{#each products}}...{{/each}}
+        {{#if low_stock}}...{{else}}...{{/if}}  {{#unless supplier}}...{{/unless}}
+    Los productos incluyen sus campos guardados más low_stock y shortfall; los
+    listados incluyen además count y generated_at, y low-stock agrupado incluye
+    groups (key, products, count, shortfall). api_version indica la versión de
+    estos formatos, que solo ganan campos opcionales hasta que cambia la versión.";
+    HelpConfiguration => "CONFIGURATION:
+    Optional settings are read from config.json in the data directory:
+        large_movement_threshold   Global removal limit, e.g. \"100\" or \"25%\"
+        aliases                    Custom command shortcuts, e.g. { \"rcv\": \"add-stock\" }
+                                   An alias may include arguments (\"lows\": \"low-stock --group-by supplier\")
+                                   and cannot replace a built-in command name
+        operator                   Name recorded on stock movements (default: login user)
+        locale                     Output language, \"en\" or \"es\" (default: from LANG)
+        log                        Rotating log file, e.g. { \"enabled\": true, \"level\": \"debug\" }
+                                   Also accepts max_bytes (default 1048576) and max_files (default 3)
+        limits                     Maximum field lengths in characters (0 = unlimited), e.g.
+                                   { \"max_name\": 80 }; also max_sku, max_description, max_label
+                                   (category, supplier, location), and max_notes
+        deletion                   delete-product safeguards, e.g. { \"recent_activity_days\": 30, \"archive\": true }
+                                   (0 days disables the recent-activity check); trash_retention_days
+                                   (default 30, 0 = keep forever) sets how long deleted products can be restored
+        views                      Named list-products filters, written by view save and run with view run
+        product_templates          Named defaults for add-product --from-template, written by template save
+        import_profiles            Named mappings for import-transactions --profile, e.g. { \"acme\": { \"columns\":
+                                   { \"sku\": \"Item No\", \"quantity\": \"Qty\" }, \"defaults\": { \"type\": \"addition\" },
+                                   \"transforms\": { \"sku\": [\"uppercase\", { \"prefix\": \"ACME-\" }] } } }; fields are
+                                   sku, timestamp, type, quantity, and notes, and transforms are uppercase,
+                                   lowercase, prefix, and strip_prefix
+        backorders                 { \"auto_allocate\": true } fills backorders from stock as it is added
+        mqtt                       Publish stock levels and events, e.g. { \"broker\": \"localhost:1883\" }; also
+                                   topic_prefix (default inventory) and client_id (needs a build with --features mqtt)
+        ledger                     { \"hash_chain\": true } links each new transaction to the previous one by
+                                   SHA-256 so verify-ledger can detect tampering and truncation
+        retention                  Years to keep personal data for retention apply, e.g.
+                                   { \"notes_years\": 3, \"customer_years\": 6 }
+        shop                       Push stock levels to a store, e.g. { \"platform\": \"shopify\", \"url\": ...,
+                                   \"access_token\": ..., \"location_id\": ... } or woocommerce with consumer_key and
+                                   consumer_secret; mapping names the SKU,STORE_ID file (needs --features shop)
+        feeds                      Named feeds for feed push, e.g. { \"bigretail\": { \"format\": \"x12\",
+                                   \"destination\": \"sftp://edi@host/in\", \"interval_minutes\": 60, \"sender\": ...,
+                                   \"receiver\": ... } }; destinations are sftp://, s3://, or a directory, and
+                                   file_name may use {name}, {date}, {timestamp}, and {ext}
+        clock                      Clock drift guards, e.g. { \"tolerance_seconds\": 300, \"action\": \"reject\" }:
+                                   a product with a transaction later than now plus the tolerance, or
+                                   imported movements dated before it was added, warn (default) or are refused
+        stocktake                  Count variances reconcile --apply applies as counted, e.g.
+                                   { \"tolerance_units\": 2, \"tolerance_percent\": 5 }; larger ones need --recount or
+                                   --confirm (default: all applied)
+        storage                    { \"autosave\": { \"debounce_ms\": 500 } } saves at most every 500 ms, holding
+                                   later changes until the next one or exit; \"manual\" saves only on exit
+                                   (default \"immediate\", after every change)
+        quotas                     Soft size limits reported by stats and warned about when a change reaches
+                                   warn_percent (default 80) of one, e.g. { \"max_products\": 5000,
+                                   \"max_transactions\": 200000, \"max_data_bytes\": 50000000 }
+        inbox                      Shipping notices for receive inbox, e.g. { \"maildir\": \"/var/mail/asn\",
+                                   \"suppliers\": { \"orders@acme.example\": \"Acme\", \"@bolts.example\": \"Bolt Co\" } }
+                                   (needs --features email)",
+        "CONFIGURACIÓN:
+    Los ajustes opcionales se leen de config.json en el directorio de datos:
+        large_movement_threshold   Límite global de salida, p. ej. \"100\" o \"25%\"
+        aliases                    Atajos de comandos, p. ej. { \"rcv\": \"add-stock\" }
+                                   Un alias puede incluir argumentos (\"lows\": \"low-stock --group-by supplier\")
+                                   y no puede sustituir un comando integrado
+        operator                   Nombre registrado en los movimientos (por defecto: usuario)
+        locale                     Idioma de salida, \"en\" o \"es\" (por defecto: según LANG)
+        log                        Archivo de registro rotativo, p. ej. { \"enabled\": true, \"level\": \"debug\" }
+                                   También admite max_bytes (por defecto 1048576) y max_files (por defecto 3)
+        limits                     Longitud máxima de los campos en caracteres (0 = sin límite), p. ej.
+                                   { \"max_name\": 80 }; también max_sku, max_description, max_label
+                                   (categoría, proveedor, ubicación) y max_notes
+        deletion                   Protecciones de delete-product, p. ej. { \"recent_activity_days\": 30, \"archive\": true }
+                                   (0 días desactiva la comprobación de actividad reciente); trash_retention_days
+                                   (por defecto 30, 0 = para siempre) fija cuánto tiempo se pueden restaurar los productos eliminados
+        views                      Filtros de list-products con nombre, guardados con view save y usados con view run
+        product_templates          Valores por defecto con nombre para add-product --from-template, guardados con template save
+        import_profiles            Correspondencias con nombre para import-transactions --profile, p. ej. { \"acme\":
+                                   { \"columns\": { \"sku\": \"Item No\", \"quantity\": \"Qty\" }, \"defaults\": { \"type\": \"addition\" },
+                                   \"transforms\": { \"sku\": [\"uppercase\", { \"prefix\": \"ACME-\" }] } } }; los campos son
+                                   sku, timestamp, type, quantity y notes, y las transformaciones uppercase,
+                                   lowercase, prefix y strip_prefix
+        backorders                 { \"auto_allocate\": true } sirve los pedidos pendientes al añadir stock
+        mqtt                       Publica niveles de stock y eventos, p. ej. { \"broker\": \"localhost:1883\" }; también
+                                   topic_prefix (por defecto inventory) y client_id (requiere compilar con --features mqtt)
+        ledger                     { \"hash_chain\": true } enlaza cada movimiento nuevo con el anterior mediante
+                                   SHA-256 para que verify-ledger detecte manipulaciones y truncamientos
+        retention                  Años que se conservan los datos personales para retention apply, p. ej.
+                                   { \"notes_years\": 3, \"customer_years\": 6 }
+        shop                       Envía niveles de stock a una tienda, p. ej. { \"platform\": \"shopify\", \"url\": ...,
+                                   \"access_token\": ..., \"location_id\": ... } o woocommerce con consumer_key y
+                                   consumer_secret; mapping indica el archivo SKU,ID_TIENDA (requiere --features shop)
+        feeds                      Feeds con nombre para feed push, p. ej. { \"bigretail\": { \"format\": \"x12\",
+                                   \"destination\": \"sftp://edi@host/in\", \"interval_minutes\": 60, \"sender\": ...,
+                                   \"receiver\": ... } }; los destinos son sftp://, s3:// o un directorio, y
+                                   file_name admite {name}, {date}, {timestamp} y {ext}
+        clock                      Protección ante desfases de reloj, p. ej. { \"tolerance_seconds\": 300, \"action\": \"reject\" }:
+                                   un producto con un movimiento posterior a ahora más la tolerancia, o movimientos
+                                   importados con fecha anterior a su alta, avisan (por defecto) o se rechazan
+        stocktake                  Diferencias de recuento que reconcile --apply aplica tal cual, p. ej.
+                                   { \"tolerance_units\": 2, \"tolerance_percent\": 5 }; las mayores necesitan --recount
+                                   o --confirm (por defecto: se aplican todas)
+        storage                    { \"autosave\": { \"debounce_ms\": 500 } } guarda como mucho cada 500 ms y deja
+                                   los cambios siguientes para el próximo o la salida; \"manual\" solo guarda al salir
+                                   (por defecto \"immediate\", tras cada cambio)
+        quotas                     Límites de tamaño orientativos que muestra stats y que avisan cuando un cambio
+                                   alcanza warn_percent (por defecto 80) de uno, p. ej. { \"max_products\": 5000,
+                                   \"max_transactions\": 200000, \"max_data_bytes\": 50000000 }
+        inbox                      Avisos de envío para receive inbox, p. ej. { \"maildir\": \"/var/mail/asn\",
+                                   \"suppliers\": { \"orders@acme.example\": \"Acme\", \"@bolts.example\": \"Bolt Co\" } }
+                                   (requiere --features email)";
+    HelpCommandUsage => "USAGE:", "USO:";
+    HelpCommandAliases => "ALIASES:", "ALIAS:";
+    HelpCommandOptions => "OPTIONS:", "OPCIONES:";
+    HelpCommandExamples => "EXAMPLES:", "EJEMPLOS:";
+}
+
+/// Spanish command summaries and notes, keyed by command name
+const COMMAND_DESCRIPTIONS_ES: &[(&str, &[&str])] = &[
+    ("add-product", &[
+        "Añade un producto nuevo al inventario",
+        "Con --interactive, pide cada campo con validación y valores por defecto",
+        "--from-template completa lo que no se indique con una plantilla guardada con template save; la cantidad es 0 por defecto",
+    ]),
+    ("bulk-update", &[
+        "Aplica los mismos cambios a todos los productos que cumplen un filtro",
+        "Sin --yes, lista los productos que cambiarían y no cambia nada",
+        "Filtros: sku (prefijo o patrón), category, supplier, condition y below-reorder=true",
+        "Campos: description, reorder-point, category, supplier, location, large-threshold, unit-cost,",
+        "tare-weight, unit-weight y lead-time; un valor vacío borra todos salvo description y reorder-point",
+    ]),
+    ("update-product", &[
+        "Actualiza los datos de un producto existente",
+        "Un valor vacío borra category, supplier, location, barcode, large-threshold, components, unit-cost, un peso o lead-time",
+        "--barcode fija un código que el quiosco acepta en lugar del SKU",
+        "--components convierte el producto en un kit montado con los productos indicados",
+        "--unit-cost fija el coste de una unidad (p. ej. 12.50), usado para valorar el stock",
+        "--tare-weight y --unit-weight (p. ej. 120g, 1.5kg) permiten a weigh contar el producto por peso",
+        "--lead-time fija cuántos días tarda en llegar un pedido, usado por suggest-reorder-points",
+    ]),
+    ("check-buildable", &[
+        "Indica cuántos kits se pueden montar con el stock actual de sus componentes",
+        "El componente que se agota primero se marca como limitante",
+    ]),
+    ("view-product", &["Muestra los datos de un producto"]),
+    ("list-products", &[
+        "Lista todos los productos del inventario, por orden de SKU",
+        "--sku-prefix muestra solo los SKU que empiezan por el patrón, que admite los comodines * y ?",
+        "--category y --supplier muestran solo los productos de esa categoría o proveedor",
+        "--condition muestra solo los productos con stock en ese estado: new, refurbished o damaged",
+        "--below-reorder muestra solo los productos en su punto de pedido o por debajo",
+        "--sort ordena la lista por sku, name o quantity",
+        "--columns muestra una tabla con las columnas separadas por comas: sku, name, quantity, reorder-point,",
+        "category, supplier, location, unit-cost y value (el stock en existencia a coste FIFO)",
+        "--totals añade una fila con el total de cantidad, punto de pedido y valor; sola muestra sku, name y quantity",
+        "--group-by lista el número de productos, las unidades y el valor por categoría, proveedor o ubicación",
+    ]),
+    ("migrate-opening-balances", &[
+        "Crea productos con su stock del sistema anterior como movimientos de saldo inicial en la fecha de corte",
+        "El archivo contiene líneas SKU,CANT,PUNTO_REORDEN,NOMBRE; todos los SKU deben ser nuevos",
+        "No se crea nada si alguna línea no es válida; después se muestra una conciliación de totales esperados y creados",
+        "--merge admite SKU existentes: name y reorder-point con keep, overwrite o fill-if-empty, y quantity con keep, add o set",
+        "Por defecto se conserva todo; una actualización de catálogo usa name=overwrite y una sincronización de stock quantity=set",
+    ]),
+    ("delete-product", &[
+        "Elimina un producto y todos sus movimientos",
+        "Se rechaza si el producto tiene stock o movimientos recientes, salvo con --force",
+        "Los productos eliminados y su historial van a la papelera y pueden recuperarse con trash-restore",
+    ]),
+    ("link-product", &[
+        "Vincula un producto con otro: substitute-for si puede venderse en su lugar, accessory-of si se vende junto a él",
+        "low-stock y view-product sugieren los sustitutos con stock de los productos agotados",
+        "--remove quita el vínculo",
+    ]),
+    ("add-stock", &[
+        "Añade stock a un producto",
+        "Varias --notes se combinan",
+        "--condition registra stock reacondicionado o dañado, p. ej. devoluciones (por defecto: new)",
+        "--unit-cost registra el coste de cada unidad en esta entrada (por defecto: el coste unitario del producto)",
+        "--from-stdin lee líneas SKU,CANT[,NOTAS]; no se aplica nada si alguna línea no es válida",
+    ]),
+    ("remove-stock", &[
+        "Retira stock de un producto",
+        "--type registra una pérdida en lugar de una salida: damage, write-off o adjustment (por defecto: removal)",
+        "--reason etiqueta la salida para el informe de consumo: sale, internal-use, sample o damage",
+        "Las salidas por encima del umbral de movimiento grande requieren --confirm-large",
+        "--condition retira stock de ese estado; si no, se retira primero el stock nuevo",
+        "--backorder retira lo disponible y deja pendiente el resto en lugar de fallar",
+        "--customer registra a qué cliente se envió la salida",
+        "--from-stdin lee líneas SKU,CANT[,NOTAS]; no se aplica nada si alguna línea no es válida",
+    ]),
+    ("quarantine", &[
+        "Retiene stock disponible, p. ej. mercancía pendiente de control de calidad",
+        "Las unidades en cuarentena no se pueden retirar ni cuentan para el punto de reorden",
+    ]),
+    ("release-quarantine", &[
+        "Vuelve a poner disponible el stock en cuarentena",
+        "Para desechar unidades que no pasaron el control, libéralas y registra un write-off",
+    ]),
+    ("mark-ordered", &[
+        "Registra unidades pedidas a un proveedor que aún no se han recibido",
+        "Las unidades pedidas se descuentan de lo que falta según low-stock; al añadir stock nuevo o confirmar una recepción se quitan del pedido",
+        "--cancel quita unidades del pedido",
+    ]),
+    ("change-condition", &[
+        "Mueve stock disponible de un estado a otro: new, refurbished o damaged",
+        "--from es new por defecto; la cantidad total no cambia",
+    ]),
+    ("weigh", &[
+        "Añade stock contado por peso en una báscula, o lo retira con --out",
+        "Se descuenta la tara del producto y el resto se divide por su peso unitario, redondeando a unidades enteras",
+        "La lectura queda registrada en las notas del movimiento",
+        "Leer la báscula en --port (por defecto /dev/ttyUSB0, 9600 baudios) requiere compilar con --features scale",
+        "--weight indica la lectura bruta en su lugar, p. ej. 12.4kg",
+    ]),
+    ("kiosk", &[
+        "Modo bloqueado para un terminal compartido: solo entrada, salida y consulta de stock por escaneo",
+        "unlock <código> permite los demás comandos hasta 'lock'; el código se define en kiosk.unlock_code de config.json",
+        "Un código de barras compartido por varios productos pregunta cuál se quería y recuerda la respuesta durante la sesión",
+    ]),
+    ("history", &[
+        "Muestra el historial de movimientos de un producto",
+        "Formato de fecha y hora: AAAA-MM-DDTHH:MM:SS",
+        "--include-archived también busca en los movimientos movidos por archive-transactions",
+        "--customer muestra solo las salidas enviadas a ese cliente",
+    ]),
+    ("receipt", &[
+        "Imprime un albarán de entrada o de salida de un movimiento de stock",
+        "El ID del movimiento puede abreviarse a los 8 caracteres que muestra history",
+        "Los comprobantes en PDF deben guardarse en un archivo con --output",
+        "--format zpl o escpos genera trabajos para impresoras de etiquetas Zebra o de tickets térmicas",
+        "--printer envía el comprobante directamente a tcp://host[:puerto] (puerto 9100 por defecto) o a usb://<dispositivo>",
+    ]),
+    ("import-transactions", &[
+        "Importa movimientos pasados con su fecha original, p. ej. al migrar desde una hoja de cálculo",
+        "El archivo contiene líneas SKU,FECHA,TIPO,CANT[,NOTAS]; TIPO es addition, removal, damage, write-off o adjustment",
+        "FECHA es AAAA-MM-DD, AAAA-MM-DDTHH:MM:SS (UTC) o RFC 3339; los movimientos de cada producto van del más antiguo al más reciente",
+        "La cantidad se recalcula con el historial completo; no se importa nada si alguna línea no es válida o el stock quedaría negativo",
+        "--profile lee el CSV de otro sistema con una línea de cabecera, o un array .json de objetos, mediante una correspondencia de import_profiles en config.json",
+        "Los archivos de más de 500 filas se importan por bloques; si uno falla, --resume <id> continúa tras el último bloque guardado",
+    ]),
+    ("diff", &[
+        "Muestra los productos añadidos, eliminados y con cambios de cantidad entre dos estados del inventario",
+        "Una instantánea es un directorio de datos, una copia de seguridad o un archivo products.json",
+        "Con una sola instantánea compara con el inventario actual; --since reconstruye el estado en esa fecha a partir de los movimientos",
+        "--verify comprueba antes que cada instantánea es una exportación firmada con la clave privada correspondiente (export --sign)",
+    ]),
+    ("low-stock", &[
+        "Lista los productos con stock igual o inferior al punto de reorden",
+        "La salida agrupada incluye subtotales de unidades que faltan por grupo",
+        "Los productos confirmados con ack no se muestran hasta que termina su aplazamiento",
+    ]),
+    ("check-rules", &[
+        "Lista las reglas de alerta de config.json y los productos que cumple cada una ahora",
+        "Las reglas también se comprueban tras cada comando, que avisa cuando una regla empieza a cumplirse para un producto",
+    ]),
+    ("ack", &[
+        "Confirma la alerta de stock bajo de un producto para que low-stock no lo muestre, p. ej. mientras llega un pedido",
+        "La alerta vuelve a mostrarse en la fecha de --until, cuando el stock baja del nivel actual o tras una reposición",
+        "--clear retira la confirmación",
+    ]),
+    ("stats", &["Muestra los totales de productos, unidades y valor, en total y por categoría, y cuántos productos tienen stock bajo"]),
+    ("history-compare", &[
+        "Compara los movimientos de hasta 10 productos lado a lado, p. ej. productos que se sustituyen entre sí",
+        "Muestra las unidades recibidas, retiradas y perdidas, el cambio neto, el número de movimientos y las unidades en existencia",
+        "--merge lista en cambio sus movimientos juntos en orden cronológico",
+        "Formato de fecha y hora: AAAA-MM-DDTHH:MM:SS",
+    ]),
+    ("value-history", &[
+        "Muestra las unidades en existencia y su valor al inicio de cada periodo y al final",
+        "El stock se reconstruye a partir del historial de movimientos y se valora al coste de cada entrada, saliendo primero la más antigua",
+        "--interval es month por defecto; el periodo va desde el inicio de este año hasta ahora si no se indica",
+    ]),
+    ("suggest-reorder-points", &[
+        "Sugiere puntos de reorden que cubren la demanda durante el plazo de entrega de cada producto, más un stock de seguridad",
+        "La demanda son las unidades retiradas por día en los últimos --days (90 por defecto); el stock de seguridad crece con su variabilidad",
+        "--service-level es la probabilidad de no quedarse sin stock antes de que llegue un pedido (0.95 por defecto)",
+        "--lead-time se usa para los productos sin plazo fijado con update-product --lead-time (7 días por defecto)",
+        "--apply pregunta por cada cambio antes de aplicarlo",
+    ]),
+    ("forecast", &[
+        "Prevé cuándo se agota cada producto, primero el más próximo, a partir de las unidades retiradas por día en los últimos --days",
+        "No se cuenta el stock comprometido en pedidos pendientes; los productos que no se agotan en --horizon días (90 por defecto) van al final",
+        "--seasonality ajusta la demanda por día de la semana o mes del año; por defecto se usa forecast.seasonality de config.json",
+        "--days es 90 por defecto, o 365 con estacionalidad mensual",
+    ]),
+    ("kpis", &[
+        "Muestra en gráfico los totales diarios registrados por snapshot-kpis, del más antiguo al más reciente",
+        "Cada día muestra las unidades disponibles, el valor del stock con una barra a escala, los productos con stock bajo y los movimientos",
+        "--start y --end limitan los días mostrados, ambos incluidos",
+    ]),
+    ("cogs", &[
+        "Muestra el coste de las mercancías retiradas en un periodo, por producto",
+        "Las salidas toman primero las unidades de las entradas más antiguas, al coste con que entró cada una",
+        "--start y --end limitan el periodo a esas fechas, ambas incluidas; las pérdidas quedan para shrinkage",
+    ]),
+    ("ledger-entries", &[
+        "Escribe las entradas y salidas valoradas al coste como asientos de partida doble de beancount o ledger-cli",
+        "Las entradas cargan Assets:Inventory; las salidas, daños, bajas y ajustes lo abonan contra una cuenta de gastos",
+        "Las salidas se valoran al coste de las entradas más antiguas; los movimientos sin coste conocido se omiten",
+        "--format es beancount por defecto y --currency es USD; --start y --end limitan el periodo, ambos incluidos",
+    ]),
+    ("shipments", &[
+        "Resume las unidades enviadas a cada cliente por producto",
+        "Solo cuentan las salidas registradas con remove-stock --customer",
+        "--start y --end limitan el periodo a esas fechas, ambas incluidas",
+    ]),
+    ("add-customer", &[
+        "Añade un cliente al que se pueden enviar salidas",
+        "El código identifica al cliente en remove-stock --customer, history --customer y shipments",
+    ]),
+    ("list-customers", &["Lista los clientes por código"]),
+    ("backorders", &[
+        "Lista los pedidos pendientes, del más antiguo al más reciente",
+        "remove-stock --backorder registra un pedido pendiente cuando falta stock",
+    ]),
+    ("fill-backorders", &[
+        "Retira el stock disponible para servir los pedidos pendientes de un producto, del más antiguo al más reciente",
+        "add-stock avisa de los pedidos pendientes; fije backorders.auto_allocate en config.json para servirlos automáticamente",
+    ]),
+    ("import-prices", &[
+        "Guarda la lista de precios de un proveedor, conservando los precios anteriores",
+        "El archivo contiene líneas SKU,PRECIO[,FECHA_EFECTIVA]; FECHA_EFECTIVA es AAAA-MM-DD y por defecto hoy",
+        "Un precio para una fecha que el proveedor ya tenía reemplaza al anterior; no se guarda nada si alguna línea no es válida",
+    ]),
+    ("compare-prices", &[
+        "Muestra el precio actual de cada proveedor para un producto, del más barato al más caro",
+        "--date compara los precios vigentes ese día en lugar de hoy",
+        "low-stock indica el proveedor más barato de cada producto con precios",
+    ]),
+    ("shrinkage", &[
+        "Resume las pérdidas registradas como damage, write-off o adjustment por producto y tipo",
+        "--start y --end limitan el periodo a esas fechas, ambas incluidas",
+        "--condition cuenta solo el stock en ese estado",
+    ]),
+    ("consumption", &[
+        "Resume las unidades retiradas por motivo, de todos los productos o de uno",
+        "Las salidas registradas sin --reason aparecen como sin especificar",
+        "--start y --end limitan el periodo a esas fechas, ambas incluidas",
+        "--condition cuenta solo el stock en ese estado",
+    ]),
+    ("receive-start", &[
+        "Empieza a registrar una entrega; el stock solo cambia con receive commit",
+        "--expected lee las líneas SKU,CANT pedidas, para revisar las diferencias",
+    ]),
+    ("receive-add", &[
+        "Registra unidades contadas en la entrega en curso",
+        "Un producto puede añadirse más de una vez, p. ej. una vez por caja",
+    ]),
+    ("receive-review", &["Muestra la entrega registrada y sus diferencias con las cantidades esperadas"]),
+    ("receive-commit", &[
+        "Añade al stock la entrega registrada, una entrada por línea",
+        "Las líneas sin notas se anotan con la referencia de la recepción",
+    ]),
+    ("receive-cancel", &["Descarta la entrega registrada sin cambiar el stock"]),
+    ("receive-inbox", &[
+        "Registra los avisos de envío que los proveedores de inbox.suppliers en config.json han enviado a un maildir",
+        "Las líneas SKU,QTY de un aviso salen de su adjunto CSV o del cuerpo, y su referencia del final del asunto",
+        "Lista los avisos registrados; --start empieza a recibir uno con sus líneas como cantidades esperadas",
+        "Leer el correo requiere una compilación con --features email",
+    ]),
+    ("trash-list", &[
+        "Lista los productos eliminados que aún pueden restaurarse",
+        "Los productos se purgan cuando llevan en la papelera más tiempo que el periodo de retención",
+    ]),
+    ("trash-restore", &[
+        "Restaura desde la papelera un producto eliminado y sus movimientos",
+        "Si el SKU se eliminó más de una vez, se restaura la eliminación más reciente",
+    ]),
+    ("export", &[
+        "Escribe los productos y movimientos en un nuevo directorio de datos",
+        "--anonymize sustituye notas, operadores, clientes y proveedores por identificadores opacos, conservando SKU y cantidades",
+        "--sign añade un manifiesto SHA-256 y su firma Ed25519; cree una clave con openssl genpkey -algorithm ed25519",
+        "--format cdc escribe el cambio de existencias de cada movimiento en changes.ndjson como un registro al estilo de Debezium con imágenes antes y después",
+        "--since omite los cambios hasta ese número de secuencia; la salida silenciosa es el último escrito, para el siguiente --since",
+    ]),
+    ("heal-ids", &[
+        "Da un nuevo ID a cada producto y movimiento cuyo ID ya tiene un registro anterior",
+        "El primer registro con un ID lo conserva; cada cambio se anota en el registro de auditoría",
+        "Ejecútalo antes de compact, que solo conserva el primer movimiento con cada ID",
+        "--dry-run lista los registros que recibirían nuevos ID sin cambiar nada",
+    ]),
+    ("retention-apply", &[
+        "Borra las notas y los códigos de cliente más antiguos de lo que permite la sección retention de config.json",
+        "Abarca los movimientos del registro, el archivo y la papelera, y los pedidos pendientes",
+        "--dry-run indica cuántos registros se borrarían sin cambiar nada",
+    ]),
+    ("snapshot-kpis", &[
+        "Registra en kpis.ndjson las unidades disponibles, el valor del stock, los productos con stock bajo y los movimientos de hoy",
+        "Pensado para ejecutarse a diario desde cron; si se ejecuta otra vez el mismo día sustituye los totales de ese día",
+        "Los totales se toman del stock actual, así que no se vuelve a recorrer el historial de movimientos",
+    ]),
+    ("verify-ledger", &[
+        "Comprueba la cadena de hashes de los movimientos en busca de registros cambiados, eliminados o insertados",
+        "Requiere ledger.hash_chain en config.json; se comprueban el registro, el archivo y la papelera",
+        "--against comprueba además que la cabeza de la cadena guardada en una copia de seguridad sigue en la cadena",
+    ]),
+    ("convert-storage", &[
+        "Copia todos los tipos de datos guardados a un directorio de datos vacío, quizá con otro almacenamiento",
+        "La copia se vuelve a leer y se comparan sus recuentos de registros y sumas de verificación con el origen",
+        "El origen solo se lee; cambie al destino cuando la conversión termine bien",
+        "Almacenamientos: json (el predeterminado para ambos)",
+    ]),
+    ("archive-transactions", &[
+        "Mueve los movimientos anteriores a una fecha a archive/transactions-<año>.ndjson",
+        "Los movimientos archivados ya no se cargan al iniciar; consúltalos con history --include-archived",
+    ]),
+    ("compact", &[
+        "Reescribe los archivos de datos sin registros duplicados ni papelera caducada e informa del espacio recuperado",
+        "--archive-older-than mueve los movimientos más antiguos a archive/transactions-<año>.ndjson",
+    ]),
+    ("save-view", &[
+        "Guarda filtros y orden de list-products con un nombre en config.json",
+        "Admite los mismos filtros que list-products; guardar un nombre existente lo reemplaza",
+    ]),
+    ("run-view", &["Lista los productos que cumplen una vista guardada"]),
+    ("list-views", &["Lista las vistas guardadas y sus filtros"]),
+    ("delete-view", &["Elimina una vista guardada de config.json"]),
+    ("save-template", &[
+        "Guarda valores por defecto para add-product --from-template con un nombre en config.json",
+        "--sku-prefix se antepone al SKU indicado; --name-pattern es el nombre con {name} en lugar del indicado",
+        "Guardar un nombre existente lo reemplaza",
+    ]),
+    ("list-templates", &["Lista las plantillas de producto guardadas y sus valores"]),
+    ("delete-template", &["Elimina una plantilla de producto guardada de config.json"]),
+    ("run-script", &[
+        "Ejecuta un script Rhai para un informe o una comprobación a medida; puede leer el inventario pero no cambiarlo",
+        "Los scripts llaman a products(), product(sku), low_stock(), transactions([sku]), sum, count_by y group_by",
+        "Hasta 16 argumentos tras el archivo se pasan en args; pon -- antes de los que empiecen por -",
+        "Necesita una compilación con --features script",
+    ]),
+    ("reconcile", &[
+        "Compara las cantidades disponibles con una exportación CSV de otro sistema, como un ERP o una tienda web, o con un recuento",
+        "La cabecera de la exportación nombra sus columnas; --key es sku y --qty-column es quantity por defecto",
+        "Lista los productos cuyas cantidades difieren y los SKU que solo tiene una de las partes",
+        "--apply añade o retira (como ajustes) unidades para que los productos de ambas partes coincidan con la exportación",
+        "Las diferencias fuera de la tolerancia de stocktake en config.json se retienen hasta que --recount da la misma cantidad o --confirm nombra el SKU",
+        "--sheet lee solo las líneas de esa hoja de count-sheets y omite los productos que no están en ella",
+    ]),
+    ("count-sheets", &[
+        "Escribe hojas de recuento numeradas con los productos a contar, una ubicación tras otra",
+        "Cada línea muestra la ubicación, el código de barras y la cantidad registrada, y deja una columna counted para rellenar",
+        "--blind oculta las cantidades registradas; --location toma las ubicaciones que empiezan así y --category una categoría",
+        "El CSV relleno se introduce hoja a hoja con reconcile <archivo> --qty-column counted --sheet <n>; el PDF debe ir a un archivo con --output",
+    ]),
+    ("shop-sync", &[
+        "Envía el stock disponible de cada producto mapeado a la tienda Shopify o WooCommerce de config.json",
+        "Los SKU se emparejan con los ID de la tienda en shop-mapping.csv (líneas SKU,ID_TIENDA) salvo que se configure otro archivo",
+        "Los cambios de stock también se envían tras cada comando; --dry-run muestra las peticiones sin enviarlas",
+        "Necesita una compilación con --features shop",
+    ]),
+    ("feed-push", &[
+        "Genera los feeds configurados en feeds de config.json y los sube a SFTP, S3 o un directorio",
+        "Sin nombres se envían todos los feeds; --due envía solo aquellos cuyo interval_minutes ha pasado",
+        "Ejecuta feed push --due desde cron cada pocos minutos para entregar los feeds a tiempo; los fallos salen con error",
+    ]),
+    ("feed-status", &["Muestra cada feed configurado, cuándo se envió por última vez y su último error"]),
+    ("events-tail", &[
+        "Muestra los eventos registrados en events.ndjson tras un número de secuencia, un objeto JSON por línea",
+        "Cada evento que genera un comando se añade allí con el siguiente seq; un consumidor pasa el último que procesó",
+    ]),
+    ("inventory-advice", &[
+        "Escribe las unidades disponibles y pedidas de cada producto como aviso de inventario EDI 846 para socios minoristas",
+        "El intercambio X12 (versión 004010) necesita los ID de los socios; --control es AADDDHHMM por defecto",
+        "--format csv o ndjson escribe las mismas existencias en CSV, o un producto JSON por línea, para socios que no aceptan X12",
+    ]),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locale_from_tag() {
+        assert_eq!("es".parse::<Locale>(), Ok(Locale::Es));
+        assert_eq!("es_MX.UTF-8".parse::<Locale>(), Ok(Locale::Es));
+        assert_eq!("en-GB".parse::<Locale>(), Ok(Locale::En));
+        assert_eq!("C".parse::<Locale>(), Ok(Locale::En));
+        assert!("fr_FR".parse::<Locale>().is_err());
+        assert_eq!(Locale::detect(Some("es")), Ok(Locale::Es));
+    }
+
+    #[test]
+    fn test_translate_with_placeholders() {
+        set_locale(Locale::Es);
+        assert_eq!(
+            trf(Msg::ErrInsufficientStock, &[("sku", &"A"), ("requested", &5), ("available", &2)]),
+            "Error: Stock insuficiente para 'A'. Solicitado: 5, Disponible: 2"
+        );
+        assert!(command_description("add-stock").is_some());
+
+        set_locale(Locale::En);
+        assert_eq!(tr(Msg::NoProducts), "No products in inventory.");
+        assert!(command_description("add-stock").is_none());
+    }
+}
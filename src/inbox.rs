@@ -0,0 +1,311 @@
+// Advance shipping notices e-mailed by suppliers, staged for receiving
+//
+// Suppliers that send a notice with every delivery are listed under
+// `inbox.suppliers` in config.json, keyed by sender address or `@domain`.
+// `receive inbox` reads new messages from a maildir (kept filled from IMAP by
+// fetchmail, mbsync, or the mail server itself), and from each supplier's
+// message takes the `SKU,QTY` lines of its CSV attachment, or of the text
+// body when there is none. The delivery reference is the last word of the
+// subject, e.g. `PO-17` in "Advance shipping notice PO-17".
+//
+// Each notice is staged as inbox/<reference>.csv in the data directory, in
+// the form `receive start --expected` reads, until `receive inbox --start`
+// turns it into a receipt. Handled messages are marked seen by moving them
+// to the maildir's cur/ directory; mail from other senders is left unread.
+// Reading messages needs the `email` feature.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::ReceiptLine;
+
+/// Directory in the data directory holding staged notices
+pub const INBOX_DIR: &str = "inbox";
+
+/// Settings for reading shipping notices from e-mail
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InboxConfig {
+    /// Maildir read when `receive inbox` isn't given one
+    pub maildir: Option<String>,
+    /// Supplier names by sender address or `@domain`; mail from anyone else
+    /// is ignored
+    pub suppliers: BTreeMap<String, String>,
+}
+
+impl InboxConfig {
+    /// The supplier a sender address belongs to, if it's one of theirs
+    pub fn supplier_for(&self, address: &str) -> Option<&str> {
+        let address = address.trim().to_lowercase();
+        let domain = address.rfind('@').map(|at| &address[at..]);
+        self.suppliers
+            .iter()
+            .find(|(sender, _)| {
+                let sender = sender.trim().to_lowercase();
+                sender == address || Some(sender.as_str()) == domain
+            })
+            .map(|(_, supplier)| supplier.as_str())
+    }
+}
+
+/// A supplier's notice of what a delivery contains
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notice {
+    /// Delivery reference taken from the subject
+    pub reference: String,
+    /// Supplier name from the configuration
+    pub supplier: String,
+    /// Units expected of each product
+    pub lines: Vec<ReceiptLine>,
+}
+
+impl Notice {
+    /// The notice as `receive start --expected` reads it, headed by comments
+    /// naming the supplier
+    pub fn to_csv(&self) -> String {
+        let mut csv = format!("# Advance shipping notice {} from {}\n", self.reference, self.supplier);
+        for line in &self.lines {
+            csv.push_str(&format!("{},{}\n", line.sku, line.quantity));
+        }
+        csv
+    }
+
+    /// Stage the notice in the data directory, refusing to replace one
+    /// already staged under the same reference
+    pub fn save(&self, data_dir: &Path) -> Result<PathBuf, String> {
+        let dir = data_dir.join(INBOX_DIR);
+        std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        let path = notice_path(data_dir, &self.reference);
+        let mut file = std::fs::OpenOptions::new().write(true).create_new(true).open(&path).map_err(|e| {
+            if e.kind() == std::io::ErrorKind::AlreadyExists {
+                format!("Notice {} is already staged", self.reference)
+            } else {
+                format!("Failed to write {}: {}", path.display(), e)
+            }
+        })?;
+        std::io::Write::write_all(&mut file, self.to_csv().as_bytes())
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+}
+
+/// A staged notice waiting to be received
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pending {
+    pub reference: String,
+    /// The first line of the file, naming the supplier
+    pub summary: String,
+    /// Number of `SKU,QTY` lines
+    pub lines: usize,
+    pub path: PathBuf,
+}
+
+/// What `fetch` did with a supplier's message
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fetched {
+    /// The notice was staged
+    Staged(Notice),
+    /// The message couldn't be read as a notice
+    Rejected { message: String, error: String },
+}
+
+/// Path of a staged notice
+pub fn notice_path(data_dir: &Path, reference: &str) -> PathBuf {
+    data_dir.join(INBOX_DIR).join(format!("{}.csv", reference))
+}
+
+/// Notices staged in the data directory, by reference
+pub fn pending(data_dir: &Path) -> Result<Vec<Pending>, String> {
+    let dir = data_dir.join(INBOX_DIR);
+    let entries = match std::fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", dir.display(), e)),
+    };
+    let mut pending = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?.path();
+        let Some(reference) = path.file_stem().filter(|_| path.extension().is_some_and(|ext| ext == "csv")) else {
+            continue;
+        };
+        let text = std::fs::read_to_string(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        pending.push(Pending {
+            reference: reference.to_string_lossy().into_owned(),
+            summary: text.lines().next().unwrap_or_default().trim_start_matches('#').trim().to_string(),
+            lines: text.lines().filter(|line| !line.trim().is_empty() && !line.starts_with('#')).count(),
+            path,
+        });
+    }
+    pending.sort_by(|a, b| a.reference.cmp(&b.reference));
+    Ok(pending)
+}
+
+/// The delivery reference in a subject: its last word, which may only hold
+/// letters, digits, `-`, `_`, and `.`
+pub fn reference_from_subject(subject: &str) -> Option<String> {
+    let word = subject.split_whitespace().last()?.trim_matches(|c: char| matches!(c, '.' | ',' | ':' | ';' | '(' | ')' | '[' | ']' | '#'));
+    let valid = !word.is_empty()
+        && word.chars().any(|c| c.is_ascii_digit())
+        && word.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    valid.then(|| word.to_string())
+}
+
+/// Read the `SKU,QTY` lines of a notice; a first line whose quantity isn't a
+/// number is taken as a header, and further columns are ignored
+pub fn parse_lines(text: &str) -> Result<Vec<ReceiptLine>, String> {
+    let mut lines = Vec::new();
+    let rows = text.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#'));
+    for (index, row) in rows.enumerate() {
+        let mut fields = row.split([',', ';', '\t']).map(|field| field.trim().trim_matches('"'));
+        let (sku, quantity) = (fields.next().unwrap_or_default(), fields.next().unwrap_or_default());
+        match quantity.parse::<u32>() {
+            Ok(quantity) if quantity > 0 && !sku.is_empty() => {
+                lines.push(ReceiptLine { sku: sku.to_string(), quantity, notes: None });
+            }
+            Err(_) if index == 0 => continue,
+            _ => return Err(format!("Invalid line '{}': expected SKU,QTY with a positive quantity", row)),
+        }
+    }
+    if lines.is_empty() {
+        return Err("No SKU,QTY lines found".to_string());
+    }
+    Ok(lines)
+}
+
+/// Read a message as a supplier's notice; `None` when it isn't from one
+#[cfg(feature = "email")]
+pub fn read_message(raw: &[u8], config: &InboxConfig) -> Result<Option<Notice>, String> {
+    use mail_parser::{MessageParser, MimeHeaders};
+
+    let message = MessageParser::default().parse(raw).ok_or("Not an e-mail message")?;
+    let sender = message.from().and_then(|from| from.first()).and_then(|addr| addr.address()).unwrap_or_default();
+    let Some(supplier) = config.supplier_for(sender) else {
+        return Ok(None);
+    };
+    let subject = message.subject().unwrap_or_default();
+    let reference = reference_from_subject(subject)
+        .ok_or_else(|| format!("No delivery reference at the end of the subject '{}'", subject))?;
+    let attachment = message.attachments().find(|part| {
+        let csv_type = part.content_type().is_some_and(|ct| ct.subtype().is_some_and(|sub| sub.eq_ignore_ascii_case("csv")));
+        csv_type || part.attachment_name().is_some_and(|name| name.to_lowercase().ends_with(".csv"))
+    });
+    let text = match attachment {
+        Some(part) => String::from_utf8_lossy(part.contents()).into_owned(),
+        None => message.body_text(0).map(|body| body.into_owned()).unwrap_or_default(),
+    };
+    let lines = parse_lines(&text)?;
+    Ok(Some(Notice { reference, supplier: supplier.to_string(), lines }))
+}
+
+/// Stage the notices among a maildir's new messages, moving each supplier's
+/// message to cur/ once handled
+#[cfg(feature = "email")]
+pub fn fetch(config: &InboxConfig, maildir: &Path, data_dir: &Path) -> Result<Vec<Fetched>, String> {
+    let new = maildir.join("new");
+    let mut files: Vec<PathBuf> = std::fs::read_dir(&new)
+        .map_err(|e| format!("Failed to read the maildir {}: {}", new.display(), e))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+
+    let mut fetched = Vec::new();
+    for path in files {
+        let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let raw = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let outcome = match read_message(&raw, config) {
+            Ok(None) => continue,
+            Ok(Some(notice)) => match notice.save(data_dir) {
+                Ok(_) => Fetched::Staged(notice),
+                Err(error) => Fetched::Rejected { message: name.clone(), error },
+            },
+            Err(error) => Fetched::Rejected { message: name.clone(), error },
+        };
+        match &outcome {
+            Fetched::Staged(notice) => log::info!("Staged notice {} from {} ({})", notice.reference, notice.supplier, name),
+            Fetched::Rejected { error, .. } => log::warn!("Rejected message {}: {}", name, error),
+        }
+        let seen = maildir.join("cur").join(format!("{}:2,S", name.split(':').next().unwrap_or(&name)));
+        std::fs::rename(&path, &seen).map_err(|e| format!("Failed to mark {} as seen: {}", path.display(), e))?;
+        fetched.push(outcome);
+    }
+    Ok(fetched)
+}
+
+/// Without the `email` feature messages can't be read
+#[cfg(not(feature = "email"))]
+pub fn fetch(_config: &InboxConfig, _maildir: &Path, _data_dir: &Path) -> Result<Vec<Fetched>, String> {
+    Err("Reading e-mail isn't supported by this build; rebuild with --features email".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notices_are_read_and_staged() {
+        let config = InboxConfig {
+            maildir: None,
+            suppliers: BTreeMap::from([
+                ("orders@acme.example".to_string(), "Acme".to_string()),
+                ("@bolts.example".to_string(), "Bolt Co".to_string()),
+            ]),
+        };
+        assert_eq!(config.supplier_for("Orders@ACME.example"), Some("Acme"));
+        assert_eq!(config.supplier_for("asn@bolts.example"), Some("Bolt Co"));
+        assert_eq!(config.supplier_for("someone@else.example"), None);
+
+        assert_eq!(reference_from_subject("Advance shipping notice PO-17"), Some("PO-17".to_string()));
+        assert_eq!(reference_from_subject("ASN: (4500012)."), Some("4500012".to_string()));
+        assert_eq!(reference_from_subject("Your delivery"), None);
+        assert_eq!(reference_from_subject("ASN ../../etc"), None);
+
+        let lines = parse_lines("sku,qty\nA-1,12\n\"B-2\";3;carton 4\n").unwrap();
+        assert_eq!(lines.iter().map(|line| (line.sku.as_str(), line.quantity)).collect::<Vec<_>>(), [("A-1", 12), ("B-2", 3)]);
+        assert!(parse_lines("A-1,12\nB-2,none\n").unwrap_err().contains("'B-2,none'"));
+        assert!(parse_lines("Thanks for your order\n").is_err());
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let notice = Notice { reference: "PO-17".to_string(), supplier: "Acme".to_string(), lines };
+        let path = notice.save(dir.path()).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "# Advance shipping notice PO-17 from Acme\nA-1,12\nB-2,3\n");
+        assert_eq!(notice.save(dir.path()).unwrap_err(), "Notice PO-17 is already staged");
+        let staged = pending(dir.path()).unwrap();
+        assert_eq!((staged[0].reference.as_str(), staged[0].summary.as_str(), staged[0].lines), ("PO-17", "Advance shipping notice PO-17 from Acme", 2));
+    }
+
+    #[cfg(feature = "email")]
+    #[test]
+    fn test_fetch_stages_csv_attachments_from_suppliers() {
+        let config = InboxConfig {
+            maildir: None,
+            suppliers: BTreeMap::from([("orders@acme.example".to_string(), "Acme".to_string())]),
+        };
+        let maildir = tempfile::TempDir::new().unwrap();
+        let data_dir = tempfile::TempDir::new().unwrap();
+        for sub in ["new", "cur", "tmp"] {
+            std::fs::create_dir(maildir.path().join(sub)).unwrap();
+        }
+        std::fs::write(
+            maildir.path().join("new/1.host"),
+            "From: Acme <orders@acme.example>\r\nSubject: Shipping notice PO-17\r\nMIME-Version: 1.0\r\n\
+             Content-Type: multipart/mixed; boundary=\"b\"\r\n\r\n--b\r\nContent-Type: text/plain\r\n\r\nSee attached.\r\n\
+             --b\r\nContent-Type: text/csv; name=\"po-17.csv\"\r\nContent-Disposition: attachment; filename=\"po-17.csv\"\r\n\
+             Content-Transfer-Encoding: base64\r\n\r\nc2t1LHF0eQpBLTEsMTIK\r\n--b--\r\n",
+        )
+        .unwrap();
+        std::fs::write(maildir.path().join("new/2.host"), "From: friend@else.example\r\nSubject: Lunch 12\r\n\r\nHi\r\n").unwrap();
+        std::fs::write(maildir.path().join("new/3.host"), "From: orders@acme.example\r\nSubject: Hello\r\n\r\nA-1,1\r\n").unwrap();
+
+        let fetched = fetch(&config, maildir.path(), data_dir.path()).unwrap();
+        assert_eq!(fetched.len(), 2);
+        let Fetched::Staged(notice) = &fetched[0] else { panic!("{:?}", fetched[0]) };
+        assert_eq!((notice.reference.as_str(), notice.lines[0].sku.as_str(), notice.lines[0].quantity), ("PO-17", "A-1", 12));
+        assert!(matches!(&fetched[1], Fetched::Rejected { error, .. } if error.contains("'Hello'")));
+        assert!(maildir.path().join("new/2.host").exists());
+        assert!(maildir.path().join("cur/1.host:2,S").exists());
+        assert_eq!(pending(data_dir.path()).unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,285 @@
+// Tamper evidence for the transaction ledger
+//
+// With `ledger.hash_chain` enabled, every new transaction stores the SHA-256
+// of the transaction recorded before it in `prev_hash`, the first one linking
+// to `GENESIS`. The hash of the newest one is kept as the chain head together
+// with the number of chained transactions. Changing, removing, or inserting a
+// record breaks a link or leaves the head pointing at nothing, which `verify`
+// reports.
+//
+// The chain follows the order transactions were recorded in, not the order of
+// the file, so archiving, deleting, and restoring products (which move records
+// between the ledger, the archive, and the trash) leave it intact. The head is
+// stored in the data directory, so a backup of the directory carries the head
+// as of the backup; finding that head at the same position on the current
+// chain shows the history up to the backup hasn't been rewritten since.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use sha2::{Digest, Sha256};
+
+use crate::models::{LedgerHead, Transaction};
+
+/// Link of the first chained transaction
+pub const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// SHA-256 of a transaction as stored, including its link, as hex
+///
+/// Fields that are unset aren't serialized, so adding an optional field to
+/// `Transaction` doesn't change the hash of older records.
+pub fn hash(transaction: &Transaction) -> String {
+    let json = serde_json::to_string(transaction).expect("transactions serialize");
+    Sha256::digest(json.as_bytes()).iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Link a new transaction to the chain, returning the new head
+pub fn link(transaction: &mut Transaction, head: Option<&LedgerHead>) -> LedgerHead {
+    transaction.prev_hash = Some(head.map_or(GENESIS, |head| head.hash.as_str()).to_string());
+    LedgerHead { hash: hash(transaction), count: head.map_or(0, |head| head.count) + 1 }
+}
+
+/// Indexes of the chained transactions in chain order, oldest first
+///
+/// `None` if the chain from `head` doesn't lead back to `GENESIS`.
+pub fn chain_order(transactions: &[Transaction], head: &LedgerHead) -> Option<Vec<usize>> {
+    let by_hash: HashMap<String, usize> = transactions
+        .iter()
+        .enumerate()
+        .filter(|(_, transaction)| transaction.prev_hash.is_some())
+        .map(|(index, transaction)| (hash(transaction), index))
+        .collect();
+    let mut order = Vec::new();
+    let mut current = head.hash.as_str();
+    while current != GENESIS {
+        let index = *by_hash.get(current)?;
+        if order.len() == by_hash.len() {
+            return None;
+        }
+        order.push(index);
+        current = transactions[index].prev_hash.as_deref()?;
+    }
+    order.reverse();
+    Some(order)
+}
+
+/// Link the transactions at `order` again after some were changed on purpose,
+/// e.g. redacted, returning the new head
+pub fn relink(transactions: &mut [Transaction], order: &[usize]) -> Option<LedgerHead> {
+    let mut head = None;
+    for &index in order {
+        head = Some(link(&mut transactions[index], head.as_ref()));
+    }
+    head
+}
+
+/// Something `verify` found wrong with the chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LedgerProblem {
+    /// Transactions are chained but no head is stored
+    NoHead { chained: usize },
+    /// The head names a transaction that isn't there
+    HeadMissing { hash: String },
+    /// A transaction links to one that isn't there
+    BrokenLink { id: String },
+    /// A chained transaction that the chain doesn't lead to
+    OffChain { id: String },
+    /// A transaction without a link, newer than the start of the chain
+    Unchained { id: String },
+    /// The chain is a different length than the head records
+    CountMismatch { expected: usize, found: usize },
+    /// A backup's head isn't at its position on the chain
+    BackupHeadMissing { head: LedgerHead },
+}
+
+impl fmt::Display for LedgerProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedgerProblem::NoHead { chained } => {
+                write!(f, "{} transactions are chained but no chain head is stored", chained)
+            }
+            LedgerProblem::HeadMissing { hash } => write!(
+                f,
+                "the newest transaction ({}...) is missing or was changed; the ledger may have been truncated",
+                abbreviated(hash)
+            ),
+            LedgerProblem::BrokenLink { id } => {
+                write!(f, "transaction {} follows one that is missing or was changed", id)
+            }
+            LedgerProblem::OffChain { id } => {
+                write!(f, "transaction {} is not on the chain; it was inserted or changed", id)
+            }
+            LedgerProblem::Unchained { id } => {
+                write!(f, "transaction {} has no link although it is newer than the start of the chain", id)
+            }
+            LedgerProblem::CountMismatch { expected, found } => {
+                write!(f, "the chain holds {} transactions but the head records {}", found, expected)
+            }
+            LedgerProblem::BackupHeadMissing { head } => write!(
+                f,
+                "the backup's chain head ({}..., {} transactions) is not on the current chain; \
+                 history before the backup was rewritten",
+                abbreviated(&head.hash),
+                head.count
+            ),
+        }
+    }
+}
+
+/// The first 12 characters of a hash, or all of it if it is shorter
+///
+/// Stored heads may have been edited, so the hash isn't assumed to be a
+/// well-formed hex digest.
+fn abbreviated(hash: &str) -> &str {
+    hash.get(..12).unwrap_or(hash)
+}
+
+/// Outcome of checking the chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedgerReport {
+    /// Transactions on the chain, from the head back to `GENESIS`
+    pub chained: usize,
+    /// Transactions recorded before the chain was enabled
+    pub unchained: usize,
+    /// The stored head, if any
+    pub head: Option<LedgerHead>,
+    pub problems: Vec<LedgerProblem>,
+}
+
+impl LedgerReport {
+    /// Whether no tampering or truncation was found
+    pub fn is_intact(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Check that `transactions` form one unbroken chain ending at `head`
+///
+/// `transactions` are all records kept anywhere, in any order. With
+/// `backup`, the head from a backup must also be on the chain, as many links
+/// from `GENESIS` as it counted.
+pub fn verify(transactions: &[Transaction], head: Option<&LedgerHead>, backup: Option<&LedgerHead>) -> LedgerReport {
+    let mut by_hash: HashMap<String, &Transaction> = HashMap::new();
+    let mut unchained = Vec::new();
+    for transaction in transactions {
+        match transaction.prev_hash {
+            Some(_) => {
+                by_hash.insert(hash(transaction), transaction);
+            }
+            None => unchained.push(transaction),
+        }
+    }
+    let mut report = LedgerReport { chained: 0, unchained: unchained.len(), head: head.cloned(), problems: Vec::new() };
+    let Some(head) = head else {
+        if !by_hash.is_empty() {
+            report.problems.push(LedgerProblem::NoHead { chained: by_hash.len() });
+        }
+        return report;
+    };
+
+    // Walk back from the head; `walked` ends up newest first
+    let mut walked: Vec<&str> = Vec::new();
+    let mut on_chain = HashSet::new();
+    let mut current = head.hash.as_str();
+    let mut complete = false;
+    while let Some(transaction) = by_hash.get(current) {
+        if !on_chain.insert(current) {
+            break;
+        }
+        walked.push(current);
+        current = transaction.prev_hash.as_deref().unwrap_or_default();
+        if current == GENESIS {
+            complete = true;
+            break;
+        }
+    }
+    report.chained = walked.len();
+
+    if !complete {
+        report.problems.push(match walked.last() {
+            None => LedgerProblem::HeadMissing { hash: head.hash.clone() },
+            Some(last) => LedgerProblem::BrokenLink { id: by_hash[*last].id.clone() },
+        });
+    } else {
+        if walked.len() != head.count {
+            report.problems.push(LedgerProblem::CountMismatch { expected: head.count, found: walked.len() });
+        }
+        let mut off_chain: Vec<&Transaction> = by_hash
+            .iter()
+            .filter(|(hash, _)| !on_chain.contains(hash.as_str()))
+            .map(|(_, transaction)| *transaction)
+            .collect();
+        off_chain.sort_by_key(|t| t.order_key());
+        report.problems.extend(off_chain.into_iter().map(|t| LedgerProblem::OffChain { id: t.id.clone() }));
+
+        let start = by_hash[walked[walked.len() - 1]].timestamp;
+        unchained.sort_by_key(|t| t.order_key());
+        report.problems.extend(
+            unchained.iter().filter(|t| t.timestamp > start).map(|t| LedgerProblem::Unchained { id: t.id.clone() }),
+        );
+    }
+
+    if let Some(backup) = backup {
+        let position = walked.len().checked_sub(backup.count);
+        if position.and_then(|position| walked.get(position)) != Some(&backup.hash.as_str()) {
+            report.problems.push(LedgerProblem::BackupHeadMissing { head: backup.clone() });
+        }
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TransactionType;
+
+    fn chain(count: usize) -> (Vec<Transaction>, LedgerHead) {
+        let mut head = None;
+        let transactions = (0..count)
+            .map(|i| {
+                let mut transaction = Transaction::new("SKU001", TransactionType::Addition, i as u32 + 1, None);
+                head = Some(link(&mut transaction, head.as_ref()));
+                transaction
+            })
+            .collect();
+        (transactions, head.unwrap())
+    }
+
+    #[test]
+    fn test_verify_detects_tampering_and_truncation() {
+        let (mut transactions, head) = chain(4);
+        let backup = LedgerHead { hash: hash(&transactions[1]), count: 2 };
+        transactions.reverse();
+        assert!(verify(&transactions, Some(&head), Some(&backup)).is_intact());
+        assert_eq!(verify(&transactions, Some(&head), None).chained, 4);
+
+        // Editing a quantity breaks the link of the next transaction
+        let mut edited = transactions.clone();
+        edited[2].quantity = 99;
+        let report = verify(&edited, Some(&head), Some(&backup));
+        assert_eq!(report.problems[0], LedgerProblem::BrokenLink { id: transactions[1].id.clone() });
+        assert!(matches!(report.problems[1], LedgerProblem::BackupHeadMissing { .. }));
+
+        // Dropping the newest transaction leaves the head pointing at nothing
+        let report = verify(&transactions[1..], Some(&head), None);
+        assert_eq!(report.problems, [LedgerProblem::HeadMissing { hash: head.hash.clone() }]);
+
+        // An unlinked transaction slipped in after the chain started
+        let mut inserted = transactions.clone();
+        inserted.push(Transaction::new("SKU001", TransactionType::Removal, 1, None));
+        let report = verify(&inserted, Some(&head), None);
+        assert!(matches!(&report.problems[..], [LedgerProblem::Unchained { .. }]));
+    }
+
+    #[test]
+    fn test_edited_heads_are_reported_not_panicked_on() {
+        let (transactions, _) = chain(2);
+        for hash in ["abc", "aéééééé"] {
+            let head = LedgerHead { hash: hash.to_string(), count: 2 };
+            let report = verify(&transactions, Some(&head), Some(&head));
+            let messages: Vec<String> = report.problems.iter().map(ToString::to_string).collect();
+            assert!(messages[0].contains(&format!("({}...)", hash)), "{:?}", messages);
+            assert!(messages[1].contains(&format!("({}..., 2 transactions)", hash)), "{:?}", messages);
+        }
+    }
+}
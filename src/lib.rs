@@ -1,7 +1,41 @@
 // Library exports for Stock Control System
 
 pub mod models;
+pub mod api;
 pub mod errors;
 pub mod storage;
 pub mod service;
+pub mod books;
+pub mod cache;
+pub mod checkpoint;
+pub mod costing;
 pub mod cli;
+pub mod config;
+pub mod convert;
+pub mod diff;
+pub mod edi;
+pub mod export;
+pub mod feed;
+pub mod forecast;
+pub mod hooks;
+pub mod events;
+pub mod i18n;
+pub mod inbox;
+pub mod ledger;
+pub mod limits;
+pub mod logging;
+pub mod mapping;
+pub mod mqtt;
+pub mod outbox;
+pub mod plugins;
+pub mod receipt;
+pub mod reorder;
+pub mod script;
+pub mod shop;
+pub mod stocktake;
+pub mod rules;
+pub mod schema;
+pub mod scale;
+pub mod template;
+pub mod testing;
+pub mod validation;
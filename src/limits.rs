@@ -0,0 +1,142 @@
+// Length and content limits for free-text fields
+//
+// Text is normalized to Unicode NFC before it is checked and stored, so the
+// same name typed on different systems compares and sorts the same. Control
+// characters (including newlines and tabs) are rejected because they break
+// table output and line-oriented exports.
+
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::errors::ServiceError;
+use crate::i18n::{tr, trf, Msg};
+
+/// Free-text fields subject to limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextField {
+    Sku,
+    Name,
+    Description,
+    Category,
+    Supplier,
+    Location,
+    Notes,
+}
+
+impl TextField {
+    /// Field name used in error messages
+    fn label(self) -> &'static str {
+        match self {
+            TextField::Sku => tr(Msg::LabelSku),
+            TextField::Name => tr(Msg::LabelName),
+            TextField::Description => tr(Msg::LabelDescription),
+            TextField::Category => tr(Msg::LabelCategory),
+            TextField::Supplier => tr(Msg::LabelSupplier),
+            TextField::Location => tr(Msg::LabelLocation),
+            TextField::Notes => tr(Msg::LabelNotes),
+        }
+    }
+}
+
+/// Maximum field lengths in characters, read from the `limits` section of the config file
+///
+/// A limit of 0 disables the length check for that field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldLimits {
+    /// Maximum SKU length
+    pub max_sku: usize,
+    /// Maximum product name length
+    pub max_name: usize,
+    /// Maximum product description length
+    pub max_description: usize,
+    /// Maximum category, supplier, and location length
+    pub max_label: usize,
+    /// Maximum transaction notes length
+    pub max_notes: usize,
+}
+
+impl Default for FieldLimits {
+    fn default() -> Self {
+        FieldLimits {
+            max_sku: 64,
+            max_name: 200,
+            max_description: 2000,
+            max_label: 100,
+            max_notes: 1000,
+        }
+    }
+}
+
+impl FieldLimits {
+    /// Maximum length of a field in characters (0 for unlimited)
+    pub fn max_length(&self, field: TextField) -> usize {
+        match field {
+            TextField::Sku => self.max_sku,
+            TextField::Name => self.max_name,
+            TextField::Description => self.max_description,
+            TextField::Category | TextField::Supplier | TextField::Location => self.max_label,
+            TextField::Notes => self.max_notes,
+        }
+    }
+
+    /// Normalize a field value to NFC and check its length and characters
+    ///
+    /// SKUs are looked up by exact match, so they are checked but returned
+    /// unchanged.
+    pub fn clean(&self, field: TextField, value: &str) -> Result<String, ServiceError> {
+        let value: String = match field {
+            TextField::Sku => value.to_string(),
+            _ => value.nfc().collect(),
+        };
+
+        if let Some((position, c)) = value.chars().enumerate().find(|(_, c)| c.is_control()) {
+            return Err(ServiceError::invalid_input(trf(
+                Msg::InputControlCharacter,
+                &[("field", &field.label()), ("code", &format!("{:04X}", u32::from(c))), ("position", &(position + 1))],
+            )));
+        }
+
+        let max = self.max_length(field);
+        let length = value.chars().count();
+        if max > 0 && length > max {
+            return Err(ServiceError::invalid_input(trf(
+                Msg::InputTooLong, &[("field", &field.label()), ("length", &length), ("max", &max)]
+            )));
+        }
+        Ok(value)
+    }
+
+    /// Clean an optional field value
+    pub fn clean_optional(&self, field: TextField, value: Option<String>) -> Result<Option<String>, ServiceError> {
+        value.map(|value| self.clean(field, &value)).transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clean_normalizes_to_nfc() {
+        let limits = FieldLimits::default();
+        // "e" followed by a combining acute accent
+        let decomposed = "Cafe\u{301} beans";
+        assert_eq!(limits.clean(TextField::Name, decomposed).unwrap(), "Caf\u{e9} beans");
+        assert_eq!(limits.clean(TextField::Sku, "SKU\u{301}").unwrap(), "SKU\u{301}");
+    }
+
+    #[test]
+    fn test_clean_rejects_control_characters_and_long_values() {
+        let limits = FieldLimits { max_name: 5, max_notes: 0, ..Default::default() };
+        assert_eq!(
+            limits.clean(TextField::Notes, "line one\nline two").unwrap_err().to_string(),
+            "Invalid input: Notes contains a control character (U+000A) at position 9"
+        );
+        assert_eq!(
+            limits.clean(TextField::Name, "Widgets").unwrap_err().to_string(),
+            "Invalid input: Name is too long (7 characters, maximum 5)"
+        );
+        assert!(limits.clean(TextField::Notes, &"x".repeat(5000)).is_ok());
+    }
+}
@@ -0,0 +1,205 @@
+// Diagnostic logging to stderr and an optional rotating log file
+
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::Utc;
+use log::{LevelFilter, Log, Metadata, Record};
+use serde::{Deserialize, Serialize};
+
+/// Default log file name in the data directory
+pub const LOG_FILE: &str = "stock-control.log";
+
+/// Log file settings read from the `log` section of the config file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogFileConfig {
+    /// Whether to write a log file in the data directory
+    pub enabled: bool,
+    /// Most verbose level written to the file: error, warn, info, debug, or trace
+    pub level: String,
+    /// Size in bytes at which the file is rotated
+    pub max_bytes: u64,
+    /// Number of rotated files kept (`stock-control.log.1` is the newest)
+    pub max_files: usize,
+}
+
+impl Default for LogFileConfig {
+    fn default() -> Self {
+        LogFileConfig {
+            enabled: false,
+            level: "info".to_string(),
+            max_bytes: 1024 * 1024,
+            max_files: 3,
+        }
+    }
+}
+
+/// Map the number of `-v` flags to a stderr log level
+///
+/// Without `-v` nothing is logged to stderr, so normal output is unchanged.
+pub fn verbosity_level(verbosity: u8) -> LevelFilter {
+    match verbosity {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    }
+}
+
+/// Install the global logger
+///
+/// Logging is best-effort: a log file that cannot be opened is reported once
+/// on stderr and otherwise ignored, and a logger that is already installed
+/// (e.g. when running several commands in one process) is left in place.
+pub fn init(verbosity: u8, data_dir: &Path, file_config: &LogFileConfig) -> Result<(), String> {
+    let stderr_level = verbosity_level(verbosity);
+    let (file, file_level) = if file_config.enabled {
+        let level = file_config.level.parse::<LevelFilter>().map_err(|_| {
+            format!(
+                "Invalid log level '{}': expected one of error, warn, info, debug, trace",
+                file_config.level
+            )
+        })?;
+        let path = data_dir.join(LOG_FILE);
+        match RotatingFile::open(path, file_config.max_bytes, file_config.max_files) {
+            Ok(file) => (Some(Mutex::new(file)), level),
+            Err(e) => {
+                eprintln!("Warning: logging to file disabled: {}", e);
+                (None, LevelFilter::Off)
+            }
+        }
+    } else {
+        (None, LevelFilter::Off)
+    };
+
+    let logger = Logger { stderr_level, file, file_level };
+    let max_level = stderr_level.max(file_level);
+    if log::set_boxed_logger(Box::new(logger)).is_ok() {
+        log::set_max_level(max_level);
+    }
+    Ok(())
+}
+
+/// Logger writing to stderr and/or a rotating file at independent levels
+struct Logger {
+    stderr_level: LevelFilter,
+    file: Option<Mutex<RotatingFile>>,
+    file_level: LevelFilter,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.stderr_level || metadata.level() <= self.file_level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "{} {:<5} {}: {}",
+            Utc::now().format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        if record.level() <= self.stderr_level {
+            eprintln!("{}", line);
+        }
+        if record.level() <= self.file_level {
+            if let Some(file) = &self.file {
+                if let Ok(mut file) = file.lock() {
+                    // Never let a logging failure interrupt the command
+                    let _ = file.write_line(&line);
+                }
+            }
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Append-only log file rotated by size
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_bytes: u64, max_files: usize) -> std::io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let size = file.metadata()?.len();
+        Ok(RotatingFile { path, file, size, max_bytes, max_files })
+    }
+
+    fn write_line(&mut self, line: &str) -> std::io::Result<()> {
+        if self.max_bytes > 0 && self.size >= self.max_bytes {
+            self.rotate()?;
+        }
+        writeln!(self.file, "{}", line)?;
+        self.size += line.len() as u64 + 1;
+        Ok(())
+    }
+
+    /// Shift `log.N-1` to `log.N`, ..., `log` to `log.1`, and start a new file
+    fn rotate(&mut self) -> std::io::Result<()> {
+        if self.max_files == 0 {
+            self.file = File::create(&self.path)?;
+        } else {
+            let _ = fs::remove_file(rotated_path(&self.path, self.max_files));
+            for index in (1..self.max_files).rev() {
+                let from = rotated_path(&self.path, index);
+                if from.exists() {
+                    fs::rename(&from, rotated_path(&self.path, index + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+            self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+/// Path of the `index`-th rotated log file
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rotating_file_keeps_max_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(LOG_FILE);
+        let mut file = RotatingFile::open(path.clone(), 10, 2).unwrap();
+
+        for line in ["first line", "second line", "third line", "fourth line"] {
+            file.write_line(line).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth line\n");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 1)).unwrap(), "third line\n");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 2)).unwrap(), "second line\n");
+        assert!(!rotated_path(&path, 3).exists());
+    }
+
+    #[test]
+    fn test_verbosity_level() {
+        assert_eq!(verbosity_level(0), LevelFilter::Off);
+        assert_eq!(verbosity_level(1), LevelFilter::Info);
+        assert_eq!(verbosity_level(2), LevelFilter::Debug);
+        assert_eq!(verbosity_level(5), LevelFilter::Trace);
+    }
+}
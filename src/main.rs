@@ -1,11 +1,7 @@
-mod errors;
-mod models;
-mod storage;
-mod service;
-mod cli;
-
 use std::process;
 
+use stock_control::cli;
+
 fn main() {
     // Use current directory for data storage
     let data_dir = ".";
@@ -0,0 +1,140 @@
+// Field mapping profiles for importing other systems' files
+//
+// A supplier or point-of-sale system that sends the same file every week
+// rarely uses our column names. A profile, saved under `import_profiles` in
+// config.json, says which column (or JSON key) holds each field, gives
+// values for fields the file lacks, and cleans values up on the way in:
+//
+//     "import_profiles": {
+//         "acme": {
+//             "columns": { "sku": "Item No", "quantity": "Qty Shipped", "timestamp": "Ship Date" },
+//             "defaults": { "type": "addition" },
+//             "transforms": { "sku": ["uppercase", { "prefix": "ACME-" }] }
+//         }
+//     }
+//
+// `import-transactions --profile acme shipments.csv` then reads the file
+// through it. A field without a column mapping is looked for under its own
+// name, ignoring case.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+/// A field of an imported movement
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportField {
+    Sku,
+    Timestamp,
+    Type,
+    Quantity,
+    Notes,
+}
+
+impl ImportField {
+    /// Every field, in the order of an import line
+    pub const ALL: [ImportField; 5] =
+        [ImportField::Sku, ImportField::Timestamp, ImportField::Type, ImportField::Quantity, ImportField::Notes];
+
+    /// Name used in profiles, and the column looked for when none is mapped
+    pub fn name(self) -> &'static str {
+        match self {
+            ImportField::Sku => "sku",
+            ImportField::Timestamp => "timestamp",
+            ImportField::Type => "type",
+            ImportField::Quantity => "quantity",
+            ImportField::Notes => "notes",
+        }
+    }
+}
+
+impl fmt::Display for ImportField {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A change made to an imported value
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transform {
+    Uppercase,
+    Lowercase,
+    /// Put this in front of the value, unless it already starts with it
+    Prefix(String),
+    /// Remove this from the start of the value
+    StripPrefix(String),
+}
+
+impl Transform {
+    fn apply(&self, value: String) -> String {
+        match self {
+            Transform::Uppercase => value.to_uppercase(),
+            Transform::Lowercase => value.to_lowercase(),
+            Transform::Prefix(prefix) if !value.starts_with(prefix.as_str()) => format!("{}{}", prefix, value),
+            Transform::Prefix(_) => value,
+            Transform::StripPrefix(prefix) => value.strip_prefix(prefix.as_str()).map(String::from).unwrap_or(value),
+        }
+    }
+}
+
+/// How the columns of another system's file map onto an import
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportProfile {
+    /// The column or JSON key holding each field
+    pub columns: BTreeMap<ImportField, String>,
+    /// Values for fields the file leaves out or empty
+    pub defaults: BTreeMap<ImportField, String>,
+    /// Changes made to each field's values, in order
+    pub transforms: BTreeMap<ImportField, Vec<Transform>>,
+}
+
+impl ImportProfile {
+    /// The column or JSON key a field is read from
+    pub fn column(&self, field: ImportField) -> &str {
+        self.columns.get(&field).map_or(field.name(), String::as_str)
+    }
+
+    /// Whether the profile gives a value for a field the file lacks
+    pub fn has_default(&self, field: ImportField) -> bool {
+        self.defaults.contains_key(&field)
+    }
+
+    /// A field's value from what the file holds, if anything
+    ///
+    /// The value is trimmed, the default stands in for a missing or blank
+    /// one, and then the transforms are applied in order.
+    pub fn value(&self, field: ImportField, raw: Option<&str>) -> Option<String> {
+        let value = raw
+            .map(str::trim)
+            .filter(|raw| !raw.is_empty())
+            .or_else(|| self.defaults.get(&field).map(String::as_str))?
+            .to_string();
+        let transforms = self.transforms.get(&field).map(Vec::as_slice).unwrap_or_default();
+        Some(transforms.iter().fold(value, |value, transform| transform.apply(value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_maps_defaults_and_transforms() {
+        let profile: ImportProfile = serde_json::from_str(
+            r#"{ "columns": { "sku": "Item No" }, "defaults": { "type": "addition" },
+                 "transforms": { "sku": ["uppercase", { "strip_prefix": "OLD-" }, { "prefix": "ACME-" }] } }"#,
+        )
+        .unwrap();
+        assert_eq!(profile.column(ImportField::Sku), "Item No");
+        assert_eq!(profile.column(ImportField::Quantity), "quantity");
+        assert_eq!(profile.value(ImportField::Sku, Some(" old-w1 ")).as_deref(), Some("ACME-W1"));
+        assert_eq!(profile.value(ImportField::Sku, Some("ACME-W2")).as_deref(), Some("ACME-W2"));
+        assert_eq!(profile.value(ImportField::Type, Some("")).as_deref(), Some("addition"));
+        assert_eq!(profile.value(ImportField::Type, Some("removal")).as_deref(), Some("removal"));
+        assert_eq!(profile.value(ImportField::Notes, None), None);
+    }
+}
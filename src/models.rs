@@ -1,10 +1,14 @@
 // Data models for the Stock Control System
 
-use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use uuid::Uuid;
 
 /// Represents a product in the inventory system
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct Product {
     /// Unique identifier (UUID)
     pub id: String,
@@ -18,6 +22,590 @@ pub struct Product {
     pub quantity: u32,
     /// Minimum stock level that triggers reorder alert
     pub reorder_point: u32,
+    /// Optional product category (e.g. "widgets")
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Optional supplier the product is purchased from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub supplier: Option<String>,
+    /// Optional storage location (e.g. warehouse or bin)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    /// Optional barcode printed on the product, if it differs from the SKU;
+    /// several products may share one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub barcode: Option<String>,
+    /// Per-product large-movement threshold, overriding the global one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub large_movement_threshold: Option<MovementThreshold>,
+    /// Bill of materials: components needed to assemble one unit of a kit
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<Component>,
+    /// Typed links to related products, e.g. the products this one can replace
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<ProductLink>,
+    /// Units on hold (e.g. awaiting QC), not included in `quantity`
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub quarantined: u32,
+    /// Units ordered from a supplier but not yet received
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub on_order: u32,
+    /// Units of `quantity` in a condition other than new; the rest are new
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub conditions: BTreeMap<Condition, u32>,
+    /// Cost of one unit, used to value the stock
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit_cost: Option<Money>,
+    /// Weight of the container the product is weighed in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tare_weight: Option<Weight>,
+    /// Weight of one unit, for counting stock by weight
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit_weight: Option<Weight>,
+    /// Days a reorder takes to arrive, for suggesting reorder points
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lead_time_days: Option<u32>,
+    /// Acknowledged low-stock alert, hidden from `low-stock` while it lasts
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub alert_snooze: Option<AlertSnooze>,
+    /// When the product was added; unknown for products from older data files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+    /// Fields this version doesn't know, as written by a newer version or
+    /// another tool; kept so saving the product doesn't drop them
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// A low-stock alert acknowledged with `ack`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AlertSnooze {
+    /// Available units when the alert was acknowledged; falling below them ends the snooze
+    pub quantity: u32,
+    /// First day the alert shows again; without it, the snooze lasts until stock changes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub until: Option<NaiveDate>,
+}
+
+impl Product {
+    /// Units physically held: available plus quarantined
+    pub fn on_hand(&self) -> u64 {
+        u64::from(self.quantity) + u64::from(self.quarantined)
+    }
+
+    /// Available units in a condition
+    pub fn condition_quantity(&self, condition: Condition) -> u32 {
+        match condition {
+            Condition::New => {
+                let other: u32 = self.conditions.values().sum();
+                self.quantity.saturating_sub(other)
+            }
+            _ => self.conditions.get(&condition).copied().unwrap_or(0),
+        }
+    }
+
+    /// Record units added to or taken from the stock in a condition
+    ///
+    /// New stock isn't stored; it's whatever `quantity` leaves over.
+    pub fn adjust_condition(&mut self, condition: Condition, change: i64) {
+        if condition == Condition::New {
+            return;
+        }
+        let current = i64::from(self.condition_quantity(condition));
+        let updated = u32::try_from((current + change).max(0)).unwrap_or(u32::MAX);
+        if updated == 0 {
+            self.conditions.remove(&condition);
+        } else {
+            self.conditions.insert(condition, updated);
+        }
+    }
+
+    /// Trim the condition breakdown after `quantity` went down
+    ///
+    /// Removals that don't name a condition take new stock first, then
+    /// refurbished, then damaged.
+    pub fn settle_conditions(&mut self) {
+        let mut excess = self.conditions.values().sum::<u32>().saturating_sub(self.quantity);
+        for condition in Condition::ALL {
+            if excess == 0 {
+                break;
+            }
+            let taken = excess.min(self.condition_quantity(condition));
+            self.adjust_condition(condition, -i64::from(taken));
+            excess -= taken;
+        }
+    }
+}
+
+/// State of stock units, e.g. for shops dealing in returns and open-box items
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "kebab-case")]
+pub enum Condition {
+    /// Unused stock in its original state
+    #[default]
+    New,
+    /// Returned or repaired stock that's fit for sale
+    Refurbished,
+    /// Damaged stock that's still held, e.g. for sale as-is
+    Damaged,
+}
+
+impl Condition {
+    /// Every condition, in report order
+    pub const ALL: [Condition; 3] = [Condition::New, Condition::Refurbished, Condition::Damaged];
+
+    /// Name used on the command line and in storage
+    pub fn name(self) -> &'static str {
+        match self {
+            Condition::New => "new",
+            Condition::Refurbished => "refurbished",
+            Condition::Damaged => "damaged",
+        }
+    }
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for Condition {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        Condition::ALL.into_iter().find(|condition| condition.name() == lower).ok_or_else(|| {
+            let names: Vec<&str> = Condition::ALL.iter().map(|condition| condition.name()).collect();
+            format!("Invalid condition '{}': expected one of {}", s, names.join(", "))
+        })
+    }
+}
+
+/// Whether a count is zero, for skipping default fields when serializing
+fn is_zero(n: &u32) -> bool {
+    *n == 0
+}
+
+/// Whether a transaction predates sequence numbers
+fn is_unsequenced(seq: &u64) -> bool {
+    *seq == 0
+}
+
+/// A component of a kit and how many go into one kit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Component {
+    /// SKU of the component product
+    pub sku: String,
+    /// Units of the component per kit
+    pub quantity: u32,
+}
+
+impl std::fmt::Display for Component {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.sku, self.quantity)
+    }
+}
+
+impl std::str::FromStr for Component {
+    type Err = String;
+
+    /// Parse `SKU:QTY`, e.g. `BOLT-M6:4`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (sku, quantity) = s
+            .rsplit_once(':')
+            .ok_or_else(|| format!("Invalid component '{}': expected SKU:QTY", s))?;
+        let quantity = quantity
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| format!("Invalid component quantity '{}': must be a positive integer", quantity))?;
+        Ok(Component { sku: sku.trim().to_string(), quantity })
+    }
+}
+
+/// How a linked product relates to the product holding the link
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LinkKind {
+    /// The product can be sold in place of the linked one
+    SubstituteFor,
+    /// The product is sold alongside the linked one, e.g. a case for a phone
+    AccessoryOf,
+}
+
+impl LinkKind {
+    /// Every kind of link
+    pub const ALL: [LinkKind; 2] = [LinkKind::SubstituteFor, LinkKind::AccessoryOf];
+
+    /// Name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            LinkKind::SubstituteFor => "substitute-for",
+            LinkKind::AccessoryOf => "accessory-of",
+        }
+    }
+}
+
+impl std::fmt::Display for LinkKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for LinkKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        LinkKind::ALL.into_iter().find(|kind| kind.name() == lower).ok_or_else(|| {
+            let names: Vec<&str> = LinkKind::ALL.iter().map(|kind| kind.name()).collect();
+            format!("Invalid link type '{}': expected one of {}", s, names.join(", "))
+        })
+    }
+}
+
+/// A typed link from one product to another
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProductLink {
+    pub kind: LinkKind,
+    /// SKU of the linked product
+    pub sku: String,
+}
+
+impl Product {
+    /// Whether the product's stock is at or below its reorder point
+    pub fn is_low_stock(&self) -> bool {
+        self.quantity <= self.reorder_point
+    }
+
+    /// Units still to order to get back to the reorder point, after those already on order
+    pub fn shortfall(&self) -> u32 {
+        self.reorder_point.saturating_sub(self.quantity).saturating_sub(self.on_order)
+    }
+
+    /// Whether the product is low on stock but its alert is acknowledged on `today`
+    pub fn is_snoozed(&self, today: NaiveDate) -> bool {
+        self.is_low_stock()
+            && self.alert_snooze.is_some_and(|snooze| {
+                self.quantity >= snooze.quantity && snooze.until.is_none_or(|until| today < until)
+            })
+    }
+
+    /// Drop an acknowledged alert once stock is back above the reorder point or
+    /// has fallen below the acknowledged level
+    pub fn settle_snooze(&mut self) {
+        if self.alert_snooze.is_some_and(|snooze| !self.is_low_stock() || self.quantity < snooze.quantity) {
+            self.alert_snooze = None;
+        }
+    }
+
+    /// Get the value of the field used for grouping, if set
+    pub fn group_key(&self, group_by: GroupBy) -> Option<&str> {
+        match group_by {
+            GroupBy::Category => self.category.as_deref(),
+            GroupBy::Supplier => self.supplier.as_deref(),
+            GroupBy::Location => self.location.as_deref(),
+        }
+    }
+}
+
+/// Product field used to group listings and reports
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    /// Group by product category
+    Category,
+    /// Group by supplier
+    Supplier,
+    /// Group by storage location
+    Location,
+}
+
+impl std::fmt::Display for GroupBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GroupBy::Category => write!(f, "category"),
+            GroupBy::Supplier => write!(f, "supplier"),
+            GroupBy::Location => write!(f, "location"),
+        }
+    }
+}
+
+impl std::str::FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "category" => Ok(GroupBy::Category),
+            "supplier" => Ok(GroupBy::Supplier),
+            "location" => Ok(GroupBy::Location),
+            _ => Err(format!(
+                "Invalid group '{}': expected one of supplier, category, location",
+                s
+            )),
+        }
+    }
+}
+
+/// Order of a product listing
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ProductSort {
+    /// By SKU
+    #[default]
+    Sku,
+    /// By product name, then SKU
+    Name,
+    /// By available quantity, lowest first, then SKU
+    Quantity,
+}
+
+impl ProductSort {
+    /// Every sort order
+    pub const ALL: [ProductSort; 3] = [ProductSort::Sku, ProductSort::Name, ProductSort::Quantity];
+
+    /// Name used on the command line and in the configuration
+    pub fn name(self) -> &'static str {
+        match self {
+            ProductSort::Sku => "sku",
+            ProductSort::Name => "name",
+            ProductSort::Quantity => "quantity",
+        }
+    }
+}
+
+impl std::fmt::Display for ProductSort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for ProductSort {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        ProductSort::ALL.into_iter().find(|sort| sort.name() == lower).ok_or_else(|| {
+            let names: Vec<&str> = ProductSort::ALL.iter().map(|sort| sort.name()).collect();
+            format!("Invalid sort order '{}': expected one of {}", s, names.join(", "))
+        })
+    }
+}
+
+/// A column of a `list-products --columns` listing
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListColumn {
+    Sku,
+    Name,
+    /// Units on hand, or in the filtered condition
+    Quantity,
+    ReorderPoint,
+    Category,
+    Supplier,
+    Location,
+    /// Cost of one unit as set on the product
+    UnitCost,
+    /// Stock on hand at FIFO cost
+    Value,
+}
+
+impl ListColumn {
+    /// Every column
+    pub const ALL: [ListColumn; 9] = [
+        ListColumn::Sku,
+        ListColumn::Name,
+        ListColumn::Quantity,
+        ListColumn::ReorderPoint,
+        ListColumn::Category,
+        ListColumn::Supplier,
+        ListColumn::Location,
+        ListColumn::UnitCost,
+        ListColumn::Value,
+    ];
+
+    /// Name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            ListColumn::Sku => "sku",
+            ListColumn::Name => "name",
+            ListColumn::Quantity => "quantity",
+            ListColumn::ReorderPoint => "reorder-point",
+            ListColumn::Category => "category",
+            ListColumn::Supplier => "supplier",
+            ListColumn::Location => "location",
+            ListColumn::UnitCost => "unit-cost",
+            ListColumn::Value => "value",
+        }
+    }
+
+    /// Whether the column holds numbers, which are right-aligned and totalled
+    pub fn is_numeric(self) -> bool {
+        matches!(self, ListColumn::Quantity | ListColumn::ReorderPoint | ListColumn::Value)
+    }
+}
+
+impl std::fmt::Display for ListColumn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for ListColumn {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.trim().to_lowercase();
+        ListColumn::ALL.into_iter().find(|column| column.name() == lower).ok_or_else(|| {
+            let names: Vec<&str> = ListColumn::ALL.iter().map(|column| column.name()).collect();
+            format!("Invalid column '{}': expected one of {}", s, names.join(", "))
+        })
+    }
+}
+
+/// Defaults for products added with `add-product --from-template`, as saved
+/// with `template save`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProductTemplate {
+    /// Put in front of the SKU given, unless it already starts with it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sku_prefix: Option<String>,
+    /// Product name with `{name}` standing for the name given, e.g. `"Cable {name}"`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name_pattern: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reorder_point: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supplier: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit_cost: Option<Money>,
+}
+
+impl ProductTemplate {
+    /// The SKU of a product added with the template
+    pub fn sku(&self, sku: &str) -> String {
+        match self.sku_prefix.as_deref() {
+            Some(prefix) if !sku.starts_with(prefix) => format!("{}{}", prefix, sku),
+            _ => sku.to_string(),
+        }
+    }
+
+    /// The name of a product added with the template
+    pub fn name(&self, name: &str) -> String {
+        match self.name_pattern.as_deref() {
+            Some(pattern) => pattern.replace("{name}", name),
+            None => name.to_string(),
+        }
+    }
+}
+
+/// How an imported value is combined with what an existing product has
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FieldMerge {
+    /// Leave the product's value alone
+    #[default]
+    Keep,
+    /// Replace it with the imported value
+    Overwrite,
+    /// Use the imported value only where the product has none (a blank
+    /// name, or a reorder point of zero)
+    FillIfEmpty,
+}
+
+impl FieldMerge {
+    /// The merged value, where `is_empty` says whether the current one counts as missing
+    pub fn apply<T>(self, current: T, imported: T, is_empty: impl Fn(&T) -> bool) -> T {
+        match self {
+            FieldMerge::Keep => current,
+            FieldMerge::Overwrite => imported,
+            FieldMerge::FillIfEmpty if is_empty(&current) => imported,
+            FieldMerge::FillIfEmpty => current,
+        }
+    }
+}
+
+impl std::str::FromStr for FieldMerge {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(FieldMerge::Keep),
+            "overwrite" => Ok(FieldMerge::Overwrite),
+            "fill-if-empty" => Ok(FieldMerge::FillIfEmpty),
+            _ => Err(format!("Invalid merge strategy '{}': expected one of keep, overwrite, fill-if-empty", s)),
+        }
+    }
+}
+
+/// How an imported quantity is combined with an existing product's stock
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QuantityMerge {
+    /// Leave the stock alone, as for a catalog refresh
+    #[default]
+    Keep,
+    /// Add the imported units to the stock
+    Add,
+    /// Bring the stock to the imported quantity, as for a stock sync
+    Set,
+}
+
+impl std::str::FromStr for QuantityMerge {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep" => Ok(QuantityMerge::Keep),
+            "add" => Ok(QuantityMerge::Add),
+            "set" => Ok(QuantityMerge::Set),
+            _ => Err(format!("Invalid quantity strategy '{}': expected one of keep, add, set", s)),
+        }
+    }
+}
+
+/// What an import does with products that already exist, field by field
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergePolicy {
+    pub name: FieldMerge,
+    pub reorder_point: FieldMerge,
+    pub quantity: QuantityMerge,
+}
+
+/// Filters and sort order for a product listing, as saved in a named view
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProductFilter {
+    /// SKU prefix or glob pattern
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sku_prefix: Option<String>,
+    /// Only products in this category
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<String>,
+    /// Only products from this supplier
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supplier: Option<String>,
+    /// Only products with available stock in this condition
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub condition: Option<Condition>,
+    /// Only products at or below their reorder point
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub below_reorder: bool,
+    /// Order of the listing
+    pub sort: ProductSort,
+}
+
+impl ProductFilter {
+    /// Whether a product passes every filter except the SKU pattern
+    pub fn matches(&self, product: &Product) -> bool {
+        self.category.as_ref().is_none_or(|category| product.category.as_ref() == Some(category))
+            && self.supplier.as_ref().is_none_or(|supplier| product.supplier.as_ref() == Some(supplier))
+            && self.condition.is_none_or(|condition| product.condition_quantity(condition) > 0)
+            && (!self.below_reorder || product.is_low_stock())
+    }
 }
 
 /// Type of stock transaction
@@ -27,6 +615,59 @@ pub enum TransactionType {
     Addition,
     /// Stock removal
     Removal,
+    /// Opening balance recorded when migrating from another system
+    InitialStock,
+    /// Stock lost to damage
+    Damage,
+    /// Stock written off, e.g. expired, obsolete, or missing
+    WriteOff,
+    /// Stock-count correction reducing the recorded quantity
+    Adjustment,
+    /// Available stock put on hold, e.g. awaiting QC
+    Quarantine,
+    /// Quarantined stock made available again
+    ReleaseQuarantine,
+    /// Available stock moved from one condition to another, e.g. new to damaged
+    ConditionChange,
+}
+
+impl TransactionType {
+    /// Whether the transaction increases the available quantity
+    pub fn adds_stock(self) -> bool {
+        matches!(
+            self,
+            TransactionType::Addition | TransactionType::InitialStock | TransactionType::ReleaseQuarantine
+        )
+    }
+
+    /// Whether the transaction moves stock between quarantine or conditions
+    /// rather than in or out of the inventory
+    pub fn is_internal_move(self) -> bool {
+        matches!(
+            self,
+            TransactionType::Quarantine | TransactionType::ReleaseQuarantine | TransactionType::ConditionChange
+        )
+    }
+
+    /// Whether the transaction records lost stock rather than stock issued
+    pub fn is_shrinkage(self) -> bool {
+        matches!(self, TransactionType::Damage | TransactionType::WriteOff | TransactionType::Adjustment)
+    }
+
+    /// Lowercase name used on the command line and in reports, e.g. `write-off`
+    pub fn name(self) -> &'static str {
+        match self {
+            TransactionType::Addition => "addition",
+            TransactionType::Removal => "removal",
+            TransactionType::InitialStock => "initial-stock",
+            TransactionType::Damage => "damage",
+            TransactionType::WriteOff => "write-off",
+            TransactionType::Adjustment => "adjustment",
+            TransactionType::Quarantine => "quarantine",
+            TransactionType::ReleaseQuarantine => "release-quarantine",
+            TransactionType::ConditionChange => "condition-change",
+        }
+    }
 }
 
 impl std::fmt::Display for TransactionType {
@@ -34,10 +675,87 @@ impl std::fmt::Display for TransactionType {
         match self {
             TransactionType::Addition => write!(f, "Addition"),
             TransactionType::Removal => write!(f, "Removal"),
+            TransactionType::InitialStock => write!(f, "InitialStock"),
+            TransactionType::Damage => write!(f, "Damage"),
+            TransactionType::WriteOff => write!(f, "WriteOff"),
+            TransactionType::Adjustment => write!(f, "Adjustment"),
+            TransactionType::Quarantine => write!(f, "Quarantine"),
+            TransactionType::ReleaseQuarantine => write!(f, "ReleaseQuarantine"),
+            TransactionType::ConditionChange => write!(f, "ConditionChange"),
         }
     }
 }
 
+impl std::str::FromStr for TransactionType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        const TYPES: [TransactionType; 9] = [
+            TransactionType::Addition,
+            TransactionType::Removal,
+            TransactionType::InitialStock,
+            TransactionType::Damage,
+            TransactionType::WriteOff,
+            TransactionType::Adjustment,
+            TransactionType::Quarantine,
+            TransactionType::ReleaseQuarantine,
+            TransactionType::ConditionChange,
+        ];
+        let lower = s.to_lowercase();
+        TYPES.into_iter().find(|t| t.name() == lower).ok_or_else(|| {
+            let names: Vec<&str> = TYPES.iter().map(|t| t.name()).collect();
+            format!("Invalid type '{}': expected one of {}", s, names.join(", "))
+        })
+    }
+}
+
+/// Why stock was removed, recorded with `--reason`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReasonCode {
+    /// Sold to a customer
+    Sale,
+    /// Used within the business
+    InternalUse,
+    /// Given away as a sample
+    Sample,
+    /// Damaged and no longer usable
+    Damage,
+}
+
+impl ReasonCode {
+    /// Every reason code, in report order
+    pub const ALL: [ReasonCode; 4] = [ReasonCode::Sale, ReasonCode::InternalUse, ReasonCode::Sample, ReasonCode::Damage];
+
+    /// Name used on the command line and in storage, e.g. `internal-use`
+    pub fn name(self) -> &'static str {
+        match self {
+            ReasonCode::Sale => "sale",
+            ReasonCode::InternalUse => "internal-use",
+            ReasonCode::Sample => "sample",
+            ReasonCode::Damage => "damage",
+        }
+    }
+}
+
+impl std::fmt::Display for ReasonCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for ReasonCode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        ReasonCode::ALL.into_iter().find(|reason| reason.name() == lower).ok_or_else(|| {
+            let names: Vec<&str> = ReasonCode::ALL.iter().map(|reason| reason.name()).collect();
+            format!("Invalid reason '{}': expected one of {}", s, names.join(", "))
+        })
+    }
+}
+
 /// Represents a stock transaction (addition or removal)
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Transaction {
@@ -45,7 +763,7 @@ pub struct Transaction {
     pub id: String,
     /// SKU of the product this transaction affects
     pub product_sku: String,
-    /// Type of transaction (Addition or Removal)
+    /// Type of transaction (stock in, stock out, or a loss such as Damage)
     pub transaction_type: TransactionType,
     /// Amount of stock added or removed
     pub quantity: u32,
@@ -53,4 +771,692 @@ pub struct Transaction {
     pub timestamp: DateTime<Utc>,
     /// Optional notes about the transaction
     pub notes: Option<String>,
+    /// Who recorded the transaction, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operator: Option<String>,
+    /// Why stock was removed, if a reason code was given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<ReasonCode>,
+    /// Condition of the stock added or removed, or moved to by a condition
+    /// change; stock without one is new
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<Condition>,
+    /// Condition a condition change moved stock from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from_condition: Option<Condition>,
+    /// Cost of one unit of the stock received, for FIFO costing
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit_cost: Option<Money>,
+    /// Code of the customer the stock was shipped to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub customer: Option<String>,
+    /// SHA-256 of the transaction recorded before this one, when the ledger
+    /// is hash-chained
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev_hash: Option<String>,
+    /// Order in which the transaction was recorded, increasing by one with
+    /// each; 0 for transactions recorded before sequence numbers
+    #[serde(default, skip_serializing_if = "is_unsequenced")]
+    pub seq: u64,
+    /// Fields this version doesn't know, kept so saving doesn't drop them
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl Transaction {
+    /// Create a transaction with a new UUID, timestamped now
+    pub fn new(
+        product_sku: &str,
+        transaction_type: TransactionType,
+        quantity: u32,
+        notes: Option<String>,
+    ) -> Self {
+        Transaction {
+            id: Uuid::new_v4().to_string(),
+            product_sku: product_sku.to_string(),
+            transaction_type,
+            quantity,
+            timestamp: Utc::now(),
+            notes,
+            operator: None,
+            reason: None,
+            condition: None,
+            from_condition: None,
+            unit_cost: None,
+            customer: None,
+            prev_hash: None,
+            seq: 0,
+            extra: Map::new(),
+        }
+    }
+
+    /// Key that orders transactions by timestamp, and those with the same
+    /// timestamp in the order they were recorded
+    pub fn order_key(&self) -> (DateTime<Utc>, u64) {
+        (self.timestamp, self.seq)
+    }
+}
+
+/// Limit above which a single stock removal counts as a large movement
+///
+/// Written as a plain number of units (`"100"`) or a percentage of the
+/// stock on hand (`"25%"`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum MovementThreshold {
+    /// More than this many units
+    Units(u32),
+    /// More than this percentage of the available stock
+    Percent(u32),
+}
+
+impl MovementThreshold {
+    /// Whether removing `quantity` units out of `available` exceeds the threshold
+    pub fn is_exceeded(&self, quantity: u32, available: u32) -> bool {
+        match *self {
+            MovementThreshold::Units(limit) => quantity > limit,
+            MovementThreshold::Percent(percent) => {
+                u64::from(quantity) * 100 > u64::from(available) * u64::from(percent)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for MovementThreshold {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MovementThreshold::Units(limit) => write!(f, "{}", limit),
+            MovementThreshold::Percent(percent) => write!(f, "{}%", percent),
+        }
+    }
+}
+
+impl std::str::FromStr for MovementThreshold {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid threshold '{}': expected units (e.g. 100) or a percentage (e.g. 25%)", s);
+        match s.strip_suffix('%') {
+            Some(percent) => percent.parse().map(MovementThreshold::Percent).map_err(|_| invalid()),
+            None => s.parse().map(MovementThreshold::Units).map_err(|_| invalid()),
+        }
+    }
+}
+
+impl TryFrom<String> for MovementThreshold {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<MovementThreshold> for String {
+    fn from(threshold: MovementThreshold) -> Self {
+        threshold.to_string()
+    }
+}
+
+/// An amount of money, held exactly in cents
+///
+/// Written with up to two decimals (`"12.5"`, `"12.50"`); stored as a string
+/// in the same form so the data files stay readable.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Money(u64);
+
+impl Money {
+    /// The amount of `cents` hundredths
+    pub fn from_cents(cents: u64) -> Self {
+        Money(cents)
+    }
+
+    /// The amount in cents
+    pub fn cents(self) -> u64 {
+        self.0
+    }
+
+    /// The cost of `units` at this price, saturating at the largest amount
+    pub fn times(self, units: u64) -> Money {
+        Money(self.0.saturating_mul(units))
+    }
+}
+
+impl std::ops::Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0.saturating_add(other.0))
+    }
+}
+
+impl std::ops::Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money(self.0.saturating_sub(other.0))
+    }
+}
+
+impl std::iter::Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::default(), |total, amount| total + amount)
+    }
+}
+
+impl std::fmt::Display for Money {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{:02}", self.0 / 100, self.0 % 100)
+    }
+}
+
+impl std::str::FromStr for Money {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid amount '{}': expected a non-negative number with up to two decimals", s);
+        let (whole, fraction) = s.trim().split_once('.').unwrap_or((s.trim(), ""));
+        let digits = |part: &str| part.chars().all(|c| c.is_ascii_digit());
+        if whole.is_empty() || !digits(whole) || !digits(fraction) || fraction.len() > 2 {
+            return Err(invalid());
+        }
+        let whole: u64 = whole.parse().map_err(|_| invalid())?;
+        let fraction: u64 = format!("{:0<2}", fraction).parse().map_err(|_| invalid())?;
+        whole.checked_mul(100).and_then(|cents| cents.checked_add(fraction)).map(Money).ok_or_else(invalid)
+    }
+}
+
+impl TryFrom<String> for Money {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Money> for String {
+    fn from(amount: Money) -> Self {
+        amount.to_string()
+    }
+}
+
+/// A weight, kept in whole milligrams
+///
+/// Written as a number with a `g` or `kg` unit (`"250g"`, `"1.5 kg"`); stored
+/// as a string in kilograms, or in grams when it isn't a whole gram.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Weight(u64);
+
+impl Weight {
+    /// The weight of `milligrams` milligrams
+    pub fn from_milligrams(milligrams: u64) -> Self {
+        Weight(milligrams)
+    }
+
+    /// The weight in milligrams
+    pub fn milligrams(self) -> u64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for Weight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.0.is_multiple_of(1000) {
+            write!(f, "{}.{:03} kg", self.0 / 1_000_000, self.0 / 1000 % 1000)
+        } else {
+            write!(f, "{}.{:03} g", self.0 / 1000, self.0 % 1000)
+        }
+    }
+}
+
+impl std::str::FromStr for Weight {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid weight '{}': expected a number with a unit, e.g. 250g or 1.5kg", s);
+        let text = s.trim().to_lowercase();
+        let number_end = text.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(text.len());
+        let (number, unit) = text.split_at(number_end);
+        let milligrams_per_unit: u64 = match unit.trim() {
+            "kg" => 1_000_000,
+            "g" => 1000,
+            _ => return Err(invalid()),
+        };
+        let (whole, fraction) = number.split_once('.').unwrap_or((number, ""));
+        if whole.is_empty() || fraction.contains('.') || fraction.len() > 6 {
+            return Err(invalid());
+        }
+        let whole: u64 = whole.parse().map_err(|_| invalid())?;
+        let millionths: u64 = format!("{:0<6}", fraction).parse().map_err(|_| invalid())?;
+        let fraction = millionths * milligrams_per_unit;
+        if !fraction.is_multiple_of(1_000_000) {
+            return Err(format!("Invalid weight '{}': weights are kept to the milligram", s));
+        }
+        whole
+            .checked_mul(milligrams_per_unit)
+            .and_then(|milligrams| milligrams.checked_add(fraction / 1_000_000))
+            .map(Weight)
+            .ok_or_else(invalid)
+    }
+}
+
+impl TryFrom<String> for Weight {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<Weight> for String {
+    fn from(weight: Weight) -> Self {
+        weight.to_string()
+    }
+}
+
+/// Length of the periods in a report over time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Interval {
+    /// Calendar days
+    Day,
+    /// Weeks starting on Monday
+    Week,
+    /// Calendar months
+    #[default]
+    Month,
+    /// Calendar quarters
+    Quarter,
+    /// Calendar years
+    Year,
+}
+
+impl Interval {
+    /// Every interval, shortest first
+    pub const ALL: [Interval; 5] = [Interval::Day, Interval::Week, Interval::Month, Interval::Quarter, Interval::Year];
+
+    /// Name used on the command line
+    pub fn name(self) -> &'static str {
+        match self {
+            Interval::Day => "day",
+            Interval::Week => "week",
+            Interval::Month => "month",
+            Interval::Quarter => "quarter",
+            Interval::Year => "year",
+        }
+    }
+
+    /// First day of the period containing `date`
+    pub fn start_of(self, date: NaiveDate) -> NaiveDate {
+        let first_of_month = |month: u32| NaiveDate::from_ymd_opt(date.year(), month, 1).expect("valid month");
+        match self {
+            Interval::Day => date,
+            Interval::Week => date - Duration::days(i64::from(date.weekday().num_days_from_monday())),
+            Interval::Month => first_of_month(date.month()),
+            Interval::Quarter => first_of_month((date.month() - 1) / 3 * 3 + 1),
+            Interval::Year => first_of_month(1),
+        }
+    }
+
+    /// First day of the period after the one starting on `start`
+    pub fn next(self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Interval::Day => start + Duration::days(1),
+            Interval::Week => start + Duration::days(7),
+            Interval::Month => start + Months::new(1),
+            Interval::Quarter => start + Months::new(3),
+            Interval::Year => start + Months::new(12),
+        }
+    }
+}
+
+impl std::fmt::Display for Interval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl std::str::FromStr for Interval {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_lowercase();
+        Interval::ALL.into_iter().find(|interval| interval.name() == lower).ok_or_else(|| {
+            let names: Vec<&str> = Interval::ALL.iter().map(|interval| interval.name()).collect();
+            format!("Invalid interval '{}': expected one of {}", s, names.join(", "))
+        })
+    }
+}
+
+/// Safeguards applied when deleting a product, read from the `deletion`
+/// section of the config file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeletionPolicy {
+    /// Refuse to delete a product with transactions in this many days (0 disables the check)
+    pub recent_activity_days: u32,
+    /// Keep deleted products and their history in the trash (the tombstone file)
+    pub archive: bool,
+    /// Days a deleted product stays restorable before it is purged (0 keeps it forever)
+    pub trash_retention_days: u32,
+}
+
+impl Default for DeletionPolicy {
+    fn default() -> Self {
+        DeletionPolicy { recent_activity_days: 30, archive: true, trash_retention_days: 30 }
+    }
+}
+
+/// How long personal data is kept, read from the `retention` section of the
+/// config file and applied by `retention apply`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// Clear notes on transactions and backorders older than this many years
+    #[serde(deserialize_with = "retention_years")]
+    pub notes_years: Option<u32>,
+    /// Clear customer codes on transactions and backorders older than this many years
+    #[serde(deserialize_with = "retention_years")]
+    pub customer_years: Option<u32>,
+}
+
+/// Longest retention period accepted in the config file, in years
+pub const MAX_RETENTION_YEARS: u32 = 1000;
+
+/// Read a retention period, rejecting ones too long to count back from today
+fn retention_years<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<u32>, D::Error> {
+    match Option::<u32>::deserialize(deserializer)? {
+        Some(years) if years > MAX_RETENTION_YEARS => Err(serde::de::Error::custom(format!(
+            "retention of {} years is longer than the {} years allowed",
+            years, MAX_RETENTION_YEARS
+        ))),
+        years => Ok(years),
+    }
+}
+
+impl RetentionPolicy {
+    /// Whether any rule is set
+    pub fn is_empty(&self) -> bool {
+        self.notes_years.is_none() && self.customer_years.is_none()
+    }
+}
+
+/// How far a count may differ from the recorded stock and still be applied
+/// without a second count or confirmation, read from the `stocktake`
+/// section of the config file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StocktakePolicy {
+    /// Largest variance, in units, applied as counted
+    pub tolerance_units: Option<u32>,
+    /// Largest variance, as a percentage of the recorded quantity, applied as counted
+    pub tolerance_percent: Option<f64>,
+}
+
+impl StocktakePolicy {
+    /// Whether counting `counted` units of a product recorded at `recorded`
+    /// is within every tolerance set; with none set, every count is
+    pub fn within_tolerance(&self, recorded: u32, counted: u32) -> bool {
+        let variance = recorded.abs_diff(counted);
+        self.tolerance_units.is_none_or(|units| variance <= units)
+            && self.tolerance_percent.is_none_or(|percent| f64::from(variance) <= f64::from(recorded) * percent / 100.0)
+    }
+}
+
+/// What happens to a transaction timestamped outside the clock tolerance
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClockAction {
+    /// Record it and raise an event
+    #[default]
+    Warn,
+    /// Refuse to record it
+    Reject,
+}
+
+/// Guards against clock drift, read from the `clock` section of the config
+/// file
+///
+/// A product whose history already holds a transaction later than now plus
+/// the tolerance, as when machines sharing the data disagree about the time,
+/// gets a warning or refusal before more is recorded for it. The same goes
+/// for imported movements dated before the product was added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClockPolicy {
+    /// Seconds a timestamp may be off before it counts
+    pub tolerance_seconds: u32,
+    /// `warn` (the default) or `reject`
+    pub action: ClockAction,
+}
+
+impl Default for ClockPolicy {
+    fn default() -> Self {
+        ClockPolicy { tolerance_seconds: 300, action: ClockAction::Warn }
+    }
+}
+
+impl ClockPolicy {
+    /// The tolerance as a duration
+    pub fn tolerance(&self) -> chrono::Duration {
+        chrono::Duration::seconds(i64::from(self.tolerance_seconds))
+    }
+}
+
+/// Soft limits on the size of the inventory, read from the `quotas` section
+/// of the config file
+///
+/// Nothing is refused at a limit: `stats` reports usage, and a change that
+/// leaves usage at or above `warn_percent` of a limit raises an event, as a
+/// nudge to archive old transactions before the JSON files grow unwieldy.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SizeQuotas {
+    /// Number of products
+    pub max_products: Option<u64>,
+    /// Number of live (unarchived) transactions
+    pub max_transactions: Option<u64>,
+    /// Combined size of the data files in bytes
+    pub max_data_bytes: Option<u64>,
+    /// Percentage of a limit at which warnings start (default 80)
+    pub warn_percent: u8,
+}
+
+impl Default for SizeQuotas {
+    fn default() -> Self {
+        SizeQuotas { max_products: None, max_transactions: None, max_data_bytes: None, warn_percent: 80 }
+    }
+}
+
+impl SizeQuotas {
+    /// Whether any limit is set
+    pub fn is_empty(&self) -> bool {
+        self.max_products.is_none() && self.max_transactions.is_none() && self.max_data_bytes.is_none()
+    }
+}
+
+/// What a size quota limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quota {
+    Products,
+    Transactions,
+    DataBytes,
+}
+
+impl std::fmt::Display for Quota {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Quota::Products => write!(f, "products"),
+            Quota::Transactions => write!(f, "transactions"),
+            Quota::DataBytes => write!(f, "data bytes"),
+        }
+    }
+}
+
+/// How much of a size quota is used
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaUsage {
+    pub quota: Quota,
+    pub used: u64,
+    pub limit: u64,
+}
+
+impl QuotaUsage {
+    /// Usage as a whole percentage of the limit, rounded down
+    pub fn percent(&self) -> u64 {
+        if self.limit == 0 {
+            return 100;
+        }
+        (u128::from(self.used) * 100 / u128::from(self.limit)) as u64
+    }
+}
+
+/// When the service writes changes to the data files
+///
+/// In config.json: `"immediate"`, `{ "debounce_ms": 500 }`, or `"manual"`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SaveMode {
+    /// After every operation
+    #[default]
+    Immediate,
+    /// At most once per this many milliseconds; changes made in between are
+    /// saved with the next change after the window, or on flush
+    DebounceMs(u64),
+    /// Only when flushed
+    Manual,
+}
+
+/// Newest link of the transaction hash chain
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LedgerHead {
+    /// SHA-256 of the newest chained transaction
+    pub hash: String,
+    /// Number of transactions on the chain
+    pub count: usize,
+}
+
+/// A deleted product together with the history removed with it
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Tombstone {
+    /// The product as it was when deleted
+    pub product: Product,
+    /// Transactions of the product, oldest first
+    pub transactions: Vec<Transaction>,
+    /// When the product was deleted
+    pub deleted_at: DateTime<Utc>,
+    /// Who deleted the product, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_by: Option<String>,
+}
+
+impl Tombstone {
+    /// When the tombstone is purged from the trash, given a retention in days (0 = never)
+    pub fn expires_at(&self, retention_days: u32) -> Option<DateTime<Utc>> {
+        (retention_days > 0).then(|| self.deleted_at + chrono::Duration::days(i64::from(retention_days)))
+    }
+}
+
+/// Goods being received, staged until the receipt is committed
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReceivingSession {
+    /// Delivery or purchase order reference, if given
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference: Option<String>,
+    /// When receiving started
+    pub started_at: DateTime<Utc>,
+    /// Who started receiving, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_by: Option<String>,
+    /// Quantities the delivery should contain
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub expected: Vec<ReceiptLine>,
+    /// Quantities counted so far, in the order they were staged
+    #[serde(default)]
+    pub received: Vec<ReceiptLine>,
+}
+
+/// A quantity of one product expected or counted in a delivery
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReceiptLine {
+    /// Product SKU
+    pub sku: String,
+    /// Units
+    pub quantity: u32,
+    /// Optional notes, carried over to the Addition transaction
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+/// Someone stock is shipped to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Customer {
+    /// Short code used on the command line, e.g. `ACME`
+    pub code: String,
+    /// Full name
+    pub name: String,
+    /// When the customer was added
+    pub created_at: DateTime<Utc>,
+}
+
+/// Units of a product owed to someone because a removal couldn't be filled
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Backorder {
+    /// Unique identifier (UUID)
+    pub id: String,
+    /// Product SKU
+    pub sku: String,
+    /// Units still owed
+    pub quantity: u32,
+    /// When the backorder was recorded
+    pub created_at: DateTime<Utc>,
+    /// Who recorded the backorder, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_by: Option<String>,
+    /// Optional notes, carried over to the Removal transactions that fill it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    /// Code of the customer the units are owed to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub customer: Option<String>,
+}
+
+impl Backorder {
+    /// First 8 characters of the ID, as shown in listings
+    pub fn short_id(&self) -> &str {
+        &self.id[..self.id.len().min(8)]
+    }
+}
+
+/// A supplier's price for one product, in force from a given date
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SupplierPrice {
+    /// Supplier name, as given to `import-prices`
+    pub supplier: String,
+    /// Product SKU
+    pub sku: String,
+    /// Price of one unit
+    pub unit_cost: Money,
+    /// First day the price applies
+    pub effective_from: NaiveDate,
+}
+
+/// Inventory totals for one day, recorded by `snapshot-kpis`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct KpiSnapshot {
+    /// Day the totals describe (UTC)
+    pub date: NaiveDate,
+    /// When the totals were taken; a later snapshot on the same day replaces it
+    pub taken_at: DateTime<Utc>,
+    /// Number of products
+    pub products: usize,
+    /// Units available
+    pub units: u64,
+    /// FIFO value of the stock on hand with a known cost
+    pub value: Money,
+    /// Products at or below their reorder point
+    pub low_stock: usize,
+    /// Transactions recorded on the day
+    pub movements: usize,
 }
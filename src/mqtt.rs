@@ -0,0 +1,166 @@
+// Inventory events and stock levels published to an MQTT broker
+//
+// After each command, every product whose available quantity changed gets a
+// retained message on `{prefix}/{sku}/quantity` holding the new quantity, and
+// each event raised is sent as JSON on `{prefix}/{sku}/events`. Retained
+// quantities let a dashboard or home-automation rule that subscribes later
+// see the current level straight away. Connecting to the broker needs the
+// `mqtt` feature.
+//
+// Events are sent in the `api::v1` shape.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::api;
+use crate::events::InventoryEvent;
+
+/// Topic prefix used when the configuration doesn't give one
+pub const DEFAULT_TOPIC_PREFIX: &str = "inventory";
+
+/// Port used when the broker address doesn't give one
+const DEFAULT_BROKER_PORT: u16 = 1883;
+
+/// Settings for publishing to an MQTT broker
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttConfig {
+    /// Broker address as `host[:port]`; nothing is published without one
+    pub broker: Option<String>,
+    /// First level of every topic (default `inventory`)
+    pub topic_prefix: Option<String>,
+    /// Client ID to connect with (default `stock-control`)
+    pub client_id: Option<String>,
+}
+
+impl MqttConfig {
+    /// The broker's host and port, if one is configured
+    pub fn broker_address(&self) -> Option<(String, u16)> {
+        let broker = self.broker.as_deref()?.trim();
+        let broker = broker.strip_prefix("mqtt://").or_else(|| broker.strip_prefix("tcp://")).unwrap_or(broker);
+        if broker.is_empty() {
+            return None;
+        }
+        Some(match broker.rsplit_once(':').map(|(host, port)| (host, port.parse::<u16>())) {
+            Some((host, Ok(port))) => (host.to_string(), port),
+            _ => (broker.to_string(), DEFAULT_BROKER_PORT),
+        })
+    }
+}
+
+/// One message to publish
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub topic: String,
+    pub payload: String,
+    /// Whether the broker keeps it for later subscribers
+    pub retain: bool,
+}
+
+/// Messages for the quantities that changed and the events raised
+///
+/// `before` and `after` map SKUs to available quantities; products missing
+/// from `after` were deleted and aren't published.
+pub fn messages(
+    prefix: &str,
+    before: &BTreeMap<String, u32>,
+    after: &BTreeMap<String, u32>,
+    events: &[InventoryEvent],
+) -> Vec<Message> {
+    let prefix = prefix.trim_end_matches('/');
+    let mut messages: Vec<Message> = after
+        .iter()
+        .filter(|(sku, quantity)| before.get(*sku) != Some(quantity))
+        .map(|(sku, quantity)| Message {
+            topic: format!("{}/{}/quantity", prefix, sku),
+            payload: quantity.to_string(),
+            retain: true,
+        })
+        .collect();
+    messages.extend(events.iter().filter_map(|event| {
+        Some(Message {
+            topic: match event.sku() {
+                Some(sku) => format!("{}/{}/events", prefix, sku),
+                None => format!("{}/events", prefix),
+            },
+            payload: serde_json::to_string(&api::v1::Event::from(event)).ok()?,
+            retain: false,
+        })
+    }));
+    messages
+}
+
+/// Connect to the broker, publish the messages, and disconnect
+#[cfg(feature = "mqtt")]
+pub fn publish(config: &MqttConfig, messages: &[Message]) -> Result<(), String> {
+    use std::time::Duration;
+
+    use rumqttc::{Client, Event, MqttOptions, Outgoing, QoS};
+
+    let Some((host, port)) = config.broker_address() else {
+        return Ok(());
+    };
+    if messages.is_empty() {
+        return Ok(());
+    }
+    let client_id = config.client_id.as_deref().unwrap_or("stock-control");
+    let mut options = MqttOptions::new(client_id, host.as_str(), port);
+    options.set_keep_alive(Duration::from_secs(10));
+    let (client, mut connection) = Client::new(options, messages.len() + 1);
+    let failed = |e: &dyn std::fmt::Display| format!("Failed to publish to MQTT broker {}:{}: {}", host, port, e);
+
+    for message in messages {
+        client
+            .publish(message.topic.as_str(), QoS::AtLeastOnce, message.retain, message.payload.as_bytes())
+            .map_err(|e| failed(&e))?;
+    }
+    client.disconnect().map_err(|e| failed(&e))?;
+    for notification in connection.iter() {
+        match notification {
+            Ok(Event::Outgoing(Outgoing::Disconnect)) => return Ok(()),
+            Ok(_) => {}
+            Err(e) => return Err(failed(&e)),
+        }
+    }
+    Ok(())
+}
+
+/// Without the `mqtt` feature there's no client to publish with
+#[cfg(not(feature = "mqtt"))]
+pub fn publish(config: &MqttConfig, messages: &[Message]) -> Result<(), String> {
+    if config.broker_address().is_none() || messages.is_empty() {
+        return Ok(());
+    }
+    Err("mqtt.broker is set in config.json, but this build can't publish to MQTT; rebuild with --features mqtt"
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    #[test]
+    fn test_messages_for_changed_quantities_and_events() {
+        let before = BTreeMap::from([("A".to_string(), 5), ("B".to_string(), 7), ("GONE".to_string(), 1)]);
+        let after = BTreeMap::from([("A".to_string(), 3), ("B".to_string(), 7), ("NEW".to_string(), 0)]);
+        let event = InventoryEvent::BackordersWaiting {
+            sku: "A".to_string(),
+            available: 3,
+            outstanding: 4,
+            timestamp: Utc::now(),
+        };
+
+        let messages = messages("inventory/", &before, &after, &[event]);
+        let topics: Vec<(&str, &str, bool)> =
+            messages.iter().map(|m| (m.topic.as_str(), m.payload.as_str(), m.retain)).collect();
+        assert_eq!(topics[..2], [("inventory/A/quantity", "3", true), ("inventory/NEW/quantity", "0", true)]);
+        assert_eq!(topics[2].0, "inventory/A/events");
+        assert!(topics[2].1.starts_with(r#"{"type":"backorders_waiting","sku":"A""#));
+
+        let config = |broker: &str| MqttConfig { broker: Some(broker.to_string()), ..Default::default() };
+        assert_eq!(config("mqtt://broker.local").broker_address(), Some(("broker.local".to_string(), 1883)));
+        assert_eq!(config("10.0.0.2:8883").broker_address(), Some(("10.0.0.2".to_string(), 8883)));
+    }
+}
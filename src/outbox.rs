@@ -0,0 +1,154 @@
+// Outbox of raised events for systems that consume them
+//
+// After each command, every event it raised is appended to events.ndjson in
+// the data directory, one JSON object per line in the `api::v1` shape with a
+// `seq` added:
+//
+//     {"seq":42,"type":"large_movement","sku":"SKU001","quantity":120,...}
+//
+// Sequence numbers start at 1 and increase by one with each event, and lines
+// are never rewritten, so a consumer that was offline only needs to remember
+// the last number it handled and ask `events tail --since <seq>` for the
+// rest.
+
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::api;
+use crate::events::InventoryEvent;
+
+/// File in the data directory holding the outbox
+pub const OUTBOX_FILE: &str = "events.ndjson";
+
+/// One line of the outbox
+#[derive(Serialize)]
+struct Record {
+    seq: u64,
+    #[serde(flatten)]
+    event: api::v1::Event,
+}
+
+/// Append events to the outbox, numbering them after the last one there,
+/// and return the number of the last one appended
+///
+/// The file is locked from reading the last number until the new lines are
+/// written, so commands run at the same time never hand out the same number.
+pub fn append(data_dir: &Path, events: &[InventoryEvent]) -> Result<u64, String> {
+    if events.is_empty() {
+        return last_seq(data_dir);
+    }
+    let path = data_dir.join(OUTBOX_FILE);
+    let failed = |e: std::io::Error| format!("Failed to write {}: {}", path.display(), e);
+    let mut file = OpenOptions::new().read(true).append(true).create(true).open(&path).map_err(failed)?;
+    file.lock().map_err(failed)?;
+    let mut seq = read_from(&path, &mut file)?.last().map_or(0, |(seq, _)| *seq);
+    let mut lines = String::new();
+    for event in events {
+        seq += 1;
+        let record = Record { seq, event: api::v1::Event::from(event) };
+        lines.push_str(&serde_json::to_string(&record).map_err(|e| e.to_string())?);
+        lines.push('\n');
+    }
+    file.write_all(lines.as_bytes()).map_err(failed)?;
+    Ok(seq)
+}
+
+/// Lines of the outbox numbered after `since`, oldest first, at most `limit` of them
+pub fn since(data_dir: &Path, since: u64, limit: Option<usize>) -> Result<Vec<String>, String> {
+    let lines = read(data_dir)?
+        .into_iter()
+        .filter(|(seq, _)| *seq > since)
+        .map(|(_, line)| line)
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    Ok(lines)
+}
+
+/// Number of the last event in the outbox; 0 when it is empty
+pub fn last_seq(data_dir: &Path) -> Result<u64, String> {
+    Ok(read(data_dir)?.last().map_or(0, |(seq, _)| *seq))
+}
+
+/// Every line of the outbox with its number, read under a shared lock so an
+/// append in progress isn't seen half written
+fn read(data_dir: &Path) -> Result<Vec<(u64, String)>, String> {
+    let path = data_dir.join(OUTBOX_FILE);
+    let mut file = match File::open(&path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+    };
+    file.lock_shared().map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    read_from(&path, &mut file)
+}
+
+/// Every line of an open outbox file with its number
+fn read_from(path: &Path, file: &mut File) -> Result<Vec<(u64, String)>, String> {
+    let mut contents = String::new();
+    file.read_to_string(&mut contents).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    contents
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            let seq = serde_json::from_str::<Value>(line).ok().and_then(|record| record.get("seq")?.as_u64());
+            let seq = seq.ok_or_else(|| format!("{} line {} has no sequence number", path.display(), index + 1))?;
+            Ok((seq, line.to_string()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_events_numbered_and_read_since() {
+        let temp_dir = TempDir::new().unwrap();
+        let event = |sku: &str| InventoryEvent::BackordersWaiting {
+            sku: sku.to_string(),
+            available: 1,
+            outstanding: 2,
+            timestamp: Utc::now(),
+        };
+        assert_eq!(append(temp_dir.path(), &[]).unwrap(), 0);
+        assert_eq!(append(temp_dir.path(), &[event("A"), event("B")]).unwrap(), 2);
+        assert_eq!(append(temp_dir.path(), &[event("C")]).unwrap(), 3);
+
+        let lines = since(temp_dir.path(), 1, None).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with(r#"{"seq":2,"type":"backorders_waiting","sku":"B""#), "{}", lines[0]);
+        assert_eq!(since(temp_dir.path(), 0, Some(1)).unwrap().len(), 1);
+        assert!(since(temp_dir.path(), 3, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_appends_get_unique_numbers() {
+        let temp_dir = TempDir::new().unwrap();
+        let event = InventoryEvent::BackordersWaiting {
+            sku: "A".to_string(),
+            available: 1,
+            outstanding: 2,
+            timestamp: Utc::now(),
+        };
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..10 {
+                        append(temp_dir.path(), std::slice::from_ref(&event)).unwrap();
+                    }
+                });
+            }
+        });
+
+        let mut seqs: Vec<u64> = read(temp_dir.path()).unwrap().into_iter().map(|(seq, _)| seq).collect();
+        seqs.sort_unstable();
+        assert_eq!(seqs, (1..=80).collect::<Vec<u64>>());
+    }
+}
@@ -0,0 +1,149 @@
+// External subcommands found on PATH
+//
+// As with git, an executable named `stock-control-<name>` anywhere on PATH
+// adds a `<name>` command: `stock-control label-print SKU001` runs
+// `stock-control-label-print SKU001`. Built-in commands, groups, and aliases
+// always win, so a plugin can't shadow one. The plugin gets the arguments
+// after its name, with global options already taken out, and finds the rest
+// of its context in the environment:
+//
+//     STOCK_DATA_DIR   absolute path of the data directory
+//     STOCK_QUIET      "1" when -q/--quiet was given, otherwise "0"
+//     STOCK_VERBOSITY  number of -v flags given
+//
+// It runs in the caller's working directory with the caller's stdin, stdout,
+// and stderr, and a nonzero exit status fails the command.
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::cli::GlobalOptions;
+
+/// Prefix a plugin executable's file name starts with
+pub const PREFIX: &str = "stock-control-";
+
+/// Extensions tried after the bare name when looking a plugin up
+#[cfg(windows)]
+const EXTENSIONS: &[&str] = &["", ".exe", ".bat", ".cmd"];
+#[cfg(not(windows))]
+const EXTENSIONS: &[&str] = &[""];
+
+/// Executable for the plugin `name` in the first directory of `path_var`
+/// (a PATH-style list) that has one
+pub fn find(name: &str, path_var: &OsStr) -> Option<PathBuf> {
+    if !is_valid_name(name) {
+        return None;
+    }
+    std::env::split_paths(path_var).find_map(|dir| {
+        EXTENSIONS
+            .iter()
+            .map(|extension| dir.join(format!("{}{}{}", PREFIX, name, extension)))
+            .find(|candidate| is_executable(candidate))
+    })
+}
+
+/// Every plugin on `path_var` by name, with the executable that runs it;
+/// earlier directories win, as when running one
+pub fn discover(path_var: &OsStr) -> BTreeMap<String, PathBuf> {
+    let mut plugins = BTreeMap::new();
+    for dir in std::env::split_paths(path_var) {
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let Some(name) = plugin_name(&path) else { continue };
+            if is_executable(&path) {
+                plugins.entry(name).or_insert(path);
+            }
+        }
+    }
+    plugins
+}
+
+/// Run a plugin with its arguments, handing it the global options and data
+/// directory through the environment
+pub fn run(executable: &Path, args: &[String], options: &GlobalOptions, data_dir: &Path) -> Result<(), String> {
+    let data_dir = std::fs::canonicalize(data_dir).unwrap_or_else(|_| data_dir.to_path_buf());
+    log::info!("Running plugin {}", executable.display());
+    let status = Command::new(executable)
+        .args(args)
+        .env("STOCK_DATA_DIR", &data_dir)
+        .env("STOCK_QUIET", if options.quiet { "1" } else { "0" })
+        .env("STOCK_VERBOSITY", options.verbosity.to_string())
+        .status()
+        .map_err(|e| format!("Failed to run plugin '{}': {}", executable.display(), e))?;
+    if !status.success() {
+        return Err(format!("Plugin '{}' failed: {}", executable.display(), status));
+    }
+    Ok(())
+}
+
+/// Whether a command name can name a plugin: no path separators, and not an option
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && !name.starts_with('-') && !name.contains(['/', '\\'])
+}
+
+/// Command name of a plugin executable, without its prefix or extension
+fn plugin_name(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    let name = file_name.strip_prefix(PREFIX)?;
+    let name = EXTENSIONS
+        .iter()
+        .filter(|extension| !extension.is_empty())
+        .find_map(|extension| name.strip_suffix(extension))
+        .unwrap_or(name);
+    is_valid_name(name).then(|| name.to_string())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata().is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+
+    fn write_script(dir: &Path, file_name: &str, body: &str, mode: u32) {
+        let path = dir.join(file_name);
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    #[test]
+    fn test_plugins_are_found_on_path_and_get_options_in_the_environment() {
+        let first = tempfile::TempDir::new().unwrap();
+        let second = tempfile::TempDir::new().unwrap();
+        let out = first.path().join("out.txt");
+        let body = format!("echo \"$* $STOCK_DATA_DIR $STOCK_QUIET $STOCK_VERBOSITY\" > '{}'", out.display());
+        write_script(first.path(), "stock-control-hello", &body, 0o755);
+        write_script(second.path(), "stock-control-hello", "exit 1", 0o755);
+        write_script(second.path(), "stock-control-fail", "exit 4", 0o755);
+        write_script(second.path(), "stock-control-notes", "", 0o644);
+        let path_var = std::env::join_paths([first.path(), second.path()]).unwrap();
+
+        // The first directory wins, and files that can't be run aren't plugins
+        let hello = find("hello", &path_var).unwrap();
+        assert_eq!(hello, first.path().join("stock-control-hello"));
+        assert_eq!(find("notes", &path_var), None);
+        assert_eq!(find("../stock-control-hello", &path_var), None);
+        let names: Vec<String> = discover(&path_var).into_keys().collect();
+        assert_eq!(names, ["fail", "hello"]);
+
+        let options = GlobalOptions { quiet: true, verbosity: 2, ..Default::default() };
+        run(&hello, &["a".to_string(), "b".to_string()], &options, second.path()).unwrap();
+        let data_dir = std::fs::canonicalize(second.path()).unwrap();
+        assert_eq!(std::fs::read_to_string(&out).unwrap(), format!("a b {} 1 2\n", data_dir.display()));
+
+        let error = run(&find("fail", &path_var).unwrap(), &[], &options, second.path()).unwrap_err();
+        assert!(error.ends_with("failed: exit status: 4"), "{}", error);
+    }
+}
@@ -0,0 +1,406 @@
+// Goods-received and goods-issued notes for stock movements
+
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::models::{Product, Transaction, TransactionType};
+
+/// Output format of a receipt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiptFormat {
+    /// Plain text
+    Text,
+    /// Single-page PDF
+    Pdf,
+    /// ZPL for Zebra label printers
+    Zpl,
+    /// ESC/POS for thermal receipt printers
+    EscPos,
+}
+
+impl ReceiptFormat {
+    /// Whether the format is binary and can't be shown on a terminal
+    pub fn is_binary(self) -> bool {
+        matches!(self, ReceiptFormat::Pdf | ReceiptFormat::EscPos)
+    }
+}
+
+impl std::str::FromStr for ReceiptFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" | "txt" => Ok(ReceiptFormat::Text),
+            "pdf" => Ok(ReceiptFormat::Pdf),
+            "zpl" => Ok(ReceiptFormat::Zpl),
+            "escpos" | "esc-pos" => Ok(ReceiptFormat::EscPos),
+            _ => Err(format!("Invalid receipt format '{}': expected text, pdf, zpl, or escpos", s)),
+        }
+    }
+}
+
+/// Port raw print jobs are sent to when a network printer address has none
+const RAW_PRINT_PORT: u16 = 9100;
+
+/// How long to wait for a network printer to accept a connection
+const PRINTER_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Where a print job is sent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Printer {
+    /// A network printer taking raw jobs, as `host:port`
+    Tcp(String),
+    /// A printer device file, e.g. `/dev/usb/lp0`
+    Device(PathBuf),
+}
+
+impl Printer {
+    /// Send a print job to the printer
+    pub fn send(&self, job: &[u8]) -> std::io::Result<()> {
+        match self {
+            Printer::Tcp(address) => {
+                let addresses = address.to_socket_addrs()?;
+                let mut last_error = None;
+                for socket in addresses {
+                    match TcpStream::connect_timeout(&socket, PRINTER_TIMEOUT) {
+                        Ok(mut stream) => {
+                            stream.set_write_timeout(Some(PRINTER_TIMEOUT))?;
+                            return stream.write_all(job);
+                        }
+                        Err(e) => last_error = Some(e),
+                    }
+                }
+                Err(last_error.unwrap_or_else(|| {
+                    std::io::Error::new(std::io::ErrorKind::NotFound, format!("no address found for {}", address))
+                }))
+            }
+            Printer::Device(path) => std::fs::OpenOptions::new().write(true).open(path)?.write_all(job),
+        }
+    }
+}
+
+impl std::str::FromStr for Printer {
+    type Err = String;
+
+    /// Parse `tcp://host[:port]` or `usb://<device path>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(address) = s.strip_prefix("tcp://") {
+            let address = address.trim_end_matches('/');
+            if address.is_empty() {
+                return Err(format!("Invalid printer '{}': missing host", s));
+            }
+            let has_port = address.rsplit_once(':').is_some_and(|(host, port)| {
+                !host.is_empty() && !host.ends_with(':') && port.parse::<u16>().is_ok()
+            });
+            let address = if has_port { address.to_string() } else { format!("{}:{}", address, RAW_PRINT_PORT) };
+            Ok(Printer::Tcp(address))
+        } else if let Some(device) = s.strip_prefix("usb://") {
+            if device.is_empty() {
+                return Err(format!("Invalid printer '{}': missing device path", s));
+            }
+            Ok(Printer::Device(PathBuf::from(device)))
+        } else {
+            Err(format!("Invalid printer '{}': expected tcp://host[:port] or usb://<device>", s))
+        }
+    }
+}
+
+impl std::fmt::Display for Printer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Printer::Tcp(address) => write!(f, "tcp://{}", address),
+            Printer::Device(path) => write!(f, "usb://{}", path.display()),
+        }
+    }
+}
+
+/// A printable note documenting a single stock movement
+pub struct Receipt<'a> {
+    /// The movement being documented
+    pub transaction: &'a Transaction,
+    /// The product moved
+    pub product: &'a Product,
+}
+
+impl Receipt<'_> {
+    /// Document title: goods-received note for additions, goods-issued for removals,
+    /// opening-balance note for migrated stock, stock-loss note for shrinkage
+    pub fn title(&self) -> &'static str {
+        match self.transaction.transaction_type {
+            TransactionType::Addition => "GOODS RECEIVED NOTE",
+            TransactionType::Removal => "GOODS ISSUED NOTE",
+            TransactionType::InitialStock => "OPENING BALANCE NOTE",
+            TransactionType::Damage | TransactionType::WriteOff | TransactionType::Adjustment => "STOCK LOSS NOTE",
+            TransactionType::Quarantine => "QUARANTINE NOTE",
+            TransactionType::ReleaseQuarantine => "QUARANTINE RELEASE NOTE",
+            TransactionType::ConditionChange => "CONDITION CHANGE NOTE",
+        }
+    }
+
+    /// Document reference, e.g. `GRN-1A2B3C4D`
+    pub fn reference(&self) -> String {
+        let prefix = match self.transaction.transaction_type {
+            TransactionType::Addition => "GRN",
+            TransactionType::Removal => "GIN",
+            TransactionType::InitialStock => "OBN",
+            TransactionType::Damage | TransactionType::WriteOff | TransactionType::Adjustment => "SLN",
+            TransactionType::Quarantine => "QHN",
+            TransactionType::ReleaseQuarantine => "QRN",
+            TransactionType::ConditionChange => "CCN",
+        };
+        let short_id: String = self.transaction.id.chars().take(8).collect();
+        format!("{}-{}", prefix, short_id.to_uppercase())
+    }
+
+    /// Render the receipt as lines of text
+    pub fn lines(&self) -> Vec<String> {
+        let txn = self.transaction;
+        let product = self.product;
+        let (quantity_label, signature_label) = match txn.transaction_type {
+            TransactionType::Addition => ("Quantity received", "Received by"),
+            TransactionType::Removal => ("Quantity issued", "Issued by"),
+            TransactionType::InitialStock => ("Opening quantity", "Counted by"),
+            TransactionType::Damage | TransactionType::WriteOff | TransactionType::Adjustment => {
+                ("Quantity lost", "Recorded by")
+            }
+            TransactionType::Quarantine => ("Quantity held", "Held by"),
+            TransactionType::ReleaseQuarantine => ("Quantity released", "Released by"),
+            TransactionType::ConditionChange => ("Quantity moved", "Recorded by"),
+        };
+
+        let mut lines = vec![
+            self.title().to_string(),
+            "=".repeat(self.title().len()),
+            String::new(),
+            format!("Reference:   {}", self.reference()),
+            format!("Date:        {}", txn.timestamp.format("%Y-%m-%d %H:%M:%S UTC")),
+            format!("Operator:    {}", txn.operator.as_deref().unwrap_or("(not recorded)")),
+            format!("Transaction: {}", txn.id),
+            String::new(),
+            "PRODUCT".to_string(),
+            format!("  SKU:         {}", product.sku),
+            format!("  Name:        {}", product.name),
+        ];
+        if !product.description.is_empty() {
+            lines.push(format!("  Description: {}", product.description));
+        }
+        if let Some(supplier) = &product.supplier {
+            lines.push(format!("  Supplier:    {}", supplier));
+        }
+        if let Some(location) = &product.location {
+            lines.push(format!("  Location:    {}", location));
+        }
+
+        lines.push(String::new());
+        lines.push("MOVEMENT".to_string());
+        lines.push(format!("  {}: {}", quantity_label, txn.quantity));
+        match (txn.from_condition, txn.condition) {
+            (Some(from), Some(to)) => lines.push(format!("  Condition: {} -> {}", from, to)),
+            (None, Some(condition)) => lines.push(format!("  Condition: {}", condition)),
+            _ => {}
+        }
+        if let Some(reason) = txn.reason {
+            lines.push(format!("  Reason: {}", reason));
+        }
+        if let Some(notes) = &txn.notes {
+            lines.push(format!("  Notes: {}", notes));
+        }
+
+        lines.push(String::new());
+        lines.push(String::new());
+        lines.push(format!("{}: ______________________   Date: ____________", signature_label));
+        lines
+    }
+
+    /// Render the receipt as plain text
+    pub fn to_text(&self) -> String {
+        self.lines().join("\n")
+    }
+
+    /// Render the receipt as a single-page PDF document
+    pub fn to_pdf(&self) -> Vec<u8> {
+        pdf_document(&[self.lines()])
+    }
+
+    /// Render the receipt as a ZPL label
+    pub fn to_zpl(&self) -> String {
+        let lines = self.lines();
+        let mut zpl = format!("^XA\n^CI28\n^LL{}\n", 40 + lines.len() * ZPL_LINE_HEIGHT);
+        for (index, line) in lines.iter().enumerate().filter(|(_, line)| !line.is_empty()) {
+            let y = 20 + index * ZPL_LINE_HEIGHT;
+            zpl.push_str(&format!("^FO20,{}^A0N,22,22^FH^FD{}^FS\n", y, zpl_escape(line)));
+        }
+        zpl.push_str("^XZ\n");
+        zpl
+    }
+
+    /// Render the receipt as an ESC/POS job: the title in bold, then the
+    /// other lines, then a paper feed and cut
+    pub fn to_escpos(&self) -> Vec<u8> {
+        let mut job = vec![0x1b, b'@'];
+        for (index, line) in self.lines().iter().enumerate() {
+            if index == 0 {
+                job.extend_from_slice(&[0x1b, b'E', 1]);
+            }
+            job.extend(line.chars().map(|c| if c.is_ascii() && !c.is_ascii_control() { c as u8 } else { b'?' }));
+            job.push(b'\n');
+            if index == 0 {
+                job.extend_from_slice(&[0x1b, b'E', 0]);
+            }
+        }
+        job.extend_from_slice(&[0x1d, b'V', 66, 3]);
+        job
+    }
+
+    /// Render the receipt in a format
+    pub fn render(&self, format: ReceiptFormat) -> Vec<u8> {
+        match format {
+            ReceiptFormat::Text => format!("{}\n", self.to_text()).into_bytes(),
+            ReceiptFormat::Pdf => self.to_pdf(),
+            ReceiptFormat::Zpl => self.to_zpl().into_bytes(),
+            ReceiptFormat::EscPos => self.to_escpos(),
+        }
+    }
+}
+
+/// Dots between lines of a ZPL receipt
+const ZPL_LINE_HEIGHT: usize = 28;
+
+/// Escape ZPL's command characters for a field with `^FH` hex escapes
+fn zpl_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '^' | '~' | '_' => escaped.push_str(&format!("_{:02X}", c as u32)),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Build a minimal PDF with each page's lines set in 10pt Courier on A4 pages
+pub(crate) fn pdf_document(pages: &[Vec<String>]) -> Vec<u8> {
+    let page_ids: Vec<String> = (0..pages.len()).map(|index| format!("{} 0 R", 4 + 2 * index)).collect();
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", page_ids.join(" "), pages.len()),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Courier >>".to_string(),
+    ];
+    for (index, lines) in pages.iter().enumerate() {
+        let mut content = String::from("BT\n/F1 10 Tf\n12 TL\n50 790 Td\n");
+        for line in lines {
+            content.push_str(&format!("({}) Tj T*\n", pdf_escape(line)));
+        }
+        content.push_str("ET\n");
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 595 842] \
+             /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            5 + 2 * index
+        ));
+        objects.push(format!("<< /Length {} >>\nstream\n{}endstream", content.len(), content));
+    }
+
+    let mut pdf = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.push_str(&format!("{} 0 obj\n{}\nendobj\n", index + 1, object));
+    }
+
+    let xref_offset = pdf.len();
+    pdf.push_str(&format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1));
+    for offset in offsets {
+        pdf.push_str(&format!("{:010} 00000 n \n", offset));
+    }
+    pdf.push_str(&format!(
+        "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+        objects.len() + 1,
+        xref_offset
+    ));
+    pdf.into_bytes()
+}
+
+/// Escape a string for a PDF literal, replacing characters the base font can't show
+fn pdf_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> (Transaction, Product) {
+        let mut transaction = Transaction::new("SKU001", TransactionType::Removal, 4, Some("Order (42)".to_string()));
+        transaction.id = "1a2b3c4d-0000-4000-8000-000000000000".to_string();
+        transaction.operator = Some("alice".to_string());
+        let product = Product {
+            sku: "SKU001".to_string(),
+            name: "Widget".to_string(),
+            location: Some("Aisle 3".to_string()),
+            ..Default::default()
+        };
+        (transaction, product)
+    }
+
+    #[test]
+    fn test_text_receipt() {
+        let (transaction, product) = sample();
+        let receipt = Receipt { transaction: &transaction, product: &product };
+        let text = receipt.to_text();
+
+        assert!(text.starts_with("GOODS ISSUED NOTE\n"));
+        assert!(text.contains("Reference:   GIN-1A2B3C4D"));
+        assert!(text.contains("Operator:    alice"));
+        assert!(text.contains("  Location:    Aisle 3"));
+        assert!(text.contains("  Quantity issued: 4"));
+        assert!(text.contains("  Notes: Order (42)"));
+    }
+
+    #[test]
+    fn test_pdf_receipt_structure() {
+        let (transaction, product) = sample();
+        let receipt = Receipt { transaction: &transaction, product: &product };
+        let pdf = String::from_utf8(receipt.to_pdf()).unwrap();
+
+        assert!(pdf.starts_with("%PDF-1.4\n"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        assert!(pdf.contains("(  Notes: Order \\(42\\)) Tj"));
+
+        // The xref offset must point at the xref table
+        let startxref: usize = pdf.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+        assert!(pdf[startxref..].starts_with("xref\n"));
+    }
+
+    #[test]
+    fn test_printer_formats() {
+        let (mut transaction, product) = sample();
+        transaction.notes = Some("Bay ^2_a".to_string());
+        let receipt = Receipt { transaction: &transaction, product: &product };
+
+        let zpl = receipt.to_zpl();
+        assert!(zpl.starts_with("^XA\n") && zpl.ends_with("^XZ\n"));
+        assert!(zpl.contains("^FH^FD  Notes: Bay _5E2_5Fa^FS"));
+
+        let escpos = receipt.to_escpos();
+        assert!(escpos.starts_with(b"\x1b@\x1bE\x01GOODS ISSUED NOTE\n\x1bE\x00"));
+        assert!(escpos.ends_with(b"\x1dVB\x03"));
+
+        assert_eq!("tcp://10.0.0.5".parse(), Ok(Printer::Tcp("10.0.0.5:9100".to_string())));
+        assert_eq!("tcp://zebra:6101/".parse(), Ok(Printer::Tcp("zebra:6101".to_string())));
+        assert_eq!("usb:///dev/usb/lp0".parse(), Ok(Printer::Device("/dev/usb/lp0".into())));
+        assert!("lpt1".parse::<Printer>().is_err());
+    }
+}
@@ -0,0 +1,116 @@
+// Reorder points suggested from the demand recorded in the ledger
+//
+// Daily demand is the units removed as ordinary removals on each day of a
+// look-back window, days without any counting as zero. A reorder point has to
+// cover the mean demand over the lead time plus a safety stock of
+// `z * σ * √lead_time`, where σ is the standard deviation of daily demand and
+// `z` the standard normal quantile of the service level: the chance of not
+// running out before a reorder arrives.
+
+/// Service level used when none is given
+pub const DEFAULT_SERVICE_LEVEL: f64 = 0.95;
+
+/// Lead time for products that don't record one
+pub const DEFAULT_LEAD_TIME_DAYS: u32 = 7;
+
+/// Days of history demand is measured over
+pub const DEFAULT_WINDOW_DAYS: u32 = 90;
+
+/// Mean and spread of daily demand
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Demand {
+    /// Mean units per day
+    pub mean: f64,
+    /// Standard deviation of units per day
+    pub std_dev: f64,
+    /// Days measured
+    pub days: usize,
+}
+
+impl Demand {
+    /// Demand from the units taken on each day of a window
+    pub fn from_daily(units: &[u64]) -> Self {
+        let days = units.len();
+        if days == 0 {
+            return Demand { mean: 0.0, std_dev: 0.0, days };
+        }
+        let mean = units.iter().sum::<u64>() as f64 / days as f64;
+        let variance = units.iter().map(|&day| (day as f64 - mean).powi(2)).sum::<f64>() / days as f64;
+        Demand { mean, std_dev: variance.sqrt(), days }
+    }
+
+    /// Units held back for days of above-average demand during the lead time
+    pub fn safety_stock(&self, lead_time_days: u32, service_level: f64) -> f64 {
+        z_score(service_level) * self.std_dev * f64::from(lead_time_days).sqrt()
+    }
+
+    /// Stock level at which to reorder: lead-time demand plus safety stock, rounded up
+    pub fn reorder_point(&self, lead_time_days: u32, service_level: f64) -> u32 {
+        let point = self.mean * f64::from(lead_time_days) + self.safety_stock(lead_time_days, service_level);
+        // Round away float noise before taking the ceiling, so 10.000000001 stays 10
+        let point = (point * 1e6).round() / 1e6;
+        point.ceil().clamp(0.0, f64::from(u32::MAX)) as u32
+    }
+}
+
+/// Whether a service level can be used: above one half and below one
+pub fn is_valid_service_level(service_level: f64) -> bool {
+    service_level > 0.5 && service_level < 1.0
+}
+
+/// Standard normal quantile, i.e. the inverse of its cumulative distribution
+///
+/// Uses Acklam's rational approximation, accurate to about 1e-9.
+pub fn z_score(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e1, 2.209460984245205e2, -2.759285104469687e2,
+        1.38357751867269e2, -3.066479806614716e1, 2.506628277459239,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e1, 1.615858368580409e2, -1.556989798598866e2,
+        6.680131188771972e1, -1.328068155288572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-3, -3.223964580411365e-1, -2.400758277161838,
+        -2.549732539343734, 4.374664141464968, 2.938163982698783,
+    ];
+    const D: [f64; 4] = [7.784695709041462e-3, 3.224671290700398e-1, 2.445134137142996, 3.754408661907416];
+    const LOW: f64 = 0.02425;
+
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    };
+    if p < LOW {
+        tail((-2.0 * p.ln()).sqrt())
+    } else if p > 1.0 - LOW {
+        -tail((-2.0 * (1.0 - p).ln()).sqrt())
+    } else {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reorder_point_covers_lead_time_demand_and_safety_stock() {
+        assert!((z_score(0.95) - 1.644854).abs() < 1e-6);
+        assert!((z_score(0.99) - 2.326348).abs() < 1e-6);
+        assert!((z_score(0.999) - 3.090232).abs() < 1e-6);
+
+        // Steady demand needs no safety stock
+        let steady = Demand::from_daily(&[2; 30]);
+        assert_eq!(steady.reorder_point(5, 0.95), 10);
+
+        // Mean 2, σ 2: 2 * 4 + 1.645 * 2 * √4 = 14.58
+        let bursty = Demand::from_daily(&[0, 4, 0, 4]);
+        assert_eq!((bursty.mean, bursty.std_dev), (2.0, 2.0));
+        assert_eq!(bursty.reorder_point(4, 0.95), 15);
+        assert_eq!(bursty.reorder_point(4, 0.99), 18);
+    }
+}
@@ -0,0 +1,427 @@
+// Alert rules defined in the configuration
+//
+// Each rule in the `rules` list of config.json has a name and a condition on a
+// product's fields, such as
+//
+//     quantity < 5 AND category == "perishable"
+//
+// Comparisons (`<`, `<=`, `>`, `>=`, `==`, `!=`) take a field on the left and
+// a number or a double-quoted string on the right; they combine with `AND`,
+// `OR`, `NOT`, and parentheses. The boolean fields `low_stock` and
+// `out_of_stock` stand alone. Unset text fields compare as the empty string.
+//
+// After every command the rules are checked against all products, and a rule
+// that starts matching a product raises a `RuleMatched` event; one that keeps
+// matching stays quiet until it has stopped matching in between.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::Product;
+
+/// A rule as written in the configuration
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertRule {
+    /// Name shown when the rule fires
+    pub name: String,
+    /// Condition on a product's fields, e.g. `quantity < 5 AND category == "perishable"`
+    pub when: String,
+    /// Optional text shown with the alert
+    pub message: Option<String>,
+}
+
+/// A rule ready to be checked against products
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub name: String,
+    /// The condition as written
+    pub when: String,
+    pub message: Option<String>,
+    condition: Expr,
+}
+
+impl Rule {
+    /// Parse a configured rule's condition
+    pub fn compile(rule: &AlertRule) -> Result<Self, String> {
+        if rule.name.trim().is_empty() {
+            return Err(format!("Rule '{}' has no name", rule.when));
+        }
+        let condition = Parser::new(&rule.when)
+            .and_then(|mut parser| parser.parse())
+            .map_err(|e| format!("Invalid condition in rule '{}': {}", rule.name, e))?;
+        Ok(Rule { name: rule.name.clone(), when: rule.when.clone(), message: rule.message.clone(), condition })
+    }
+
+    /// Whether a product meets the rule's condition
+    pub fn matches(&self, product: &Product) -> bool {
+        self.condition.eval(product)
+    }
+}
+
+/// Compile every configured rule, rejecting duplicate names
+pub fn compile_all(rules: &[AlertRule]) -> Result<Vec<Rule>, String> {
+    let mut compiled: Vec<Rule> = Vec::with_capacity(rules.len());
+    for rule in rules {
+        let rule = Rule::compile(rule)?;
+        if compiled.iter().any(|other| other.name == rule.name) {
+            return Err(format!("Rule '{}' is defined more than once", rule.name));
+        }
+        compiled.push(rule);
+    }
+    Ok(compiled)
+}
+
+/// Product fields a condition can test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Quantity,
+    ReorderPoint,
+    Quarantined,
+    OnOrder,
+    Shortfall,
+    Sku,
+    Name,
+    Category,
+    Supplier,
+    Location,
+    Barcode,
+    LowStock,
+    OutOfStock,
+}
+
+/// Kind of value a field holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Number,
+    Text,
+    Flag,
+}
+
+impl Field {
+    const ALL: [(&'static str, Field); 13] = [
+        ("quantity", Field::Quantity),
+        ("reorder_point", Field::ReorderPoint),
+        ("quarantined", Field::Quarantined),
+        ("on_order", Field::OnOrder),
+        ("shortfall", Field::Shortfall),
+        ("sku", Field::Sku),
+        ("name", Field::Name),
+        ("category", Field::Category),
+        ("supplier", Field::Supplier),
+        ("location", Field::Location),
+        ("barcode", Field::Barcode),
+        ("low_stock", Field::LowStock),
+        ("out_of_stock", Field::OutOfStock),
+    ];
+
+    fn lookup(name: &str) -> Option<Field> {
+        Field::ALL.iter().find(|(field, _)| field.eq_ignore_ascii_case(name)).map(|(_, field)| *field)
+    }
+
+    fn kind(self) -> Kind {
+        match self {
+            Field::Quantity | Field::ReorderPoint | Field::Quarantined | Field::OnOrder | Field::Shortfall => {
+                Kind::Number
+            }
+            Field::Sku | Field::Name | Field::Category | Field::Supplier | Field::Location | Field::Barcode => {
+                Kind::Text
+            }
+            Field::LowStock | Field::OutOfStock => Kind::Flag,
+        }
+    }
+
+    fn number(self, product: &Product) -> u32 {
+        match self {
+            Field::Quantity => product.quantity,
+            Field::ReorderPoint => product.reorder_point,
+            Field::Quarantined => product.quarantined,
+            Field::OnOrder => product.on_order,
+            Field::Shortfall => product.shortfall(),
+            _ => 0,
+        }
+    }
+
+    fn text(self, product: &Product) -> &str {
+        match self {
+            Field::Sku => &product.sku,
+            Field::Name => &product.name,
+            Field::Category => product.category.as_deref().unwrap_or_default(),
+            Field::Supplier => product.supplier.as_deref().unwrap_or_default(),
+            Field::Location => product.location.as_deref().unwrap_or_default(),
+            Field::Barcode => product.barcode.as_deref().unwrap_or_default(),
+            _ => "",
+        }
+    }
+
+    fn flag(self, product: &Product) -> bool {
+        match self {
+            Field::LowStock => product.is_low_stock(),
+            Field::OutOfStock => product.quantity == 0,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+}
+
+impl Op {
+    fn test<T: PartialOrd + ?Sized>(self, left: &T, right: &T) -> bool {
+        match self {
+            Op::Lt => left < right,
+            Op::Le => left <= right,
+            Op::Gt => left > right,
+            Op::Ge => left >= right,
+            Op::Eq => left == right,
+            Op::Ne => left != right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Number(Field, Op, u64),
+    Text(Field, Op, String),
+    Flag(Field),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, product: &Product) -> bool {
+        match self {
+            Expr::Number(field, op, value) => op.test(&u64::from(field.number(product)), value),
+            Expr::Text(field, op, value) => op.test(field.text(product), value.as_str()),
+            Expr::Flag(field) => field.flag(product),
+            Expr::Not(inner) => !inner.eval(product),
+            Expr::And(left, right) => left.eval(product) && right.eval(product),
+            Expr::Or(left, right) => left.eval(product) || right.eval(product),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(u64),
+    Text(String),
+    Op(Op),
+    Open,
+    Close,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Ident(name) => write!(f, "'{}'", name),
+            Token::Number(n) => write!(f, "{}", n),
+            Token::Text(text) => write!(f, "\"{}\"", text),
+            Token::Op(_) => write!(f, "a comparison"),
+            Token::Open => write!(f, "'('"),
+            Token::Close => write!(f, "')'"),
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            '<' | '>' | '=' | '!' => {
+                chars.next();
+                let equals = chars.next_if_eq(&'=').is_some();
+                tokens.push(Token::Op(match (c, equals) {
+                    ('<', false) => Op::Lt,
+                    ('<', true) => Op::Le,
+                    ('>', false) => Op::Gt,
+                    ('>', true) => Op::Ge,
+                    ('=', true) => Op::Eq,
+                    ('!', true) => Op::Ne,
+                    _ => return Err(format!("unexpected '{}'; comparisons are <, <=, >, >=, ==, and !=", c)),
+                }));
+            }
+            '"' => {
+                chars.next();
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => text.extend(chars.next()),
+                        Some(c) => text.push(c),
+                        None => return Err("unterminated string".to_string()),
+                    }
+                }
+                tokens.push(Token::Text(text));
+            }
+            c if c.is_ascii_digit() => {
+                let mut digits = String::new();
+                while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                    digits.push(c);
+                }
+                tokens.push(Token::Number(digits.parse().map_err(|_| format!("number {} is too large", digits))?));
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                    ident.push(c);
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            c => return Err(format!("unexpected '{}'", c)),
+        }
+    }
+    Ok(tokens)
+}
+
+/// Recursive-descent parser; `OR` binds loosest, then `AND`, then `NOT`
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn new(source: &str) -> Result<Self, String> {
+        Ok(Parser { tokens: tokenize(source)?, position: 0 })
+    }
+
+    fn parse(&mut self) -> Result<Expr, String> {
+        if self.tokens.is_empty() {
+            return Err("the condition is empty".to_string());
+        }
+        let expr = self.parse_or()?;
+        match self.tokens.get(self.position) {
+            None => Ok(expr),
+            Some(token) => Err(format!("unexpected {} after a complete condition", token)),
+        }
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.tokens.get(self.position), Some(Token::Ident(word)) if word.eq_ignore_ascii_case(keyword));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn advance(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_and()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_not()?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_not()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, String> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, String> {
+        let name = match self.advance() {
+            Some(Token::Open) => {
+                let expr = self.parse_or()?;
+                return match self.advance() {
+                    Some(Token::Close) => Ok(expr),
+                    _ => Err("missing ')'".to_string()),
+                };
+            }
+            Some(Token::Ident(name)) => name,
+            Some(token) => return Err(format!("expected a field name, found {}", token)),
+            None => return Err("the condition ends too early".to_string()),
+        };
+        let field = Field::lookup(&name).ok_or_else(|| {
+            let names: Vec<&str> = Field::ALL.iter().map(|(name, _)| *name).collect();
+            format!("unknown field '{}'; expected one of {}", name, names.join(", "))
+        })?;
+        if field.kind() == Kind::Flag {
+            return Ok(Expr::Flag(field));
+        }
+        let op = match self.advance() {
+            Some(Token::Op(op)) => op,
+            _ => return Err(format!("expected a comparison after '{}'", name)),
+        };
+        match (field.kind(), self.advance()) {
+            (Kind::Number, Some(Token::Number(value))) => Ok(Expr::Number(field, op, value)),
+            (Kind::Text, Some(Token::Text(value))) if matches!(op, Op::Eq | Op::Ne) => Ok(Expr::Text(field, op, value)),
+            (Kind::Text, Some(Token::Text(_))) => Err(format!("'{}' can only be compared with == or !=", name)),
+            (Kind::Number, _) => Err(format!("'{}' must be compared with a number", name)),
+            _ => Err(format!("'{}' must be compared with a double-quoted string", name)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(when: &str) -> Result<Rule, String> {
+        Rule::compile(&AlertRule { name: "test".to_string(), when: when.to_string(), message: None })
+    }
+
+    #[test]
+    fn test_conditions_parse_and_match_products() {
+        let milk = Product {
+            sku: "MILK".to_string(),
+            quantity: 3,
+            reorder_point: 10,
+            category: Some("perishable".to_string()),
+            ..Default::default()
+        };
+        let bolt = Product { sku: "BOLT".to_string(), quantity: 3, reorder_point: 2, ..Default::default() };
+
+        let perishable = rule(r#"quantity < 5 AND category == "perishable""#).unwrap();
+        assert!(perishable.matches(&milk));
+        assert!(!perishable.matches(&bolt));
+
+        // AND binds tighter than OR; NOT and parentheses override
+        let either = rule(r#"sku == "BOLT" or quantity >= 100 and low_stock"#).unwrap();
+        assert!(either.matches(&bolt));
+        assert!(!either.matches(&milk));
+        assert!(rule("NOT (low_stock OR out_of_stock)").unwrap().matches(&bolt));
+        assert!(rule(r#"supplier != "Acme" AND shortfall == 7"#).unwrap().matches(&milk));
+
+        assert!(rule("quantity < \"five\"").unwrap_err().contains("must be compared with a number"));
+        assert!(rule("weight > 5").unwrap_err().contains("unknown field 'weight'"));
+        assert!(rule("quantity < 5 AND").unwrap_err().contains("ends too early"));
+        assert!(rule(r#"name > "A""#).is_err());
+        assert!(rule("(low_stock").unwrap_err().contains("missing ')'"));
+        assert!(rule("quantity = 5").is_err());
+
+        let twice = AlertRule { name: "same".to_string(), when: "low_stock".to_string(), message: None };
+        assert!(compile_all(&[twice.clone(), twice]).unwrap_err().contains("more than once"));
+    }
+}
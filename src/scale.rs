@@ -0,0 +1,81 @@
+// Weight readings from a serial or USB weighing scale
+//
+// Most bench scales stream or answer with a line per reading, such as
+// `ST,GS,+  1.234kg` or `  250.0 g`. Only the number and its unit matter here;
+// readings the scale marks unstable (`US`) or negative are skipped. Talking to
+// the port itself needs the `scale` feature.
+
+use crate::models::Weight;
+
+/// The weight in one line of scale output, if it holds a stable, non-negative one
+pub fn parse_reading(line: &str) -> Option<Weight> {
+    let line = line.trim();
+    if line.split([',', ' ']).any(|field| field.eq_ignore_ascii_case("US")) {
+        return None;
+    }
+    let start = line.find(|c: char| c.is_ascii_digit())?;
+    if line[..start].trim_end().ends_with('-') {
+        return None;
+    }
+    line[start..].parse().ok()
+}
+
+/// Read a stable weight from a scale on a serial port
+///
+/// Lines are read until one holds a stable weight, giving up after
+/// `SCALE_TIMEOUT` without one.
+#[cfg(feature = "scale")]
+pub fn read_weight(port: &str, baud: u32) -> Result<Weight, String> {
+    use std::time::{Duration, Instant};
+
+    const SCALE_TIMEOUT: Duration = Duration::from_secs(10);
+
+    let mut serial = serial2::SerialPort::open(port, baud)
+        .map_err(|e| format!("Error: Failed to open scale on {}: {}", port, e))?;
+    serial
+        .set_read_timeout(SCALE_TIMEOUT)
+        .map_err(|e| format!("Error: Failed to configure scale on {}: {}", port, e))?;
+
+    let started = Instant::now();
+    let mut line = Vec::new();
+    let mut buffer = [0u8; 64];
+    while started.elapsed() < SCALE_TIMEOUT {
+        let read = serial
+            .read(&mut buffer)
+            .map_err(|e| format!("Error: Failed to read scale on {}: {}", port, e))?;
+        for &byte in &buffer[..read] {
+            if byte != b'\n' && byte != b'\r' {
+                line.push(byte);
+                continue;
+            }
+            if let Some(weight) = parse_reading(&String::from_utf8_lossy(&line)) {
+                return Ok(weight);
+            }
+            line.clear();
+        }
+    }
+    Err(format!("Error: No stable reading from the scale on {} within {} seconds", port, SCALE_TIMEOUT.as_secs()))
+}
+
+/// Without the `scale` feature there's no serial support to read with
+#[cfg(not(feature = "scale"))]
+pub fn read_weight(_port: &str, _baud: u32) -> Result<Weight, String> {
+    Err("Error: This build can't read a scale; rebuild with --features scale, or give the reading with --weight"
+        .to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_reading() {
+        let grams = |line: &str| parse_reading(line).map(|weight| weight.milligrams() / 1000);
+        assert_eq!(grams("ST,GS,+  1.234kg"), Some(1234));
+        assert_eq!(grams("  250.0 g\r"), Some(250));
+        assert_eq!(grams("ST,NT,+0000.500 kg"), Some(500));
+        assert_eq!(grams("US,GS,+  1.240kg"), None);
+        assert_eq!(grams("ST,GS,-  0.010kg"), None);
+        assert_eq!(grams("OL"), None);
+    }
+}
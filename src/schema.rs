@@ -0,0 +1,181 @@
+// Strict checks of the records loaded at startup (`--strict-load`)
+//
+// Loading is lenient by default: fields this version doesn't know are kept
+// in `extra`, and nothing checks that IDs are unique or timestamps plausible.
+// Strict loading checks every product and transaction before the inventory
+// is opened and refuses to start if any record fails, so a corrupted or
+// hand-edited file is caught before more is written on top of it.
+//
+// Enum values this version doesn't recognize, such as an unknown transaction
+// type, already fail to load in either mode.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::{Product, Transaction};
+
+/// Which kind of record a problem was found in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordKind {
+    Product,
+    Transaction,
+}
+
+impl fmt::Display for RecordKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordKind::Product => write!(f, "product"),
+            RecordKind::Transaction => write!(f, "transaction"),
+        }
+    }
+}
+
+/// A record that fails the strict checks; records are numbered from 1 in
+/// stored order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SchemaProblem {
+    /// A record has an empty ID
+    EmptyId { kind: RecordKind, record: usize },
+    /// A record has an empty SKU
+    EmptySku { kind: RecordKind, record: usize },
+    /// Several records of one kind share an ID
+    DuplicateId { kind: RecordKind, id: String, records: Vec<usize> },
+    /// Several products share a SKU, so all but the last are hidden on load
+    DuplicateSku { sku: String, records: Vec<usize> },
+    /// A record has a field this version doesn't know
+    UnknownField { kind: RecordKind, record: usize, field: String },
+    /// A timestamp is later than the clock tolerance allows
+    FutureTimestamp { kind: RecordKind, record: usize, timestamp: DateTime<Utc> },
+    /// A timestamp is before the Unix epoch, as left by a zeroed or negative value
+    EarlyTimestamp { kind: RecordKind, record: usize, timestamp: DateTime<Utc> },
+}
+
+impl fmt::Display for SchemaProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |records: &[usize]| records.iter().map(|r| r.to_string()).collect::<Vec<_>>().join(", ");
+        match self {
+            SchemaProblem::EmptyId { kind, record } => write!(f, "{} {} has no ID", kind, record),
+            SchemaProblem::EmptySku { kind, record } => write!(f, "{} {} has no SKU", kind, record),
+            SchemaProblem::DuplicateId { kind, id, records } => {
+                write!(f, "{}s {} share the ID {}", kind, list(records), id)
+            }
+            SchemaProblem::DuplicateSku { sku, records } => {
+                write!(f, "products {} share the SKU '{}'", list(records), sku)
+            }
+            SchemaProblem::UnknownField { kind, record, field } => {
+                write!(f, "{} {} has an unrecognized field '{}'", kind, record, field)
+            }
+            SchemaProblem::FutureTimestamp { kind, record, timestamp } => write!(
+                f,
+                "{} {} is dated {}, later than this machine's clock allows",
+                kind, record, timestamp.format("%Y-%m-%d %H:%M:%S")
+            ),
+            SchemaProblem::EarlyTimestamp { kind, record, timestamp } => write!(
+                f,
+                "{} {} is dated {}, before 1970",
+                kind, record, timestamp.format("%Y-%m-%d %H:%M:%S")
+            ),
+        }
+    }
+}
+
+/// Check every product and transaction, returning the problems found
+///
+/// Timestamps later than `now` plus `tolerance` count as in the future.
+pub fn check(products: &[Product], transactions: &[Transaction], now: DateTime<Utc>, tolerance: Duration) -> Vec<SchemaProblem> {
+    let mut problems = Vec::new();
+    let mut check_timestamp = |kind, record, timestamp: DateTime<Utc>| {
+        if timestamp > now + tolerance {
+            problems.push(SchemaProblem::FutureTimestamp { kind, record, timestamp });
+        } else if timestamp < DateTime::UNIX_EPOCH {
+            problems.push(SchemaProblem::EarlyTimestamp { kind, record, timestamp });
+        }
+    };
+    for (record, product) in (1..).zip(products) {
+        if let Some(created_at) = product.created_at {
+            check_timestamp(RecordKind::Product, record, created_at);
+        }
+    }
+    for (record, transaction) in (1..).zip(transactions) {
+        check_timestamp(RecordKind::Transaction, record, transaction.timestamp);
+    }
+
+    let records = products.iter().map(|p| (p.id.as_str(), p.sku.as_str(), &p.extra));
+    problems.extend(check_records(RecordKind::Product, records));
+    let records = transactions.iter().map(|t| (t.id.as_str(), t.product_sku.as_str(), &t.extra));
+    problems.extend(check_records(RecordKind::Transaction, records));
+    problems.extend(duplicates(products.iter().map(|p| p.sku.as_str()).filter(|sku| !sku.is_empty()))
+        .into_iter()
+        .map(|(sku, records)| SchemaProblem::DuplicateSku { sku, records }));
+    problems
+}
+
+/// Empty IDs and SKUs, unknown fields, and duplicate IDs among one kind of record
+fn check_records<'a>(
+    kind: RecordKind,
+    records: impl Iterator<Item = (&'a str, &'a str, &'a serde_json::Map<String, serde_json::Value>)> + Clone,
+) -> Vec<SchemaProblem> {
+    let mut problems = Vec::new();
+    for (record, (id, sku, extra)) in (1..).zip(records.clone()) {
+        if id.is_empty() {
+            problems.push(SchemaProblem::EmptyId { kind, record });
+        }
+        if sku.is_empty() {
+            problems.push(SchemaProblem::EmptySku { kind, record });
+        }
+        problems.extend(extra.keys().map(|field| SchemaProblem::UnknownField { kind, record, field: field.clone() }));
+    }
+    problems.extend(duplicates(records.map(|(id, _, _)| id).filter(|id| !id.is_empty()))
+        .into_iter()
+        .map(|(id, records)| SchemaProblem::DuplicateId { kind, id, records }));
+    problems
+}
+
+/// Values that occur more than once, with the record numbers they occur at
+fn duplicates<'a>(values: impl Iterator<Item = &'a str>) -> Vec<(String, Vec<usize>)> {
+    let mut seen: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (record, value) in (1..).zip(values) {
+        seen.entry(value).or_default().push(record);
+    }
+    seen.into_iter()
+        .filter(|(_, records)| records.len() > 1)
+        .map(|(value, records)| (value.to_string(), records))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::TransactionType;
+    use crate::testing::ProductBuilder;
+
+    #[test]
+    fn test_check_reports_every_problem() {
+        let now = Utc::now();
+        let mut products = vec![ProductBuilder::new("SKU001").build(), ProductBuilder::new("SKU001").build()];
+        products[1].extra.insert("bin".to_string(), "A-3".into());
+        let mut transactions = vec![
+            Transaction::new("SKU001", TransactionType::Addition, 1, None),
+            Transaction::new("", TransactionType::Removal, 1, None),
+        ];
+        transactions[1].id = transactions[0].id.clone();
+        transactions[1].timestamp = now + Duration::days(1);
+        assert!(check(&products[..1], &transactions[..1], now, Duration::minutes(5)).is_empty());
+
+        let problems: Vec<String> = check(&products, &transactions, now, Duration::minutes(5))
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(problems.len(), 6, "{:#?}", problems);
+        assert!(problems[0].starts_with("transaction 2 is dated"));
+        assert_eq!(problems[1], "product 2 has an unrecognized field 'bin'");
+        assert_eq!(problems[2], "products 1, 2 share the ID product-SKU001");
+        assert_eq!(problems[3], "transaction 2 has no SKU");
+        assert_eq!(problems[4], format!("transactions 1, 2 share the ID {}", transactions[0].id));
+        assert_eq!(problems[5], "products 1, 2 share the SKU 'SKU001'");
+    }
+}
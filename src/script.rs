@@ -0,0 +1,183 @@
+// Custom reports and checks written as Rhai scripts
+//
+// `script run report.rhai` evaluates a script against a read-only copy of
+// the inventory. Products and transactions come in the `api::v1` shapes, as
+// object maps, so a script keeps working when the stored models change:
+//
+//     products()           every product
+//     product(sku)         one product, or () when there's none
+//     low_stock()          products at or below their reorder point
+//     transactions()       every transaction, oldest first
+//     transactions(sku)    one product's transactions
+//     sum(list, field)     total of a numeric field; numeric strings such as
+//                          unit_cost count too
+//     count_by(list, field)  number of items per value of a field
+//     group_by(list, field)  items per value of a field
+//
+// Arguments after the script file are in the `args` array. Everything the
+// script prints, and its final value unless that is `()`, becomes the
+// command's output; `throw "message"` fails the command, which makes a
+// script usable as a validation. The engine needs the `script` feature.
+
+use crate::service::InventoryService;
+
+/// Upper bound on the operations one script may run, so a runaway loop stops
+#[cfg(feature = "script")]
+const MAX_OPERATIONS: u64 = 50_000_000;
+
+/// Run a script against the inventory, returning its output
+#[cfg(feature = "script")]
+pub fn run(source: &str, service: &InventoryService, args: &[String]) -> Result<String, String> {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use rhai::{Array, Dynamic, Engine, Scope};
+
+    use crate::api;
+
+    let to_array = |values: Vec<serde_json::Value>| -> Result<Array, String> {
+        values
+            .iter()
+            .map(|value| rhai::serde::to_dynamic(value).map_err(|e| e.to_string()))
+            .collect()
+    };
+    let product_values = |products: Vec<&crate::models::Product>| -> Vec<serde_json::Value> {
+        products.into_iter().map(|product| serde_json::json!(api::v1::Product::from(product))).collect()
+    };
+    let products = to_array(product_values(service.list_products()))?;
+    let low_stock = to_array(product_values(service.list_low_stock()))?;
+    let mut transactions: Vec<&crate::models::Transaction> = service.all_transactions();
+    transactions.sort_by_key(|transaction| transaction.order_key());
+    let transactions = to_array(
+        transactions
+            .into_iter()
+            .map(|transaction| serde_json::json!(api::v1::Transaction::from(transaction)))
+            .collect(),
+    )?;
+
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    let output = Rc::new(RefCell::new(Vec::<String>::new()));
+    let printed = Rc::clone(&output);
+    engine.on_print(move |line| printed.borrow_mut().push(line.to_string()));
+    engine.on_debug(|line, _, _| log::debug!("script: {}", line));
+
+    let all = products.clone();
+    engine.register_fn("products", move || all.clone());
+    let all = products.clone();
+    engine.register_fn("product", move |sku: &str| {
+        all.iter().find(|product| field(product, "sku") == Some(sku.to_string())).cloned().unwrap_or(Dynamic::UNIT)
+    });
+    engine.register_fn("low_stock", move || low_stock.clone());
+    let all = transactions.clone();
+    engine.register_fn("transactions", move || all.clone());
+    engine.register_fn("transactions", move |sku: &str| -> Array {
+        transactions.iter().filter(|transaction| field(transaction, "product_sku") == Some(sku.to_string())).cloned().collect()
+    });
+    engine.register_fn("sum", sum);
+    engine.register_fn("count_by", count_by);
+    engine.register_fn("group_by", group_by);
+
+    let mut scope = Scope::new();
+    scope.push_constant("args", args.iter().cloned().map(Dynamic::from).collect::<Array>());
+    let result = engine.eval_with_scope::<Dynamic>(&mut scope, source).map_err(|e| format!("Script failed: {}", e))?;
+
+    let mut output = output.take();
+    if !result.is_unit() {
+        output.push(result.to_string());
+    }
+    Ok(output.join("\n"))
+}
+
+/// Without the `script` feature there's no engine to run scripts with
+#[cfg(not(feature = "script"))]
+pub fn run(_source: &str, _service: &InventoryService, _args: &[String]) -> Result<String, String> {
+    Err("Error: This build can't run scripts; rebuild with --features script".to_string())
+}
+
+/// A field of an object map as text, if the item is a map that has it
+#[cfg(feature = "script")]
+fn field(item: &rhai::Dynamic, name: &str) -> Option<String> {
+    let map = item.read_lock::<rhai::Map>()?;
+    map.get(name).filter(|value| !value.is_unit()).map(|value| value.to_string())
+}
+
+/// Total of a field over a list; integers stay integers, anything else that
+/// reads as a number makes the total a float
+#[cfg(feature = "script")]
+fn sum(items: rhai::Array, name: &str) -> Result<rhai::Dynamic, Box<rhai::EvalAltResult>> {
+    let mut integer: i64 = 0;
+    let mut float = 0.0;
+    let mut is_float = false;
+    for item in &items {
+        let Some(map) = item.read_lock::<rhai::Map>() else { continue };
+        let Some(value) = map.get(name) else { continue };
+        if let Ok(value) = value.as_int() {
+            integer += value;
+        } else if let Some(value) = value.as_float().ok().or_else(|| value.to_string().parse().ok()) {
+            float += value;
+            is_float = true;
+        } else if !value.is_unit() {
+            return Err(format!("Field '{}' holds '{}', which isn't a number", name, value).into());
+        }
+    }
+    Ok(if is_float { (float + integer as f64).into() } else { integer.into() })
+}
+
+/// Number of items per value of a field; items without it are left out
+#[cfg(feature = "script")]
+fn count_by(items: rhai::Array, name: &str) -> rhai::Map {
+    let mut counts = rhai::Map::new();
+    for key in items.iter().filter_map(|item| field(item, name)) {
+        let count = counts.entry(key.into()).or_insert_with(|| rhai::Dynamic::from(0_i64));
+        *count = (count.as_int().unwrap_or(0) + 1).into();
+    }
+    counts
+}
+
+/// Items per value of a field; items without it are left out
+#[cfg(feature = "script")]
+fn group_by(items: rhai::Array, name: &str) -> rhai::Map {
+    let mut groups: std::collections::BTreeMap<String, rhai::Array> = std::collections::BTreeMap::new();
+    for item in items {
+        if let Some(key) = field(&item, name) {
+            groups.entry(key).or_default().push(item);
+        }
+    }
+    groups.into_iter().map(|(key, items)| (key.into(), items.into())).collect()
+}
+
+#[cfg(all(test, feature = "script"))]
+mod tests {
+    use super::*;
+    use crate::storage::JsonStorage;
+
+    #[test]
+    fn test_scripts_read_products_and_transactions() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path().to_str().unwrap());
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+        for (sku, category, quantity) in [("A", "tools", 3), ("B", "tools", 20), ("C", "paint", 7)] {
+            service.add_product(sku.to_string(), sku.to_string(), String::new(), quantity, 5).unwrap();
+            service
+                .update_product(sku, crate::service::ProductUpdate { category: Some(category.to_string()), ..Default::default() })
+                .unwrap();
+        }
+        service.remove_stock("B", 4, None).unwrap();
+
+        let report = r#"
+            for category in count_by(products(), "category").keys() {
+                print(category + ": " + sum(group_by(products(), "category")[category], "quantity"));
+            }
+            print("low: " + low_stock().map(|p| p.sku));
+            print("B moves: " + transactions(args[0]).len());
+            product("nope") == ()
+        "#;
+        let output = run(report, &service, &["B".to_string()]).unwrap();
+        assert_eq!(output, "paint: 7\ntools: 19\nlow: [\"A\"]\nB moves: 1\ntrue");
+
+        let error = run(r#"if sum(products(), "quantity") > 10 { throw "too much stock" }"#, &service, &[]).unwrap_err();
+        assert!(error.contains("too much stock"), "{}", error);
+        assert!(run("loop {}", &service, &[]).is_err());
+    }
+}
@@ -1,21 +1,681 @@
 // Business logic and inventory operations
 
-use std::collections::HashMap;
-use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::ops::Bound;
+use std::time::Instant;
+use chrono::{DateTime, NaiveDate, Utc};
 use uuid::Uuid;
 
+use crate::cache::ReadModel;
+use crate::costing::{CostLayers, Valuation};
+use crate::diff::InventoryDiff;
 use crate::errors::ServiceError;
-use crate::models::{Product, Transaction, TransactionType};
+use crate::events::InventoryEvent;
+use crate::forecast::{Forecast, Seasonality};
+use crate::i18n::{condition_label, tr, trf, Msg};
+use crate::ledger::{self, LedgerReport};
+use crate::limits::{FieldLimits, TextField};
+use crate::errors::DeletionBlocker;
+use crate::reorder::{self, Demand};
+use crate::rules::Rule;
+use crate::schema::RecordKind;
+use crate::models::{
+    AlertSnooze, Backorder, ClockAction, ClockPolicy, Component, Condition, Customer, DeletionPolicy, FieldMerge, GroupBy, Interval, KpiSnapshot, LedgerHead, LinkKind, MergePolicy, Money, MovementThreshold, Product, ProductFilter, ProductLink, ProductSort,
+    QuantityMerge, ReasonCode, ReceiptLine, ReceivingSession, Quota, QuotaUsage, RetentionPolicy, SaveMode, SizeQuotas, StocktakePolicy, SupplierPrice, Tombstone, Transaction, TransactionType, Weight,
+};
 use crate::storage::Storage;
+use crate::validation::ValidationReport;
+
+/// Changes to apply to an existing product; `None` leaves a field unchanged
+///
+/// For the optional classification fields (category, supplier, location),
+/// providing an empty string clears the value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProductUpdate {
+    /// New product name
+    pub name: Option<String>,
+    /// New product description
+    pub description: Option<String>,
+    /// New reorder point
+    pub reorder_point: Option<u32>,
+    /// New category (empty string clears it)
+    pub category: Option<String>,
+    /// New supplier (empty string clears it)
+    pub supplier: Option<String>,
+    /// New location (empty string clears it)
+    pub location: Option<String>,
+    /// New barcode (empty string clears it)
+    pub barcode: Option<String>,
+    /// New per-product large-movement threshold (`Some(None)` clears it)
+    pub large_movement_threshold: Option<Option<MovementThreshold>>,
+    /// New bill of materials (an empty list makes the product a plain item again)
+    pub components: Option<Vec<Component>>,
+    /// New unit cost (`Some(None)` clears it)
+    pub unit_cost: Option<Option<Money>>,
+    /// New tare weight (`Some(None)` clears it)
+    pub tare_weight: Option<Option<Weight>>,
+    /// New unit weight (`Some(None)` clears it)
+    pub unit_weight: Option<Option<Weight>>,
+    /// New lead time in days (`Some(None)` clears it)
+    pub lead_time_days: Option<Option<u32>>,
+}
+
+/// Optional settings for a stock addition
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AdditionOptions {
+    /// Condition of the units added; new if not given
+    pub condition: Condition,
+    /// Cost of one unit received; the product's unit cost if not given
+    pub unit_cost: Option<Money>,
+}
+
+/// Optional settings for a stock removal
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RemovalOptions {
+    /// Allow a removal that exceeds the large-movement threshold
+    pub confirm_large: bool,
+    /// Record the removal as a loss (damage, write-off, or adjustment)
+    /// instead of a plain removal
+    pub transaction_type: Option<TransactionType>,
+    /// Why the stock is being removed
+    pub reason: Option<ReasonCode>,
+    /// Take the units from stock in this condition; otherwise new stock goes
+    /// first. Batches ignore this.
+    pub condition: Option<Condition>,
+    /// Code of the customer the stock is shipped to; only for plain removals
+    pub customer: Option<String>,
+}
+
+/// Optional settings for a product deletion
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeleteOptions {
+    /// Delete even if the deletion policy would refuse
+    pub force: bool,
+}
+
+/// One line of a batch stock movement
+#[derive(Debug, Clone, PartialEq)]
+pub struct StockEntry {
+    /// Product SKU
+    pub sku: String,
+    /// Quantity to add or remove
+    pub quantity: u32,
+    /// Optional transaction notes
+    pub notes: Option<String>,
+}
+
+/// A past stock movement imported with its original timestamp
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoricalEntry {
+    /// Product SKU
+    pub sku: String,
+    /// When the movement happened
+    pub timestamp: DateTime<Utc>,
+    /// Whether stock was added or removed
+    pub transaction_type: TransactionType,
+    /// Quantity added or removed
+    pub quantity: u32,
+    /// Optional transaction notes
+    pub notes: Option<String>,
+}
+
+/// A product and its stock on hand in the system being migrated from
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpeningBalance {
+    /// Product SKU
+    pub sku: String,
+    /// Product name
+    pub name: String,
+    /// Stock on hand at the cutover date
+    pub quantity: u32,
+    /// Reorder point
+    pub reorder_point: u32,
+}
+
+/// One line of a supplier's price list
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceEntry {
+    /// Product SKU
+    pub sku: String,
+    /// Price of one unit
+    pub unit_cost: Money,
+    /// First day the price applies
+    pub effective_from: NaiveDate,
+}
+
+/// A product's quantity as another system, such as an ERP or web shop, reports it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalQuantity {
+    /// Product SKU
+    pub sku: String,
+    /// Units the other system holds
+    pub quantity: u32,
+}
+
+/// A product whose quantity here differs from another system's, or that
+/// only one of them has
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discrepancy {
+    pub sku: String,
+    /// Available units here; `None` if the product isn't in the inventory
+    pub ours: Option<u32>,
+    /// Units the other system reports; `None` if it doesn't list the product
+    pub theirs: Option<u32>,
+}
+
+impl Discrepancy {
+    /// Units to add (positive) or remove (negative) here to match the other
+    /// system, when both have the product
+    pub fn adjustment(&self) -> Option<i64> {
+        Some(i64::from(self.theirs?) - i64::from(self.ours?))
+    }
+}
+
+/// Expected and created totals of an opening-balance migration
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Reconciliation {
+    /// Products listed in the input
+    pub expected_products: usize,
+    /// Units listed in the input
+    pub expected_units: u64,
+    /// Products found in the inventory after the migration
+    pub created_products: usize,
+    /// Units recorded by InitialStock transactions after the migration
+    pub created_units: u64,
+    /// SKUs whose recorded opening balance differs from the input: (SKU, expected, recorded)
+    pub mismatches: Vec<(String, u32, u64)>,
+    /// Existing products the input was merged into, which the totals leave out
+    pub merged_products: usize,
+}
+
+impl Reconciliation {
+    /// Whether every expected product and unit was created
+    pub fn is_balanced(&self) -> bool {
+        self.expected_products == self.created_products
+            && self.expected_units == self.created_units
+            && self.mismatches.is_empty()
+    }
+}
+
+/// Stock of one kit component
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentStock {
+    /// Component SKU
+    pub sku: String,
+    /// Units needed per kit
+    pub per_kit: u32,
+    /// Units in stock
+    pub available: u32,
+}
+
+impl ComponentStock {
+    /// Kits this component alone has stock for
+    pub fn kits(&self) -> u32 {
+        self.available / self.per_kit
+    }
+}
+
+/// How many kits the current component stock can make
+#[derive(Debug, Clone, PartialEq)]
+pub struct Buildable {
+    /// Kits that can be assembled
+    pub kits: u32,
+    /// Stock of each component, in bill-of-materials order
+    pub components: Vec<ComponentStock>,
+}
+
+impl Buildable {
+    /// The component that runs out first (the first one, on a tie)
+    pub fn limiting(&self) -> Option<&ComponentStock> {
+        self.components.iter().find(|component| component.kits() == self.kits)
+    }
+}
+
+/// Expected and received units of one product in a receipt
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceiptVariance {
+    /// Product SKU
+    pub sku: String,
+    /// Units expected, or `None` if the product wasn't expected at all
+    pub expected: Option<u32>,
+    /// Units received so far
+    pub received: u32,
+}
+
+impl ReceiptVariance {
+    /// Received minus expected: negative when short, positive when over
+    pub fn variance(&self) -> i64 {
+        i64::from(self.received) - i64::from(self.expected.unwrap_or(0))
+    }
+}
+
+/// A staged receipt with its lines compared against what was expected
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReceivingReview {
+    /// The staged receipt
+    pub session: ReceivingSession,
+    /// One entry per product: expected products first, then unexpected ones as received
+    pub lines: Vec<ReceiptVariance>,
+}
+
+impl ReceivingReview {
+    /// Build the comparison for a session
+    fn of(session: ReceivingSession) -> Self {
+        let mut lines: Vec<ReceiptVariance> = Vec::new();
+        let expected = session.expected.iter().map(|line| (line, true));
+        let received = session.received.iter().map(|line| (line, false));
+        for (line, is_expected) in expected.chain(received) {
+            let index = match lines.iter().position(|variance| variance.sku == line.sku) {
+                Some(index) => index,
+                None => {
+                    lines.push(ReceiptVariance { sku: line.sku.clone(), expected: None, received: 0 });
+                    lines.len() - 1
+                }
+            };
+            let variance = &mut lines[index];
+            if is_expected {
+                variance.expected = Some(variance.expected.unwrap_or(0).saturating_add(line.quantity));
+            } else {
+                variance.received = variance.received.saturating_add(line.quantity);
+            }
+        }
+        ReceivingReview { session, lines }
+    }
+
+    /// Units received across all products
+    pub fn received_units(&self) -> u64 {
+        self.lines.iter().map(|line| u64::from(line.received)).sum()
+    }
+
+    /// Whether everything expected arrived and nothing else did
+    pub fn matches_expected(&self) -> bool {
+        self.lines.iter().all(|line| line.expected.is_some() && line.variance() == 0)
+    }
+}
+
+/// What a storage compaction removed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompactionReport {
+    /// Size of the live data files before compaction, in bytes
+    pub bytes_before: u64,
+    /// Size of the live data files after compaction, in bytes
+    pub bytes_after: u64,
+    /// Products dropped because another record had the same SKU
+    pub duplicate_products: usize,
+    /// Transactions dropped because another record had the same ID
+    pub duplicate_transactions: usize,
+    /// Deleted products purged from the trash after their retention period
+    pub purged_trash: usize,
+    /// Transactions moved to the archive
+    pub archived_transactions: usize,
+}
+
+impl CompactionReport {
+    /// Bytes freed in the live data files
+    pub fn reclaimed(&self) -> u64 {
+        self.bytes_before.saturating_sub(self.bytes_after)
+    }
+}
+
+/// What applying the retention policy cleared, or would clear on a dry run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RetentionReport {
+    /// Transactions and backorders whose notes were cleared
+    pub notes: usize,
+    /// Transactions and backorders whose customer code was cleared
+    pub customers: usize,
+    /// Chained transactions linked again because redacting changed their hashes
+    pub relinked: usize,
+}
+
+/// A record given a new ID by `heal_ids`
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdChange {
+    /// Whether a product or a transaction was changed
+    pub record: RecordKind,
+    /// SKU of the product, or the product the transaction belongs to
+    pub sku: String,
+    /// ID shared with an earlier record
+    pub old_id: String,
+    /// Newly assigned ID
+    pub new_id: String,
+}
+
+/// What healing duplicate IDs changed, or would change on a dry run
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IdHealingReport {
+    /// Records given new IDs
+    pub changes: Vec<IdChange>,
+    /// Transactions that are exact copies of an earlier one, left for `compact`
+    pub copies: usize,
+}
+
+/// Units one product lost through one kind of shrinkage
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShrinkageLine {
+    /// Product SKU
+    pub sku: String,
+    /// Product name
+    pub name: String,
+    /// Kind of loss: damage, write-off, or adjustment
+    pub transaction_type: TransactionType,
+    /// Number of transactions
+    pub transactions: usize,
+    /// Units lost
+    pub units: u64,
+}
+
+/// Units removed for one reason code over a period
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReasonTotal {
+    /// Reason code, or `None` for removals recorded without one
+    pub reason: Option<ReasonCode>,
+    /// Number of transactions
+    pub transactions: usize,
+    /// Units removed
+    pub units: u64,
+}
+
+/// Stock held and its value at one point in time
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValuePoint {
+    /// When the stock was held
+    pub at: DateTime<Utc>,
+    /// Units on hand, quarantined ones included
+    pub units: u64,
+    /// Value of the units at the cost they were received at
+    pub value: Money,
+    /// Units received without a cost, left out of `value`
+    pub uncosted_units: u64,
+}
+
+/// Units of a product counted from a gross weight
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weighing {
+    /// Weight read from the scale, container included
+    pub gross: Weight,
+    /// Weight of the container
+    pub tare: Weight,
+    /// Weight of one unit
+    pub unit_weight: Weight,
+    /// Whole units in the net weight, rounded to the nearest
+    pub units: u32,
+}
+
+impl std::fmt::Display for Weighing {
+    /// The note recorded on the transaction, e.g. `Weighed 1.250 kg gross,
+    /// tare 0.050 kg, 0.025 kg per unit`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Weighed {} gross, tare {}, {} per unit", self.gross, self.tare, self.unit_weight)
+    }
+}
+
+/// Units of one product shipped to one customer in a period
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShipmentLine {
+    /// Customer code
+    pub customer: String,
+    /// Product SKU
+    pub sku: String,
+    /// Number of removals
+    pub transactions: usize,
+    /// Units shipped
+    pub units: u64,
+}
+
+/// Cost of the goods one product issued in a period
+#[derive(Debug, Clone, PartialEq)]
+pub struct CogsLine {
+    /// Product SKU
+    pub sku: String,
+    /// Product name
+    pub name: String,
+    /// Units issued and their cost
+    pub cost: Valuation,
+}
+
+/// A stock movement valued at cost, for posting to the books
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountingEntry {
+    /// When the movement took place
+    pub timestamp: DateTime<Utc>,
+    /// Product SKU
+    pub sku: String,
+    /// Product name
+    pub name: String,
+    /// Type of the movement
+    pub transaction_type: TransactionType,
+    /// Units moved and their cost: what receipts came in at, and what issues
+    /// took from the oldest receipts
+    pub cost: Valuation,
+}
+
+/// A reorder point suggested from a product's demand
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReorderSuggestion {
+    /// Product SKU
+    pub sku: String,
+    /// Product name
+    pub name: String,
+    /// Reorder point the product has now
+    pub current: u32,
+    /// Reorder point that meets the service level
+    pub suggested: u32,
+    /// Lead time the suggestion assumes
+    pub lead_time_days: u32,
+    /// Daily demand it was computed from
+    pub demand: Demand,
+    /// Part of `suggested` held back for above-average demand, rounded up
+    pub safety_stock: u32,
+}
+
+/// Stock movements of one product over a period, by kind
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MovementSummary {
+    /// Product SKU
+    pub sku: String,
+    /// Units added, including opening balances
+    pub received: u64,
+    /// Units taken out by ordinary removals
+    pub removed: u64,
+    /// Units lost to damage, write-offs, and count adjustments
+    pub shrinkage: u64,
+    /// Transactions in the period, including quarantine and condition moves
+    pub movements: usize,
+    /// Available units now
+    pub on_hand: u32,
+}
+
+impl MovementSummary {
+    /// Change in stock over the period, leaving out internal moves
+    pub fn net_change(&self) -> i64 {
+        self.received as i64 - self.removed as i64 - self.shrinkage as i64
+    }
+}
+
+/// When a product is expected to run out
+#[derive(Debug, Clone, PartialEq)]
+pub struct StockOutForecast {
+    /// Product SKU
+    pub sku: String,
+    /// Product name
+    pub name: String,
+    /// Units on hand less those committed to backorders
+    pub available: u32,
+    /// Daily demand projected from the product's removals
+    pub forecast: Forecast,
+    /// Day the available units are expected to run out, if within the horizon
+    pub stock_out: Option<NaiveDate>,
+}
+
+/// Products sharing the same value of a grouping field
+#[derive(Debug, PartialEq)]
+pub struct ProductGroup<'a> {
+    /// Group value, or `None` for products without the field set
+    pub key: Option<String>,
+    /// Products in the group, ordered by SKU
+    pub products: Vec<&'a Product>,
+}
+
+/// Count, units, and value of the products sharing a value of a grouping field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupTotals {
+    /// Group value, or `None` for products without the field set
+    pub key: Option<String>,
+    /// Products in the group
+    pub products: usize,
+    /// Units on hand, or in the filtered condition
+    pub quantity: u64,
+    /// Stock on hand at FIFO cost
+    pub value: Valuation,
+}
 
 /// Inventory service that manages products and transactions
 pub struct InventoryService {
-    /// Products indexed by SKU for fast lookup
-    products: HashMap<String, Product>,
+    /// Products indexed by SKU, in SKU order for prefix scans
+    products: BTreeMap<String, Product>,
+    /// Low-stock set, category totals, and stock value, updated with every
+    /// change to a product or its cost layers
+    read_model: ReadModel,
+    /// FIFO cost layers of each product's stock on hand
+    cost_layers: HashMap<String, CostLayers>,
     /// All stock transactions
     transactions: Vec<Transaction>,
+    /// Timestamp of each product's latest transaction, for the clock check
+    latest_timestamps: HashMap<String, DateTime<Utc>>,
     /// Storage backend for persistence
     storage: Box<dyn Storage>,
+    /// Threshold for products that don't define their own
+    large_movement_threshold: Option<MovementThreshold>,
+    /// Events raised since they were last taken
+    events: Vec<InventoryEvent>,
+    /// Stock changes not yet saved, moved to `events` once they are
+    unsaved_events: Vec<InventoryEvent>,
+    /// Operator recorded on new transactions
+    operator: Option<String>,
+    /// Length and content limits for free-text fields
+    limits: FieldLimits,
+    /// Safeguards and archiving applied when deleting products
+    deletion_policy: DeletionPolicy,
+    /// Count variances applied without a second count or confirmation
+    stocktake: StocktakePolicy,
+    /// Opened by `open_read_only`: transactions stay in storage and nothing is saved
+    read_only: bool,
+    /// Fill backorders from stock as it is added instead of only reporting them
+    backorder_allocation: bool,
+    /// How long notes and customer references are kept
+    retention_policy: RetentionPolicy,
+    /// Link each new transaction to the previous one by hash
+    hash_chain: bool,
+    /// Newest link of the hash chain, as stored
+    ledger_head: Option<LedgerHead>,
+    /// Pattern demand follows in stock-out forecasts unless one is given
+    seasonality: Seasonality,
+    /// Alert rules checked by `raise_rule_events`
+    alert_rules: Vec<Rule>,
+    /// Guards against transactions timestamped beyond the clock tolerance
+    clock: ClockPolicy,
+    /// Sequence number of the newest recorded transaction
+    last_seq: u64,
+    /// Data files changed but not yet saved
+    pending: PendingWrites,
+    /// Whether a batch is open, holding back saving until it is committed
+    batching: bool,
+    /// When changes are saved
+    save_mode: SaveMode,
+    /// When pending changes were last saved
+    last_saved: Instant,
+    /// Soft limits on the size of the inventory
+    quotas: SizeQuotas,
+    /// Quotas already warned about, so each is raised once per service
+    quotas_warned: BTreeSet<Quota>,
+}
+
+/// Which data files have changes waiting to be saved
+#[derive(Debug, Clone, Copy, Default)]
+struct PendingWrites {
+    products: bool,
+    transactions: bool,
+}
+
+/// Operations whose saving is deferred until `commit`, from `InventoryService::begin_batch`
+///
+/// The batch dereferences to the service, so stock is added and removed
+/// through it as usual. Committing saves whatever the save mode, so even a
+/// manual one. Dropping the batch without committing, e.g. when an operation
+/// in it fails and `?` returns early, discards its changes to products and
+/// transactions and the events it raised; audit log lines already appended
+/// stay.
+pub struct Batch<'a> {
+    service: &'a mut InventoryService,
+    /// State before the batch, restored if it is dropped without committing
+    snapshot: Option<BatchSnapshot>,
+}
+
+/// In-memory state a batch can change, as it was when the batch began
+struct BatchSnapshot {
+    products: BTreeMap<String, Product>,
+    read_model: ReadModel,
+    cost_layers: HashMap<String, CostLayers>,
+    transactions: Vec<Transaction>,
+    latest_timestamps: HashMap<String, DateTime<Utc>>,
+    last_seq: u64,
+    ledger_head: Option<LedgerHead>,
+    pending: PendingWrites,
+    events: usize,
+    unsaved_events: usize,
+}
+
+impl Batch<'_> {
+    /// Save everything the batch changed
+    pub fn commit(mut self) -> Result<(), ServiceError> {
+        self.snapshot = None;
+        self.service.end_batch()
+    }
+}
+
+impl std::ops::Deref for Batch<'_> {
+    type Target = InventoryService;
+
+    fn deref(&self) -> &InventoryService {
+        self.service
+    }
+}
+
+impl std::ops::DerefMut for Batch<'_> {
+    fn deref_mut(&mut self) -> &mut InventoryService {
+        self.service
+    }
+}
+
+impl Drop for Batch<'_> {
+    fn drop(&mut self) {
+        let Some(snapshot) = self.snapshot.take() else {
+            return;
+        };
+        let service = &mut *self.service;
+        let discarded = service.transactions.len().saturating_sub(snapshot.transactions.len());
+        service.products = snapshot.products;
+        service.read_model = snapshot.read_model;
+        service.cost_layers = snapshot.cost_layers;
+        service.transactions = snapshot.transactions;
+        service.latest_timestamps = snapshot.latest_timestamps;
+        service.last_seq = snapshot.last_seq;
+        service.ledger_head = snapshot.ledger_head;
+        service.pending = snapshot.pending;
+        service.events.truncate(snapshot.events);
+        service.unsaved_events.truncate(snapshot.unsaved_events);
+        service.batching = false;
+        log::warn!("Discarded an uncommitted batch and its {} transactions", discarded);
+    }
+}
+
+impl Drop for InventoryService {
+    fn drop(&mut self) {
+        if let Err(err) = self.flush() {
+            log::error!("Failed to save changes held back by the save mode: {}", err);
+        }
+    }
 }
 
 impl InventoryService {
@@ -23,24 +683,325 @@ impl InventoryService {
     /// 
     /// # Requirements
     /// - 8.1: Load all product and transaction data from persistent storage on startup
+    ///
+    /// Transactions, usually the larger file, load on a separate thread while
+    /// products are loaded and indexed; the timings are logged at info level.
     pub fn new(storage: Box<dyn Storage>) -> Result<Self, ServiceError> {
-        // Load products from storage and index by SKU
-        let products_vec = storage.load_products()?;
-        let products: HashMap<String, Product> = products_vec
+        let started = Instant::now();
+        let (products, transactions, timings) = std::thread::scope(|scope| {
+            let transactions = scope.spawn(|| {
+                let started = Instant::now();
+                let transactions = storage.load_transactions();
+                (transactions, started.elapsed())
+            });
+
+            // Load products from storage and index by SKU
+            let products_vec = storage.load_products().map_err(ServiceError::storage("loading products"))?;
+            let loaded = started.elapsed();
+            let products: BTreeMap<String, Product> = products_vec
+                .into_iter()
+                .map(|p| (p.sku.clone(), p))
+                .collect();
+            let indexed = started.elapsed() - loaded;
+
+            let (transactions, transactions_time) = transactions.join().expect("transaction loader panicked");
+            let transactions = transactions.map_err(ServiceError::storage("loading transactions"))?;
+            Ok::<_, ServiceError>((products, transactions, (loaded, indexed, transactions_time)))
+        })?;
+        // Deleted and archived transactions may have held the highest numbers
+        let saved_seq = storage.load_last_seq().map_err(ServiceError::storage("loading the sequence number"))?;
+        let (products_time, index_time, transactions_time) = timings;
+        let mut ids = HashSet::new();
+        let shared = products.values().filter(|p| !ids.insert(p.id.as_str())).count()
+            + transactions.iter().filter(|t| !ids.insert(t.id.as_str())).count();
+        if shared > 0 {
+            log::warn!("{} records share an ID with an earlier record; heal-ids gives them new ones", shared);
+        }
+        let cost_layers = build_cost_layers(&products, &transactions);
+        let latest_timestamps = latest_timestamps(&transactions);
+        let read_model = ReadModel::build(products.values().map(|product| (product, cost_layers[&product.sku].valuation())));
+        let last_seq = transactions.iter().map(|t| t.seq).max().unwrap_or(0).max(saved_seq.unwrap_or(0));
+        log::info!(
+            "Loaded {} products and {} transactions in {:.1?} (products {:.1?}, indexes {:.1?}; transactions {:.1?} on a second thread)",
+            products.len(), transactions.len(), started.elapsed(), products_time, index_time, transactions_time
+        );
+        
+        Ok(InventoryService {
+            read_model,
+            cost_layers,
+            products,
+            transactions,
+            latest_timestamps,
+            storage,
+            large_movement_threshold: None,
+            events: Vec::new(),
+            unsaved_events: Vec::new(),
+            operator: None,
+            limits: FieldLimits::default(),
+            deletion_policy: DeletionPolicy::default(),
+            stocktake: StocktakePolicy::default(),
+            read_only: false,
+            backorder_allocation: false,
+            retention_policy: RetentionPolicy::default(),
+            hash_chain: false,
+            ledger_head: None,
+            seasonality: Seasonality::None,
+            alert_rules: Vec::new(),
+            clock: ClockPolicy::default(),
+            last_seq,
+            pending: PendingWrites::default(),
+            batching: false,
+            save_mode: SaveMode::Immediate,
+            last_saved: Instant::now(),
+            quotas: SizeQuotas::default(),
+            quotas_warned: BTreeSet::new(),
+        })
+    }
+
+    /// Open the inventory for a read-only command, loading products only
+    ///
+    /// Transactions are left in storage and read per product by
+    /// `transactions_for`, so viewing one product doesn't load the whole
+    /// ledger. Anything that would save changes fails with `ReadOnly`.
+    pub fn open_read_only(storage: Box<dyn Storage>) -> Result<Self, ServiceError> {
+        let started = Instant::now();
+        let products: BTreeMap<String, Product> = storage
+            .load_products()
+            .map_err(ServiceError::storage("loading products"))?
             .into_iter()
             .map(|p| (p.sku.clone(), p))
             .collect();
-        
-        // Load transactions from storage
-        let transactions = storage.load_transactions()?;
-        
+        log::info!("Loaded {} products read-only in {:.1?}", products.len(), started.elapsed());
+
         Ok(InventoryService {
+            // Without the history there are no cost layers to value stock by
+            read_model: ReadModel::build(products.values().map(|product| (product, Valuation::default()))),
+            cost_layers: HashMap::new(),
             products,
-            transactions,
+            transactions: Vec::new(),
+            latest_timestamps: HashMap::new(),
             storage,
+            large_movement_threshold: None,
+            events: Vec::new(),
+            unsaved_events: Vec::new(),
+            operator: None,
+            limits: FieldLimits::default(),
+            deletion_policy: DeletionPolicy::default(),
+            stocktake: StocktakePolicy::default(),
+            read_only: true,
+            backorder_allocation: false,
+            retention_policy: RetentionPolicy::default(),
+            hash_chain: false,
+            ledger_head: None,
+            seasonality: Seasonality::None,
+            alert_rules: Vec::new(),
+            clock: ClockPolicy::default(),
+            last_seq: 0,
+            pending: PendingWrites::default(),
+            batching: false,
+            save_mode: SaveMode::Immediate,
+            last_saved: Instant::now(),
+            quotas: SizeQuotas::default(),
+            quotas_warned: BTreeSet::new(),
         })
     }
 
+    /// Set the global large-movement threshold used for products without their own
+    pub fn with_large_movement_threshold(mut self, threshold: Option<MovementThreshold>) -> Self {
+        self.large_movement_threshold = threshold;
+        self
+    }
+
+    /// Set the operator recorded on transactions created by this service
+    pub fn with_operator(mut self, operator: Option<String>) -> Self {
+        self.operator = operator;
+        self
+    }
+
+    /// Set the length and content limits for free-text fields
+    pub fn with_field_limits(mut self, limits: FieldLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Set the safeguards and archiving applied when deleting products
+    pub fn with_deletion_policy(mut self, policy: DeletionPolicy) -> Self {
+        self.deletion_policy = policy;
+        self
+    }
+
+    /// Set how far counts may differ from recorded stock and still be applied
+    pub fn with_stocktake_policy(mut self, policy: StocktakePolicy) -> Self {
+        self.stocktake = policy;
+        self
+    }
+
+    /// Fill backorders from stock as it is added, oldest first, instead of
+    /// only raising a `BackordersWaiting` event
+    pub fn with_backorder_allocation(mut self, enabled: bool) -> Self {
+        self.backorder_allocation = enabled;
+        self
+    }
+
+    /// Set how long notes and customer references are kept by `apply_retention`
+    pub fn with_retention_policy(mut self, policy: RetentionPolicy) -> Self {
+        self.retention_policy = policy;
+        self
+    }
+
+    /// Set soft limits on the number of products and transactions and the
+    /// size of the data files
+    pub fn with_size_quotas(mut self, quotas: SizeQuotas) -> Self {
+        self.quotas = quotas;
+        self
+    }
+
+    /// Set when changes are saved: after every operation (the default), at
+    /// most once per debounce window, or only on `flush`
+    ///
+    /// Changes held back are saved by `flush` or when the service is dropped.
+    pub fn with_save_mode(mut self, mode: SaveMode) -> Self {
+        self.save_mode = mode;
+        self
+    }
+
+    /// Set the tolerance for clock drift and whether drift is refused or
+    /// only warned about
+    pub fn with_clock_policy(mut self, policy: ClockPolicy) -> Self {
+        self.clock = policy;
+        self
+    }
+
+    /// Set the pattern demand follows in stock-out forecasts by default
+    pub fn with_seasonality(mut self, seasonality: Seasonality) -> Self {
+        self.seasonality = seasonality;
+        self
+    }
+
+    /// Set the alert rules checked by `raise_rule_events`
+    pub fn with_alert_rules(mut self, rules: Vec<Rule>) -> Self {
+        self.alert_rules = rules;
+        self
+    }
+
+    /// The configured alert rules
+    pub fn alert_rules(&self) -> &[Rule] {
+        &self.alert_rules
+    }
+
+    /// The seasonality stock-out forecasts use by default
+    pub fn seasonality(&self) -> Seasonality {
+        self.seasonality
+    }
+
+    /// The retention policy `apply_retention` enforces
+    pub fn retention_policy(&self) -> &RetentionPolicy {
+        &self.retention_policy
+    }
+
+    /// Link each new transaction to the one recorded before it by SHA-256
+    ///
+    /// Loads the stored chain head to link to, so unlike the other settings
+    /// this can fail. See `ledger` for how the chain is checked.
+    pub fn with_hash_chain(mut self, enabled: bool) -> Result<Self, ServiceError> {
+        if enabled {
+            self.ledger_head = self.storage.load_ledger_head()
+                .map_err(ServiceError::storage("loading the ledger head"))?;
+        }
+        self.hash_chain = enabled;
+        Ok(self)
+    }
+
+    /// Create a transaction attributed to the current operator
+    fn new_transaction(
+        &self,
+        sku: &str,
+        transaction_type: TransactionType,
+        quantity: u32,
+        notes: Option<String>,
+    ) -> Transaction {
+        Transaction {
+            operator: self.operator.clone(),
+            ..Transaction::new(sku, transaction_type, quantity, notes)
+        }
+    }
+
+    /// Take all events raised since the last call
+    pub fn take_events(&mut self) -> Vec<InventoryEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Products a rule matches now, in SKU order
+    pub fn products_matching(&self, rule: &Rule) -> Vec<&Product> {
+        self.products.values().filter(|product| rule.matches(product)).collect()
+    }
+
+    /// Every (rule name, SKU) pair that matches now
+    pub fn rule_matches(&self) -> BTreeSet<(String, String)> {
+        self.alert_rules
+            .iter()
+            .flat_map(|rule| {
+                self.products_matching(rule).into_iter().map(|product| (rule.name.clone(), product.sku.clone()))
+            })
+            .collect()
+    }
+
+    /// Raise a `RuleMatched` event for each rule that matches a product now
+    /// but didn't in `before`, taken from `rule_matches` before a command
+    pub fn raise_rule_events(&mut self, before: &BTreeSet<(String, String)>) {
+        let timestamp = Utc::now();
+        let mut raised = Vec::new();
+        for rule in &self.alert_rules {
+            for product in self.products_matching(rule) {
+                if !before.contains(&(rule.name.clone(), product.sku.clone())) {
+                    log::info!("Alert rule '{}' matched '{}'", rule.name, product.sku);
+                    raised.push(InventoryEvent::RuleMatched {
+                        rule: rule.name.clone(),
+                        sku: product.sku.clone(),
+                        message: rule.message.clone(),
+                        timestamp,
+                    });
+                }
+            }
+        }
+        self.events.extend(raised);
+    }
+
+    /// Queue an event, appending it to the audit log if it is auditable
+    fn raise_event(&mut self, event: InventoryEvent) -> Result<(), ServiceError> {
+        log::warn!("{}", event);
+        if event.is_auditable() {
+            self.storage.append_audit_event(&event)
+                .map_err(ServiceError::storage("appending to the audit log"))?;
+        }
+        self.events.push(event);
+        Ok(())
+    }
+
+    /// Queue events in order, stopping at the first that can't be audited
+    fn raise_events(&mut self, events: impl IntoIterator<Item = InventoryEvent>) -> Result<(), ServiceError> {
+        for event in events {
+            self.raise_event(event)?;
+        }
+        Ok(())
+    }
+
+    /// Refuse, or warn about, recording another transaction for a product
+    /// whose history already holds one later than now plus the tolerance
+    ///
+    /// A warning is returned rather than raised, so the caller raises it
+    /// only once the transaction has been recorded.
+    fn check_clock(&self, sku: &str) -> Result<Option<InventoryEvent>, ServiceError> {
+        let now = Utc::now();
+        let Some(&latest) = self.latest_timestamps.get(sku).filter(|latest| **latest > now + self.clock.tolerance()) else {
+            return Ok(None);
+        };
+        match self.clock.action {
+            ClockAction::Reject => Err(ServiceError::ClockDrift { sku: sku.to_string(), latest }),
+            ClockAction::Warn => Ok(Some(InventoryEvent::ClockDrift { sku: sku.to_string(), latest, timestamp: now })),
+        }
+    }
+
     /// Add a new product to the inventory
     /// 
     /// # Requirements
@@ -59,17 +1020,22 @@ impl InventoryService {
     ) -> Result<Product, ServiceError> {
         // Validate SKU is non-empty
         if sku.trim().is_empty() {
-            return Err(ServiceError::InvalidInput("SKU cannot be empty".to_string()));
+            return Err(ServiceError::invalid_input(tr(Msg::InputSkuEmpty)));
         }
         
         // Validate name is non-empty
         if name.trim().is_empty() {
-            return Err(ServiceError::InvalidInput("Name cannot be empty".to_string()));
+            return Err(ServiceError::invalid_input(tr(Msg::InputNameEmpty)));
         }
+
+        // Enforce field limits, normalizing text to NFC
+        let sku = self.limits.clean(TextField::Sku, &sku)?;
+        let name = self.limits.clean(TextField::Name, &name)?;
+        let description = self.limits.clean(TextField::Description, &description)?;
         
         // Check for duplicate SKU
         if self.products.contains_key(&sku) {
-            return Err(ServiceError::DuplicateSKU(sku));
+            return Err(ServiceError::DuplicateSKU { sku });
         }
         
         // Create new product with UUID
@@ -80,13 +1046,20 @@ impl InventoryService {
             description,
             quantity: initial_quantity,
             reorder_point,
+            created_at: Some(Utc::now()),
+            ..Default::default()
         };
         
-        // Insert into HashMap
+        // Insert into the SKU index
         self.products.insert(sku, product.clone());
+        self.rebuild_cost_layers(&product.sku);
+        if product.quantity > 0 {
+            self.stock_changed(&product.sku, 0, product.quantity, None);
+        }
         
         // Persist to storage
         self.persist_products()?;
+        log::info!("Added product '{}' with quantity {}", product.sku, product.quantity);
         
         Ok(product)
     }
@@ -101,97 +1074,1017 @@ impl InventoryService {
     pub fn update_product(
         &mut self,
         sku: &str,
-        name: Option<String>,
-        description: Option<String>,
-        reorder_point: Option<u32>,
+        update: ProductUpdate,
     ) -> Result<Product, ServiceError> {
         // Check product exists
-        let product = self.products.get_mut(sku)
-            .ok_or_else(|| ServiceError::ProductNotFound(sku.to_string()))?;
-        
-        // Validate and update name if provided
-        if let Some(new_name) = name {
-            if new_name.trim().is_empty() {
-                return Err(ServiceError::InvalidInput("Name cannot be empty".to_string()));
-            }
+        if !self.products.contains_key(sku) {
+            return Err(ServiceError::ProductNotFound { sku: sku.to_string() });
+        }
+
+        // Validate every field before changing anything
+        if update.name.as_ref().is_some_and(|name| name.trim().is_empty()) {
+            return Err(ServiceError::invalid_input(tr(Msg::InputNameEmpty)));
+        }
+        let limits = &self.limits;
+        let update = ProductUpdate {
+            name: limits.clean_optional(TextField::Name, update.name)?,
+            description: limits.clean_optional(TextField::Description, update.description)?,
+            category: limits.clean_optional(TextField::Category, update.category)?,
+            supplier: limits.clean_optional(TextField::Supplier, update.supplier)?,
+            location: limits.clean_optional(TextField::Location, update.location)?,
+            barcode: limits.clean_optional(TextField::Sku, update.barcode)?,
+            ..update
+        };
+        if let Some(components) = &update.components {
+            self.validate_components(sku, components)?;
+        }
+        let product = self.products.get_mut(sku).unwrap();
+
+        // Update name if provided
+        if let Some(new_name) = update.name {
             product.name = new_name;
         }
         
         // Update description if provided
-        if let Some(new_description) = description {
+        if let Some(new_description) = update.description {
             product.description = new_description;
         }
         
         // Update reorder_point if provided
-        if let Some(new_reorder_point) = reorder_point {
+        if let Some(new_reorder_point) = update.reorder_point {
             product.reorder_point = new_reorder_point;
         }
+
+        // Update classification fields, treating empty values as "clear"
+        if let Some(category) = update.category {
+            product.category = non_empty(category);
+        }
+        if let Some(supplier) = update.supplier {
+            product.supplier = non_empty(supplier);
+        }
+        if let Some(location) = update.location {
+            product.location = non_empty(location);
+        }
+        if let Some(barcode) = update.barcode {
+            product.barcode = non_empty(barcode);
+        }
+        if let Some(threshold) = update.large_movement_threshold {
+            product.large_movement_threshold = threshold;
+        }
+        if let Some(components) = update.components {
+            product.components = components;
+        }
+        let cost_changed = update.unit_cost.is_some_and(|unit_cost| unit_cost != product.unit_cost);
+        if let Some(unit_cost) = update.unit_cost {
+            product.unit_cost = unit_cost;
+        }
+        if let Some(tare_weight) = update.tare_weight {
+            product.tare_weight = tare_weight;
+        }
+        if let Some(unit_weight) = update.unit_weight {
+            product.unit_weight = unit_weight;
+        }
+        if let Some(lead_time_days) = update.lead_time_days {
+            product.lead_time_days = lead_time_days;
+        }
+        product.settle_snooze();
         
         let updated_product = product.clone();
+        // Only the opening layer takes the product's cost; receipts keep their own
+        if cost_changed {
+            self.rebuild_cost_layers(sku);
+        } else {
+            self.refresh_read_model(sku);
+        }
         
         // Persist to storage
         self.persist_products()?;
+        log::info!("Updated product '{}'", sku);
         
         Ok(updated_product)
     }
 
-    /// Get a product by SKU
-    /// 
-    /// # Requirements
-    /// - 5.1: Return current stock level and product details
-    pub fn get_product(&self, sku: &str) -> Result<&Product, ServiceError> {
-        self.products.get(sku)
-            .ok_or_else(|| ServiceError::ProductNotFound(sku.to_string()))
+    /// Apply the same changes to every product passing a filter, returning the updated products
+    ///
+    /// Names, barcodes, and components identify a single product, so they
+    /// can't be changed this way. The changes are checked against the first
+    /// product before anything is changed, and saved once at the end.
+    pub fn update_where(&mut self, filter: &ProductFilter, changes: ProductUpdate) -> Result<Vec<Product>, ServiceError> {
+        if changes.name.is_some() || changes.barcode.is_some() || changes.components.is_some() {
+            return Err(ServiceError::invalid_input(tr(Msg::InputSingleProductFields)));
+        }
+        let skus: Vec<String> = self.query_products(filter).into_iter().map(|p| p.sku.clone()).collect();
+        let mut batch = self.begin_batch();
+        let updated = skus
+            .iter()
+            .map(|sku| batch.update_product(sku, changes.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        batch.commit()?;
+        log::info!("Updated {} products matching a filter", updated.len());
+        Ok(updated)
     }
 
-    /// List all products in the inventory
-    /// 
-    /// # Requirements
-    /// - 5.2: Return list of all products with current stock levels
-    pub fn list_products(&self) -> Vec<&Product> {
-        self.products.values().collect()
+    /// Check a kit's bill of materials: existing, distinct components other than the kit
+    fn validate_components(&self, kit_sku: &str, components: &[Component]) -> Result<(), ServiceError> {
+        for (index, component) in components.iter().enumerate() {
+            if component.quantity == 0 {
+                return Err(ServiceError::invalid_input(trf(
+                    Msg::InputComponentQuantity, &[("sku", &component.sku)]
+                )));
+            }
+            if component.sku == kit_sku {
+                return Err(ServiceError::invalid_input(trf(Msg::InputSelfComponent, &[("sku", &kit_sku)])));
+            }
+            if components[..index].iter().any(|other| other.sku == component.sku) {
+                return Err(ServiceError::invalid_input(trf(
+                    Msg::InputComponentRepeated, &[("sku", &component.sku)]
+                )));
+            }
+            if !self.products.contains_key(&component.sku) {
+                return Err(ServiceError::ProductNotFound { sku: component.sku.clone() });
+            }
+        }
+        Ok(())
     }
 
-    /// Delete a product and all its associated transactions
-    /// 
-    /// # Requirements
-    /// - 7.1: Remove product record from storage
-    /// - 7.2: Return error for non-existent product
-    /// - 7.3: Remove all associated transaction records
-    /// - 7.4: Confirm deletion
-    /// - 7.5: Allow deletion of products with current stock
-    pub fn delete_product(&mut self, sku: &str) -> Result<(), ServiceError> {
-        // Check product exists
-        if !self.products.contains_key(sku) {
-            return Err(ServiceError::ProductNotFound(sku.to_string()));
+    /// Link a product to another, e.g. as a substitute for it
+    ///
+    /// Returns `false` if the link already existed.
+    pub fn link_products(&mut self, sku: &str, kind: LinkKind, target: &str) -> Result<bool, ServiceError> {
+        if sku == target {
+            return Err(ServiceError::invalid_input(trf(Msg::InputSelfLink, &[("sku", &sku)])));
         }
-        
-        // Remove product from HashMap
-        self.products.remove(sku);
-        
-        // Remove all associated transactions
-        self.transactions.retain(|t| t.product_sku != sku);
-        
+        if !self.products.contains_key(target) {
+            return Err(ServiceError::ProductNotFound { sku: target.to_string() });
+        }
+        let product = self.products.get_mut(sku)
+            .ok_or_else(|| ServiceError::ProductNotFound { sku: sku.to_string() })?;
+        let link = ProductLink { kind, sku: target.to_string() };
+        if product.links.contains(&link) {
+            return Ok(false);
+        }
+        product.links.push(link);
+        product.links.sort_by(|a, b| (a.kind, &a.sku).cmp(&(b.kind, &b.sku)));
+        log::info!("Linked '{}' as {} '{}'", sku, kind, target);
+        self.persist_products()?;
+        Ok(true)
+    }
+
+    /// Remove a link between two products, returning whether there was one
+    pub fn unlink_products(&mut self, sku: &str, kind: LinkKind, target: &str) -> Result<bool, ServiceError> {
+        let product = self.products.get_mut(sku)
+            .ok_or_else(|| ServiceError::ProductNotFound { sku: sku.to_string() })?;
+        let before = product.links.len();
+        product.links.retain(|link| !(link.kind == kind && link.sku == target));
+        if product.links.len() == before {
+            return Ok(false);
+        }
+        log::info!("Unlinked '{}' as {} '{}'", sku, kind, target);
+        self.persist_products()?;
+        Ok(true)
+    }
+
+    /// Products that link to `sku` with the given kind, in SKU order
+    pub fn linked_to(&self, sku: &str, kind: LinkKind) -> Vec<&Product> {
+        self.products
+            .values()
+            .filter(|product| product.links.iter().any(|link| link.kind == kind && link.sku == sku))
+            .collect()
+    }
+
+    /// Substitutes for a product that have stock available, most stock first
+    pub fn substitutes_in_stock(&self, sku: &str) -> Vec<&Product> {
+        let mut substitutes: Vec<&Product> = self
+            .linked_to(sku, LinkKind::SubstituteFor)
+            .into_iter()
+            .filter(|product| product.quantity > 0)
+            .collect();
+        substitutes.sort_by(|a, b| b.quantity.cmp(&a.quantity).then_with(|| a.sku.cmp(&b.sku)));
+        substitutes
+    }
+
+    /// How many units of a kit can be assembled from the components in stock
+    ///
+    /// A component that has since been deleted counts as out of stock.
+    pub fn check_buildable(&self, kit_sku: &str) -> Result<Buildable, ServiceError> {
+        let kit = self.get_product(kit_sku)?;
+        if kit.components.is_empty() {
+            return Err(ServiceError::invalid_input(trf(
+                Msg::InputNoComponents, &[("sku", &kit_sku)]
+            )));
+        }
+        let components: Vec<ComponentStock> = kit.components
+            .iter()
+            .map(|component| ComponentStock {
+                sku: component.sku.clone(),
+                per_kit: component.quantity,
+                available: self.products.get(&component.sku).map_or(0, |product| product.quantity),
+            })
+            .collect();
+        let kits = components.iter().map(ComponentStock::kits).min().unwrap_or(0);
+        Ok(Buildable { kits, components })
+    }
+
+    /// Get a product by SKU
+    /// 
+    /// # Requirements
+    /// - 5.1: Return current stock level and product details
+    pub fn get_product(&self, sku: &str) -> Result<&Product, ServiceError> {
+        self.products.get(sku)
+            .ok_or_else(|| ServiceError::ProductNotFound { sku: sku.to_string() })
+    }
+
+    /// List all products in the inventory, in SKU order
+    /// 
+    /// # Requirements
+    /// - 5.2: Return list of all products with current stock levels
+    pub fn list_products(&self) -> Vec<&Product> {
+        self.products.values().collect()
+    }
+
+    /// Products passing a filter, in the filter's sort order
+    ///
+    /// A SKU prefix without wildcards matches every SKU starting with it.
+    pub fn query_products(&self, filter: &ProductFilter) -> Vec<&Product> {
+        let candidates = match filter.sku_prefix.as_deref() {
+            Some(prefix) if prefix.ends_with('*') => self.find_products(prefix),
+            Some(prefix) => self.find_products(&format!("{}*", prefix)),
+            None => self.list_products(),
+        };
+        let mut products: Vec<&Product> = candidates.into_iter().filter(|p| filter.matches(p)).collect();
+        match filter.sort {
+            ProductSort::Sku => {}
+            ProductSort::Name => products.sort_by(|a, b| a.name.cmp(&b.name)),
+            ProductSort::Quantity => products.sort_by_key(|p| p.quantity),
+        }
+        products
+    }
+
+    /// Products whose SKU matches a glob pattern, in SKU order
+    ///
+    /// `*` matches any run of characters and `?` a single one; matching is
+    /// case-sensitive. Only the SKUs sharing the pattern's literal prefix are
+    /// scanned, so `WID*` stays cheap in a large catalog.
+    pub fn find_products(&self, pattern: &str) -> Vec<&Product> {
+        let prefix = &pattern[..pattern.find(['*', '?']).unwrap_or(pattern.len())];
+        self.products
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(|(sku, _)| sku.starts_with(prefix))
+            .filter(|(sku, _)| glob_matches(pattern, sku))
+            .map(|(_, product)| product)
+            .collect()
+    }
+
+    /// Count the units of a product in a gross weight read from a scale
+    ///
+    /// The product's tare weight, if any, is taken off first; the rest is
+    /// divided by its unit weight and rounded to the nearest whole unit.
+    pub fn units_for_weight(&self, sku: &str, gross: Weight) -> Result<Weighing, ServiceError> {
+        let product = self.get_product(sku)?;
+        let unit_weight = product.unit_weight.filter(|weight| weight.milligrams() > 0).ok_or_else(|| {
+            ServiceError::invalid_input(trf(Msg::InputNoUnitWeight, &[("sku", &sku)]))
+        })?;
+        let tare = product.tare_weight.unwrap_or_default();
+        let net = gross.milligrams().saturating_sub(tare.milligrams());
+        let unit = unit_weight.milligrams();
+        let units = u32::try_from((net + unit / 2) / unit).unwrap_or(u32::MAX);
+        if units == 0 {
+            return Err(ServiceError::invalid_input(trf(
+                Msg::InputWeightBelowUnit,
+                &[("gross", &gross), ("sku", &sku), ("unit_weight", &unit_weight), ("tare", &tare)],
+            )));
+        }
+        Ok(Weighing { gross, tare, unit_weight, units })
+    }
+
+    /// Products a scanned code identifies, in SKU order
+    ///
+    /// That's the product with the code as its SKU plus every product with
+    /// the code as its barcode, so more than one means the code is ambiguous.
+    pub fn products_for_code(&self, code: &str) -> Vec<&Product> {
+        self.products
+            .values()
+            .filter(|product| product.sku == code || product.barcode.as_deref() == Some(code))
+            .collect()
+    }
+
+    /// Delete a product and all its associated transactions
+    /// 
+    /// # Requirements
+    /// - 7.1: Remove product record from storage
+    /// - 7.2: Return error for non-existent product
+    /// - 7.3: Remove all associated transaction records
+    /// - 7.4: Confirm deletion
+    /// - 7.5: Allow deletion of products with current stock
+    ///
+    /// This bypasses the deletion policy's safeguards; the product is still
+    /// archived to the tombstone file if the policy says so.
+    pub fn delete_product(&mut self, sku: &str) -> Result<(), ServiceError> {
+        self.delete_product_with_options(sku, DeleteOptions { force: true })
+    }
+
+    /// Delete a product, refusing if it has stock or recent activity
+    ///
+    /// Unless `options.force` is set, deletion fails with `DeletionBlocked`
+    /// while the product has stock on hand or transactions within the
+    /// policy's recent-activity window. With archiving enabled the product
+    /// and its transactions are written to the tombstone file before they
+    /// are removed.
+    pub fn delete_product_with_options(&mut self, sku: &str, options: DeleteOptions) -> Result<(), ServiceError> {
+        // Check product exists
+        let product = self.products.get(sku)
+            .ok_or_else(|| ServiceError::ProductNotFound { sku: sku.to_string() })?;
+
+        if !options.force {
+            if let Some(blocker) = self.deletion_blocker(product) {
+                log::debug!("Refused to delete '{}': {}", sku, blocker);
+                return Err(ServiceError::DeletionBlocked { sku: sku.to_string(), blocker });
+            }
+        }
+
+        // Remove product and all associated transactions
+        let product = self.products.remove(sku).unwrap();
+        self.read_model.remove(sku);
+        self.cost_layers.remove(sku);
+        if product.quantity > 0 {
+            self.stock_changed(sku, product.quantity, 0, None);
+        }
+        let (removed, kept): (Vec<Transaction>, Vec<Transaction>) = std::mem::take(&mut self.transactions)
+            .into_iter()
+            .partition(|t| t.product_sku == sku);
+        self.transactions = kept;
+        self.latest_timestamps.remove(sku);
+
+        // Archive before persisting, so a failed write loses nothing
+        if self.deletion_policy.archive {
+            let tombstone = Tombstone {
+                product,
+                transactions: removed,
+                deleted_at: Utc::now(),
+                deleted_by: self.operator.clone(),
+            };
+            self.storage.append_tombstone(&tombstone)
+                .map_err(ServiceError::storage("archiving the deleted product"))?;
+            self.purge_expired_trash()?;
+        } else if self.hash_chain && removed.iter().any(|t| t.prev_hash.is_some()) {
+            // Without the trash, keep the history in the archive so the chain holds
+            self.storage.archive_transactions(&removed)
+                .map_err(ServiceError::storage("archiving the deleted product's transactions"))?;
+        }
+
         // Persist changes to storage
         self.persist_products()?;
         self.persist_transactions()?;
+        log::info!("Deleted product '{}' and its transactions", sku);
         
         Ok(())
     }
 
-    /// Persist products to storage
-    fn persist_products(&self) -> Result<(), ServiceError> {
-        let products: Vec<Product> = self.products.values().cloned().collect();
-        self.storage.save_products(&products)?;
+    /// Deleted products that can still be restored, oldest deletion first
+    pub fn trash(&self) -> Result<Vec<Tombstone>, ServiceError> {
+        let mut tombstones = self.storage.load_tombstones()
+            .map_err(ServiceError::storage("loading the trash"))?;
+        tombstones.retain(|tombstone| !self.is_expired(tombstone));
+        Ok(tombstones)
+    }
+
+    /// When a trashed product will be purged, or `None` if the trash is kept forever
+    pub fn trash_expiry(&self, tombstone: &Tombstone) -> Option<DateTime<Utc>> {
+        tombstone.expires_at(self.deletion_policy.trash_retention_days)
+    }
+
+    /// Whether a trashed product is past the retention period
+    fn is_expired(&self, tombstone: &Tombstone) -> bool {
+        self.trash_expiry(tombstone).is_some_and(|expires| expires <= Utc::now())
+    }
+
+    /// Restore the most recently deleted product with the given SKU from the trash
+    ///
+    /// The product comes back with its transactions and is removed from the
+    /// trash. Fails with `DuplicateSKU` if a product with the SKU was added
+    /// since the deletion.
+    pub fn restore_product(&mut self, sku: &str) -> Result<Product, ServiceError> {
+        if self.products.contains_key(sku) {
+            return Err(ServiceError::DuplicateSKU { sku: sku.to_string() });
+        }
+        let mut trash = self.trash()?;
+        let index = trash.iter().rposition(|tombstone| tombstone.product.sku == sku)
+            .ok_or_else(|| ServiceError::NotInTrash { sku: sku.to_string() })?;
+        let tombstone = trash.remove(index);
+
+        let product = tombstone.product;
+        self.products.insert(product.sku.clone(), product.clone());
+        let restored = tombstone.transactions.len();
+        if let Some(latest) = tombstone.transactions.iter().map(|t| t.timestamp).max() {
+            self.latest_timestamps.insert(sku.to_string(), latest);
+        }
+        self.transactions.extend(tombstone.transactions);
+        self.transactions.sort_by_key(|t| t.order_key());
+        self.rebuild_cost_layers(sku);
+        if product.quantity > 0 {
+            self.stock_changed(sku, 0, product.quantity, None);
+        }
+
+        self.persist_products()?;
+        self.persist_transactions()?;
+        self.storage.save_tombstones(&trash).map_err(ServiceError::storage("updating the trash"))?;
+        log::info!("Restored product '{}' with {} transactions", sku, restored);
+        Ok(product)
+    }
+
+    /// Permanently remove trashed products past the retention period
+    ///
+    /// Returns the number of products purged. With the hash chain on, their
+    /// chained transactions are archived rather than dropped, so the chain
+    /// stays verifiable.
+    pub fn purge_expired_trash(&self) -> Result<usize, ServiceError> {
+        let (expired, kept): (Vec<Tombstone>, Vec<Tombstone>) = self.storage.load_tombstones()
+            .map_err(ServiceError::storage("loading the trash"))?
+            .into_iter()
+            .partition(|tombstone| self.is_expired(tombstone));
+        let purged = expired.len();
+        if self.hash_chain {
+            let chained: Vec<Transaction> = expired
+                .iter()
+                .flat_map(|tombstone| &tombstone.transactions)
+                .filter(|t| t.prev_hash.is_some())
+                .cloned()
+                .collect();
+            if !chained.is_empty() {
+                self.storage.archive_transactions(&chained)
+                    .map_err(ServiceError::storage("archiving purged transactions"))?;
+            }
+        }
+        if purged > 0 {
+            self.storage.save_tombstones(&kept).map_err(ServiceError::storage("purging the trash"))?;
+            log::info!("Purged {} expired products from the trash", purged);
+        }
+        Ok(purged)
+    }
+
+    /// Rewrite the data files without duplicates or expired trash
+    ///
+    /// Products with a repeated SKU keep the last record (as at startup) and
+    /// transactions with a repeated ID keep the first. With `archive_before`,
+    /// older transactions are moved to the archive; stock levels are
+    /// unaffected since they are stored on the products.
+    pub fn compact(&mut self, archive_before: Option<DateTime<Utc>>) -> Result<CompactionReport, ServiceError> {
+        let bytes_before = self.storage.data_size().map_err(ServiceError::storage("measuring data files"))?;
+        let stored_products = self.storage.load_products()
+            .map_err(ServiceError::storage("loading products"))?
+            .len();
+
+        let mut seen = HashSet::new();
+        let count = self.transactions.len();
+        self.transactions.retain(|t| seen.insert(t.id.clone()));
+        let duplicate_transactions = count - self.transactions.len();
+
+        let archived_transactions = match archive_before {
+            Some(before) => self.move_to_archive(before)?,
+            None => 0,
+        };
+        let purged_trash = self.purge_expired_trash()?;
+        self.persist_products()?;
+        self.persist_transactions()?;
+
+        let report = CompactionReport {
+            bytes_before,
+            bytes_after: self.storage.data_size().map_err(ServiceError::storage("measuring data files"))?,
+            duplicate_products: stored_products.saturating_sub(self.products.len()),
+            duplicate_transactions,
+            purged_trash,
+            archived_transactions,
+        };
+        log::info!("Compacted storage, reclaiming {} bytes", report.reclaimed());
+        Ok(report)
+    }
+
+    /// Move transactions older than `before` to the archive
+    ///
+    /// Archived transactions no longer load at startup but stay available
+    /// through `archived_transactions`. Returns how many were moved.
+    pub fn archive_transactions(&mut self, before: DateTime<Utc>) -> Result<usize, ServiceError> {
+        let archived = self.move_to_archive(before)?;
+        if archived > 0 {
+            self.persist_transactions()?;
+            log::info!("Archived {} transactions older than {}", archived, before.to_rfc3339());
+        }
+        Ok(archived)
+    }
+
+    /// Archive and drop transactions older than `before`, without saving the rest
+    fn move_to_archive(&mut self, before: DateTime<Utc>) -> Result<usize, ServiceError> {
+        let (archived, kept): (Vec<Transaction>, Vec<Transaction>) = std::mem::take(&mut self.transactions)
+            .into_iter()
+            .partition(|t| t.timestamp < before);
+        // Archive before persisting, so a failed write loses nothing
+        if !archived.is_empty() {
+            if let Err(err) = self.storage.archive_transactions(&archived) {
+                self.transactions = archived.into_iter().chain(kept).collect();
+                return Err(ServiceError::storage("archiving transactions")(err));
+            }
+        }
+        self.transactions = kept;
+        self.latest_timestamps = latest_timestamps(&self.transactions);
+        Ok(archived.len())
+    }
+
+    /// Record today's totals in the KPI file, replacing an earlier snapshot of the day
+    ///
+    /// Totals come from the read model and the cost layers already in memory,
+    /// so taking a snapshot doesn't replay the ledger; only today's
+    /// transactions are counted as movements.
+    pub fn snapshot_kpis(&self, now: DateTime<Utc>) -> Result<KpiSnapshot, ServiceError> {
+        if self.read_only {
+            return Err(ServiceError::ReadOnly);
+        }
+        let date = now.date_naive();
+        let totals = self.read_model.totals();
+        let snapshot = KpiSnapshot {
+            date,
+            taken_at: now,
+            products: totals.products,
+            units: totals.units,
+            value: totals.value.value,
+            low_stock: self.read_model.low_stock().count(),
+            movements: self.transactions.iter().filter(|t| t.timestamp.date_naive() == date).count(),
+        };
+
+        let mut snapshots = self.storage.load_kpi_snapshots()
+            .map_err(ServiceError::storage("loading KPI snapshots"))?;
+        snapshots.retain(|existing| existing.date != date);
+        snapshots.push(snapshot.clone());
+        snapshots.sort_by_key(|existing| existing.date);
+        self.storage.save_kpi_snapshots(&snapshots).map_err(ServiceError::storage("saving KPI snapshots"))?;
+        Ok(snapshot)
+    }
+
+    /// Recorded KPI snapshots with `start <= date < end`, oldest first
+    pub fn kpi_snapshots(&self, start: Option<NaiveDate>, end: Option<NaiveDate>) -> Result<Vec<KpiSnapshot>, ServiceError> {
+        let mut snapshots = self.storage.load_kpi_snapshots()
+            .map_err(ServiceError::storage("loading KPI snapshots"))?;
+        snapshots.retain(|snapshot| {
+            start.is_none_or(|start| snapshot.date >= start) && end.is_none_or(|end| snapshot.date < end)
+        });
+        snapshots.sort_by_key(|snapshot| snapshot.date);
+        Ok(snapshots)
+    }
+
+    /// Check the transaction hash chain for tampering or truncation
+    ///
+    /// Transactions in the ledger, the archive, and the trash are all checked.
+    /// With `backup`, the chain head saved in a backup must also still be on
+    /// the chain.
+    pub fn verify_ledger(&self, backup: Option<&LedgerHead>) -> Result<LedgerReport, ServiceError> {
+        let mut transactions = self.transactions.clone();
+        transactions.extend(self.storage.load_archived_transactions()
+            .map_err(ServiceError::storage("loading archived transactions"))?);
+        for tombstone in self.storage.load_tombstones().map_err(ServiceError::storage("loading the trash"))? {
+            transactions.extend(tombstone.transactions);
+        }
+        let head = self.storage.load_ledger_head().map_err(ServiceError::storage("loading the ledger head"))?;
+        Ok(ledger::verify(&transactions, head.as_ref(), backup))
+    }
+
+    /// Clear notes and customer codes older than the retention policy allows
+    ///
+    /// Transactions in the ledger, the archive, and the trash are redacted, as
+    /// are outstanding backorders (by when they were recorded). Redacting
+    /// changes the hashes of chained transactions, so the hash chain is linked
+    /// again and heads saved in earlier backups no longer match; a chain that
+    /// is already broken is left alone and the redaction refused. With
+    /// `dry_run`, nothing is saved.
+    pub fn apply_retention(&mut self, dry_run: bool) -> Result<RetentionReport, ServiceError> {
+        let now = Utc::now();
+        // A period reaching back before the earliest representable date
+        // clears nothing, as nothing is that old
+        let cutoff = |years: Option<u32>| {
+            years.map(|years| now.checked_sub_months(chrono::Months::new(years.saturating_mul(12))).unwrap_or(DateTime::<Utc>::MIN_UTC))
+        };
+        let notes_before = cutoff(self.retention_policy.notes_years);
+        let customers_before = cutoff(self.retention_policy.customer_years);
+        let mut report = RetentionReport::default();
+        let mut redact = |timestamp: DateTime<Utc>, notes: &mut Option<String>, customer: &mut Option<String>| {
+            let mut changed = false;
+            if notes.is_some() && notes_before.is_some_and(|before| timestamp < before) {
+                *notes = None;
+                report.notes += 1;
+                changed = true;
+            }
+            if customer.is_some() && customers_before.is_some_and(|before| timestamp < before) {
+                *customer = None;
+                report.customers += 1;
+                changed = true;
+            }
+            changed
+        };
+
+        // Ledger, archive, and trash in one list, so the chain can be relinked across them
+        let archived = self.storage.load_archived_transactions()
+            .map_err(ServiceError::storage("loading archived transactions"))?;
+        let mut trash = self.storage.load_tombstones().map_err(ServiceError::storage("loading the trash"))?;
+        let (live_count, archived_count) = (self.transactions.len(), archived.len());
+        let trash_counts: Vec<usize> = trash.iter().map(|tombstone| tombstone.transactions.len()).collect();
+        let mut transactions = self.transactions.clone();
+        transactions.extend(archived);
+        for tombstone in &mut trash {
+            transactions.append(&mut tombstone.transactions);
+        }
+        let head = self.storage.load_ledger_head().map_err(ServiceError::storage("loading the ledger head"))?;
+        let order = head.as_ref().and_then(|head| ledger::chain_order(&transactions, head));
+
+        let mut changed = vec![false; transactions.len()];
+        for (transaction, changed) in transactions.iter_mut().zip(&mut changed) {
+            *changed = redact(transaction.timestamp, &mut transaction.notes, &mut transaction.customer);
+        }
+        let mut backorders = self.storage.load_backorders().map_err(ServiceError::storage("loading backorders"))?;
+        let mut backorders_changed = false;
+        for backorder in &mut backorders {
+            backorders_changed |= redact(backorder.created_at, &mut backorder.notes, &mut backorder.customer);
+        }
+
+        let chained_changed = transactions.iter().zip(&changed).any(|(t, changed)| *changed && t.prev_hash.is_some());
+        let new_head = match (chained_changed, &order) {
+            (false, _) => None,
+            (true, Some(order)) => {
+                report.relinked = order.len();
+                ledger::relink(&mut transactions, order)
+            }
+            (true, None) => {
+                return Err(ServiceError::invalid_input(tr(Msg::InputRedactBrokenChain)))
+            }
+        };
+        if dry_run {
+            return Ok(report);
+        }
+
+        // Split the list back up; relinking may have touched any part of it
+        let relinked = new_head.is_some();
+        let changed_in = |range: std::ops::Range<usize>| relinked || changed[range].contains(&true);
+        let (live_changed, archive_changed, trash_changed) = (
+            changed_in(0..live_count),
+            changed_in(live_count..live_count + archived_count),
+            changed_in(live_count + archived_count..transactions.len()),
+        );
+        let mut archived = transactions.split_off(live_count);
+        let mut trashed = archived.split_off(archived_count);
+        for (tombstone, count) in trash.iter_mut().zip(trash_counts).rev() {
+            tombstone.transactions = trashed.split_off(trashed.len() - count);
+        }
+        if archive_changed {
+            self.storage.save_archived_transactions(&archived)
+                .map_err(ServiceError::storage("redacting archived transactions"))?;
+        }
+        if trash_changed {
+            self.storage.save_tombstones(&trash).map_err(ServiceError::storage("redacting the trash"))?;
+        }
+        if backorders_changed {
+            self.storage.save_backorders(&backorders).map_err(ServiceError::storage("redacting backorders"))?;
+        }
+        if live_changed {
+            self.transactions = transactions;
+            self.persist_transactions()?;
+        }
+        if let Some(head) = new_head {
+            self.storage.save_ledger_head(&head).map_err(ServiceError::storage("saving the ledger head"))?;
+            self.ledger_head = Some(head);
+        }
+        log::info!("Applied retention: cleared {} notes and {} customer codes", report.notes, report.customers);
+        Ok(report)
+    }
+
+    /// Give a new ID to every product and transaction whose ID an earlier one has
+    ///
+    /// Products are taken in SKU order and transactions in stored order; the
+    /// first record with an ID keeps it. A transaction that is an exact copy
+    /// of an earlier one is left for `compact` to drop. Each change is
+    /// recorded in the audit log. Chained transactions can't change ID
+    /// without breaking the hash chain, so finding one refuses the healing.
+    /// With `dry_run`, nothing is saved.
+    pub fn heal_ids(&mut self, dry_run: bool) -> Result<IdHealingReport, ServiceError> {
+        let mut report = IdHealingReport::default();
+        let mut seen = HashSet::new();
+        let mut products = Vec::new();
+        for product in self.products.values() {
+            if !seen.insert(product.id.clone()) {
+                products.push(product.sku.clone());
+            }
+        }
+        let mut first: HashMap<&str, &Transaction> = HashMap::new();
+        let mut transactions = Vec::new();
+        for (index, transaction) in self.transactions.iter().enumerate() {
+            match first.get(transaction.id.as_str()) {
+                None => {
+                    first.insert(&transaction.id, transaction);
+                }
+                Some(earlier) if *earlier == transaction => report.copies += 1,
+                Some(_) if transaction.prev_hash.is_some() => {
+                    return Err(ServiceError::invalid_input(trf(
+                        Msg::InputRedactChainedId, &[("id", &transaction.id)]
+                    )))
+                }
+                Some(_) => transactions.push(index),
+            }
+        }
+
+        let new_id = |record, sku: &str, old_id: &str| IdChange {
+            record,
+            sku: sku.to_string(),
+            old_id: old_id.to_string(),
+            new_id: Uuid::new_v4().to_string(),
+        };
+        report.changes.extend(products.iter().map(|sku| new_id(RecordKind::Product, sku, &self.products[sku].id)));
+        report.changes.extend(transactions.iter().map(|&index| {
+            let transaction = &self.transactions[index];
+            new_id(RecordKind::Transaction, &transaction.product_sku, &transaction.id)
+        }));
+        if dry_run || report.changes.is_empty() {
+            return Ok(report);
+        }
+
+        let (product_changes, transaction_changes) = report.changes.split_at(products.len());
+        for change in product_changes {
+            let product = self.products.get_mut(&change.sku).expect("SKU taken from the product map");
+            product.id = change.new_id.clone();
+            self.refresh_read_model(&change.sku);
+        }
+        for (&index, change) in transactions.iter().zip(transaction_changes) {
+            self.transactions[index].id = change.new_id.clone();
+        }
+        if !products.is_empty() {
+            self.persist_products()?;
+        }
+        if !transactions.is_empty() {
+            self.persist_transactions()?;
+        }
+        let now = Utc::now();
+        for change in &report.changes {
+            let event = InventoryEvent::IdReassigned {
+                record: change.record,
+                sku: change.sku.clone(),
+                old_id: change.old_id.clone(),
+                new_id: change.new_id.clone(),
+                timestamp: now,
+            };
+            self.storage.append_audit_event(&event).map_err(ServiceError::storage("appending to the audit log"))?;
+        }
+        log::info!("Gave {} records new IDs", report.changes.len());
+        Ok(report)
+    }
+
+    /// Archived transactions of a product, ordered by timestamp
+    pub fn archived_transactions(&self, sku: &str) -> Result<Vec<Transaction>, ServiceError> {
+        let mut transactions: Vec<Transaction> = self.storage.load_archived_transactions()
+            .map_err(ServiceError::storage("loading archived transactions"))?
+            .into_iter()
+            .filter(|t| t.product_sku == sku)
+            .collect();
+        transactions.sort_by_key(|t| t.order_key());
+        Ok(transactions)
+    }
+
+    /// The first reason the deletion policy refuses to delete `product`, if any
+    fn deletion_blocker(&self, product: &Product) -> Option<DeletionBlocker> {
+        if product.on_hand() > 0 {
+            let quantity = u32::try_from(product.on_hand()).unwrap_or(u32::MAX);
+            return Some(DeletionBlocker::InStock { quantity });
+        }
+        let days = self.deletion_policy.recent_activity_days;
+        if days == 0 {
+            return None;
+        }
+        let since = Utc::now() - chrono::Duration::days(i64::from(days));
+        let transactions = self.transactions
+            .iter()
+            .filter(|t| t.product_sku == product.sku && t.timestamp >= since)
+            .count();
+        (transactions > 0).then_some(DeletionBlocker::RecentActivity { transactions, days })
+    }
+
+    /// Defer saving products and transactions until the returned batch is committed
+    ///
+    /// For callers recording many movements at once, e.g. from a point-of-sale
+    /// end-of-day file: each operation still updates memory, checks its input,
+    /// and appends to the audit log right away, but the data files are
+    /// written once by `Batch::commit` instead of after every call. Until
+    /// then a crash loses the batch's changes to products and transactions,
+    /// and dropping the batch uncommitted puts them back as they were.
+    pub fn begin_batch(&mut self) -> Batch<'_> {
+        let snapshot = BatchSnapshot {
+            products: self.products.clone(),
+            read_model: self.read_model.clone(),
+            cost_layers: self.cost_layers.clone(),
+            transactions: self.transactions.clone(),
+            latest_timestamps: self.latest_timestamps.clone(),
+            last_seq: self.last_seq,
+            ledger_head: self.ledger_head.clone(),
+            pending: self.pending,
+            events: self.events.len(),
+            unsaved_events: self.unsaved_events.len(),
+        };
+        self.batching = true;
+        Batch { service: self, snapshot: Some(snapshot) }
+    }
+
+    /// Close the open batch, saving the data files it changed
+    fn end_batch(&mut self) -> Result<(), ServiceError> {
+        if !self.batching {
+            return Ok(());
+        }
+        self.batching = false;
+        self.flush()
+    }
+
+    /// Save the changes the save mode or an open batch has held back
+    ///
+    /// A data file that fails to save stays pending, so the next save tries
+    /// it again. Stock changes are only raised as events once saved.
+    pub fn flush(&mut self) -> Result<(), ServiceError> {
+        let pending = std::mem::take(&mut self.pending);
+        if pending.products {
+            if let Err(err) = self.write_products() {
+                self.pending = pending;
+                return Err(err);
+            }
+        }
+        if pending.transactions {
+            if let Err(err) = self.write_transactions() {
+                self.pending.transactions = true;
+                return Err(err);
+            }
+        }
+        self.last_saved = Instant::now();
+        self.events.append(&mut self.unsaved_events);
+        if pending.products || pending.transactions {
+            self.check_quotas()?;
+        }
         Ok(())
     }
 
-    /// Persist transactions to storage
-    fn persist_transactions(&self) -> Result<(), ServiceError> {
-        self.storage.save_transactions(&self.transactions)?;
+    /// The configured size quotas
+    pub fn size_quotas(&self) -> &SizeQuotas {
+        &self.quotas
+    }
+
+    /// Usage of each configured size quota
+    pub fn quota_usage(&self) -> Result<Vec<QuotaUsage>, ServiceError> {
+        let mut usage = Vec::new();
+        if let Some(limit) = self.quotas.max_products {
+            usage.push(QuotaUsage { quota: Quota::Products, used: self.products.len() as u64, limit });
+        }
+        if let Some(limit) = self.quotas.max_transactions {
+            usage.push(QuotaUsage { quota: Quota::Transactions, used: self.transactions.len() as u64, limit });
+        }
+        if let Some(limit) = self.quotas.max_data_bytes {
+            let used = self.storage.data_size().map_err(ServiceError::storage("measuring data files"))?;
+            usage.push(QuotaUsage { quota: Quota::DataBytes, used, limit });
+        }
+        Ok(usage)
+    }
+
+    /// Raise an event for each quota newly at or above its warning level
+    fn check_quotas(&mut self) -> Result<(), ServiceError> {
+        if self.quotas.is_empty() {
+            return Ok(());
+        }
+        let usage = match self.quota_usage() {
+            Ok(usage) => usage,
+            Err(err) => {
+                log::warn!("Failed to check size quotas: {}", err);
+                return Ok(());
+            }
+        };
+        for usage in usage {
+            if usage.percent() >= u64::from(self.quotas.warn_percent) && self.quotas_warned.insert(usage.quota) {
+                let QuotaUsage { quota, used, limit } = usage;
+                self.raise_event(InventoryEvent::QuotaApproached { quota, used, limit, timestamp: Utc::now() })?;
+            }
+        }
         Ok(())
     }
 
+    /// Save pending changes if the save mode says it's time
+    fn autosave(&mut self) -> Result<(), ServiceError> {
+        if self.batching {
+            return Ok(());
+        }
+        match self.save_mode {
+            SaveMode::Immediate => self.flush(),
+            SaveMode::DebounceMs(ms) if self.last_saved.elapsed() >= std::time::Duration::from_millis(ms) => self.flush(),
+            SaveMode::DebounceMs(_) | SaveMode::Manual => Ok(()),
+        }
+    }
+
+    /// Mark products as changed, saving them if the save mode says so
+    fn persist_products(&mut self) -> Result<(), ServiceError> {
+        if self.read_only {
+            return Err(ServiceError::ReadOnly);
+        }
+        self.pending.products = true;
+        self.autosave()
+    }
+
+    /// Write products to storage
+    fn write_products(&self) -> Result<(), ServiceError> {
+        let products: Vec<Product> = self.products.values().cloned().collect();
+        self.storage.save_products(&products).map_err(ServiceError::storage("saving products"))
+    }
+
+    /// Add a transaction to the history and apply it to the product's cost layers
+    ///
+    /// Stock received without a unit cost is costed at the product's unit
+    /// cost. A `StockChanged` event is queued for the move from the `before`
+    /// quantity.
+    fn record_transaction(&mut self, mut transaction: Transaction, before: u32) {
+        let kind = transaction.transaction_type;
+        if kind.adds_stock() && !kind.is_internal_move() && transaction.unit_cost.is_none() {
+            transaction.unit_cost = self.products.get(&transaction.product_sku).and_then(|p| p.unit_cost);
+        }
+        self.last_seq += 1;
+        transaction.seq = self.last_seq;
+        if self.hash_chain {
+            self.ledger_head = Some(ledger::link(&mut transaction, self.ledger_head.as_ref()));
+        }
+        self.cost_layers.entry(transaction.product_sku.clone()).or_default().apply(&transaction);
+        if let Some(product) = self.products.get_mut(&transaction.product_sku) {
+            product.settle_snooze();
+        }
+        let after = u32::try_from(i64::from(before) + signed_quantity(kind, transaction.quantity)).unwrap_or(0);
+        self.stock_changed(&transaction.product_sku, before, after, Some(&transaction));
+        let sku = transaction.product_sku.clone();
+        let latest = self.latest_timestamps.entry(sku.clone()).or_insert(transaction.timestamp);
+        *latest = (*latest).max(transaction.timestamp);
+        self.transactions.push(transaction);
+        self.refresh_read_model(&sku);
+    }
+
+    /// Bring a product's entry in the read model up to date with the product
+    /// and the value of its cost layers
+    fn refresh_read_model(&mut self, sku: &str) {
+        match self.products.get(sku) {
+            Some(product) => {
+                let value = self.cost_layers.get(sku).map(CostLayers::valuation).unwrap_or_default();
+                self.read_model.update(product, value);
+            }
+            None => self.read_model.remove(sku),
+        }
+    }
+
+    /// Queue a `StockChanged` event for a product's available quantity
+    /// going from `before` to `after`, to be raised when the change is saved
+    fn stock_changed(&mut self, sku: &str, before: u32, after: u32, transaction: Option<&Transaction>) {
+        log::debug!("Stock of '{}' changed: quantity {} -> {}", sku, before, after);
+        self.unsaved_events.push(InventoryEvent::StockChanged {
+            sku: sku.to_string(),
+            transaction_id: transaction.map(|t| t.id.clone()),
+            transaction_seq: transaction.map(|t| t.seq),
+            transaction_type: transaction.map(|t| t.transaction_type),
+            before,
+            after,
+            timestamp: transaction.map_or_else(Utc::now, |t| t.timestamp),
+        });
+    }
+
+    /// Replay a product's history into fresh cost layers, e.g. after backdated changes
+    fn rebuild_cost_layers(&mut self, sku: &str) {
+        match self.products.get(sku) {
+            Some(product) => {
+                let layers = CostLayers::replay(product, &self.get_transactions(sku), None);
+                self.cost_layers.insert(sku.to_string(), layers);
+            }
+            None => {
+                self.cost_layers.remove(sku);
+            }
+        }
+        self.refresh_read_model(sku);
+    }
+
+    /// Mark transactions as changed, saving them if the save mode says so
+    fn persist_transactions(&mut self) -> Result<(), ServiceError> {
+        if self.read_only {
+            return Err(ServiceError::ReadOnly);
+        }
+        self.pending.transactions = true;
+        self.autosave()
+    }
+
+    /// Write transactions, and the sequence number and chain head that go with them, to storage
+    fn write_transactions(&self) -> Result<(), ServiceError> {
+        self.storage.save_transactions(&self.transactions)
+            .map_err(ServiceError::storage("saving transactions"))?;
+        // Once the newest transactions have left the live data, only the
+        // saved number keeps their sequence numbers from being handed out again
+        if self.transactions.iter().all(|t| t.seq < self.last_seq) && self.last_seq > 0 {
+            self.storage.save_last_seq(self.last_seq)
+                .map_err(ServiceError::storage("saving the sequence number"))?;
+        }
+        match &self.ledger_head {
+            Some(head) if self.hash_chain => self.storage.save_ledger_head(head)
+                .map_err(ServiceError::storage("saving the ledger head")),
+            _ => Ok(()),
+        }
+    }
+
     /// Add stock to an existing product
     /// 
     /// # Requirements
@@ -206,36 +2099,299 @@ impl InventoryService {
         quantity: u32,
         notes: Option<String>,
     ) -> Result<(), ServiceError> {
+        self.add_stock_with_options(sku, quantity, notes, AdditionOptions::default())
+    }
+
+    /// Add stock in a given condition, e.g. refurbished returns
+    pub fn add_stock_in_condition(
+        &mut self,
+        sku: &str,
+        quantity: u32,
+        condition: Condition,
+        notes: Option<String>,
+    ) -> Result<(), ServiceError> {
+        self.add_stock_with_options(sku, quantity, notes, AdditionOptions { condition, ..Default::default() })
+    }
+
+    /// Add stock received in a condition or at a unit cost
+    ///
+    /// The units open a new FIFO cost layer at `options.unit_cost`, or at the
+    /// product's unit cost if none is given. New units are taken off the
+    /// product's on-order quantity; returns in another condition are not.
+    pub fn add_stock_with_options(
+        &mut self,
+        sku: &str,
+        quantity: u32,
+        notes: Option<String>,
+        options: AdditionOptions,
+    ) -> Result<(), ServiceError> {
+        let condition = options.condition;
         // Validate quantity is positive (non-zero)
         if quantity == 0 {
-            return Err(ServiceError::InvalidInput("Quantity must be positive".to_string()));
+            return Err(ServiceError::invalid_input(tr(Msg::InputQuantityPositive)));
         }
+        let notes = self.limits.clean_optional(TextField::Notes, notes)?;
+        let drift = self.check_clock(sku)?;
 
         // Validate product exists
         let product = self.products.get_mut(sku)
-            .ok_or_else(|| ServiceError::ProductNotFound(sku.to_string()))?;
+            .ok_or_else(|| ServiceError::ProductNotFound { sku: sku.to_string() })?;
 
         // Increase product quantity
+        let previous = product.quantity;
         product.quantity += quantity;
+        product.adjust_condition(condition, i64::from(quantity));
+        if condition == Condition::New {
+            product.on_order = product.on_order.saturating_sub(quantity);
+        }
+        log::info!(
+            "Added {} {} units to '{}': quantity {} -> {}{}",
+            quantity, condition, sku, previous, product.quantity, format_notes(notes.as_deref())
+        );
 
         // Create transaction record
         let transaction = Transaction {
-            id: Uuid::new_v4().to_string(),
-            product_sku: sku.to_string(),
-            transaction_type: TransactionType::Addition,
-            quantity,
-            timestamp: Utc::now(),
-            notes,
+            condition: (condition != Condition::New).then_some(condition),
+            unit_cost: options.unit_cost,
+            ..self.new_transaction(sku, TransactionType::Addition, quantity, notes)
         };
 
         // Add transaction to vector
-        self.transactions.push(transaction);
+        self.record_transaction(transaction, previous);
 
         // Persist both products and transactions
         self.persist_products()?;
         self.persist_transactions()?;
+        self.raise_events(drift)?;
 
-        Ok(())
+        self.handle_backorders(sku)
+    }
+
+    /// Report or fill a product's outstanding backorders after stock is added
+    fn handle_backorders(&mut self, sku: &str) -> Result<(), ServiceError> {
+        let outstanding: u32 = self.backorders()?
+            .iter()
+            .filter(|backorder| backorder.sku == sku)
+            .map(|backorder| backorder.quantity)
+            .sum();
+        if outstanding == 0 {
+            return Ok(());
+        }
+        if self.backorder_allocation {
+            self.fill_backorders(sku)?;
+            return Ok(());
+        }
+        self.raise_event(InventoryEvent::BackordersWaiting {
+            sku: sku.to_string(),
+            available: self.products[sku].quantity,
+            outstanding,
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// Add a customer that removals can be shipped to
+    pub fn add_customer(&mut self, code: String, name: String) -> Result<Customer, ServiceError> {
+        if code.trim().is_empty() {
+            return Err(ServiceError::invalid_input(tr(Msg::InputCustomerEmpty)));
+        }
+        if name.trim().is_empty() {
+            return Err(ServiceError::invalid_input(tr(Msg::InputNameEmpty)));
+        }
+        let code = self.limits.clean(TextField::Sku, &code)?;
+        let name = self.limits.clean(TextField::Name, &name)?;
+        if self.read_only {
+            return Err(ServiceError::ReadOnly);
+        }
+
+        let mut customers = self.customers()?;
+        if customers.iter().any(|customer| customer.code == code) {
+            return Err(ServiceError::DuplicateCustomer { code });
+        }
+        let customer = Customer { code, name, created_at: Utc::now() };
+        customers.push(customer.clone());
+        customers.sort_by(|a, b| a.code.cmp(&b.code));
+        self.storage.save_customers(&customers).map_err(ServiceError::storage("saving customers"))?;
+        log::info!("Added customer '{}'", customer.code);
+        Ok(customer)
+    }
+
+    /// All customers, by code
+    pub fn customers(&self) -> Result<Vec<Customer>, ServiceError> {
+        self.storage.load_customers().map_err(ServiceError::storage("loading customers"))
+    }
+
+    /// The customer with the given code
+    pub fn customer(&self, code: &str) -> Result<Customer, ServiceError> {
+        self.customers()?
+            .into_iter()
+            .find(|customer| customer.code == code)
+            .ok_or_else(|| ServiceError::CustomerNotFound { code: code.to_string() })
+    }
+
+    /// Units shipped to customers, by customer and product
+    ///
+    /// Only removals recorded with a customer and `start <= timestamp < end`
+    /// are counted; either end may be left open. With `customer`, only that
+    /// customer's shipments are counted. Lines are ordered by customer code,
+    /// then SKU.
+    pub fn shipments(
+        &self,
+        customer: Option<&str>,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<Vec<ShipmentLine>, ServiceError> {
+        if let Some(code) = customer {
+            self.customer(code)?;
+        }
+        let mut lines: BTreeMap<(&str, &str), ShipmentLine> = BTreeMap::new();
+        for transaction in &self.transactions {
+            let Some(shipped_to) = transaction.customer.as_deref() else {
+                continue;
+            };
+            let in_period = start.is_none_or(|start| transaction.timestamp >= start)
+                && end.is_none_or(|end| transaction.timestamp < end);
+            if !in_period || customer.is_some_and(|code| code != shipped_to) {
+                continue;
+            }
+            let line = lines.entry((shipped_to, &transaction.product_sku)).or_insert_with(|| ShipmentLine {
+                customer: shipped_to.to_string(),
+                sku: transaction.product_sku.clone(),
+                transactions: 0,
+                units: 0,
+            });
+            line.transactions += 1;
+            line.units += u64::from(transaction.quantity);
+        }
+        Ok(lines.into_values().collect())
+    }
+
+    /// Outstanding backorders, oldest first
+    pub fn backorders(&self) -> Result<Vec<Backorder>, ServiceError> {
+        self.storage.load_backorders().map_err(ServiceError::storage("loading backorders"))
+    }
+
+    /// Units of a product committed to customers by outstanding backorders
+    ///
+    /// Backorders are the open sales orders this inventory knows about, so
+    /// the stock that can still be promised is `quantity` less these.
+    pub fn committed_quantity(&self, sku: &str) -> Result<u32, ServiceError> {
+        self.get_product(sku)?;
+        Ok(self.backorders()?
+            .iter()
+            .filter(|backorder| backorder.sku == sku)
+            .fold(0u32, |committed, backorder| committed.saturating_add(backorder.quantity)))
+    }
+
+    /// Record units of a product owed to someone, optionally a known customer
+    pub fn record_backorder(
+        &mut self,
+        sku: &str,
+        quantity: u32,
+        notes: Option<String>,
+        customer: Option<String>,
+    ) -> Result<Backorder, ServiceError> {
+        if quantity == 0 {
+            return Err(ServiceError::invalid_input(tr(Msg::InputQuantityPositive)));
+        }
+        let notes = self.limits.clean_optional(TextField::Notes, notes)?;
+        self.get_product(sku)?;
+        if let Some(code) = &customer {
+            self.customer(code)?;
+        }
+        if self.read_only {
+            return Err(ServiceError::ReadOnly);
+        }
+
+        let backorder = Backorder {
+            id: Uuid::new_v4().to_string(),
+            sku: sku.to_string(),
+            quantity,
+            created_at: Utc::now(),
+            created_by: self.operator.clone(),
+            notes,
+            customer,
+        };
+        let mut backorders = self.backorders()?;
+        backorders.push(backorder.clone());
+        self.storage.save_backorders(&backorders).map_err(ServiceError::storage("saving backorders"))?;
+        log::info!("Backordered {} units of '{}'{}", quantity, sku, format_notes(backorder.notes.as_deref()));
+        Ok(backorder)
+    }
+
+    /// Remove stock, backordering whatever isn't available
+    ///
+    /// The units on hand are removed as with `remove_stock_with_options` and
+    /// the shortfall is recorded as a backorder, which is returned. Only
+    /// plain removals can be backordered, not losses.
+    pub fn remove_or_backorder(
+        &mut self,
+        sku: &str,
+        quantity: u32,
+        notes: Option<String>,
+        options: RemovalOptions,
+    ) -> Result<Option<Backorder>, ServiceError> {
+        if options.transaction_type.is_some_and(|t| t != TransactionType::Removal) {
+            return Err(ServiceError::invalid_input(tr(Msg::InputBackorderLoss)));
+        }
+        let product = self.get_product(sku)?;
+        let available = options.condition.map_or(product.quantity, |c| product.condition_quantity(c));
+        if quantity <= available {
+            self.remove_stock_with_options(sku, quantity, notes, options)?;
+            return Ok(None);
+        }
+        let customer = options.customer.clone();
+        if available > 0 {
+            self.remove_stock_with_options(sku, available, notes.clone(), options)?;
+        }
+        self.record_backorder(sku, quantity - available, notes, customer).map(Some)
+    }
+
+    /// Fill a product's backorders from its available stock, oldest first
+    ///
+    /// Each fill is a Removal transaction noting the backorder, and raises a
+    /// `BackorderFilled` event. Backorders are filled in part when stock runs
+    /// out. Returns the units removed.
+    pub fn fill_backorders(&mut self, sku: &str) -> Result<u32, ServiceError> {
+        self.get_product(sku)?;
+        let mut backorders = self.backorders()?;
+        let mut filled = 0;
+        for index in 0..backorders.len() {
+            let available = self.products[sku].quantity;
+            if available == 0 {
+                break;
+            }
+            let backorder = &backorders[index];
+            if backorder.sku != sku {
+                continue;
+            }
+            let quantity = backorder.quantity.min(available);
+            let notes = match &backorder.notes {
+                Some(notes) => format!("Backorder {}: {}", backorder.short_id(), notes),
+                None => format!("Backorder {}", backorder.short_id()),
+            };
+            let options = RemovalOptions {
+                confirm_large: true,
+                customer: backorder.customer.clone(),
+                ..Default::default()
+            };
+            self.remove_stock_with_options(sku, quantity, Some(notes), options)?;
+            filled += quantity;
+
+            // Save after every fill so a later failure can't fill it twice
+            let backorder = &mut backorders[index];
+            backorder.quantity -= quantity;
+            let event = InventoryEvent::BackorderFilled {
+                sku: sku.to_string(),
+                backorder_id: backorder.id.clone(),
+                quantity,
+                remaining: backorder.quantity,
+                timestamp: Utc::now(),
+            };
+            let outstanding: Vec<Backorder> = backorders.iter().filter(|b| b.quantity > 0).cloned().collect();
+            self.storage.save_backorders(&outstanding).map_err(ServiceError::storage("saving backorders"))?;
+            self.raise_event(event)?;
+        }
+        Ok(filled)
     }
 
     /// Remove stock from an existing product
@@ -250,101 +2406,3367 @@ impl InventoryService {
         sku: &str,
         quantity: u32,
         notes: Option<String>,
+    ) -> Result<(), ServiceError> {
+        self.remove_stock_with_options(sku, quantity, notes, RemovalOptions::default())
+    }
+
+    /// Remove stock from an existing product with additional options
+    ///
+    /// A removal above the product's (or the global) large-movement threshold
+    /// is rejected unless `options.confirm_large` is set; confirmed large
+    /// removals raise a `LargeMovement` event recorded in the audit log.
+    /// `options.transaction_type` may record the removal as a loss type;
+    /// types that add stock and quarantine moves are rejected. Damage is tagged with the damage
+    /// reason code unless another reason is given.
+    pub fn remove_stock_with_options(
+        &mut self,
+        sku: &str,
+        quantity: u32,
+        notes: Option<String>,
+        options: RemovalOptions,
     ) -> Result<(), ServiceError> {
         // Validate quantity is positive (non-zero)
         if quantity == 0 {
-            return Err(ServiceError::InvalidInput("Quantity must be positive".to_string()));
+            return Err(ServiceError::invalid_input(tr(Msg::InputQuantityPositive)));
         }
+        let transaction_type = options.transaction_type.unwrap_or(TransactionType::Removal);
+        if transaction_type.adds_stock() || transaction_type.is_internal_move() {
+            return Err(ServiceError::invalid_input(trf(
+                Msg::InputNotRemovalType, &[("type", &transaction_type.name())]
+            )));
+        }
+        let notes = self.limits.clean_optional(TextField::Notes, notes)?;
+        if let Some(code) = &options.customer {
+            if transaction_type != TransactionType::Removal {
+                return Err(ServiceError::invalid_input(tr(Msg::InputShipLoss)));
+            }
+            self.customer(code)?;
+        }
+        let drift = self.check_clock(sku)?;
 
         // Validate product exists and get current quantity
         let product = self.products.get(sku)
-            .ok_or_else(|| ServiceError::ProductNotFound(sku.to_string()))?;
+            .ok_or_else(|| ServiceError::ProductNotFound { sku: sku.to_string() })?;
 
         // Validate sufficient stock available
-        if quantity > product.quantity {
+        let in_condition = options.condition.map_or(product.quantity, |c| product.condition_quantity(c));
+        if quantity > in_condition {
             return Err(ServiceError::InsufficientStock {
                 sku: sku.to_string(),
                 requested: quantity,
-                available: product.quantity,
+                available: in_condition,
             });
         }
 
+        // Guard against fat-finger quantities
+        let available = product.quantity;
+        let exceeded_threshold = self.exceeded_threshold(product, quantity, available);
+        if let Some(threshold) = exceeded_threshold {
+            if !options.confirm_large {
+                log::debug!("Rejected unconfirmed removal of {} units of '{}' (threshold {})", quantity, sku, threshold);
+                return Err(ServiceError::LargeMovement {
+                    sku: sku.to_string(),
+                    requested: quantity,
+                    threshold,
+                });
+            }
+        }
+
         // Now get mutable reference and decrease quantity
         let product = self.products.get_mut(sku).unwrap();
+        if let Some(condition) = options.condition {
+            product.adjust_condition(condition, -i64::from(quantity));
+        }
         product.quantity -= quantity;
+        product.settle_conditions();
+        log::info!(
+            "Removed {} units from '{}' ({}): quantity {} -> {}{}",
+            quantity, sku, transaction_type.name(), available, product.quantity, format_notes(notes.as_deref())
+        );
 
         // Create transaction record
+        let reason = options.reason
+            .or((transaction_type == TransactionType::Damage).then_some(ReasonCode::Damage));
         let transaction = Transaction {
-            id: Uuid::new_v4().to_string(),
-            product_sku: sku.to_string(),
-            transaction_type: TransactionType::Removal,
-            quantity,
-            timestamp: Utc::now(),
-            notes,
+            reason,
+            condition: options.condition.filter(|&c| c != Condition::New),
+            customer: options.customer,
+            ..self.new_transaction(sku, transaction_type, quantity, notes)
         };
+        let timestamp = transaction.timestamp;
 
         // Add transaction to vector
-        self.transactions.push(transaction);
+        self.record_transaction(transaction, available);
 
         // Persist both products and transactions
         self.persist_products()?;
         self.persist_transactions()?;
+        self.raise_events(drift)?;
+
+        if let Some(threshold) = exceeded_threshold {
+            self.raise_event(InventoryEvent::LargeMovement {
+                sku: sku.to_string(),
+                quantity,
+                available,
+                threshold,
+                timestamp,
+            })?;
+        }
 
         Ok(())
     }
 
-    /// List all products with low stock (quantity at or below reorder point)
-    /// 
-    /// # Requirements
-    /// - 4.5: Flag products for reorder when stock falls below reorder point
-    /// - 5.3: Return all products where current stock level is at or below reorder point
-    pub fn list_low_stock(&self) -> Vec<&Product> {
-        self.products
-            .values()
-            .filter(|p| p.quantity <= p.reorder_point)
-            .collect()
+    /// The large-movement threshold a removal exceeds, if any
+    ///
+    /// A product's own threshold takes precedence over the global one.
+    fn exceeded_threshold(&self, product: &Product, quantity: u32, available: u32) -> Option<MovementThreshold> {
+        product.large_movement_threshold
+            .or(self.large_movement_threshold)
+            .filter(|threshold| threshold.is_exceeded(quantity, available))
     }
 
-    /// Get transaction history for a product, ordered by timestamp
-    /// 
-    /// # Requirements
-    /// - 6.1: Return all transactions for a product ordered by timestamp
-    /// - 6.2: Include transaction type, quantity, timestamp, and notes
-    /// - 6.5: Preserve chronological order of all stock movements
-    pub fn get_transactions(&self, sku: &str) -> Vec<&Transaction> {
-        let mut transactions: Vec<&Transaction> = self.transactions
+    /// Check a batch of stock movements without applying it
+    ///
+    /// Entries are checked in order against the running quantities, so two
+    /// removals of the same SKU must fit the stock together. The report lists
+    /// every rejected entry by its one-based position in `entries`.
+    pub fn validate_stock_entries(
+        &self,
+        entries: &[StockEntry],
+        transaction_type: TransactionType,
+        options: &RemovalOptions,
+    ) -> ValidationReport {
+        let mut running: HashMap<&str, u32> = HashMap::new();
+        let mut report = ValidationReport::new(entries.len());
+
+        for (index, entry) in entries.iter().enumerate() {
+            let row = index + 1;
+            if entry.quantity == 0 {
+                report.add_error(row, &ServiceError::invalid_input(tr(Msg::InputQuantityPositive)));
+                continue;
+            }
+            if let Some(notes) = &entry.notes {
+                if let Err(err) = self.limits.clean(TextField::Notes, notes) {
+                    report.add_error(row, &err);
+                    continue;
+                }
+            }
+            let product = match self.products.get(&entry.sku) {
+                Some(product) => product,
+                None => {
+                    report.add_error(row, &ServiceError::ProductNotFound { sku: entry.sku.clone() });
+                    continue;
+                }
+            };
+            let available = running.entry(&product.sku).or_insert(product.quantity);
+
+            match transaction_type {
+                TransactionType::Addition | TransactionType::InitialStock => match available.checked_add(entry.quantity) {
+                    Some(total) => *available = total,
+                    None => report.add_error(
+                        row,
+                        &ServiceError::invalid_input(trf(Msg::InputQuantityOverflow, &[("sku", &entry.sku)])),
+                    ),
+                },
+                TransactionType::Removal
+                | TransactionType::Damage
+                | TransactionType::WriteOff
+                | TransactionType::Adjustment => {
+                    if entry.quantity > *available {
+                        report.add_error(row, &ServiceError::InsufficientStock {
+                            sku: entry.sku.clone(),
+                            requested: entry.quantity,
+                            available: *available,
+                        });
+                        continue;
+                    }
+                    let threshold = self.exceeded_threshold(product, entry.quantity, *available);
+                    if let Some(threshold) = threshold.filter(|_| !options.confirm_large) {
+                        report.add_error(row, &ServiceError::LargeMovement {
+                            sku: entry.sku.clone(),
+                            requested: entry.quantity,
+                            threshold,
+                        });
+                        continue;
+                    }
+                    *available -= entry.quantity;
+                }
+                TransactionType::Quarantine | TransactionType::ReleaseQuarantine => {
+                    report.add_error(row, &ServiceError::invalid_input(tr(Msg::InputQuarantineMove)));
+                }
+                TransactionType::ConditionChange => {
+                    report.add_error(row, &ServiceError::invalid_input(tr(Msg::InputConditionMove)));
+                }
+            }
+        }
+
+        report
+    }
+
+    /// Apply a batch of stock movements of one type, all or nothing
+    ///
+    /// Every entry is validated with `validate_stock_entries` before any is
+    /// applied; if one is rejected the inventory is left unchanged. Products
+    /// and transactions are persisted once for the whole batch. Added units
+    /// are taken off the products' on-order quantities.
+    pub fn apply_stock_entries(
+        &mut self,
+        entries: &[StockEntry],
+        transaction_type: TransactionType,
+        options: RemovalOptions,
+    ) -> Result<(), ServiceError> {
+        let report = self.validate_stock_entries(entries, transaction_type, &options);
+        if !report.is_valid() {
+            return Err(ServiceError::ValidationFailed { report });
+        }
+        let notes = entries
             .iter()
-            .filter(|t| t.product_sku == sku)
+            .map(|entry| self.limits.clean_optional(TextField::Notes, entry.notes.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut skus: Vec<&str> = entries.iter().map(|entry| entry.sku.as_str()).collect();
+        skus.sort_unstable();
+        skus.dedup();
+        let mut events = Vec::new();
+        for sku in skus {
+            events.extend(self.check_clock(sku)?);
+        }
+
+        for (entry, notes) in entries.iter().zip(notes) {
+            let product = self.products.get_mut(&entry.sku).unwrap();
+            let previous = product.quantity;
+            if transaction_type.adds_stock() {
+                product.quantity += entry.quantity;
+                if transaction_type == TransactionType::Addition {
+                    product.on_order = product.on_order.saturating_sub(entry.quantity);
+                }
+            } else {
+                product.quantity -= entry.quantity;
+                product.settle_conditions();
+            }
+            log::info!(
+                "Batch {}: {} units of '{}', quantity {} -> {}{}",
+                transaction_type.name(), entry.quantity, entry.sku, previous, product.quantity,
+                format_notes(notes.as_deref())
+            );
+
+            let transaction = Transaction {
+                reason: options.reason,
+                ..self.new_transaction(&entry.sku, transaction_type, entry.quantity, notes)
+            };
+
+            let product = &self.products[&entry.sku];
+            if !transaction_type.adds_stock() {
+                if let Some(threshold) = self.exceeded_threshold(product, entry.quantity, previous) {
+                    events.push(InventoryEvent::LargeMovement {
+                        sku: entry.sku.clone(),
+                        quantity: entry.quantity,
+                        available: previous,
+                        threshold,
+                        timestamp: transaction.timestamp,
+                    });
+                }
+            }
+            self.record_transaction(transaction, previous);
+        }
+
+        self.persist_products()?;
+        self.persist_transactions()?;
+
+        self.raise_events(events)
+    }
+
+    /// Put available units of a product on hold, e.g. while awaiting QC
+    ///
+    /// Quarantined units stay on the product but are left out of `quantity`,
+    /// so they can't be removed and don't count toward the reorder point.
+    pub fn quarantine(&mut self, sku: &str, quantity: u32, notes: Option<String>) -> Result<(), ServiceError> {
+        if quantity == 0 {
+            return Err(ServiceError::invalid_input(tr(Msg::InputQuantityPositive)));
+        }
+        let notes = self.limits.clean_optional(TextField::Notes, notes)?;
+        let drift = self.check_clock(sku)?;
+        let product = self.products.get_mut(sku)
+            .ok_or_else(|| ServiceError::ProductNotFound { sku: sku.to_string() })?;
+        if quantity > product.quantity {
+            return Err(ServiceError::InsufficientStock {
+                sku: sku.to_string(),
+                requested: quantity,
+                available: product.quantity,
+            });
+        }
+        let quarantined = product.quarantined.checked_add(quantity)
+            .ok_or_else(|| ServiceError::invalid_input(trf(Msg::InputQuarantineOverflow, &[("sku", &sku)])))?;
+        let previous = product.quantity;
+        product.quantity -= quantity;
+        product.quarantined = quarantined;
+        product.settle_conditions();
+        log::info!("Quarantined {} units of '{}'{}", quantity, sku, format_notes(notes.as_deref()));
+
+        let transaction = self.new_transaction(sku, TransactionType::Quarantine, quantity, notes);
+        self.record_transaction(transaction, previous);
+        self.persist_products()?;
+        self.persist_transactions()?;
+        self.raise_events(drift)
+    }
+
+    /// Make quarantined units of a product available again
+    pub fn release_quarantine(&mut self, sku: &str, quantity: u32, notes: Option<String>) -> Result<(), ServiceError> {
+        if quantity == 0 {
+            return Err(ServiceError::invalid_input(tr(Msg::InputQuantityPositive)));
+        }
+        let notes = self.limits.clean_optional(TextField::Notes, notes)?;
+        let drift = self.check_clock(sku)?;
+        let product = self.products.get_mut(sku)
+            .ok_or_else(|| ServiceError::ProductNotFound { sku: sku.to_string() })?;
+        if quantity > product.quarantined {
+            return Err(ServiceError::invalid_input(trf(
+                Msg::InputReleaseTooMany, &[("quantity", &quantity), ("sku", &sku), ("quarantined", &product.quarantined)]
+            )));
+        }
+        let available = product.quantity.checked_add(quantity)
+            .ok_or_else(|| ServiceError::invalid_input(trf(Msg::InputQuantityOverflow, &[("sku", &sku)])))?;
+        let previous = product.quantity;
+        product.quarantined -= quantity;
+        product.quantity = available;
+        log::info!("Released {} units of '{}' from quarantine{}", quantity, sku, format_notes(notes.as_deref()));
+
+        let transaction = self.new_transaction(sku, TransactionType::ReleaseQuarantine, quantity, notes);
+        self.record_transaction(transaction, previous);
+        self.persist_products()?;
+        self.persist_transactions()?;
+        self.raise_events(drift)
+    }
+
+    /// Record units ordered from a supplier, returning the new on-order quantity
+    ///
+    /// Units on order are taken off the shortfall that low-stock reports,
+    /// and adding new stock or committing a receipt takes them off again.
+    pub fn mark_ordered(&mut self, sku: &str, quantity: u32) -> Result<u32, ServiceError> {
+        if quantity == 0 {
+            return Err(ServiceError::invalid_input(tr(Msg::InputQuantityPositive)));
+        }
+        let product = self.products.get_mut(sku)
+            .ok_or_else(|| ServiceError::ProductNotFound { sku: sku.to_string() })?;
+        product.on_order = product.on_order.checked_add(quantity)
+            .ok_or_else(|| ServiceError::invalid_input(trf(Msg::InputOnOrderOverflow, &[("sku", &sku)])))?;
+        let on_order = product.on_order;
+        log::info!("Marked {} units of '{}' as ordered: {} on order", quantity, sku, on_order);
+        self.persist_products()?;
+        Ok(on_order)
+    }
+
+    /// Take units off a product's order, e.g. when the supplier cancels them
+    pub fn cancel_ordered(&mut self, sku: &str, quantity: u32) -> Result<u32, ServiceError> {
+        if quantity == 0 {
+            return Err(ServiceError::invalid_input(tr(Msg::InputQuantityPositive)));
+        }
+        let product = self.products.get_mut(sku)
+            .ok_or_else(|| ServiceError::ProductNotFound { sku: sku.to_string() })?;
+        if quantity > product.on_order {
+            return Err(ServiceError::invalid_input(trf(
+                Msg::InputCancelTooMany, &[("quantity", &quantity), ("sku", &sku), ("on_order", &product.on_order)]
+            )));
+        }
+        product.on_order -= quantity;
+        let on_order = product.on_order;
+        log::info!("Cancelled {} ordered units of '{}': {} on order", quantity, sku, on_order);
+        self.persist_products()?;
+        Ok(on_order)
+    }
+
+    /// Move available units of a product from one condition to another
+    ///
+    /// The quantity stays the same; the move is recorded as a
+    /// `ConditionChange` transaction.
+    pub fn change_condition(
+        &mut self,
+        sku: &str,
+        quantity: u32,
+        from: Condition,
+        to: Condition,
+        notes: Option<String>,
+    ) -> Result<(), ServiceError> {
+        if quantity == 0 {
+            return Err(ServiceError::invalid_input(tr(Msg::InputQuantityPositive)));
+        }
+        if from == to {
+            return Err(ServiceError::invalid_input(trf(Msg::InputSameCondition, &[("condition", &condition_label(to))])));
+        }
+        let notes = self.limits.clean_optional(TextField::Notes, notes)?;
+        let drift = self.check_clock(sku)?;
+        let product = self.products.get_mut(sku)
+            .ok_or_else(|| ServiceError::ProductNotFound { sku: sku.to_string() })?;
+        let available = product.condition_quantity(from);
+        if quantity > available {
+            return Err(ServiceError::InsufficientStock { sku: sku.to_string(), requested: quantity, available });
+        }
+        product.adjust_condition(from, -i64::from(quantity));
+        product.adjust_condition(to, i64::from(quantity));
+        log::info!("Moved {} units of '{}' from {} to {}{}", quantity, sku, from, to, format_notes(notes.as_deref()));
+
+        let transaction = Transaction {
+            condition: Some(to),
+            from_condition: Some(from),
+            ..self.new_transaction(sku, TransactionType::ConditionChange, quantity, notes)
+        };
+        let available = self.products[sku].quantity;
+        self.record_transaction(transaction, available);
+        self.persist_products()?;
+        self.persist_transactions()?;
+        self.raise_events(drift)
+    }
+
+    /// Start staging a delivery, optionally against the quantities expected on its order
+    ///
+    /// Only one receipt can be staged at a time. Nothing changes in the
+    /// inventory until `commit_receiving`.
+    pub fn start_receiving(
+        &mut self,
+        reference: Option<String>,
+        expected: &[StockEntry],
+    ) -> Result<ReceivingSession, ServiceError> {
+        if let Some(session) = self.receiving()? {
+            return Err(ServiceError::invalid_input(trf(
+                Msg::InputReceiptInProgress,
+                &[("reference", &session.reference.as_deref().unwrap_or(tr(Msg::InputNoReference)))],
+            )));
+        }
+        let reference = self.limits.clean_optional(TextField::Notes, reference)?;
+        let report = self.validate_stock_entries(expected, TransactionType::Addition, &RemovalOptions::default());
+        if !report.is_valid() {
+            return Err(ServiceError::ValidationFailed { report });
+        }
+
+        let session = ReceivingSession {
+            reference,
+            started_at: Utc::now(),
+            started_by: self.operator.clone(),
+            expected: expected
+                .iter()
+                .map(|entry| ReceiptLine { sku: entry.sku.clone(), quantity: entry.quantity, notes: None })
+                .collect(),
+            received: Vec::new(),
+        };
+        self.storage.save_receiving(Some(&session)).map_err(ServiceError::storage("saving the staged receipt"))?;
+        log::info!("Started receiving with {} expected lines", session.expected.len());
+        Ok(session)
+    }
+
+    /// The receipt being staged, if any
+    pub fn receiving(&self) -> Result<Option<ReceivingSession>, ServiceError> {
+        self.storage.load_receiving().map_err(ServiceError::storage("loading the staged receipt"))
+    }
+
+    /// The receipt being staged, or an error telling the user to start one
+    fn open_receiving(&self) -> Result<ReceivingSession, ServiceError> {
+        self.receiving()?.ok_or_else(|| {
+            ServiceError::invalid_input(tr(Msg::InputNoReceipt))
+        })
+    }
+
+    /// Stage units of a product counted in the delivery being received
+    pub fn stage_receipt(
+        &mut self,
+        sku: &str,
+        quantity: u32,
+        notes: Option<String>,
+    ) -> Result<ReceivingReview, ServiceError> {
+        let mut session = self.open_receiving()?;
+        if quantity == 0 {
+            return Err(ServiceError::invalid_input(tr(Msg::InputQuantityPositive)));
+        }
+        self.get_product(sku)?;
+        let notes = self.limits.clean_optional(TextField::Notes, notes)?;
+        session.received.push(ReceiptLine { sku: sku.to_string(), quantity, notes });
+        self.storage.save_receiving(Some(&session)).map_err(ServiceError::storage("saving the staged receipt"))?;
+        log::debug!("Staged {} units of '{}' for receiving", quantity, sku);
+        Ok(ReceivingReview::of(session))
+    }
+
+    /// Compare the staged receipt with what was expected
+    pub fn review_receiving(&self) -> Result<ReceivingReview, ServiceError> {
+        Ok(ReceivingReview::of(self.open_receiving()?))
+    }
+
+    /// Record the staged receipt as Addition transactions, one per staged line
+    ///
+    /// Lines without notes are noted with the receipt reference. The receipt
+    /// is applied all or nothing and only cleared once the stock is saved.
+    /// Received units are taken off the products' on-order quantities.
+    pub fn commit_receiving(&mut self) -> Result<ReceivingReview, ServiceError> {
+        let session = self.open_receiving()?;
+        if session.received.is_empty() {
+            return Err(ServiceError::invalid_input(tr(Msg::InputNothingReceived)));
+        }
+        let default_notes = session.reference.as_ref().map(|reference| format!("Receipt {}", reference));
+        let entries: Vec<StockEntry> = session.received
+            .iter()
+            .map(|line| StockEntry {
+                sku: line.sku.clone(),
+                quantity: line.quantity,
+                notes: line.notes.clone().or_else(|| default_notes.clone()),
+            })
             .collect();
-        
-        // Sort by timestamp ascending (earliest to latest)
-        transactions.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
-        
-        transactions
+        self.apply_stock_entries(&entries, TransactionType::Addition, RemovalOptions::default())?;
+        self.storage.save_receiving(None).map_err(ServiceError::storage("clearing the staged receipt"))?;
+        log::info!("Committed receipt with {} lines", entries.len());
+        Ok(ReceivingReview::of(session))
     }
 
-    /// Get transaction history for a product within a date range, ordered by timestamp
-    /// 
-    /// # Requirements
-    /// - 6.3: Return only transactions within the specified period
-    pub fn get_transactions_in_range(
+    /// Discard the staged receipt without changing any stock
+    pub fn cancel_receiving(&mut self) -> Result<ReceivingSession, ServiceError> {
+        let session = self.open_receiving()?;
+        self.storage.save_receiving(None).map_err(ServiceError::storage("clearing the staged receipt"))?;
+        log::info!("Cancelled receipt with {} staged lines", session.received.len());
+        Ok(session)
+    }
+
+    /// Check a batch of historical movements without importing it
+    ///
+    /// Each product's entries must be listed oldest first and none may be in
+    /// the future. The product's history is then replayed with the entries
+    /// merged in by timestamp, starting from the quantity it had before its
+    /// first recorded transaction; an entry is rejected if stock would go
+    /// negative at any point, including at a later recorded removal.
+    pub fn validate_historical_entries(&self, entries: &[HistoricalEntry]) -> ValidationReport {
+        let now = Utc::now();
+        let mut report = ValidationReport::new(entries.len());
+        let mut latest: HashMap<&str, DateTime<Utc>> = HashMap::new();
+        let mut accepted: Vec<(&str, Vec<usize>)> = Vec::new();
+
+        for (index, entry) in entries.iter().enumerate() {
+            let row = index + 1;
+            if entry.quantity == 0 {
+                report.add_error(row, &ServiceError::invalid_input(tr(Msg::InputQuantityPositive)));
+                continue;
+            }
+            if entry.transaction_type.is_internal_move() {
+                report.add_error(row, &ServiceError::invalid_input(tr(Msg::InputQuarantineNotImported)));
+                continue;
+            }
+            if let Some(notes) = &entry.notes {
+                if let Err(err) = self.limits.clean(TextField::Notes, notes) {
+                    report.add_error(row, &err);
+                    continue;
+                }
+            }
+            if !self.products.contains_key(&entry.sku) {
+                report.add_error(row, &ServiceError::ProductNotFound { sku: entry.sku.clone() });
+                continue;
+            }
+            if entry.timestamp > now + self.clock.tolerance() {
+                report.add_error(row, &ServiceError::invalid_input(trf(
+                    Msg::InputTimestampFuture, &[("timestamp", &entry.timestamp.format("%Y-%m-%d %H:%M:%S"))]
+                )));
+                continue;
+            }
+            if let Some(created_at) = self.predates_product(entry).filter(|_| self.clock.action == ClockAction::Reject) {
+                report.add_error(row, &ServiceError::invalid_input(trf(
+                    Msg::InputTimestampBeforeAdded,
+                    &[
+                        ("timestamp", &entry.timestamp.format("%Y-%m-%d %H:%M:%S")),
+                        ("sku", &entry.sku),
+                        ("added", &created_at.format("%Y-%m-%d %H:%M:%S")),
+                    ],
+                )));
+                continue;
+            }
+            if let Some(previous) = latest.get(entry.sku.as_str()).filter(|previous| entry.timestamp < **previous) {
+                report.add_error(row, &ServiceError::invalid_input(trf(
+                    Msg::InputTimestampOutOfOrder,
+                    &[
+                        ("timestamp", &entry.timestamp.format("%Y-%m-%d %H:%M:%S")),
+                        ("sku", &entry.sku),
+                        ("previous", &previous.format("%Y-%m-%d %H:%M:%S")),
+                    ],
+                )));
+                continue;
+            }
+            latest.insert(&entry.sku, entry.timestamp);
+            match accepted.iter_mut().find(|(sku, _)| *sku == entry.sku) {
+                Some((_, indices)) => indices.push(index),
+                None => accepted.push((&entry.sku, vec![index])),
+            }
+        }
+
+        for (sku, indices) in accepted {
+            if let Some((index, err)) = self.replay_with_imports(sku, entries, &indices) {
+                report.add_error(index + 1, &err);
+            }
+        }
+        report
+    }
+
+    /// When the entry's product was added, if the entry is dated more than
+    /// the clock tolerance before that
+    fn predates_product(&self, entry: &HistoricalEntry) -> Option<DateTime<Utc>> {
+        let created_at = self.products.get(&entry.sku)?.created_at?;
+        (entry.timestamp < created_at - self.clock.tolerance()).then_some(created_at)
+    }
+
+    /// Replay a product's history with imported entries merged in
+    ///
+    /// Returns the first imported entry that leaves the stock negative or
+    /// overflowing, with the reason.
+    fn replay_with_imports(
         &self,
         sku: &str,
-        start: DateTime<Utc>,
-        end: DateTime<Utc>,
-    ) -> Vec<&Transaction> {
-        let mut transactions: Vec<&Transaction> = self.transactions
+        entries: &[HistoricalEntry],
+        indices: &[usize],
+    ) -> Option<(usize, ServiceError)> {
+        let recorded: Vec<&Transaction> = self.transactions.iter().filter(|t| t.product_sku == sku).collect();
+        let net: i64 = recorded.iter().map(|t| signed_quantity(t.transaction_type, t.quantity)).sum();
+        let mut balance = i64::from(self.products[sku].quantity) - net;
+
+        // Recorded transactions sort before imported ones with the same timestamp
+        let mut movements: Vec<(DateTime<Utc>, TransactionType, u32, Option<usize>)> = recorded
             .iter()
-            .filter(|t| {
-                t.product_sku == sku 
-                    && t.timestamp >= start 
-                    && t.timestamp <= end
-            })
+            .map(|t| (t.timestamp, t.transaction_type, t.quantity, None))
+            .chain(indices.iter().map(|&i| {
+                let entry = &entries[i];
+                (entry.timestamp, entry.transaction_type, entry.quantity, Some(i))
+            }))
             .collect();
-        
-        // Sort by timestamp ascending (earliest to latest)
-        transactions.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
-        
-        transactions
+        movements.sort_by_key(|(timestamp, ..)| *timestamp);
+
+        let mut last_import = None;
+        for (timestamp, transaction_type, quantity, import) in movements {
+            let available = balance;
+            balance += signed_quantity(transaction_type, quantity);
+            if import.is_some() {
+                last_import = import;
+            }
+            let Some(index) = last_import else {
+                continue;
+            };
+            if balance > i64::from(u32::MAX) {
+                return Some((index, ServiceError::invalid_input(trf(Msg::InputQuantityOverflow, &[("sku", &sku)]))));
+            }
+            if balance >= 0 {
+                continue;
+            }
+            let err = match import {
+                Some(_) => ServiceError::InsufficientStock {
+                    sku: sku.to_string(),
+                    requested: quantity,
+                    available: u32::try_from(available.max(0)).unwrap_or(u32::MAX),
+                },
+                None => ServiceError::invalid_input(trf(
+                    Msg::InputLeavesTooLittle,
+                    &[("sku", &sku), ("quantity", &quantity), ("timestamp", &timestamp.format("%Y-%m-%d %H:%M:%S"))],
+                )),
+            };
+            return Some((index, err));
+        }
+        None
+    }
+
+    /// Import past stock movements with their original timestamps, all or nothing
+    ///
+    /// Entries are validated with `validate_historical_entries` first. Each
+    /// product's quantity is adjusted by the net change of its entries, and
+    /// the imported transactions take their place in the history by
+    /// timestamp. No large-movement events are raised for past movements.
+    pub fn import_transactions(&mut self, entries: &[HistoricalEntry]) -> Result<(), ServiceError> {
+        let report = self.validate_historical_entries(entries);
+        if !report.is_valid() {
+            return Err(ServiceError::ValidationFailed { report });
+        }
+        let notes = entries
+            .iter()
+            .map(|entry| self.limits.clean_optional(TextField::Notes, entry.notes.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut early: BTreeMap<&str, (usize, DateTime<Utc>)> = BTreeMap::new();
+        for entry in entries {
+            if let Some(created_at) = self.predates_product(entry) {
+                early.entry(&entry.sku).or_insert((0, created_at)).0 += 1;
+            }
+        }
+
+        let mut net: HashMap<&str, i64> = HashMap::new();
+        for (entry, notes) in entries.iter().zip(notes) {
+            // Stock changes are reported in input order; only the end result
+            // is checked, so a step in between may dip below zero
+            let change = net.entry(&entry.sku).or_default();
+            let before = i64::from(self.products[&entry.sku].quantity) + *change;
+            *change += signed_quantity(entry.transaction_type, entry.quantity);
+            let transaction = Transaction {
+                timestamp: entry.timestamp,
+                ..self.new_transaction(&entry.sku, entry.transaction_type, entry.quantity, notes)
+            };
+            self.record_transaction(transaction, u32::try_from(before.max(0)).unwrap_or(u32::MAX));
+        }
+        let skus: Vec<&str> = net.keys().copied().collect();
+        for (sku, change) in net {
+            let product = self.products.get_mut(sku).unwrap();
+            let previous = product.quantity;
+            // Validation guarantees the result fits in a u32
+            product.quantity = u32::try_from(i64::from(previous) + change).unwrap();
+            product.settle_conditions();
+            log::info!("Imported history for '{}': quantity {} -> {}", sku, previous, product.quantity);
+        }
+        self.transactions.sort_by_key(|t| t.order_key());
+        // Backdated receipts and issues change which layers later issues took
+        for sku in skus {
+            self.rebuild_cost_layers(sku);
+        }
+
+        self.persist_products()?;
+        self.persist_transactions()?;
+        log::info!("Imported {} historical transactions", entries.len());
+        let now = Utc::now();
+        for (sku, (count, created_at)) in early {
+            self.raise_event(InventoryEvent::BeforeCreation { sku: sku.to_string(), count, created_at, timestamp: now })?;
+        }
+        Ok(())
+    }
+
+    /// Sequence number of the most recently recorded transaction
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
+    /// Whether every entry was recorded as a transaction after `seq`, as when
+    /// an import whose save failed reached the data files later on
+    pub fn imported_since(&self, seq: u64, entries: &[HistoricalEntry]) -> bool {
+        let mut recorded: Vec<&Transaction> = self.transactions.iter().filter(|t| t.seq > seq).collect();
+        entries.iter().all(|entry| {
+            let found = recorded.iter().position(|t| {
+                t.product_sku == entry.sku
+                    && t.timestamp == entry.timestamp
+                    && t.transaction_type == entry.transaction_type
+                    && t.quantity == entry.quantity
+            });
+            found.map(|index| recorded.swap_remove(index)).is_some()
+        })
+    }
+
+    /// Check opening balances without creating any products
+    ///
+    /// Every SKU must be new, both to the inventory and within the batch.
+    pub fn validate_opening_balances(&self, balances: &[OpeningBalance]) -> ValidationReport {
+        self.check_opening_balances(balances, None)
+    }
+
+    /// Check opening balances to be merged into the inventory without changing anything
+    ///
+    /// As `validate_opening_balances`, except that SKUs already in the
+    /// inventory are allowed, and the stock movements `policy` makes for
+    /// them must be possible. The name of an existing product may be left
+    /// blank unless it is overwritten.
+    pub fn validate_opening_merge(&self, balances: &[OpeningBalance], policy: &MergePolicy) -> ValidationReport {
+        let mut report = self.check_opening_balances(balances, Some(policy));
+        let (additions, removals) = self.merge_movements(balances, policy);
+        for (movements, transaction_type, options) in [
+            (additions, TransactionType::Addition, RemovalOptions::default()),
+            (removals, TransactionType::Adjustment, merge_removal_options()),
+        ] {
+            let (rows, entries): (Vec<usize>, Vec<StockEntry>) = movements.into_iter().unzip();
+            let movement_report = self.validate_stock_entries(&entries, transaction_type, &options);
+            report.extend(movement_report.renumber(|row| rows[row - 1]));
+        }
+        report
+    }
+
+    /// Check opening balances, allowing existing SKUs when merging them
+    fn check_opening_balances(&self, balances: &[OpeningBalance], merge: Option<&MergePolicy>) -> ValidationReport {
+        let mut report = ValidationReport::new(balances.len());
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+
+        for (index, balance) in balances.iter().enumerate() {
+            let row = index + 1;
+            if balance.sku.trim().is_empty() {
+                report.add_error(row, &ServiceError::invalid_input(tr(Msg::InputSkuEmpty)));
+                continue;
+            }
+            let exists = self.products.contains_key(&balance.sku);
+            let name_used = !exists || merge.is_some_and(|policy| policy.name == FieldMerge::Overwrite);
+            if name_used && balance.name.trim().is_empty() {
+                report.add_error(row, &ServiceError::invalid_input(tr(Msg::InputNameEmpty)));
+                continue;
+            }
+            let cleaned = self.limits.clean(TextField::Sku, &balance.sku)
+                .and_then(|_| self.limits.clean(TextField::Name, &balance.name));
+            if let Err(err) = cleaned {
+                report.add_error(row, &err);
+                continue;
+            }
+            if exists && merge.is_none() {
+                report.add_error(row, &ServiceError::DuplicateSKU { sku: balance.sku.clone() });
+                continue;
+            }
+            if let Some(first) = seen.insert(&balance.sku, row) {
+                report.add_error(row, &ServiceError::invalid_input(trf(
+                    Msg::InputSkuRepeated, &[("sku", &balance.sku), ("first", &first)]
+                )));
+            }
+        }
+        report
+    }
+
+    /// Create products with opening balances dated to a go-live cutover, all or nothing
+    ///
+    /// Each product's stock is recorded as an InitialStock transaction at
+    /// `cutover`, so history shows where the balance came from. Products with
+    /// a zero balance are created without a transaction. After persisting,
+    /// the inventory is compared with the input and the totals returned.
+    pub fn migrate_opening_balances(
+        &mut self,
+        balances: &[OpeningBalance],
+        cutover: DateTime<Utc>,
+    ) -> Result<Reconciliation, ServiceError> {
+        if cutover > Utc::now() {
+            return Err(ServiceError::invalid_input(trf(
+                Msg::InputCutoverFuture, &[("date", &cutover.format("%Y-%m-%d"))]
+            )));
+        }
+        let report = self.validate_opening_balances(balances);
+        if !report.is_valid() {
+            return Err(ServiceError::ValidationFailed { report });
+        }
+        // Clean every name before inserting any, so a failure changes nothing
+        let names = balances
+            .iter()
+            .map(|balance| self.limits.clean(TextField::Name, &balance.name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (balance, name) in balances.iter().zip(names) {
+            let product = Product {
+                id: Uuid::new_v4().to_string(),
+                sku: balance.sku.clone(),
+                name,
+                quantity: balance.quantity,
+                reorder_point: balance.reorder_point,
+                created_at: Some(cutover),
+                ..Default::default()
+            };
+            self.products.insert(product.sku.clone(), product);
+            self.refresh_read_model(&balance.sku);
+            if balance.quantity > 0 {
+                let transaction = Transaction {
+                    timestamp: cutover,
+                    ..self.new_transaction(&balance.sku, TransactionType::InitialStock, balance.quantity, None)
+                };
+                self.record_transaction(transaction, 0);
+            }
+        }
+        self.transactions.sort_by_key(|t| t.order_key());
+
+        self.persist_products()?;
+        self.persist_transactions()?;
+        log::info!("Migrated {} opening balances as of {}", balances.len(), cutover.format("%Y-%m-%d"));
+        Ok(self.reconcile_opening_balances(balances))
+    }
+
+    /// Merge opening balances into the inventory, all or nothing
+    ///
+    /// New SKUs are created as by `migrate_opening_balances`. Existing
+    /// products have their name and reorder point merged by `policy`, and
+    /// their stock added to or brought to the listed quantity by a movement
+    /// dated now rather than at the cutover. The reconciliation covers the
+    /// new products and counts the existing ones merged.
+    pub fn merge_opening_balances(
+        &mut self,
+        balances: &[OpeningBalance],
+        cutover: DateTime<Utc>,
+        policy: &MergePolicy,
+    ) -> Result<Reconciliation, ServiceError> {
+        if cutover > Utc::now() {
+            return Err(ServiceError::invalid_input(trf(
+                Msg::InputCutoverFuture, &[("date", &cutover.format("%Y-%m-%d"))]
+            )));
+        }
+        let report = self.validate_opening_merge(balances, policy);
+        if !report.is_valid() {
+            return Err(ServiceError::ValidationFailed { report });
+        }
+        let (existing, new): (Vec<OpeningBalance>, Vec<OpeningBalance>) =
+            balances.iter().cloned().partition(|balance| self.products.contains_key(&balance.sku));
+        let (additions, removals) = self.merge_movements(&existing, policy);
+        let additions: Vec<StockEntry> = additions.into_iter().map(|(_, entry)| entry).collect();
+        let removals: Vec<StockEntry> = removals.into_iter().map(|(_, entry)| entry).collect();
+
+        let mut batch = self.begin_batch();
+        // Removals are the movements that can still be refused, so they go first
+        if !removals.is_empty() {
+            batch.apply_stock_entries(&removals, TransactionType::Adjustment, merge_removal_options())?;
+        }
+        if !additions.is_empty() {
+            batch.apply_stock_entries(&additions, TransactionType::Addition, RemovalOptions::default())?;
+        }
+        for balance in &existing {
+            let product = &batch.products[&balance.sku];
+            let name = policy.name.apply(product.name.clone(), balance.name.clone(), |name| name.trim().is_empty());
+            let reorder_point = policy.reorder_point.apply(product.reorder_point, balance.reorder_point, |&point| point == 0);
+            let update = ProductUpdate {
+                name: (name != product.name).then_some(name),
+                reorder_point: (reorder_point != product.reorder_point).then_some(reorder_point),
+                ..Default::default()
+            };
+            if update != ProductUpdate::default() {
+                batch.update_product(&balance.sku, update)?;
+            }
+        }
+        let mut reconciliation = batch.migrate_opening_balances(&new, cutover)?;
+        batch.commit()?;
+        reconciliation.merged_products = existing.len();
+        log::info!("Merged {} opening balances into existing products", existing.len());
+        Ok(reconciliation)
+    }
+
+    /// The stock movements that merge opening balances into existing
+    /// products, as additions and removals with the row each came from
+    fn merge_movements(
+        &self,
+        balances: &[OpeningBalance],
+        policy: &MergePolicy,
+    ) -> (Vec<NumberedMovement>, Vec<NumberedMovement>) {
+        let mut additions = Vec::new();
+        let mut removals = Vec::new();
+        for (index, balance) in balances.iter().enumerate() {
+            let Some(product) = self.products.get(&balance.sku) else { continue };
+            let change = match policy.quantity {
+                QuantityMerge::Keep => 0,
+                QuantityMerge::Add => i64::from(balance.quantity),
+                QuantityMerge::Set => i64::from(balance.quantity) - i64::from(product.quantity),
+            };
+            if change == 0 {
+                continue;
+            }
+            let entry = StockEntry {
+                sku: balance.sku.clone(),
+                quantity: change.unsigned_abs() as u32,
+                notes: Some("Merged opening balance".to_string()),
+            };
+            if change > 0 { additions.push((index + 1, entry)) } else { removals.push((index + 1, entry)) }
+        }
+        (additions, removals)
+    }
+
+    /// Compare opening balances with the products and InitialStock transactions recorded for them
+    pub fn reconcile_opening_balances(&self, balances: &[OpeningBalance]) -> Reconciliation {
+        let mut reconciliation = Reconciliation {
+            expected_products: balances.len(),
+            expected_units: balances.iter().map(|balance| u64::from(balance.quantity)).sum(),
+            ..Default::default()
+        };
+        for balance in balances {
+            if !self.products.contains_key(&balance.sku) {
+                continue;
+            }
+            reconciliation.created_products += 1;
+            let recorded: u64 = self.transactions
+                .iter()
+                .filter(|t| t.product_sku == balance.sku && t.transaction_type == TransactionType::InitialStock)
+                .map(|t| u64::from(t.quantity))
+                .sum();
+            reconciliation.created_units += recorded;
+            if recorded != u64::from(balance.quantity) {
+                reconciliation.mismatches.push((balance.sku.clone(), balance.quantity, recorded));
+            }
+        }
+        reconciliation
+    }
+
+    /// Check another system's quantities before comparing them: each SKU
+    /// may be listed only once
+    pub fn validate_external_quantities(&self, quantities: &[ExternalQuantity]) -> ValidationReport {
+        let mut report = ValidationReport::new(quantities.len());
+        let mut seen: HashMap<&str, usize> = HashMap::new();
+        for (index, quantity) in quantities.iter().enumerate() {
+            let row = index + 1;
+            if let Some(first) = seen.insert(&quantity.sku, row) {
+                report.add_error(row, &ServiceError::invalid_input(trf(
+                    Msg::InputAlreadyListed, &[("sku", &quantity.sku), ("first", &first)]
+                )));
+            }
+        }
+        report
+    }
+
+    /// Products whose available quantity differs from another system's, by SKU
+    ///
+    /// Products the other system doesn't list, and SKUs it lists that aren't
+    /// in the inventory, are included with the missing side as `None`.
+    pub fn compare_external(&self, quantities: &[ExternalQuantity]) -> Vec<Discrepancy> {
+        let theirs: BTreeMap<&str, u32> = quantities.iter().map(|quantity| (quantity.sku.as_str(), quantity.quantity)).collect();
+        let skus: BTreeSet<&str> = theirs.keys().copied().chain(self.products.keys().map(String::as_str)).collect();
+        skus.into_iter()
+            .map(|sku| Discrepancy {
+                sku: sku.to_string(),
+                ours: self.products.get(sku).map(|product| product.quantity),
+                theirs: theirs.get(sku).copied(),
+            })
+            .filter(|discrepancy| discrepancy.adjustment() != Some(0))
+            .collect()
+    }
+
+    /// Whether a discrepancy is too large to apply without a second count or
+    /// a supervisor's confirmation, under the stocktake tolerance
+    pub fn needs_confirmation(&self, discrepancy: &Discrepancy) -> bool {
+        match (discrepancy.ours, discrepancy.theirs) {
+            (Some(ours), Some(theirs)) => !self.stocktake.within_tolerance(ours, theirs),
+            _ => false,
+        }
+    }
+
+    /// Bring quantities in line with another system's
+    ///
+    /// For each discrepancy both sides have, the missing units are added, or
+    /// the extra ones removed as an adjustment, with `notes` on the
+    /// transaction. Removals go first and are checked as a batch, so if one
+    /// can't be made nothing changes. Returns the number of products adjusted.
+    pub fn align_to_external(&mut self, discrepancies: &[Discrepancy], notes: &str) -> Result<usize, ServiceError> {
+        let mut additions = Vec::new();
+        let mut removals = Vec::new();
+        for discrepancy in discrepancies {
+            let Some(quantity) = discrepancy.adjustment().filter(|&quantity| quantity != 0) else { continue };
+            let entry = StockEntry {
+                sku: discrepancy.sku.clone(),
+                quantity: quantity.unsigned_abs() as u32,
+                notes: Some(notes.to_string()),
+            };
+            if quantity > 0 { additions.push(entry) } else { removals.push(entry) }
+        }
+        if !removals.is_empty() {
+            let options = RemovalOptions { confirm_large: true, transaction_type: Some(TransactionType::Adjustment), ..Default::default() };
+            self.apply_stock_entries(&removals, TransactionType::Adjustment, options)?;
+        }
+        if !additions.is_empty() {
+            self.apply_stock_entries(&additions, TransactionType::Addition, RemovalOptions::default())?;
+        }
+        Ok(additions.len() + removals.len())
+    }
+
+    /// Check a supplier's price list without storing it
+    ///
+    /// Every SKU must belong to a product, and a SKU may be priced only once
+    /// per effective date.
+    pub fn validate_price_entries(&self, entries: &[PriceEntry]) -> ValidationReport {
+        let mut report = ValidationReport::new(entries.len());
+        let mut seen: HashMap<(&str, NaiveDate), usize> = HashMap::new();
+        for (index, entry) in entries.iter().enumerate() {
+            let row = index + 1;
+            if !self.products.contains_key(&entry.sku) {
+                report.add_error(row, &ServiceError::ProductNotFound { sku: entry.sku.clone() });
+                continue;
+            }
+            if let Some(first) = seen.insert((&entry.sku, entry.effective_from), row) {
+                report.add_error(row, &ServiceError::invalid_input(trf(
+                    Msg::InputAlreadyPriced, &[("sku", &entry.sku), ("date", &entry.effective_from), ("first", &first)]
+                )));
+            }
+        }
+        report
+    }
+
+    /// Store a supplier's price list
+    ///
+    /// Entries are validated with `validate_price_entries` first. A price
+    /// for a SKU and effective date the supplier has already priced replaces
+    /// the old one; other prices are kept, so past prices remain on record.
+    /// Returns the number of prices replaced.
+    pub fn import_prices(&mut self, supplier: &str, entries: &[PriceEntry]) -> Result<usize, ServiceError> {
+        let supplier = self.limits.clean(TextField::Supplier, supplier)?;
+        if supplier.trim().is_empty() {
+            return Err(ServiceError::invalid_input(tr(Msg::InputSupplierEmpty)));
+        }
+        let report = self.validate_price_entries(entries);
+        if !report.is_valid() {
+            return Err(ServiceError::ValidationFailed { report });
+        }
+        if self.read_only {
+            return Err(ServiceError::ReadOnly);
+        }
+
+        let mut prices = self.storage.load_prices().map_err(ServiceError::storage("loading supplier prices"))?;
+        let count = prices.len();
+        prices.retain(|price| {
+            price.supplier != supplier
+                || !entries.iter().any(|entry| entry.sku == price.sku && entry.effective_from == price.effective_from)
+        });
+        let replaced = count - prices.len();
+        prices.extend(entries.iter().map(|entry| SupplierPrice {
+            supplier: supplier.clone(),
+            sku: entry.sku.clone(),
+            unit_cost: entry.unit_cost,
+            effective_from: entry.effective_from,
+        }));
+        prices.sort_by(|a, b| (&a.sku, &a.supplier, a.effective_from).cmp(&(&b.sku, &b.supplier, b.effective_from)));
+        self.storage.save_prices(&prices).map_err(ServiceError::storage("saving supplier prices"))?;
+        log::info!("Imported {} prices from '{}', replacing {}", entries.len(), supplier, replaced);
+        Ok(replaced)
+    }
+
+    /// Each supplier's price for a product on the given day, cheapest first
+    ///
+    /// A supplier's price is the one with the latest effective date on or
+    /// before `on`; suppliers whose prices only start later are left out.
+    /// Ties are ordered by supplier name.
+    pub fn compare_prices(&self, sku: &str, on: NaiveDate) -> Result<Vec<SupplierPrice>, ServiceError> {
+        self.get_product(sku)?;
+        let prices = self.storage.load_prices().map_err(ServiceError::storage("loading supplier prices"))?;
+        let mut current = prices_on(prices.into_iter().filter(|price| price.sku == sku), on);
+        current.sort_by(|a, b| (a.unit_cost, &a.supplier).cmp(&(b.unit_cost, &b.supplier)));
+        Ok(current)
+    }
+
+    /// The cheapest supplier price on the given day for every priced product, by SKU
+    pub fn cheapest_prices(&self, on: NaiveDate) -> Result<BTreeMap<String, SupplierPrice>, ServiceError> {
+        let prices = self.storage.load_prices().map_err(ServiceError::storage("loading supplier prices"))?;
+        let mut cheapest: BTreeMap<String, SupplierPrice> = BTreeMap::new();
+        for price in prices_on(prices, on) {
+            let better = cheapest.get(&price.sku).is_none_or(|best| {
+                (price.unit_cost, &price.supplier) < (best.unit_cost, &best.supplier)
+            });
+            if better {
+                cheapest.insert(price.sku.clone(), price);
+            }
+        }
+        Ok(cheapest)
+    }
+
+    /// Changes to the inventory since the given time
+    ///
+    /// The earlier state is rebuilt by undoing every transaction after
+    /// `since`, archived ones included; products deleted since then come back from the trash and are
+    /// reported as removed. Products have no creation date, so one added
+    /// since `since` shows as a quantity change from zero rather than as added.
+    pub fn diff_since(&self, since: DateTime<Utc>) -> Result<InventoryDiff, ServiceError> {
+        let current: Vec<Product> = self.products.values().cloned().collect();
+        let archived = self.storage.load_archived_transactions()
+            .map_err(ServiceError::storage("loading archived transactions"))?;
+        let rewind = |product: &Product, transactions: &[&Transaction]| {
+            let net: i64 = transactions
+                .iter()
+                .filter(|t| t.timestamp > since)
+                .map(|t| signed_quantity(t.transaction_type, t.quantity))
+                .sum();
+            let quantity = (i64::from(product.quantity) - net).clamp(0, i64::from(u32::MAX));
+            Product { quantity: quantity as u32, ..product.clone() }
+        };
+
+        let mut before: Vec<Product> = current
+            .iter()
+            .map(|product| {
+                let mut transactions = self.get_transactions(&product.sku);
+                transactions.extend(archived.iter().filter(|t| t.product_sku == product.sku));
+                rewind(product, &transactions)
+            })
+            .collect();
+        for tombstone in self.trash()? {
+            let deleted_since = tombstone.deleted_at > since;
+            if deleted_since && !self.products.contains_key(&tombstone.product.sku) {
+                let transactions: Vec<&Transaction> = tombstone.transactions.iter().collect();
+                before.push(rewind(&tombstone.product, &transactions));
+            }
+        }
+        Ok(InventoryDiff::between(&before, &current))
+    }
+
+    /// Stock value at each period boundary, for a trend over time
+    ///
+    /// The boundaries are the starts of the periods from the one containing
+    /// `start` up to `end`, followed by `end` itself. The stock held at each
+    /// is rebuilt by replaying the transactions before it into FIFO cost
+    /// layers, archived ones and deleted products included, so units are
+    /// valued at the cost they were received at.
+    pub fn value_history(
+        &self,
+        interval: Interval,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Result<Vec<ValuePoint>, ServiceError> {
+        let archived = self.storage.load_archived_transactions()
+            .map_err(ServiceError::storage("loading archived transactions"))?;
+        let trash = self.trash()?;
+        let histories = self.product_histories(&archived, &trash);
+
+        let mut boundaries = Vec::new();
+        let mut period = interval.start_of(start.date_naive());
+        while let Some(at) = period.and_hms_opt(0, 0, 0).map(|at| at.and_utc()).filter(|at| *at < end) {
+            boundaries.push(at);
+            period = interval.next(period);
+        }
+        boundaries.push(end);
+
+        let points = boundaries
+            .into_iter()
+            .map(|at| {
+                let mut held = Valuation::default();
+                for history in &histories {
+                    if history.deleted_at.is_some_and(|deleted_at| deleted_at <= at) {
+                        continue;
+                    }
+                    held.merge(history.layers_at(at).valuation());
+                }
+                ValuePoint { at, units: held.units, value: held.value, uncosted_units: held.uncosted_units }
+            })
+            .collect();
+        Ok(points)
+    }
+
+    /// Cost of the goods issued in a period, by product, taken from FIFO cost layers
+    ///
+    /// Only issues with `start <= timestamp < end` are counted; either end
+    /// may be left open. Losses recorded as damage, write-off, or adjustment
+    /// are left to the shrinkage report. Lines are ordered by SKU; products
+    /// with no issues in the period are left out.
+    pub fn cost_of_goods(
+        &self,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<Vec<CogsLine>, ServiceError> {
+        let archived = self.storage.load_archived_transactions()
+            .map_err(ServiceError::storage("loading archived transactions"))?;
+        let trash = self.trash()?;
+        let mut lines: BTreeMap<&str, CogsLine> = BTreeMap::new();
+        for history in self.product_histories(&archived, &trash) {
+            let product = history.product;
+            CostLayers::replay_with(product, &history.transactions, end, |transaction, issued| {
+                let before_start = start.is_some_and(|start| transaction.timestamp < start);
+                if before_start || issued.units == 0 || transaction.transaction_type.is_shrinkage() {
+                    return;
+                }
+                lines.entry(&product.sku)
+                    .or_insert_with(|| CogsLine {
+                        sku: product.sku.clone(),
+                        name: product.name.clone(),
+                        cost: Valuation::default(),
+                    })
+                    .cost
+                    .merge(issued);
+            });
+        }
+        Ok(lines.into_values().collect())
+    }
+
+    /// Receipts and issues in a period valued at cost, in the order they took place
+    ///
+    /// Only movements with `start <= timestamp < end` are listed; either end
+    /// may be left open. Issues are costed from FIFO layers replayed over the
+    /// whole history, archived transactions and deleted products included.
+    /// Moves into and out of quarantine or between conditions change no
+    /// value and are left out.
+    pub fn accounting_entries(
+        &self,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<Vec<AccountingEntry>, ServiceError> {
+        let archived = self.storage.load_archived_transactions()
+            .map_err(ServiceError::storage("loading archived transactions"))?;
+        let trash = self.trash()?;
+        let mut entries = Vec::new();
+        for history in self.product_histories(&archived, &trash) {
+            let product = history.product;
+            CostLayers::replay_with(product, &history.transactions, end, |transaction, issued| {
+                if start.is_some_and(|start| transaction.timestamp < start) {
+                    return;
+                }
+                let cost = if transaction.transaction_type.adds_stock() {
+                    let units = u64::from(transaction.quantity);
+                    match transaction.unit_cost {
+                        Some(unit_cost) => Valuation { units, value: unit_cost.times(units), uncosted_units: 0 },
+                        None => Valuation { units, value: Money::default(), uncosted_units: units },
+                    }
+                } else {
+                    issued
+                };
+                entries.push((transaction.order_key(), AccountingEntry {
+                    timestamp: transaction.timestamp,
+                    sku: product.sku.clone(),
+                    name: product.name.clone(),
+                    transaction_type: transaction.transaction_type,
+                    cost,
+                }));
+            });
+        }
+        entries.sort_by_key(|(order, _)| *order);
+        Ok(entries.into_iter().map(|(_, entry)| entry).collect())
+    }
+
+    /// Suggest reorder points from the removals of the last `window_days` days
+    ///
+    /// Each product's window starts no earlier than its first transaction, so
+    /// new products aren't diluted by days before they were stocked. Products
+    /// use their own lead time, or `default_lead_time_days`; those without
+    /// removals in the window get no suggestion. Suggestions are in SKU order.
+    pub fn suggest_reorder_points(
+        &self,
+        service_level: f64,
+        default_lead_time_days: u32,
+        window_days: u32,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<ReorderSuggestion>, ServiceError> {
+        if !reorder::is_valid_service_level(service_level) {
+            return Err(ServiceError::invalid_input(trf(
+                Msg::InputServiceLevel, &[("level", &service_level)]
+            )));
+        }
+        if window_days == 0 {
+            return Err(ServiceError::invalid_input(tr(Msg::InputDemandWindow)));
+        }
+        let today = now.date_naive();
+        let window_start = today - chrono::Duration::days(i64::from(window_days) - 1);
+
+        let mut suggestions = Vec::new();
+        let mut products: Vec<&Product> = self.products.values().collect();
+        products.sort_by(|a, b| a.sku.cmp(&b.sku));
+        for product in products {
+            let Some((_, daily)) = self.daily_demand(&product.sku, window_start, today) else {
+                continue;
+            };
+            let demand = Demand::from_daily(&daily);
+            let lead_time_days = product.lead_time_days.unwrap_or(default_lead_time_days);
+            suggestions.push(ReorderSuggestion {
+                sku: product.sku.clone(),
+                name: product.name.clone(),
+                current: product.reorder_point,
+                suggested: demand.reorder_point(lead_time_days, service_level),
+                lead_time_days,
+                demand,
+                safety_stock: demand.safety_stock(lead_time_days, service_level).ceil() as u32,
+            });
+        }
+        Ok(suggestions)
+    }
+
+    /// Forecast when each product runs out, soonest first
+    ///
+    /// Demand is measured over the last `window_days` days as for
+    /// `suggest_reorder_points` and projected with `seasonality`, or the
+    /// service's default. Stock committed to backorders isn't counted as
+    /// available. Products without removals in the window are left out;
+    /// those not expected to run out within `horizon_days` come last.
+    pub fn forecast_stock_outs(
+        &self,
+        seasonality: Option<Seasonality>,
+        window_days: u32,
+        horizon_days: u32,
+        now: DateTime<Utc>,
+    ) -> Result<Vec<StockOutForecast>, ServiceError> {
+        if window_days == 0 {
+            return Err(ServiceError::invalid_input(tr(Msg::InputDemandWindow)));
+        }
+        let seasonality = seasonality.unwrap_or(self.seasonality);
+        let today = now.date_naive();
+        let window_start = today - chrono::Duration::days(i64::from(window_days) - 1);
+        let mut committed: HashMap<&str, u32> = HashMap::new();
+        let backorders = self.backorders()?;
+        for backorder in &backorders {
+            let units = committed.entry(backorder.sku.as_str()).or_default();
+            *units = units.saturating_add(backorder.quantity);
+        }
+
+        let mut forecasts = Vec::new();
+        for product in self.products.values() {
+            let Some((start, daily)) = self.daily_demand(&product.sku, window_start, today) else {
+                continue;
+            };
+            let forecast = Forecast::fit(start, &daily, seasonality);
+            let available = product.quantity.saturating_sub(committed.get(product.sku.as_str()).copied().unwrap_or(0));
+            forecasts.push(StockOutForecast {
+                sku: product.sku.clone(),
+                name: product.name.clone(),
+                available,
+                stock_out: forecast.stock_out(available, today, horizon_days),
+                forecast,
+            });
+        }
+        forecasts.sort_by(|a, b| {
+            (a.stock_out.is_none(), a.stock_out, &a.sku).cmp(&(b.stock_out.is_none(), b.stock_out, &b.sku))
+        });
+        Ok(forecasts)
+    }
+
+    /// Units of a product removed on each day from `window_start`, or from
+    /// its first transaction if later, through `today`
+    ///
+    /// Returns the first day measured, or `None` if nothing was removed.
+    fn daily_demand(&self, sku: &str, window_start: NaiveDate, today: NaiveDate) -> Option<(NaiveDate, Vec<u64>)> {
+        let transactions = self.get_transactions(sku);
+        let first = transactions.iter().map(|t| t.timestamp.date_naive()).min()?;
+        let start = window_start.max(first);
+        let mut daily = vec![0u64; (today - start).num_days().max(0) as usize + 1];
+        for transaction in &transactions {
+            let day = transaction.timestamp.date_naive();
+            if transaction.transaction_type == TransactionType::Removal && day >= start && day <= today {
+                daily[(day - start).num_days() as usize] += u64::from(transaction.quantity);
+            }
+        }
+        if daily.iter().all(|&units| units == 0) {
+            return None;
+        }
+        Some((start, daily))
+    }
+
+    /// FIFO cost layers of a product's stock on hand, oldest first
+    pub fn current_cost_layers(&self, sku: &str) -> Result<CostLayers, ServiceError> {
+        let product = self.get_product(sku)?;
+        if self.read_only {
+            // Transactions aren't loaded up front, so replay them from storage
+            let transactions = self.transactions_for(sku)?;
+            let transactions: Vec<&Transaction> = transactions.iter().collect();
+            return Ok(CostLayers::replay(product, &transactions, None));
+        }
+        Ok(self.cost_layers.get(sku).cloned().unwrap_or_default())
+    }
+
+    /// Every product, current or deleted, with its transactions, archived ones included
+    fn product_histories<'a>(&'a self, archived: &'a [Transaction], trash: &'a [Tombstone]) -> Vec<ProductHistory<'a>> {
+        let mut histories: Vec<ProductHistory> = self.products
+            .values()
+            .map(|product| {
+                let mut transactions = self.get_transactions(&product.sku);
+                transactions.extend(archived.iter().filter(|t| t.product_sku == product.sku));
+                ProductHistory { product, transactions, deleted_at: None }
+            })
+            .collect();
+        histories.extend(trash.iter().map(|tombstone| ProductHistory {
+            product: &tombstone.product,
+            transactions: tombstone.transactions.iter().collect(),
+            deleted_at: Some(tombstone.deleted_at),
+        }));
+        histories
+    }
+
+    /// Losses recorded as damage, write-off, or adjustment, by product and type
+    ///
+    /// Only transactions with `start <= timestamp < end` are counted; either
+    /// end may be left open. Lines are ordered by SKU, then type. With
+    /// `condition`, only losses of stock in that condition are counted.
+    pub fn shrinkage(
+        &self,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        condition: Option<Condition>,
+    ) -> Vec<ShrinkageLine> {
+        let mut lines: BTreeMap<(&str, &'static str), ShrinkageLine> = BTreeMap::new();
+        let in_period = |t: &Transaction| {
+            start.is_none_or(|start| t.timestamp >= start)
+                && end.is_none_or(|end| t.timestamp < end)
+                && condition.is_none_or(|condition| t.condition.unwrap_or_default() == condition)
+        };
+        for transaction in self.transactions.iter().filter(|t| t.transaction_type.is_shrinkage() && in_period(t)) {
+            let Some(product) = self.products.get(&transaction.product_sku) else {
+                continue;
+            };
+            let line = lines
+                .entry((&product.sku, transaction.transaction_type.name()))
+                .or_insert_with(|| ShrinkageLine {
+                    sku: product.sku.clone(),
+                    name: product.name.clone(),
+                    transaction_type: transaction.transaction_type,
+                    transactions: 0,
+                    units: 0,
+                });
+            line.transactions += 1;
+            line.units += u64::from(transaction.quantity);
+        }
+        lines.into_values().collect()
+    }
+
+    /// Units removed in a period, by reason code
+    ///
+    /// Counts every transaction that reduces stock, losses included, with
+    /// `start <= timestamp < end`. Reasons are in `ReasonCode::ALL` order,
+    /// followed by removals without a reason; reasons never used are omitted.
+    /// With `sku`, only that product's removals are counted, and with
+    /// `condition` only removals of stock in that condition.
+    pub fn consumption_by_reason(
+        &self,
+        sku: Option<&str>,
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+        condition: Option<Condition>,
+    ) -> Vec<ReasonTotal> {
+        let mut totals: BTreeMap<Option<ReasonCode>, ReasonTotal> = BTreeMap::new();
+        let removals = self.transactions.iter().filter(|t| {
+            !t.transaction_type.adds_stock()
+                && !t.transaction_type.is_internal_move()
+                && sku.is_none_or(|sku| t.product_sku == sku)
+                && start.is_none_or(|start| t.timestamp >= start)
+                && end.is_none_or(|end| t.timestamp < end)
+                && condition.is_none_or(|condition| t.condition.unwrap_or_default() == condition)
+        });
+        for transaction in removals {
+            let total = totals.entry(transaction.reason).or_insert(ReasonTotal {
+                reason: transaction.reason,
+                transactions: 0,
+                units: 0,
+            });
+            total.transactions += 1;
+            total.units += u64::from(transaction.quantity);
+        }
+        // None sorts first in a BTreeMap; list unreasoned removals last
+        let mut totals: Vec<ReasonTotal> = totals.into_values().collect();
+        if totals.first().is_some_and(|total| total.reason.is_none()) {
+            totals.rotate_left(1);
+        }
+        totals
+    }
+
+    /// List all products with low stock (quantity at or below reorder point)
+    /// 
+    /// # Requirements
+    /// - 4.5: Flag products for reorder when stock falls below reorder point
+    /// - 5.3: Return all products where current stock level is at or below reorder point
+    ///
+    /// Products come from the read model, in SKU order, without scanning the catalog.
+    /// Products whose alert is acknowledged with `acknowledge_alert` are left out.
+    pub fn list_low_stock(&self) -> Vec<&Product> {
+        let today = Utc::now().date_naive();
+        self.read_model
+            .low_stock()
+            .map(|sku| &self.products[sku])
+            .filter(|product| !product.is_snoozed(today))
+            .collect()
+    }
+
+    /// Low-stock products whose alert is acknowledged on `today`, in SKU order
+    pub fn snoozed_low_stock(&self, today: NaiveDate) -> Vec<&Product> {
+        self.read_model
+            .low_stock()
+            .map(|sku| &self.products[sku])
+            .filter(|product| product.is_snoozed(today))
+            .collect()
+    }
+
+    /// Acknowledge a product's low-stock alert so `low-stock` leaves it out
+    ///
+    /// The alert shows again on `until`, once stock falls below today's
+    /// level, or after a restock lifts the product above its reorder point.
+    pub fn acknowledge_alert(&mut self, sku: &str, until: Option<NaiveDate>) -> Result<AlertSnooze, ServiceError> {
+        let product = self.products.get_mut(sku)
+            .ok_or_else(|| ServiceError::ProductNotFound { sku: sku.to_string() })?;
+        if !product.is_low_stock() {
+            return Err(ServiceError::invalid_input(trf(Msg::InputNotLowStock, &[("sku", &sku)])));
+        }
+        let snooze = AlertSnooze { quantity: product.quantity, until };
+        product.alert_snooze = Some(snooze);
+        log::info!("Acknowledged the low-stock alert for '{}' at {} units", sku, snooze.quantity);
+        self.persist_products()?;
+        Ok(snooze)
+    }
+
+    /// Withdraw an acknowledgement so the product's alert shows again
+    ///
+    /// Returns whether the product had one.
+    pub fn clear_alert_snooze(&mut self, sku: &str) -> Result<bool, ServiceError> {
+        let product = self.products.get_mut(sku)
+            .ok_or_else(|| ServiceError::ProductNotFound { sku: sku.to_string() })?;
+        if product.alert_snooze.take().is_none() {
+            return Ok(false);
+        }
+        self.persist_products()?;
+        Ok(true)
+    }
+
+    /// Product and unit totals, overall and per category, kept current as products change
+    pub fn stats(&self) -> &ReadModel {
+        &self.read_model
+    }
+
+    /// Count, units, and value of the products passing a filter, per
+    /// supplier, category, or location
+    ///
+    /// Groups are ordered by name with products lacking the field last. Units
+    /// are those in the filter's condition if it has one; values cover all
+    /// stock on hand.
+    pub fn aggregate_products(&self, filter: &ProductFilter, group_by: GroupBy) -> Result<Vec<GroupTotals>, ServiceError> {
+        group_products(self.query_products(filter), group_by)
+            .into_iter()
+            .map(|group| {
+                let mut totals =
+                    GroupTotals { key: group.key, products: group.products.len(), quantity: 0, value: Valuation::default() };
+                for product in group.products {
+                    let quantity = filter.condition.map_or(product.quantity, |c| product.condition_quantity(c));
+                    totals.quantity += u64::from(quantity);
+                    totals.value.merge(self.current_cost_layers(&product.sku)?.valuation());
+                }
+                Ok(totals)
+            })
+            .collect()
+    }
+
+    /// List low-stock products grouped by supplier, category, or location
+    ///
+    /// Groups are ordered by name with products lacking the field last;
+    /// products within a group are ordered by SKU.
+    pub fn list_low_stock_grouped(&self, group_by: GroupBy) -> Vec<ProductGroup<'_>> {
+        group_products(self.list_low_stock(), group_by)
+    }
+
+    /// Find a transaction by its ID or a unique prefix of it
+    ///
+    /// Prefixes must be at least 8 characters, the length shown by `history`.
+    pub fn find_transaction(&self, id: &str) -> Result<&Transaction, ServiceError> {
+        if let Some(transaction) = self.transactions.iter().find(|t| t.id == id) {
+            return Ok(transaction);
+        }
+        if id.len() < 8 {
+            return Err(ServiceError::TransactionNotFound { id: id.to_string() });
+        }
+
+        let mut matches = self.transactions.iter().filter(|t| t.id.starts_with(id));
+        match (matches.next(), matches.next()) {
+            (Some(transaction), None) => Ok(transaction),
+            (Some(_), Some(_)) => Err(ServiceError::invalid_input(trf(Msg::InputAmbiguousId, &[("id", &id)]))),
+            (None, _) => Err(ServiceError::TransactionNotFound { id: id.to_string() }),
+        }
+    }
+
+    /// Get transaction history for a product, ordered by timestamp
+    /// 
+    /// # Requirements
+    /// - 6.1: Return all transactions for a product ordered by timestamp
+    /// - 6.2: Include transaction type, quantity, timestamp, and notes
+    /// - 6.5: Preserve chronological order of all stock movements
+    pub fn get_transactions(&self, sku: &str) -> Vec<&Transaction> {
+        let mut transactions: Vec<&Transaction> = self.transactions
+            .iter()
+            .filter(|t| t.product_sku == sku)
+            .collect();
+        
+        // Sort by timestamp ascending (earliest to latest)
+        transactions.sort_by_key(|t| t.order_key());
+        
+        transactions
+    }
+
+    /// Transaction history for a product, ordered by timestamp
+    ///
+    /// Unlike `get_transactions` this also works on a service opened with
+    /// `open_read_only`, reading just this product's records from storage.
+    pub fn transactions_for(&self, sku: &str) -> Result<Vec<Transaction>, ServiceError> {
+        if !self.read_only {
+            return Ok(self.get_transactions(sku).into_iter().cloned().collect());
+        }
+        let mut transactions = self.storage
+            .load_transactions_for(sku)
+            .map_err(ServiceError::storage("loading transactions"))?;
+        transactions.sort_by_key(|t| t.order_key());
+        Ok(transactions)
+    }
+
+    /// Summarize the movements of several products side by side, with
+    /// `start <= timestamp <= end`
+    ///
+    /// Summaries are in the order the SKUs are given.
+    pub fn summarize_movements(
+        &self,
+        skus: &[String],
+        start: Option<DateTime<Utc>>,
+        end: Option<DateTime<Utc>>,
+    ) -> Result<Vec<MovementSummary>, ServiceError> {
+        skus.iter()
+            .map(|sku| {
+                let product = self.get_product(sku)?;
+                let mut summary = MovementSummary { sku: sku.clone(), on_hand: product.quantity, ..Default::default() };
+                let transactions = self.transactions_for(sku)?;
+                let in_period = transactions.iter().filter(|t| {
+                    start.is_none_or(|start| t.timestamp >= start) && end.is_none_or(|end| t.timestamp <= end)
+                });
+                for transaction in in_period {
+                    summary.movements += 1;
+                    let units = u64::from(transaction.quantity);
+                    match transaction.transaction_type {
+                        TransactionType::Removal => summary.removed += units,
+                        t if t.is_shrinkage() => summary.shrinkage += units,
+                        t if t.adds_stock() && !t.is_internal_move() => summary.received += units,
+                        _ => {}
+                    }
+                }
+                Ok(summary)
+            })
+            .collect()
+    }
+
+    /// All transactions of all products, ordered by timestamp
+    pub fn all_transactions(&self) -> Vec<&Transaction> {
+        let mut transactions: Vec<&Transaction> = self.transactions.iter().collect();
+        transactions.sort_by_key(|t| t.order_key());
+        transactions
+    }
+
+    /// Get transaction history for a product within a date range, ordered by timestamp
+    /// 
+    /// # Requirements
+    /// - 6.3: Return only transactions within the specified period
+    pub fn get_transactions_in_range(
+        &self,
+        sku: &str,
+        start: DateTime<Utc>,
+        end: DateTime<Utc>,
+    ) -> Vec<&Transaction> {
+        let mut transactions: Vec<&Transaction> = self.transactions
+            .iter()
+            .filter(|t| {
+                t.product_sku == sku 
+                    && t.timestamp >= start 
+                    && t.timestamp <= end
+            })
+            .collect();
+        
+        // Sort by timestamp ascending (earliest to latest)
+        transactions.sort_by_key(|t| t.order_key());
+        
+        transactions
+    }
+}
+
+/// Whether `text` matches a glob pattern of `*` and `?` wildcards
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text it has absorbed up to, for backtracking
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                Some((star_p, star_t)) => {
+                    p = star_p + 1;
+                    t = star_t + 1;
+                    star = Some((star_p, star_t + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// A product, current or deleted, with all its transactions
+struct ProductHistory<'a> {
+    product: &'a Product,
+    transactions: Vec<&'a Transaction>,
+    /// When the product was deleted, if it was
+    deleted_at: Option<DateTime<Utc>>,
+}
+
+impl ProductHistory<'_> {
+    /// Cost layers of the stock held at `at`, found by replaying the earlier transactions
+    fn layers_at(&self, at: DateTime<Utc>) -> CostLayers {
+        CostLayers::replay(self.product, &self.transactions, Some(at))
+    }
+}
+
+/// The price in force on `on` for each supplier and SKU
+fn prices_on(prices: impl IntoIterator<Item = SupplierPrice>, on: NaiveDate) -> Vec<SupplierPrice> {
+    let mut current: HashMap<(String, String), SupplierPrice> = HashMap::new();
+    for price in prices.into_iter().filter(|price| price.effective_from <= on) {
+        let key = (price.supplier.clone(), price.sku.clone());
+        if current.get(&key).is_none_or(|kept| kept.effective_from < price.effective_from) {
+            current.insert(key, price);
+        }
+    }
+    current.into_values().collect()
+}
+
+/// Replay every product's transactions into its FIFO cost layers
+/// Timestamp of each product's latest transaction
+fn latest_timestamps(transactions: &[Transaction]) -> HashMap<String, DateTime<Utc>> {
+    let mut latest: HashMap<String, DateTime<Utc>> = HashMap::new();
+    for transaction in transactions {
+        let timestamp = latest.entry(transaction.product_sku.clone()).or_insert(transaction.timestamp);
+        *timestamp = (*timestamp).max(transaction.timestamp);
+    }
+    latest
+}
+
+fn build_cost_layers(
+    products: &BTreeMap<String, Product>,
+    transactions: &[Transaction],
+) -> HashMap<String, CostLayers> {
+    let mut by_sku: HashMap<&str, Vec<&Transaction>> = HashMap::new();
+    for transaction in transactions {
+        by_sku.entry(&transaction.product_sku).or_default().push(transaction);
+    }
+    products
+        .values()
+        .map(|product| {
+            let transactions = by_sku.get(product.sku.as_str()).map_or(&[][..], Vec::as_slice);
+            (product.sku.clone(), CostLayers::replay(product, transactions, None))
+        })
+        .collect()
+}
+
+/// Quantity of a movement as a signed change in stock
+fn signed_quantity(transaction_type: TransactionType, quantity: u32) -> i64 {
+    if transaction_type == TransactionType::ConditionChange {
+        0
+    } else if transaction_type.adds_stock() {
+        i64::from(quantity)
+    } else {
+        -i64::from(quantity)
+    }
+}
+
+/// A stock movement with the input row it came from
+type NumberedMovement = (usize, StockEntry);
+
+/// Removals that bring stock down to a merged opening balance: adjustments,
+/// however large
+fn merge_removal_options() -> RemovalOptions {
+    RemovalOptions { confirm_large: true, transaction_type: Some(TransactionType::Adjustment), ..Default::default() }
+}
+
+/// Format optional transaction notes for a log message
+fn format_notes(notes: Option<&str>) -> String {
+    notes.map(|notes| format!(" ({})", notes)).unwrap_or_default()
+}
+
+/// Convert an empty (or whitespace-only) string into `None`
+fn non_empty(value: String) -> Option<String> {
+    if value.trim().is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+/// Group products by the given field, ordering groups by key (unset last)
+/// and products within each group by SKU
+fn group_products(products: Vec<&Product>, group_by: GroupBy) -> Vec<ProductGroup<'_>> {
+    let mut groups: Vec<ProductGroup> = Vec::new();
+    for product in products {
+        let key = product.group_key(group_by).map(str::to_string);
+        match groups.iter_mut().find(|g| g.key == key) {
+            Some(group) => group.products.push(product),
+            None => groups.push(ProductGroup { key, products: vec![product] }),
+        }
+    }
+
+    for group in &mut groups {
+        group.products.sort_by(|a, b| a.sku.cmp(&b.sku));
+    }
+    // `None` sorts before `Some`, so order by (is_none, key) to put it last
+    groups.sort_by(|a, b| (a.key.is_none(), &a.key).cmp(&(b.key.is_none(), &b.key)));
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use tempfile::TempDir;
+    use crate::storage::{JsonStorage, Storage};
+    use crate::testing::FlakyStorage;
+
+    fn create_test_service(temp_dir: &TempDir) -> InventoryService {
+        InventoryService::new(Box::new(JsonStorage::new(temp_dir.path()))).unwrap()
+    }
+
+    /// Events taken from the service, leaving out stock changes
+    fn take_warnings(service: &mut InventoryService) -> Vec<InventoryEvent> {
+        service.take_events().into_iter().filter(InventoryEvent::is_warning).collect()
+    }
+
+    fn add_with_supplier(service: &mut InventoryService, sku: &str, quantity: u32, supplier: Option<&str>) {
+        service
+            .add_product(sku.to_string(), sku.to_string(), String::new(), quantity, 10)
+            .unwrap();
+        let update = ProductUpdate {
+            supplier: supplier.map(str::to_string),
+            ..Default::default()
+        };
+        service.update_product(sku, update).unwrap();
+    }
+
+    #[test]
+    fn test_update_product_clears_classification_with_empty_value() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        add_with_supplier(&mut service, "SKU001", 5, Some("Acme"));
+
+        let update = ProductUpdate { supplier: Some(String::new()), ..Default::default() };
+        let product = service.update_product("SKU001", update).unwrap();
+        assert_eq!(product.supplier, None);
+    }
+
+    #[test]
+    fn test_every_stock_movement_raises_stock_changed() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 5, 1).unwrap();
+        service.add_stock("SKU001", 3, None).unwrap();
+        service.remove_stock("SKU001", 2, None).unwrap();
+        service.quarantine("SKU001", 1, None).unwrap();
+        let entries = [StockEntry { sku: "SKU001".into(), quantity: 4, notes: None }];
+        service.apply_stock_entries(&entries, TransactionType::Addition, RemovalOptions::default()).unwrap();
+        service.delete_product("SKU001").unwrap();
+
+        let changes: Vec<(Option<TransactionType>, Option<u64>, u32, u32)> = service
+            .take_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                InventoryEvent::StockChanged { transaction_type, transaction_seq, before, after, .. } => {
+                    Some((transaction_type, transaction_seq, before, after))
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(changes, [
+            (None, None, 0, 5),
+            (Some(TransactionType::Addition), Some(1), 5, 8),
+            (Some(TransactionType::Removal), Some(2), 8, 6),
+            (Some(TransactionType::Quarantine), Some(3), 6, 5),
+            (Some(TransactionType::Addition), Some(4), 5, 9),
+            (None, None, 9, 0),
+        ]);
+    }
+
+    #[test]
+    fn test_large_removal_requires_confirmation() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir)
+            .with_large_movement_threshold(Some(MovementThreshold::Units(100)));
+        add_with_supplier(&mut service, "SKU001", 1000, None);
+
+        let result = service.remove_stock("SKU001", 500, None);
+        assert!(matches!(result, Err(ServiceError::LargeMovement { requested: 500, .. })));
+        assert_eq!(service.get_product("SKU001").unwrap().quantity, 1000);
+
+        let options = RemovalOptions { confirm_large: true, ..Default::default() };
+        service.remove_stock_with_options("SKU001", 500, None, options).unwrap();
+        assert_eq!(service.get_product("SKU001").unwrap().quantity, 500);
+
+        let events = take_warnings(&mut service);
+        assert!(matches!(events.as_slice(), [InventoryEvent::LargeMovement { quantity: 500, available: 1000, .. }]));
+        assert_eq!(JsonStorage::new(temp_dir.path()).load_audit_log().unwrap(), events);
+    }
+
+    #[test]
+    fn test_product_threshold_overrides_global() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir)
+            .with_large_movement_threshold(Some(MovementThreshold::Units(100)));
+        add_with_supplier(&mut service, "SKU001", 1000, None);
+        let update = ProductUpdate {
+            large_movement_threshold: Some(Some(MovementThreshold::Percent(10))),
+            ..Default::default()
+        };
+        service.update_product("SKU001", update).unwrap();
+
+        assert!(service.remove_stock("SKU001", 100, None).is_ok());
+        assert!(matches!(
+            service.remove_stock("SKU001", 91, None),
+            Err(ServiceError::LargeMovement { .. })
+        ));
+    }
+
+    #[test]
+    fn test_list_low_stock_grouped_orders_groups_and_products() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        add_with_supplier(&mut service, "SKU003", 1, Some("Zeta"));
+        add_with_supplier(&mut service, "SKU002", 2, Some("Acme"));
+        add_with_supplier(&mut service, "SKU001", 3, Some("Acme"));
+        add_with_supplier(&mut service, "SKU004", 4, None);
+        add_with_supplier(&mut service, "SKU005", 50, Some("Acme"));
+
+        let groups = service.list_low_stock_grouped(GroupBy::Supplier);
+        let summary: Vec<(Option<&str>, Vec<&str>)> = groups
+            .iter()
+            .map(|g| (g.key.as_deref(), g.products.iter().map(|p| p.sku.as_str()).collect()))
+            .collect();
+
+        assert_eq!(summary, vec![
+            (Some("Acme"), vec!["SKU001", "SKU002"]),
+            (Some("Zeta"), vec!["SKU003"]),
+            (None, vec!["SKU004"]),
+        ]);
+    }
+
+    #[test]
+    fn test_field_limits_are_enforced_before_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let limits = FieldLimits { max_description: 10, ..Default::default() };
+        let mut service = create_test_service(&temp_dir).with_field_limits(limits);
+        service.add_product("SKU001".into(), "Cafe\u{301}".into(), "Beans".into(), 5, 1).unwrap();
+        assert_eq!(service.get_product("SKU001").unwrap().name, "Caf\u{e9}");
+
+        let update = ProductUpdate {
+            name: Some("Coffee".to_string()),
+            description: Some("Freshly roasted beans".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(service.update_product("SKU001", update), Err(ServiceError::InvalidInput { .. })));
+        assert_eq!(service.get_product("SKU001").unwrap().name, "Caf\u{e9}");
+
+        assert!(service.add_stock("SKU001", 1, Some("tab\there".to_string())).is_err());
+        assert!(service.get_transactions("SKU001").is_empty());
+    }
+
+    #[test]
+    fn test_delete_policy_blocks_stock_and_recent_activity() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir).with_operator(Some("alice".to_string()));
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        service.add_stock("SKU001", 3, None).unwrap();
+
+        let result = service.delete_product_with_options("SKU001", DeleteOptions::default());
+        assert!(matches!(
+            result,
+            Err(ServiceError::DeletionBlocked { blocker: DeletionBlocker::InStock { quantity: 3 }, .. })
+        ));
+
+        service.remove_stock("SKU001", 3, None).unwrap();
+        let result = service.delete_product_with_options("SKU001", DeleteOptions::default());
+        assert!(matches!(
+            result,
+            Err(ServiceError::DeletionBlocked {
+                blocker: DeletionBlocker::RecentActivity { transactions: 2, days: 30 },
+                ..
+            })
+        ));
+
+        service.delete_product_with_options("SKU001", DeleteOptions { force: true }).unwrap();
+        assert!(service.get_product("SKU001").is_err());
+
+        let tombstones = JsonStorage::new(temp_dir.path()).load_tombstones().unwrap();
+        assert_eq!(tombstones.len(), 1);
+        assert_eq!(tombstones[0].product.sku, "SKU001");
+        assert_eq!(tombstones[0].transactions.len(), 2);
+        assert_eq!(tombstones[0].deleted_by.as_deref(), Some("alice"));
+    }
+
+    #[test]
+    fn test_restore_product_from_trash() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        service.add_stock("SKU001", 5, None).unwrap();
+        service.delete_product("SKU001").unwrap();
+
+        let trash = service.trash().unwrap();
+        assert_eq!(trash.len(), 1);
+        assert!(service.trash_expiry(&trash[0]).is_some());
+
+        service.add_product("SKU001".into(), "Replacement".into(), "".into(), 0, 1).unwrap();
+        assert!(matches!(service.restore_product("SKU001"), Err(ServiceError::DuplicateSKU { .. })));
+        service.delete_product("SKU001").unwrap();
+
+        // The most recent deletion comes back; the older one stays in the trash
+        let restored = service.restore_product("SKU001").unwrap();
+        assert_eq!(restored.name, "Replacement");
+        assert_eq!(service.trash().unwrap()[0].product.name, "Widget");
+
+        service.delete_product("SKU001").unwrap();
+        service.restore_product("SKU001").unwrap();
+        assert!(matches!(service.restore_product("SKU999"), Err(ServiceError::NotInTrash { .. })));
+    }
+
+    #[test]
+    fn test_restore_brings_back_transactions() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        service.add_stock("SKU001", 5, None).unwrap();
+        service.remove_stock("SKU001", 2, None).unwrap();
+        service.delete_product("SKU001").unwrap();
+
+        let product = service.restore_product("SKU001").unwrap();
+        assert_eq!(product.quantity, 3);
+        assert_eq!(service.get_transactions("SKU001").len(), 2);
+        assert!(service.trash().unwrap().is_empty());
+
+        let reloaded = create_test_service(&temp_dir);
+        assert_eq!(reloaded.get_transactions("SKU001").len(), 2);
+    }
+
+    fn historical(sku: &str, date: &str, transaction_type: TransactionType, quantity: u32) -> HistoricalEntry {
+        HistoricalEntry {
+            sku: sku.to_string(),
+            timestamp: DateTime::parse_from_rfc3339(&format!("{}T12:00:00Z", date)).unwrap().with_timezone(&Utc),
+            transaction_type,
+            quantity,
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn test_import_transactions_recomputes_quantity_in_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        service.add_stock("SKU001", 4, None).unwrap();
+
+        let entries = vec![
+            historical("SKU001", "2024-01-10", TransactionType::Addition, 20),
+            historical("SKU001", "2024-02-01", TransactionType::Removal, 8),
+        ];
+        service.import_transactions(&entries).unwrap();
+
+        assert_eq!(service.get_product("SKU001").unwrap().quantity, 16);
+        let quantities: Vec<u32> = service.get_transactions("SKU001").iter().map(|t| t.quantity).collect();
+        assert_eq!(quantities, vec![20, 8, 4]);
+        let reloaded = create_test_service(&temp_dir);
+        assert_eq!(reloaded.get_transactions("SKU001")[0].timestamp, entries[0].timestamp);
+    }
+
+    #[test]
+    fn test_import_transactions_rejects_bad_chronology_and_negative_stock() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        service.add_stock("SKU001", 5, None).unwrap();
+        service.remove_stock("SKU001", 5, None).unwrap();
+
+        let mut future = historical("SKU001", "2024-01-01", TransactionType::Addition, 1);
+        future.timestamp = Utc::now() + chrono::Duration::days(1);
+        let entries = vec![
+            historical("SKU001", "2024-03-01", TransactionType::Addition, 2),
+            historical("SKU001", "2024-02-01", TransactionType::Addition, 2),
+            future,
+            historical("SKU002", "2024-01-01", TransactionType::Addition, 1),
+            historical("SKU001", "2024-04-01", TransactionType::Removal, 3),
+        ];
+        let report = service.validate_historical_entries(&entries);
+        let codes: Vec<(usize, &str)> = report.issues().iter().map(|issue| (issue.row, issue.code)).collect();
+        assert_eq!(codes, vec![
+            (2, "INVALID_INPUT"),
+            (3, "INVALID_INPUT"),
+            (4, "PRODUCT_NOT_FOUND"),
+            (5, "INSUFFICIENT_STOCK"),
+        ]);
+
+        // A past removal can't take stock a later recorded removal needed
+        let entries = vec![historical("SKU001", "2024-01-01", TransactionType::Removal, 1)];
+        assert!(matches!(service.import_transactions(&entries), Err(ServiceError::ValidationFailed { .. })));
+        assert_eq!(service.get_transactions("SKU001").len(), 2);
+    }
+
+    #[test]
+    fn test_clock_guards_and_sequence_numbers() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        service.add_stock("SKU001", 5, None).unwrap();
+        service.remove_stock("SKU001", 2, None).unwrap();
+
+        // Transactions recorded in the same instant keep the order they were recorded in
+        let timestamp = service.transactions[0].timestamp;
+        service.transactions[1].timestamp = timestamp;
+        service.transactions.swap(0, 1);
+        service.transactions.sort_by_key(|t| t.order_key());
+        let kinds: Vec<(TransactionType, u64)> = service.transactions.iter().map(|t| (t.transaction_type, t.seq)).collect();
+        assert_eq!(kinds, [(TransactionType::Addition, 1), (TransactionType::Removal, 2)]);
+        assert_eq!(create_test_service(&temp_dir).last_seq, 2);
+
+        // Another machine's clock ran ahead
+        service.transactions[1].timestamp = Utc::now() + chrono::Duration::hours(1);
+        service.persist_transactions().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_stock("SKU001", 1, None).unwrap();
+        assert!(matches!(take_warnings(&mut service).as_slice(), [InventoryEvent::ClockDrift { sku, .. }] if sku == "SKU001"));
+        assert_eq!(service.transactions.last().unwrap().seq, 3);
+        // Only movements that go through are warned about
+        assert!(matches!(service.remove_stock("SKU001", 100, None), Err(ServiceError::InsufficientStock { .. })));
+        assert!(take_warnings(&mut service).is_empty());
+        service.clock = ClockPolicy { tolerance_seconds: 300, action: ClockAction::Reject };
+        assert!(matches!(service.add_stock("SKU001", 1, None), Err(ServiceError::ClockDrift { .. })));
+        service.clock.tolerance_seconds = 7200;
+        service.add_stock("SKU001", 1, None).unwrap();
+
+        // Imported movements dated before the product was added
+        let entries = vec![historical("SKU001", "2024-01-01", TransactionType::Addition, 1)];
+        let report = service.validate_historical_entries(&entries);
+        assert!(report.to_string().contains("is before 'SKU001' was added"), "{}", report);
+        service.clock.action = ClockAction::Warn;
+        service.import_transactions(&entries).unwrap();
+        assert!(matches!(take_warnings(&mut service).as_slice(), [InventoryEvent::BeforeCreation { count: 1, .. }]));
+    }
+
+    #[test]
+    fn test_sequence_numbers_outlive_deleted_transactions() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        service.add_product("SKU002".into(), "Gadget".into(), "".into(), 0, 1).unwrap();
+        service.add_stock("SKU001", 5, None).unwrap();
+        service.add_stock("SKU002", 5, None).unwrap();
+        service.remove_stock("SKU002", 5, None).unwrap();
+        service.delete_product_with_options("SKU002", DeleteOptions { force: true }).unwrap();
+
+        // The deleted product held numbers 2 and 3, so they aren't handed out again
+        let mut service = create_test_service(&temp_dir);
+        service.add_stock("SKU001", 1, None).unwrap();
+        let seqs: Vec<u64> = service.get_transactions("SKU001").iter().map(|t| t.seq).collect();
+        assert_eq!(seqs, [1, 4]);
+    }
+
+    #[test]
+    fn test_migrate_opening_balances() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("OLD".into(), "Existing".into(), "".into(), 0, 1).unwrap();
+        let balance = |sku: &str, quantity| OpeningBalance {
+            sku: sku.to_string(),
+            name: "Migrated".to_string(),
+            quantity,
+            reorder_point: 5,
+        };
+        let cutover = DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z").unwrap().with_timezone(&Utc);
+
+        let invalid = vec![balance("SKU001", 1), balance("OLD", 1), balance("SKU001", 2)];
+        let report = service.validate_opening_balances(&invalid);
+        let codes: Vec<(usize, &str)> = report.issues().iter().map(|issue| (issue.row, issue.code)).collect();
+        assert_eq!(codes, vec![(2, "DUPLICATE_SKU"), (3, "INVALID_INPUT")]);
+        assert!(service.migrate_opening_balances(&invalid, cutover).is_err());
+        assert!(service.get_product("SKU001").is_err());
+
+        let balances = vec![balance("SKU001", 40), balance("SKU002", 0)];
+        let reconciliation = service.migrate_opening_balances(&balances, cutover).unwrap();
+        assert!(reconciliation.is_balanced());
+        assert_eq!((reconciliation.created_products, reconciliation.created_units), (2, 40));
+        assert_eq!(service.get_product("SKU001").unwrap().quantity, 40);
+
+        let transactions = service.get_transactions("SKU001");
+        assert_eq!(transactions.len(), 1);
+        assert_eq!(transactions[0].transaction_type, TransactionType::InitialStock);
+        assert_eq!(transactions[0].timestamp, cutover);
+        assert!(service.get_transactions("SKU002").is_empty());
+
+        // Migrated products date from the cutover, so history recorded after it can still be imported
+        assert_eq!(service.get_product("SKU001").unwrap().created_at, Some(cutover));
+        service.import_transactions(&[historical("SKU001", "2025-02-01", TransactionType::Removal, 5)]).unwrap();
+        assert_eq!(service.get_product("SKU001").unwrap().quantity, 35);
+    }
+
+    #[test]
+    fn test_merge_opening_balances_into_existing_products() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("A".into(), "Widget".into(), "".into(), 10, 0).unwrap();
+        service.add_product("B".into(), "Gadget".into(), "".into(), 3, 4).unwrap();
+        let balance = |sku: &str, name: &str, quantity, reorder_point| OpeningBalance {
+            sku: sku.to_string(),
+            name: name.to_string(),
+            quantity,
+            reorder_point,
+        };
+        let cutover = DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z").unwrap().with_timezone(&Utc);
+        let balances = vec![balance("A", "Widget v2", 6, 5), balance("B", "", 8, 9), balance("C", "New", 2, 1)];
+
+        // Without a merge existing SKUs are refused, and an overwritten name can't be blank
+        assert!(!service.validate_opening_balances(&balances).is_valid());
+        let overwrite = MergePolicy { name: FieldMerge::Overwrite, ..Default::default() };
+        let rows: Vec<usize> = service.validate_opening_merge(&balances, &overwrite).issues().iter().map(|issue| issue.row).collect();
+        assert_eq!(rows, vec![2]);
+
+        let sync = MergePolicy { reorder_point: FieldMerge::FillIfEmpty, quantity: QuantityMerge::Set, ..Default::default() };
+        let reconciliation = service.merge_opening_balances(&balances, cutover, &sync).unwrap();
+        assert!(reconciliation.is_balanced());
+        assert_eq!((reconciliation.created_products, reconciliation.merged_products), (1, 2));
+        let a = service.get_product("A").unwrap();
+        assert_eq!((a.name.as_str(), a.quantity, a.reorder_point), ("Widget", 6, 5));
+        let b = service.get_product("B").unwrap();
+        assert_eq!((b.name.as_str(), b.quantity, b.reorder_point), ("Gadget", 8, 4));
+        assert_eq!(service.get_transactions("A").last().unwrap().transaction_type, TransactionType::Adjustment);
+        assert_eq!(service.get_product("C").unwrap().quantity, 2);
+
+        let refresh = MergePolicy { quantity: QuantityMerge::Add, ..Default::default() };
+        service.merge_opening_balances(&[balance("B", "", 2, 0)], cutover, &refresh).unwrap();
+        assert_eq!(service.get_product("B").unwrap().quantity, 10);
+    }
+
+    #[test]
+    fn test_failed_merge_leaves_quantities_unchanged() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("A".into(), "Widget".into(), "".into(), 10, 0).unwrap();
+        service.add_product("B".into(), "Gadget".into(), "".into(), 0, 0).unwrap();
+        service.add_stock("B", 3, None).unwrap();
+        let balance = |sku: &str, quantity| OpeningBalance { sku: sku.to_string(), name: "New".to_string(), quantity, reorder_point: 0 };
+        let cutover = DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z").unwrap().with_timezone(&Utc);
+
+        // The removal from A goes through, then B's addition is refused for clock drift
+        service.transactions.last_mut().unwrap().timestamp = Utc::now() + chrono::Duration::hours(1);
+        service.latest_timestamps = latest_timestamps(&service.transactions);
+        service.clock = ClockPolicy { tolerance_seconds: 300, action: ClockAction::Reject };
+        let sync = MergePolicy { quantity: QuantityMerge::Set, ..Default::default() };
+        let result = service.merge_opening_balances(&[balance("A", 6), balance("B", 8), balance("C", 1)], cutover, &sync);
+        assert!(matches!(result, Err(ServiceError::ClockDrift { .. })), "{:?}", result);
+
+        let reloaded = create_test_service(&temp_dir);
+        assert_eq!(reloaded.get_product("A").unwrap().quantity, 10);
+        assert_eq!(reloaded.get_product("B").unwrap().quantity, 3);
+        assert!(reloaded.get_product("C").is_err());
+        assert_eq!(service.get_product("A").unwrap().quantity, 10);
+        assert_eq!(service.get_transactions("A").len(), 0);
+    }
+
+    #[test]
+    fn test_diff_since_undoes_later_transactions() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        let since = Utc::now();
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 10, 1).unwrap();
+        service.add_product("SKU002".into(), "Gadget".into(), "".into(), 0, 1).unwrap();
+        service.remove_stock("SKU001", 4, None).unwrap();
+        service.add_stock("SKU002", 2, None).unwrap();
+        service.delete_product("SKU002").unwrap();
+
+        let diff = service.diff_since(since).unwrap();
+        assert!(diff.added.is_empty());
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!((diff.removed[0].sku.as_str(), diff.removed[0].quantity), ("SKU002", 0));
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!((diff.changed[0].before, diff.changed[0].after), (10, 6));
+        assert!(service.diff_since(Utc::now()).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_alert_rules_fire_when_they_start_matching() {
+        let temp_dir = TempDir::new().unwrap();
+        let rules = crate::rules::compile_all(&[crate::rules::AlertRule {
+            name: "perishable-low".into(),
+            when: r#"quantity < 5 AND category == "perishable""#.into(),
+            message: Some("Call the dairy".into()),
+        }]).unwrap();
+        let mut service = create_test_service(&temp_dir).with_alert_rules(rules);
+        service.add_product("MILK".into(), "Milk".into(), "".into(), 8, 2).unwrap();
+        let update = ProductUpdate { category: Some("perishable".into()), ..Default::default() };
+        service.update_product("MILK", update).unwrap();
+
+        let before = service.rule_matches();
+        service.remove_stock("MILK", 4, None).unwrap();
+        service.raise_rule_events(&before);
+        let events = take_warnings(&mut service);
+        assert!(matches!(&events[..], [InventoryEvent::RuleMatched { rule, sku, .. }] if rule == "perishable-low" && sku == "MILK"));
+        assert_eq!(events[0].to_string(), "Rule 'perishable-low' matched 'MILK': Call the dairy");
+
+        // Still matching after the next removal: no repeat
+        let before = service.rule_matches();
+        service.remove_stock("MILK", 1, None).unwrap();
+        service.raise_rule_events(&before);
+        assert!(take_warnings(&mut service).is_empty());
+    }
+
+    #[test]
+    fn test_substitutes_in_stock_follow_product_links() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 5).unwrap();
+        service.add_product("SKU002".into(), "Widget II".into(), "".into(), 3, 5).unwrap();
+        service.add_product("SKU003".into(), "Widget Pro".into(), "".into(), 8, 5).unwrap();
+        service.add_product("SKU004".into(), "Widget Lite".into(), "".into(), 0, 5).unwrap();
+        service.add_product("CASE".into(), "Widget case".into(), "".into(), 9, 5).unwrap();
+        for sku in ["SKU002", "SKU003", "SKU004"] {
+            assert!(service.link_products(sku, LinkKind::SubstituteFor, "SKU001").unwrap());
+        }
+        service.link_products("CASE", LinkKind::AccessoryOf, "SKU001").unwrap();
+        assert!(!service.link_products("SKU002", LinkKind::SubstituteFor, "SKU001").unwrap());
+
+        // Only substitutes with stock, most first; links are one-way
+        let skus = |products: Vec<&Product>| products.iter().map(|p| p.sku.clone()).collect::<Vec<_>>();
+        assert_eq!(skus(service.substitutes_in_stock("SKU001")), ["SKU003", "SKU002"]);
+        assert!(service.substitutes_in_stock("SKU002").is_empty());
+        assert_eq!(skus(service.linked_to("SKU001", LinkKind::AccessoryOf)), ["CASE"]);
+
+        assert!(service.unlink_products("SKU003", LinkKind::SubstituteFor, "SKU001").unwrap());
+        assert!(!service.unlink_products("SKU003", LinkKind::SubstituteFor, "SKU001").unwrap());
+        assert_eq!(skus(service.substitutes_in_stock("SKU001")), ["SKU002"]);
+        assert!(service.link_products("SKU001", LinkKind::SubstituteFor, "SKU001").is_err());
+        assert!(matches!(
+            service.link_products("SKU001", LinkKind::SubstituteFor, "NOPE"),
+            Err(ServiceError::ProductNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_summarize_movements_side_by_side() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 5).unwrap();
+        service.add_product("SKU002".into(), "Gadget".into(), "".into(), 0, 5).unwrap();
+        service.import_transactions(&[
+            historical("SKU001", "2025-01-01", TransactionType::InitialStock, 20),
+            historical("SKU001", "2025-02-01", TransactionType::Removal, 5),
+            historical("SKU002", "2025-01-01", TransactionType::Addition, 10),
+            historical("SKU002", "2025-02-01", TransactionType::WriteOff, 1),
+        ]).unwrap();
+        service.quarantine("SKU001", 2, None).unwrap();
+        service.release_quarantine("SKU001", 2, None).unwrap();
+        let skus = ["SKU002".to_string(), "SKU001".to_string()];
+
+        // Quarantine moves count as movements but not as stock in or out
+        let summaries = service.summarize_movements(&skus, None, None).unwrap();
+        assert_eq!(summaries[0], MovementSummary {
+            sku: "SKU002".into(), received: 10, removed: 0, shrinkage: 1, movements: 2, on_hand: 9,
+        });
+        assert_eq!((summaries[1].received, summaries[1].removed, summaries[1].movements), (20, 5, 4));
+        assert_eq!(summaries[1].net_change(), 15);
+
+        let february = Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap();
+        let summaries = service.summarize_movements(&skus, Some(february), None).unwrap();
+        assert_eq!((summaries[0].net_change(), summaries[1].net_change()), (-1, -5));
+        assert!(service.summarize_movements(&["NOPE".to_string()], None, None).is_err());
+    }
+
+    #[test]
+    fn test_value_history_rebuilds_stock_at_each_boundary() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        service.add_product("SKU002".into(), "Gadget".into(), "".into(), 0, 1).unwrap();
+        let update = ProductUpdate { unit_cost: Some(Some("2.50".parse().unwrap())), ..Default::default() };
+        service.update_product("SKU001", update).unwrap();
+        service.add_stock("SKU001", 10, None).unwrap();
+        service.remove_stock("SKU001", 4, None).unwrap();
+        service.quarantine("SKU001", 1, None).unwrap();
+        service.add_stock("SKU002", 3, None).unwrap();
+
+        // Backdate the movements: +10 in January, -4 and the quarantine in February
+        let storage = JsonStorage::new(temp_dir.path());
+        let mut transactions = storage.load_transactions().unwrap();
+        let date = |month| Utc.with_ymd_and_hms(2025, month, 15, 12, 0, 0).unwrap();
+        for (transaction, month) in transactions.iter_mut().zip([1, 2, 2, 2]) {
+            transaction.timestamp = date(month);
+        }
+        storage.save_transactions(&transactions).unwrap();
+        let service = create_test_service(&temp_dir);
+
+        let start = Utc.with_ymd_and_hms(2025, 1, 10, 0, 0, 0).unwrap();
+        let end = Utc.with_ymd_and_hms(2025, 3, 1, 0, 0, 0).unwrap();
+        let points = service.value_history(Interval::Month, start, end).unwrap();
+        let summary: Vec<(String, u64, String, u64)> = points
+            .iter()
+            .map(|p| (p.at.format("%Y-%m-%d").to_string(), p.units, p.value.to_string(), p.uncosted_units))
+            .collect();
+        assert_eq!(summary, vec![
+            ("2025-01-01".to_string(), 0, "0.00".to_string(), 0),
+            ("2025-02-01".to_string(), 10, "25.00".to_string(), 0),
+            ("2025-03-01".to_string(), 9, "15.00".to_string(), 3),
+        ]);
+    }
+
+    #[test]
+    fn test_suggest_reorder_points_from_removals_since_first_movement() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 5).unwrap();
+        service.add_product("SKU002".into(), "Gadget".into(), "".into(), 0, 5).unwrap();
+        let update = ProductUpdate { lead_time_days: Some(Some(4)), ..Default::default() };
+        service.update_product("SKU001", update).unwrap();
+        service.import_transactions(&[
+            historical("SKU001", "2025-01-01", TransactionType::Addition, 50),
+            historical("SKU001", "2025-01-02", TransactionType::Removal, 4),
+            historical("SKU001", "2025-01-03", TransactionType::Damage, 10),
+            historical("SKU001", "2025-01-04", TransactionType::Removal, 4),
+            historical("SKU002", "2025-01-01", TransactionType::Addition, 50),
+        ]).unwrap();
+        let now = Utc.with_ymd_and_hms(2025, 1, 4, 18, 0, 0).unwrap();
+
+        // Four days of history, [0, 4, 0, 4]: mean 2, σ 2; damage isn't demand
+        let suggestions = service.suggest_reorder_points(0.95, 7, 90, now).unwrap();
+        assert_eq!(suggestions.len(), 1);
+        let suggestion = &suggestions[0];
+        assert_eq!((suggestion.demand.mean, suggestion.demand.std_dev, suggestion.demand.days), (2.0, 2.0, 4));
+        assert_eq!((suggestion.current, suggestion.suggested, suggestion.safety_stock), (5, 15, 7));
+
+        // A shorter window only sees the last removal
+        let suggestions = service.suggest_reorder_points(0.95, 7, 1, now).unwrap();
+        assert_eq!(suggestions[0].suggested, 16);
+        assert!(service.suggest_reorder_points(1.0, 7, 90, now).is_err());
+    }
+
+    #[test]
+    fn test_forecast_stock_outs_with_weekly_seasonality() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 5).unwrap();
+        service.add_product("SKU002".into(), "Gadget".into(), "".into(), 0, 5).unwrap();
+        // Two weeks from Monday 2025-01-06: Widget sells 7 every Saturday, Gadget 1 a day
+        let mut history = vec![
+            historical("SKU001", "2025-01-06", TransactionType::Addition, 40),
+            historical("SKU002", "2025-01-06", TransactionType::Addition, 40),
+            historical("SKU001", "2025-01-11", TransactionType::Removal, 7),
+            historical("SKU001", "2025-01-18", TransactionType::Removal, 7),
+        ];
+        for day in 6..=19 {
+            history.push(historical("SKU002", &format!("2025-01-{:02}", day), TransactionType::Removal, 1));
+        }
+        service.import_transactions(&history).unwrap();
+        service.record_backorder("SKU001", 23, None, None).unwrap();
+        let now = Utc.with_ymd_and_hms(2025, 1, 19, 18, 0, 0).unwrap();
+
+        // Widget has 26 on hand but only 3 free; a flat 1/day uses them up by Wednesday
+        let flat = service.forecast_stock_outs(None, 90, 90, now).unwrap();
+        assert_eq!(flat.iter().map(|f| f.sku.as_str()).collect::<Vec<_>>(), ["SKU001", "SKU002"]);
+        assert_eq!(flat[0].available, 3);
+        assert_eq!(flat[0].stock_out, NaiveDate::from_ymd_opt(2025, 1, 22));
+        assert_eq!(flat[1].stock_out, NaiveDate::from_ymd_opt(2025, 2, 14));
+
+        // With weekly demand, the service default here, nothing sells until Saturday
+        let service = service.with_seasonality(Seasonality::Weekly);
+        let weekly = service.forecast_stock_outs(None, 90, 90, now).unwrap();
+        assert_eq!(weekly[0].forecast.seasonality, Seasonality::Weekly);
+        assert_eq!(weekly[0].stock_out, NaiveDate::from_ymd_opt(2025, 1, 25));
+        let weekly = service.forecast_stock_outs(None, 90, 5, now).unwrap();
+        assert!(weekly.iter().all(|f| f.stock_out.is_none()));
+        assert!(service.forecast_stock_outs(None, 0, 90, now).is_err());
+    }
+
+    #[test]
+    fn test_removals_are_costed_from_the_oldest_receipts() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        let at = |cost: &str| AdditionOptions { unit_cost: Some(cost.parse().unwrap()), ..Default::default() };
+        service.add_stock_with_options("SKU001", 5, None, at("2.00")).unwrap();
+        service.add_stock_with_options("SKU001", 5, None, at("3.00")).unwrap();
+        service.remove_stock("SKU001", 7, None).unwrap();
+        service.remove_stock_with_options("SKU001", 1, None, RemovalOptions {
+            transaction_type: Some(TransactionType::Damage),
+            ..Default::default()
+        }).unwrap();
+
+        let layers = service.current_cost_layers("SKU001").unwrap();
+        let remaining: Vec<(u32, String)> = layers
+            .layers()
+            .map(|layer| (layer.quantity, layer.unit_cost.unwrap().to_string()))
+            .collect();
+        assert_eq!(remaining, vec![(2, "3.00".to_string())]);
+
+        // Five units at 2.00 and two at 3.00; the damaged unit is shrinkage
+        let cogs = service.cost_of_goods(None, None).unwrap();
+        assert_eq!(cogs.len(), 1);
+        assert_eq!((cogs[0].cost.units, cogs[0].cost.value.to_string()), (7, "16.00".to_string()));
+
+        // The layers are rebuilt the same from storage
+        let reloaded = create_test_service(&temp_dir);
+        assert_eq!(reloaded.current_cost_layers("SKU001").unwrap(), layers);
+    }
+
+    #[test]
+    fn test_align_to_external_quantities() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        for (sku, quantity) in [("A", 10), ("B", 5), ("C", 7), ("D", 2)] {
+            service.add_product(sku.into(), sku.into(), "".into(), quantity, 1).unwrap();
+        }
+        let external = |sku: &str, quantity| ExternalQuantity { sku: sku.to_string(), quantity };
+        let quantities = [external("A", 8), external("B", 9), external("C", 7), external("E", 3)];
+        assert!(service.validate_external_quantities(&quantities).is_valid());
+        assert_eq!(
+            service.validate_external_quantities(&[external("A", 1), external("A", 2)]).to_string(),
+            "Line 2: Invalid input: 'A' is already listed on line 1"
+        );
+
+        let discrepancies = service.compare_external(&quantities);
+        let found: Vec<(&str, Option<u32>, Option<u32>)> = discrepancies
+            .iter()
+            .map(|discrepancy| (discrepancy.sku.as_str(), discrepancy.ours, discrepancy.theirs))
+            .collect();
+        assert_eq!(found, [("A", Some(10), Some(8)), ("B", Some(5), Some(9)), ("D", Some(2), None), ("E", None, Some(3))]);
+
+        assert_eq!(service.align_to_external(&discrepancies, "Reconciled with erp.csv").unwrap(), 2);
+        assert_eq!(service.get_product("A").unwrap().quantity, 8);
+        assert_eq!(service.get_product("B").unwrap().quantity, 9);
+        assert_eq!(service.get_product("D").unwrap().quantity, 2);
+        let types: Vec<TransactionType> = service.all_transactions().iter().map(|t| t.transaction_type).collect();
+        assert_eq!(types, [TransactionType::Adjustment, TransactionType::Addition]);
+        assert_eq!(service.compare_external(&quantities).len(), 2);
+    }
+
+    #[test]
+    fn test_compare_prices_uses_each_suppliers_current_price() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        let date = |month| NaiveDate::from_ymd_opt(2025, month, 1).unwrap();
+        let price = |cost: &str, month| PriceEntry {
+            sku: "SKU001".to_string(),
+            unit_cost: cost.parse().unwrap(),
+            effective_from: date(month),
+        };
+        service.import_prices("Acme", &[price("2.00", 1), price("2.60", 6)]).unwrap();
+        service.import_prices("Bolt", &[price("2.40", 1), price("9.99", 3)]).unwrap();
+        // A second list for the same date replaces the price
+        assert_eq!(service.import_prices("Bolt", &[price("2.20", 3)]).unwrap(), 1);
+
+        let summary = |on| -> Vec<(String, String)> {
+            service.compare_prices("SKU001", on).unwrap()
+                .into_iter()
+                .map(|price| (price.supplier, price.unit_cost.to_string()))
+                .collect()
+        };
+        assert_eq!(summary(date(2)), vec![("Acme".into(), "2.00".into()), ("Bolt".into(), "2.40".into())]);
+        assert_eq!(summary(date(7)), vec![("Bolt".into(), "2.20".into()), ("Acme".into(), "2.60".into())]);
+        assert_eq!(service.cheapest_prices(date(7)).unwrap()["SKU001"].supplier, "Bolt");
+
+        let unknown = PriceEntry { sku: "NOPE".to_string(), ..price("1.00", 1) };
+        assert!(!service.validate_price_entries(&[unknown]).is_valid());
+    }
+
+    #[test]
+    fn test_backorders_are_reported_or_filled_oldest_first() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 3, 1).unwrap();
+
+        let first = service.remove_or_backorder("SKU001", 5, Some("Order 1".into()), RemovalOptions::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!((first.quantity, service.get_product("SKU001").unwrap().quantity), (2, 0));
+        service.record_backorder("SKU001", 4, None, None).unwrap();
+
+        // Without allocation, new stock only raises an event
+        service.add_stock("SKU001", 2, None).unwrap();
+        assert!(matches!(
+            take_warnings(&mut service).as_slice(),
+            [InventoryEvent::BackordersWaiting { available: 2, outstanding: 6, .. }]
+        ));
+        assert_eq!(service.committed_quantity("SKU001").unwrap(), 6);
+
+        // The oldest backorder is filled first; the second only in part
+        let mut service = service.with_backorder_allocation(true);
+        service.add_stock("SKU001", 1, None).unwrap();
+        assert_eq!(service.get_product("SKU001").unwrap().quantity, 0);
+        let backorders = service.backorders().unwrap();
+        assert_eq!(backorders.iter().map(|b| b.quantity).collect::<Vec<_>>(), vec![3]);
+        assert_eq!(service.committed_quantity("SKU001").unwrap(), 3);
+        let notes: Vec<Option<&str>> = service.get_transactions("SKU001")
+            .iter()
+            .rev()
+            .take(2)
+            .map(|t| t.notes.as_deref())
+            .collect();
+        assert_eq!(notes, vec![
+            Some(format!("Backorder {}", backorders[0].short_id()).as_str()),
+            Some(format!("Backorder {}: Order 1", first.short_id()).as_str()),
+        ]);
+    }
+
+    #[test]
+    fn test_shipments_group_removals_by_customer() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 20, 1).unwrap();
+        service.add_product("SKU002".into(), "Gadget".into(), "".into(), 20, 1).unwrap();
+        service.add_customer("ACME".into(), "Acme Corporation".into()).unwrap();
+        service.add_customer("BETA".into(), "Beta Ltd".into()).unwrap();
+        assert!(matches!(
+            service.add_customer("ACME".into(), "Again".into()),
+            Err(ServiceError::DuplicateCustomer { .. })
+        ));
+
+        let to = |customer: &str| RemovalOptions { customer: Some(customer.to_string()), ..Default::default() };
+        service.remove_stock_with_options("SKU001", 2, None, to("BETA")).unwrap();
+        service.remove_stock_with_options("SKU001", 3, None, to("ACME")).unwrap();
+        service.remove_stock_with_options("SKU002", 1, None, to("ACME")).unwrap();
+        service.remove_stock_with_options("SKU001", 4, None, to("ACME")).unwrap();
+        service.remove_stock("SKU001", 5, None).unwrap();
+        assert!(matches!(
+            service.remove_stock_with_options("SKU001", 1, None, to("NOBODY")),
+            Err(ServiceError::CustomerNotFound { .. })
+        ));
+        let damage = RemovalOptions { transaction_type: Some(TransactionType::Damage), ..to("ACME") };
+        assert!(service.remove_stock_with_options("SKU001", 1, None, damage).is_err());
+
+        let summary = |lines: Vec<ShipmentLine>| -> Vec<(String, String, usize, u64)> {
+            lines.into_iter().map(|l| (l.customer, l.sku, l.transactions, l.units)).collect()
+        };
+        assert_eq!(summary(service.shipments(None, None, None).unwrap()), vec![
+            ("ACME".to_string(), "SKU001".to_string(), 2, 7),
+            ("ACME".to_string(), "SKU002".to_string(), 1, 1),
+            ("BETA".to_string(), "SKU001".to_string(), 1, 2),
+        ]);
+        assert_eq!(service.shipments(Some("BETA"), None, None).unwrap().len(), 1);
+        assert!(service.shipments(None, Some(Utc::now() + chrono::Duration::days(1)), None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_units_for_weight_takes_off_the_tare() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("BOLT".into(), "Bolt".into(), "".into(), 0, 0).unwrap();
+        let weight = |text: &str| text.parse::<Weight>().unwrap();
+        assert!(service.units_for_weight("BOLT", weight("1kg")).is_err());
+
+        let update = ProductUpdate {
+            tare_weight: Some(Some(weight("250g"))),
+            unit_weight: Some(Some(weight("12.5g"))),
+            ..Default::default()
+        };
+        service.update_product("BOLT", update).unwrap();
+        // 1.506 kg less 250 g is 100.48 units
+        let weighing = service.units_for_weight("BOLT", weight("1.506kg")).unwrap();
+        assert_eq!(weighing.units, 100);
+        assert_eq!(weighing.to_string(), "Weighed 1.506 kg gross, tare 0.250 kg, 12.500 g per unit");
+        assert!(service.units_for_weight("BOLT", weight("0.255kg")).is_err());
+    }
+
+    #[test]
+    fn test_hash_chain_survives_archiving_and_detects_edits() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir).with_hash_chain(true).unwrap();
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        service.add_product("SKU002".into(), "Gadget".into(), "".into(), 0, 1).unwrap();
+        service.add_stock("SKU001", 10, None).unwrap();
+        service.add_stock("SKU002", 5, None).unwrap();
+        service.remove_stock("SKU002", 5, None).unwrap();
+        service.add_stock("SKU001", 2, None).unwrap();
+
+        // History moved to the archive and the trash still counts
+        service.delete_product("SKU002").unwrap();
+        service.archive_transactions(Utc::now() - chrono::Duration::milliseconds(1)).unwrap();
+        let report = service.verify_ledger(None).unwrap();
+        assert!(report.is_intact(), "{:?}", report.problems);
+        assert_eq!(report.chained, 4);
+
+        let storage = JsonStorage::new(temp_dir.path());
+        let mut archived = storage.load_archived_transactions().unwrap();
+        archived[0].quantity = 100;
+        std::fs::remove_dir_all(temp_dir.path().join("archive")).unwrap();
+        storage.archive_transactions(&archived).unwrap();
+        let service = InventoryService::new(Box::new(storage)).unwrap();
+        assert!(!service.verify_ledger(None).unwrap().is_intact());
+    }
+
+    #[test]
+    fn test_retention_redacts_old_notes_and_relinks_the_chain() {
+        let temp_dir = TempDir::new().unwrap();
+        let policy = RetentionPolicy { notes_years: Some(3), customer_years: None };
+        let mut service = create_test_service(&temp_dir)
+            .with_retention_policy(policy)
+            .with_hash_chain(true)
+            .unwrap();
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        let mut entries = vec![
+            historical("SKU001", "2015-03-01", TransactionType::Addition, 10),
+            historical("SKU001", "2015-03-02", TransactionType::Removal, 2),
+        ];
+        entries[0].notes = Some("Delivered to J. Doe, 12 High St".into());
+        service.import_transactions(&entries).unwrap();
+        service.add_stock("SKU001", 1, Some("Recount".into())).unwrap();
+        service.archive_transactions(Utc::now() - chrono::Duration::days(365)).unwrap();
+
+        let dry_run = service.apply_retention(true).unwrap();
+        assert_eq!((dry_run.notes, dry_run.relinked), (1, 3));
+        assert!(service.archived_transactions("SKU001").unwrap()[0].notes.is_some());
+
+        assert_eq!(service.apply_retention(false).unwrap(), dry_run);
+        let archived = service.archived_transactions("SKU001").unwrap();
+        assert!(archived.iter().all(|t| t.notes.is_none()));
+        assert_eq!(service.get_transactions("SKU001")[0].notes.as_deref(), Some("Recount"));
+        assert!(service.verify_ledger(None).unwrap().is_intact());
+
+        // A period longer than the calendar goes back clears nothing
+        let mut service = service.with_retention_policy(RetentionPolicy { notes_years: None, customer_years: Some(u32::MAX) });
+        assert_eq!(service.apply_retention(false).unwrap().customers, 0);
+    }
+
+    #[test]
+    fn test_acknowledged_alerts_leave_low_stock_until_stock_changes() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 5, 10).unwrap();
+        service.add_product("SKU002".into(), "Gadget".into(), "".into(), 50, 10).unwrap();
+        assert!(service.acknowledge_alert("SKU002", None).is_err());
+
+        let today = Utc::now().date_naive();
+        service.acknowledge_alert("SKU001", None).unwrap();
+        assert!(service.list_low_stock().is_empty());
+        assert_eq!(service.snoozed_low_stock(today).len(), 1);
+        // Topping up without clearing the reorder point keeps the snooze
+        service.add_stock("SKU001", 2, None).unwrap();
+        assert!(service.list_low_stock().is_empty());
+
+        // Falling below the acknowledged level ends it for good
+        service.remove_stock("SKU001", 3, None).unwrap();
+        assert_eq!(service.list_low_stock().len(), 1);
+        service.add_stock("SKU001", 1, None).unwrap();
+        assert_eq!(service.get_product("SKU001").unwrap().alert_snooze, None);
+
+        // A snooze with an end date lapses on that day
+        service.acknowledge_alert("SKU001", Some(today + chrono::Duration::days(7))).unwrap();
+        let product = service.get_product("SKU001").unwrap();
+        assert!(product.is_snoozed(today));
+        assert!(!product.is_snoozed(today + chrono::Duration::days(7)));
+
+        // A restock above the reorder point ends it, and it stays saved until then
+        let reloaded = create_test_service(&temp_dir);
+        assert!(reloaded.get_product("SKU001").unwrap().alert_snooze.is_some());
+        service.add_stock("SKU001", 20, None).unwrap();
+        service.remove_stock("SKU001", 20, None).unwrap();
+        assert_eq!(service.list_low_stock().len(), 1);
+        assert!(!service.clear_alert_snooze("SKU001").unwrap());
+    }
+
+    #[test]
+    fn test_snapshot_kpis_keeps_one_snapshot_per_day() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 5).unwrap();
+        let update = ProductUpdate { unit_cost: Some(Some("2.50".parse().unwrap())), ..Default::default() };
+        service.update_product("SKU001", update).unwrap();
+        service.add_stock("SKU001", 4, None).unwrap();
+
+        let now = Utc::now();
+        let first = service.snapshot_kpis(now - chrono::Duration::days(1)).unwrap();
+        assert_eq!(first.movements, 0);
+        let today = service.snapshot_kpis(now).unwrap();
+        assert_eq!((today.units, today.low_stock, today.movements), (4, 1, 1));
+        assert_eq!(today.value, "10.00".parse().unwrap());
+
+        // A later run on the same day replaces that day's snapshot
+        service.add_stock("SKU001", 6, None).unwrap();
+        service.snapshot_kpis(now).unwrap();
+        let snapshots = service.kpi_snapshots(None, None).unwrap();
+        assert_eq!(snapshots.len(), 2);
+        assert_eq!((snapshots[1].units, snapshots[1].low_stock, snapshots[1].movements), (10, 0, 2));
+        assert_eq!(service.kpi_snapshots(Some(now.date_naive()), None).unwrap(), &snapshots[1..]);
+    }
+
+    #[test]
+    fn test_compact_removes_duplicates_and_archives_old_transactions() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 10, 1).unwrap();
+        service.add_stock("SKU001", 5, None).unwrap();
+        service.add_stock("SKU001", 5, None).unwrap();
+
+        // Duplicate the product and its transactions, and backdate one transaction
+        let storage = JsonStorage::new(temp_dir.path());
+        let mut products = storage.load_products().unwrap();
+        products.push(products[0].clone());
+        storage.save_products(&products).unwrap();
+        let mut transactions = storage.load_transactions().unwrap();
+        transactions[0].timestamp = Utc::now() - chrono::Duration::days(800);
+        transactions.extend(transactions.clone());
+        storage.save_transactions(&transactions).unwrap();
+
+        let mut service = create_test_service(&temp_dir);
+        let report = service.compact(Some(Utc::now() - chrono::Duration::days(365))).unwrap();
+        assert_eq!(report.duplicate_products, 1);
+        assert_eq!(report.duplicate_transactions, 2);
+        assert_eq!(report.archived_transactions, 1);
+        assert!(report.reclaimed() > 0);
+        assert_eq!(storage.load_products().unwrap().len(), 1);
+        assert_eq!(storage.load_transactions().unwrap().len(), 1);
+        assert_eq!(service.get_product("SKU001").unwrap().quantity, 20);
+
+        let report = service.compact(None).unwrap();
+        assert_eq!(report, CompactionReport { bytes_before: report.bytes_after, bytes_after: report.bytes_after, ..Default::default() });
+    }
+
+    #[test]
+    fn test_archived_transactions_stay_queryable() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        service.add_stock("SKU001", 10, None).unwrap();
+        service.remove_stock("SKU001", 4, None).unwrap();
+        let cutoff = Utc::now();
+        service.add_stock("SKU001", 1, None).unwrap();
+
+        assert_eq!(service.archive_transactions(cutoff).unwrap(), 2);
+        assert_eq!(service.get_transactions("SKU001").len(), 1);
+        assert_eq!(service.archived_transactions("SKU001").unwrap().len(), 2);
+        assert!(service.archived_transactions("SKU002").unwrap().is_empty());
+
+        let service = create_test_service(&temp_dir);
+        assert_eq!(service.get_transactions("SKU001").len(), 1);
+        let diff = service.diff_since(cutoff - chrono::Duration::days(1)).unwrap();
+        assert_eq!(diff.changed[0].before, 0);
+        assert_eq!(diff.changed[0].after, 7);
+    }
+
+    #[test]
+    fn test_failed_archive_keeps_transactions() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        service.add_stock("SKU001", 10, None).unwrap();
+
+        // Loading takes operations 1 to 3; the archive write is the fourth
+        let storage = FlakyStorage::new(JsonStorage::new(temp_dir.path()), 4);
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+        let err = service.archive_transactions(Utc::now()).unwrap_err();
+        assert_eq!(err.code(), "STORAGE_WRITE");
+        assert_eq!(service.get_transactions("SKU001").len(), 1);
+        assert_eq!(service.archive_transactions(Utc::now()).unwrap(), 1);
+    }
+
+    #[test]
+    fn test_batch_defers_saving_until_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+
+        let mut batch = service.begin_batch();
+        for _ in 0..3 {
+            batch.add_stock("SKU001", 2, None).unwrap();
+        }
+        assert_eq!(batch.get_product("SKU001").unwrap().quantity, 6);
+        assert_eq!(create_test_service(&temp_dir).get_product("SKU001").unwrap().quantity, 0);
+        batch.commit().unwrap();
+        let reloaded = create_test_service(&temp_dir);
+        assert_eq!(reloaded.get_product("SKU001").unwrap().quantity, 6);
+        assert_eq!(reloaded.get_transactions("SKU001").len(), 3);
+
+        // An uncommitted batch is discarded when dropped
+        service.begin_batch().remove_stock("SKU001", 1, None).unwrap();
+        assert_eq!(service.get_product("SKU001").unwrap().quantity, 6);
+        assert_eq!(service.get_transactions("SKU001").len(), 3);
+    }
+
+    #[test]
+    fn test_failed_batch_leaves_files_unchanged() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 5, 1).unwrap();
+        service.add_product("SKU002".into(), "Gadget".into(), "".into(), 1, 1).unwrap();
+        service.add_stock("SKU002", 1, None).unwrap();
+        let products = std::fs::read(temp_dir.path().join("products.json")).unwrap();
+        let transactions = std::fs::read(temp_dir.path().join("transactions.json")).unwrap();
+
+        let removed = (|| -> Result<(), ServiceError> {
+            let mut batch = service.begin_batch();
+            batch.remove_stock("SKU001", 2, None)?;
+            batch.remove_stock("SKU002", 5, None)?;
+            batch.commit()
+        })();
+        assert!(matches!(removed, Err(ServiceError::InsufficientStock { .. })));
+        assert_eq!(std::fs::read(temp_dir.path().join("products.json")).unwrap(), products);
+        assert_eq!(std::fs::read(temp_dir.path().join("transactions.json")).unwrap(), transactions);
+        assert_eq!(service.get_product("SKU001").unwrap().quantity, 5);
+
+        // The service carries on saving as usual afterwards
+        service.remove_stock("SKU001", 1, None).unwrap();
+        assert_eq!(create_test_service(&temp_dir).get_product("SKU001").unwrap().quantity, 4);
+    }
+
+    #[test]
+    fn test_save_modes_hold_back_changes_until_flushed() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir).with_save_mode(SaveMode::Manual);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        service.add_stock("SKU001", 4, None).unwrap();
+        assert!(create_test_service(&temp_dir).get_product("SKU001").is_err());
+        service.flush().unwrap();
+        assert_eq!(create_test_service(&temp_dir).get_product("SKU001").unwrap().quantity, 4);
+
+        // Within the debounce window only the first change is written; the
+        // rest are saved when the service is dropped
+        let mut service = service.with_save_mode(SaveMode::DebounceMs(60_000));
+        service.last_saved = Instant::now() - std::time::Duration::from_secs(120);
+        service.add_stock("SKU001", 1, None).unwrap();
+        service.add_stock("SKU001", 1, None).unwrap();
+        assert_eq!(create_test_service(&temp_dir).get_product("SKU001").unwrap().quantity, 5);
+        drop(service);
+        let reloaded = create_test_service(&temp_dir);
+        assert_eq!(reloaded.get_product("SKU001").unwrap().quantity, 6);
+        assert_eq!(reloaded.get_transactions("SKU001").len(), 3);
+    }
+
+    #[test]
+    fn test_aggregate_products_by_category() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        for (sku, category) in [("A", Some("tools")), ("B", Some("tools")), ("C", None)] {
+            service.add_product(sku.into(), sku.into(), "".into(), 2, 1).unwrap();
+            let update = ProductUpdate { category: category.map(String::from), ..Default::default() };
+            service.update_product(sku, update).unwrap();
+        }
+        let options = AdditionOptions { unit_cost: Some(Money::from_cents(100)), ..Default::default() };
+        service.add_stock_with_options("A", 3, None, options).unwrap();
+
+        let groups = service.aggregate_products(&ProductFilter::default(), GroupBy::Category).unwrap();
+        assert_eq!(groups.len(), 2);
+        assert_eq!((groups[0].key.as_deref(), groups[0].products, groups[0].quantity), (Some("tools"), 2, 7));
+        assert_eq!(groups[0].value, Valuation { units: 7, value: Money::from_cents(300), uncosted_units: 4 });
+        assert_eq!((groups[1].key.as_deref(), groups[1].products, groups[1].quantity), (None, 1, 2));
+    }
+
+    #[test]
+    fn test_update_where_changes_matching_products() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        for sku in ["WID-1", "WID-2", "GAD-1"] {
+            service.add_product(sku.into(), sku.into(), "".into(), 0, 5).unwrap();
+        }
+        let filter = ProductFilter { sku_prefix: Some("WID".to_string()), ..Default::default() };
+        let changes = ProductUpdate { reorder_point: Some(30), location: Some("B-12".to_string()), ..Default::default() };
+        let updated = service.update_where(&filter, changes).unwrap();
+        assert_eq!(updated.iter().map(|p| p.sku.as_str()).collect::<Vec<_>>(), ["WID-1", "WID-2"]);
+
+        let reloaded = create_test_service(&temp_dir);
+        assert_eq!(reloaded.get_product("WID-2").unwrap().reorder_point, 30);
+        assert_eq!(reloaded.get_product("WID-2").unwrap().location.as_deref(), Some("B-12"));
+        assert_eq!(reloaded.get_product("GAD-1").unwrap().reorder_point, 5);
+
+        let rename = ProductUpdate { name: Some("Widget".to_string()), ..Default::default() };
+        assert!(service.update_where(&filter, rename).is_err());
+    }
+
+    #[test]
+    fn test_quota_warning_raised_once_when_approached() {
+        let temp_dir = TempDir::new().unwrap();
+        let quotas = SizeQuotas { max_transactions: Some(4), ..Default::default() };
+        let mut service = create_test_service(&temp_dir).with_size_quotas(quotas);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        for _ in 0..3 {
+            service.add_stock("SKU001", 1, None).unwrap();
+            assert!(take_warnings(&mut service).is_empty());
+        }
+        service.add_stock("SKU001", 1, None).unwrap();
+        service.add_stock("SKU001", 1, None).unwrap();
+        let events = take_warnings(&mut service);
+        assert_eq!(events.len(), 1);
+        assert!(matches!(events[0], InventoryEvent::QuotaApproached { quota: Quota::Transactions, used: 4, limit: 4, .. }));
+        assert_eq!(service.quota_usage().unwrap()[0].percent(), 125);
+    }
+
+    #[test]
+    fn test_heal_ids_reassigns_duplicates() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 0, 1).unwrap();
+        service.add_product("SKU002".into(), "Gadget".into(), "".into(), 0, 1).unwrap();
+        service.add_stock("SKU001", 5, None).unwrap();
+        service.add_stock("SKU002", 5, None).unwrap();
+        let shared = service.products["SKU001"].id.clone();
+        service.products.get_mut("SKU002").unwrap().id = shared.clone();
+        service.transactions[1].id = service.transactions[0].id.clone();
+        let copy = service.transactions[0].clone();
+        service.transactions.push(copy);
+
+        let report = service.heal_ids(true).unwrap();
+        assert_eq!((report.changes.len(), report.copies), (2, 1));
+        assert_eq!(service.products["SKU002"].id, shared);
+
+        let report = service.heal_ids(false).unwrap();
+        assert_eq!(report.changes[0].record, RecordKind::Product);
+        assert_eq!((report.changes[1].record, report.changes[1].sku.as_str()), (RecordKind::Transaction, "SKU002"));
+        let reloaded = create_test_service(&temp_dir);
+        assert_ne!(reloaded.products["SKU002"].id, shared);
+        assert_eq!(reloaded.transactions[1].id, report.changes[1].new_id);
+        let audit = reloaded.storage.load_audit_log().unwrap();
+        assert!(matches!(&audit[..], [InventoryEvent::IdReassigned { .. }, InventoryEvent::IdReassigned { new_id, .. }]
+            if *new_id == report.changes[1].new_id));
+        assert_eq!(service.heal_ids(false).unwrap().changes.len(), 0);
+    }
+
+    #[test]
+    fn test_quarantine_holds_stock_out_of_available() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 30, 10).unwrap();
+
+        service.quarantine("SKU001", 25, Some("Awaiting QC".into())).unwrap();
+        let product = service.get_product("SKU001").unwrap();
+        assert_eq!((product.quantity, product.quarantined, product.on_hand()), (5, 25, 30));
+        assert_eq!(service.list_low_stock().len(), 1);
+        assert!(service.remove_stock("SKU001", 6, None).is_err());
+        assert!(service.quarantine("SKU001", 6, None).is_err());
+        assert!(service.release_quarantine("SKU001", 26, None).is_err());
+        assert!(service.delete_product_with_options("SKU001", DeleteOptions { force: false }).is_err());
+
+        service.release_quarantine("SKU001", 20, None).unwrap();
+        let product = service.get_product("SKU001").unwrap();
+        assert_eq!((product.quantity, product.quarantined), (25, 5));
+        let types: Vec<TransactionType> = service.get_transactions("SKU001").iter().map(|t| t.transaction_type).collect();
+        assert_eq!(types, vec![TransactionType::Quarantine, TransactionType::ReleaseQuarantine]);
+        assert!(service.consumption_by_reason(None, None, None, None).is_empty());
+        let quarantine = RemovalOptions { transaction_type: Some(TransactionType::Quarantine), ..Default::default() };
+        assert!(service.remove_stock_with_options("SKU001", 1, None, quarantine).is_err());
+    }
+
+    #[test]
+    fn test_find_products_by_sku_pattern() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        for sku in ["WID-001", "WID-002", "WIDE-1", "ACME-A-7", "ACME-BB-7", "GAD-001"] {
+            service.add_product(sku.to_string(), sku.to_string(), String::new(), 1, 0).unwrap();
+        }
+        let skus = |pattern| service.find_products(pattern).iter().map(|p| p.sku.as_str()).collect::<Vec<_>>();
+
+        assert_eq!(skus("WID*"), vec!["WID-001", "WID-002", "WIDE-1"]);
+        assert_eq!(skus("WID-*"), vec!["WID-001", "WID-002"]);
+        assert_eq!(skus("ACME-?-*"), vec!["ACME-A-7"]);
+        assert_eq!(skus("*-001"), vec!["GAD-001", "WID-001"]);
+        assert_eq!(skus("WID-001"), vec!["WID-001"]);
+        assert!(skus("wid*").is_empty());
+    }
+
+    #[test]
+    fn test_query_products_filters_and_sorts() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        for (sku, name, quantity) in [("W1", "Zeta", 5), ("W2", "Alpha", 50), ("W3", "Mid", 2), ("G1", "Gadget", 1)] {
+            service.add_product(sku.to_string(), name.to_string(), String::new(), quantity, 10).unwrap();
+            let category = if sku.starts_with('W') { "widgets" } else { "gadgets" };
+            service.update_product(sku, ProductUpdate { category: Some(category.to_string()), ..Default::default() }).unwrap();
+        }
+        let skus = |filter: &ProductFilter| service.query_products(filter).iter().map(|p| p.sku.as_str()).collect::<Vec<_>>();
+
+        let low_widgets = ProductFilter { category: Some("widgets".to_string()), below_reorder: true, ..Default::default() };
+        assert_eq!(skus(&low_widgets), vec!["W1", "W3"]);
+        assert_eq!(skus(&ProductFilter { sort: ProductSort::Quantity, ..low_widgets }), vec!["W3", "W1"]);
+        assert_eq!(skus(&ProductFilter { sku_prefix: Some("W".to_string()), sort: ProductSort::Name, ..Default::default() }), vec!["W2", "W3", "W1"]);
+    }
+
+    #[test]
+    fn test_read_only_service_reads_history_from_storage() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("A".to_string(), "A".to_string(), String::new(), 10, 2).unwrap();
+        service.add_product("B".to_string(), "B".to_string(), String::new(), 10, 2).unwrap();
+        service.add_stock("A", 5, None).unwrap();
+        service.remove_stock("B", 3, None).unwrap();
+
+        let storage = JsonStorage::new(temp_dir.path()).with_memory_map(true);
+        let mut read_only = InventoryService::open_read_only(Box::new(storage)).unwrap();
+        assert_eq!(read_only.get_product("A").unwrap().quantity, 15);
+        assert_eq!(read_only.transactions_for("A").unwrap(), service.transactions_for("A").unwrap());
+        assert_eq!(read_only.transactions_for("A").unwrap().len(), 1);
+
+        assert!(matches!(read_only.add_stock("A", 1, None), Err(ServiceError::ReadOnly)));
+        assert_eq!(create_test_service(&temp_dir).get_transactions("B").len(), 1);
+    }
+
+    #[test]
+    fn test_read_model_follows_every_change() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        crate::testing::seed_demo_data(&mut service).unwrap();
+        // Recomputed in full, with each product's history replayed for its value
+        let in_sync = |service: &InventoryService| {
+            let values: Vec<Valuation> = service
+                .list_products()
+                .into_iter()
+                .map(|p| CostLayers::replay(p, &service.get_transactions(&p.sku), None).valuation())
+                .collect();
+            *service.stats() == ReadModel::build(service.list_products().into_iter().zip(values))
+        };
+        assert!(in_sync(&service));
+
+        let cost = |cents| AdditionOptions { unit_cost: Some(Money::from_cents(cents)), ..Default::default() };
+        let value_before = service.stats().totals().value;
+        service.add_stock_with_options("WIDGET-01", 10, None, cost(250)).unwrap();
+        assert_eq!(service.stats().totals().value.units, value_before.units + 10);
+        assert!(in_sync(&service));
+        service.remove_stock("WIDGET-01", 100, None).unwrap();
+        assert!(in_sync(&service));
+        service.update_product("BOLT-M6", ProductUpdate { category: Some("fasteners".into()), ..Default::default() }).unwrap();
+        service.quarantine("PANEL-01", 20, None).unwrap();
+        service.delete_product("GADGET-02").unwrap();
+        assert!(in_sync(&service));
+        let update = ProductUpdate { unit_cost: Some(Some(Money::from_cents(99))), ..Default::default() };
+        service.update_product("BOLT-M6", update).unwrap();
+        assert!(in_sync(&service));
+        let low: Vec<&str> = service.list_low_stock().iter().map(|p| p.sku.as_str()).collect();
+        assert_eq!(low, vec!["PANEL-01", "SHELF-KIT", "WIDGET-01"]);
+
+        service.restore_product("GADGET-02").unwrap();
+        let reloaded = InventoryService::new(Box::new(JsonStorage::new(temp_dir.path()))).unwrap();
+        assert!(in_sync(&service));
+        assert_eq!(reloaded.stats(), service.stats());
+    }
+
+    #[test]
+    fn test_condition_tracking() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 10, 0).unwrap();
+        service.add_stock_in_condition("SKU001", 4, Condition::Refurbished, Some("Returns".into())).unwrap();
+        service.change_condition("SKU001", 3, Condition::New, Condition::Damaged, None).unwrap();
+
+        let product = service.get_product("SKU001").unwrap();
+        let counts: Vec<u32> = Condition::ALL.iter().map(|&c| product.condition_quantity(c)).collect();
+        assert_eq!((product.quantity, counts), (14, vec![7, 4, 3]));
+        assert!(service.change_condition("SKU001", 5, Condition::Damaged, Condition::New, None).is_err());
+        assert!(service.change_condition("SKU001", 1, Condition::New, Condition::New, None).is_err());
+
+        let refurbished = RemovalOptions { condition: Some(Condition::Refurbished), ..Default::default() };
+        service.remove_stock_with_options("SKU001", 2, None, refurbished.clone()).unwrap();
+        assert!(service.remove_stock_with_options("SKU001", 3, None, refurbished).is_err());
+        let consumption = service.consumption_by_reason(None, None, None, Some(Condition::Refurbished));
+        assert_eq!(consumption[0].units, 2);
+
+        // Removals without a condition take new stock, then refurbished
+        service.remove_stock("SKU001", 8, None).unwrap();
+        let product = service.get_product("SKU001").unwrap();
+        let counts: Vec<u32> = Condition::ALL.iter().map(|&c| product.condition_quantity(c)).collect();
+        assert_eq!((product.quantity, counts), (4, vec![0, 1, 3]));
+        assert_eq!(service.get_transactions("SKU001")[1].from_condition, Some(Condition::New));
+    }
+
+    #[test]
+    fn test_units_on_order_reduce_shortfall_until_received() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 2, 10).unwrap();
+        assert_eq!(service.mark_ordered("SKU001", 5).unwrap(), 5);
+        assert!(service.cancel_ordered("SKU001", 6).is_err());
+        assert_eq!(service.cancel_ordered("SKU001", 1).unwrap(), 4);
+        let product = service.get_product("SKU001").unwrap();
+        assert_eq!((product.shortfall(), product.is_low_stock()), (4, true));
+
+        // Received units come off the order, but no further than zero
+        let mut service = create_test_service(&temp_dir);
+        service.start_receiving(Some("PO-18".into()), &[]).unwrap();
+        service.stage_receipt("SKU001", 3, None).unwrap();
+        service.commit_receiving().unwrap();
+        assert_eq!(create_test_service(&temp_dir).get_product("SKU001").unwrap().on_order, 1);
+        service.start_receiving(None, &[]).unwrap();
+        service.stage_receipt("SKU001", 3, None).unwrap();
+        service.commit_receiving().unwrap();
+        assert_eq!(service.get_product("SKU001").unwrap().on_order, 0);
+
+        // Stock added outside a receipt comes off the order too, but returns don't
+        service.mark_ordered("SKU001", 5).unwrap();
+        service.add_stock_in_condition("SKU001", 2, Condition::Refurbished, None).unwrap();
+        assert_eq!(service.get_product("SKU001").unwrap().on_order, 5);
+        service.add_stock("SKU001", 2, None).unwrap();
+        assert_eq!(create_test_service(&temp_dir).get_product("SKU001").unwrap().on_order, 3);
+        service.apply_stock_entries(
+            &[StockEntry { sku: "SKU001".into(), quantity: 4, notes: None }],
+            TransactionType::Addition,
+            RemovalOptions::default(),
+        ).unwrap();
+        assert_eq!(service.get_product("SKU001").unwrap().on_order, 0);
+    }
+
+    #[test]
+    fn test_receiving_stages_until_commit() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 5, 1).unwrap();
+        service.add_product("SKU002".into(), "Gadget".into(), "".into(), 0, 1).unwrap();
+        service.add_product("SKU003".into(), "Gizmo".into(), "".into(), 0, 1).unwrap();
+        let expected = |sku: &str, quantity| StockEntry { sku: sku.to_string(), quantity, notes: None };
+
+        assert!(service.stage_receipt("SKU001", 1, None).is_err());
+        assert!(service.start_receiving(None, &[expected("SKU404", 1)]).is_err());
+        service.start_receiving(Some("PO-17".into()), &[expected("SKU001", 10), expected("SKU002", 4)]).unwrap();
+        assert!(service.start_receiving(None, &[]).is_err());
+        service.stage_receipt("SKU001", 6, None).unwrap();
+        service.stage_receipt("SKU001", 3, Some("Second carton".into())).unwrap();
+        service.stage_receipt("SKU003", 2, None).unwrap();
+        assert_eq!(service.get_product("SKU001").unwrap().quantity, 5);
+
+        // Staging survives a restart
+        let mut service = create_test_service(&temp_dir);
+        let review = service.review_receiving().unwrap();
+        let variances: Vec<(&str, Option<u32>, u32)> = review.lines
+            .iter()
+            .map(|line| (line.sku.as_str(), line.expected, line.received))
+            .collect();
+        assert_eq!(variances, vec![("SKU001", Some(10), 9), ("SKU002", Some(4), 0), ("SKU003", None, 2)]);
+        assert!(!review.matches_expected());
+
+        let committed = service.commit_receiving().unwrap();
+        assert_eq!(committed.received_units(), 11);
+        assert_eq!(service.get_product("SKU001").unwrap().quantity, 14);
+        let notes: Vec<Option<&str>> = service.get_transactions("SKU001").iter().map(|t| t.notes.as_deref()).collect();
+        assert_eq!(notes, vec![Some("Receipt PO-17"), Some("Second carton")]);
+        assert!(service.receiving().unwrap().is_none());
+        assert!(service.commit_receiving().is_err());
+
+        service.start_receiving(None, &[]).unwrap();
+        service.stage_receipt("SKU002", 1, None).unwrap();
+        assert_eq!(service.cancel_receiving().unwrap().received.len(), 1);
+        assert_eq!(service.get_product("SKU002").unwrap().quantity, 0);
+    }
+
+    #[test]
+    fn test_check_buildable_reports_limiting_component() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("KIT".into(), "Shelf kit".into(), "".into(), 0, 1).unwrap();
+        service.add_product("BOLT".into(), "Bolt".into(), "".into(), 30, 1).unwrap();
+        service.add_product("PANEL".into(), "Panel".into(), "".into(), 5, 1).unwrap();
+        let components = |list: &[(&str, u32)]| ProductUpdate {
+            components: Some(list.iter().map(|(sku, quantity)| Component { sku: sku.to_string(), quantity: *quantity }).collect()),
+            ..Default::default()
+        };
+
+        assert!(service.check_buildable("KIT").is_err());
+        assert!(service.update_product("KIT", components(&[("KIT", 1)])).is_err());
+        assert!(service.update_product("KIT", components(&[("BOLT", 0)])).is_err());
+        assert!(service.update_product("KIT", components(&[("BOLT", 1), ("BOLT", 2)])).is_err());
+        assert!(service.update_product("KIT", components(&[("SCREW", 1)])).is_err());
+        service.update_product("KIT", components(&[("BOLT", 8), ("PANEL", 2)])).unwrap();
+
+        let buildable = service.check_buildable("KIT").unwrap();
+        assert_eq!(buildable.kits, 2);
+        assert_eq!(buildable.limiting().unwrap().sku, "PANEL");
+        service.remove_stock("BOLT", 20, None).unwrap();
+        let buildable = service.check_buildable("KIT").unwrap();
+        assert_eq!(buildable.kits, 1);
+        assert_eq!(buildable.limiting().unwrap().sku, "BOLT");
+    }
+
+    #[test]
+    fn test_shrinkage_by_product_and_type() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU002".into(), "Gadget".into(), "".into(), 50, 1).unwrap();
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 50, 1).unwrap();
+        let loss = |transaction_type| RemovalOptions { transaction_type: Some(transaction_type), ..Default::default() };
+        service.remove_stock_with_options("SKU002", 3, None, loss(TransactionType::Damage)).unwrap();
+        service.remove_stock_with_options("SKU001", 2, None, loss(TransactionType::WriteOff)).unwrap();
+        service.remove_stock_with_options("SKU001", 1, None, loss(TransactionType::Damage)).unwrap();
+        service.remove_stock_with_options("SKU001", 4, None, loss(TransactionType::Damage)).unwrap();
+        service.remove_stock("SKU001", 10, None).unwrap();
+
+        let lines = service.shrinkage(None, None, None);
+        let summary: Vec<(&str, &str, usize, u64)> = lines
+            .iter()
+            .map(|line| (line.sku.as_str(), line.transaction_type.name(), line.transactions, line.units))
+            .collect();
+        assert_eq!(summary, vec![
+            ("SKU001", "damage", 2, 5),
+            ("SKU001", "write-off", 1, 2),
+            ("SKU002", "damage", 1, 3),
+        ]);
+        assert_eq!(service.get_product("SKU001").unwrap().quantity, 33);
+        assert!(service.shrinkage(Some(Utc::now()), None, None).is_empty());
+        assert!(service.remove_stock_with_options("SKU001", 1, None, loss(TransactionType::Addition)).is_err());
+    }
+
+    #[test]
+    fn test_consumption_by_reason() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = create_test_service(&temp_dir);
+        service.add_product("SKU001".into(), "Widget".into(), "".into(), 100, 1).unwrap();
+        let options = |reason| RemovalOptions { reason, ..Default::default() };
+        service.remove_stock_with_options("SKU001", 5, None, options(Some(ReasonCode::Sample))).unwrap();
+        service.remove_stock_with_options("SKU001", 10, None, options(Some(ReasonCode::Sale))).unwrap();
+        service.remove_stock_with_options("SKU001", 2, None, options(None)).unwrap();
+        let damage = RemovalOptions { transaction_type: Some(TransactionType::Damage), ..Default::default() };
+        service.remove_stock_with_options("SKU001", 1, None, damage).unwrap();
+        let entries = [StockEntry { sku: "SKU001".to_string(), quantity: 3, notes: None }];
+        service.apply_stock_entries(&entries, TransactionType::Removal, options(Some(ReasonCode::Sale))).unwrap();
+
+        let totals: Vec<(Option<ReasonCode>, usize, u64)> = service
+            .consumption_by_reason(None, None, None, None)
+            .iter()
+            .map(|total| (total.reason, total.transactions, total.units))
+            .collect();
+        assert_eq!(totals, vec![
+            (Some(ReasonCode::Sale), 2, 13),
+            (Some(ReasonCode::Sample), 1, 5),
+            (Some(ReasonCode::Damage), 1, 1),
+            (None, 1, 2),
+        ]);
+        assert!(service.consumption_by_reason(Some("OTHER"), None, None, None).is_empty());
+
+        let reloaded = create_test_service(&temp_dir);
+        assert_eq!(reloaded.get_transactions("SKU001")[0].reason, Some(ReasonCode::Sample));
     }
 }
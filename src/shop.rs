@@ -0,0 +1,279 @@
+// Stock levels pushed to a Shopify or WooCommerce store
+//
+// The `shop` section of config.json names the platform, the store, and its
+// credentials. A mapping file in the data directory (shop-mapping.csv unless
+// configured) pairs SKUs with the store's IDs, one `SKU,STORE_ID` line each:
+// a Shopify inventory item ID, or a WooCommerce product ID, written
+// `PRODUCT_ID/VARIATION_ID` for a variation. SKUs without a line aren't
+// pushed.
+//
+// After each command, every mapped product whose available quantity changed
+// has its level set in the store; `shop-sync` pushes every mapped product,
+// or with `--dry-run` only shows the requests it would send. Levels are set,
+// never adjusted, so a push that fails can simply be repeated. Talking to
+// the store needs the `shop` feature.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+/// Mapping file read when the configuration doesn't name one
+pub const DEFAULT_MAPPING_FILE: &str = "shop-mapping.csv";
+
+/// Version of the Shopify Admin API the requests are written against
+const SHOPIFY_API_VERSION: &str = "2024-07";
+
+/// E-commerce platforms stock can be pushed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Platform {
+    Shopify,
+    WooCommerce,
+}
+
+impl Platform {
+    /// Name used in the config file
+    pub fn name(self) -> &'static str {
+        match self {
+            Platform::Shopify => "shopify",
+            Platform::WooCommerce => "woocommerce",
+        }
+    }
+}
+
+impl std::fmt::Display for Platform {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Settings for pushing stock levels to an online store
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShopConfig {
+    /// `shopify` or `woocommerce`; nothing is pushed without one
+    pub platform: Option<Platform>,
+    /// Store address, e.g. `https://example.myshopify.com`
+    pub url: Option<String>,
+    /// Shopify Admin API access token
+    pub access_token: Option<String>,
+    /// Shopify location whose levels are set
+    pub location_id: Option<String>,
+    /// WooCommerce REST API consumer key
+    pub consumer_key: Option<String>,
+    /// WooCommerce REST API consumer secret
+    pub consumer_secret: Option<String>,
+    /// `SKU,STORE_ID` mapping file, relative to the data directory
+    pub mapping: Option<String>,
+}
+
+impl ShopConfig {
+    /// Whether a platform and store are set, so stock should be pushed
+    pub fn is_configured(&self) -> bool {
+        self.platform.is_some() && self.url.as_deref().is_some_and(|url| !url.trim().is_empty())
+    }
+
+    /// Path of the mapping file for a data directory
+    pub fn mapping_path(&self, data_dir: &Path) -> std::path::PathBuf {
+        data_dir.join(self.mapping.as_deref().unwrap_or(DEFAULT_MAPPING_FILE))
+    }
+}
+
+/// A stock level to set in the store
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Push {
+    pub sku: String,
+    /// The store's ID for the product
+    pub store_id: String,
+    /// Available units
+    pub quantity: u32,
+}
+
+/// An HTTP request to the store's API, without its credentials
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub method: &'static str,
+    pub url: String,
+    pub body: Value,
+}
+
+impl std::fmt::Display for Request {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} {}", self.method, self.url, self.body)
+    }
+}
+
+/// Read `SKU,STORE_ID` lines into a map, skipping blank lines and `#` comments
+pub fn parse_mapping(text: &str) -> Result<BTreeMap<String, String>, String> {
+    let mut mapping = BTreeMap::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (sku, store_id) = line
+            .split_once(',')
+            .map(|(sku, store_id)| (sku.trim(), store_id.trim()))
+            .filter(|(sku, store_id)| !sku.is_empty() && !store_id.is_empty())
+            .ok_or_else(|| format!("Line {} of the shop mapping: expected SKU,STORE_ID", index + 1))?;
+        if mapping.insert(sku.to_string(), store_id.to_string()).is_some() {
+            return Err(format!("Line {} of the shop mapping: '{}' is mapped twice", index + 1, sku));
+        }
+    }
+    Ok(mapping)
+}
+
+/// Read the configured mapping file
+pub fn load_mapping(config: &ShopConfig, data_dir: &Path) -> Result<BTreeMap<String, String>, String> {
+    let path = config.mapping_path(data_dir);
+    let text = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read the shop mapping {}: {}", path.display(), e))?;
+    parse_mapping(&text)
+}
+
+/// Levels to push for the mapped products whose quantity differs between
+/// `before` and `after` (SKU to available units); with an empty `before`,
+/// every mapped product in `after`
+pub fn pushes(mapping: &BTreeMap<String, String>, before: &BTreeMap<String, u32>, after: &BTreeMap<String, u32>) -> Vec<Push> {
+    after
+        .iter()
+        .filter(|(sku, quantity)| before.is_empty() || before.get(*sku) != Some(quantity))
+        .filter_map(|(sku, &quantity)| {
+            let store_id = mapping.get(sku)?;
+            Some(Push { sku: sku.clone(), store_id: store_id.clone(), quantity })
+        })
+        .collect()
+}
+
+/// The API request that sets one product's level in the store
+pub fn request(config: &ShopConfig, push: &Push) -> Result<Request, String> {
+    let url = config.url.as_deref().unwrap_or_default().trim().trim_end_matches('/');
+    match config.platform {
+        Some(Platform::Shopify) => {
+            let location_id = config
+                .location_id
+                .as_deref()
+                .ok_or("shop.location_id must be set in config.json to push to Shopify")?;
+            Ok(Request {
+                method: "POST",
+                url: format!("{}/admin/api/{}/inventory_levels/set.json", url, SHOPIFY_API_VERSION),
+                body: json!({
+                    "location_id": numeric_id(location_id),
+                    "inventory_item_id": numeric_id(&push.store_id),
+                    "available": push.quantity,
+                }),
+            })
+        }
+        Some(Platform::WooCommerce) => {
+            let path = match push.store_id.split_once('/') {
+                Some((product, variation)) => format!("products/{}/variations/{}", product, variation),
+                None => format!("products/{}", push.store_id),
+            };
+            Ok(Request {
+                method: "PUT",
+                url: format!("{}/wp-json/wc/v3/{}", url, path),
+                body: json!({ "manage_stock": true, "stock_quantity": push.quantity }),
+            })
+        }
+        None => Err("shop.platform must be set in config.json".to_string()),
+    }
+}
+
+/// Push the levels that changed between two snapshots of available
+/// quantities, returning how many were pushed
+pub fn push_changes(
+    config: &ShopConfig,
+    data_dir: &Path,
+    before: &BTreeMap<String, u32>,
+    after: &BTreeMap<String, u32>,
+) -> Result<usize, String> {
+    let changed: BTreeMap<String, u32> =
+        after.iter().filter(|(sku, quantity)| before.get(*sku) != Some(quantity)).map(|(sku, &quantity)| (sku.clone(), quantity)).collect();
+    if changed.is_empty() {
+        return Ok(0);
+    }
+    let pushes = pushes(&load_mapping(config, data_dir)?, &BTreeMap::new(), &changed);
+    let requests = pushes.iter().map(|push| request(config, push)).collect::<Result<Vec<_>, _>>()?;
+    send(config, &requests)?;
+    Ok(requests.len())
+}
+
+/// Send requests to the store, stopping at the first that fails
+#[cfg(feature = "shop")]
+pub fn send(config: &ShopConfig, requests: &[Request]) -> Result<(), String> {
+    for request in requests {
+        log::info!("Pushing to {} store: {}", config.platform.map_or("", Platform::name), request);
+        let mut call = ureq::request(request.method, &request.url);
+        match config.platform {
+            Some(Platform::Shopify) => {
+                let token = config.access_token.as_deref().ok_or("shop.access_token must be set in config.json")?;
+                call = call.set("X-Shopify-Access-Token", token);
+            }
+            Some(Platform::WooCommerce) => {
+                let (Some(key), Some(secret)) = (config.consumer_key.as_deref(), config.consumer_secret.as_deref()) else {
+                    return Err("shop.consumer_key and shop.consumer_secret must be set in config.json".to_string());
+                };
+                call = call.query("consumer_key", key).query("consumer_secret", secret);
+            }
+            None => {}
+        }
+        call.send_json(&request.body)
+            .map_err(|e| format!("Failed to push stock to {} {}: {}", request.method, request.url, e))?;
+    }
+    Ok(())
+}
+
+/// Without the `shop` feature there's no HTTP client to push with
+#[cfg(not(feature = "shop"))]
+pub fn send(_config: &ShopConfig, requests: &[Request]) -> Result<(), String> {
+    if requests.is_empty() {
+        return Ok(());
+    }
+    Err("shop is set in config.json, but this build can't push to a store; rebuild with --features shop".to_string())
+}
+
+/// An ID as a JSON number when it is one, as Shopify expects, otherwise as text
+fn numeric_id(id: &str) -> Value {
+    id.parse::<u64>().map_or_else(|_| json!(id), |id| json!(id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pushes_set_levels_of_mapped_products() {
+        let mapping = parse_mapping("# SKU,STORE_ID\nA,111\nB, 42/7\n").unwrap();
+        assert!(parse_mapping("A,1\nA,2\n").unwrap_err().contains("'A' is mapped twice"));
+        assert!(parse_mapping("A\n").is_err());
+
+        let before = BTreeMap::from([("A".to_string(), 5), ("B".to_string(), 3), ("C".to_string(), 1)]);
+        let after = BTreeMap::from([("A".to_string(), 4), ("B".to_string(), 3), ("C".to_string(), 0)]);
+        let changed = pushes(&mapping, &before, &after);
+        assert_eq!(changed, [Push { sku: "A".to_string(), store_id: "111".to_string(), quantity: 4 }]);
+        assert_eq!(pushes(&mapping, &BTreeMap::new(), &after).len(), 2);
+
+        let mut config = ShopConfig {
+            platform: Some(Platform::Shopify),
+            url: Some("https://example.myshopify.com/".to_string()),
+            ..Default::default()
+        };
+        assert!(request(&config, &changed[0]).unwrap_err().contains("location_id"));
+        config.location_id = Some("900".to_string());
+        assert_eq!(
+            request(&config, &changed[0]).unwrap().to_string(),
+            "POST https://example.myshopify.com/admin/api/2024-07/inventory_levels/set.json \
+             {\"available\":4,\"inventory_item_id\":111,\"location_id\":900}"
+        );
+
+        config.platform = Some(Platform::WooCommerce);
+        let variation = Push { sku: "B".to_string(), store_id: "42/7".to_string(), quantity: 3 };
+        assert_eq!(
+            request(&config, &variation).unwrap().to_string(),
+            "PUT https://example.myshopify.com/wp-json/wc/v3/products/42/variations/7 \
+             {\"manage_stock\":true,\"stock_quantity\":3}"
+        );
+    }
+}
@@ -0,0 +1,195 @@
+// Count sheets for a stocktake
+//
+// `stocktake sheets` lists the products to count on numbered sheets, one
+// location after another and at most SHEET_LINES to a sheet, so each can be
+// handed to a different counter. A sheet shows each product's bin location,
+// barcode, and recorded quantity, or leaves the quantity off for a blind
+// count, and has an empty `counted` column to fill in.
+//
+// The CSV form goes back in with `reconcile sheets.csv --qty-column counted
+// --sheet 3 --apply` once sheet 3 is counted; the other sheets' lines are
+// ignored, so sheets can be entered one at a time from the same file.
+
+use crate::edi::csv_field;
+use crate::models::Product;
+use crate::receipt;
+
+/// Products listed on one sheet at most
+pub const SHEET_LINES: usize = 40;
+
+/// Column of the CSV form holding the sheet number
+pub const SHEET_COLUMN: &str = "sheet";
+
+/// Output format of count sheets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SheetFormat {
+    /// CSV with a `counted` column to fill in, for entering counts
+    #[default]
+    Csv,
+    /// One printable page per sheet
+    Pdf,
+}
+
+impl std::str::FromStr for SheetFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "csv" => Ok(SheetFormat::Csv),
+            "pdf" => Ok(SheetFormat::Pdf),
+            _ => Err(format!("Invalid count sheet format '{}': expected csv or pdf", s)),
+        }
+    }
+}
+
+/// A product to count
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountLine {
+    pub sku: String,
+    pub name: String,
+    pub location: Option<String>,
+    pub barcode: Option<String>,
+    /// Units recorded as available; `None` on a blind count
+    pub expected: Option<u32>,
+}
+
+/// A numbered sheet of products in one location
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountSheet {
+    /// Sheet number, from 1
+    pub number: usize,
+    /// Location of every product on the sheet; `None` for products without one
+    pub location: Option<String>,
+    pub lines: Vec<CountLine>,
+}
+
+/// Split products into sheets by location, in location then SKU order,
+/// products without a location last
+pub fn build(products: &[&Product], blind: bool) -> Vec<CountSheet> {
+    let mut products = products.to_vec();
+    products.sort_by(|a, b| {
+        (a.location.is_none(), &a.location, &a.sku).cmp(&(b.location.is_none(), &b.location, &b.sku))
+    });
+    let mut sheets: Vec<CountSheet> = Vec::new();
+    for product in products {
+        let line = CountLine {
+            sku: product.sku.clone(),
+            name: product.name.clone(),
+            location: product.location.clone(),
+            barcode: product.barcode.clone(),
+            expected: (!blind).then_some(product.quantity),
+        };
+        match sheets.last_mut() {
+            Some(sheet) if sheet.location == line.location && sheet.lines.len() < SHEET_LINES => sheet.lines.push(line),
+            _ => sheets.push(CountSheet { number: sheets.len() + 1, location: line.location.clone(), lines: vec![line] }),
+        }
+    }
+    sheets
+}
+
+/// The sheets as one CSV, with an `expected` column unless the count is blind
+pub fn to_csv(sheets: &[CountSheet], blind: bool) -> String {
+    let mut csv = if blind {
+        format!("{},line,location,sku,name,barcode,counted\n", SHEET_COLUMN)
+    } else {
+        format!("{},line,location,sku,name,barcode,expected,counted\n", SHEET_COLUMN)
+    };
+    for sheet in sheets {
+        for (index, line) in sheet.lines.iter().enumerate() {
+            let mut fields = vec![
+                sheet.number.to_string(),
+                (index + 1).to_string(),
+                csv_field(line.location.as_deref().unwrap_or_default()),
+                csv_field(&line.sku),
+                csv_field(&line.name),
+                csv_field(line.barcode.as_deref().unwrap_or_default()),
+            ];
+            if !blind {
+                fields.push(line.expected.map(|expected| expected.to_string()).unwrap_or_default());
+            }
+            fields.push(String::new());
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+    }
+    csv
+}
+
+/// The sheets as a PDF, one page each, with room to write the counts and sign
+pub fn to_pdf(sheets: &[CountSheet], blind: bool) -> Vec<u8> {
+    let pages: Vec<Vec<String>> = sheets
+        .iter()
+        .map(|sheet| {
+            let title = format!("COUNT SHEET {} OF {}{}", sheet.number, sheets.len(), if blind { " (BLIND)" } else { "" });
+            let mut lines = vec![
+                title.clone(),
+                "=".repeat(title.len()),
+                String::new(),
+                format!("Location: {}", sheet.location.as_deref().unwrap_or("(none)")),
+                String::new(),
+                format!("{:<3} {:<10} {:<14} {:<22} {:<14} {:>8}  {}", "#", "Bin", "SKU", "Name", "Barcode", "Expected", "Counted"),
+            ];
+            for (index, line) in sheet.lines.iter().enumerate() {
+                let expected = line.expected.map_or_else(|| "-".to_string(), |expected| expected.to_string());
+                lines.push(format!(
+                    "{:<3} {:<10} {:<14} {:<22} {:<14} {:>8}  ________",
+                    index + 1,
+                    truncate(line.location.as_deref().unwrap_or_default(), 10),
+                    truncate(&line.sku, 14),
+                    truncate(&line.name, 22),
+                    truncate(line.barcode.as_deref().unwrap_or_default(), 14),
+                    expected,
+                ));
+            }
+            lines.push(String::new());
+            lines.push(String::new());
+            lines.push("Counted by: ______________________   Date: ____________".to_string());
+            lines
+        })
+        .collect();
+    receipt::pdf_document(&pages)
+}
+
+/// At most `width` characters of a column value
+fn truncate(text: &str, width: usize) -> String {
+    text.chars().take(width).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn product(sku: &str, location: Option<&str>, quantity: u32) -> Product {
+        Product {
+            sku: sku.to_string(),
+            name: format!("Part {}", sku),
+            location: location.map(String::from),
+            barcode: Some(format!("0000{}", sku)),
+            quantity,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_sheets_split_by_location_and_length() {
+        let mut products = vec![product("Z1", None, 1), product("B1", Some("B"), 2)];
+        products.extend((0..SHEET_LINES + 1).map(|n| product(&format!("A{:02}", n), Some("A"), 3)));
+        let products: Vec<&Product> = products.iter().collect();
+
+        let sheets = build(&products, false);
+        let summary: Vec<_> = sheets.iter().map(|sheet| (sheet.number, sheet.location.as_deref(), sheet.lines.len())).collect();
+        assert_eq!(summary, vec![(1, Some("A"), SHEET_LINES), (2, Some("A"), 1), (3, Some("B"), 1), (4, None, 1)]);
+
+        let csv = to_csv(&sheets, false);
+        assert!(csv.starts_with("sheet,line,location,sku,name,barcode,expected,counted\n1,1,A,A00,Part A00,0000A00,3,\n"));
+        assert!(csv.ends_with("4,1,,Z1,Part Z1,0000Z1,1,\n"));
+
+        let blind = build(&products, true);
+        assert!(blind.iter().flat_map(|sheet| &sheet.lines).all(|line| line.expected.is_none()));
+        assert!(to_csv(&blind, true).starts_with("sheet,line,location,sku,name,barcode,counted\n1,1,A,A00,Part A00,0000A00,\n"));
+
+        let pdf = String::from_utf8(to_pdf(&blind, true)).unwrap();
+        assert!(pdf.contains("/Count 4"));
+        assert!(pdf.contains("(COUNT SHEET 3 OF 4 \\(BLIND\\)) Tj"));
+    }
+}
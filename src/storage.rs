@@ -1,14 +1,26 @@
 // Storage layer for persistence
 
-use std::fs;
-use std::io;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::marker::PhantomData;
 use std::path::{Path, PathBuf};
 
+use memmap2::Mmap;
+use serde::de::{Deserialize, SeqAccess, Visitor};
+
 use crate::errors::StorageError;
-use crate::models::{Product, Transaction};
+use crate::events::InventoryEvent;
+use crate::models::{
+    Backorder, Customer, KpiSnapshot, LedgerHead, Product, ReceivingSession, SupplierPrice, Tombstone, Transaction,
+};
 
 /// Trait defining storage operations for products and transactions
-pub trait Storage {
+///
+/// Implementations must be shareable across threads: products and
+/// transactions are loaded concurrently at startup.
+pub trait Storage: Send + Sync {
     /// Save products to persistent storage
     fn save_products(&self, products: &[Product]) -> Result<(), StorageError>;
     
@@ -20,6 +32,84 @@ pub trait Storage {
     
     /// Load transactions from persistent storage
     fn load_transactions(&self) -> Result<Vec<Transaction>, StorageError>;
+
+    /// Load the transactions of one product, in stored order
+    ///
+    /// Backends that can skip the other records while reading should override this.
+    fn load_transactions_for(&self, sku: &str) -> Result<Vec<Transaction>, StorageError> {
+        let mut transactions = self.load_transactions()?;
+        transactions.retain(|t| t.product_sku == sku);
+        Ok(transactions)
+    }
+
+    /// Append an event to the audit log
+    fn append_audit_event(&self, event: &InventoryEvent) -> Result<(), StorageError>;
+
+    /// Load all events from the audit log, oldest first
+    fn load_audit_log(&self) -> Result<Vec<InventoryEvent>, StorageError>;
+
+    /// Append a deleted product and its history to the tombstone file
+    fn append_tombstone(&self, tombstone: &Tombstone) -> Result<(), StorageError>;
+
+    /// Load all tombstones, oldest first
+    fn load_tombstones(&self) -> Result<Vec<Tombstone>, StorageError>;
+
+    /// Replace the tombstone file, e.g. after a restore or purge
+    fn save_tombstones(&self, tombstones: &[Tombstone]) -> Result<(), StorageError>;
+
+    /// Append transactions to cold storage, outside the data loaded at startup
+    fn archive_transactions(&self, transactions: &[Transaction]) -> Result<(), StorageError>;
+
+    /// Load all archived transactions, oldest archive first
+    fn load_archived_transactions(&self) -> Result<Vec<Transaction>, StorageError>;
+
+    /// Replace the whole archive, e.g. after redacting old records
+    fn save_archived_transactions(&self, transactions: &[Transaction]) -> Result<(), StorageError>;
+
+    /// Total size in bytes of the data loaded at startup (archives excluded)
+    fn data_size(&self) -> Result<u64, StorageError>;
+
+    /// Load the receipt being staged, if any
+    fn load_receiving(&self) -> Result<Option<ReceivingSession>, StorageError>;
+
+    /// Save the receipt being staged, or clear it with `None`
+    fn save_receiving(&self, session: Option<&ReceivingSession>) -> Result<(), StorageError>;
+
+    /// Load every supplier price, current and past
+    fn load_prices(&self) -> Result<Vec<SupplierPrice>, StorageError>;
+
+    /// Save all supplier prices, replacing the stored ones
+    fn save_prices(&self, prices: &[SupplierPrice]) -> Result<(), StorageError>;
+
+    /// Load outstanding backorders, oldest first
+    fn load_backorders(&self) -> Result<Vec<Backorder>, StorageError>;
+
+    /// Save outstanding backorders, replacing the stored ones
+    fn save_backorders(&self, backorders: &[Backorder]) -> Result<(), StorageError>;
+
+    /// Load all customers
+    fn load_customers(&self) -> Result<Vec<Customer>, StorageError>;
+
+    /// Save all customers, replacing the stored ones
+    fn save_customers(&self, customers: &[Customer]) -> Result<(), StorageError>;
+
+    /// Load the head of the transaction hash chain, if one was saved
+    fn load_ledger_head(&self) -> Result<Option<LedgerHead>, StorageError>;
+
+    /// Save the head of the transaction hash chain
+    fn save_ledger_head(&self, head: &LedgerHead) -> Result<(), StorageError>;
+
+    /// Load the highest transaction sequence number handed out, if one was saved
+    fn load_last_seq(&self) -> Result<Option<u64>, StorageError>;
+
+    /// Save the highest transaction sequence number handed out
+    fn save_last_seq(&self, seq: u64) -> Result<(), StorageError>;
+
+    /// Load the daily KPI snapshots, oldest first
+    fn load_kpi_snapshots(&self) -> Result<Vec<KpiSnapshot>, StorageError>;
+
+    /// Save the daily KPI snapshots, replacing the stored ones
+    fn save_kpi_snapshots(&self, snapshots: &[KpiSnapshot]) -> Result<(), StorageError>;
 }
 
 /// JSON file-based storage implementation
@@ -28,6 +118,28 @@ pub struct JsonStorage {
     products_path: PathBuf,
     /// Path to the transactions JSON file
     transactions_path: PathBuf,
+    /// Path to the append-only audit log (one JSON event per line)
+    audit_path: PathBuf,
+    /// Path to the append-only tombstone file of deleted products
+    tombstone_path: PathBuf,
+    /// Directory of archived transactions, one NDJSON file per year
+    archive_dir: PathBuf,
+    /// Path to the receipt being staged by `receive`
+    receiving_path: PathBuf,
+    /// Path to the supplier price lists
+    prices_path: PathBuf,
+    /// Path to the outstanding backorders
+    backorders_path: PathBuf,
+    /// Path to the customer list
+    customers_path: PathBuf,
+    /// Path to the head of the transaction hash chain
+    ledger_head_path: PathBuf,
+    /// Path to the highest transaction sequence number handed out
+    sequence_path: PathBuf,
+    /// Path to the daily KPI snapshots (one JSON record per line)
+    kpi_path: PathBuf,
+    /// Read JSON files through a memory map instead of copying them into memory
+    memory_map: bool,
 }
 
 impl JsonStorage {
@@ -35,28 +147,146 @@ impl JsonStorage {
     /// 
     /// Products will be stored in `{dir}/products.json`
     /// Transactions will be stored in `{dir}/transactions.json`
+    /// Audit events will be appended to `{dir}/audit.ndjson`
+    /// Deleted products will be appended to `{dir}/deleted.ndjson`
+    /// Archived transactions go to `{dir}/archive/transactions-{year}.ndjson`
+    /// A receipt being staged is kept in `{dir}/receiving.json`
+    /// Supplier prices are stored in `{dir}/prices.json`
+    /// Outstanding backorders are stored in `{dir}/backorders.json`
+    /// Customers are stored in `{dir}/customers.json`
+    /// The transaction hash chain head is stored in `{dir}/ledger-head.json`
+    /// The highest transaction sequence number is stored in `{dir}/sequence.json`
+    /// Daily KPI snapshots are stored in `{dir}/kpis.ndjson`
     pub fn new<P: AsRef<Path>>(dir: P) -> Self {
         let dir = dir.as_ref();
         JsonStorage {
             products_path: dir.join("products.json"),
             transactions_path: dir.join("transactions.json"),
+            audit_path: dir.join("audit.ndjson"),
+            tombstone_path: dir.join("deleted.ndjson"),
+            archive_dir: dir.join("archive"),
+            receiving_path: dir.join("receiving.json"),
+            prices_path: dir.join("prices.json"),
+            backorders_path: dir.join("backorders.json"),
+            customers_path: dir.join("customers.json"),
+            ledger_head_path: dir.join("ledger-head.json"),
+            sequence_path: dir.join("sequence.json"),
+            kpi_path: dir.join("kpis.ndjson"),
+            memory_map: false,
         }
     }
     
     /// Create a new JsonStorage with explicit file paths
+    ///
+    /// Auxiliary files such as the audit log are kept next to the products file.
     pub fn with_paths<P1: AsRef<Path>, P2: AsRef<Path>>(
         products_path: P1,
         transactions_path: P2,
     ) -> Self {
+        let products_path = products_path.as_ref().to_path_buf();
+        let dir = products_path.parent().map(Path::to_path_buf).unwrap_or_default();
         JsonStorage {
-            products_path: products_path.as_ref().to_path_buf(),
+            products_path,
             transactions_path: transactions_path.as_ref().to_path_buf(),
+            audit_path: dir.join("audit.ndjson"),
+            tombstone_path: dir.join("deleted.ndjson"),
+            archive_dir: dir.join("archive"),
+            receiving_path: dir.join("receiving.json"),
+            prices_path: dir.join("prices.json"),
+            backorders_path: dir.join("backorders.json"),
+            customers_path: dir.join("customers.json"),
+            ledger_head_path: dir.join("ledger-head.json"),
+            sequence_path: dir.join("sequence.json"),
+            kpi_path: dir.join("kpis.ndjson"),
+            memory_map: false,
         }
     }
 
-    
+
+    /// Read JSON files through a memory map
+    ///
+    /// The file is parsed straight from the mapped pages rather than a copy in
+    /// memory, and `load_transactions_for` keeps only the matching records as
+    /// it goes, so looking up one product in a huge ledger stays small. The
+    /// files must not be rewritten by another process while they are mapped.
+    pub fn with_memory_map(mut self, enabled: bool) -> Self {
+        self.memory_map = enabled;
+        self
+    }
+
+    /// Paths of the yearly archive files, oldest year first
+    fn archive_paths(&self) -> Result<Vec<PathBuf>, StorageError> {
+        let entries = match fs::read_dir(&self.archive_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(source) => return Err(StorageError::Read { path: self.archive_dir.clone(), source }),
+        };
+        let mut paths = Vec::new();
+        for entry in entries {
+            let entry = entry.map_err(|source| StorageError::Read { path: self.archive_dir.clone(), source })?;
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if name.starts_with("transactions-") && name.ends_with(".ndjson") {
+                paths.push(entry.path());
+            }
+        }
+        paths.sort();
+        Ok(paths)
+    }
+
+    /// Map a file into memory, returning `None` if it doesn't exist or is empty
+    fn map_file(&self, path: &Path) -> Result<Option<Mmap>, StorageError> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => {
+                log::error!("Failed to read {}: {}", path.display(), source);
+                return Err(StorageError::Read { path: path.to_path_buf(), source });
+            }
+        };
+        let read_error = |source| StorageError::Read { path: path.to_path_buf(), source };
+        if file.metadata().map_err(read_error)?.len() == 0 {
+            return Ok(None);
+        }
+        // SAFETY: the map is only read, and nothing modifies a data file in
+        // place: `write_json_file` writes a new file and renames it over the
+        // old one, so another process saving while this one reads replaces
+        // the directory entry and leaves the mapped file's contents as they
+        // were. Editing a data file in place by hand while a command runs is
+        // not supported.
+        let map = unsafe { Mmap::map(&file) }.map_err(read_error)?;
+        log::trace!("Mapped {} bytes of {}", map.len(), path.display());
+        Ok(Some(map))
+    }
+
+    /// Parse a JSON array from mapped bytes, keeping the elements `keep` accepts
+    fn parse_mapped<T, F>(&self, path: &Path, bytes: &[u8], keep: F) -> Result<Vec<T>, StorageError>
+    where
+        T: serde::de::DeserializeOwned,
+        F: FnMut(&T) -> bool,
+    {
+        let parse_error = |source| {
+            log::error!("Failed to parse {}: {}", path.display(), source);
+            StorageError::Parse { path: path.to_path_buf(), line: None, source }
+        };
+        if bytes.iter().all(u8::is_ascii_whitespace) {
+            return Ok(Vec::new());
+        }
+        let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+        let records = serde::Deserializer::deserialize_seq(&mut deserializer, FilteredSeq { keep, marker: PhantomData })
+            .map_err(parse_error)?;
+        deserializer.end().map_err(parse_error)?;
+        Ok(records)
+    }
+
     /// Read JSON data from a file, returning empty vector if file doesn't exist
     fn read_json_file<T: serde::de::DeserializeOwned>(&self, path: &Path) -> Result<Vec<T>, StorageError> {
+        if self.memory_map {
+            return match self.map_file(path)? {
+                Some(map) => self.parse_mapped(path, &map, |_| true),
+                None => Ok(Vec::new()),
+            };
+        }
         match fs::read_to_string(path) {
             Ok(contents) => {
                 // Handle empty files as empty vectors
@@ -64,45 +294,163 @@ impl JsonStorage {
                     return Ok(Vec::new());
                 }
                 
-                serde_json::from_str(&contents).map_err(|e| {
-                    StorageError::ParseError(format!("Failed to parse {}: {}", path.display(), e))
+                log::trace!("Read {} bytes from {}", contents.len(), path.display());
+                serde_json::from_str(&contents).map_err(|source| {
+                    log::error!("Failed to parse {}: {}", path.display(), source);
+                    StorageError::Parse { path: path.to_path_buf(), line: None, source }
                 })
             }
             Err(e) if e.kind() == io::ErrorKind::NotFound => {
                 // Missing files return empty vectors (Requirements 8.3)
                 Ok(Vec::new())
             }
-            Err(e) => Err(StorageError::ReadError(format!(
-                "Failed to read {}: {}",
-                path.display(),
-                e
-            ))),
+            Err(source) => {
+                log::error!("Failed to read {}: {}", path.display(), source);
+                Err(StorageError::Read { path: path.to_path_buf(), source })
+            }
         }
     }
     
     /// Write JSON data to a file
     fn write_json_file<T: serde::Serialize>(&self, path: &Path, data: &[T]) -> Result<(), StorageError> {
-        // Ensure parent directory exists
-        if let Some(parent) = path.parent() {
-            if !parent.exists() {
-                fs::create_dir_all(parent).map_err(|e| {
-                    StorageError::WriteError(format!(
-                        "Failed to create directory {}: {}",
-                        parent.display(),
-                        e
-                    ))
-                })?;
-            }
-        }
+        ensure_parent_dir(path)?;
         
-        let json = serde_json::to_string_pretty(data).map_err(|e| {
-            StorageError::WriteError(format!("Failed to serialize data: {}", e))
-        })?;
+        let json = serde_json::to_string_pretty(data)
+            .map_err(|source| StorageError::Serialize { path: path.to_path_buf(), source })?;
         
-        fs::write(path, json).map_err(|e| {
-            StorageError::WriteError(format!("Failed to write {}: {}", path.display(), e))
+        log::debug!("Writing {} records to {}", data.len(), path.display());
+        replace_file(path, json.as_bytes()).map_err(|source| {
+            log::error!("Failed to write {}: {}", path.display(), source);
+            StorageError::Write { path: path.to_path_buf(), source }
         })
     }
+
+    /// Read newline-delimited JSON records, returning empty vector if file doesn't exist
+    fn read_ndjson_file<T: serde::de::DeserializeOwned>(&self, path: &Path) -> Result<Vec<T>, StorageError> {
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(source) => return Err(StorageError::Read { path: path.to_path_buf(), source }),
+        };
+
+        contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|source| StorageError::Parse {
+                    path: path.to_path_buf(),
+                    line: Some(index + 1),
+                    source,
+                })
+            })
+            .collect()
+    }
+
+    /// Write records as newline-delimited JSON, replacing the file
+    fn write_ndjson_file<T: serde::Serialize>(&self, path: &Path, records: &[T]) -> Result<(), StorageError> {
+        let mut contents = String::new();
+        for record in records {
+            let line = serde_json::to_string(record)
+                .map_err(|source| StorageError::Serialize { path: path.to_path_buf(), source })?;
+            contents.push_str(&line);
+            contents.push('\n');
+        }
+
+        ensure_parent_dir(path)?;
+        log::debug!("Writing {} records to {}", records.len(), path.display());
+        replace_file(path, contents.as_bytes()).map_err(|source| {
+            log::error!("Failed to write {}: {}", path.display(), source);
+            StorageError::Write { path: path.to_path_buf(), source }
+        })
+    }
+
+    /// Append a single JSON record as a new line, creating the file if needed
+    fn append_ndjson_record<T: serde::Serialize>(&self, path: &Path, record: &T) -> Result<(), StorageError> {
+        self.append_ndjson_records(path, std::slice::from_ref(record))
+    }
+
+    /// Append JSON records, one per line, creating the file if needed
+    fn append_ndjson_records<T: serde::Serialize>(&self, path: &Path, records: &[T]) -> Result<(), StorageError> {
+        let mut lines = String::new();
+        for record in records {
+            let line = serde_json::to_string(record)
+                .map_err(|source| StorageError::Serialize { path: path.to_path_buf(), source })?;
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+
+        ensure_parent_dir(path)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|source| StorageError::Write { path: path.to_path_buf(), source })?;
+        log::debug!("Appending {} records to {}", records.len(), path.display());
+        file.write_all(lines.as_bytes()).map_err(|source| {
+            log::error!("Failed to write {}: {}", path.display(), source);
+            StorageError::Write { path: path.to_path_buf(), source }
+        })
+    }
+}
+
+/// Visitor that keeps the elements of a JSON array matching a predicate
+///
+/// Each element is dropped as soon as it is rejected, so only the kept ones
+/// are held in memory.
+struct FilteredSeq<T, F> {
+    keep: F,
+    marker: PhantomData<T>,
+}
+
+impl<'de, T, F> Visitor<'de> for FilteredSeq<T, F>
+where
+    T: Deserialize<'de>,
+    F: FnMut(&T) -> bool,
+{
+    type Value = Vec<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(mut self, mut seq: A) -> Result<Vec<T>, A::Error> {
+        let mut kept = Vec::new();
+        while let Some(item) = seq.next_element::<T>()? {
+            if (self.keep)(&item) {
+                kept.push(item);
+            }
+        }
+        Ok(kept)
+    }
+}
+
+/// Replace the contents of `path` atomically
+///
+/// The data goes to a temporary file beside `path`, which is then renamed
+/// over it, so readers see either the old file or the new one in full and a
+/// file mapped by another process is never truncated under it.
+fn replace_file(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+    let temp_path = path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()));
+    let written = File::create(&temp_path)
+        .and_then(|mut file| file.write_all(contents).and_then(|()| file.sync_all()))
+        .and_then(|()| fs::rename(&temp_path, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    written
+}
+
+/// Create the parent directory of `path` if it doesn't exist yet
+fn ensure_parent_dir(path: &Path) -> Result<(), StorageError> {
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() && !parent.exists() {
+            fs::create_dir_all(parent)
+                .map_err(|source| StorageError::CreateDir { path: parent.to_path_buf(), source })?;
+        }
+    }
+    Ok(())
 }
 
 impl Storage for JsonStorage {
@@ -121,6 +469,186 @@ impl Storage for JsonStorage {
     fn load_transactions(&self) -> Result<Vec<Transaction>, StorageError> {
         self.read_json_file(&self.transactions_path)
     }
+
+    fn load_transactions_for(&self, sku: &str) -> Result<Vec<Transaction>, StorageError> {
+        if !self.memory_map {
+            let mut transactions: Vec<Transaction> = self.read_json_file(&self.transactions_path)?;
+            transactions.retain(|t| t.product_sku == sku);
+            return Ok(transactions);
+        }
+        match self.map_file(&self.transactions_path)? {
+            Some(map) => self.parse_mapped(&self.transactions_path, &map, |t: &Transaction| t.product_sku == sku),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn append_audit_event(&self, event: &InventoryEvent) -> Result<(), StorageError> {
+        self.append_ndjson_record(&self.audit_path, event)
+    }
+
+    fn load_audit_log(&self) -> Result<Vec<InventoryEvent>, StorageError> {
+        self.read_ndjson_file(&self.audit_path)
+    }
+
+    fn append_tombstone(&self, tombstone: &Tombstone) -> Result<(), StorageError> {
+        self.append_ndjson_record(&self.tombstone_path, tombstone)
+    }
+
+    fn load_tombstones(&self) -> Result<Vec<Tombstone>, StorageError> {
+        self.read_ndjson_file(&self.tombstone_path)
+    }
+
+    fn save_tombstones(&self, tombstones: &[Tombstone]) -> Result<(), StorageError> {
+        self.write_ndjson_file(&self.tombstone_path, tombstones)
+    }
+
+    fn archive_transactions(&self, transactions: &[Transaction]) -> Result<(), StorageError> {
+        let mut by_year: BTreeMap<String, Vec<&Transaction>> = BTreeMap::new();
+        for transaction in transactions {
+            by_year.entry(transaction.timestamp.format("%Y").to_string()).or_default().push(transaction);
+        }
+        for (year, transactions) in by_year {
+            let path = self.archive_dir.join(format!("transactions-{}.ndjson", year));
+            self.append_ndjson_records(&path, &transactions)?;
+        }
+        Ok(())
+    }
+
+    fn load_archived_transactions(&self) -> Result<Vec<Transaction>, StorageError> {
+        let mut transactions = Vec::new();
+        for path in self.archive_paths()? {
+            transactions.extend(self.read_ndjson_file::<Transaction>(&path)?);
+        }
+        Ok(transactions)
+    }
+
+    fn save_archived_transactions(&self, transactions: &[Transaction]) -> Result<(), StorageError> {
+        let mut by_year: BTreeMap<PathBuf, Vec<&Transaction>> = BTreeMap::new();
+        for transaction in transactions {
+            let path = self.archive_dir.join(format!("transactions-{}.ndjson", transaction.timestamp.format("%Y")));
+            by_year.entry(path).or_default().push(transaction);
+        }
+        for path in self.archive_paths()? {
+            if !by_year.contains_key(&path) {
+                fs::remove_file(&path).map_err(|source| StorageError::Write { path: path.clone(), source })?;
+            }
+        }
+        for (path, transactions) in by_year {
+            self.write_ndjson_file(&path, &transactions)?;
+        }
+        Ok(())
+    }
+
+    fn data_size(&self) -> Result<u64, StorageError> {
+        let paths = [&self.products_path, &self.transactions_path, &self.audit_path, &self.tombstone_path];
+        let mut total = 0;
+        for path in paths {
+            match fs::metadata(path) {
+                Ok(metadata) => total += metadata.len(),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+                Err(source) => return Err(StorageError::Read { path: path.to_path_buf(), source }),
+            }
+        }
+        Ok(total)
+    }
+
+    fn load_receiving(&self) -> Result<Option<ReceivingSession>, StorageError> {
+        let path = &self.receiving_path;
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map(Some)
+                .map_err(|source| StorageError::Parse { path: path.clone(), line: None, source }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(source) => Err(StorageError::Read { path: path.clone(), source }),
+        }
+    }
+
+    fn save_receiving(&self, session: Option<&ReceivingSession>) -> Result<(), StorageError> {
+        let path = &self.receiving_path;
+        let Some(session) = session else {
+            return match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                    Err(StorageError::Write { path: path.clone(), source: e })
+                }
+                _ => Ok(()),
+            };
+        };
+        let json = serde_json::to_string_pretty(session)
+            .map_err(|source| StorageError::Serialize { path: path.clone(), source })?;
+        ensure_parent_dir(path)?;
+        log::debug!("Writing {} staged receipt lines to {}", session.received.len(), path.display());
+        fs::write(path, json).map_err(|source| StorageError::Write { path: path.clone(), source })
+    }
+
+    fn load_prices(&self) -> Result<Vec<SupplierPrice>, StorageError> {
+        self.read_json_file(&self.prices_path)
+    }
+
+    fn save_prices(&self, prices: &[SupplierPrice]) -> Result<(), StorageError> {
+        self.write_json_file(&self.prices_path, prices)
+    }
+
+    fn load_backorders(&self) -> Result<Vec<Backorder>, StorageError> {
+        self.read_json_file(&self.backorders_path)
+    }
+
+    fn save_backorders(&self, backorders: &[Backorder]) -> Result<(), StorageError> {
+        self.write_json_file(&self.backorders_path, backorders)
+    }
+
+    fn load_customers(&self) -> Result<Vec<Customer>, StorageError> {
+        self.read_json_file(&self.customers_path)
+    }
+
+    fn save_customers(&self, customers: &[Customer]) -> Result<(), StorageError> {
+        self.write_json_file(&self.customers_path, customers)
+    }
+
+    fn load_ledger_head(&self) -> Result<Option<LedgerHead>, StorageError> {
+        let path = &self.ledger_head_path;
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map(Some)
+                .map_err(|source| StorageError::Parse { path: path.clone(), line: None, source }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(source) => Err(StorageError::Read { path: path.clone(), source }),
+        }
+    }
+
+    fn save_ledger_head(&self, head: &LedgerHead) -> Result<(), StorageError> {
+        let path = &self.ledger_head_path;
+        let json = serde_json::to_string_pretty(head)
+            .map_err(|source| StorageError::Serialize { path: path.clone(), source })?;
+        ensure_parent_dir(path)?;
+        log::debug!("Writing ledger head after {} chained transactions to {}", head.count, path.display());
+        fs::write(path, json).map_err(|source| StorageError::Write { path: path.clone(), source })
+    }
+
+    fn load_last_seq(&self) -> Result<Option<u64>, StorageError> {
+        let path = &self.sequence_path;
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .map(Some)
+                .map_err(|source| StorageError::Parse { path: path.clone(), line: None, source }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(source) => Err(StorageError::Read { path: path.clone(), source }),
+        }
+    }
+
+    fn save_last_seq(&self, seq: u64) -> Result<(), StorageError> {
+        let path = &self.sequence_path;
+        ensure_parent_dir(path)?;
+        log::debug!("Writing sequence number {} to {}", seq, path.display());
+        fs::write(path, seq.to_string()).map_err(|source| StorageError::Write { path: path.clone(), source })
+    }
+
+    fn load_kpi_snapshots(&self) -> Result<Vec<KpiSnapshot>, StorageError> {
+        self.read_ndjson_file(&self.kpi_path)
+    }
+
+    fn save_kpi_snapshots(&self, snapshots: &[KpiSnapshot]) -> Result<(), StorageError> {
+        self.write_ndjson_file(&self.kpi_path, snapshots)
+    }
 }
 
 #[cfg(test)]
@@ -138,6 +666,7 @@ mod tests {
             description: "A test product".to_string(),
             quantity: 100,
             reorder_point: 20,
+            ..Default::default()
         }
     }
     
@@ -149,6 +678,15 @@ mod tests {
             quantity: 50,
             timestamp: Utc::now(),
             notes: Some("Test transaction".to_string()),
+            operator: None,
+            reason: None,
+            condition: None,
+            from_condition: None,
+            unit_cost: None,
+            customer: None,
+            prev_hash: None,
+            seq: 0,
+            extra: Default::default(),
         }
     }
     
@@ -178,6 +716,24 @@ mod tests {
         assert_eq!(loaded[0].product_sku, "SKU001");
     }
     
+    #[test]
+    fn test_unknown_fields_survive_a_save() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path()).with_memory_map(true);
+        fs::write(
+            temp_dir.path().join("products.json"),
+            r#"[{"id": "p1", "sku": "SKU001", "name": "Widget", "description": "", "quantity": 1, "reorder_point": 0, "bin": "A-3"}]"#,
+        ).unwrap();
+        let mut transaction = serde_json::to_value(create_test_transaction()).unwrap();
+        transaction["lot"] = serde_json::json!({ "number": "L42", "expires": "2027-01-31" });
+        fs::write(temp_dir.path().join("transactions.json"), serde_json::to_string(&[transaction]).unwrap()).unwrap();
+
+        storage.save_products(&storage.load_products().unwrap()).unwrap();
+        storage.save_transactions(&storage.load_transactions().unwrap()).unwrap();
+        assert_eq!(storage.load_products().unwrap()[0].extra["bin"], "A-3");
+        assert_eq!(storage.load_transactions_for("SKU001").unwrap()[0].extra["lot"]["number"], "L42");
+    }
+
     #[test]
     fn test_load_missing_files_returns_empty() {
         let temp_dir = TempDir::new().unwrap();
@@ -191,6 +747,49 @@ mod tests {
         assert!(transactions.is_empty());
     }
     
+    #[test]
+    fn test_append_and_load_audit_log() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path());
+        assert!(storage.load_audit_log().unwrap().is_empty());
+
+        let event = InventoryEvent::LargeMovement {
+            sku: "SKU001".to_string(),
+            quantity: 500,
+            available: 600,
+            threshold: crate::models::MovementThreshold::Units(100),
+            timestamp: Utc::now(),
+        };
+        storage.append_audit_event(&event).unwrap();
+        storage.append_audit_event(&event).unwrap();
+
+        let loaded = storage.load_audit_log().unwrap();
+        assert_eq!(loaded, vec![event.clone(), event]);
+    }
+
+    #[test]
+    fn test_archive_transactions_by_year() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path());
+        let mut old = create_test_transaction();
+        old.timestamp = "2021-06-01T00:00:00Z".parse().unwrap();
+        let mut older = old.clone();
+        older.timestamp = "2020-06-01T00:00:00Z".parse().unwrap();
+
+        storage.archive_transactions(&[old.clone(), older]).unwrap();
+        storage.archive_transactions(&[old]).unwrap();
+
+        let archive = temp_dir.path().join("archive");
+        let year_2021 = fs::read_to_string(archive.join("transactions-2021.ndjson")).unwrap();
+        assert_eq!(year_2021.lines().count(), 2);
+        assert!(archive.join("transactions-2020.ndjson").exists());
+        assert_eq!(storage.data_size().unwrap(), 0);
+
+        let loaded = storage.load_archived_transactions().unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded[0].timestamp.format("%Y").to_string(), "2020");
+    }
+
     #[test]
     fn test_corrupted_json_returns_parse_error() {
         let temp_dir = TempDir::new().unwrap();
@@ -200,6 +799,41 @@ mod tests {
         fs::write(temp_dir.path().join("products.json"), "not valid json {{{").unwrap();
         
         let result = storage.load_products();
-        assert!(matches!(result, Err(StorageError::ParseError(_))));
+        assert!(matches!(result, Err(StorageError::Parse { .. })));
+    }
+
+    #[test]
+    fn test_memory_mapped_reads_match_buffered_reads() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path());
+        let mapped = JsonStorage::new(temp_dir.path()).with_memory_map(true);
+        assert!(mapped.load_transactions().unwrap().is_empty());
+
+        let other = Transaction { id: "txn-2".to_string(), product_sku: "SKU002".to_string(), ..create_test_transaction() };
+        storage.save_transactions(&[create_test_transaction(), other]).unwrap();
+        assert_eq!(mapped.load_transactions().unwrap(), storage.load_transactions().unwrap());
+        let for_sku = mapped.load_transactions_for("SKU001").unwrap();
+        assert_eq!(for_sku.len(), 1);
+        assert_eq!(for_sku[0].id, "txn-id-123");
+
+        fs::write(temp_dir.path().join("transactions.json"), "[{}] trailing").unwrap();
+        assert!(matches!(mapped.load_transactions_for("SKU001"), Err(StorageError::Parse { .. })));
+    }
+
+    #[test]
+    fn test_saving_replaces_rather_than_truncates_a_mapped_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = JsonStorage::new(temp_dir.path()).with_memory_map(true);
+        let other = Transaction { id: "txn-2".to_string(), ..create_test_transaction() };
+        storage.save_transactions(&[create_test_transaction(), other]).unwrap();
+        let path = temp_dir.path().join("transactions.json");
+        let map = storage.map_file(&path).unwrap().unwrap();
+
+        storage.save_transactions(&[]).unwrap();
+        let before: Vec<Transaction> = storage.parse_mapped(&path, &map, |_| true).unwrap();
+        assert_eq!(before.len(), 2);
+        assert!(storage.load_transactions().unwrap().is_empty());
+        let names: Vec<_> = fs::read_dir(temp_dir.path()).unwrap().map(|entry| entry.unwrap().file_name()).collect();
+        assert_eq!(names, vec!["transactions.json"]);
     }
 }
@@ -0,0 +1,250 @@
+// Minimal Handlebars-style templates for custom report output
+//
+// Supported syntax:
+//   {{path.to.value}}                 insert a value (`this` is the current item)
+//   {{@index}}                        zero-based position inside #each
+//   {{#each list}}...{{else}}...{{/each}}
+//   {{#if value}}...{{else}}...{{/if}}
+//   {{#unless value}}...{{/unless}}
+//   {{! comment }}
+//
+// Values are inserted as-is (no HTML escaping); paths are looked up in the
+// current item first, then in the enclosing scopes.
+
+use serde_json::Value;
+
+/// A parsed template ready to render
+#[derive(Debug, PartialEq)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Node {
+    Text(String),
+    Value(String),
+    Each { path: String, body: Vec<Node>, otherwise: Vec<Node> },
+    If { path: String, negate: bool, body: Vec<Node>, otherwise: Vec<Node> },
+}
+
+impl Template {
+    /// Parse template source, reporting the first syntax error
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let tokens = tokenize(source)?;
+        let mut tokens = tokens.into_iter();
+        let (nodes, end) = parse_nodes(&mut tokens)?;
+        match end {
+            None => Ok(Template { nodes }),
+            Some(tag) => Err(format!("Unexpected {{{{{}}}}}", tag)),
+        }
+    }
+
+    /// Render the template against a JSON context
+    pub fn render(&self, context: &Value) -> String {
+        let mut output = String::new();
+        let mut scopes = vec![Scope { value: context, index: None }];
+        render_nodes(&self.nodes, &mut scopes, &mut output);
+        output
+    }
+}
+
+enum Token {
+    Text(String),
+    Tag(String),
+}
+
+/// Split source into literal text and `{{ ... }}` tags
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut rest = source;
+    while let Some(start) = rest.find("{{") {
+        if start > 0 {
+            tokens.push(Token::Text(rest[..start].to_string()));
+        }
+        let after = &rest[start + 2..];
+        let end = after.find("}}").ok_or_else(|| {
+            let line = source[..source.len() - rest.len() + start].matches('\n').count() + 1;
+            format!("Unclosed '{{{{' on line {}", line)
+        })?;
+        let tag = after[..end].trim();
+        if !tag.starts_with('!') {
+            tokens.push(Token::Tag(tag.to_string()));
+        }
+        rest = &after[end + 2..];
+    }
+    if !rest.is_empty() {
+        tokens.push(Token::Text(rest.to_string()));
+    }
+    Ok(tokens)
+}
+
+/// Parse nodes until a closing or `else` tag, which is returned
+fn parse_nodes(tokens: &mut impl Iterator<Item = Token>) -> Result<(Vec<Node>, Option<String>), String> {
+    let mut nodes = Vec::new();
+    while let Some(token) = tokens.next() {
+        let tag = match token {
+            Token::Text(text) => {
+                nodes.push(Node::Text(text));
+                continue;
+            }
+            Token::Tag(tag) => tag,
+        };
+
+        if tag == "else" || tag.starts_with('/') {
+            return Ok((nodes, Some(tag)));
+        }
+        let Some(block) = tag.strip_prefix('#') else {
+            nodes.push(Node::Value(tag));
+            continue;
+        };
+
+        let (helper, path) = block.split_once(char::is_whitespace).unwrap_or((block, ""));
+        let path = path.trim().to_string();
+        if path.is_empty() {
+            return Err(format!("{{{{#{}}}}} needs a value to test", helper));
+        }
+        let (body, otherwise) = parse_block(tokens, helper)?;
+        nodes.push(match helper {
+            "each" => Node::Each { path, body, otherwise },
+            "if" => Node::If { path, negate: false, body, otherwise },
+            "unless" => Node::If { path, negate: true, body, otherwise },
+            _ => return Err(format!("Unknown block helper '#{}': expected each, if, or unless", helper)),
+        });
+    }
+    Ok((nodes, None))
+}
+
+/// Parse the body (and optional `else` branch) of a block up to its closing tag
+fn parse_block(tokens: &mut impl Iterator<Item = Token>, helper: &str) -> Result<(Vec<Node>, Vec<Node>), String> {
+    let closing = format!("/{}", helper);
+    let (body, end) = parse_nodes(tokens)?;
+    let (otherwise, end) = match end.as_deref() {
+        Some("else") => parse_nodes(tokens)?,
+        _ => (Vec::new(), end),
+    };
+    match end {
+        Some(tag) if tag == closing => Ok((body, otherwise)),
+        Some(tag) => Err(format!("Expected {{{{{}}}}} but found {{{{{}}}}}", closing, tag)),
+        None => Err(format!("Missing {{{{{}}}}}", closing)),
+    }
+}
+
+struct Scope<'a> {
+    value: &'a Value,
+    index: Option<usize>,
+}
+
+fn render_nodes<'a>(nodes: &'a [Node], scopes: &mut Vec<Scope<'a>>, output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Value(path) => {
+                if path == "@index" {
+                    if let Some(index) = scopes.last().and_then(|scope| scope.index) {
+                        output.push_str(&index.to_string());
+                    }
+                } else if let Some(value) = lookup(scopes, path) {
+                    push_value(value, output);
+                }
+            }
+            Node::Each { path, body, otherwise } => {
+                let items = match lookup(scopes, path) {
+                    Some(Value::Array(items)) if !items.is_empty() => items,
+                    _ => {
+                        render_nodes(otherwise, scopes, output);
+                        continue;
+                    }
+                };
+                for (index, item) in items.iter().enumerate() {
+                    scopes.push(Scope { value: item, index: Some(index) });
+                    render_nodes(body, scopes, output);
+                    scopes.pop();
+                }
+            }
+            Node::If { path, negate, body, otherwise } => {
+                let truthy = lookup(scopes, path).is_some_and(is_truthy);
+                render_nodes(if truthy != *negate { body } else { otherwise }, scopes, output);
+            }
+        }
+    }
+}
+
+/// Resolve a dotted path, searching from the innermost scope outwards
+fn lookup<'a>(scopes: &[Scope<'a>], path: &str) -> Option<&'a Value> {
+    if path == "this" {
+        return scopes.last().map(|scope| scope.value);
+    }
+    let path = path.strip_prefix("this.").unwrap_or(path);
+    scopes.iter().rev().find_map(|scope| {
+        path.split('.').try_fold(scope.value, |value, key| match value {
+            Value::Object(map) => map.get(key),
+            Value::Array(items) => key.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => None,
+        })
+    })
+}
+
+fn push_value(value: &Value, output: &mut String) {
+    match value {
+        Value::Null => {}
+        Value::String(text) => output.push_str(text),
+        other => output.push_str(&other.to_string()),
+    }
+}
+
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(flag) => *flag,
+        Value::Number(number) => number.as_f64() != Some(0.0),
+        Value::String(text) => !text.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        Value::Object(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn render(source: &str, context: Value) -> String {
+        Template::parse(source).unwrap().render(&context)
+    }
+
+    #[test]
+    fn test_values_and_blocks() {
+        let context = json!({
+            "title": "Order sheet",
+            "products": [
+                { "sku": "A", "quantity": 0, "supplier": "Acme" },
+                { "sku": "B", "quantity": 3, "supplier": null },
+            ],
+        });
+        let source = "{{title}}\n{{#each products}}{{@index}}:{{sku}} {{#if quantity}}{{quantity}}{{else}}OUT{{/if}}\
+                      {{#unless supplier}} (no supplier){{/unless}} [{{title}}]\n{{/each}}";
+        assert_eq!(
+            render(source, context),
+            "Order sheet\n0:A OUT [Order sheet]\n1:B 3 (no supplier) [Order sheet]\n"
+        );
+    }
+
+    #[test]
+    fn test_each_else_and_comments() {
+        let source = "{{! empty list }}{{#each items}}{{this}}{{else}}none{{/each}}";
+        assert_eq!(render(source, json!({ "items": [] })), "none");
+        assert_eq!(render(source, json!({ "items": [1, 2] })), "12");
+        assert_eq!(render("{{product.name}}", json!({ "product": { "name": "Bolt" } })), "Bolt");
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert_eq!(Template::parse("{{#each items}}x").unwrap_err(), "Missing {{/each}}");
+        assert_eq!(
+            Template::parse("{{#if a}}x{{/each}}").unwrap_err(),
+            "Expected {{/if}} but found {{/each}}"
+        );
+        assert_eq!(Template::parse("a\n{{b").unwrap_err(), "Unclosed '{{' on line 2");
+        assert!(Template::parse("{{#with a}}{{/with}}").unwrap_err().contains("Unknown block helper"));
+    }
+}
@@ -0,0 +1,267 @@
+// Fixtures for tests and examples
+//
+// Builders fill every field with a plain default so a test only spells out
+// what it cares about, and `seed_demo_data` sets up a small but realistic
+// inventory. Nothing depends on the clock or a random source: IDs derive from
+// SKUs and timestamps from `fixture_epoch`.
+//
+// With the `test-util` feature, `FlakyStorage` and `FailingStorage` inject
+// storage errors to exercise persistence failure handling.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+use crate::errors::ServiceError;
+use crate::models::{Component, MovementThreshold, Product, ReasonCode, Transaction, TransactionType};
+use crate::service::{HistoricalEntry, InventoryService, OpeningBalance, ProductUpdate};
+
+#[cfg(any(test, feature = "test-util"))]
+mod storage;
+#[cfg(any(test, feature = "test-util"))]
+pub use storage::{FailingStorage, FlakyStorage};
+
+/// Default timestamp of built transactions and date of the demo data's opening balances
+pub fn fixture_epoch() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap()
+}
+
+/// Builds a `Product`; the ID is `product-{sku}` and the name defaults to the SKU
+///
+/// Each setter replaces the default of the field with the same name.
+#[derive(Debug, Clone)]
+pub struct ProductBuilder {
+    product: Product,
+}
+
+impl ProductBuilder {
+    /// Start a product with the given SKU, no stock, and a zero reorder point
+    pub fn new(sku: &str) -> Self {
+        ProductBuilder {
+            product: Product {
+                id: format!("product-{}", sku),
+                sku: sku.to_string(),
+                name: sku.to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn name(mut self, name: &str) -> Self {
+        self.product.name = name.to_string();
+        self
+    }
+
+    pub fn description(mut self, description: &str) -> Self {
+        self.product.description = description.to_string();
+        self
+    }
+
+    pub fn quantity(mut self, quantity: u32) -> Self {
+        self.product.quantity = quantity;
+        self
+    }
+
+    pub fn reorder_point(mut self, reorder_point: u32) -> Self {
+        self.product.reorder_point = reorder_point;
+        self
+    }
+
+    pub fn category(mut self, category: &str) -> Self {
+        self.product.category = Some(category.to_string());
+        self
+    }
+
+    pub fn supplier(mut self, supplier: &str) -> Self {
+        self.product.supplier = Some(supplier.to_string());
+        self
+    }
+
+    pub fn location(mut self, location: &str) -> Self {
+        self.product.location = Some(location.to_string());
+        self
+    }
+
+    pub fn large_movement_threshold(mut self, threshold: MovementThreshold) -> Self {
+        self.product.large_movement_threshold = Some(threshold);
+        self
+    }
+
+    /// Add a kit component
+    pub fn component(mut self, sku: &str, quantity: u32) -> Self {
+        self.product.components.push(Component { sku: sku.to_string(), quantity });
+        self
+    }
+
+    pub fn build(self) -> Product {
+        self.product
+    }
+}
+
+/// Builds a `Transaction` dated `fixture_epoch()` unless told otherwise
+///
+/// Setters work as on `ProductBuilder`. Without an explicit `id`, the ID is
+/// `{sku}-{type}-{unix seconds}`, so two otherwise identical transactions
+/// need different timestamps or IDs.
+#[derive(Debug, Clone)]
+pub struct TransactionBuilder {
+    transaction: Transaction,
+    id: Option<String>,
+}
+
+impl TransactionBuilder {
+    /// Start a transaction of `quantity` units of `sku`
+    pub fn new(sku: &str, transaction_type: TransactionType, quantity: u32) -> Self {
+        TransactionBuilder {
+            transaction: Transaction {
+                timestamp: fixture_epoch(),
+                ..Transaction::new(sku, transaction_type, quantity, None)
+            },
+            id: None,
+        }
+    }
+
+    pub fn id(mut self, id: &str) -> Self {
+        self.id = Some(id.to_string());
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.transaction.timestamp = timestamp;
+        self
+    }
+
+    /// Date the transaction a number of days after `fixture_epoch()`
+    pub fn days_after_epoch(self, days: i64) -> Self {
+        self.timestamp(fixture_epoch() + Duration::days(days))
+    }
+
+    pub fn notes(mut self, notes: &str) -> Self {
+        self.transaction.notes = Some(notes.to_string());
+        self
+    }
+
+    pub fn operator(mut self, operator: &str) -> Self {
+        self.transaction.operator = Some(operator.to_string());
+        self
+    }
+
+    pub fn reason(mut self, reason: ReasonCode) -> Self {
+        self.transaction.reason = Some(reason);
+        self
+    }
+
+    pub fn build(self) -> Transaction {
+        let transaction = self.transaction;
+        let id = self.id.unwrap_or_else(|| format!(
+            "{}-{}-{}",
+            transaction.product_sku,
+            transaction.transaction_type.name(),
+            transaction.timestamp.timestamp()
+        ));
+        Transaction { id, ..transaction }
+    }
+}
+
+/// Populate an empty inventory with a few products and a month of history
+///
+/// Opening balances are dated `fixture_epoch()` and movements follow during
+/// January 2025. The result includes a low-stock product (`GADGET-02`), a
+/// kit (`SHELF-KIT`), and damage to report as shrinkage.
+pub fn seed_demo_data(service: &mut InventoryService) -> Result<(), ServiceError> {
+    let balances = [
+        ("WIDGET-01", "Widget", 120, 20),
+        ("GADGET-02", "Gadget", 15, 10),
+        ("BOLT-M6", "M6 bolt", 400, 100),
+        ("PANEL-01", "Shelf panel", 30, 10),
+        ("SHELF-KIT", "Shelf kit", 0, 2),
+    ];
+    let balances: Vec<OpeningBalance> = balances
+        .iter()
+        .map(|&(sku, name, quantity, reorder_point)| OpeningBalance {
+            sku: sku.to_string(),
+            name: name.to_string(),
+            quantity,
+            reorder_point,
+        })
+        .collect();
+    service.migrate_opening_balances(&balances, fixture_epoch())?;
+
+    let details = [
+        ("WIDGET-01", "widgets", "Acme", "A-1"),
+        ("GADGET-02", "widgets", "Globex", "A-2"),
+        ("BOLT-M6", "hardware", "Acme", "B-1"),
+        ("PANEL-01", "hardware", "Initech", "B-2"),
+        ("SHELF-KIT", "kits", "Initech", "C-1"),
+    ];
+    for (sku, category, supplier, location) in details {
+        let update = ProductUpdate {
+            category: Some(category.to_string()),
+            supplier: Some(supplier.to_string()),
+            location: Some(location.to_string()),
+            ..Default::default()
+        };
+        service.update_product(sku, update)?;
+    }
+    let kit = ProductUpdate {
+        components: Some(vec![
+            Component { sku: "BOLT-M6".to_string(), quantity: 8 },
+            Component { sku: "PANEL-01".to_string(), quantity: 2 },
+        ]),
+        ..Default::default()
+    };
+    service.update_product("SHELF-KIT", kit)?;
+
+    let movements = [
+        ("WIDGET-01", 3, TransactionType::Removal, 40, Some("Order 1001")),
+        ("GADGET-02", 5, TransactionType::Removal, 9, Some("Order 1002")),
+        ("WIDGET-01", 10, TransactionType::Addition, 50, Some("PO 17")),
+        ("BOLT-M6", 12, TransactionType::Removal, 64, None),
+        ("PANEL-01", 14, TransactionType::Damage, 2, Some("Dropped in receiving")),
+        ("WIDGET-01", 20, TransactionType::Removal, 25, Some("Order 1003")),
+    ];
+    let entries: Vec<HistoricalEntry> = movements
+        .iter()
+        .map(|&(sku, day, transaction_type, quantity, notes)| HistoricalEntry {
+            sku: sku.to_string(),
+            timestamp: fixture_epoch() + Duration::days(day),
+            transaction_type,
+            quantity,
+            notes: notes.map(String::from),
+        })
+        .collect();
+    service.import_transactions(&entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::JsonStorage;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_builders_are_deterministic() {
+        let product = ProductBuilder::new("KIT").quantity(3).component("BOLT", 4).build();
+        assert_eq!(product.id, "product-KIT");
+        assert_eq!(product.name, "KIT");
+        assert_eq!(product.components, vec![Component { sku: "BOLT".to_string(), quantity: 4 }]);
+
+        let build = || TransactionBuilder::new("KIT", TransactionType::Removal, 2).days_after_epoch(1).build();
+        assert_eq!(build(), build());
+        assert_eq!(build().timestamp, fixture_epoch() + Duration::days(1));
+        assert_eq!(TransactionBuilder::new("KIT", TransactionType::Removal, 2).id("t1").build().id, "t1");
+    }
+
+    #[test]
+    fn test_seed_demo_data() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut service = InventoryService::new(Box::new(JsonStorage::new(temp_dir.path()))).unwrap();
+        seed_demo_data(&mut service).unwrap();
+
+        assert_eq!(service.list_products().len(), 5);
+        assert_eq!(service.get_product("WIDGET-01").unwrap().quantity, 105);
+        let mut low: Vec<&str> = service.list_low_stock().iter().map(|p| p.sku.as_str()).collect();
+        low.sort();
+        assert_eq!(low, vec!["GADGET-02", "SHELF-KIT"]);
+        assert_eq!(service.check_buildable("SHELF-KIT").unwrap().kits, 14);
+        assert_eq!(service.shrinkage(None, None, None).len(), 1);
+    }
+}
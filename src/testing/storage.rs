@@ -0,0 +1,401 @@
+// Storage wrappers that fail on purpose
+//
+// Both wrappers count every call to the `Storage` trait, loads included, so
+// an `InventoryService` built on top has already used three operations
+// (loading products, transactions, and the last sequence number) when its
+// constructor returns.
+
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::errors::StorageError;
+use crate::events::InventoryEvent;
+use crate::models::{
+    Backorder, Customer, KpiSnapshot, LedgerHead, Product, ReceivingSession, SupplierPrice, Tombstone, Transaction,
+};
+use crate::storage::Storage;
+
+/// Counts operations and decides which ones fail
+#[derive(Debug)]
+struct Injector {
+    operations: AtomicUsize,
+    fail_on: usize,
+    persistent: bool,
+}
+
+impl Injector {
+    fn new(fail_on: usize, persistent: bool) -> Self {
+        Injector { operations: AtomicUsize::new(0), fail_on, persistent }
+    }
+
+    /// Count an operation, returning the injected error if it should fail
+    fn check(&self, write: bool) -> Result<(), StorageError> {
+        let operation = self.operations.fetch_add(1, Ordering::SeqCst) + 1;
+        let fails = if self.persistent { operation >= self.fail_on } else { operation == self.fail_on };
+        if !fails {
+            return Ok(());
+        }
+        let path = PathBuf::from("<injected>");
+        let source = io::Error::other(format!("injected failure on operation {}", operation));
+        log::debug!("Injecting storage failure on operation {}", operation);
+        Err(if write { StorageError::Write { path, source } } else { StorageError::Read { path, source } })
+    }
+}
+
+/// Storage that fails once, on the Nth operation (1-based), like a transient I/O error
+///
+/// Loads fail with `StorageError::Read`, everything else with `StorageError::Write`.
+#[derive(Debug)]
+pub struct FlakyStorage<S> {
+    inner: S,
+    injector: Injector,
+}
+
+impl<S: Storage> FlakyStorage<S> {
+    /// Wrap `inner`, failing only its `fail_on`th operation
+    pub fn new(inner: S, fail_on: usize) -> Self {
+        FlakyStorage { inner, injector: Injector::new(fail_on, false) }
+    }
+
+    /// Operations attempted so far
+    pub fn operations(&self) -> usize {
+        self.injector.operations.load(Ordering::SeqCst)
+    }
+}
+
+/// Storage that fails every operation from the Nth (1-based) on, like a full disk
+///
+/// With `fail_from` set to 1 nothing succeeds, not even loading.
+#[derive(Debug)]
+pub struct FailingStorage<S> {
+    inner: S,
+    injector: Injector,
+}
+
+impl<S: Storage> FailingStorage<S> {
+    /// Wrap `inner`, failing its `fail_from`th operation and all later ones
+    pub fn new(inner: S, fail_from: usize) -> Self {
+        FailingStorage { inner, injector: Injector::new(fail_from, true) }
+    }
+
+    /// Operations attempted so far
+    pub fn operations(&self) -> usize {
+        self.injector.operations.load(Ordering::SeqCst)
+    }
+}
+
+impl<S: Storage> Storage for FlakyStorage<S> {
+    fn save_products(&self, products: &[Product]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_products(products)
+    }
+
+    fn load_products(&self) -> Result<Vec<Product>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_products()
+    }
+
+    fn save_transactions(&self, transactions: &[Transaction]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_transactions(transactions)
+    }
+
+    fn load_transactions(&self) -> Result<Vec<Transaction>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_transactions()
+    }
+
+    fn load_transactions_for(&self, sku: &str) -> Result<Vec<Transaction>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_transactions_for(sku)
+    }
+
+    fn append_audit_event(&self, event: &InventoryEvent) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.append_audit_event(event)
+    }
+
+    fn load_audit_log(&self) -> Result<Vec<InventoryEvent>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_audit_log()
+    }
+
+    fn append_tombstone(&self, tombstone: &Tombstone) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.append_tombstone(tombstone)
+    }
+
+    fn load_tombstones(&self) -> Result<Vec<Tombstone>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_tombstones()
+    }
+
+    fn save_tombstones(&self, tombstones: &[Tombstone]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_tombstones(tombstones)
+    }
+
+    fn archive_transactions(&self, transactions: &[Transaction]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.archive_transactions(transactions)
+    }
+
+    fn load_archived_transactions(&self) -> Result<Vec<Transaction>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_archived_transactions()
+    }
+
+    fn save_archived_transactions(&self, transactions: &[Transaction]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_archived_transactions(transactions)
+    }
+
+    fn data_size(&self) -> Result<u64, StorageError> {
+        self.injector.check(false)?;
+        self.inner.data_size()
+    }
+
+    fn load_receiving(&self) -> Result<Option<ReceivingSession>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_receiving()
+    }
+
+    fn save_receiving(&self, session: Option<&ReceivingSession>) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_receiving(session)
+    }
+
+    fn load_prices(&self) -> Result<Vec<SupplierPrice>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_prices()
+    }
+
+    fn save_prices(&self, prices: &[SupplierPrice]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_prices(prices)
+    }
+
+    fn load_backorders(&self) -> Result<Vec<Backorder>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_backorders()
+    }
+
+    fn save_backorders(&self, backorders: &[Backorder]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_backorders(backorders)
+    }
+
+    fn load_customers(&self) -> Result<Vec<Customer>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_customers()
+    }
+
+    fn save_customers(&self, customers: &[Customer]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_customers(customers)
+    }
+
+    fn load_ledger_head(&self) -> Result<Option<LedgerHead>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_ledger_head()
+    }
+
+    fn save_ledger_head(&self, head: &LedgerHead) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_ledger_head(head)
+    }
+
+    fn load_last_seq(&self) -> Result<Option<u64>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_last_seq()
+    }
+
+    fn save_last_seq(&self, seq: u64) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_last_seq(seq)
+    }
+
+    fn load_kpi_snapshots(&self) -> Result<Vec<KpiSnapshot>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_kpi_snapshots()
+    }
+
+    fn save_kpi_snapshots(&self, snapshots: &[KpiSnapshot]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_kpi_snapshots(snapshots)
+    }
+}
+
+impl<S: Storage> Storage for FailingStorage<S> {
+    fn save_products(&self, products: &[Product]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_products(products)
+    }
+
+    fn load_products(&self) -> Result<Vec<Product>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_products()
+    }
+
+    fn save_transactions(&self, transactions: &[Transaction]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_transactions(transactions)
+    }
+
+    fn load_transactions(&self) -> Result<Vec<Transaction>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_transactions()
+    }
+
+    fn load_transactions_for(&self, sku: &str) -> Result<Vec<Transaction>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_transactions_for(sku)
+    }
+
+    fn append_audit_event(&self, event: &InventoryEvent) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.append_audit_event(event)
+    }
+
+    fn load_audit_log(&self) -> Result<Vec<InventoryEvent>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_audit_log()
+    }
+
+    fn append_tombstone(&self, tombstone: &Tombstone) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.append_tombstone(tombstone)
+    }
+
+    fn load_tombstones(&self) -> Result<Vec<Tombstone>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_tombstones()
+    }
+
+    fn save_tombstones(&self, tombstones: &[Tombstone]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_tombstones(tombstones)
+    }
+
+    fn archive_transactions(&self, transactions: &[Transaction]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.archive_transactions(transactions)
+    }
+
+    fn load_archived_transactions(&self) -> Result<Vec<Transaction>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_archived_transactions()
+    }
+
+    fn save_archived_transactions(&self, transactions: &[Transaction]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_archived_transactions(transactions)
+    }
+
+    fn data_size(&self) -> Result<u64, StorageError> {
+        self.injector.check(false)?;
+        self.inner.data_size()
+    }
+
+    fn load_receiving(&self) -> Result<Option<ReceivingSession>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_receiving()
+    }
+
+    fn save_receiving(&self, session: Option<&ReceivingSession>) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_receiving(session)
+    }
+
+    fn load_prices(&self) -> Result<Vec<SupplierPrice>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_prices()
+    }
+
+    fn save_prices(&self, prices: &[SupplierPrice]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_prices(prices)
+    }
+
+    fn load_backorders(&self) -> Result<Vec<Backorder>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_backorders()
+    }
+
+    fn save_backorders(&self, backorders: &[Backorder]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_backorders(backorders)
+    }
+
+    fn load_customers(&self) -> Result<Vec<Customer>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_customers()
+    }
+
+    fn save_customers(&self, customers: &[Customer]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_customers(customers)
+    }
+
+    fn load_ledger_head(&self) -> Result<Option<LedgerHead>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_ledger_head()
+    }
+
+    fn save_ledger_head(&self, head: &LedgerHead) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_ledger_head(head)
+    }
+
+    fn load_last_seq(&self) -> Result<Option<u64>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_last_seq()
+    }
+
+    fn save_last_seq(&self, seq: u64) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_last_seq(seq)
+    }
+
+    fn load_kpi_snapshots(&self) -> Result<Vec<KpiSnapshot>, StorageError> {
+        self.injector.check(false)?;
+        self.inner.load_kpi_snapshots()
+    }
+
+    fn save_kpi_snapshots(&self, snapshots: &[KpiSnapshot]) -> Result<(), StorageError> {
+        self.injector.check(true)?;
+        self.inner.save_kpi_snapshots(snapshots)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::InventoryService;
+    use crate::storage::JsonStorage;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_flaky_storage_fails_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = FlakyStorage::new(JsonStorage::new(temp_dir.path()), 2);
+
+        assert!(storage.load_products().is_ok());
+        assert_eq!(storage.load_transactions().unwrap_err().code(), "STORAGE_READ");
+        assert_eq!(storage.save_products(&[]).map_err(|e| e.code()), Ok(()));
+        assert_eq!(storage.operations(), 3);
+    }
+
+    #[test]
+    fn test_failing_storage_surfaces_through_service() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = FailingStorage::new(JsonStorage::new(temp_dir.path()), 4);
+        let mut service = InventoryService::new(Box::new(storage)).unwrap();
+
+        let err = service.add_product("SKU001".into(), "Widget".into(), "".into(), 1, 1).unwrap_err();
+        assert_eq!(err.code(), "STORAGE_WRITE");
+        assert!(service.add_stock("SKU001", 1, None).is_err());
+        assert!(InventoryService::new(Box::new(FailingStorage::new(JsonStorage::new(temp_dir.path()), 1))).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+// Validation reports for batch operations
+//
+// Batch commands check every row before applying anything and collect all
+// problems in a `ValidationReport`, so a whole input file can be fixed in
+// one pass instead of one error at a time.
+
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::errors::ServiceError;
+
+/// A problem with one row of a batch
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RowIssue {
+    /// One-based row or input line number
+    pub row: usize,
+    /// Stable error code, e.g. `PRODUCT_NOT_FOUND`
+    pub code: &'static str,
+    /// SKU the row refers to, if known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sku: Option<String>,
+    /// Description of the problem
+    pub message: String,
+}
+
+/// Every problem found while validating a batch
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ValidationReport {
+    /// Number of rows checked
+    pub rows: usize,
+    issues: Vec<RowIssue>,
+}
+
+impl ValidationReport {
+    /// Create an empty report for a batch of `rows` rows
+    pub fn new(rows: usize) -> Self {
+        ValidationReport { rows, issues: Vec::new() }
+    }
+
+    /// Record a problem that isn't a service error, such as a malformed line
+    pub fn add(&mut self, row: usize, code: &'static str, message: impl Into<String>) {
+        self.issues.push(RowIssue { row, code, sku: None, message: message.into() });
+    }
+
+    /// Record a service error for a row, keeping its code and SKU
+    pub fn add_error(&mut self, row: usize, error: &ServiceError) {
+        self.issues.push(RowIssue {
+            row,
+            code: error.code(),
+            sku: error.sku().map(String::from),
+            message: error.to_string(),
+        });
+    }
+
+    /// Add the issues of another report over the same rows, keeping row order
+    pub fn extend(&mut self, other: ValidationReport) {
+        self.issues.extend(other.issues);
+        self.issues.sort_by_key(|issue| issue.row);
+    }
+
+    /// Map every row number, e.g. from entry positions to input line numbers
+    pub fn renumber(mut self, row: impl Fn(usize) -> usize) -> Self {
+        for issue in &mut self.issues {
+            issue.row = row(issue.row);
+        }
+        self
+    }
+
+    /// Whether no problems were found
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// The problems found, in row order
+    pub fn issues(&self) -> &[RowIssue] {
+        &self.issues
+    }
+
+    /// Number of distinct rows with at least one problem
+    pub fn rejected_rows(&self) -> usize {
+        let mut rows: Vec<usize> = self.issues.iter().map(|issue| issue.row).collect();
+        rows.dedup();
+        rows.len()
+    }
+
+    /// Whether any problem has the given code
+    pub fn has_code(&self, code: &str) -> bool {
+        self.issues.iter().any(|issue| issue.code == code)
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines: Vec<String> = self.issues
+            .iter()
+            .map(|issue| format!("Line {}: {}", issue.row, issue.message))
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_collects_issues_by_row() {
+        let mut report = ValidationReport::new(5);
+        report.add(4, "INVALID_INPUT", "SKU cannot be empty");
+
+        let mut service_report = ValidationReport::new(3);
+        service_report.add_error(1, &ServiceError::ProductNotFound { sku: "Z".to_string() });
+        service_report.add_error(1, &ServiceError::invalid_input("Quantity must be positive"));
+        report.extend(service_report.renumber(|row| row + 1));
+
+        assert!(!report.is_valid());
+        assert_eq!(report.rejected_rows(), 2);
+        assert!(report.has_code("PRODUCT_NOT_FOUND"));
+        assert_eq!(report.issues()[0].sku.as_deref(), Some("Z"));
+        assert_eq!(
+            report.to_string(),
+            "Line 2: Product not found: Z\nLine 2: Invalid input: Quantity must be positive\nLine 4: SKU cannot be empty"
+        );
+    }
+}
@@ -46,6 +46,7 @@ impl Arbitrary for ValidProduct {
             description,
             quantity,
             reorder_point,
+            ..Default::default()
         })
     }
 }
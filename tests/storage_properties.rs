@@ -4,7 +4,7 @@
 // Validates: Requirements 8.4
 
 use quickcheck::{Arbitrary, Gen, QuickCheck};
-use stock_control::models::{Product, Transaction, TransactionType};
+use stock_control::models::{Condition, Money, Product, ReasonCode, Transaction, TransactionType};
 use chrono::{TimeZone, Utc};
 
 // Custom generator for valid system state
@@ -50,6 +50,7 @@ impl Arbitrary for ValidProduct {
             description,
             quantity,
             reorder_point,
+            ..Default::default()
         })
     }
 }
@@ -63,11 +64,19 @@ impl Arbitrary for ValidTransaction {
         let id = format!("txn-{}", u32::arbitrary(g));
         let product_sku = format!("SKU-{}", u32::arbitrary(g));
         
-        let transaction_type = if bool::arbitrary(g) {
-            TransactionType::Addition
-        } else {
-            TransactionType::Removal
-        };
+        let transaction_type = *g
+            .choose(&[
+                TransactionType::Addition,
+                TransactionType::Removal,
+                TransactionType::InitialStock,
+                TransactionType::Damage,
+                TransactionType::WriteOff,
+                TransactionType::Adjustment,
+                TransactionType::Quarantine,
+                TransactionType::ReleaseQuarantine,
+                TransactionType::ConditionChange,
+            ])
+            .unwrap();
         
         let quantity = u32::arbitrary(g);
         
@@ -97,6 +106,15 @@ impl Arbitrary for ValidTransaction {
             quantity,
             timestamp,
             notes,
+            operator: None,
+            reason: g.choose(&ReasonCode::ALL).copied().filter(|_| bool::arbitrary(g)),
+            condition: g.choose(&Condition::ALL).copied().filter(|_| bool::arbitrary(g)),
+            from_condition: g.choose(&Condition::ALL).copied().filter(|_| bool::arbitrary(g)),
+            unit_cost: Option::<u32>::arbitrary(g).map(|cents| Money::from_cents(u64::from(cents))),
+            customer: None,
+            prev_hash: None,
+            seq: u64::from(u32::arbitrary(g)),
+            extra: Default::default(),
         })
     }
 }